@@ -0,0 +1,318 @@
+use crate::{Cli, PollSchedule, RunSummary, UploadStatus};
+use anyhow::{anyhow, Error};
+use reqwest::Client;
+use serde::Serialize;
+use slog::{error, info, Logger};
+use std::{
+    collections::HashMap,
+    sync::Mutex,
+    time::{Duration, Instant},
+};
+
+/// Why a run's summary was sent to the alert webhook
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertReason {
+    /// The run returned an error
+    Failed,
+    /// The pipeline hasn't uploaded a file in longer than the stale threshold
+    Stale,
+}
+
+#[derive(Serialize)]
+struct AlertBody<'a> {
+    reason: AlertReason,
+    /// Seconds since the pipeline last uploaded a file, or since the daemon started if it never has
+    data_age_secs: u64,
+    summary: &'a RunSummary,
+}
+
+/// How long each pipeline can go without uploading a file before its data is stale
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StaleThresholds {
+    pub forecasts: Duration,
+    pub observations: Duration,
+}
+
+impl StaleThresholds {
+    pub fn for_pipeline(&self, pipeline: &str) -> Duration {
+        match pipeline {
+            "observations" => self.observations,
+            _ => self.forecasts,
+        }
+    }
+}
+
+/// POSTs a run's summary to a webhook when the run fails or its pipeline's data has gone stale.
+/// Failing to deliver an alert is logged and never stops the daemon
+pub struct Alerter {
+    logger: Logger,
+    client: Client,
+    webhook_url: String,
+    stale_after: StaleThresholds,
+    started_at: Instant,
+    last_upload: Mutex<HashMap<&'static str, Instant>>,
+}
+
+impl Alerter {
+    pub fn new(logger: Logger, webhook_url: String, stale_after: StaleThresholds) -> Self {
+        Self {
+            logger,
+            client: Client::new(),
+            webhook_url,
+            stale_after,
+            started_at: Instant::now(),
+            last_upload: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// Only built when an alert webhook is configured. Each pipeline uses its own threshold, falling
+    /// back to the shared one, then to twice the longest pipeline interval
+    pub fn from_cli(logger: Logger, cli: &Cli, schedule: &PollSchedule) -> Option<Self> {
+        let webhook_url = cli.alert_webhook_url.clone()?;
+        let default = cli
+            .alert_stale_after
+            .map(Duration::from_secs)
+            .unwrap_or_else(|| {
+                schedule
+                    .forecast_interval
+                    .max(schedule.observation_interval)
+                    .saturating_mul(2)
+            });
+        let stale_after = StaleThresholds {
+            forecasts: cli
+                .alert_forecast_stale_after
+                .map(Duration::from_secs)
+                .unwrap_or(default),
+            observations: cli
+                .alert_observation_stale_after
+                .map(Duration::from_secs)
+                .unwrap_or(default),
+        };
+        Some(Self::new(logger, webhook_url, stale_after))
+    }
+
+    pub fn stale_after(&self) -> StaleThresholds {
+        self.stale_after
+    }
+
+    /// Checks a finished run, sending its summary to the webhook if it failed or its data is stale.
+    /// Returns the reason an alert was sent for
+    pub async fn check_run(&self, summary: &RunSummary) -> Option<AlertReason> {
+        let data_age = self.data_age(summary);
+        let reason = if summary.error.is_some() {
+            AlertReason::Failed
+        } else if data_age > self.stale_after.for_pipeline(summary.pipeline) {
+            AlertReason::Stale
+        } else {
+            return None;
+        };
+        match self.send(reason, data_age, summary).await {
+            Ok(()) => info!(
+                self.logger,
+                "sent {:?} alert for {} run", reason, summary.pipeline
+            ),
+            Err(err) => error!(
+                self.logger,
+                "failed to send {:?} alert for {} run: {}", reason, summary.pipeline, err
+            ),
+        }
+        Some(reason)
+    }
+
+    fn data_age(&self, summary: &RunSummary) -> Duration {
+        let mut last_upload = self.last_upload.lock().unwrap();
+        if summary.upload == UploadStatus::Sent {
+            last_upload.insert(summary.pipeline, Instant::now());
+        }
+        last_upload
+            .get(summary.pipeline)
+            .unwrap_or(&self.started_at)
+            .elapsed()
+    }
+
+    async fn send(
+        &self,
+        reason: AlertReason,
+        data_age: Duration,
+        summary: &RunSummary,
+    ) -> Result<(), Error> {
+        let body = AlertBody {
+            reason,
+            data_age_secs: data_age.as_secs(),
+            summary,
+        };
+        let response = self
+            .client
+            .post(&self.webhook_url)
+            .timeout(Duration::from_secs(10))
+            .json(&body)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow!("webhook responded with {}", response.status()));
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{AlertReason, Alerter, StaleThresholds};
+    use crate::{
+        test_server::{spawn_server, Response},
+        RunSummary, UploadStatus,
+    };
+    use serde_json::Value;
+    use slog::{o, Discard, Logger};
+    use std::{future::ready, time::Duration};
+    use tokio::{
+        net::TcpListener,
+        sync::mpsc::{unbounded_channel, UnboundedReceiver},
+        time::timeout,
+    };
+
+    /// Accepts webhook requests, passing each JSON body along, returns the webhook's url
+    async fn spawn_webhook() -> (String, UnboundedReceiver<Value>) {
+        let (sender, receiver) = unbounded_channel();
+        let webhook = spawn_server(move |_, request| {
+            sender
+                .send(serde_json::from_slice(&request.body).unwrap())
+                .unwrap();
+            ready(Response::ok(""))
+        })
+        .await;
+        (format!("{}/alerts", webhook.url()), receiver)
+    }
+
+    fn alerter(webhook_url: String, stale_after: Duration) -> Alerter {
+        thresholds_alerter(
+            webhook_url,
+            StaleThresholds {
+                forecasts: stale_after,
+                observations: stale_after,
+            },
+        )
+    }
+
+    fn thresholds_alerter(webhook_url: String, stale_after: StaleThresholds) -> Alerter {
+        Alerter::new(Logger::root(Discard, o!()), webhook_url, stale_after)
+    }
+
+    #[tokio::test]
+    async fn alerts_on_failed_run() {
+        let (webhook_url, mut alerts) = spawn_webhook().await;
+        let alerter = alerter(webhook_url, Duration::from_secs(3600));
+        let mut summary = RunSummary::new("forecasts");
+        summary.stations = 3;
+        summary.upload = UploadStatus::Queued;
+        summary.error = Some(String::from("failed to upload forecasts"));
+
+        assert_eq!(alerter.check_run(&summary).await, Some(AlertReason::Failed));
+
+        let alert = timeout(Duration::from_secs(5), alerts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert["reason"], "failed");
+        assert_eq!(alert["summary"]["pipeline"], "forecasts");
+        assert_eq!(alert["summary"]["stations"], 3);
+        assert_eq!(alert["summary"]["upload"], "queued");
+        assert_eq!(alert["summary"]["error"], "failed to upload forecasts");
+        assert!(alert["summary"]["duration_ms"].is_u64());
+    }
+
+    #[tokio::test]
+    async fn alerts_once_data_is_older_than_the_threshold() {
+        let (webhook_url, mut alerts) = spawn_webhook().await;
+        let alerter = alerter(webhook_url, Duration::from_millis(50));
+        let mut sent = RunSummary::new("observations");
+        sent.upload = UploadStatus::Sent;
+
+        assert_eq!(alerter.check_run(&sent).await, None);
+
+        // The next run didn't get to upload anything
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        let skipped = RunSummary::new("observations");
+        assert_eq!(alerter.check_run(&skipped).await, Some(AlertReason::Stale));
+
+        let alert = timeout(Duration::from_secs(5), alerts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert["reason"], "stale");
+        assert_eq!(alert["summary"]["pipeline"], "observations");
+        assert_eq!(alert["summary"]["upload"], "not_attempted");
+        assert_eq!(alert["summary"]["error"], Value::Null);
+        assert!(alerts.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn forecasts_go_stale_on_their_own_threshold() {
+        let (webhook_url, mut alerts) = spawn_webhook().await;
+        let alerter = thresholds_alerter(
+            webhook_url,
+            StaleThresholds {
+                forecasts: Duration::from_millis(50),
+                observations: Duration::from_secs(3600),
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(
+            alerter.check_run(&RunSummary::new("observations")).await,
+            None
+        );
+        assert_eq!(
+            alerter.check_run(&RunSummary::new("forecasts")).await,
+            Some(AlertReason::Stale)
+        );
+
+        let alert = timeout(Duration::from_secs(5), alerts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert["reason"], "stale");
+        assert_eq!(alert["summary"]["pipeline"], "forecasts");
+        assert!(alerts.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn observations_go_stale_on_their_own_threshold() {
+        let (webhook_url, mut alerts) = spawn_webhook().await;
+        let alerter = thresholds_alerter(
+            webhook_url,
+            StaleThresholds {
+                forecasts: Duration::from_secs(3600),
+                observations: Duration::from_millis(50),
+            },
+        );
+
+        tokio::time::sleep(Duration::from_millis(100)).await;
+        assert_eq!(alerter.check_run(&RunSummary::new("forecasts")).await, None);
+        assert_eq!(
+            alerter.check_run(&RunSummary::new("observations")).await,
+            Some(AlertReason::Stale)
+        );
+
+        let alert = timeout(Duration::from_secs(5), alerts.recv())
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(alert["reason"], "stale");
+        assert_eq!(alert["summary"]["pipeline"], "observations");
+        assert!(alerts.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn unreachable_webhook_is_not_fatal() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let webhook_url = format!("http://{}/alerts", listener.local_addr().unwrap());
+        drop(listener);
+        let alerter = alerter(webhook_url, Duration::from_secs(3600));
+        let mut summary = RunSummary::new("forecasts");
+        summary.error = Some(String::from("no stations"));
+
+        assert_eq!(alerter.check_run(&summary).await, Some(AlertReason::Failed));
+    }
+}
@@ -0,0 +1,375 @@
+use anyhow::{anyhow, Error};
+use slog::{info, warn, Logger};
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    sync::{Mutex, MutexGuard},
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// Requests are sent to NOAA as normal
+    Closed,
+    /// Too many requests in a row have failed, requests are short-circuited until the cooldown is over
+    Open,
+    /// The cooldown is over, a single request is let through to check if NOAA has recovered
+    HalfOpen,
+}
+
+impl Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Closed => write!(f, "closed"),
+            Self::Open => write!(f, "open"),
+            Self::HalfOpen => write!(f, "half_open"),
+        }
+    }
+}
+
+/// The request was held back by the rate limiter or NOAA answered 429, it says nothing about whether NOAA is up
+/// so the circuit breaker counts it as neither a success nor a failure
+#[derive(Debug)]
+pub struct RateLimited {
+    pub reason: String,
+}
+
+impl Display for RateLimited {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "rate limited: {}", self.reason)
+    }
+}
+
+impl std::error::Error for RateLimited {}
+
+struct BreakerState {
+    state: CircuitState,
+    consecutive_failures: usize,
+    opened_at: Option<Instant>,
+    /// Set while the half open request checking if NOAA recovered is running
+    probe_in_flight: bool,
+}
+
+/// Frees the half open probe slot, also when the probe's future is dropped before it finishes
+struct Probe<'a>(&'a CircuitBreaker);
+
+impl Drop for Probe<'_> {
+    fn drop(&mut self) {
+        self.0.lock().probe_in_flight = false;
+    }
+}
+
+pub struct CircuitBreaker {
+    logger: Logger,
+    failure_threshold: usize,
+    cooldown: Duration,
+    inner: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(logger: Logger, failure_threshold: usize, cooldown: Duration) -> Self {
+        Self {
+            logger,
+            failure_threshold: failure_threshold.max(1),
+            cooldown,
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                consecutive_failures: 0,
+                opened_at: None,
+                probe_in_flight: false,
+            }),
+        }
+    }
+
+    pub fn state(&self) -> CircuitState {
+        let mut inner = self.lock();
+        self.refresh_state(&mut inner);
+        inner.state
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BreakerState> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Runs the request unless the circuit is open, the result is used to move between states. While half open
+    /// only one request is let through, the rest are short-circuited until it finishes
+    pub async fn call<T, F, Fut>(&self, request: F) -> Result<T, Error>
+    where
+        F: FnOnce() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let probe = {
+            let mut inner = self.lock();
+            self.refresh_state(&mut inner);
+            match inner.state {
+                CircuitState::Closed => None,
+                CircuitState::Open => {
+                    return Err(anyhow!(
+                        "circuit breaker is open, skipping request to NOAA for up to {} seconds",
+                        self.cooldown.as_secs()
+                    ))
+                }
+                CircuitState::HalfOpen if inner.probe_in_flight => {
+                    return Err(anyhow!(
+                        "circuit breaker is half open and already checking if NOAA recovered, skipping request"
+                    ))
+                }
+                CircuitState::HalfOpen => {
+                    inner.probe_in_flight = true;
+                    Some(Probe(self))
+                }
+            }
+        };
+        let result = request().await;
+        match &result {
+            Ok(_) => self.record_success(),
+            Err(err) if err.downcast_ref::<RateLimited>().is_some() => {}
+            Err(_) => self.record_failure(),
+        }
+        drop(probe);
+        result
+    }
+
+    pub fn record_success(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures = 0;
+        inner.opened_at = None;
+        self.transition(&mut inner, CircuitState::Closed);
+    }
+
+    pub fn record_failure(&self) {
+        let mut inner = self.lock();
+        inner.consecutive_failures += 1;
+        // Requests started before the circuit opened can still fail, they don't push the cooldown back
+        if inner.state == CircuitState::Open {
+            return;
+        }
+        if inner.state == CircuitState::HalfOpen
+            || inner.consecutive_failures >= self.failure_threshold
+        {
+            inner.opened_at = Some(Instant::now());
+            self.transition(&mut inner, CircuitState::Open);
+        }
+    }
+
+    fn refresh_state(&self, inner: &mut BreakerState) {
+        if inner.state != CircuitState::Open {
+            return;
+        }
+        let cooled_down = inner
+            .opened_at
+            .is_none_or(|opened_at| opened_at.elapsed() >= self.cooldown);
+        if cooled_down {
+            self.transition(inner, CircuitState::HalfOpen);
+        }
+    }
+
+    fn transition(&self, inner: &mut BreakerState, next_state: CircuitState) {
+        if inner.state == next_state {
+            return;
+        }
+        match next_state {
+            CircuitState::Open => warn!(
+                self.logger,
+                "circuit breaker {} -> {} after {} consecutive failures",
+                inner.state,
+                next_state,
+                inner.consecutive_failures
+            ),
+            _ => info!(
+                self.logger,
+                "circuit breaker {} -> {}", inner.state, next_state
+            ),
+        }
+        inner.state = next_state;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{CircuitBreaker, CircuitState, RateLimited};
+    use anyhow::anyhow;
+    use slog::{o, Discard, Logger};
+    use std::{
+        sync::atomic::{AtomicUsize, Ordering},
+        time::Duration,
+    };
+    use tokio::sync::oneshot;
+
+    struct MockFetcher {
+        calls: AtomicUsize,
+    }
+
+    impl MockFetcher {
+        async fn fetch(&self, should_fail: bool) -> Result<String, anyhow::Error> {
+            self.calls.fetch_add(1, Ordering::Relaxed);
+            if should_fail {
+                Err(anyhow!("NOAA is down"))
+            } else {
+                Ok(String::from("<dwml></dwml>"))
+            }
+        }
+    }
+
+    async fn rate_limited() -> Result<String, anyhow::Error> {
+        Err(anyhow::Error::new(RateLimited {
+            reason: String::from("429 Too Many Requests"),
+        }))
+    }
+
+    fn setup() -> (CircuitBreaker, MockFetcher) {
+        let breaker =
+            CircuitBreaker::new(Logger::root(Discard, o!()), 3, Duration::from_millis(50));
+        let fetcher = MockFetcher {
+            calls: AtomicUsize::new(0),
+        };
+        (breaker, fetcher)
+    }
+
+    #[tokio::test]
+    async fn opens_after_consecutive_failures() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            assert_eq!(breaker.state(), CircuitState::Closed);
+            assert!(breaker.call(|| fetcher.fetch(true)).await.is_err());
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        // short-circuits without calling the fetcher
+        assert!(breaker.call(|| fetcher.fetch(false)).await.is_err());
+        assert_eq!(fetcher.calls.load(Ordering::Relaxed), 3);
+    }
+
+    #[tokio::test]
+    async fn half_opens_after_cooldown_and_closes_on_success() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.call(|| fetcher.fetch(false)).await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn reopens_when_half_open_request_fails() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.call(|| fetcher.fetch(true)).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn half_open_lets_one_probe_through_at_a_time() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        let (breaker, fetcher) = (&breaker, &fetcher);
+        let (finish_probe, probe_finished) = oneshot::channel::<()>();
+        let probe = breaker.call(|| async move {
+            let _ = probe_finished.await;
+            fetcher.fetch(false).await
+        });
+        let while_probing = async move {
+            let result = breaker.call(|| fetcher.fetch(false)).await;
+            finish_probe.send(()).unwrap();
+            result
+        };
+        let (probe, while_probing) = tokio::join!(probe, while_probing);
+
+        assert!(probe.is_ok());
+        assert!(while_probing.is_err());
+        // Only the probe reached the fetcher after the 3 failures
+        assert_eq!(fetcher.calls.load(Ordering::Relaxed), 4);
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn cancelled_probe_lets_the_next_request_through() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        let cancelled = tokio::time::timeout(
+            Duration::from_millis(10),
+            breaker.call(|| std::future::pending::<Result<String, anyhow::Error>>()),
+        )
+        .await;
+        assert!(cancelled.is_err());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+
+        assert!(breaker.call(|| fetcher.fetch(false)).await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn failures_while_open_do_not_extend_the_cooldown() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        // A request that started before the circuit opened fails late
+        breaker.record_failure();
+        assert_eq!(breaker.state(), CircuitState::Open);
+
+        tokio::time::sleep(Duration::from_millis(30)).await;
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+    }
+
+    #[tokio::test]
+    async fn success_resets_failure_count() {
+        let (breaker, fetcher) = setup();
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        let _ = breaker.call(|| fetcher.fetch(false)).await;
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_requests_are_not_failures() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..5 {
+            let err = breaker.call(rate_limited).await.unwrap_err();
+            assert!(err.downcast_ref::<RateLimited>().is_some());
+        }
+        assert_eq!(breaker.state(), CircuitState::Closed);
+
+        // Nor do they reset the failures counted before them
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        let _ = breaker.call(rate_limited).await;
+        let _ = breaker.call(|| fetcher.fetch(true)).await;
+        assert_eq!(breaker.state(), CircuitState::Open);
+    }
+
+    #[tokio::test]
+    async fn rate_limited_probe_keeps_the_circuit_half_open() {
+        let (breaker, fetcher) = setup();
+        for _ in 0..3 {
+            let _ = breaker.call(|| fetcher.fetch(true)).await;
+        }
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(breaker.call(rate_limited).await.is_err());
+        assert_eq!(breaker.state(), CircuitState::HalfOpen);
+        assert!(breaker.call(|| fetcher.fetch(false)).await.is_ok());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -4,9 +4,10 @@ use std::{
     sync::Arc,
 };
 
-use crate::{Point, XmlFetcher};
+use crate::{check_response, with_retry, Activity, OracleRetry, PayloadKind, Point, XmlFetcher};
 use anyhow::Error;
 use serde::{Deserialize, Serialize};
+use slog::{error, info, Logger};
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub struct WeatherStation {
@@ -84,6 +85,12 @@ impl CityWeather {
         self.city_data
             .retain(|_, v| !(v.latitude == point.latitude && v.longitude == point.longitude));
     }
+    /// Drops every station not in `station_ids`
+    pub fn retain_stations(&mut self, station_ids: &HashSet<String>) {
+        self.city_data
+            .retain(|_, station| station_ids.contains(&station.station_id));
+    }
+
     pub fn get_station_ids(&self) -> HashSet<String> {
         let mut station_ids: HashSet<String> = HashSet::new();
         self.city_data.iter().for_each(|(_city_name, city_data)| {
@@ -135,9 +142,11 @@ pub async fn get_coordinates(fetcher: Arc<XmlFetcher>) -> Result<CityWeather, Er
     // Broken @ NOAA: https://forecast.weather.gov/xml/current_obs/index.xml
 
     let raw_xml = fetcher
-        .fetch_xml_gzip("https://aviationweather.gov/data/cache/stations.cache.xml.gz")
+        .fetch_xml_gzip(&fetcher.endpoints().stations_url())
         .await?;
-    let converted_xml: WxStationIndex = serde_xml_rs::from_str(&raw_xml)?;
+    let converted_xml: Result<WxStationIndex, _> = serde_xml_rs::from_str(&raw_xml);
+    fetcher.record_payload(PayloadKind::Stations, &raw_xml, converted_xml.is_err());
+    let converted_xml = converted_xml?;
 
     for station in converted_xml.data.station {
         // Skip any place not in the US
@@ -160,6 +169,88 @@ pub async fn get_coordinates(fetcher: Arc<XmlFetcher>) -> Result<CityWeather, Er
     Ok(CityWeather { city_data })
 }
 
+/// A station used by at least one of the oracle's unsigned events, from `GET /stations/active`
+#[derive(Debug, Deserialize, Serialize, Clone)]
+pub struct ActiveStation {
+    pub station_id: String,
+    pub event_count: usize,
+}
+
+/// Retries the lookup with `retry` while the oracle can't be reached, times out or is unavailable
+pub async fn get_active_station_ids(
+    logger: &Logger,
+    oracle_url: &str,
+    retry: OracleRetry,
+) -> Result<HashSet<String>, Error> {
+    let url = &format!("{}/stations/active", oracle_url.trim_end_matches('/'));
+    let client = &retry.client()?;
+    let stations: Vec<ActiveStation> = with_retry(
+        logger,
+        retry,
+        &format!("getting active stations from {}", url),
+        || async move {
+            let response = check_response(client.get(url).send().await?)?;
+            Ok(response.json().await?)
+        },
+    )
+    .await?;
+    Ok(stations
+        .into_iter()
+        .map(|station| station.station_id)
+        .collect())
+}
+
+/// Idle when none of the oracle's unsigned events use a station, an oracle that can't be reached counts as
+/// active so no data is missed
+pub async fn get_oracle_activity(
+    logger: &Logger,
+    oracle_url: &str,
+    retry: OracleRetry,
+) -> Activity {
+    match get_active_station_ids(logger, oracle_url, retry).await {
+        Ok(station_ids) if station_ids.is_empty() => Activity::Idle,
+        Ok(_) => Activity::Active,
+        Err(err) => {
+            error!(
+                logger,
+                "error getting active stations from the oracle, treating it as active: {}", err
+            );
+            Activity::Active
+        }
+    }
+}
+
+/// All NOAA stations, or only the ones the oracle's events use when `oracle_url` is set. Falls back to all
+/// stations if the oracle can't be reached so no data is missed
+pub async fn get_station_coordinates(
+    logger: &Logger,
+    fetcher: Arc<XmlFetcher>,
+    oracle_url: Option<&str>,
+    retry: OracleRetry,
+) -> Result<CityWeather, Error> {
+    let mut city_weather = get_coordinates(fetcher).await?;
+    let Some(oracle_url) = oracle_url else {
+        return Ok(city_weather);
+    };
+    match get_active_station_ids(logger, oracle_url, retry).await {
+        Ok(station_ids) => {
+            let total = city_weather.city_data.len();
+            city_weather.retain_stations(&station_ids);
+            info!(
+                logger,
+                "fetching {} of {} stations, only the stations used by the oracle's events",
+                city_weather.city_data.len(),
+                total
+            );
+        }
+        Err(err) => error!(
+            logger,
+            "error getting active stations from the oracle, fetching all stations: {}", err
+        ),
+    }
+    Ok(city_weather)
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(rename = "response")]
 pub struct WxStationIndex {
@@ -229,3 +320,234 @@ pub struct Request {
     #[serde(rename = "type")]
     request_type: String,
 }
+
+#[cfg(test)]
+mod test {
+    use super::{get_oracle_activity, get_station_coordinates};
+    use crate::{
+        run_poll_schedule,
+        test_server::{spawn_server, Response},
+        Activity, CircuitBreaker, NoaaEndpoints, OracleRetry, PollSchedule, RateLimiter,
+        XmlFetcher,
+    };
+    use async_compression::tokio::bufread::GzipEncoder;
+    use slog::{o, Discard, Logger};
+    use std::{
+        collections::HashSet,
+        future::ready,
+        sync::{Arc, Mutex as StdMutex},
+        time::{Duration, Instant},
+    };
+    use tokio::{io::AsyncReadExt, net::TcpListener, sync::Mutex, time::timeout};
+    use tokio_util::sync::CancellationToken;
+
+    const STATIONS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<response>
+  <request_index>1</request_index>
+  <data_source name="stations" />
+  <request type="retrieve" />
+  <errors />
+  <warnings />
+  <data num_results="3">
+    <Station>
+      <station_id>KLWV</station_id>
+      <latitude>38.76</latitude>
+      <longitude>-87.61</longitude>
+      <elevation_m>130</elevation_m>
+      <site>Lawrenceville</site>
+      <state>IL</state>
+      <country>US</country>
+    </Station>
+    <Station>
+      <station_id>KLBB</station_id>
+      <latitude>33.66</latitude>
+      <longitude>-101.82</longitude>
+      <elevation_m>992</elevation_m>
+      <site>Lubbock</site>
+      <state>TX</state>
+      <country>US</country>
+    </Station>
+    <Station>
+      <station_id>KSAW</station_id>
+      <latitude>46.35</latitude>
+      <longitude>-87.40</longitude>
+      <elevation_m>435</elevation_m>
+      <site>Marquette</site>
+      <state>MI</state>
+      <country>US</country>
+    </Station>
+  </data>
+</response>"#;
+
+    /// Serves the same response to every request, returns the server's base url
+    async fn serve(body: Vec<u8>) -> String {
+        spawn_server(move |_, _| ready(Response::ok(body.clone())))
+            .await
+            .url()
+    }
+
+    async fn fetcher() -> Arc<XmlFetcher> {
+        let mut gzipped = vec![];
+        GzipEncoder::new(STATIONS_XML.as_bytes())
+            .read_to_end(&mut gzipped)
+            .await
+            .unwrap();
+        let endpoints = NoaaEndpoints {
+            aviation_weather: serve(gzipped).await,
+            ..Default::default()
+        };
+        let logger = Logger::root(Discard, o!());
+        Arc::new(
+            XmlFetcher::new(
+                logger.clone(),
+                String::from("noaa-data-pipeline/test"),
+                Arc::new(Mutex::new(RateLimiter::new(3, 15.0))),
+                Arc::new(CircuitBreaker::new(logger, 5, Duration::from_secs(300))),
+            )
+            .with_endpoints(endpoints),
+        )
+    }
+
+    fn retry() -> OracleRetry {
+        OracleRetry {
+            max_retries: 1,
+            initial_delay: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    fn station_ids(ids: &[&str]) -> HashSet<String> {
+        ids.iter().map(|id| id.to_string()).collect()
+    }
+
+    #[tokio::test]
+    async fn scopes_stations_to_the_oracles_active_stations() {
+        let oracle_url = serve(
+            br#"[{"station_id":"KSAW","event_count":2},{"station_id":"KLWV","event_count":1},{"station_id":"PFNO","event_count":1}]"#
+                .to_vec(),
+        )
+        .await;
+        let logger = Logger::root(Discard, o!());
+
+        let city_weather =
+            get_station_coordinates(&logger, fetcher().await, Some(&oracle_url), retry())
+                .await
+                .unwrap();
+
+        // PFNO isn't in NOAA's station list so there is nothing to fetch for it
+        assert_eq!(
+            city_weather.get_station_ids(),
+            station_ids(&["KLWV", "KSAW"])
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_all_stations_without_the_oracle() {
+        let logger = Logger::root(Discard, o!());
+
+        let city_weather = get_station_coordinates(&logger, fetcher().await, None, retry())
+            .await
+            .unwrap();
+
+        assert_eq!(
+            city_weather.get_station_ids(),
+            station_ids(&["KLBB", "KLWV", "KSAW"])
+        );
+    }
+
+    #[tokio::test]
+    async fn fetches_all_stations_when_the_oracle_is_unreachable() {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let oracle_url = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+        let logger = Logger::root(Discard, o!());
+
+        let city_weather =
+            get_station_coordinates(&logger, fetcher().await, Some(&oracle_url), retry())
+                .await
+                .unwrap();
+
+        assert_eq!(city_weather.city_data.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn reports_activity_from_the_oracles_active_stations() {
+        let logger = Logger::root(Discard, o!());
+        let idle_oracle = serve(b"[]".to_vec()).await;
+        let active_oracle = serve(br#"[{"station_id":"KSAW","event_count":1}]"#.to_vec()).await;
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let unreachable_oracle = format!("http://{}", listener.local_addr().unwrap());
+        drop(listener);
+
+        assert_eq!(
+            get_oracle_activity(&logger, &idle_oracle, retry()).await,
+            Activity::Idle
+        );
+        assert_eq!(
+            get_oracle_activity(&logger, &active_oracle, retry()).await,
+            Activity::Active
+        );
+        assert_eq!(
+            get_oracle_activity(&logger, &unreachable_oracle, retry()).await,
+            Activity::Active
+        );
+    }
+
+    /// Time between each run of a pipeline polling `oracle_url` for activity
+    async fn run_gaps(oracle_url: &str, idle_backoff: Option<Duration>) -> Vec<Duration> {
+        let logger = Logger::root(Discard, o!());
+        let runs = StdMutex::new(vec![]);
+        let schedule = PollSchedule {
+            forecast_interval: Duration::from_millis(20),
+            observation_interval: Duration::from_secs(60),
+            idle_backoff,
+        };
+
+        let shutdown = CancellationToken::new();
+        let (logger, runs_ref) = (&logger, &runs);
+        let _ = timeout(
+            Duration::from_millis(500),
+            run_poll_schedule(
+                schedule,
+                &shutdown,
+                move || async move {
+                    runs_ref.lock().unwrap().push(Instant::now());
+                    get_oracle_activity(logger, oracle_url, retry()).await
+                },
+                || async { Activity::Active },
+            ),
+        )
+        .await;
+
+        let runs = runs.into_inner().unwrap();
+        runs.windows(2).map(|pair| pair[1] - pair[0]).collect()
+    }
+
+    #[tokio::test]
+    async fn backs_off_while_the_oracle_has_no_active_events() {
+        let oracle_url = serve(b"[]".to_vec()).await;
+
+        let gaps = run_gaps(&oracle_url, Some(Duration::from_millis(160))).await;
+
+        // waits 40, 80, then 160ms from then on
+        assert!(gaps.len() >= 3, "gaps: {:?}", gaps);
+        assert!(gaps[0] >= Duration::from_millis(40), "gaps: {:?}", gaps);
+        assert!(gaps[1] >= Duration::from_millis(80), "gaps: {:?}", gaps);
+        assert!(gaps[2] >= Duration::from_millis(160), "gaps: {:?}", gaps);
+        assert!(gaps[2] < Duration::from_millis(300), "gaps: {:?}", gaps);
+    }
+
+    #[tokio::test]
+    async fn keeps_interval_while_the_oracle_has_active_events() {
+        let oracle_url = serve(br#"[{"station_id":"KSAW","event_count":1}]"#.to_vec()).await;
+
+        let gaps = run_gaps(&oracle_url, Some(Duration::from_millis(160))).await;
+
+        assert!(gaps.len() >= 10, "gaps: {:?}", gaps);
+        assert!(
+            gaps.iter().all(|gap| *gap < Duration::from_millis(80)),
+            "gaps: {:?}",
+            gaps
+        );
+    }
+}
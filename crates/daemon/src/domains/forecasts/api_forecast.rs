@@ -0,0 +1,463 @@
+use crate::{
+    get_forecasts_ranges, ForecastProduct, Location, Point, WeatherForecast, WeatherStation,
+};
+use anyhow::{anyhow, Error};
+use serde::Deserialize;
+use std::fmt::{self, Display};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+
+/*
+api.weather.gov forecasts are pulled in two steps:
+https://api.weather.gov/points/{latitude},{longitude} -> gives the forecastGridData url for the point
+https://api.weather.gov/gridpoints/{office}/{grid x},{grid y} -> gives the raw forecast values
+
+Each gridpoint value is a time series with its own unit of measure and ISO 8601 intervals,
+ie. "2024-08-12T12:00:00+00:00/PT6H", which are folded into the same 3 hour windows the XML path uses
+*/
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ForecastSource {
+    /// Legacy graphical.weather.gov NDFD XML
+    #[default]
+    Xml,
+    /// api.weather.gov JSON, stations it can't provide fall back to the XML source
+    Api,
+}
+
+impl Display for ForecastSource {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForecastSource::Xml => write!(f, "xml"),
+            ForecastSource::Api => write!(f, "api"),
+        }
+    }
+}
+
+impl TryFrom<&str> for ForecastSource {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.trim() {
+            "xml" => Ok(ForecastSource::Xml),
+            "api" => Ok(ForecastSource::Api),
+            val => Err(anyhow!("invalid forecast source: {}", val)),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiPoint {
+    pub properties: ApiPointProperties,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiPointProperties {
+    pub forecast_grid_data: String,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiGridpoint {
+    pub properties: ApiGridpointProperties,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiGridpointProperties {
+    pub update_time: Option<String>,
+    pub max_temperature: Option<ApiSeries>,
+    pub min_temperature: Option<ApiSeries>,
+    pub wind_speed: Option<ApiSeries>,
+    pub wind_direction: Option<ApiSeries>,
+    pub relative_humidity: Option<ApiSeries>,
+    pub quantitative_precipitation: Option<ApiSeries>,
+    pub probability_of_precipitation: Option<ApiSeries>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+pub struct ApiSeries {
+    pub uom: String,
+    pub values: Vec<ApiValue>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiValue {
+    pub valid_time: String,
+    pub value: Option<f64>,
+}
+
+struct ApiReading {
+    start: OffsetDateTime,
+    end: OffsetDateTime,
+    value: f64,
+}
+
+pub fn get_api_point_url(
+    weather_api_url: &str,
+    weather_station: &WeatherStation,
+) -> Result<String, Error> {
+    let latitude = weather_station.latitude.parse::<f64>()?;
+    let longitude = weather_station.longitude.parse::<f64>()?;
+    // api.weather.gov redirects requests with more than 4 decimal places
+    Ok(format!(
+        "{}/points/{:.4},{:.4}",
+        weather_api_url, latitude, longitude
+    ))
+}
+
+pub fn parse_api_point(raw_json: &str) -> Result<ApiPoint, Error> {
+    serde_json::from_str(raw_json).map_err(|e| anyhow!("error deserializing point json: {}", e))
+}
+
+pub fn parse_api_gridpoint(raw_json: &str) -> Result<ApiGridpoint, Error> {
+    serde_json::from_str(raw_json).map_err(|e| anyhow!("error deserializing gridpoint json: {}", e))
+}
+
+/// Maps a gridpoint forecast into 3 hour windows for a week from `start`, in the same units the XML path requests
+pub fn api_gridpoint_to_forecasts(
+    weather_station: &WeatherStation,
+    gridpoint: ApiGridpoint,
+    products: &[ForecastProduct],
+    start: OffsetDateTime,
+) -> Result<Vec<WeatherForecast>, Error> {
+    let properties = gridpoint.properties;
+    let generated_at = properties
+        .update_time
+        .as_ref()
+        .and_then(|update_time| OffsetDateTime::parse(update_time, &Rfc3339).ok())
+        .unwrap_or(OffsetDateTime::now_utc());
+    let location = Location {
+        location_key: weather_station.station_id.clone(),
+        point: Point {
+            latitude: weather_station.get_latitude(),
+            longitude: weather_station.get_longitude(),
+        },
+        station_id: Some(weather_station.station_id.clone()),
+    };
+
+    let requested = |product: ForecastProduct, series: &Option<ApiSeries>| {
+        if products.contains(&product) {
+            series.as_ref().map(to_readings).transpose()
+        } else {
+            Ok(None)
+        }
+    };
+    let max_temps = requested(ForecastProduct::MaxTemp, &properties.max_temperature)?;
+    let min_temps = requested(ForecastProduct::MinTemp, &properties.min_temperature)?;
+    let wind_speeds = requested(ForecastProduct::WindSpeed, &properties.wind_speed)?;
+    let wind_directions = requested(ForecastProduct::WindDirection, &properties.wind_direction)?;
+    let max_humidities = requested(
+        ForecastProduct::MaxRelativeHumidity,
+        &properties.relative_humidity,
+    )?;
+    let min_humidities = requested(
+        ForecastProduct::MinRelativeHumidity,
+        &properties.relative_humidity,
+    )?;
+    let precipitation = requested(
+        ForecastProduct::LiquidPrecipitation,
+        &properties.quantitative_precipitation,
+    )?;
+    let probability_of_precipitation = requested(
+        ForecastProduct::TwelveHourProbabilityOfPrecipitation,
+        &properties.probability_of_precipitation,
+    )?;
+
+    let mut forecasts = get_forecasts_ranges(&location, generated_at, start);
+    for forecast in forecasts.iter_mut() {
+        let window = (forecast.begin_time, forecast.end_time);
+        forecast.max_temp = max_in_window(&max_temps, window).map(|val| val.round() as i64);
+        forecast.min_temp = min_in_window(&min_temps, window).map(|val| val.round() as i64);
+        forecast.wind_speed = max_in_window(&wind_speeds, window).map(|val| val.round() as i64);
+        forecast.wind_direction =
+            first_in_window(&wind_directions, window).map(|val| val.round() as i64);
+        forecast.relative_humidity_max =
+            max_in_window(&max_humidities, window).map(|val| val.round() as i64);
+        forecast.relative_humidity_min =
+            min_in_window(&min_humidities, window).map(|val| val.round() as i64);
+        // Rounded to the configured `precipitation_decimals` with the XML forecasts when converted to parquet
+        forecast.liquid_precipitation_amt = first_in_window(&precipitation, window);
+        forecast.twelve_hour_probability_of_precipitation =
+            max_in_window(&probability_of_precipitation, window).map(|val| val.round() as i64);
+    }
+    Ok(forecasts)
+}
+
+fn to_readings(series: &ApiSeries) -> Result<Vec<ApiReading>, Error> {
+    let mut readings = vec![];
+    for api_value in &series.values {
+        let Some(value) = api_value.value else {
+            continue;
+        };
+        let (start, end) = parse_valid_time(&api_value.valid_time)?;
+        readings.push(ApiReading {
+            start,
+            end,
+            value: convert_unit(&series.uom, value)?,
+        });
+    }
+    Ok(readings)
+}
+
+// The XML path requests english units (Unit=e), convert to match so both sources produce the same parquet
+fn convert_unit(uom: &str, value: f64) -> Result<f64, Error> {
+    match uom {
+        "wmoUnit:degC" => Ok(value * 9.0 / 5.0 + 32.0),
+        "wmoUnit:degF" => Ok(value),
+        "wmoUnit:km_h-1" => Ok(value * 0.539957),
+        "wmoUnit:m_s-1" => Ok(value * 1.943844),
+        "wmoUnit:kt" => Ok(value),
+        "wmoUnit:mm" => Ok(value / 25.4),
+        "wmoUnit:degree_(angle)" | "wmoUnit:percent" => Ok(value),
+        val => Err(anyhow!("unsupported unit of measure: {}", val)),
+    }
+}
+
+/// Parses an ISO 8601 interval of a start time and duration, ie. "2024-08-12T12:00:00+00:00/P1DT6H"
+pub fn parse_valid_time(valid_time: &str) -> Result<(OffsetDateTime, OffsetDateTime), Error> {
+    let (raw_start, raw_duration) = valid_time
+        .split_once('/')
+        .ok_or_else(|| anyhow!("invalid valid time: {}", valid_time))?;
+    let start = OffsetDateTime::parse(raw_start, &Rfc3339)
+        .map_err(|e| anyhow!("error parsing valid time start {}: {}", raw_start, e))?;
+    let end = start + parse_duration(raw_duration)?;
+    Ok((start, end))
+}
+
+fn parse_duration(raw_duration: &str) -> Result<Duration, Error> {
+    let invalid = || anyhow!("invalid duration: {}", raw_duration);
+    let raw_duration = raw_duration.strip_prefix('P').ok_or_else(invalid)?;
+    let mut duration = Duration::ZERO;
+    let mut in_time = false;
+    let mut amount = String::new();
+    for current in raw_duration.chars() {
+        match current {
+            'T' => in_time = true,
+            '0'..='9' => amount.push(current),
+            unit => {
+                let value = amount.parse::<i64>().map_err(|_| invalid())?;
+                amount.clear();
+                duration += match (unit, in_time) {
+                    ('D', false) => Duration::days(value),
+                    ('H', true) => Duration::hours(value),
+                    ('M', true) => Duration::minutes(value),
+                    _ => return Err(invalid()),
+                };
+            }
+        }
+    }
+    if !amount.is_empty() || duration == Duration::ZERO {
+        return Err(invalid());
+    }
+    Ok(duration)
+}
+
+fn in_window<'a>(
+    readings: &'a Option<Vec<ApiReading>>,
+    (begin, end): (OffsetDateTime, OffsetDateTime),
+) -> impl Iterator<Item = f64> + 'a {
+    readings
+        .iter()
+        .flatten()
+        .filter(move |reading| reading.start < end && reading.end > begin)
+        .map(|reading| reading.value)
+}
+
+fn max_in_window(
+    readings: &Option<Vec<ApiReading>>,
+    window: (OffsetDateTime, OffsetDateTime),
+) -> Option<f64> {
+    in_window(readings, window).reduce(f64::max)
+}
+
+fn min_in_window(
+    readings: &Option<Vec<ApiReading>>,
+    window: (OffsetDateTime, OffsetDateTime),
+) -> Option<f64> {
+    in_window(readings, window).reduce(f64::min)
+}
+
+fn first_in_window(
+    readings: &Option<Vec<ApiReading>>,
+    window: (OffsetDateTime, OffsetDateTime),
+) -> Option<f64> {
+    in_window(readings, window).next()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        api_gridpoint_to_forecasts, parse_api_gridpoint, parse_api_point, parse_valid_time,
+        ForecastSource,
+    };
+    use crate::{Forecast, ForecastProduct, Units, WeatherStation};
+    use time::macros::datetime;
+
+    const GRIDPOINT_FIXTURE: &str = include_str!(concat!(
+        env!("CARGO_MANIFEST_DIR"),
+        "/tests/fixtures/api_gridpoint.json"
+    ));
+
+    fn station() -> WeatherStation {
+        WeatherStation {
+            station_id: String::from("KLWV"),
+            station_name: String::from("Lawrenceville"),
+            latitude: String::from("38.76"),
+            longitude: String::from("-87.61"),
+        }
+    }
+
+    #[test]
+    fn can_parse_forecast_source() {
+        assert_eq!(
+            ForecastSource::try_from("api").unwrap(),
+            ForecastSource::Api
+        );
+        assert_eq!(
+            ForecastSource::try_from("xml").unwrap(),
+            ForecastSource::Xml
+        );
+        assert!(ForecastSource::try_from("json").is_err());
+    }
+
+    #[test]
+    fn can_parse_valid_time_intervals() {
+        let (start, end) = parse_valid_time("2024-08-12T12:00:00+00:00/PT6H").unwrap();
+        assert_eq!(start, datetime!(2024-08-12 12:00:00 UTC));
+        assert_eq!(end, datetime!(2024-08-12 18:00:00 UTC));
+
+        let (_, end) = parse_valid_time("2024-08-12T12:00:00+00:00/P1DT6H").unwrap();
+        assert_eq!(end, datetime!(2024-08-13 18:00:00 UTC));
+
+        assert!(parse_valid_time("2024-08-12T12:00:00+00:00/6H").is_err());
+        assert!(parse_valid_time("2024-08-12T12:00:00+00:00").is_err());
+    }
+
+    #[test]
+    fn can_parse_point_grid_data_url() {
+        let raw_point = r#"{
+            "type": "Feature",
+            "properties": {
+                "gridId": "ILX",
+                "gridX": 95,
+                "gridY": 26,
+                "forecast": "https://api.weather.gov/gridpoints/ILX/95,26/forecast",
+                "forecastGridData": "https://api.weather.gov/gridpoints/ILX/95,26"
+            }
+        }"#;
+        let point = parse_api_point(raw_point).unwrap();
+        assert_eq!(
+            point.properties.forecast_grid_data,
+            "https://api.weather.gov/gridpoints/ILX/95,26"
+        );
+    }
+
+    #[test]
+    fn can_map_gridpoint_to_weather_forecasts() {
+        let gridpoint = parse_api_gridpoint(GRIDPOINT_FIXTURE).unwrap();
+        let forecasts = api_gridpoint_to_forecasts(
+            &station(),
+            gridpoint,
+            &ForecastProduct::all(),
+            datetime!(2024-08-12 12:00:00 UTC),
+        )
+        .unwrap();
+
+        let first = forecasts.first().unwrap();
+        assert_eq!(first.station_id, "KLWV");
+        assert_eq!(first.generated_at, datetime!(2024-08-12 09:14:39 UTC));
+        assert_eq!(first.begin_time, datetime!(2024-08-12 12:00:00 UTC));
+        assert_eq!(first.end_time, datetime!(2024-08-12 15:00:00 UTC));
+        assert_eq!(first.max_temp, Some(86));
+        assert_eq!(first.min_temp, None);
+        assert_eq!(first.temperature_unit_code, Units::Fahrenheit.to_string());
+        assert_eq!(first.wind_speed, Some(10));
+        assert_eq!(first.wind_speed_unit_code, Units::Knots.to_string());
+        assert_eq!(first.wind_direction, Some(180));
+        assert_eq!(first.relative_humidity_max, Some(40));
+        assert_eq!(first.relative_humidity_min, Some(40));
+        assert_eq!(first.liquid_precipitation_amt, Some(1.0));
+        assert_eq!(
+            first.liquid_precipitation_unit_code,
+            Units::Inches.to_string()
+        );
+        assert_eq!(first.twelve_hour_probability_of_precipitation, Some(30));
+
+        let overnight = forecasts
+            .iter()
+            .find(|forecast| forecast.begin_time == datetime!(2024-08-13 00:00:00 UTC))
+            .unwrap();
+        assert_eq!(overnight.max_temp, None);
+        assert_eq!(overnight.min_temp, Some(68));
+        assert_eq!(overnight.wind_speed, None);
+    }
+
+    #[test]
+    fn only_maps_requested_products() {
+        let gridpoint = parse_api_gridpoint(GRIDPOINT_FIXTURE).unwrap();
+        let forecasts = api_gridpoint_to_forecasts(
+            &station(),
+            gridpoint,
+            &[ForecastProduct::MaxTemp],
+            datetime!(2024-08-12 12:00:00 UTC),
+        )
+        .unwrap();
+
+        let first = forecasts.first().unwrap();
+        assert_eq!(first.max_temp, Some(86));
+        assert_eq!(first.wind_speed, None);
+        assert_eq!(first.relative_humidity_max, None);
+        assert_eq!(first.liquid_precipitation_amt, None);
+    }
+
+    #[test]
+    fn rejects_unknown_units() {
+        let raw_gridpoint = r#"{
+            "properties": {
+                "maxTemperature": {
+                    "uom": "wmoUnit:K",
+                    "values": [{ "validTime": "2024-08-12T12:00:00+00:00/PT12H", "value": 300.0 }]
+                }
+            }
+        }"#;
+        let gridpoint = parse_api_gridpoint(raw_gridpoint).unwrap();
+        let res = api_gridpoint_to_forecasts(
+            &station(),
+            gridpoint,
+            &ForecastProduct::all(),
+            datetime!(2024-08-12 12:00:00 UTC),
+        );
+        assert!(res.is_err());
+    }
+
+    #[test]
+    fn rounds_precipitation_to_the_configured_decimals() {
+        let raw_gridpoint = r#"{
+            "properties": {
+                "quantitativePrecipitation": {
+                    "uom": "wmoUnit:mm",
+                    "values": [{ "validTime": "2024-08-12T12:00:00+00:00/PT6H", "value": 10.0 }]
+                }
+            }
+        }"#;
+        let precipitation = |decimals: Option<u32>| {
+            let gridpoint = parse_api_gridpoint(raw_gridpoint).unwrap();
+            let forecasts = api_gridpoint_to_forecasts(
+                &station(),
+                gridpoint,
+                &ForecastProduct::all(),
+                datetime!(2024-08-12 12:00:00 UTC),
+            )
+            .unwrap();
+            let first = forecasts.first().unwrap().clone();
+            Forecast::try_from_weather_forecast(first, decimals)
+                .unwrap()
+                .liquid_precipitation_amt
+        };
+
+        assert_eq!(precipitation(Some(4)), Some(0.3937));
+        assert_eq!(precipitation(Some(1)), Some(0.4));
+        // without a precision the converted amount is written as is
+        assert_eq!(precipitation(None), Some(10.0 / 25.4));
+    }
+}
@@ -3,7 +3,9 @@ use crate::Type::{
     ProbabilityOfPrecipitationWithin12Hours, Sustained, Wind,
 };
 use crate::{
-    split_cityweather, CityWeather, DataReading, Dwml, Location, Units, WeatherStation, XmlFetcher,
+    api_gridpoint_to_forecasts, get_api_point_url, parse_api_gridpoint, parse_api_point,
+    split_cityweather, CityWeather, DataReading, Dwml, FetchTimings, ForecastSource, Location,
+    PayloadKind, SanityBounds, Units, WeatherStation, XmlFetcher,
 };
 use anyhow::{anyhow, Error};
 use core::time::Duration as StdDuration;
@@ -13,17 +15,23 @@ use parquet::{
     schema::types::Type,
 };
 use parquet_derive::ParquetRecordWriter;
+use serde::Deserialize;
 use serde_xml_rs::from_str;
-use slog::{debug, error, info, Logger};
-use std::sync::atomic::{AtomicUsize, Ordering};
+use slog::{debug, error, info, warn, Logger};
 use std::sync::Arc;
-use std::{collections::HashMap, ops::Add};
+use std::time::Instant;
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    ops::Add,
+};
 use time::{
     format_description::well_known::Rfc3339, macros::format_description, Duration, OffsetDateTime,
 };
 use tokio::sync::{mpsc, Mutex};
 use tokio::task::JoinSet;
 use tokio::time::sleep;
+use tokio_util::sync::CancellationToken;
 /*
 More Options defined  here:
 https://graphical.weather.gov/xml/docs/elementInputNames.php
@@ -37,14 +45,108 @@ Liquid Precipitation Amount 	qpf
 Maximum Relative Humidity 	maxrh
 Minimum Relative Humidity 	minrh
 */
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForecastProduct {
+    MaxTemp,
+    MinTemp,
+    WindSpeed,
+    WindDirection,
+    TwelveHourProbabilityOfPrecipitation,
+    LiquidPrecipitation,
+    MaxRelativeHumidity,
+    MinRelativeHumidity,
+}
+
+impl ForecastProduct {
+    pub fn all() -> Vec<ForecastProduct> {
+        vec![
+            ForecastProduct::MaxTemp,
+            ForecastProduct::MinTemp,
+            ForecastProduct::WindSpeed,
+            ForecastProduct::WindDirection,
+            ForecastProduct::TwelveHourProbabilityOfPrecipitation,
+            ForecastProduct::LiquidPrecipitation,
+            ForecastProduct::MaxRelativeHumidity,
+            ForecastProduct::MinRelativeHumidity,
+        ]
+    }
+
+    /// Name of the element used by NOAA's ndfdXMLclient query params
+    pub fn param(&self) -> &'static str {
+        match self {
+            ForecastProduct::MaxTemp => "maxt",
+            ForecastProduct::MinTemp => "mint",
+            ForecastProduct::WindSpeed => "wspd",
+            ForecastProduct::WindDirection => "wdir",
+            ForecastProduct::TwelveHourProbabilityOfPrecipitation => "pop12",
+            ForecastProduct::LiquidPrecipitation => "qpf",
+            ForecastProduct::MaxRelativeHumidity => "maxrh",
+            ForecastProduct::MinRelativeHumidity => "minrh",
+        }
+    }
+
+    fn is_reading(&self, reading: &DataReading) -> bool {
+        matches!(
+            (self, &reading.reading_type),
+            (ForecastProduct::MaxTemp, Maximum)
+                | (ForecastProduct::MinTemp, Minimum)
+                | (ForecastProduct::WindSpeed, Sustained)
+                | (ForecastProduct::WindDirection, Wind)
+                | (
+                    ForecastProduct::TwelveHourProbabilityOfPrecipitation,
+                    ProbabilityOfPrecipitationWithin12Hours
+                )
+                | (ForecastProduct::LiquidPrecipitation, Liquid)
+                | (ForecastProduct::MaxRelativeHumidity, MaximumRelative)
+                | (ForecastProduct::MinRelativeHumidity, MinimumRelative)
+        )
+    }
+}
+
+impl TryFrom<&str> for ForecastProduct {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        ForecastProduct::all()
+            .into_iter()
+            .find(|product| product.param() == s.trim())
+            .ok_or_else(|| anyhow!("invalid forecast product: {}", s))
+    }
+}
+
+/// Converts the requested product names into products, all products are requested when none are provided
+pub fn get_forecast_products(
+    requested: Option<Vec<String>>,
+) -> Result<Vec<ForecastProduct>, Error> {
+    let Some(requested) = requested else {
+        return Ok(ForecastProduct::all());
+    };
+    let mut products: Vec<ForecastProduct> = vec![];
+    for product in requested {
+        let product = ForecastProduct::try_from(product.as_str())?;
+        if !products.contains(&product) {
+            products.push(product);
+        }
+    }
+    if products.is_empty() {
+        return Err(anyhow!(
+            "at least one forecast product needs to be requested"
+        ));
+    }
+    Ok(products)
+}
+
+#[derive(Debug, Clone, Deserialize)]
 pub struct WeatherForecast {
     pub station_id: String,
     pub station_name: String,
     pub latitude: String,
     pub longitude: String,
+    #[serde(with = "time::serde::rfc3339")]
     pub generated_at: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
     pub begin_time: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
     pub end_time: OffsetDateTime,
     pub max_temp: Option<i64>,
     pub min_temp: Option<i64>,
@@ -73,25 +175,26 @@ pub struct Forecast {
     pub end_time: String,
     pub max_temp: Option<i64>,
     pub min_temp: Option<i64>,
-    pub temperature_unit_code: String,
+    pub temperature_unit_code: Option<String>,
     pub wind_speed: Option<i64>,
-    pub wind_speed_unit_code: String,
+    pub wind_speed_unit_code: Option<String>,
     pub wind_direction: Option<i64>,
-    pub wind_direction_unit_code: String,
+    pub wind_direction_unit_code: Option<String>,
     pub relative_humidity_max: Option<i64>,
     pub relative_humidity_min: Option<i64>,
-    pub relative_humidity_unit_code: String,
+    pub relative_humidity_unit_code: Option<String>,
     pub liquid_precipitation_amt: Option<f64>,
-    pub liquid_precipitation_unit_code: String,
+    pub liquid_precipitation_unit_code: Option<String>,
     pub twelve_hour_probability_of_precipitation: Option<i64>,
-    pub twelve_hour_probability_of_precipitation_unit_code: String,
+    pub twelve_hour_probability_of_precipitation_unit_code: Option<String>,
 }
 
 impl TryFrom<WeatherForecast> for Forecast {
     type Error = anyhow::Error;
     fn try_from(val: WeatherForecast) -> Result<Self, Self::Error> {
         let parquet = Forecast {
-            station_id: val.station_id,
+            // The oracle filters the parquet files on upper case station ids as stored
+            station_id: val.station_id.to_ascii_uppercase(),
             station_name: String::from(""),
             latitude: val.latitude.parse::<f64>()?,
             longitude: val.longitude.parse::<f64>()?,
@@ -109,24 +212,73 @@ impl TryFrom<WeatherForecast> for Forecast {
                 .map_err(|e| anyhow!("error formatting end time: {}", e))?,
             max_temp: val.max_temp,
             min_temp: val.min_temp,
-            temperature_unit_code: val.temperature_unit_code,
+            temperature_unit_code: unit_code(
+                val.temperature_unit_code,
+                val.max_temp.is_some() || val.min_temp.is_some(),
+            ),
             wind_speed: val.wind_speed,
-            wind_speed_unit_code: val.wind_speed_unit_code,
+            wind_speed_unit_code: unit_code(val.wind_speed_unit_code, val.wind_speed.is_some()),
             wind_direction: val.wind_direction,
-            wind_direction_unit_code: val.wind_direction_unit_code,
+            wind_direction_unit_code: unit_code(
+                val.wind_direction_unit_code,
+                val.wind_direction.is_some(),
+            ),
             relative_humidity_max: val.relative_humidity_max,
             relative_humidity_min: val.relative_humidity_min,
-            relative_humidity_unit_code: val.relative_humidity_unit_code,
+            relative_humidity_unit_code: unit_code(
+                val.relative_humidity_unit_code,
+                val.relative_humidity_max.is_some() || val.relative_humidity_min.is_some(),
+            ),
             liquid_precipitation_amt: val.liquid_precipitation_amt,
-            liquid_precipitation_unit_code: val.liquid_precipitation_unit_code,
+            liquid_precipitation_unit_code: unit_code(
+                val.liquid_precipitation_unit_code,
+                val.liquid_precipitation_amt.is_some(),
+            ),
             twelve_hour_probability_of_precipitation: val.twelve_hour_probability_of_precipitation,
-            twelve_hour_probability_of_precipitation_unit_code: val
-                .twelve_hour_probability_of_precipitation_unit_code,
+            twelve_hour_probability_of_precipitation_unit_code: unit_code(
+                val.twelve_hour_probability_of_precipitation_unit_code,
+                val.twelve_hour_probability_of_precipitation.is_some(),
+            ),
         };
         Ok(parquet)
     }
 }
 
+impl Forecast {
+    /// Converts the forecast, rounding `liquid_precipitation_amt` to `precipitation_decimals` places when set.
+    /// The rounding is lossy, the raw NOAA amount can't be recovered from the rounded value.
+    pub fn try_from_weather_forecast(
+        val: WeatherForecast,
+        precipitation_decimals: Option<u32>,
+    ) -> Result<Self, Error> {
+        let mut forecast = Forecast::try_from(val)?;
+        if let Some(decimals) = precipitation_decimals {
+            forecast.liquid_precipitation_amt = forecast
+                .liquid_precipitation_amt
+                .map(|amt| round_to_decimals(amt, decimals));
+        }
+        Ok(forecast)
+    }
+}
+
+pub fn round_to_decimals(value: f64, decimals: u32) -> f64 {
+    // past 15 decimals an f64 can't hold the extra precision, scaling by the factor would only add error
+    if decimals >= 15 {
+        return value;
+    }
+    let factor = 10_f64.powi(decimals as i32);
+    (value * factor).round() / factor
+}
+
+// A unit code is only meaningful when there is a value it describes
+fn unit_code(unit_code: String, has_value: bool) -> Option<String> {
+    if has_value && !unit_code.is_empty() {
+        Some(unit_code)
+    } else {
+        None
+    }
+}
+
 pub fn create_forecast_schema() -> Type {
     let station_id = Type::primitive_type_builder("station_id", PhysicalType::BYTE_ARRAY)
         .with_logical_type(Some(LogicalType::String))
@@ -181,7 +333,7 @@ pub fn create_forecast_schema() -> Type {
     let temperature_unit_code =
         Type::primitive_type_builder("temperature_unit_code", PhysicalType::BYTE_ARRAY)
             .with_logical_type(Some(LogicalType::String))
-            .with_repetition(Repetition::REQUIRED)
+            .with_repetition(Repetition::OPTIONAL)
             .build()
             .unwrap();
 
@@ -193,7 +345,7 @@ pub fn create_forecast_schema() -> Type {
     let wind_speed_unit_code =
         Type::primitive_type_builder("wind_speed_unit_code", PhysicalType::BYTE_ARRAY)
             .with_logical_type(Some(LogicalType::String))
-            .with_repetition(Repetition::REQUIRED)
+            .with_repetition(Repetition::OPTIONAL)
             .build()
             .unwrap();
 
@@ -205,7 +357,7 @@ pub fn create_forecast_schema() -> Type {
     let wind_direction_unit_code =
         Type::primitive_type_builder("wind_direction_unit_code", PhysicalType::BYTE_ARRAY)
             .with_logical_type(Some(LogicalType::String))
-            .with_repetition(Repetition::REQUIRED)
+            .with_repetition(Repetition::OPTIONAL)
             .build()
             .unwrap();
 
@@ -224,7 +376,7 @@ pub fn create_forecast_schema() -> Type {
     let relative_humidity_unit_code =
         Type::primitive_type_builder("relative_humidity_unit_code", PhysicalType::BYTE_ARRAY)
             .with_logical_type(Some(LogicalType::String))
-            .with_repetition(Repetition::REQUIRED)
+            .with_repetition(Repetition::OPTIONAL)
             .build()
             .unwrap();
 
@@ -237,7 +389,7 @@ pub fn create_forecast_schema() -> Type {
     let liquid_precipitation_unit_code =
         Type::primitive_type_builder("liquid_precipitation_unit_code", PhysicalType::BYTE_ARRAY)
             .with_logical_type(Some(LogicalType::String))
-            .with_repetition(Repetition::REQUIRED)
+            .with_repetition(Repetition::OPTIONAL)
             .build()
             .unwrap();
 
@@ -254,7 +406,7 @@ pub fn create_forecast_schema() -> Type {
         PhysicalType::BYTE_ARRAY,
     )
     .with_logical_type(Some(LogicalType::String))
-    .with_repetition(Repetition::REQUIRED)
+    .with_repetition(Repetition::OPTIONAL)
     .build()
     .unwrap();
 
@@ -327,7 +479,8 @@ impl TryFrom<Dwml> for HashMap<String, Vec<WeatherForecast>> {
         let generated_at = get_generated_at(&raw_data);
 
         raw_data.data.location.iter().for_each(|location| {
-            let weather_forecast = get_forecasts_ranges(location, generated_at);
+            let weather_forecast =
+                get_forecasts_ranges(location, generated_at, OffsetDateTime::now_utc());
             weather.insert(location.location_key.clone(), weather_forecast);
         });
         // Used to pull the data forward from last time we had a forecast for a value
@@ -588,25 +741,129 @@ fn get_interval(current_data: &WeatherForecast, time_ranges: &[TimeRange]) -> Op
     time_interval_index
 }
 
+/// How the forecast request for a station turned out, empty means NOAA answered without any data for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ForecastOutcome {
+    Ok,
+    Empty,
+    Failed,
+}
+
+impl Display for ForecastOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ForecastOutcome::Ok => write!(f, "ok"),
+            ForecastOutcome::Empty => write!(f, "empty"),
+            ForecastOutcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ForecastSummary {
+    pub ok: usize,
+    pub empty: usize,
+    pub failed: usize,
+    /// Outcome keyed by station_id
+    pub stations: HashMap<String, ForecastOutcome>,
+    /// Durations of the batched xml requests, forecasts from the api aren't fetched in chunks
+    pub fetch_timings: FetchTimings,
+}
+
+impl ForecastSummary {
+    pub fn add(&mut self, station_id: String, outcome: ForecastOutcome) {
+        if let Some(previous) = self.stations.insert(station_id, outcome) {
+            self.decrement(previous);
+        }
+        match outcome {
+            ForecastOutcome::Ok => self.ok += 1,
+            ForecastOutcome::Empty => self.empty += 1,
+            ForecastOutcome::Failed => self.failed += 1,
+        }
+    }
+
+    fn decrement(&mut self, outcome: ForecastOutcome) {
+        match outcome {
+            ForecastOutcome::Ok => self.ok -= 1,
+            ForecastOutcome::Empty => self.empty -= 1,
+            ForecastOutcome::Failed => self.failed -= 1,
+        }
+    }
+}
+
+impl Display for ForecastSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ok: {}, empty: {}, failed: {}",
+            self.ok, self.empty, self.failed
+        )
+    }
+}
+
+/// The result of a single batched forecast request, every request sends exactly one
+pub struct ForecastBatch {
+    pub outcomes: HashMap<String, ForecastOutcome>,
+    pub forecasts: HashMap<String, Vec<WeatherForecast>>,
+    /// How long the request took, retries included
+    pub fetch_duration: StdDuration,
+}
+
+impl ForecastBatch {
+    pub fn new(
+        city_weather: &CityWeather,
+        converted: Result<HashMap<String, Vec<WeatherForecast>>, Error>,
+    ) -> Self {
+        let forecasts = converted.as_ref().cloned().unwrap_or_default();
+        let outcomes = city_weather
+            .city_data
+            .keys()
+            .map(|station_id| {
+                let outcome = match &converted {
+                    Err(_) => ForecastOutcome::Failed,
+                    Ok(data) if data.get(station_id).is_some_and(|f| !f.is_empty()) => {
+                        ForecastOutcome::Ok
+                    }
+                    Ok(_) => ForecastOutcome::Empty,
+                };
+                (station_id.clone(), outcome)
+            })
+            .collect();
+        ForecastBatch {
+            outcomes,
+            forecasts,
+            fetch_duration: StdDuration::ZERO,
+        }
+    }
+
+    pub fn with_fetch_duration(mut self, fetch_duration: StdDuration) -> Self {
+        self.fetch_duration = fetch_duration;
+        self
+    }
+}
+
 pub struct ForecastRetry {
-    pub tx: mpsc::Sender<Result<HashMap<String, Vec<WeatherForecast>>, Error>>,
+    pub tx: mpsc::Sender<ForecastBatch>,
     pub max_retries: usize,
     pub fetcher: Arc<XmlFetcher>,
     pub logger: Logger,
+    pub products: Vec<ForecastProduct>,
 }
 
 impl ForecastRetry {
     pub fn new(
-        tx: mpsc::Sender<Result<HashMap<String, Vec<WeatherForecast>>, Error>>,
+        tx: mpsc::Sender<ForecastBatch>,
         max_retries: usize,
         fetcher: Arc<XmlFetcher>,
         logger: Logger,
+        products: Vec<ForecastProduct>,
     ) -> Self {
         ForecastRetry {
             tx,
             max_retries,
             fetcher,
             logger,
+            products,
         }
     }
 
@@ -616,50 +873,29 @@ impl ForecastRetry {
         city_weather: &CityWeather,
     ) -> Result<(), Error> {
         info!(self.logger, "url: {}", url);
-        loop {
+        let started = Instant::now();
+        let mut attempts = 0;
+        let converted = loop {
+            attempts += 1;
             match self.fetcher.fetch_xml(&url).await {
                 Ok(xml) => {
-                    let converted_xml: Dwml = match from_str(&xml) {
-                        Ok(xml) => xml,
-                        Err(err) => {
-                            error!(
-                                self.logger,
-                                "error converting xml: {} \n raw string: {}", err, xml
-                            );
-                            Dwml::default()
-                        }
+                    let converted =
+                        convert_forecast_xml(&self.logger, &xml, city_weather, &self.products);
+                    // An empty conversion of a non-empty response is how a NOAA format change usually shows up
+                    let failed = match &converted {
+                        Err(_) => true,
+                        Ok(forecasts) => forecasts.is_empty() && !xml.trim().is_empty(),
                     };
-                    if converted_xml == Dwml::default() {
-                        info!(
-                            self.logger,
-                            "no current forecast xml found, skipping converting"
-                        );
-                        if let Err(err) = self.tx.send(Ok(HashMap::new())).await {
-                            error!(self.logger, "Error sending result through channel: {}", err);
-                            return Ok(());
-                        }
-                        return Ok(());
-                    }
-                    let weather_with_stations = add_station_ids(city_weather, converted_xml);
-                    let current_forecast_data: HashMap<String, Vec<WeatherForecast>> =
-                        match weather_with_stations.try_into() {
-                            Ok(weather) => weather,
-                            Err(err) => {
-                                error!(self.logger, "error converting to Forecast: {}", err);
-
-                                HashMap::new()
-                            }
-                        };
-                    if current_forecast_data.is_empty() {
-                        info!(self.logger, "no current forecast data found");
-                        return Ok(());
-                    }
-                    // Send the result through the channel
-                    if let Err(err) = self.tx.send(Ok(current_forecast_data)).await {
-                        error!(self.logger, "Error sending result through channel: {}", err);
-                    }
-
-                    return Ok(());
+                    self.fetcher
+                        .record_payload(PayloadKind::Forecast, &xml, failed);
+                    break converted;
+                }
+                Err(err) if attempts >= self.max_retries => {
+                    break Err(anyhow!(
+                        "error fetching XML after {} tries: {}",
+                        attempts,
+                        err
+                    ))
                 }
                 Err(err) => {
                     // Log the error and retry after a delay
@@ -667,36 +903,256 @@ impl ForecastRetry {
                     sleep(StdDuration::from_secs(5)).await;
                 }
             }
+        };
+        let failed = converted
+            .as_ref()
+            .err()
+            .map(|err| anyhow!("error getting forecast: {}", err));
+        // Send the result through the channel
+        if let Err(err) = self
+            .tx
+            .send(
+                ForecastBatch::new(city_weather, converted).with_fetch_duration(started.elapsed()),
+            )
+            .await
+        {
+            error!(self.logger, "Error sending result through channel: {}", err);
         }
+        match failed {
+            Some(err) => Err(err),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Converts a raw forecast response, an empty map means NOAA had no current forecast for the stations
+pub fn convert_forecast_xml(
+    logger: &Logger,
+    xml: &str,
+    city_weather: &CityWeather,
+    products: &[ForecastProduct],
+) -> Result<HashMap<String, Vec<WeatherForecast>>, Error> {
+    if xml.trim().is_empty() {
+        info!(logger, "no current forecast xml found, skipping converting");
+        return Ok(HashMap::new());
+    }
+    let converted_xml: Dwml = from_str(xml).map_err(|err| {
+        error!(
+            logger,
+            "error converting xml: {} \n raw string: {}", err, xml
+        );
+        anyhow!("error converting xml: {}", err)
+    })?;
+    if converted_xml == Dwml::default() {
+        info!(logger, "no current forecast xml found, skipping converting");
+        return Ok(HashMap::new());
     }
+    let requested_xml = remove_unrequested_products(converted_xml, products);
+    let weather_with_stations = add_station_ids(city_weather, requested_xml);
+    let current_forecast_data: HashMap<String, Vec<WeatherForecast>> = weather_with_stations
+        .try_into()
+        .map_err(|err| anyhow!("error converting to Forecast: {}", err))?;
+    if current_forecast_data.is_empty() {
+        info!(logger, "no current forecast data found");
+    }
+    Ok(current_forecast_data)
 }
 
 pub struct ForecastService {
     pub fetcher: Arc<XmlFetcher>,
     pub logger: Logger,
+    pub products: Vec<ForecastProduct>,
+    pub source: ForecastSource,
+    pub precipitation_decimals: Option<u32>,
+    pub sanity_bounds: SanityBounds,
+    /// Cancelling aborts the requests still in flight, the run returns the forecasts fetched so far
+    pub cancel: CancellationToken,
 }
 
 impl ForecastService {
-    pub fn new(logger: Logger, fetcher: Arc<XmlFetcher>) -> Self {
-        ForecastService { logger, fetcher }
+    pub fn new(
+        logger: Logger,
+        fetcher: Arc<XmlFetcher>,
+        products: Vec<ForecastProduct>,
+        source: ForecastSource,
+    ) -> Self {
+        ForecastService {
+            logger,
+            fetcher,
+            products,
+            source,
+            precipitation_decimals: None,
+            sanity_bounds: SanityBounds::default(),
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    pub fn with_precipitation_decimals(mut self, precipitation_decimals: Option<u32>) -> Self {
+        self.precipitation_decimals = precipitation_decimals;
+        self
+    }
+
+    pub fn with_sanity_bounds(mut self, sanity_bounds: SanityBounds) -> Self {
+        self.sanity_bounds = sanity_bounds;
+        self
+    }
+
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = cancel;
+        self
+    }
+
+    pub async fn get_forecasts(
+        &self,
+        city_weather: &CityWeather,
+    ) -> Result<(Vec<Forecast>, ForecastSummary), Error> {
+        let mut summary = ForecastSummary::default();
+        let mut forecast_data = match self.source {
+            ForecastSource::Api => self.get_api_forecasts(city_weather).await,
+            ForecastSource::Xml => HashMap::new(),
+        };
+        for station_id in forecast_data.keys() {
+            summary.add(station_id.clone(), ForecastOutcome::Ok);
+        }
+
+        let missing_stations = CityWeather {
+            city_data: city_weather
+                .city_data
+                .iter()
+                .filter(|(station_id, _)| !forecast_data.contains_key(*station_id))
+                .map(|(station_id, station)| (station_id.clone(), station.clone()))
+                .collect(),
+        };
+        if !missing_stations.city_data.is_empty() && !self.cancel.is_cancelled() {
+            if self.source == ForecastSource::Api {
+                warn!(
+                    self.logger,
+                    "falling back to xml forecasts for {} stations",
+                    missing_stations.city_data.len()
+                );
+            }
+            let (xml_forecast_data, outcomes, fetch_timings) =
+                self.get_xml_forecasts(&missing_stations).await;
+            forecast_data.extend(xml_forecast_data);
+            summary.fetch_timings = fetch_timings;
+            for (station_id, outcome) in outcomes {
+                summary.add(station_id, outcome);
+            }
+        }
+        info!(self.logger, "forecast station outcomes: {}", summary);
+
+        let mut forecasts = vec![];
+        for all_forecasts in forecast_data.values() {
+            for weather_forecats in all_forecasts {
+                let current = weather_forecats.clone();
+                debug!(
+                    self.logger.clone(),
+                    "current weather forecast: {:?}", current
+                );
+                let mut forecast =
+                    Forecast::try_from_weather_forecast(current, self.precipitation_decimals)?;
+                self.sanity_bounds
+                    .apply_to_forecast(&self.logger, &mut forecast);
+                debug!(
+                    self.logger.clone(),
+                    "parquet format forecast: {:?}", forecast
+                );
+                let city = city_weather.city_data.get(&forecast.station_id).unwrap();
+                forecast.station_name = city.station_name.clone();
+                forecasts.push(forecast)
+            }
+        }
+
+        Ok((forecasts, summary))
+    }
+
+    // Stations that fail here are left out of the result so the caller can fall back to xml for them
+    async fn get_api_forecasts(
+        &self,
+        city_weather: &CityWeather,
+    ) -> HashMap<String, Vec<WeatherForecast>> {
+        let mut forecast_data = HashMap::new();
+        for (station_id, weather_station) in &city_weather.city_data {
+            let api_forecast = tokio::select! {
+                api_forecast = self.get_api_forecast(weather_station) => api_forecast,
+                _ = self.cancel.cancelled() => {
+                    warn!(self.logger, "forecast run cancelled, skipping the remaining api forecasts");
+                    break;
+                }
+            };
+            match api_forecast {
+                Ok(weather_forecasts) => {
+                    forecast_data.insert(station_id.clone(), weather_forecasts);
+                }
+                Err(err) => error!(
+                    self.logger,
+                    "error getting api forecast for {}: {}", station_id, err
+                ),
+            }
+        }
+        info!(
+            self.logger,
+            "found api forecasts for {} of {} stations",
+            forecast_data.len(),
+            city_weather.city_data.len()
+        );
+        forecast_data
+    }
+
+    async fn get_api_forecast(
+        &self,
+        weather_station: &WeatherStation,
+    ) -> Result<Vec<WeatherForecast>, Error> {
+        let url = get_api_point_url(&self.fetcher.endpoints().weather_api, weather_station)?;
+        let raw_point = self.fetcher.fetch_json(&url).await?;
+        let point = parse_api_point(&raw_point);
+        self.fetcher
+            .record_payload(PayloadKind::ApiForecast, &raw_point, point.is_err());
+        let raw_gridpoint = self
+            .fetcher
+            .fetch_json(&point?.properties.forecast_grid_data)
+            .await?;
+        let gridpoint = parse_api_gridpoint(&raw_gridpoint);
+        self.fetcher
+            .record_payload(PayloadKind::ApiForecast, &raw_gridpoint, gridpoint.is_err());
+        let gridpoint = gridpoint?;
+        api_gridpoint_to_forecasts(
+            weather_station,
+            gridpoint,
+            &self.products,
+            OffsetDateTime::now_utc(),
+        )
     }
-    pub async fn get_forecasts(&self, city_weather: &CityWeather) -> Result<Vec<Forecast>, Error> {
+
+    async fn get_xml_forecasts(
+        &self,
+        city_weather: &CityWeather,
+    ) -> (
+        HashMap<String, Vec<WeatherForecast>>,
+        HashMap<String, ForecastOutcome>,
+        FetchTimings,
+    ) {
         let split_maps = split_cityweather(city_weather.clone(), 50);
         let total_requests = split_maps.len();
-        let (tx, mut rx) =
-            mpsc::channel::<Result<HashMap<String, Vec<WeatherForecast>>, Error>>(total_requests);
+        if total_requests == 0 {
+            return (HashMap::new(), HashMap::new(), FetchTimings::default());
+        }
+        let (tx, mut rx) = mpsc::channel::<ForecastBatch>(total_requests);
 
         let max_retries = 3;
-        let request_counter = Arc::new(AtomicUsize::new(total_requests));
         let mut set = JoinSet::new();
         for city_weather in split_maps {
-            let url = get_url(&city_weather);
-            let counter_clone = Arc::clone(&request_counter);
+            let url = get_url(
+                &self.fetcher.endpoints().ndfd,
+                &city_weather,
+                &self.products,
+            );
             let forecast_retry = ForecastRetry::new(
                 tx.clone(),
                 max_retries,
                 self.fetcher.clone(),
                 self.logger.clone(),
+                self.products.clone(),
             );
             let logger_cpy = self.logger.clone();
 
@@ -707,11 +1163,9 @@ impl ForecastService {
                 {
                     Ok(_) => {
                         info!(&logger_cpy, "completed getting forecast data for: {}", url);
-                        counter_clone.fetch_sub(1, Ordering::Relaxed);
                     }
                     Err(_) => {
                         error!(&logger_cpy, "error getting forecast data for: {}", url);
-                        counter_clone.fetch_sub(1, Ordering::Relaxed);
                     }
                 }
             });
@@ -719,26 +1173,29 @@ impl ForecastService {
 
         let forecast_data = Arc::new(Mutex::new(HashMap::new()));
         let forecast_data_clone = Arc::clone(&forecast_data);
+        let outcomes = Arc::new(Mutex::new(HashMap::new()));
+        let outcomes_clone = Arc::clone(&outcomes);
+        let fetch_timings = Arc::new(Mutex::new(FetchTimings::default()));
+        let fetch_timings_clone = Arc::clone(&fetch_timings);
         let logger_clone = self.logger.clone();
         set.spawn(async move {
-            while let Some(result) = rx.recv().await {
-                match result {
-                    Ok(data) => {
-                        info!(
-                            &logger_clone,
-                            "found more forecast data for: {:?}",
-                            data.keys()
-                        );
-                        let mut forecast_data = forecast_data_clone.lock().await;
-                        //using station_id as the key
-                        forecast_data.extend(data);
-                    }
-                    Err(err) => {
-                        error!(&logger_clone, "Error fetching forecast data: {}", err);
-                    }
-                }
+            // Each request sends exactly one batch, even when it failed or was empty
+            let mut batches_left = total_requests;
+            while let Some(batch) = rx.recv().await {
+                batches_left -= 1;
+                info!(
+                    &logger_clone,
+                    "found more forecast data for: {:?}",
+                    batch.forecasts.keys()
+                );
+                //using station_id as the key
+                forecast_data_clone.lock().await.extend(batch.forecasts);
+                outcomes_clone.lock().await.extend(batch.outcomes);
+                fetch_timings_clone
+                    .lock()
+                    .await
+                    .record(batch.fetch_duration);
 
-                let batches_left = request_counter.load(Ordering::Relaxed);
                 if batches_left > 0 {
                     let progress = ((total_requests as f64 - batches_left as f64)
                         / total_requests as f64)
@@ -758,42 +1215,43 @@ impl ForecastService {
             }
         });
 
-        while let Some(inner_res) = set.join_next().await {
-            match inner_res {
-                Ok(_) => info!(self.logger, "task finished"),
-                Err(e) => error!(self.logger, "error with task: {}", e),
-            }
+        let cancelled = tokio::select! {
+            _ = async {
+                while let Some(inner_res) = set.join_next().await {
+                    match inner_res {
+                        Ok(_) => info!(self.logger, "task finished"),
+                        Err(e) => error!(self.logger, "error with task: {}", e),
+                    }
+                }
+            } => false,
+            _ = self.cancel.cancelled() => true,
+        };
+        if cancelled {
+            warn!(
+                self.logger,
+                "forecast run cancelled, aborting {} requests still in flight",
+                set.len()
+            );
+            set.abort_all();
+            while set.join_next().await.is_some() {}
         }
 
         info!(self.logger, "done waiting for data, continuing");
-        let mut forecasts = vec![];
-        for all_forecasts in forecast_data.lock().await.values() {
-            for weather_forecats in all_forecasts {
-                let current = weather_forecats.clone();
-                debug!(
-                    self.logger.clone(),
-                    "current weather forecast: {:?}", current
-                );
-                let mut forecast: Forecast = current.try_into()?;
-                debug!(
-                    self.logger.clone(),
-                    "parquet format forecast: {:?}", forecast
-                );
-                let city = city_weather.city_data.get(&forecast.station_id).unwrap();
-                forecast.station_name = city.station_name.clone();
-                forecasts.push(forecast)
-            }
-        }
-
-        Ok(forecasts)
+        let forecast_data = forecast_data.lock().await.clone();
+        let outcomes = outcomes.lock().await.clone();
+        let fetch_timings = fetch_timings.lock().await.clone();
+        (forecast_data, outcomes, fetch_timings)
     }
 }
 
-fn get_forecasts_ranges(location: &Location, generated_at: OffsetDateTime) -> Vec<WeatherForecast> {
-    let now = OffsetDateTime::now_utc();
-    let one_week_from_now = now + Duration::weeks(1);
+pub(crate) fn get_forecasts_ranges(
+    location: &Location,
+    generated_at: OffsetDateTime,
+    start: OffsetDateTime,
+) -> Vec<WeatherForecast> {
+    let one_week_from_now = start + Duration::weeks(1);
 
-    let mut current_time = now;
+    let mut current_time = start;
     let mut forecasts = vec![];
     while current_time <= one_week_from_now {
         let weather_forecast = WeatherForecast {
@@ -854,6 +1312,28 @@ fn add_station_ids(city_weather: &CityWeather, mut converted_xml: Dwml) -> Dwml
     converted_xml
 }
 
+// NOAA may still send readings that were not asked for, drop them so they are never parsed
+fn remove_unrequested_products(mut converted_xml: Dwml, products: &[ForecastProduct]) -> Dwml {
+    let is_requested =
+        |reading: &DataReading| products.iter().any(|product| product.is_reading(reading));
+    for parameter in converted_xml.data.parameters.iter_mut() {
+        if let Some(temperature) = parameter.temperature.as_mut() {
+            temperature.retain(is_requested);
+        }
+        if let Some(humidity) = parameter.humidity.as_mut() {
+            humidity.retain(is_requested);
+        }
+        parameter.precipitation = parameter.precipitation.take().filter(is_requested);
+        parameter.wind_speed = parameter.wind_speed.take().filter(is_requested);
+        parameter.wind_direction = parameter.wind_direction.take().filter(is_requested);
+        parameter.probability_of_precipitation = parameter
+            .probability_of_precipitation
+            .take()
+            .filter(is_requested);
+    }
+    converted_xml
+}
+
 // forecast xml files always provide these to 2 decimal places, make sure to match on that percision
 fn compare_coordinates(weather_station: &WeatherStation, latitude: &str, longitude: &str) -> bool {
     let station_lat = weather_station.get_latitude();
@@ -862,7 +1342,7 @@ fn compare_coordinates(weather_station: &WeatherStation, latitude: &str, longitu
     station_lat == latitude && station_long == longitude
 }
 
-fn get_url(city_weather: &CityWeather) -> String {
+fn get_url(ndfd_url: &str, city_weather: &CityWeather, products: &[ForecastProduct]) -> String {
     // Get the current time
     let mut current_time = OffsetDateTime::now_utc();
 
@@ -897,5 +1377,302 @@ fn get_url(city_weather: &CityWeather) -> String {
     let one_week_from_now = current_time.add(one_week_duration);
 
     let one_week = one_week_from_now.format(&format_description).unwrap();
-    format!("https://graphical.weather.gov/xml/sample_products/browser_interface/ndfdXMLclient.php?listLatLon={}&product=time-series&begin={}&end={}&Unit=e&maxt=maxt&mint=mint&wspd=wspd&wdir=wdir&pop12=pop12&qpf=qpf&maxrh=maxrh&minrh=minrh", city_weather.get_coordinates_url(),now,one_week)
+    let product_params = products
+        .iter()
+        .map(|product| format!("&{}={}", product.param(), product.param()))
+        .collect::<String>();
+    format!("{}/xml/sample_products/browser_interface/ndfdXMLclient.php?listLatLon={}&product=time-series&begin={}&end={}&Unit=e{}", ndfd_url, city_weather.get_coordinates_url(),now,one_week,product_params)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        convert_forecast_xml, get_url, round_to_decimals, Forecast, ForecastBatch, ForecastOutcome,
+        ForecastProduct, ForecastService, ForecastSummary, WeatherForecast,
+    };
+    use crate::{
+        test_server::{spawn_server, MockServer, Response},
+        CircuitBreaker, CityWeather, ForecastSource, NoaaEndpoints, RateLimiter, Units,
+        WeatherStation, XmlFetcher, DEFAULT_NDFD_URL,
+    };
+    use slog::{o, Discard, Logger};
+    use std::{collections::HashMap, future::pending, sync::Arc, time::Duration};
+    use time::OffsetDateTime;
+    use tokio::{
+        sync::{
+            mpsc::{unbounded_channel, UnboundedReceiver},
+            Mutex,
+        },
+        time::timeout,
+    };
+    use tokio_util::sync::CancellationToken;
+
+    fn single_station() -> CityWeather {
+        CityWeather {
+            city_data: HashMap::from([(
+                String::from("KLWV"),
+                WeatherStation {
+                    station_id: String::from("KLWV"),
+                    station_name: String::from("Lawrenceville"),
+                    latitude: String::from("38.76"),
+                    longitude: String::from("-87.61"),
+                },
+            )]),
+        }
+    }
+
+    #[test]
+    fn empty_forecast_xml_is_counted_as_empty() {
+        let logger = Logger::root(Discard, o!());
+        let city_weather = single_station();
+        let converted = convert_forecast_xml(&logger, "", &city_weather, &ForecastProduct::all());
+        let batch = ForecastBatch::new(&city_weather, converted);
+
+        assert!(batch.forecasts.is_empty());
+        assert_eq!(batch.outcomes.get("KLWV"), Some(&ForecastOutcome::Empty));
+
+        let mut summary = ForecastSummary::default();
+        for (station_id, outcome) in batch.outcomes {
+            summary.add(station_id, outcome);
+        }
+        assert_eq!(summary.ok, 0);
+        assert_eq!(summary.empty, 1);
+        assert_eq!(summary.failed, 0);
+    }
+
+    #[test]
+    fn unparsable_forecast_xml_is_counted_as_failed() {
+        let logger = Logger::root(Discard, o!());
+        let city_weather = single_station();
+        let converted =
+            convert_forecast_xml(&logger, "<dwml>", &city_weather, &ForecastProduct::all());
+        let batch = ForecastBatch::new(&city_weather, converted);
+
+        assert_eq!(batch.outcomes.get("KLWV"), Some(&ForecastOutcome::Failed));
+    }
+
+    #[test]
+    fn summary_keeps_latest_station_outcome() {
+        let mut summary = ForecastSummary::default();
+        summary.add(String::from("KLWV"), ForecastOutcome::Failed);
+        summary.add(String::from("KLWV"), ForecastOutcome::Ok);
+
+        assert_eq!(summary.ok, 1);
+        assert_eq!(summary.failed, 0);
+        assert_eq!(summary.stations.get("KLWV"), Some(&ForecastOutcome::Ok));
+    }
+
+    fn weather_forecast_with_precipitation(
+        liquid_precipitation_amt: Option<f64>,
+    ) -> WeatherForecast {
+        let now = OffsetDateTime::now_utc();
+        WeatherForecast {
+            station_id: String::from("KLWV"),
+            station_name: String::from(""),
+            latitude: String::from("38.76"),
+            longitude: String::from("-87.61"),
+            generated_at: now,
+            begin_time: now,
+            end_time: now,
+            max_temp: None,
+            min_temp: None,
+            temperature_unit_code: Units::Fahrenheit.to_string(),
+            wind_speed: None,
+            wind_speed_unit_code: Units::Knots.to_string(),
+            wind_direction: None,
+            wind_direction_unit_code: Units::DegreesTrue.to_string(),
+            relative_humidity_max: None,
+            relative_humidity_min: None,
+            relative_humidity_unit_code: Units::Percent.to_string(),
+            liquid_precipitation_amt,
+            liquid_precipitation_unit_code: Units::Inches.to_string(),
+            twelve_hour_probability_of_precipitation: None,
+            twelve_hour_probability_of_precipitation_unit_code: Units::Percent.to_string(),
+        }
+    }
+
+    #[test]
+    fn rounds_precipitation_to_configured_decimals() {
+        let rounded = |amt: Option<f64>, decimals: Option<u32>| {
+            Forecast::try_from_weather_forecast(weather_forecast_with_precipitation(amt), decimals)
+                .unwrap()
+                .liquid_precipitation_amt
+        };
+
+        assert_eq!(rounded(Some(0.126), Some(2)), Some(0.13));
+        assert_eq!(rounded(Some(0.124), Some(2)), Some(0.12));
+        assert_eq!(rounded(Some(1.05), Some(0)), Some(1.0));
+        assert_eq!(rounded(Some(0.3333), Some(3)), Some(0.333));
+        assert_eq!(rounded(None, Some(2)), None);
+        // without a precision the raw amount is written
+        assert_eq!(rounded(Some(0.126), None), Some(0.126));
+        assert_eq!(round_to_decimals(0.123456789, 20), 0.123456789);
+    }
+
+    #[test]
+    fn absent_values_have_null_unit_codes() {
+        let now = OffsetDateTime::now_utc();
+        let weather_forecast = WeatherForecast {
+            station_id: String::from("KLWV"),
+            station_name: String::from(""),
+            latitude: String::from("38.76"),
+            longitude: String::from("-87.61"),
+            generated_at: now,
+            begin_time: now,
+            end_time: now,
+            max_temp: Some(88),
+            min_temp: None,
+            temperature_unit_code: Units::Fahrenheit.to_string(),
+            wind_speed: None,
+            wind_speed_unit_code: Units::Knots.to_string(),
+            wind_direction: None,
+            wind_direction_unit_code: Units::DegreesTrue.to_string(),
+            relative_humidity_max: None,
+            relative_humidity_min: None,
+            relative_humidity_unit_code: Units::Percent.to_string(),
+            liquid_precipitation_amt: Some(0.1),
+            liquid_precipitation_unit_code: String::from(""),
+            twelve_hour_probability_of_precipitation: None,
+            twelve_hour_probability_of_precipitation_unit_code: Units::Percent.to_string(),
+        };
+
+        let forecast: Forecast = weather_forecast.try_into().unwrap();
+
+        assert_eq!(
+            forecast.temperature_unit_code,
+            Some(Units::Fahrenheit.to_string())
+        );
+        assert_eq!(forecast.wind_speed_unit_code, None);
+        assert_eq!(forecast.wind_direction_unit_code, None);
+        assert_eq!(forecast.relative_humidity_unit_code, None);
+        assert_eq!(forecast.liquid_precipitation_unit_code, None);
+        assert_eq!(
+            forecast.twelve_hour_probability_of_precipitation_unit_code,
+            None
+        );
+    }
+
+    #[test]
+    fn url_only_contains_requested_products() {
+        let mut city_data = HashMap::new();
+        city_data.insert(
+            String::from("KLWV"),
+            WeatherStation {
+                station_id: String::from("KLWV"),
+                station_name: String::from("Lawrenceville"),
+                latitude: String::from("38.76"),
+                longitude: String::from("-87.61"),
+            },
+        );
+        let city_weather = CityWeather { city_data };
+        let products = vec![ForecastProduct::MaxTemp, ForecastProduct::WindSpeed];
+
+        let url = get_url(DEFAULT_NDFD_URL, &city_weather, &products);
+        let (_, query) = url.split_once('?').unwrap();
+        let requested_params: Vec<&str> = query
+            .split('&')
+            .filter_map(|param| param.split_once('='))
+            .map(|(key, _)| key)
+            .filter(|key| {
+                ForecastProduct::all()
+                    .iter()
+                    .any(|product| product.param() == *key)
+            })
+            .collect();
+
+        assert_eq!(requested_params, vec!["maxt", "wspd"]);
+    }
+
+    #[test]
+    fn url_uses_configured_ndfd_mirror() {
+        let url = get_url(
+            "http://localhost:8080",
+            &single_station(),
+            &[ForecastProduct::MaxTemp],
+        );
+        assert!(url.starts_with(
+            "http://localhost:8080/xml/sample_products/browser_interface/ndfdXMLclient.php?"
+        ));
+    }
+
+    /// Answers the first forecast request with an empty forecast, every request after it hangs. The index of
+    /// each request is sent on the returned channel once the server has read it
+    async fn spawn_stalling_ndfd() -> (NoaaEndpoints, MockServer, UnboundedReceiver<usize>) {
+        let (requested, requests) = unbounded_channel();
+        let ndfd = spawn_server(move |request, _| {
+            let requested = requested.clone();
+            async move {
+                let _ = requested.send(request);
+                if request > 0 {
+                    pending::<()>().await;
+                }
+                Response::ok("")
+            }
+        })
+        .await;
+        let endpoints = NoaaEndpoints {
+            ndfd: ndfd.url(),
+            ..Default::default()
+        };
+        (endpoints, ndfd, requests)
+    }
+
+    #[tokio::test]
+    async fn cancelling_mid_run_returns_the_batches_fetched_so_far() {
+        let (endpoints, ndfd, mut requests) = spawn_stalling_ndfd().await;
+        let logger = Logger::root(Discard, o!());
+        let fetcher = Arc::new(
+            XmlFetcher::new(
+                logger.clone(),
+                String::from("noaa-data-pipeline/test"),
+                Arc::new(Mutex::new(RateLimiter::new(10, 10.0))),
+                Arc::new(CircuitBreaker::new(
+                    logger.clone(),
+                    5,
+                    Duration::from_secs(300),
+                )),
+            )
+            .with_endpoints(endpoints),
+        );
+        let cancel = CancellationToken::new();
+        let service =
+            ForecastService::new(logger, fetcher, ForecastProduct::all(), ForecastSource::Xml)
+                .with_cancellation(cancel.clone());
+        // Forecasts are requested 50 stations at a time, so this is 3 requests
+        let city_weather = CityWeather {
+            city_data: (0..120)
+                .map(|index| {
+                    let station_id = format!("K{:03}", index);
+                    let station = WeatherStation {
+                        station_id: station_id.clone(),
+                        station_name: station_id.clone(),
+                        latitude: format!("{:.2}", 30.0 + index as f64 / 10.0),
+                        longitude: String::from("-87.61"),
+                    };
+                    (station_id, station)
+                })
+                .collect(),
+        };
+
+        let (forecasts, summary) = timeout(Duration::from_secs(5), async {
+            // Cancelled once every batch has been requested, while the last two are still waiting on ndfd
+            tokio::join!(service.get_forecasts(&city_weather), async {
+                for _ in 0..3 {
+                    requests.recv().await;
+                }
+                cancel.cancel();
+            })
+            .0
+        })
+        .await
+        .expect("cancelled run returns promptly")
+        .unwrap();
+
+        assert_eq!(ndfd.requests(), 3);
+        assert!(forecasts.is_empty());
+        // Only the request that was answered has outcomes, the aborted ones are left out
+        assert!(summary.empty > 0 && summary.empty < 120);
+        assert_eq!(summary.stations.len(), summary.empty);
+        assert_eq!(summary.failed, 0);
+    }
 }
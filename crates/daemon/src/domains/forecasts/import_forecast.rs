@@ -0,0 +1,177 @@
+use crate::{save_forecasts, Forecast, ParquetCompression, Units, WeatherForecast};
+use anyhow::{anyhow, Error};
+use std::fs;
+
+/// Converts a JSON array of forecasts from a non NOAA source into the same shape the XML path produces
+pub fn import_forecasts_json(raw_json: &str) -> Result<Vec<Forecast>, Error> {
+    let weather_forecasts: Vec<WeatherForecast> = serde_json::from_str(raw_json)
+        .map_err(|e| anyhow!("error deserializing forecast json: {}", e))?;
+
+    let mut forecasts = vec![];
+    for (index, weather_forecast) in weather_forecasts.into_iter().enumerate() {
+        validate_forecast(&weather_forecast)
+            .map_err(|e| anyhow!("invalid forecast at index {}: {}", index, e))?;
+        let weather_forecast = to_fahrenheit(weather_forecast);
+        let station_name = weather_forecast.station_name.clone();
+        let mut forecast: Forecast = weather_forecast
+            .try_into()
+            .map_err(|e| anyhow!("invalid forecast at index {}: {}", index, e))?;
+        forecast.station_name = station_name;
+        forecasts.push(forecast);
+    }
+    Ok(forecasts)
+}
+
+/// Reads the JSON forecasts at `file_path` and writes them to the standard forecast parquet file
+pub fn import_forecast_file(
+    file_path: &str,
+    root_path: &str,
+    file_name: String,
+    compression: ParquetCompression,
+) -> Result<String, Error> {
+    let raw_json = fs::read_to_string(file_path)
+        .map_err(|e| anyhow!("error reading forecast file {}: {}", file_path, e))?;
+    let forecasts = import_forecasts_json(&raw_json)?;
+    if forecasts.is_empty() {
+        return Err(anyhow!("no forecasts found in {}", file_path));
+    }
+    save_forecasts(forecasts, root_path, file_name, compression)
+}
+
+fn validate_forecast(forecast: &WeatherForecast) -> Result<(), Error> {
+    if forecast.station_id.trim().is_empty() {
+        return Err(anyhow!("station_id is required"));
+    }
+    if forecast.begin_time >= forecast.end_time {
+        return Err(anyhow!("begin_time needs to be before end_time"));
+    }
+    validate_unit(
+        "temperature_unit_code",
+        &forecast.temperature_unit_code,
+        &[Units::Fahrenheit, Units::Celcius],
+    )?;
+    validate_unit(
+        "wind_speed_unit_code",
+        &forecast.wind_speed_unit_code,
+        &[Units::Knots],
+    )?;
+    validate_unit(
+        "wind_direction_unit_code",
+        &forecast.wind_direction_unit_code,
+        &[Units::DegreesTrue],
+    )?;
+    validate_unit(
+        "relative_humidity_unit_code",
+        &forecast.relative_humidity_unit_code,
+        &[Units::Percent],
+    )?;
+    validate_unit(
+        "liquid_precipitation_unit_code",
+        &forecast.liquid_precipitation_unit_code,
+        &[Units::Inches],
+    )?;
+    validate_unit(
+        "twelve_hour_probability_of_precipitation_unit_code",
+        &forecast.twelve_hour_probability_of_precipitation_unit_code,
+        &[Units::Percent],
+    )?;
+    Ok(())
+}
+
+/// NOAA forecasts are in fahrenheit and the oracle scores them that way, celcius temperatures are converted
+fn to_fahrenheit(mut forecast: WeatherForecast) -> WeatherForecast {
+    if forecast.temperature_unit_code != Units::Celcius.to_string() {
+        return forecast;
+    }
+    let convert = |celcius: i64| (celcius as f64 * 9.0 / 5.0 + 32.0).round() as i64;
+    forecast.max_temp = forecast.max_temp.map(convert);
+    forecast.min_temp = forecast.min_temp.map(convert);
+    forecast.temperature_unit_code = Units::Fahrenheit.to_string();
+    forecast
+}
+
+// Unit codes need to match what the XML path writes so the oracle can read both sources the same way
+fn validate_unit(field: &str, unit_code: &str, allowed: &[Units]) -> Result<(), Error> {
+    if allowed.iter().any(|unit| unit.to_string() == unit_code) {
+        return Ok(());
+    }
+    let allowed_units = allowed
+        .iter()
+        .map(|unit| unit.to_string())
+        .collect::<Vec<String>>()
+        .join(", ");
+    Err(anyhow!(
+        "{} has unsupported unit {}, expected one of: {}",
+        field,
+        unit_code,
+        allowed_units
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{import_forecast_file, import_forecasts_json};
+    use crate::ParquetCompression;
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::RowAccessor,
+    };
+    use std::fs::{self, File};
+
+    const FIXTURE_PATH: &str =
+        concat!(env!("CARGO_MANIFEST_DIR"), "/tests/fixtures/forecasts.json");
+
+    #[test]
+    fn can_import_forecast_json_to_parquet() {
+        let root_path =
+            std::env::temp_dir().join(format!("forecast_import_{}", std::process::id()));
+        fs::create_dir_all(&root_path).unwrap();
+
+        let parquet_path = import_forecast_file(
+            FIXTURE_PATH,
+            root_path.to_str().unwrap(),
+            String::from("forecasts_import"),
+            ParquetCompression::None,
+        )
+        .unwrap();
+
+        let reader = SerializedFileReader::new(File::open(&parquet_path).unwrap()).unwrap();
+        assert_eq!(reader.metadata().file_metadata().num_rows(), 2);
+
+        let rows: Vec<_> = reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect();
+        assert_eq!(rows[0].get_string(0).unwrap(), "KLWV");
+        assert_eq!(
+            rows[0].get_string(1).unwrap(),
+            "Lawrenceville-Vincennes International Airport"
+        );
+        assert_eq!(rows[0].get_long(7).unwrap(), 88);
+        assert_eq!(rows[0].get_string(9).unwrap(), "fahrenheit");
+        assert_eq!(rows[1].get_string(0).unwrap(), "KLBB");
+        // 31 and 22 celcius
+        assert_eq!(rows[1].get_long(7).unwrap(), 88);
+        assert_eq!(rows[1].get_long(8).unwrap(), 72);
+        assert_eq!(rows[1].get_string(9).unwrap(), "fahrenheit");
+
+        fs::remove_dir_all(&root_path).unwrap();
+    }
+
+    #[test]
+    fn rejects_unsupported_units() {
+        let raw_json =
+            fs::read_to_string(FIXTURE_PATH)
+                .unwrap()
+                .replacen("\"knots\"", "\"mph\"", 1);
+        let err = import_forecasts_json(&raw_json).unwrap_err();
+        assert!(err.to_string().contains("wind_speed_unit_code"));
+    }
+
+    #[test]
+    fn rejects_missing_required_fields() {
+        let raw_json = r#"[{"station_id": "KLWV", "latitude": "38.76", "longitude": "-87.61"}]"#;
+        assert!(import_forecasts_json(raw_json).is_err());
+    }
+}
@@ -1,5 +1,9 @@
+pub mod api_forecast;
 pub mod download_forecast;
+pub mod import_forecast;
 pub mod xml_forecast;
 
+pub use api_forecast::*;
 pub use download_forecast::*;
+pub use import_forecast::*;
 pub use xml_forecast::*;
@@ -4,11 +4,21 @@ use parquet::{
     schema::types::Type,
 };
 use parquet_derive::ParquetRecordWriter;
-use slog::Logger;
-use std::sync::Arc;
+use slog::{error, info, warn, Logger};
+use std::{
+    collections::{HashMap, HashSet},
+    fmt::{self, Display},
+    fs,
+    sync::Arc,
+    time::Instant,
+};
 use time::{format_description::well_known::Rfc3339, macros::format_description, OffsetDateTime};
+use tokio::{sync::Semaphore, task::JoinSet};
 
-use crate::{CityWeather, Metar, ObservationData, Units, XmlFetcher};
+use crate::{
+    split_cityweather, CityWeather, FetchTimings, Metar, ObservationData, PayloadKind,
+    SanityBounds, Units, WeatherStation, XmlFetcher,
+};
 
 #[derive(Clone)]
 pub struct CurrentWeather {
@@ -101,7 +111,8 @@ impl TryFrom<CurrentWeather> for Observation {
         let rfc_3339_time_description =
             format_description!("[year]-[month]-[day]T[hour]:[minute]:[second]Z");
         let parquet = Observation {
-            station_id: val.station_id,
+            // The oracle filters the parquet files on upper case station ids as stored
+            station_id: val.station_id.to_ascii_uppercase(),
             station_name: String::from(""),
             latitude: val.latitude,
             longitude: val.longitude,
@@ -221,25 +232,281 @@ pub fn create_observation_schema() -> Type {
     schema
 }
 
+/// Reads a toml file of `forecast_station_id = "observation_station_id"` pairs, used for forecast
+/// points that don't have a co-located observation station
+pub fn load_observation_stations(file_path: &str) -> Result<HashMap<String, String>, Error> {
+    load_station_toml(file_path, "observation stations")
+}
+
+/// Names by station id for stations the resolved station list has no name for, ids are upper cased
+pub fn load_station_names(file_path: &str) -> Result<HashMap<String, String>, Error> {
+    Ok(load_station_toml(file_path, "station names")?
+        .into_iter()
+        .map(|(station_id, station_name)| (station_id.to_ascii_uppercase(), station_name))
+        .collect())
+}
+
+fn load_station_toml(file_path: &str, kind: &str) -> Result<HashMap<String, String>, Error> {
+    let raw_toml = fs::read_to_string(file_path)
+        .map_err(|e| anyhow!("error reading {} file {}: {}", kind, file_path, e))?;
+    toml::from_str(&raw_toml)
+        .map_err(|e| anyhow!("error parsing {} file {}: {}", kind, file_path, e))
+}
+
+/// Stations per metar request when observations are requested in batches
+pub const OBSERVATION_BATCH_SIZE: usize = 50;
+
+/// How the observation request for a station turned out, empty means NOAA had no usable reading for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ObservationOutcome {
+    Ok,
+    Empty,
+    Failed,
+}
+
+impl Display for ObservationOutcome {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ObservationOutcome::Ok => write!(f, "ok"),
+            ObservationOutcome::Empty => write!(f, "empty"),
+            ObservationOutcome::Failed => write!(f, "failed"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ObservationSummary {
+    pub ok: usize,
+    pub empty: usize,
+    pub failed: usize,
+    /// Outcome keyed by station_id
+    pub stations: HashMap<String, ObservationOutcome>,
+    pub fetch_timings: FetchTimings,
+}
+
+impl ObservationSummary {
+    pub fn add(&mut self, station_id: String, outcome: ObservationOutcome) {
+        if let Some(previous) = self.stations.insert(station_id, outcome) {
+            self.decrement(previous);
+        }
+        match outcome {
+            ObservationOutcome::Ok => self.ok += 1,
+            ObservationOutcome::Empty => self.empty += 1,
+            ObservationOutcome::Failed => self.failed += 1,
+        }
+    }
+
+    fn decrement(&mut self, outcome: ObservationOutcome) {
+        match outcome {
+            ObservationOutcome::Ok => self.ok -= 1,
+            ObservationOutcome::Empty => self.empty -= 1,
+            ObservationOutcome::Failed => self.failed -= 1,
+        }
+    }
+}
+
+impl Display for ObservationSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ok: {}, empty: {}, failed: {}",
+            self.ok, self.empty, self.failed
+        )
+    }
+}
+
 pub struct ObservationService {
     pub logger: Logger,
     pub fetcher: Arc<XmlFetcher>,
+    /// Forecast station id to the nearby station id whose observations are used in its place
+    pub substitute_stations: HashMap<String, String>,
+    /// Max batched metar requests in flight at once, unset downloads NOAA's full metar cache in one request
+    pub concurrency: Option<usize>,
+    pub sanity_bounds: SanityBounds,
+    /// Station id to the name used when the resolved station list has none for it
+    pub station_names: HashMap<String, String>,
 }
 impl ObservationService {
-    pub fn new(logger: Logger, fetcher: Arc<XmlFetcher>) -> Self {
-        ObservationService { logger, fetcher }
+    pub fn new(
+        logger: Logger,
+        fetcher: Arc<XmlFetcher>,
+        substitute_stations: HashMap<String, String>,
+    ) -> Self {
+        ObservationService {
+            logger,
+            fetcher,
+            substitute_stations,
+            concurrency: None,
+            sanity_bounds: SanityBounds::default(),
+            station_names: HashMap::new(),
+        }
+    }
+
+    pub fn with_concurrency(mut self, concurrency: Option<usize>) -> Self {
+        self.concurrency = concurrency;
+        self
     }
+
+    pub fn with_sanity_bounds(mut self, sanity_bounds: SanityBounds) -> Self {
+        self.sanity_bounds = sanity_bounds;
+        self
+    }
+
+    pub fn with_station_names(mut self, station_names: HashMap<String, String>) -> Self {
+        self.station_names = station_names;
+        self
+    }
+
     pub async fn get_observations(
         &self,
         city_weather: &CityWeather,
-    ) -> Result<Vec<Observation>, Error> {
+    ) -> Result<(Vec<Observation>, ObservationSummary), Error> {
+        let (metars, failed_stations, fetch_timings) = match self.concurrency {
+            Some(concurrency) => self.get_batched_metars(city_weather, concurrency).await,
+            None => {
+                // The full cache is a single chunk
+                let started = Instant::now();
+                let metars = self.get_cached_metars().await?;
+                let mut fetch_timings = FetchTimings::default();
+                fetch_timings.record(started.elapsed());
+                (metars, HashSet::new(), fetch_timings)
+            }
+        };
+        let observations = self.to_observations(&metars, city_weather)?;
+
+        let observed_stations: HashSet<&String> = observations
+            .iter()
+            .map(|observation| &observation.station_id)
+            .collect();
+        let mut summary = ObservationSummary {
+            fetch_timings,
+            ..Default::default()
+        };
+        for station_id in city_weather.city_data.keys() {
+            let outcome = if failed_stations.contains(station_id) {
+                ObservationOutcome::Failed
+            } else if observed_stations.contains(station_id) {
+                ObservationOutcome::Ok
+            } else {
+                ObservationOutcome::Empty
+            };
+            summary.add(station_id.clone(), outcome);
+        }
+        Ok((observations, summary))
+    }
+
+    /// Fetches the current observation of a single station through the same request and parsing as a batched
+    /// run, for checking a station without running a full pull. The station list isn't downloaded so the
+    /// observation's station_name is left empty, `None` when NOAA has no usable reading for the station
+    pub async fn get_observation(&self, station_id: &str) -> Result<Option<Observation>, Error> {
+        let station_id = station_id.to_ascii_uppercase();
+        let city_weather = CityWeather {
+            city_data: HashMap::from([(
+                station_id.clone(),
+                WeatherStation {
+                    station_id: station_id.clone(),
+                    station_name: String::new(),
+                    latitude: String::new(),
+                    longitude: String::new(),
+                },
+            )]),
+        };
+        let url = self
+            .fetcher
+            .endpoints()
+            .station_metars_url(&self.request_ids(&[station_id]));
+        let metars = fetch_metars(&self.fetcher, &url).await?;
+        Ok(self.to_observations(&metars, &city_weather)?.pop())
+    }
+
+    /// Station ids to request metars for, substitutes are requested with the stations they stand in for
+    fn request_ids(&self, station_ids: &[String]) -> Vec<String> {
+        let mut request_ids: Vec<String> = station_ids
+            .iter()
+            .filter_map(|station_id| self.substitute_stations.get(station_id).cloned())
+            .chain(station_ids.iter().cloned())
+            .collect();
+        request_ids.sort();
+        request_ids.dedup();
+        request_ids
+    }
+
+    async fn get_cached_metars(&self) -> Result<Vec<Metar>, Error> {
         // Broken @ NOAA: https://forecast.weather.gov/xml/current_obs/all_xml.zip
-        let url = "https://aviationweather.gov/data/cache/metars.cache.xml.gz";
-        let raw_observation = self.fetcher.fetch_xml_gzip(url).await?;
-        let converted_xml: ObservationData = serde_xml_rs::from_str(&raw_observation)?;
+        let url = self.fetcher.endpoints().metars_url();
+        let raw_observation = self.fetcher.fetch_xml_gzip(&url).await?;
+        let converted_xml: Result<ObservationData, _> = serde_xml_rs::from_str(&raw_observation);
+        self.fetcher.record_payload(
+            PayloadKind::Observations,
+            &raw_observation,
+            converted_xml.is_err(),
+        );
+        Ok(converted_xml?.data.metar)
+    }
 
+    /// Requests the metars of the stations in batches, at most `concurrency` at a time, along with the ids of
+    /// the stations whose request failed and how long each batch's request took
+    async fn get_batched_metars(
+        &self,
+        city_weather: &CityWeather,
+        concurrency: usize,
+    ) -> (Vec<Metar>, HashSet<String>, FetchTimings) {
+        let semaphore = Arc::new(Semaphore::new(concurrency.max(1)));
+        let mut set = JoinSet::new();
+        for batch in split_cityweather(city_weather.clone(), OBSERVATION_BATCH_SIZE) {
+            let batch_station_ids = batch.get_station_ids();
+            let url = self
+                .fetcher
+                .endpoints()
+                .station_metars_url(&self.request_ids(&batch_station_ids));
+            let fetcher = self.fetcher.clone();
+            let semaphore = semaphore.clone();
+            set.spawn(async move {
+                let _permit = semaphore
+                    .acquire_owned()
+                    .await
+                    .expect("observation semaphore is never closed");
+                // Timed from the permit so waiting on the concurrency cap isn't counted
+                let started = Instant::now();
+                let batch_metars = fetch_metars(&fetcher, &url).await;
+                (batch_station_ids, started.elapsed(), batch_metars)
+            });
+        }
+
+        let mut metars = vec![];
+        let mut failed_stations = HashSet::new();
+        let mut fetch_timings = FetchTimings::default();
+        while let Some(result) = set.join_next().await {
+            if let Ok((_, fetch_duration, _)) = &result {
+                fetch_timings.record(*fetch_duration);
+            }
+            match result {
+                Ok((_, _, Ok(batch_metars))) => metars.extend(batch_metars),
+                Ok((batch_station_ids, _, Err(err))) => {
+                    error!(
+                        self.logger,
+                        "error getting observations for {} stations: {}",
+                        batch_station_ids.len(),
+                        err
+                    );
+                    failed_stations.extend(batch_station_ids);
+                }
+                Err(err) => error!(self.logger, "observation request task failed: {}", err),
+            }
+        }
+        (metars, failed_stations, fetch_timings)
+    }
+
+    pub fn to_observations(
+        &self,
+        metars: &[Metar],
+        city_weather: &CityWeather,
+    ) -> Result<Vec<Observation>, Error> {
+        let substitute_station_ids: HashSet<&String> = self.substitute_stations.values().collect();
+        let mut substitute_weather: HashMap<String, CurrentWeather> = HashMap::new();
+        let mut observed_stations: HashSet<String> = HashSet::new();
         let mut observations = vec![];
-        for value in converted_xml.data.metar.iter() {
+        for value in metars.iter() {
             if value.temp_c.is_none()
                 || value.longitude.is_none()
                 || value.latitude.is_none()
@@ -249,14 +516,518 @@ impl ObservationService {
                 continue;
             }
             let current: CurrentWeather = value.clone().try_into()?;
+            if substitute_station_ids.contains(&current.station_id) {
+                substitute_weather.insert(current.station_id.clone(), current.clone());
+            }
 
             let mut observation: Observation = current.try_into()?;
-            if let Some(city) = city_weather.city_data.get(&observation.station_id) {
-                // only add observation if we have a station_name with it
-                observation.station_name = city.station_name.clone();
+            self.sanity_bounds
+                .apply_to_observation(&self.logger, &mut observation);
+            // only add observation if it's for one of the resolved stations
+            if city_weather.city_data.contains_key(&observation.station_id) {
+                observed_stations.insert(observation.station_id.clone());
                 observations.push(observation)
             }
         }
+
+        for (forecast_station_id, substitute_station_id) in &self.substitute_stations {
+            if observed_stations.contains(forecast_station_id) {
+                continue;
+            }
+            if !city_weather.city_data.contains_key(forecast_station_id) {
+                continue;
+            }
+            let Some(current) = substitute_weather.get(substitute_station_id) else {
+                continue;
+            };
+            info!(
+                self.logger,
+                "using observations from {} for station {}",
+                substitute_station_id,
+                forecast_station_id
+            );
+            // Keeps the substitute's coordinates so it's clear where the reading was taken
+            let mut observation: Observation = current.clone().try_into()?;
+            self.sanity_bounds
+                .apply_to_observation(&self.logger, &mut observation);
+            observation.station_id = forecast_station_id.clone();
+            observations.push(observation);
+        }
+        self.enrich_station_names(&mut observations, city_weather);
         Ok(observations)
     }
+
+    /// Names observations the same way forecasts are named, from the resolved station list, falling back
+    /// to the configured station names. Stations without a known name are logged and left unnamed
+    pub fn enrich_station_names(
+        &self,
+        observations: &mut [Observation],
+        city_weather: &CityWeather,
+    ) {
+        let mut unnamed = vec![];
+        for observation in observations.iter_mut() {
+            let station_name = city_weather
+                .city_data
+                .get(&observation.station_id)
+                .map(|city| city.station_name.as_str())
+                .filter(|station_name| !station_name.is_empty())
+                .or_else(|| {
+                    self.station_names
+                        .get(&observation.station_id)
+                        .map(String::as_str)
+                });
+            match station_name {
+                Some(station_name) => observation.station_name = station_name.to_string(),
+                None => unnamed.push(observation.station_id.clone()),
+            }
+        }
+        if !unnamed.is_empty() {
+            unnamed.sort();
+            warn!(
+                self.logger,
+                "no station name known for {} stations: {}",
+                unnamed.len(),
+                unnamed.join(", ")
+            );
+        }
+    }
+}
+
+async fn fetch_metars(fetcher: &XmlFetcher, url: &str) -> Result<Vec<Metar>, Error> {
+    let raw_observation = fetcher.fetch_xml(url).await?;
+    let converted_xml: Result<ObservationData, _> = serde_xml_rs::from_str(&raw_observation);
+    fetcher.record_payload(
+        PayloadKind::Observations,
+        &raw_observation,
+        converted_xml.is_err(),
+    );
+    Ok(converted_xml?.data.metar)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ObservationOutcome, ObservationService};
+    use crate::{
+        test_server::{spawn_server, MockServer, Response},
+        CircuitBreaker, CityWeather, Metar, NoaaEndpoints, RateLimiter, RawPayloads,
+        WeatherStation, XmlFetcher,
+    };
+    use async_compression::tokio::bufread::GzipEncoder;
+    use slog::{o, Discard, Logger};
+    use std::{
+        collections::HashMap,
+        fs,
+        future::ready,
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{io::AsyncReadExt, sync::Mutex, time::sleep};
+
+    const METARS_XML: &str = r#"<?xml version="1.0" encoding="UTF-8"?>
+<response>
+  <request_index>1</request_index>
+  <data_source name="metars" />
+  <request type="retrieve" />
+  <errors />
+  <warnings />
+  <time_taken_ms>1</time_taken_ms>
+  <data num_results="1">
+    <METAR>
+      <raw_text>KLWV 121200Z AUTO 18008KT 10SM CLR 26/18 A3001</raw_text>
+      <station_id>KLWV</station_id>
+      <observation_time>2024-08-12T12:00:00Z</observation_time>
+      <latitude>38.76</latitude>
+      <longitude>-87.61</longitude>
+      <temp_c>25.5</temp_c>
+      <dewpoint_c>18.0</dewpoint_c>
+      <wind_dir_degrees>180</wind_dir_degrees>
+      <wind_speed_kt>8</wind_speed_kt>
+      <elevation_m>130</elevation_m>
+    </METAR>
+  </data>
+</response>"#;
+
+    fn observation_service(substitute_stations: HashMap<String, String>) -> ObservationService {
+        observation_service_at(NoaaEndpoints::default(), substitute_stations)
+    }
+
+    fn observation_service_at(
+        endpoints: NoaaEndpoints,
+        substitute_stations: HashMap<String, String>,
+    ) -> ObservationService {
+        observation_service_with(endpoints, substitute_stations, None)
+    }
+
+    fn observation_service_with(
+        endpoints: NoaaEndpoints,
+        substitute_stations: HashMap<String, String>,
+        raw_payloads: Option<RawPayloads>,
+    ) -> ObservationService {
+        let logger = Logger::root(Discard, o!());
+        let fetcher = Arc::new(
+            XmlFetcher::new(
+                logger.clone(),
+                String::from("noaa-data-pipeline/test"),
+                Arc::new(Mutex::new(RateLimiter::new(10, 10.0))),
+                Arc::new(CircuitBreaker::new(
+                    logger.clone(),
+                    5,
+                    Duration::from_secs(300),
+                )),
+            )
+            .with_endpoints(endpoints)
+            .with_raw_payloads(raw_payloads),
+        );
+        ObservationService::new(logger, fetcher, substitute_stations)
+    }
+
+    /// Serves `body` gzipped to every request
+    async fn spawn_mirror(body: &str) -> MockServer {
+        let mut gzipped = vec![];
+        GzipEncoder::new(body.as_bytes())
+            .read_to_end(&mut gzipped)
+            .await
+            .unwrap();
+        spawn_server(move |_, _| ready(Response::ok(gzipped.clone()))).await
+    }
+
+    #[derive(Default)]
+    struct MetarApiCounts {
+        requests: AtomicUsize,
+        in_flight: AtomicUsize,
+        max_in_flight: AtomicUsize,
+    }
+
+    /// Answers every request with `body` after a short delay, tracking how many are being served at once
+    async fn spawn_metar_api(body: &'static str) -> (NoaaEndpoints, Arc<MetarApiCounts>) {
+        let counts = Arc::new(MetarApiCounts::default());
+        let server_counts = counts.clone();
+        let api = spawn_server(move |_, _| {
+            let counts = server_counts.clone();
+            async move {
+                counts.requests.fetch_add(1, Ordering::SeqCst);
+                let in_flight = counts.in_flight.fetch_add(1, Ordering::SeqCst) + 1;
+                counts.max_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+                sleep(Duration::from_millis(100)).await;
+                counts.in_flight.fetch_sub(1, Ordering::SeqCst);
+                Response::ok(body)
+            }
+        })
+        .await;
+        let endpoints = NoaaEndpoints {
+            aviation_weather: api.url(),
+            ..Default::default()
+        };
+        (endpoints, counts)
+    }
+
+    fn metar(station_id: &str, temp_c: &str) -> Metar {
+        Metar {
+            raw_text: String::from(""),
+            station_id: String::from(station_id),
+            observation_time: Some(String::from("2024-08-12T12:00:00Z")),
+            latitude: Some(String::from("38.76")),
+            longitude: Some(String::from("-87.61")),
+            temp_c: Some(String::from(temp_c)),
+            dewpoint_c: None,
+            wind_dir_degrees: Some(String::from("180")),
+            wind_speed_kt: Some(String::from("8")),
+            elevation_m: String::from("130"),
+            wx_string: None,
+            precip_in: None,
+        }
+    }
+
+    fn city_weather() -> CityWeather {
+        let station = |station_id: &str, station_name: &str| WeatherStation {
+            station_id: String::from(station_id),
+            station_name: String::from(station_name),
+            latitude: String::from("38.76"),
+            longitude: String::from("-87.61"),
+        };
+        CityWeather {
+            city_data: HashMap::from([
+                (String::from("KLWV"), station("KLWV", "Lawrenceville")),
+                (String::from("PFNO"), station("PFNO", "Noatak")),
+            ]),
+        }
+    }
+
+    #[test]
+    fn attaches_substitute_station_observations() {
+        let service = observation_service(HashMap::from([(
+            String::from("PFNO"),
+            String::from("PAOT"),
+        )]));
+        let metars = vec![metar("KLWV", "25.0"), metar("PAOT", "12.5")];
+
+        let observations = service.to_observations(&metars, &city_weather()).unwrap();
+
+        assert_eq!(observations.len(), 2);
+        let substituted = observations
+            .iter()
+            .find(|observation| observation.station_id == "PFNO")
+            .unwrap();
+        assert_eq!(substituted.station_name, "Noatak");
+        assert_eq!(substituted.temperature_value, Some(12.5));
+        assert!(observations
+            .iter()
+            .all(|observation| observation.station_id != "PAOT"));
+    }
+
+    #[test]
+    fn prefers_station_own_observations_over_substitute() {
+        let service = observation_service(HashMap::from([(
+            String::from("KLWV"),
+            String::from("PAOT"),
+        )]));
+        let metars = vec![metar("KLWV", "25.0"), metar("PAOT", "12.5")];
+
+        let observations = service.to_observations(&metars, &city_weather()).unwrap();
+
+        assert_eq!(observations.len(), 1);
+        assert_eq!(observations[0].station_id, "KLWV");
+        assert_eq!(observations[0].temperature_value, Some(25.0));
+    }
+
+    #[test]
+    fn names_observations_from_the_resolved_station_list() {
+        let service = observation_service(HashMap::new());
+        let metars = vec![metar("KLWV", "25.0")];
+
+        let observations = service.to_observations(&metars, &city_weather()).unwrap();
+
+        assert_eq!(observations.len(), 1);
+        assert_eq!(observations[0].station_name, "Lawrenceville");
+    }
+
+    #[test]
+    fn falls_back_to_configured_station_names() {
+        let service = observation_service(HashMap::new()).with_station_names(HashMap::from([
+            (
+                String::from("KLWV"),
+                String::from("Lawrenceville-Vincennes"),
+            ),
+            (String::from("PFNO"), String::from("Noatak Airport")),
+        ]));
+        let mut city_weather = city_weather();
+        city_weather
+            .city_data
+            .get_mut("KLWV")
+            .unwrap()
+            .station_name
+            .clear();
+        city_weather.city_data.insert(
+            String::from("PAOT"),
+            WeatherStation {
+                station_id: String::from("PAOT"),
+                station_name: String::new(),
+                latitude: String::from("66.88"),
+                longitude: String::from("-162.6"),
+            },
+        );
+        let metars = vec![
+            metar("KLWV", "25.0"),
+            metar("PFNO", "12.5"),
+            metar("PAOT", "11.0"),
+        ];
+
+        let observations = service.to_observations(&metars, &city_weather).unwrap();
+
+        let station_name = |station_id: &str| {
+            observations
+                .iter()
+                .find(|observation| observation.station_id == station_id)
+                .map(|observation| observation.station_name.clone())
+                .unwrap()
+        };
+        // The station list's own name wins over the configured one
+        assert_eq!(station_name("PFNO"), "Noatak");
+        assert_eq!(station_name("KLWV"), "Lawrenceville-Vincennes");
+        assert_eq!(station_name("PAOT"), "");
+    }
+
+    #[tokio::test]
+    async fn pulls_observations_from_configured_mirror() {
+        let mirror = spawn_mirror(METARS_XML).await;
+        let endpoints = NoaaEndpoints {
+            aviation_weather: mirror.url(),
+            ..Default::default()
+        };
+        let service = observation_service_at(endpoints, HashMap::new());
+
+        let (observations, _) = service.get_observations(&city_weather()).await.unwrap();
+
+        assert_eq!(
+            mirror.request_lines(),
+            vec!["GET /data/cache/metars.cache.xml.gz HTTP/1.1"]
+        );
+        assert_eq!(observations.len(), 1);
+        assert_eq!(observations[0].station_id, "KLWV");
+        assert_eq!(observations[0].temperature_value, Some(25.5));
+    }
+
+    #[tokio::test]
+    async fn saves_raw_payload_when_parse_fails() {
+        let broken_xml = "<response><data num_results=\"1\"><METAR><station_id>KLWV";
+        let mirror = spawn_mirror(broken_xml).await;
+        let endpoints = NoaaEndpoints {
+            aviation_weather: mirror.url(),
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir().join(format!("raw_observations_{}", std::process::id()));
+        let service = observation_service_with(
+            endpoints,
+            HashMap::new(),
+            Some(RawPayloads::new(&dir, false)),
+        );
+
+        assert!(service.get_observations(&city_weather()).await.is_err());
+
+        let saved: Vec<_> = fs::read_dir(&dir)
+            .unwrap()
+            .map(|entry| entry.unwrap().path())
+            .collect();
+        assert_eq!(saved.len(), 1);
+        let file_name = saved[0].file_name().unwrap().to_str().unwrap();
+        assert!(file_name.starts_with("observations_failed_"));
+        assert!(file_name.ends_with(".xml"));
+        assert_eq!(fs::read_to_string(&saved[0]).unwrap(), broken_xml);
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn skips_raw_payload_when_parse_succeeds() {
+        let mirror = spawn_mirror(METARS_XML).await;
+        let endpoints = NoaaEndpoints {
+            aviation_weather: mirror.url(),
+            ..Default::default()
+        };
+        let dir = std::env::temp_dir().join(format!("raw_metars_{}", std::process::id()));
+        let service = observation_service_with(
+            endpoints,
+            HashMap::new(),
+            Some(RawPayloads::new(&dir, false)),
+        );
+
+        service.get_observations(&city_weather()).await.unwrap();
+
+        assert!(!dir.exists());
+    }
+
+    #[tokio::test]
+    async fn batched_observations_stay_within_concurrency_cap() {
+        let (endpoints, counts) = spawn_metar_api(METARS_XML).await;
+        let service = observation_service_at(endpoints, HashMap::new()).with_concurrency(Some(2));
+        let city_data = (0..250)
+            .map(|i| {
+                let station_id = format!("K{:03}", i);
+                let station = WeatherStation {
+                    station_id: station_id.clone(),
+                    station_name: station_id.clone(),
+                    latitude: String::from("38.76"),
+                    longitude: String::from("-87.61"),
+                };
+                (station_id, station)
+            })
+            .collect();
+
+        let (_, summary) = service
+            .get_observations(&CityWeather { city_data })
+            .await
+            .unwrap();
+
+        assert_eq!(counts.requests.load(Ordering::SeqCst), 5);
+        assert_eq!(counts.max_in_flight.load(Ordering::SeqCst), 2);
+        assert_eq!(summary.stations.len(), 250);
+        assert_eq!(summary.empty, 250);
+    }
+
+    #[tokio::test]
+    async fn records_fetch_timing_of_each_completed_batch() {
+        let (endpoints, counts) = spawn_metar_api(METARS_XML).await;
+        let service = observation_service_at(endpoints, HashMap::new()).with_concurrency(Some(2));
+        let city_data = (0..250)
+            .map(|i| {
+                let station_id = format!("K{:03}", i);
+                let station = WeatherStation {
+                    station_id: station_id.clone(),
+                    station_name: station_id.clone(),
+                    latitude: String::from("38.76"),
+                    longitude: String::from("-87.61"),
+                };
+                (station_id, station)
+            })
+            .collect();
+
+        let (_, summary) = service
+            .get_observations(&CityWeather { city_data })
+            .await
+            .unwrap();
+
+        assert_eq!(counts.requests.load(Ordering::SeqCst), 5);
+        let durations = summary.fetch_timings.durations();
+        assert_eq!(durations.len(), 5);
+        // The api takes 100ms to answer, batches queued behind the cap aren't charged for the wait
+        assert!(durations
+            .iter()
+            .all(|duration| *duration >= Duration::from_millis(100)));
+        let stats = summary.fetch_timings.stats().unwrap();
+        assert_eq!(stats.chunks, 5);
+        assert!(stats.min_ms >= 100);
+        assert!(stats.min_ms <= stats.avg_ms && stats.avg_ms <= stats.p95_ms);
+        assert_eq!(stats.p95_ms, stats.max_ms);
+    }
+
+    #[tokio::test]
+    async fn reports_observation_outcome_per_station() {
+        let (endpoints, _counts) = spawn_metar_api(METARS_XML).await;
+        let service = observation_service_at(endpoints, HashMap::new()).with_concurrency(Some(4));
+
+        let (observations, summary) = service.get_observations(&city_weather()).await.unwrap();
+
+        assert_eq!(observations.len(), 1);
+        assert_eq!((summary.ok, summary.empty, summary.failed), (1, 1, 0));
+        assert_eq!(summary.stations["KLWV"], ObservationOutcome::Ok);
+        assert_eq!(summary.stations["PFNO"], ObservationOutcome::Empty);
+    }
+
+    #[tokio::test]
+    async fn gets_observation_of_a_single_station() {
+        let (endpoints, counts) = spawn_metar_api(METARS_XML).await;
+        let service = observation_service_at(endpoints, HashMap::new());
+
+        let observation = service.get_observation("klwv").await.unwrap().unwrap();
+
+        assert_eq!(counts.requests.load(Ordering::SeqCst), 1);
+        assert_eq!(observation.station_id, "KLWV");
+        assert_eq!(observation.temperature_value, Some(25.5));
+        assert_eq!(observation.wind_speed, Some(8));
+        assert_eq!(observation.generated_at, "2024-08-12T12:00:00Z");
+    }
+
+    #[tokio::test]
+    async fn single_station_without_a_reading_has_no_observation() {
+        let (endpoints, _counts) = spawn_metar_api(METARS_XML).await;
+        let service = observation_service_at(endpoints, HashMap::new());
+
+        assert!(service.get_observation("PFNO").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn marks_stations_failed_when_their_batch_fails() {
+        let (endpoints, _counts) =
+            spawn_metar_api("<response><data num_results=\"1\"><METAR><station_id>KLWV").await;
+        let service = observation_service_at(endpoints, HashMap::new()).with_concurrency(Some(4));
+
+        let (observations, summary) = service.get_observations(&city_weather()).await.unwrap();
+
+        assert!(observations.is_empty());
+        assert_eq!((summary.ok, summary.empty, summary.failed), (0, 0, 2));
+        assert_eq!(summary.stations["KLWV"], ObservationOutcome::Failed);
+        assert_eq!(summary.stations["PFNO"], ObservationOutcome::Failed);
+    }
 }
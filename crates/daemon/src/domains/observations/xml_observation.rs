@@ -29,7 +29,8 @@ pub struct ObservationData {
 
 #[derive(Serialize, Deserialize)]
 pub struct CurrentData {
-    #[serde(rename = "METAR")]
+    // Requests for specific stations answer without any METAR elements when none of them reported
+    #[serde(rename = "METAR", default)]
     pub metar: Vec<Metar>,
 
     #[serde(rename = "num_results")]
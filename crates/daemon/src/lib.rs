@@ -1,9 +1,29 @@
+mod alerts;
+mod circuit_breaker;
 mod coordinates;
 mod domains;
+mod oracle_client;
 mod parquet_handler;
+mod pending_uploads;
+mod raw_payloads;
+mod run_summary;
+mod sanity_bounds;
+mod scheduler;
+mod selftest;
+#[cfg(test)]
+mod test_server;
 mod utils;
 
+pub use alerts::*;
+pub use circuit_breaker::*;
 pub use coordinates::*;
 pub use domains::*;
+pub use oracle_client::*;
 pub use parquet_handler::*;
+pub use pending_uploads::*;
+pub use raw_payloads::*;
+pub use run_summary::*;
+pub use sanity_bounds::*;
+pub use scheduler::*;
+pub use selftest::*;
 pub use utils::*;
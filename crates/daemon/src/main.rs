@@ -1,101 +1,448 @@
 use daemon::{
-    create_folder, get_config_info, get_coordinates, save_forecasts, save_observations,
-    send_parquet_files, setup_logger, subfolder_exists, Cli, ForecastService, ObservationService,
-    RateLimiter, XmlFetcher,
+    create_folder, get_config_info, get_forecast_products, get_oracle_activity,
+    get_station_coordinates, import_forecast_file, load_observation_stations, load_station_names,
+    run_poll_schedule, run_selftest, save_forecasts, save_observations, send_parquet_file,
+    setup_logger, subfolder_exists, Activity, Alerter, CircuitBreaker, CityWeather, Cli, Command,
+    ForecastService, ForecastSource, NoaaEndpoints, ObservationService, OracleRetry,
+    ParquetCompression, PendingUploads, PollSchedule, RateLimiter, RawPayloads, RunSummary,
+    SanityBounds, UploadStatus, XmlFetcher, DEFAULT_REQUEST_TIMEOUT, PENDING_UPLOADS_FILE,
 };
 use slog::{debug, error, info, Logger};
-use std::{sync::Arc, time::Duration};
+use std::{collections::HashMap, path::Path, sync::Arc, time::Duration};
 use time::{format_description::well_known::Rfc3339, OffsetDateTime};
 use tokio::sync::Mutex;
-use tokio::time::interval;
+use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() -> Result<(), anyhow::Error> {
     let cli = get_config_info();
     let logger = setup_logger(&cli);
 
+    if let Some(Command::Selftest) = cli.command {
+        let report = run_selftest(&logger);
+        if !report.passed() {
+            return Err(anyhow::anyhow!("selftest failed"));
+        }
+        info!(logger, "selftest passed");
+        return Ok(());
+    }
+
+    if let Some(import_path) = cli.import_forecasts.clone() {
+        let subfolder = get_subfolder(&cli, &logger);
+        let current_utc_time: String = OffsetDateTime::now_utc().format(&Rfc3339)?;
+        let forecast_parquet = import_forecast_file(
+            &import_path,
+            &subfolder,
+            format!("{}_{}", "forecasts", current_utc_time),
+            get_parquet_compression(&cli)?,
+        )?;
+        info!(logger, "imported forecasts to: {}", forecast_parquet);
+        return Ok(());
+    }
+
     // Max send 3 requests per 15 second to noaa
     let rate_limiter = Arc::new(Mutex::new(RateLimiter::new(
         cli.token_capacity.unwrap_or(3),
         cli.refill_rate.unwrap_or(15.0_f64),
     )));
 
-    // Run once every hour
-    process_weather_data_hourly(cli, logger, Arc::clone(&rate_limiter)).await;
+    // Shared across runs so an extended NOAA outage keeps the circuit open between data pulls
+    let circuit_breaker = Arc::new(CircuitBreaker::new(
+        logger.clone(),
+        cli.breaker_failure_threshold.unwrap_or(5),
+        Duration::from_secs(cli.breaker_cooldown.unwrap_or(300)),
+    ));
+
+    if let Some(Command::Observation { station_id }) = cli.command.clone() {
+        let fetcher = new_fetcher(
+            &cli,
+            &logger,
+            rate_limiter,
+            circuit_breaker,
+            RawPayloads::from_cli(&cli),
+        );
+        let substitute_stations = match &cli.observation_stations {
+            Some(file_path) => load_observation_stations(file_path)?,
+            None => HashMap::new(),
+        };
+        let observation_service =
+            ObservationService::new(logger.clone(), fetcher, substitute_stations)
+                .with_sanity_bounds(SanityBounds::from_cli(&cli)?)
+                .with_station_names(get_station_names(&cli)?);
+        let Some(observation) = observation_service.get_observation(&station_id).await? else {
+            return Err(anyhow::anyhow!(
+                "no observation found for station {}",
+                station_id
+            ));
+        };
+        info!(logger, "observation: {:?}", observation);
+        return Ok(());
+    }
+
+    process_weather_data_on_schedule(
+        cli,
+        logger,
+        Arc::clone(&rate_limiter),
+        Arc::clone(&circuit_breaker),
+    )
+    .await;
     Ok(())
 }
 
-async fn process_weather_data_hourly(
+fn new_fetcher(
+    cli: &Cli,
+    logger: &Logger,
+    rate_limit: Arc<Mutex<RateLimiter>>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    raw_payloads: Option<RawPayloads>,
+) -> Arc<XmlFetcher> {
+    Arc::new(
+        XmlFetcher::new(
+            logger.clone(),
+            cli.user_agent
+                .clone()
+                .unwrap_or(String::from("noaa-data-pipeline/1.0")),
+            rate_limit,
+            circuit_breaker,
+        )
+        .with_endpoints(NoaaEndpoints::from_cli(cli))
+        .with_raw_payloads(raw_payloads)
+        .with_request_timeout(
+            cli.request_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_REQUEST_TIMEOUT),
+        ),
+    )
+}
+
+async fn process_weather_data_on_schedule(
     cli: Cli,
     logger: Logger,
     rate_limit: Arc<Mutex<RateLimiter>>,
+    circuit_breaker: Arc<CircuitBreaker>,
 ) {
-    // defaults to once an hour
-    let sleep_between_checks = cli.sleep_interval.unwrap_or(3600);
+    let schedule = PollSchedule::from_cli(&cli);
     info!(
         logger,
-        "wait time between data pulls: {} seconds", sleep_between_checks
+        "wait time between forecast pulls: {} seconds, observation pulls: {} seconds",
+        schedule.forecast_interval.as_secs(),
+        schedule.observation_interval.as_secs()
+    );
+    if let Some(idle_backoff) = schedule.idle_backoff {
+        info!(
+            logger,
+            "backing off up to {} seconds between pulls while the oracle has no active events",
+            idle_backoff.as_secs()
+        );
+    }
+    let raw_payloads = RawPayloads::from_cli(&cli);
+    if let Some(raw_payloads) = &raw_payloads {
+        info!(
+            logger,
+            "saving raw NOAA payloads to: {}",
+            raw_payloads.dir().display()
+        );
+    }
+    // Both pipelines share the fetcher so they stay within the same NOAA rate limit and circuit breaker
+    let fetcher = new_fetcher(
+        &cli,
+        &logger,
+        rate_limit,
+        circuit_breaker.clone(),
+        raw_payloads,
     );
-    let mut check_channel_interval = interval(Duration::from_secs(sleep_between_checks));
-    loop {
-        tokio::select! {
-            _ = check_channel_interval.tick() => {
-                    match process_data(cli.clone(), logger.clone(), rate_limit.clone()).await {
-                        Ok(_) => info!(logger, "finished processing data, waiting an hour to run again"),
-                        Err(err) => error!(&logger, "error processing data: {}", err)
-                    }
+
+    // Files that failed to upload are re-sent by whichever pipeline runs next, the queue is kept in the
+    // data dir so files left over from before a restart are re-sent too
+    let data_dir = cli.data_dir.clone().unwrap_or(String::from("./data"));
+    create_folder(&data_dir, &logger);
+    let pending_uploads =
+        PendingUploads::load(&logger, Path::new(&data_dir).join(PENDING_UPLOADS_FILE));
+    let alerter = Alerter::from_cli(logger.clone(), &cli, &schedule);
+    if let Some(alerter) = &alerter {
+        info!(
+            logger,
+            "sending alerts on failed runs, forecasts older than {} seconds or observations older than {} seconds",
+            alerter.stale_after().forecasts.as_secs(),
+            alerter.stale_after().observations.as_secs()
+        );
+    }
+
+    // Ctrl+C cuts the forecast run in flight short, its partial results are still saved and uploaded.
+    // Both pipelines stop once their current run is done
+    let shutdown = CancellationToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        let logger = logger.clone();
+        async move {
+            match tokio::signal::ctrl_c().await {
+                Ok(()) => {
+                    info!(logger, "received shutdown signal, finishing current runs");
+                    shutdown.cancel();
+                }
+                Err(err) => error!(logger, "error listening for shutdown signal: {}", err),
             }
         }
-    }
+    });
+
+    let cli = &cli;
+    let logger = &logger;
+    let fetcher = &fetcher;
+    let pending_uploads = &pending_uploads;
+    let circuit_breaker = &circuit_breaker;
+    let alerter = &alerter;
+    let shutdown = &shutdown;
+    run_poll_schedule(
+        schedule,
+        shutdown,
+        move || async move {
+            if schedule.idle_backoff.is_some() && is_oracle_idle(cli, logger).await {
+                info!(
+                    logger,
+                    "oracle has no active events, skipping forecasts run"
+                );
+                return Activity::Idle;
+            }
+            let mut summary = RunSummary::new("forecasts");
+            match process_forecasts(
+                cli,
+                logger,
+                fetcher.clone(),
+                pending_uploads,
+                shutdown,
+                &mut summary,
+            )
+            .await
+            {
+                Ok(_) => info!(logger, "finished processing forecasts"),
+                Err(err) => {
+                    error!(logger, "error processing forecasts: {}", err);
+                    summary.error = Some(err.to_string());
+                }
+            }
+            summary.log(logger);
+            write_metrics(cli, logger, &summary);
+            if let Some(alerter) = alerter {
+                alerter.check_run(&summary).await;
+            }
+            info!(
+                logger,
+                "noaa circuit breaker state: {}",
+                circuit_breaker.state()
+            );
+            Activity::Active
+        },
+        move || async move {
+            if schedule.idle_backoff.is_some() && is_oracle_idle(cli, logger).await {
+                info!(
+                    logger,
+                    "oracle has no active events, skipping observations run"
+                );
+                return Activity::Idle;
+            }
+            let mut summary = RunSummary::new("observations");
+            match process_observations(cli, logger, fetcher.clone(), pending_uploads, &mut summary)
+                .await
+            {
+                Ok(_) => info!(logger, "finished processing observations"),
+                Err(err) => {
+                    error!(logger, "error processing observations: {}", err);
+                    summary.error = Some(err.to_string());
+                }
+            }
+            summary.log(logger);
+            write_metrics(cli, logger, &summary);
+            if let Some(alerter) = alerter {
+                alerter.check_run(&summary).await;
+            }
+            info!(
+                logger,
+                "noaa circuit breaker state: {}",
+                circuit_breaker.state()
+            );
+            Activity::Active
+        },
+    )
+    .await;
 }
 
-async fn process_data(
-    cli: Cli,
-    logger: Logger,
-    rate_limiter: Arc<Mutex<RateLimiter>>,
+async fn process_forecasts(
+    cli: &Cli,
+    logger: &Logger,
+    fetcher: Arc<XmlFetcher>,
+    pending_uploads: &PendingUploads,
+    shutdown: &CancellationToken,
+    summary: &mut RunSummary,
 ) -> Result<(), anyhow::Error> {
-    let logger_cpy = &logger.clone();
-    let fetcher = Arc::new(XmlFetcher::new(
-        logger.clone(),
-        cli.user_agent
-            .clone()
-            .unwrap_or(String::from("noaa-data-pipeline/1.0")),
-        rate_limiter,
-    ));
-
-    let city_weather_coordinates = get_coordinates(fetcher.clone()).await?;
-
-    debug!(logger_cpy, "coordinates: {}", city_weather_coordinates);
+    flush_pending_uploads(cli, logger, pending_uploads).await;
+    let city_weather_coordinates = get_run_coordinates(cli, logger, fetcher.clone()).await?;
+    debug!(logger, "coordinates: {}", city_weather_coordinates);
+    if city_weather_coordinates.city_data.is_empty() {
+        info!(logger, "no stations to fetch, skipping run");
+        return Ok(());
+    }
+    summary.stations = city_weather_coordinates.city_data.len();
 
-    let forecast_service = ForecastService::new(logger.clone(), fetcher.clone());
-    let forecasts = forecast_service
+    let forecast_products = get_forecast_products(cli.forecast_products.clone())?;
+    let forecast_source =
+        ForecastSource::try_from(cli.forecast_source.as_deref().unwrap_or("xml"))?;
+    let forecast_service =
+        ForecastService::new(logger.clone(), fetcher, forecast_products, forecast_source)
+            .with_precipitation_decimals(cli.precipitation_decimals)
+            .with_sanity_bounds(SanityBounds::from_cli(cli)?)
+            .with_cancellation(shutdown.clone());
+    let (forecasts, forecast_summary) = forecast_service
         .get_forecasts(&city_weather_coordinates)
         .await?;
-    debug!(logger_cpy, "forcasts count {}", forecasts.len());
-    let observation_service = ObservationService::new(logger, fetcher);
-    let observations = observation_service
-        .get_observations(&city_weather_coordinates)
-        .await?;
+    debug!(logger, "forcasts count {}", forecasts.len());
+    summary.records = forecasts.len();
+    summary.fetch = forecast_summary.fetch_timings.stats();
+    info!(logger, "forecast stations {}", forecast_summary);
 
-    debug!(logger_cpy, "observations count: {:?}", observations.len());
     let current_utc_time: String = OffsetDateTime::now_utc().format(&Rfc3339)?;
-    let root_path = cli.data_dir.clone().unwrap_or(String::from("./data"));
-    create_folder(&root_path, logger_cpy);
-    let current_date = OffsetDateTime::now_utc().date();
-    let subfolder = format!("{}/{}", root_path, current_date);
-    if !subfolder_exists(&subfolder) {
-        create_folder(&subfolder, logger_cpy)
-    }
+    let subfolder = get_subfolder(cli, logger);
     let forecast_parquet = save_forecasts(
         forecasts,
         &subfolder,
         format!("{}_{}", "forecasts", current_utc_time),
-    );
+        get_parquet_compression(cli)?,
+    )?;
+    summary.record_file(&forecast_parquet);
+    let upload = pending_uploads
+        .send_or_queue(forecast_parquet, |file| {
+            send_parquet_file(cli, logger, file)
+        })
+        .await;
+    summary.upload = if upload.is_ok() {
+        UploadStatus::Sent
+    } else {
+        UploadStatus::Queued
+    };
+    upload.map_err(|e| anyhow::anyhow!("failed to upload forecasts: {}", e))
+}
+
+async fn process_observations(
+    cli: &Cli,
+    logger: &Logger,
+    fetcher: Arc<XmlFetcher>,
+    pending_uploads: &PendingUploads,
+    summary: &mut RunSummary,
+) -> Result<(), anyhow::Error> {
+    flush_pending_uploads(cli, logger, pending_uploads).await;
+    let city_weather_coordinates = get_run_coordinates(cli, logger, fetcher.clone()).await?;
+    debug!(logger, "coordinates: {}", city_weather_coordinates);
+    if city_weather_coordinates.city_data.is_empty() {
+        info!(logger, "no stations to fetch, skipping run");
+        return Ok(());
+    }
+    summary.stations = city_weather_coordinates.city_data.len();
+
+    let substitute_stations = match &cli.observation_stations {
+        Some(file_path) => load_observation_stations(file_path)?,
+        None => HashMap::new(),
+    };
+    let observation_service = ObservationService::new(logger.clone(), fetcher, substitute_stations)
+        .with_concurrency(cli.observation_concurrency)
+        .with_sanity_bounds(SanityBounds::from_cli(cli)?)
+        .with_station_names(get_station_names(cli)?);
+    let (observations, observation_summary) = observation_service
+        .get_observations(&city_weather_coordinates)
+        .await?;
+    info!(logger, "observation stations {}", observation_summary);
+    debug!(logger, "observations count: {:?}", observations.len());
+    summary.records = observations.len();
+    summary.fetch = observation_summary.fetch_timings.stats();
+
+    let current_utc_time: String = OffsetDateTime::now_utc().format(&Rfc3339)?;
+    let subfolder = get_subfolder(cli, logger);
     let observation_parquet = save_observations(
         observations,
         &subfolder,
         format!("{}_{}", "observations", current_utc_time),
+        get_parquet_compression(cli)?,
+    )?;
+    summary.record_file(&observation_parquet);
+    let upload = pending_uploads
+        .send_or_queue(observation_parquet, |file| {
+            send_parquet_file(cli, logger, file)
+        })
+        .await;
+    summary.upload = if upload.is_ok() {
+        UploadStatus::Sent
+    } else {
+        UploadStatus::Queued
+    };
+    upload.map_err(|e| anyhow::anyhow!("failed to upload observations: {}", e))
+}
+
+fn get_station_names(cli: &Cli) -> Result<HashMap<String, String>, anyhow::Error> {
+    match &cli.station_names {
+        Some(file_path) => load_station_names(file_path),
+        None => Ok(HashMap::new()),
+    }
+}
+
+fn write_metrics(cli: &Cli, logger: &Logger, summary: &RunSummary) {
+    let Some(metrics_file) = &cli.metrics_file else {
+        return;
+    };
+    if let Err(err) = summary.write_metrics(metrics_file) {
+        error!(
+            logger,
+            "failed to write {} run metrics to {}: {}", summary.pipeline, metrics_file, err
+        );
+    }
+}
+
+async fn flush_pending_uploads(cli: &Cli, logger: &Logger, pending_uploads: &PendingUploads) {
+    let pending = pending_uploads.files().len();
+    if pending == 0 {
+        return;
+    }
+    info!(logger, "re-sending {} pending uploads", pending);
+    let delivered = pending_uploads
+        .flush(logger, |file| send_parquet_file(cli, logger, file))
+        .await;
+    info!(
+        logger,
+        "re-sent {} of {} pending uploads", delivered, pending
     );
-    send_parquet_files(&cli, logger_cpy, observation_parquet, forecast_parquet).await?;
-    Ok(())
+}
+
+async fn get_run_coordinates(
+    cli: &Cli,
+    logger: &Logger,
+    fetcher: Arc<XmlFetcher>,
+) -> Result<CityWeather, anyhow::Error> {
+    if !cli.stations_from_oracle.unwrap_or(false) {
+        return get_station_coordinates(logger, fetcher, None, OracleRetry::from_cli(cli)).await;
+    }
+    let base_url = cli
+        .base_url
+        .clone()
+        .unwrap_or(String::from("http://localhost:9100"));
+    get_station_coordinates(logger, fetcher, Some(&base_url), OracleRetry::from_cli(cli)).await
+}
+
+async fn is_oracle_idle(cli: &Cli, logger: &Logger) -> bool {
+    let base_url = cli
+        .base_url
+        .clone()
+        .unwrap_or(String::from("http://localhost:9100"));
+    get_oracle_activity(logger, &base_url, OracleRetry::from_cli(cli)).await == Activity::Idle
+}
+
+fn get_subfolder(cli: &Cli, logger: &Logger) -> String {
+    let root_path = cli.data_dir.clone().unwrap_or(String::from("./data"));
+    create_folder(&root_path, logger);
+    let current_date = OffsetDateTime::now_utc().date();
+    let subfolder = format!("{}/{}", root_path, current_date);
+    if !subfolder_exists(&subfolder) {
+        create_folder(&subfolder, logger)
+    }
+    subfolder
+}
+
+fn get_parquet_compression(cli: &Cli) -> Result<ParquetCompression, anyhow::Error> {
+    ParquetCompression::try_from(cli.parquet_compression.as_deref().unwrap_or("none"))
 }
@@ -0,0 +1,258 @@
+use std::{
+    fmt::{self, Display},
+    future::Future,
+    time::Duration,
+};
+
+use anyhow::{anyhow, Error};
+use reqwest::{header::RETRY_AFTER, Client, Response, StatusCode};
+use slog::{error, Logger};
+use tokio::time::sleep;
+
+use crate::Cli;
+
+pub const DEFAULT_ORACLE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long each request to the oracle may take and how failed requests are retried, shared by the
+/// parquet uploads and the active station lookups
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct OracleRetry {
+    /// Number of times a failed request is retried before giving up for this run
+    pub max_retries: usize,
+    /// Wait before the first retry, doubles after each failed attempt
+    pub initial_delay: Duration,
+    /// Length of time to wait on each attempt, covers connecting and reading the response
+    pub timeout: Duration,
+}
+
+impl OracleRetry {
+    pub fn from_cli(cli: &Cli) -> Self {
+        OracleRetry {
+            max_retries: cli.upload_retries.unwrap_or(3),
+            initial_delay: Duration::from_secs(cli.upload_retry_delay.unwrap_or(5)),
+            timeout: cli
+                .oracle_timeout
+                .map(Duration::from_secs)
+                .unwrap_or(DEFAULT_ORACLE_TIMEOUT),
+        }
+    }
+
+    pub fn delay(&self, retry: usize) -> Duration {
+        self.initial_delay
+            .saturating_mul(2_u32.saturating_pow(retry.saturating_sub(1) as u32))
+    }
+
+    pub fn client(&self) -> Result<Client, Error> {
+        Client::builder()
+            .connect_timeout(self.timeout)
+            .timeout(self.timeout)
+            .build()
+            .map_err(|e| anyhow!("error building oracle client: {}", e))
+    }
+}
+
+/// A failed request to the oracle, only `Retryable` failures are tried again
+#[derive(Debug)]
+pub enum OracleError {
+    /// The oracle couldn't be reached, timed out, is overloaded (429) or unavailable (5xx), waits
+    /// `retry_after` instead of the backoff when the oracle sent a `Retry-After` header
+    Retryable {
+        error: Error,
+        retry_after: Option<Duration>,
+    },
+    /// The oracle rejected the request (4xx), sending it again gets the same answer
+    Rejected(Error),
+}
+
+impl OracleError {
+    pub fn retryable(error: Error) -> Self {
+        OracleError::Retryable {
+            error,
+            retry_after: None,
+        }
+    }
+}
+
+impl Display for OracleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OracleError::Retryable { error, .. } => write!(f, "{}", error),
+            OracleError::Rejected(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl From<reqwest::Error> for OracleError {
+    fn from(err: reqwest::Error) -> Self {
+        let error = if err.is_timeout() {
+            anyhow!("request to the oracle timed out: {}", err)
+        } else {
+            anyhow!("error sending request to the oracle: {}", err)
+        };
+        // Bad urls and bodies the oracle's response can't be decoded into fail the same way every time
+        if err.is_builder() || err.is_decode() {
+            return OracleError::Rejected(error);
+        }
+        OracleError::retryable(error)
+    }
+}
+
+/// Passes successful responses through, 429 and 5xx responses are retryable and the other statuses
+/// are rejected
+pub fn check_response(response: Response) -> Result<Response, OracleError> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+    let error = anyhow!("oracle responded to {} with {}", response.url(), status);
+    if status == StatusCode::TOO_MANY_REQUESTS || status.is_server_error() {
+        let retry_after = response
+            .headers()
+            .get(RETRY_AFTER)
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.trim().parse::<u64>().ok())
+            .map(Duration::from_secs);
+        return Err(OracleError::Retryable { error, retry_after });
+    }
+    Err(OracleError::Rejected(error))
+}
+
+/// Runs `request` until it succeeds, is rejected or `retry.max_retries` retries have failed,
+/// waiting the backoff between attempts
+pub async fn with_retry<T, F, Fut>(
+    logger: &Logger,
+    retry: OracleRetry,
+    action: &str,
+    mut request: F,
+) -> Result<T, Error>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<T, OracleError>>,
+{
+    let mut retries = 0;
+    loop {
+        match request().await {
+            Ok(value) => return Ok(value),
+            Err(OracleError::Rejected(err)) => {
+                return Err(anyhow!("error {}, not retrying: {}", action, err))
+            }
+            Err(OracleError::Retryable { error, .. }) if retries >= retry.max_retries => {
+                return Err(anyhow!(
+                    "error {} after {} attempts: {}",
+                    action,
+                    retries + 1,
+                    error
+                ))
+            }
+            Err(OracleError::Retryable { error, retry_after }) => {
+                retries += 1;
+                let delay = retry_after.unwrap_or_else(|| retry.delay(retries));
+                error!(
+                    logger,
+                    "error {}, retrying in {:?}: {}", action, delay, error
+                );
+                sleep(delay).await;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_response, with_retry, OracleRetry};
+    use crate::test_server::{spawn_server, MockServer, Response};
+    use slog::{o, Discard, Logger};
+    use std::{
+        future::{pending, ready},
+        time::{Duration, Instant},
+    };
+
+    /// Oracle stand-in answering each request with the next of `responses` (status line and extra
+    /// headers), repeating the last one once they run out
+    async fn spawn_oracle(responses: Vec<&'static str>) -> (String, MockServer) {
+        let oracle = spawn_server(move |attempt, _| {
+            ready(Response::new(
+                responses[attempt.min(responses.len() - 1)],
+                "[]",
+            ))
+        })
+        .await;
+        (format!("{}/stations/active", oracle.url()), oracle)
+    }
+
+    fn retry() -> OracleRetry {
+        OracleRetry {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    async fn get(logger: &Logger, retry: OracleRetry, url: &str) -> Result<String, anyhow::Error> {
+        let client = &retry.client().unwrap();
+        with_retry(logger, retry, "getting active stations", || async move {
+            let response = check_response(client.get(url).send().await?)?;
+            Ok(response.text().await?)
+        })
+        .await
+    }
+
+    #[test]
+    fn oracle_retry_delay_doubles() {
+        let retry = OracleRetry {
+            initial_delay: Duration::from_secs(5),
+            ..retry()
+        };
+        assert_eq!(retry.delay(1), Duration::from_secs(5));
+        assert_eq!(retry.delay(2), Duration::from_secs(10));
+        assert_eq!(retry.delay(3), Duration::from_secs(20));
+    }
+
+    #[tokio::test]
+    async fn retries_after_too_many_requests() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) =
+            spawn_oracle(vec!["429 Too Many Requests\r\nretry-after: 0", "200 OK"]).await;
+
+        let body = get(&logger, retry(), &url).await.unwrap();
+
+        assert_eq!(body, "[]");
+        assert_eq!(oracle.requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn retries_unavailable_oracle_until_retries_run_out() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_oracle(vec!["503 Service Unavailable"]).await;
+
+        assert!(get(&logger, retry(), &url).await.is_err());
+        assert_eq!(oracle.requests(), 4);
+    }
+
+    #[tokio::test]
+    async fn rejected_requests_are_not_retried() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_oracle(vec!["400 Bad Request", "200 OK"]).await;
+
+        assert!(get(&logger, retry(), &url).await.is_err());
+        assert_eq!(oracle.requests(), 1);
+    }
+
+    #[tokio::test]
+    async fn timed_out_requests_are_retried() {
+        let logger = Logger::root(Discard, o!());
+        // Reads requests but never answers them
+        let oracle = spawn_server(|_, _| pending()).await;
+        let url = format!("{}/stations/active", oracle.url());
+        let retry = OracleRetry {
+            max_retries: 1,
+            timeout: Duration::from_millis(100),
+            ..retry()
+        };
+
+        let start = Instant::now();
+        assert!(get(&logger, retry, &url).await.is_err());
+        assert!(start.elapsed() < Duration::from_secs(2));
+        assert_eq!(oracle.requests(), 2);
+    }
+}
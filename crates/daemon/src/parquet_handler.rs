@@ -1,28 +1,98 @@
-use std::{fs::File, sync::Arc};
+use std::{
+    fmt::{self, Display},
+    fs::File,
+    sync::Arc,
+};
 
 use anyhow::{anyhow, Error};
 use parquet::{
-    file::{properties::WriterProperties, writer::SerializedFileWriter},
+    basic::{Compression, GzipLevel, ZstdLevel},
+    file::{
+        properties::WriterProperties,
+        reader::{FileReader, SerializedFileReader},
+        writer::SerializedFileWriter,
+    },
     record::RecordWriter,
+    schema::types::{SchemaDescriptor, Type},
 };
 use reqwest::{multipart, Body, Client};
-use slog::{error, info, Logger};
+use slog::{info, Logger};
 use tokio::fs::File as TokioFile;
 use tokio_util::codec::{BytesCodec, FramedRead};
 
 use crate::{
-    create_forecast_schema, create_observation_schema, get_full_path, Cli, Forecast, Observation,
+    check_response, create_forecast_schema, create_observation_schema, get_full_path, with_retry,
+    Cli, Forecast, Observation, OracleError, OracleRetry,
 };
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ParquetCompression {
+    #[default]
+    None,
+    Snappy,
+    Zstd,
+    Gzip,
+}
+
+impl ParquetCompression {
+    pub fn all() -> Vec<ParquetCompression> {
+        vec![
+            ParquetCompression::None,
+            ParquetCompression::Snappy,
+            ParquetCompression::Zstd,
+            ParquetCompression::Gzip,
+        ]
+    }
+
+    pub fn codec(&self) -> Compression {
+        match self {
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+        }
+    }
+
+    fn writer_properties(&self) -> WriterProperties {
+        WriterProperties::builder()
+            .set_compression(self.codec())
+            .build()
+    }
+}
+
+impl Display for ParquetCompression {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ParquetCompression::None => write!(f, "none"),
+            ParquetCompression::Snappy => write!(f, "snappy"),
+            ParquetCompression::Zstd => write!(f, "zstd"),
+            ParquetCompression::Gzip => write!(f, "gzip"),
+        }
+    }
+}
+
+impl TryFrom<&str> for ParquetCompression {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        ParquetCompression::all()
+            .into_iter()
+            .find(|compression| compression.to_string() == s.trim().to_lowercase())
+            .ok_or_else(|| anyhow!("invalid parquet compression: {}", s))
+    }
+}
+
 pub fn save_observations(
     observations: Vec<Observation>,
     root_path: &str,
     file_name: String,
-) -> String {
+    compression: ParquetCompression,
+) -> Result<String, Error> {
     let full_name = format!("{}/{}.parquet", root_path, file_name);
+    let expected_rows = observations.len();
 
     let file = File::create(full_name.clone()).unwrap();
-    let props = WriterProperties::builder().build();
+    let props = compression.writer_properties();
     let mut writer =
         SerializedFileWriter::new(file, Arc::new(create_observation_schema()), Arc::new(props))
             .unwrap();
@@ -34,14 +104,21 @@ pub fn save_observations(
         .unwrap();
     row_group.close().unwrap();
     writer.close().unwrap();
-    full_name
+    validate_parquet_file(&full_name, expected_rows, &create_observation_schema())?;
+    Ok(full_name)
 }
 
-pub fn save_forecasts(forecast: Vec<Forecast>, root_path: &str, file_name: String) -> String {
+pub fn save_forecasts(
+    forecast: Vec<Forecast>,
+    root_path: &str,
+    file_name: String,
+    compression: ParquetCompression,
+) -> Result<String, Error> {
     let full_name = format!("{}/{}.parquet", root_path, file_name);
+    let expected_rows = forecast.len();
     let file = File::create(full_name.clone()).unwrap();
 
-    let props = WriterProperties::builder().build();
+    let props = compression.writer_properties();
     let mut writer =
         SerializedFileWriter::new(file, Arc::new(create_forecast_schema()), Arc::new(props))
             .unwrap();
@@ -53,64 +130,105 @@ pub fn save_forecasts(forecast: Vec<Forecast>, root_path: &str, file_name: Strin
         .unwrap();
     row_group.close().unwrap();
     writer.close().unwrap();
-    full_name
+    validate_parquet_file(&full_name, expected_rows, &create_forecast_schema())?;
+    Ok(full_name)
 }
 
-pub async fn send_parquet_files(
+/// Reopens a written file and reads every row back, erroring if the row count or the columns
+/// don't match what was written so a corrupt file is never uploaded
+pub fn validate_parquet_file(
+    file_path: &str,
+    expected_rows: usize,
+    expected_schema: &Type,
+) -> Result<(), Error> {
+    let file = File::open(file_path).map_err(|e| anyhow!("error opening {}: {}", file_path, e))?;
+    let reader = SerializedFileReader::new(file)
+        .map_err(|e| anyhow!("invalid parquet file {}: {}", file_path, e))?;
+
+    let columns = |schema: &SchemaDescriptor| {
+        schema
+            .columns()
+            .iter()
+            .map(|column| (column.path().string(), column.physical_type()))
+            .collect::<Vec<_>>()
+    };
+    let expected_columns = columns(&SchemaDescriptor::new(Arc::new(expected_schema.clone())));
+    if columns(reader.metadata().file_metadata().schema_descr()) != expected_columns {
+        return Err(anyhow!(
+            "schema of {} doesn't match the {} schema",
+            file_path,
+            expected_schema.name()
+        ));
+    }
+
+    let mut rows = 0;
+    for row in reader.get_row_iter(None)? {
+        row.map_err(|e| anyhow!("error reading row from {}: {}", file_path, e))?;
+        rows += 1;
+    }
+    if rows != expected_rows {
+        return Err(anyhow!(
+            "expected {} rows in {} but read {}",
+            expected_rows,
+            file_path,
+            rows
+        ));
+    }
+    Ok(())
+}
+
+pub async fn send_parquet_file(
     cli: &Cli,
     logger: &Logger,
-    observation_relative_file_path: String,
-    forecast_relative_file_path_file: String,
+    relative_file_path: String,
 ) -> Result<(), Error> {
     let base_url = cli
         .base_url
         .clone()
         .unwrap_or(String::from("http://localhost:9100"));
-    let observation_filename = observation_relative_file_path.split('/').last().unwrap();
-    let forecast_filename = forecast_relative_file_path_file.split('/').last().unwrap();
+    let filename = relative_file_path.split('/').last().unwrap();
+    let full_path = get_full_path(relative_file_path.clone());
+    let url = format!("{}/file/{}", base_url, filename);
 
-    let observation_full_path = get_full_path(observation_relative_file_path.clone());
-    let forecast_full_path = get_full_path(forecast_relative_file_path_file.clone());
-
-    let url_observ = format!("{}/file/{}", base_url, observation_filename);
-    let url_forcast = format!("{}/file/{}", base_url, forecast_filename);
-
-    match send_file_to_endpoint(
+    send_file_with_retry(
         logger,
-        &observation_full_path,
-        observation_filename,
-        &url_observ,
+        &full_path,
+        filename,
+        &url,
+        OracleRetry::from_cli(cli),
     )
     .await
-    {
-        Ok(_) => {}
-        Err(e) => {
-            error!(logger, "failed to upload observations: {}", e)
-        }
-    }
-    match send_file_to_endpoint(logger, &forecast_full_path, forecast_filename, &url_forcast).await
-    {
-        Ok(_) => {}
-        Err(e) => {
-            error!(logger, "failed to upload forecasts: {}", e)
-        }
-    }
-    Ok(())
 }
 
-async fn send_file_to_endpoint(
+/// Uploads the file, retrying with backoff when the oracle can't be reached, times out or is unavailable,
+/// a rejected upload (4xx) isn't retried. The local file is never removed here so a failed upload can be
+/// re-sent later
+pub async fn send_file_with_retry(
     logger: &Logger,
     file_path: &str,
     file_name: &str,
     endpoint_url: &str,
-) -> Result<(), anyhow::Error> {
-    // Create a reqwest client.
-    let client = Client::new();
+    retry: OracleRetry,
+) -> Result<(), Error> {
+    let client = &retry.client()?;
+    with_retry(logger, retry, &format!("uploading {}", file_name), || {
+        send_file_to_endpoint(logger, client, file_path, file_name, endpoint_url)
+    })
+    .await
+    .map_err(|err| anyhow!("{}, file kept at {} to be re-sent", err, file_path))
+}
 
+async fn send_file_to_endpoint(
+    logger: &Logger,
+    client: &Client,
+    file_path: &str,
+    file_name: &str,
+    endpoint_url: &str,
+) -> Result<(), OracleError> {
     // Open the file for reading.
     let file = TokioFile::open(file_path)
         .await
-        .map_err(|e| anyhow!("error opening file to upload: {}", e))?;
+        .map_err(|e| OracleError::Rejected(anyhow!("error opening file to upload: {}", e)))?;
 
     // read file body stream
     let stream = FramedRead::new(file, BytesCodec::new());
@@ -125,23 +243,288 @@ async fn send_file_to_endpoint(
 
     // Create a request builder for a POST request to the endpoint.
     info!(logger, "sending file to endpoint: {}", endpoint_url);
-    let response = client
-        .post(endpoint_url)
-        .multipart(form)
-        .send()
-        .await
-        .map_err(|e| anyhow!("error sending file to api: {}", e))?;
-
-    // Check the response status.
-    if response.status().is_success() {
-        info!(logger, "file successfully uploaded.");
-    } else {
-        error!(
-            logger,
-            "failed to upload the file. status code: {:?}",
-            response.status()
+    check_response(client.post(endpoint_url).multipart(form).send().await?)?;
+    info!(logger, "file successfully uploaded.");
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        save_forecasts, save_observations, send_file_with_retry, validate_parquet_file,
+        ParquetCompression,
+    };
+    use crate::test_server::{spawn_server, MockServer, Response};
+    use crate::OracleRetry;
+    use crate::{create_forecast_schema, create_observation_schema, Forecast, Observation};
+    use parquet::{
+        file::reader::{FileReader, SerializedFileReader},
+        record::Row,
+    };
+    use slog::{o, Discard, Logger};
+    use std::{
+        fs::{self, File},
+        path::Path,
+        time::Duration,
+    };
+
+    fn forecasts() -> Vec<Forecast> {
+        (0..50)
+            .map(|hour| Forecast {
+                station_id: String::from("KLWV"),
+                station_name: String::from("Lawrenceville-Vincennes International Airport"),
+                latitude: 38.76,
+                longitude: -87.61,
+                generated_at: String::from("2024-08-12T09:00:00Z"),
+                begin_time: format!("2024-08-12T{:02}:00:00Z", hour % 24),
+                end_time: format!("2024-08-12T{:02}:00:00Z", (hour + 3) % 24),
+                max_temp: Some(80 + hour % 10),
+                min_temp: None,
+                temperature_unit_code: Some(String::from("fahrenheit")),
+                wind_speed: Some(hour % 15),
+                wind_speed_unit_code: Some(String::from("knots")),
+                wind_direction: None,
+                wind_direction_unit_code: None,
+                relative_humidity_max: Some(60),
+                relative_humidity_min: Some(40),
+                relative_humidity_unit_code: Some(String::from("percent")),
+                liquid_precipitation_amt: Some(0.25),
+                liquid_precipitation_unit_code: Some(String::from("inches")),
+                twelve_hour_probability_of_precipitation: None,
+                twelve_hour_probability_of_precipitation_unit_code: None,
+            })
+            .collect()
+    }
+
+    fn observations() -> Vec<Observation> {
+        (0..50)
+            .map(|minute| Observation {
+                station_id: String::from("KLWV"),
+                station_name: String::from("Lawrenceville-Vincennes International Airport"),
+                latitude: 38.76,
+                longitude: -87.61,
+                generated_at: format!("2024-08-12T12:{:02}:00Z", minute),
+                temperature_value: Some(25.5),
+                temperature_unit_code: String::from("celcius"),
+                wind_direction: Some(180),
+                wind_direction_unit_code: String::from("degrees true"),
+                wind_speed: Some(minute % 12),
+                wind_speed_unit_code: String::from("knots"),
+                dewpoint_value: None,
+                dewpoint_unit_code: String::from("celcius"),
+            })
+            .collect()
+    }
+
+    fn read_rows(file_path: &str) -> Vec<Row> {
+        let reader = SerializedFileReader::new(File::open(file_path).unwrap()).unwrap();
+        reader
+            .get_row_iter(None)
+            .unwrap()
+            .map(|row| row.unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn can_parse_compression_options() {
+        for compression in ParquetCompression::all() {
+            assert_eq!(
+                ParquetCompression::try_from(compression.to_string().as_str()).unwrap(),
+                compression
+            );
+        }
+        assert_eq!(
+            ParquetCompression::try_from("ZSTD").unwrap(),
+            ParquetCompression::Zstd
         );
+        assert!(ParquetCompression::try_from("lz4").is_err());
     }
 
-    Ok(())
+    #[test]
+    fn compressed_files_read_back_identically() {
+        let root_path =
+            std::env::temp_dir().join(format!("parquet_compression_{}", std::process::id()));
+        fs::create_dir_all(&root_path).unwrap();
+        let root_path = root_path.to_str().unwrap();
+
+        let expected_forecasts = read_rows(
+            &save_forecasts(
+                forecasts(),
+                root_path,
+                String::from("forecasts_expected"),
+                ParquetCompression::None,
+            )
+            .unwrap(),
+        );
+        let expected_observations = read_rows(
+            &save_observations(
+                observations(),
+                root_path,
+                String::from("observations_expected"),
+                ParquetCompression::None,
+            )
+            .unwrap(),
+        );
+        assert_eq!(expected_forecasts.len(), 50);
+        assert_eq!(expected_observations.len(), 50);
+
+        for compression in ParquetCompression::all() {
+            let forecast_path = save_forecasts(
+                forecasts(),
+                root_path,
+                format!("forecasts_{}", compression),
+                compression,
+            )
+            .unwrap();
+            let reader = SerializedFileReader::new(File::open(&forecast_path).unwrap()).unwrap();
+            let column = reader.metadata().row_group(0).column(0);
+            assert_eq!(column.compression(), compression.codec());
+            assert_eq!(read_rows(&forecast_path), expected_forecasts);
+
+            let observation_path = save_observations(
+                observations(),
+                root_path,
+                format!("observations_{}", compression),
+                compression,
+            )
+            .unwrap();
+            assert_eq!(read_rows(&observation_path), expected_observations);
+        }
+
+        fs::remove_dir_all(root_path).unwrap();
+    }
+
+    #[test]
+    fn truncated_file_fails_validation() {
+        let root_path =
+            std::env::temp_dir().join(format!("parquet_validation_{}", std::process::id()));
+        fs::create_dir_all(&root_path).unwrap();
+        let root_path = root_path.to_str().unwrap();
+        let forecast_path = save_forecasts(
+            forecasts(),
+            root_path,
+            String::from("forecasts"),
+            ParquetCompression::None,
+        )
+        .unwrap();
+        validate_parquet_file(&forecast_path, 50, &create_forecast_schema()).unwrap();
+
+        let written = fs::read(&forecast_path).unwrap();
+        fs::write(&forecast_path, &written[..written.len() / 2]).unwrap();
+
+        assert!(validate_parquet_file(&forecast_path, 50, &create_forecast_schema()).is_err());
+        fs::remove_dir_all(root_path).unwrap();
+    }
+
+    #[test]
+    fn mismatched_rows_or_schema_fail_validation() {
+        let root_path =
+            std::env::temp_dir().join(format!("parquet_mismatch_{}", std::process::id()));
+        fs::create_dir_all(&root_path).unwrap();
+        let root_path = root_path.to_str().unwrap();
+        let observation_path = save_observations(
+            observations(),
+            root_path,
+            String::from("observations"),
+            ParquetCompression::None,
+        )
+        .unwrap();
+
+        assert!(
+            validate_parquet_file(&observation_path, 49, &create_observation_schema()).is_err()
+        );
+        assert!(validate_parquet_file(&observation_path, 50, &create_forecast_schema()).is_err());
+        fs::remove_dir_all(root_path).unwrap();
+    }
+
+    /// Oracle stand-in that fails the first `failures` uploads with `failure_status`
+    async fn spawn_flaky_oracle(
+        failures: usize,
+        failure_status: &'static str,
+    ) -> (String, MockServer) {
+        let oracle = spawn_server(move |upload, _| async move {
+            if upload < failures {
+                Response::new(failure_status, "")
+            } else {
+                Response::ok("")
+            }
+        })
+        .await;
+        (format!("{}/file/forecasts.parquet", oracle.url()), oracle)
+    }
+
+    fn upload_file(name: &str) -> String {
+        let root_path = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        fs::create_dir_all(&root_path).unwrap();
+        let file_path = root_path.join("forecasts.parquet");
+        fs::write(&file_path, b"not really parquet").unwrap();
+        file_path.to_str().unwrap().to_string()
+    }
+
+    fn retry() -> OracleRetry {
+        OracleRetry {
+            max_retries: 3,
+            initial_delay: Duration::from_millis(1),
+            timeout: Duration::from_secs(5),
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_upload_until_oracle_accepts_it() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_flaky_oracle(2, "503 Service Unavailable").await;
+        let file_path = upload_file("upload_retry_success");
+
+        send_file_with_retry(&logger, &file_path, "forecasts.parquet", &url, retry())
+            .await
+            .unwrap();
+        assert_eq!(oracle.requests(), 3);
+
+        fs::remove_dir_all(Path::new(&file_path).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn keeps_file_after_exhausting_upload_retries() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_flaky_oracle(usize::MAX, "503 Service Unavailable").await;
+        let file_path = upload_file("upload_retry_failure");
+
+        let res =
+            send_file_with_retry(&logger, &file_path, "forecasts.parquet", &url, retry()).await;
+        assert!(res.is_err());
+        assert_eq!(oracle.requests(), 4);
+        assert!(Path::new(&file_path).exists());
+
+        fs::remove_dir_all(Path::new(&file_path).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn retries_upload_while_oracle_is_overloaded() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_flaky_oracle(1, "429 Too Many Requests").await;
+        let file_path = upload_file("upload_retry_overloaded");
+
+        send_file_with_retry(&logger, &file_path, "forecasts.parquet", &url, retry())
+            .await
+            .unwrap();
+        assert_eq!(oracle.requests(), 2);
+
+        fs::remove_dir_all(Path::new(&file_path).parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn rejected_upload_is_not_retried() {
+        let logger = Logger::root(Discard, o!());
+        let (url, oracle) = spawn_flaky_oracle(usize::MAX, "400 Bad Request").await;
+        let file_path = upload_file("upload_rejected");
+
+        let res =
+            send_file_with_retry(&logger, &file_path, "forecasts.parquet", &url, retry()).await;
+        assert!(res.is_err());
+        assert_eq!(oracle.requests(), 1);
+        assert!(Path::new(&file_path).exists());
+
+        fs::remove_dir_all(Path::new(&file_path).parent().unwrap()).unwrap();
+    }
 }
@@ -0,0 +1,282 @@
+use anyhow::Error;
+use slog::{error, info, warn, Logger};
+use std::{
+    fs,
+    future::Future,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+};
+
+/// Name of the file the queue is kept in, inside the data dir
+pub const PENDING_UPLOADS_FILE: &str = "pending_uploads.json";
+
+/// Parquet files that failed to upload, they stay on disk and are re-sent at the start of the next run
+#[derive(Clone, Default)]
+pub struct PendingUploads {
+    queue: Arc<Mutex<Queue>>,
+    queue_file: Option<Arc<QueueFile>>,
+}
+
+#[derive(Default)]
+struct Queue {
+    files: Vec<String>,
+    // Taken out of `files` by a flush but not delivered yet, still saved so a crash mid flush doesn't lose them
+    sending: Vec<String>,
+}
+
+struct QueueFile {
+    path: PathBuf,
+    logger: Logger,
+}
+
+impl PendingUploads {
+    /// Queue that only lives as long as the process
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue that is written to `path` on every change, so files that were never uploaded are still
+    /// re-sent after a restart. Queued files that have since been removed from disk are dropped
+    pub fn load(logger: &Logger, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        let files = match read_queue(&path) {
+            Ok(files) => files,
+            Err(err) => {
+                error!(
+                    logger,
+                    "error reading pending uploads from {}, starting with an empty queue: {}",
+                    path.display(),
+                    err
+                );
+                vec![]
+            }
+        };
+        let (files, missing): (Vec<String>, Vec<String>) =
+            files.into_iter().partition(|file| Path::new(file).exists());
+        for file in missing {
+            warn!(
+                logger,
+                "dropping pending upload no longer on disk: {}", file
+            );
+        }
+        if !files.is_empty() {
+            info!(
+                logger,
+                "loaded {} pending uploads from {}",
+                files.len(),
+                path.display()
+            );
+        }
+        let pending = Self {
+            queue: Arc::new(Mutex::new(Queue {
+                files,
+                sending: vec![],
+            })),
+            queue_file: Some(Arc::new(QueueFile {
+                path,
+                logger: logger.clone(),
+            })),
+        };
+        pending.save(&pending.queue.lock().unwrap());
+        pending
+    }
+
+    pub fn add(&self, relative_file_path: String) {
+        let mut queue = self.queue.lock().unwrap();
+        queue.sending.retain(|file| *file != relative_file_path);
+        if !queue.files.contains(&relative_file_path) {
+            queue.files.push(relative_file_path);
+        }
+        self.save(&queue);
+    }
+
+    pub fn files(&self) -> Vec<String> {
+        self.queue.lock().unwrap().files.clone()
+    }
+
+    /// Sends the file, queueing it to be re-sent on the next flush if the upload fails
+    pub async fn send_or_queue<F, Fut>(
+        &self,
+        relative_file_path: String,
+        send: F,
+    ) -> Result<(), Error>
+    where
+        F: FnOnce(String) -> Fut,
+        Fut: Future<Output = Result<(), Error>>,
+    {
+        let res = send(relative_file_path.clone()).await;
+        if res.is_err() {
+            self.add(relative_file_path);
+        }
+        res
+    }
+
+    /// Re-sends every pending file, files that fail again stay queued for the next flush.
+    /// Returns the number of files that were delivered
+    pub async fn flush<F, Fut>(&self, logger: &Logger, mut send: F) -> usize
+    where
+        F: FnMut(String) -> Fut,
+        Fut: Future<Output = Result<(), Error>>,
+    {
+        // Taken out of the queue so the other pipeline doesn't send the same file at the same time
+        let pending = {
+            let mut queue = self.queue.lock().unwrap();
+            let pending = std::mem::take(&mut queue.files);
+            queue.sending.extend(pending.iter().cloned());
+            pending
+        };
+        let mut delivered = 0;
+        for relative_file_path in pending {
+            match send(relative_file_path.clone()).await {
+                Ok(()) => {
+                    info!(logger, "re-sent pending upload: {}", relative_file_path);
+                    delivered += 1;
+                    let mut queue = self.queue.lock().unwrap();
+                    queue.sending.retain(|file| *file != relative_file_path);
+                    self.save(&queue);
+                }
+                Err(err) => {
+                    error!(
+                        logger,
+                        "error re-sending pending upload {}: {}", relative_file_path, err
+                    );
+                    self.add(relative_file_path);
+                }
+            }
+        }
+        delivered
+    }
+
+    // Called with the queue locked so concurrent saves land in the same order as the changes
+    fn save(&self, queue: &Queue) {
+        let Some(queue_file) = &self.queue_file else {
+            return;
+        };
+        let files: Vec<&String> = queue.files.iter().chain(&queue.sending).collect();
+        if let Err(err) = write_queue(&queue_file.path, &files) {
+            error!(
+                queue_file.logger,
+                "error saving pending uploads to {}: {}",
+                queue_file.path.display(),
+                err
+            );
+        }
+    }
+}
+
+fn read_queue(path: &Path) -> Result<Vec<String>, Error> {
+    if !path.exists() {
+        return Ok(vec![]);
+    }
+    Ok(serde_json::from_slice(&fs::read(path)?)?)
+}
+
+// Written next to the queue file and renamed over it, so a crash mid write never leaves a truncated queue
+fn write_queue(path: &Path, files: &[&String]) -> Result<(), Error> {
+    let tmp_path = path.with_extension("json.tmp");
+    fs::write(&tmp_path, serde_json::to_vec(files)?)?;
+    fs::rename(&tmp_path, path)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PendingUploads, PENDING_UPLOADS_FILE};
+    use anyhow::anyhow;
+    use slog::{o, Discard, Logger};
+    use std::{
+        fs,
+        sync::atomic::{AtomicUsize, Ordering},
+    };
+
+    #[tokio::test]
+    async fn resends_failed_upload_on_next_cycle() {
+        let logger = Logger::root(Discard, o!());
+        let pending = PendingUploads::new();
+        let attempts = AtomicUsize::new(0);
+        // oracle is down for the first upload and back up for the next cycle
+        let send = |_: String| {
+            let attempt = attempts.fetch_add(1, Ordering::SeqCst);
+            async move {
+                if attempt == 0 {
+                    Err(anyhow!("oracle unavailable"))
+                } else {
+                    Ok(())
+                }
+            }
+        };
+
+        let file = String::from("./data/2024-08-12/forecasts_2024-08-12T12:00:00Z.parquet");
+        assert!(pending.send_or_queue(file.clone(), send).await.is_err());
+        assert_eq!(pending.files(), vec![file]);
+
+        assert_eq!(pending.flush(&logger, send).await, 1);
+        assert!(pending.files().is_empty());
+        assert_eq!(attempts.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn keeps_files_that_fail_again() {
+        let logger = Logger::root(Discard, o!());
+        let pending = PendingUploads::new();
+        pending.add(String::from("forecasts.parquet"));
+        pending.add(String::from("observations.parquet"));
+        pending.add(String::from("forecasts.parquet"));
+
+        let delivered = pending
+            .flush(&logger, |file: String| async move {
+                if file.starts_with("forecasts") {
+                    Ok(())
+                } else {
+                    Err(anyhow!("oracle unavailable"))
+                }
+            })
+            .await;
+        assert_eq!(delivered, 1);
+        assert_eq!(pending.files(), vec![String::from("observations.parquet")]);
+    }
+
+    #[tokio::test]
+    async fn reloads_the_queue_after_a_restart() {
+        let logger = Logger::root(Discard, o!());
+        let dir = std::env::temp_dir().join(format!("pending_uploads_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let queue_file = dir.join(PENDING_UPLOADS_FILE);
+        let forecasts = dir.join("forecasts.parquet").display().to_string();
+        let observations = dir.join("observations.parquet").display().to_string();
+        let removed = dir.join("removed.parquet").display().to_string();
+        for file in [&forecasts, &observations, &removed] {
+            fs::write(file, b"parquet").unwrap();
+        }
+
+        let pending = PendingUploads::load(&logger, &queue_file);
+        pending.add(forecasts.clone());
+        pending.add(observations.clone());
+        pending.add(removed.clone());
+        fs::remove_file(&removed).unwrap();
+
+        let restarted = PendingUploads::load(&logger, &queue_file);
+        assert_eq!(
+            restarted.files(),
+            vec![forecasts.clone(), observations.clone()]
+        );
+
+        let delivered = restarted
+            .flush(&logger, |file: String| {
+                let failed = file == observations;
+                async move {
+                    if failed {
+                        Err(anyhow!("oracle unavailable"))
+                    } else {
+                        Ok(())
+                    }
+                }
+            })
+            .await;
+        assert_eq!(delivered, 1);
+        let restarted = PendingUploads::load(&logger, &queue_file);
+        assert_eq!(restarted.files(), vec![observations]);
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
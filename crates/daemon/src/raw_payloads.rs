@@ -0,0 +1,120 @@
+use crate::Cli;
+use anyhow::{anyhow, Error};
+use std::{
+    fmt::{self, Display},
+    fs,
+    path::{Path, PathBuf},
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PayloadKind {
+    /// NDFD XML forecasts
+    Forecast,
+    /// api.weather.gov point and gridpoint responses
+    ApiForecast,
+    /// Metar observations
+    Observations,
+    /// Station list
+    Stations,
+}
+
+impl PayloadKind {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            PayloadKind::ApiForecast => "json",
+            PayloadKind::Forecast | PayloadKind::Observations | PayloadKind::Stations => "xml",
+        }
+    }
+}
+
+impl Display for PayloadKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PayloadKind::Forecast => write!(f, "forecast"),
+            PayloadKind::ApiForecast => write!(f, "api_forecast"),
+            PayloadKind::Observations => write!(f, "observations"),
+            PayloadKind::Stations => write!(f, "stations"),
+        }
+    }
+}
+
+/// Where raw NOAA responses are written to for debugging, responses that failed to parse are always saved
+#[derive(Debug, Clone)]
+pub struct RawPayloads {
+    dir: PathBuf,
+    save_all: bool,
+}
+
+impl RawPayloads {
+    pub fn new(dir: impl Into<PathBuf>, save_all: bool) -> Self {
+        Self {
+            dir: dir.into(),
+            save_all,
+        }
+    }
+
+    /// Saving raw payloads is off unless a directory is provided
+    pub fn from_cli(cli: &Cli) -> Option<Self> {
+        cli.save_raw_payloads
+            .as_ref()
+            .map(|dir| RawPayloads::new(dir, cli.save_all_raw_payloads.unwrap_or(false)))
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn should_save(&self, failed: bool) -> bool {
+        failed || self.save_all
+    }
+
+    /// Writes the payload to `{dir}/{kind}_{failed|ok}_{timestamp}.{extension}`, returning the file's path
+    pub fn save(&self, kind: PayloadKind, payload: &str, failed: bool) -> Result<PathBuf, Error> {
+        fs::create_dir_all(&self.dir)
+            .map_err(|e| anyhow!("error creating {}: {}", self.dir.display(), e))?;
+        let file_path = self.dir.join(format!(
+            "{}_{}_{}.{}",
+            kind,
+            if failed { "failed" } else { "ok" },
+            OffsetDateTime::now_utc().format(&Rfc3339)?,
+            kind.extension()
+        ));
+        fs::write(&file_path, payload)
+            .map_err(|e| anyhow!("error writing {}: {}", file_path.display(), e))?;
+        Ok(file_path)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{PayloadKind, RawPayloads};
+    use std::fs;
+
+    #[test]
+    fn only_saves_parsed_payloads_when_saving_all() {
+        let failed_only = RawPayloads::new("./unused", false);
+        assert!(failed_only.should_save(true));
+        assert!(!failed_only.should_save(false));
+
+        let save_all = RawPayloads::new("./unused", true);
+        assert!(save_all.should_save(true));
+        assert!(save_all.should_save(false));
+    }
+
+    #[test]
+    fn writes_payload_with_timestamped_name() {
+        let dir = std::env::temp_dir().join(format!("raw_payloads_{}", std::process::id()));
+        let raw_payloads = RawPayloads::new(&dir, false);
+
+        let file_path = raw_payloads
+            .save(PayloadKind::ApiForecast, "{\"properties\":", true)
+            .unwrap();
+
+        let file_name = file_path.file_name().unwrap().to_str().unwrap();
+        assert!(file_name.starts_with("api_forecast_failed_"));
+        assert!(file_name.ends_with(".json"));
+        assert_eq!(fs::read_to_string(&file_path).unwrap(), "{\"properties\":");
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
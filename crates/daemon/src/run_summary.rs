@@ -0,0 +1,293 @@
+use anyhow::Error;
+use serde::{Serialize, Serializer};
+use slog::{info, Logger};
+use std::{
+    fmt::{self, Display},
+    fs::{self, OpenOptions},
+    io::Write,
+    time::{Duration, Instant},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum UploadStatus {
+    /// The run stopped before there was a file to upload
+    NotAttempted,
+    Sent,
+    /// The upload failed and the file was queued to be re-sent on the next run
+    Queued,
+}
+
+impl Display for UploadStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            UploadStatus::NotAttempted => write!(f, "not_attempted"),
+            UploadStatus::Sent => write!(f, "sent"),
+            UploadStatus::Queued => write!(f, "queued"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct WrittenFile {
+    pub path: String,
+    pub bytes: u64,
+}
+
+/// How long each chunk of a run's NOAA requests took, one duration per batched request that completed,
+/// failed requests included since slow failures are usually what needs tuning
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FetchTimings {
+    durations: Vec<Duration>,
+}
+
+impl FetchTimings {
+    pub fn record(&mut self, duration: Duration) {
+        self.durations.push(duration);
+    }
+
+    pub fn durations(&self) -> &[Duration] {
+        &self.durations
+    }
+
+    /// `None` when no chunk completed
+    pub fn stats(&self) -> Option<FetchStats> {
+        let mut durations = self.durations.clone();
+        durations.sort();
+        let chunks = durations.len();
+        if chunks == 0 {
+            return None;
+        }
+        let total: Duration = durations.iter().sum();
+        // Nearest rank, the p95 of fewer than 20 chunks is their slowest
+        let p95_rank = (chunks * 95).div_ceil(100);
+        Some(FetchStats {
+            chunks,
+            min_ms: as_millis(durations[0]),
+            max_ms: as_millis(durations[chunks - 1]),
+            avg_ms: as_millis(total / chunks as u32),
+            p95_ms: as_millis(durations[p95_rank - 1]),
+        })
+    }
+}
+
+/// Aggregate of a run's chunk fetch durations, in milliseconds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct FetchStats {
+    pub chunks: usize,
+    pub min_ms: u64,
+    pub max_ms: u64,
+    pub avg_ms: u64,
+    pub p95_ms: u64,
+}
+
+impl Display for FetchStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "chunks: {}, min: {}ms, max: {}ms, avg: {}ms, p95: {}ms",
+            self.chunks, self.min_ms, self.max_ms, self.avg_ms, self.p95_ms
+        )
+    }
+}
+
+fn as_millis(duration: Duration) -> u64 {
+    duration.as_millis() as u64
+}
+
+/// Totals of a single forecast or observation run, accumulated as the run goes and logged as one line at the end
+#[derive(Debug, Clone, Serialize)]
+pub struct RunSummary {
+    /// `forecasts` or `observations`
+    pub pipeline: &'static str,
+    pub stations: usize,
+    /// Number of forecasts or observations written
+    pub records: usize,
+    pub files: Vec<WrittenFile>,
+    pub upload: UploadStatus,
+    /// Why the run failed, if it did
+    pub error: Option<String>,
+    /// Timings of the run's NOAA requests, `None` until the fetch finished with at least one chunk
+    pub fetch: Option<FetchStats>,
+    #[serde(rename = "duration_ms", serialize_with = "serialize_elapsed_ms")]
+    started_at: Instant,
+}
+
+impl RunSummary {
+    pub fn new(pipeline: &'static str) -> Self {
+        Self {
+            pipeline,
+            stations: 0,
+            records: 0,
+            files: vec![],
+            upload: UploadStatus::NotAttempted,
+            error: None,
+            fetch: None,
+            started_at: Instant::now(),
+        }
+    }
+
+    /// Adds a written file, its size is read from disk and is 0 if the file can't be found
+    pub fn record_file(&mut self, path: &str) {
+        let bytes = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        self.files.push(WrittenFile {
+            path: path.to_string(),
+            bytes,
+        });
+    }
+
+    pub fn total_bytes(&self) -> u64 {
+        self.files.iter().map(|file| file.bytes).sum()
+    }
+
+    pub fn elapsed(&self) -> Duration {
+        self.started_at.elapsed()
+    }
+
+    pub fn log(&self, logger: &Logger) {
+        let files = self
+            .files
+            .iter()
+            .map(|file| format!("{} ({} bytes)", file.path, file.bytes))
+            .collect::<Vec<String>>()
+            .join(", ");
+        let fetch = self
+            .fetch
+            .map(|fetch| fetch.to_string())
+            .unwrap_or_else(|| String::from("none"));
+        info!(
+            logger,
+            "run summary";
+            "pipeline" => self.pipeline,
+            "stations" => self.stations,
+            "records" => self.records,
+            "files" => files,
+            "bytes" => self.total_bytes(),
+            "upload" => %self.upload,
+            "error" => self.error.as_deref().unwrap_or("none"),
+            "fetch" => fetch,
+            "duration_ms" => self.elapsed().as_millis() as u64
+        );
+    }
+
+    /// Appends the summary to `path` as a single line of JSON, the file is created if it doesn't exist
+    pub fn write_metrics(&self, path: &str) -> Result<(), Error> {
+        let mut line = serde_json::to_string(self)?;
+        line.push('\n');
+        let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+        file.write_all(line.as_bytes())?;
+        Ok(())
+    }
+}
+
+fn serialize_elapsed_ms<S: Serializer>(
+    started_at: &Instant,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.serialize_u64(started_at.elapsed().as_millis() as u64)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FetchStats, FetchTimings, RunSummary, UploadStatus, WrittenFile};
+    use serde_json::Value;
+    use std::{fs, time::Duration};
+
+    #[test]
+    fn accumulates_counts_and_file_sizes() {
+        let dir = std::env::temp_dir().join(format!("run_summary_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("forecasts.parquet");
+        fs::write(&file_path, [0_u8; 128]).unwrap();
+        let file_path = file_path.to_str().unwrap().to_string();
+
+        let mut summary = RunSummary::new("forecasts");
+        summary.stations = 2;
+        summary.records = 14;
+        summary.record_file(&file_path);
+        summary.record_file("./missing.parquet");
+        summary.upload = UploadStatus::Sent;
+
+        assert_eq!(summary.pipeline, "forecasts");
+        assert_eq!(summary.stations, 2);
+        assert_eq!(summary.records, 14);
+        assert_eq!(
+            summary.files,
+            vec![
+                WrittenFile {
+                    path: file_path,
+                    bytes: 128
+                },
+                WrittenFile {
+                    path: String::from("./missing.parquet"),
+                    bytes: 0
+                },
+            ]
+        );
+        assert_eq!(summary.total_bytes(), 128);
+        assert_eq!(summary.upload.to_string(), "sent");
+        fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn upload_is_not_attempted_until_recorded() {
+        let summary = RunSummary::new("observations");
+
+        assert_eq!(summary.upload, UploadStatus::NotAttempted);
+        assert_eq!(summary.error, None);
+        assert!(summary.files.is_empty());
+        assert_eq!(summary.total_bytes(), 0);
+    }
+
+    #[test]
+    fn aggregates_chunk_fetch_durations() {
+        let mut timings = FetchTimings::default();
+        assert_eq!(timings.stats(), None);
+        for millis in (1..=20).rev() {
+            timings.record(Duration::from_millis(millis * 10));
+        }
+
+        assert_eq!(timings.durations().len(), 20);
+        assert_eq!(
+            timings.stats(),
+            Some(FetchStats {
+                chunks: 20,
+                min_ms: 10,
+                max_ms: 200,
+                avg_ms: 105,
+                p95_ms: 190,
+            })
+        );
+
+        let mut single = FetchTimings::default();
+        single.record(Duration::from_millis(42));
+        let stats = single.stats().unwrap();
+        assert_eq!((stats.min_ms, stats.max_ms, stats.p95_ms), (42, 42, 42));
+    }
+
+    #[test]
+    fn appends_summary_to_metrics_file() {
+        let dir = std::env::temp_dir().join(format!("run_metrics_{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let metrics_path = dir.join("metrics.jsonl");
+        let metrics_path = metrics_path.to_str().unwrap();
+        let mut timings = FetchTimings::default();
+        timings.record(Duration::from_millis(120));
+
+        let mut summary = RunSummary::new("observations");
+        summary.fetch = timings.stats();
+        summary.write_metrics(metrics_path).unwrap();
+        summary.write_metrics(metrics_path).unwrap();
+
+        let written = fs::read_to_string(metrics_path).unwrap();
+        let lines: Vec<Value> = written
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["pipeline"], "observations");
+        assert_eq!(lines[0]["fetch"]["chunks"], 1);
+        assert_eq!(lines[0]["fetch"]["p95_ms"], 120);
+        fs::remove_dir_all(dir).unwrap();
+    }
+}
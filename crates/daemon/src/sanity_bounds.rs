@@ -0,0 +1,299 @@
+use crate::{Cli, Forecast, Observation};
+use anyhow::{anyhow, Error};
+use slog::{warn, Logger};
+use std::fmt::{self, Display};
+
+/// What happens to a NOAA value outside of its sanity bounds
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum OutOfRange {
+    /// The value is written as null, the rest of the reading is kept
+    #[default]
+    Drop,
+    /// The value is written as the bound it went past
+    Clamp,
+}
+
+impl Display for OutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OutOfRange::Drop => write!(f, "drop"),
+            OutOfRange::Clamp => write!(f, "clamp"),
+        }
+    }
+}
+
+impl TryFrom<&str> for OutOfRange {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s.trim().to_lowercase().as_str() {
+            "drop" => Ok(OutOfRange::Drop),
+            "clamp" => Ok(OutOfRange::Clamp),
+            _ => Err(anyhow!("invalid out of range handling: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ValueBounds {
+    pub min: f64,
+    pub max: f64,
+}
+
+impl ValueBounds {
+    pub fn new(min: f64, max: f64) -> Result<Self, Error> {
+        if min.is_nan() || max.is_nan() || min > max {
+            return Err(anyhow!(
+                "invalid sanity bounds, min {} needs to be at most max {}",
+                min,
+                max
+            ));
+        }
+        Ok(Self { min, max })
+    }
+
+    fn contains(&self, value: f64) -> bool {
+        self.min <= value && value <= self.max
+    }
+}
+
+/// Range of values NOAA can plausibly report, anything outside of it is treated as a bad reading before it's
+/// written to parquet so it can't skew scoring
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SanityBounds {
+    /// Knots, used for forecast and observation wind speeds
+    pub wind_speed: ValueBounds,
+    /// Fahrenheit, observations are in celsius and are checked against the converted bounds
+    pub temperature: ValueBounds,
+    pub out_of_range: OutOfRange,
+}
+
+impl Default for SanityBounds {
+    fn default() -> Self {
+        Self {
+            // past the strongest winds ever measured at the surface
+            wind_speed: ValueBounds {
+                min: 0.0,
+                max: 250.0,
+            },
+            // past the hottest and coldest temperatures ever recorded
+            temperature: ValueBounds {
+                min: -130.0,
+                max: 140.0,
+            },
+            out_of_range: OutOfRange::Drop,
+        }
+    }
+}
+
+impl SanityBounds {
+    pub fn from_cli(cli: &Cli) -> Result<Self, Error> {
+        let default = SanityBounds::default();
+        Ok(Self {
+            wind_speed: ValueBounds::new(
+                cli.min_wind_speed.unwrap_or(default.wind_speed.min),
+                cli.max_wind_speed.unwrap_or(default.wind_speed.max),
+            )?,
+            temperature: ValueBounds::new(
+                cli.min_temperature.unwrap_or(default.temperature.min),
+                cli.max_temperature.unwrap_or(default.temperature.max),
+            )?,
+            out_of_range: match cli.out_of_range_values.as_deref() {
+                Some(out_of_range) => OutOfRange::try_from(out_of_range)?,
+                None => default.out_of_range,
+            },
+        })
+    }
+
+    pub fn apply_to_forecast(&self, logger: &Logger, forecast: &mut Forecast) {
+        let station_id = forecast.station_id.clone();
+        let check = |field: &str, value: Option<i64>, bounds: ValueBounds| {
+            self.check_whole(logger, &station_id, field, value, bounds)
+        };
+        forecast.max_temp = check("max_temp", forecast.max_temp, self.temperature);
+        forecast.min_temp = check("min_temp", forecast.min_temp, self.temperature);
+        forecast.wind_speed = check("wind_speed", forecast.wind_speed, self.wind_speed);
+    }
+
+    pub fn apply_to_observation(&self, logger: &Logger, observation: &mut Observation) {
+        let station_id = observation.station_id.clone();
+        let celsius = ValueBounds {
+            min: fahrenheit_to_celsius(self.temperature.min),
+            max: fahrenheit_to_celsius(self.temperature.max),
+        };
+        observation.temperature_value = self.check(
+            logger,
+            &station_id,
+            "temperature_value",
+            observation.temperature_value,
+            celsius,
+        );
+        observation.dewpoint_value = self.check(
+            logger,
+            &station_id,
+            "dewpoint_value",
+            observation.dewpoint_value,
+            celsius,
+        );
+        observation.wind_speed = self.check_whole(
+            logger,
+            &station_id,
+            "wind_speed",
+            observation.wind_speed,
+            self.wind_speed,
+        );
+    }
+
+    fn check(
+        &self,
+        logger: &Logger,
+        station_id: &str,
+        field: &str,
+        value: Option<f64>,
+        bounds: ValueBounds,
+    ) -> Option<f64> {
+        let value = value?;
+        if bounds.contains(value) {
+            return Some(value);
+        }
+        warn!(
+            logger,
+            "{} of {} for station {} is outside of {} to {}, applying {}",
+            field,
+            value,
+            station_id,
+            bounds.min,
+            bounds.max,
+            self.out_of_range
+        );
+        match self.out_of_range {
+            OutOfRange::Drop => None,
+            OutOfRange::Clamp => Some(value.clamp(bounds.min, bounds.max)),
+        }
+    }
+
+    fn check_whole(
+        &self,
+        logger: &Logger,
+        station_id: &str,
+        field: &str,
+        value: Option<i64>,
+        bounds: ValueBounds,
+    ) -> Option<i64> {
+        // clamping to a fractional bound rounds towards the inside of the range
+        let whole = ValueBounds {
+            min: bounds.min.ceil(),
+            max: bounds.max.floor(),
+        };
+        self.check(
+            logger,
+            station_id,
+            field,
+            value.map(|value| value as f64),
+            whole,
+        )
+        .map(|value| value as i64)
+    }
+}
+
+fn fahrenheit_to_celsius(value: f64) -> f64 {
+    (value - 32.0) * 5.0 / 9.0
+}
+
+#[cfg(test)]
+mod test {
+    use super::{OutOfRange, SanityBounds, ValueBounds};
+    use crate::{Forecast, Observation};
+    use slog::{o, Discard, Logger};
+
+    fn forecast(max_temp: i64, wind_speed: i64) -> Forecast {
+        Forecast {
+            station_id: String::from("KSAW"),
+            station_name: String::from("Marquette"),
+            latitude: 46.35,
+            longitude: -87.40,
+            generated_at: String::from("2024-08-12T00:00:00Z"),
+            begin_time: String::from("2024-08-12T06:00:00-04:00"),
+            end_time: String::from("2024-08-13T06:00:00-04:00"),
+            max_temp: Some(max_temp),
+            min_temp: Some(60),
+            temperature_unit_code: Some(String::from("fahrenheit")),
+            wind_speed: Some(wind_speed),
+            wind_speed_unit_code: Some(String::from("knots")),
+            wind_direction: Some(180),
+            wind_direction_unit_code: Some(String::from("degrees true")),
+            relative_humidity_max: None,
+            relative_humidity_min: None,
+            relative_humidity_unit_code: None,
+            liquid_precipitation_amt: None,
+            liquid_precipitation_unit_code: None,
+            twelve_hour_probability_of_precipitation: None,
+            twelve_hour_probability_of_precipitation_unit_code: None,
+        }
+    }
+
+    fn observation(temperature_value: f64, wind_speed: i64) -> Observation {
+        Observation {
+            station_id: String::from("KSAW"),
+            station_name: String::from("Marquette"),
+            latitude: 46.35,
+            longitude: -87.40,
+            generated_at: String::from("2024-08-12T00:00:00Z"),
+            temperature_value: Some(temperature_value),
+            temperature_unit_code: String::from("celsius"),
+            wind_direction: Some(180),
+            wind_direction_unit_code: String::from("degrees true"),
+            wind_speed: Some(wind_speed),
+            wind_speed_unit_code: String::from("knots"),
+            dewpoint_value: Some(10.0),
+            dewpoint_unit_code: String::from("celsius"),
+        }
+    }
+
+    #[test]
+    fn drops_out_of_range_wind_speed() {
+        let logger = Logger::root(Discard, o!());
+        let bounds = SanityBounds::default();
+
+        let mut forecast = forecast(80, 900);
+        bounds.apply_to_forecast(&logger, &mut forecast);
+        let mut observation = observation(21.5, 900);
+        bounds.apply_to_observation(&logger, &mut observation);
+
+        assert_eq!(forecast.wind_speed, None);
+        assert_eq!(forecast.max_temp, Some(80));
+        assert_eq!(observation.wind_speed, None);
+        assert_eq!(observation.temperature_value, Some(21.5));
+        assert_eq!(observation.wind_direction, Some(180));
+    }
+
+    #[test]
+    fn clamps_out_of_range_values_to_the_configured_bounds() {
+        let logger = Logger::root(Discard, o!());
+        let bounds = SanityBounds {
+            wind_speed: ValueBounds::new(0.0, 100.0).unwrap(),
+            temperature: ValueBounds::new(-40.0, 120.0).unwrap(),
+            out_of_range: OutOfRange::Clamp,
+        };
+
+        let mut forecast = forecast(150, 900);
+        bounds.apply_to_forecast(&logger, &mut forecast);
+        let mut observation = observation(-60.0, -5);
+        bounds.apply_to_observation(&logger, &mut observation);
+
+        assert_eq!(forecast.wind_speed, Some(100));
+        assert_eq!(forecast.max_temp, Some(120));
+        assert_eq!(observation.wind_speed, Some(0));
+        // -40 is the same in fahrenheit and celsius
+        assert_eq!(observation.temperature_value, Some(-40.0));
+        assert_eq!(observation.dewpoint_value, Some(10.0));
+    }
+
+    #[test]
+    fn rejects_bounds_with_min_past_max() {
+        assert!(ValueBounds::new(10.0, 0.0).is_err());
+        assert!(ValueBounds::new(f64::NAN, 0.0).is_err());
+        assert_eq!(OutOfRange::try_from("Clamp").unwrap(), OutOfRange::Clamp);
+        assert!(OutOfRange::try_from("ignore").is_err());
+    }
+}
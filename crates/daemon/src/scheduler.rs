@@ -0,0 +1,276 @@
+use crate::Cli;
+use std::{future::Future, time::Duration};
+use tokio::time::{sleep_until, Instant};
+use tokio_util::sync::CancellationToken;
+
+/// How often each NOAA pipeline is run, observations change more often than forecasts
+/// so they can be pulled on a shorter interval
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PollSchedule {
+    pub forecast_interval: Duration,
+    pub observation_interval: Duration,
+    /// Longest wait between runs while the oracle has no active events, the wait doubles after each idle
+    /// run until it gets here. Runs always keep their interval when not set
+    pub idle_backoff: Option<Duration>,
+}
+
+/// Whether a run had anything to fetch for the oracle
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Activity {
+    Active,
+    /// The oracle has no events needing weather data, the run was skipped
+    Idle,
+}
+
+/// Wait between runs of a pipeline, backs off while the oracle is idle and goes back to the pipeline's
+/// interval as soon as a run is active again
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct IdleBackoff {
+    interval: Duration,
+    max_wait: Option<Duration>,
+    wait: Duration,
+}
+
+impl IdleBackoff {
+    pub fn new(interval: Duration, max_wait: Option<Duration>) -> Self {
+        Self {
+            interval,
+            max_wait,
+            wait: interval,
+        }
+    }
+
+    /// How long to wait after a run before starting the next one
+    pub fn next_wait(&mut self, activity: Activity) -> Duration {
+        self.wait = match (activity, self.max_wait) {
+            (Activity::Idle, Some(max_wait)) => {
+                self.wait.saturating_mul(2).min(max_wait.max(self.interval))
+            }
+            _ => self.interval,
+        };
+        self.wait
+    }
+}
+
+impl PollSchedule {
+    pub fn from_cli(cli: &Cli) -> Self {
+        // defaults to once an hour
+        let sleep_interval = cli.sleep_interval.unwrap_or(3600);
+        Self {
+            forecast_interval: Duration::from_secs(cli.forecast_interval.unwrap_or(sleep_interval)),
+            observation_interval: Duration::from_secs(
+                cli.observation_interval.unwrap_or(sleep_interval),
+            ),
+            idle_backoff: cli.idle_backoff.map(Duration::from_secs),
+        }
+    }
+}
+
+/// Runs both pipelines on their own timer, both run right away then wait for their next tick.
+/// A slow run of one pipeline never delays the other, runs of the same pipeline never overlap.
+/// Once shutdown is cancelled each pipeline finishes its current run and no new ones are started.
+pub async fn run_poll_schedule<F, FFut, O, OFut>(
+    schedule: PollSchedule,
+    shutdown: &CancellationToken,
+    on_forecast_tick: F,
+    on_observation_tick: O,
+) where
+    F: FnMut() -> FFut,
+    FFut: Future<Output = Activity>,
+    O: FnMut() -> OFut,
+    OFut: Future<Output = Activity>,
+{
+    tokio::join!(
+        poll(
+            IdleBackoff::new(schedule.forecast_interval, schedule.idle_backoff),
+            shutdown,
+            on_forecast_tick
+        ),
+        poll(
+            IdleBackoff::new(schedule.observation_interval, schedule.idle_backoff),
+            shutdown,
+            on_observation_tick
+        )
+    );
+}
+
+async fn poll<F, Fut>(mut backoff: IdleBackoff, shutdown: &CancellationToken, mut on_tick: F)
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Activity>,
+{
+    while !shutdown.is_cancelled() {
+        let started_at = Instant::now();
+        let activity = on_tick().await;
+        // a run that takes longer than the wait starts the next one right away, without catch up runs
+        tokio::select! {
+            _ = sleep_until(started_at + backoff.next_wait(activity)) => {}
+            _ = shutdown.cancelled() => break,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{run_poll_schedule, Activity, IdleBackoff, PollSchedule};
+    use std::{
+        future::{ready, Ready},
+        sync::{
+            atomic::{AtomicUsize, Ordering},
+            Arc,
+        },
+        time::Duration,
+    };
+    use tokio::{
+        task::yield_now,
+        time::{advance, timeout},
+    };
+    use tokio_util::sync::CancellationToken;
+
+    fn counting_tick(runs: &Arc<AtomicUsize>) -> impl FnMut() -> Ready<Activity> {
+        let runs = runs.clone();
+        move || {
+            runs.fetch_add(1, Ordering::Relaxed);
+            ready(Activity::Active)
+        }
+    }
+
+    /// Moves the paused clock forward 10ms at a time so every run starts right when it's due
+    async fn advance_by(total: Duration) {
+        let step = Duration::from_millis(10);
+        let mut elapsed = Duration::ZERO;
+        while elapsed < total {
+            advance(step).await;
+            elapsed += step;
+        }
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn runs_forecasts_and_observations_on_independent_timers() {
+        let forecast_runs = Arc::new(AtomicUsize::new(0));
+        let observation_runs = Arc::new(AtomicUsize::new(0));
+        let schedule = PollSchedule {
+            forecast_interval: Duration::from_millis(100),
+            observation_interval: Duration::from_millis(10),
+            idle_backoff: None,
+        };
+        let shutdown = CancellationToken::new();
+
+        let handle = tokio::spawn({
+            let shutdown = shutdown.clone();
+            let on_forecast_tick = counting_tick(&forecast_runs);
+            let on_observation_tick = counting_tick(&observation_runs);
+            async move {
+                run_poll_schedule(schedule, &shutdown, on_forecast_tick, on_observation_tick).await
+            }
+        });
+        // both pipelines run right away
+        yield_now().await;
+        advance_by(Duration::from_millis(350)).await;
+
+        // forecasts tick at 0, 100, 200 and 300ms, observations every 10ms up to 350ms
+        assert_eq!(forecast_runs.load(Ordering::Relaxed), 4);
+        assert_eq!(observation_runs.load(Ordering::Relaxed), 36);
+        shutdown.cancel();
+        handle.await.unwrap();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn slow_forecast_run_does_not_delay_observations() {
+        let forecast_runs = Arc::new(AtomicUsize::new(0));
+        let observation_runs = Arc::new(AtomicUsize::new(0));
+        let schedule = PollSchedule {
+            forecast_interval: Duration::from_millis(10),
+            observation_interval: Duration::from_millis(10),
+            idle_backoff: None,
+        };
+        let shutdown = CancellationToken::new();
+
+        let handle = tokio::spawn({
+            let shutdown = shutdown.clone();
+            let forecast_runs = forecast_runs.clone();
+            let on_observation_tick = counting_tick(&observation_runs);
+            async move {
+                run_poll_schedule(
+                    schedule,
+                    &shutdown,
+                    move || {
+                        forecast_runs.fetch_add(1, Ordering::Relaxed);
+                        async {
+                            tokio::time::sleep(Duration::from_secs(60)).await;
+                            Activity::Active
+                        }
+                    },
+                    on_observation_tick,
+                )
+                .await
+            }
+        });
+        yield_now().await;
+        advance_by(Duration::from_millis(200)).await;
+
+        // the first forecast run is still going
+        assert_eq!(forecast_runs.load(Ordering::Relaxed), 1);
+        assert_eq!(observation_runs.load(Ordering::Relaxed), 21);
+        handle.abort();
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn stops_once_shut_down_after_the_current_runs() {
+        let forecast_runs = AtomicUsize::new(0);
+        let observation_runs = AtomicUsize::new(0);
+        let schedule = PollSchedule {
+            forecast_interval: Duration::from_millis(10),
+            observation_interval: Duration::from_secs(60),
+            idle_backoff: None,
+        };
+        let shutdown = CancellationToken::new();
+
+        let forecast_runs_ref = &forecast_runs;
+        let observation_runs_ref = &observation_runs;
+        let shutdown_ref = &shutdown;
+        let res = timeout(
+            Duration::from_secs(5),
+            run_poll_schedule(
+                schedule,
+                &shutdown,
+                move || async move {
+                    if forecast_runs_ref.fetch_add(1, Ordering::Relaxed) == 2 {
+                        shutdown_ref.cancel();
+                    }
+                    Activity::Active
+                },
+                move || async move {
+                    observation_runs_ref.fetch_add(1, Ordering::Relaxed);
+                    Activity::Active
+                },
+            ),
+        )
+        .await;
+
+        assert!(res.is_ok(), "schedule should stop once shut down");
+        assert_eq!(forecast_runs.load(Ordering::Relaxed), 3);
+        // observations were waiting on their next tick, which is cut short
+        assert_eq!(observation_runs.load(Ordering::Relaxed), 1);
+    }
+
+    #[test]
+    fn idle_backoff_doubles_up_to_max_wait_and_resets_once_active() {
+        let mut backoff = IdleBackoff::new(Duration::from_secs(60), Some(Duration::from_secs(300)));
+
+        assert_eq!(backoff.next_wait(Activity::Active), Duration::from_secs(60));
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(120));
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(240));
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(300));
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(300));
+        assert_eq!(backoff.next_wait(Activity::Active), Duration::from_secs(60));
+    }
+
+    #[test]
+    fn idle_runs_keep_interval_without_idle_backoff() {
+        let mut backoff = IdleBackoff::new(Duration::from_secs(60), None);
+
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(60));
+        assert_eq!(backoff.next_wait(Activity::Idle), Duration::from_secs(60));
+    }
+}
@@ -0,0 +1,358 @@
+use crate::{
+    convert_forecast_xml, save_forecasts, save_observations, CityWeather, CurrentWeather, Forecast,
+    ForecastProduct, Metar, Observation, ParquetCompression, WeatherForecast, WeatherStation,
+};
+use anyhow::{anyhow, Error};
+use parquet::file::reader::{FileReader, SerializedFileReader};
+use slog::{error, info, Logger};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    fs::{self, File},
+    panic,
+    path::Path,
+};
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, Duration, OffsetDateTime,
+    Time, UtcOffset,
+};
+
+/*
+Runs the same steps as a normal data pull without calling NOAA:
+synthetic stations -> synthetic NDFD xml -> flatten/convert -> parquet files -> read back
+*/
+pub struct StageResult {
+    pub name: &'static str,
+    pub result: Result<String, Error>,
+}
+
+impl Display for StageResult {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match &self.result {
+            Ok(details) => write!(f, "[pass] {}: {}", self.name, details),
+            Err(err) => write!(f, "[fail] {}: {}", self.name, err),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct SelfTestReport {
+    pub stages: Vec<StageResult>,
+}
+
+impl SelfTestReport {
+    pub fn passed(&self) -> bool {
+        !self.stages.is_empty() && self.stages.iter().all(|stage| stage.result.is_ok())
+    }
+
+    fn add<T>(&mut self, name: &'static str, result: Result<(T, String), Error>) -> Option<T> {
+        match result {
+            Ok((value, details)) => {
+                self.stages.push(StageResult {
+                    name,
+                    result: Ok(details),
+                });
+                Some(value)
+            }
+            Err(err) => {
+                self.stages.push(StageResult {
+                    name,
+                    result: Err(err),
+                });
+                None
+            }
+        }
+    }
+}
+
+/// Exercises the forecast/observation conversions and parquet IO offline, stopping at the first failed stage
+pub fn run_selftest(logger: &Logger) -> SelfTestReport {
+    let mut report = SelfTestReport::default();
+    let root_path = std::env::temp_dir().join(format!("noaa_selftest_{}", std::process::id()));
+    run_stages(logger, &mut report, &root_path);
+    if root_path.exists() {
+        if let Err(err) = fs::remove_dir_all(&root_path) {
+            error!(logger, "error removing selftest directory: {}", err);
+        }
+    }
+
+    for stage in &report.stages {
+        match stage.result {
+            Ok(_) => info!(logger, "{}", stage),
+            Err(_) => error!(logger, "{}", stage),
+        }
+    }
+    report
+}
+
+fn run_stages(logger: &Logger, report: &mut SelfTestReport, root_path: &Path) {
+    let city_weather = synthetic_city_weather();
+    let Some(weather_forecasts) = report.add(
+        "flatten forecasts",
+        flatten_forecasts(logger, &city_weather),
+    ) else {
+        return;
+    };
+    let Some(forecasts) = report.add(
+        "convert forecasts",
+        convert_forecasts(&city_weather, weather_forecasts),
+    ) else {
+        return;
+    };
+    let Some(observations) =
+        report.add("convert observations", convert_observations(&city_weather))
+    else {
+        return;
+    };
+    let Some((forecast_path, observation_path)) = report.add(
+        "write parquet",
+        write_parquet(root_path, forecasts, observations),
+    ) else {
+        return;
+    };
+    report.add(
+        "read parquet",
+        read_parquet(&city_weather, &forecast_path, &observation_path),
+    );
+}
+
+fn synthetic_city_weather() -> CityWeather {
+    let stations = [
+        (
+            "KLWV",
+            "Lawrenceville-Vincennes International Airport",
+            "38.76",
+            "-87.61",
+        ),
+        (
+            "KLBB",
+            "Lubbock Preston Smith International Airport",
+            "33.66",
+            "-101.82",
+        ),
+    ];
+    CityWeather {
+        city_data: stations
+            .into_iter()
+            .map(|(station_id, station_name, latitude, longitude)| {
+                (
+                    String::from(station_id),
+                    WeatherStation {
+                        station_id: String::from(station_id),
+                        station_name: String::from(station_name),
+                        latitude: String::from(latitude),
+                        longitude: String::from(longitude),
+                    },
+                )
+            })
+            .collect(),
+    }
+}
+
+// Mirrors the shape of ndfdXMLclient time-series responses, one daily layout covering the next week
+fn synthetic_forecast_xml(city_weather: &CityWeather) -> Result<String, Error> {
+    let time_format = format_description!(
+        "[year]-[month]-[day]T[hour]:[minute]:[second][offset_hour sign:mandatory]:[offset_minute]"
+    );
+    let offset = UtcOffset::from_hms(-5, 0, 0)?;
+    let start = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) - Duration::days(1);
+    let days = 9;
+
+    let mut time_layout = String::from("<layout-key>k-p24h-n9-1</layout-key>");
+    for day in 0..days {
+        let begin = (start + Duration::days(day)).to_offset(offset);
+        let end = begin + Duration::hours(12);
+        time_layout.push_str(&format!(
+            "<start-valid-time>{}</start-valid-time><end-valid-time>{}</end-valid-time>",
+            begin.format(&time_format)?,
+            end.format(&time_format)?
+        ));
+    }
+    let values = |value: i64| {
+        (0..days)
+            .map(|day| format!("<value>{}</value>", value + day))
+            .collect::<String>()
+    };
+
+    let mut locations = String::new();
+    let mut parameters = String::new();
+    for (index, station) in city_weather.city_data.values().enumerate() {
+        let location_key = format!("point{}", index + 1);
+        locations.push_str(&format!(
+            "<location><location-key>{}</location-key><point latitude=\"{}\" longitude=\"{}\"/></location>",
+            location_key,
+            station.get_latitude(),
+            station.get_longitude()
+        ));
+        parameters.push_str(&format!(
+            "<parameters applicable-location=\"{}\">\
+                <temperature type=\"maximum\" units=\"Fahrenheit\" time-layout=\"k-p24h-n9-1\">\
+                    <name>Daily Maximum Temperature</name>{}\
+                </temperature>\
+                <temperature type=\"minimum\" units=\"Fahrenheit\" time-layout=\"k-p24h-n9-1\">\
+                    <name>Daily Minimum Temperature</name>{}\
+                </temperature>\
+            </parameters>",
+            location_key,
+            values(80),
+            values(60)
+        ));
+    }
+
+    Ok(format!(
+        "<?xml version=\"1.0\"?>\
+        <dwml>\
+            <head><product><creation-date>{}</creation-date></product></head>\
+            <data>{}<time-layout time-coordinate=\"local\" summarization=\"none\">{}</time-layout>{}</data>\
+        </dwml>",
+        OffsetDateTime::now_utc().format(&Rfc3339)?,
+        locations,
+        time_layout,
+        parameters
+    ))
+}
+
+fn flatten_forecasts(
+    logger: &Logger,
+    city_weather: &CityWeather,
+) -> Result<(HashMap<String, Vec<WeatherForecast>>, String), Error> {
+    let xml = synthetic_forecast_xml(city_weather)?;
+    let weather_forecasts = convert_forecast_xml(
+        logger,
+        &xml,
+        city_weather,
+        &[ForecastProduct::MaxTemp, ForecastProduct::MinTemp],
+    )?;
+    for station_id in city_weather.city_data.keys() {
+        let station_forecasts = weather_forecasts
+            .get(station_id)
+            .ok_or_else(|| anyhow!("no forecasts found for {}", station_id))?;
+        if !station_forecasts
+            .iter()
+            .any(|forecast| forecast.max_temp.is_some() && forecast.min_temp.is_some())
+        {
+            return Err(anyhow!("no temperatures found for {}", station_id));
+        }
+    }
+    let details = format!("{} stations flattened", weather_forecasts.len());
+    Ok((weather_forecasts, details))
+}
+
+fn convert_forecasts(
+    city_weather: &CityWeather,
+    weather_forecasts: HashMap<String, Vec<WeatherForecast>>,
+) -> Result<(Vec<Forecast>, String), Error> {
+    let mut forecasts = vec![];
+    for weather_forecast in weather_forecasts.into_values().flatten() {
+        let mut forecast: Forecast = weather_forecast.try_into()?;
+        let station = city_weather
+            .city_data
+            .get(&forecast.station_id)
+            .ok_or_else(|| anyhow!("unknown station {}", forecast.station_id))?;
+        forecast.station_name = station.station_name.clone();
+        forecasts.push(forecast);
+    }
+    let details = format!("{} forecasts converted", forecasts.len());
+    Ok((forecasts, details))
+}
+
+fn convert_observations(city_weather: &CityWeather) -> Result<(Vec<Observation>, String), Error> {
+    let mut observations = vec![];
+    for station in city_weather.city_data.values() {
+        let metar = Metar {
+            raw_text: String::from(""),
+            station_id: station.station_id.clone(),
+            observation_time: Some(OffsetDateTime::now_utc().format(&Rfc3339)?),
+            latitude: Some(station.latitude.clone()),
+            longitude: Some(station.longitude.clone()),
+            temp_c: Some(String::from("25.5")),
+            dewpoint_c: Some(String::from("18.0")),
+            wind_dir_degrees: Some(String::from("180")),
+            wind_speed_kt: Some(String::from("8")),
+            elevation_m: String::from("130"),
+            wx_string: None,
+            precip_in: None,
+        };
+        let current: CurrentWeather = metar.try_into()?;
+        let mut observation: Observation = current.try_into()?;
+        observation.station_name = station.station_name.clone();
+        observations.push(observation);
+    }
+    let details = format!("{} observations converted", observations.len());
+    Ok((observations, details))
+}
+
+fn write_parquet(
+    root_path: &Path,
+    forecasts: Vec<Forecast>,
+    observations: Vec<Observation>,
+) -> Result<((String, String), String), Error> {
+    fs::create_dir_all(root_path)
+        .map_err(|e| anyhow!("error creating {}: {}", root_path.display(), e))?;
+    let root_path = root_path
+        .to_str()
+        .ok_or_else(|| anyhow!("invalid selftest path {}", root_path.display()))?
+        .to_string();
+    let forecast_count = forecasts.len();
+    let observation_count = observations.len();
+    // The parquet writers panic on IO errors, catch those so they are reported as a failed stage
+    let written = panic::catch_unwind(|| {
+        let forecast_path = save_forecasts(
+            forecasts,
+            &root_path,
+            String::from("forecasts_selftest"),
+            ParquetCompression::default(),
+        );
+        let observation_path = save_observations(
+            observations,
+            &root_path,
+            String::from("observations_selftest"),
+            ParquetCompression::default(),
+        );
+        Ok::<_, Error>((forecast_path?, observation_path?))
+    })
+    .map_err(|_| anyhow!("error writing parquet files to {}", root_path))??;
+    let details = format!(
+        "{} forecasts and {} observations written to {}",
+        forecast_count, observation_count, root_path
+    );
+    Ok((written, details))
+}
+
+fn read_parquet(
+    city_weather: &CityWeather,
+    forecast_path: &str,
+    observation_path: &str,
+) -> Result<((), String), Error> {
+    let forecast_rows = count_rows(forecast_path)?;
+    let observation_rows = count_rows(observation_path)?;
+    if forecast_rows == 0 {
+        return Err(anyhow!("no forecast rows read back from {}", forecast_path));
+    }
+    if observation_rows != city_weather.city_data.len() {
+        return Err(anyhow!(
+            "expected {} observation rows but read {}",
+            city_weather.city_data.len(),
+            observation_rows
+        ));
+    }
+    Ok((
+        (),
+        format!(
+            "{} forecast rows and {} observation rows read back",
+            forecast_rows, observation_rows
+        ),
+    ))
+}
+
+fn count_rows(file_path: &str) -> Result<usize, Error> {
+    let file = File::open(file_path).map_err(|e| anyhow!("error opening {}: {}", file_path, e))?;
+    let reader = SerializedFileReader::new(file)
+        .map_err(|e| anyhow!("error reading {}: {}", file_path, e))?;
+    let mut rows = 0;
+    for row in reader.get_row_iter(None)? {
+        row.map_err(|e| anyhow!("error reading row from {}: {}", file_path, e))?;
+        rows += 1;
+    }
+    Ok(rows)
+}
@@ -0,0 +1,142 @@
+// Bare bones http/1 server the tests stand in for NOAA, the oracle and webhooks with. Every connection
+// gets one request and one response, built by the test's handler on its own task.
+
+use std::{
+    future::Future,
+    net::SocketAddr,
+    sync::{Arc, Mutex},
+};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::{TcpListener, TcpStream},
+};
+
+pub struct Request {
+    /// ie. `GET /api/data/metar?ids=KSAW HTTP/1.1`
+    pub request_line: String,
+    /// Raw body as sent, chunked bodies keep their framing
+    pub body: Vec<u8>,
+}
+
+pub struct Response {
+    status: String,
+    body: Vec<u8>,
+}
+
+impl Response {
+    /// `status` is everything after the http version on the status line, extra header lines can follow it,
+    /// ie. `429 Too Many Requests\r\nretry-after: 0`
+    pub fn new(status: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status: String::from(status),
+            body: body.into(),
+        }
+    }
+
+    pub fn ok(body: impl Into<Vec<u8>>) -> Self {
+        Self::new("200 OK", body)
+    }
+}
+
+pub struct MockServer {
+    address: SocketAddr,
+    request_lines: Arc<Mutex<Vec<String>>>,
+}
+
+impl MockServer {
+    pub fn url(&self) -> String {
+        format!("http://{}", self.address)
+    }
+
+    /// Requests read so far, including the ones still waiting on their response
+    pub fn requests(&self) -> usize {
+        self.request_lines.lock().unwrap().len()
+    }
+
+    pub fn request_lines(&self) -> Vec<String> {
+        self.request_lines.lock().unwrap().clone()
+    }
+}
+
+/// Answers every request with what `handler` returns for it, `handler` also gets how many requests came
+/// before it. Handlers that never finish leave their client hanging
+pub async fn spawn_server<F, Fut>(handler: F) -> MockServer
+where
+    F: Fn(usize, Request) -> Fut + Send + Sync + 'static,
+    Fut: Future<Output = Response> + Send + 'static,
+{
+    let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let address = listener.local_addr().unwrap();
+    let request_lines = Arc::new(Mutex::new(vec![]));
+    let server_request_lines = request_lines.clone();
+    let handler = Arc::new(handler);
+    tokio::spawn(async move {
+        while let Ok((mut stream, _)) = listener.accept().await {
+            let request_lines = server_request_lines.clone();
+            let handler = handler.clone();
+            tokio::spawn(async move {
+                let request = read_request(&mut stream).await;
+                let index = {
+                    let mut request_lines = request_lines.lock().unwrap();
+                    request_lines.push(request.request_line.clone());
+                    request_lines.len() - 1
+                };
+                let response = handler(index, request).await;
+                let headers = format!(
+                    "HTTP/1.1 {}\r\ncontent-length: {}\r\nconnection: close\r\n\r\n",
+                    response.status,
+                    response.body.len()
+                );
+                // Clients that gave up on the request have already closed the connection
+                let _ = stream.write_all(headers.as_bytes()).await;
+                let _ = stream.write_all(&response.body).await;
+                let _ = stream.shutdown().await;
+            });
+        }
+    });
+    MockServer {
+        address,
+        request_lines,
+    }
+}
+
+// Reads the whole request so the client sees the response instead of a reset connection
+async fn read_request(stream: &mut TcpStream) -> Request {
+    let mut request = vec![];
+    let mut buf = [0_u8; 4096];
+    let body_start = loop {
+        if let Some(header_end) = request.windows(4).position(|w| w == b"\r\n\r\n") {
+            break header_end + 4;
+        }
+        match stream.read(&mut buf).await {
+            Ok(read) if read > 0 => request.extend_from_slice(&buf[..read]),
+            _ => break request.len(),
+        }
+    };
+    let headers = String::from_utf8_lossy(&request[..body_start]).to_lowercase();
+    let content_length = headers
+        .lines()
+        .find_map(|line| line.strip_prefix("content-length:"))
+        .and_then(|length| length.trim().parse::<usize>().ok());
+    loop {
+        let done = match content_length {
+            Some(length) => request.len() >= body_start + length,
+            None => !headers.contains("chunked") || request.ends_with(b"0\r\n\r\n"),
+        };
+        if done {
+            break;
+        }
+        match stream.read(&mut buf).await {
+            Ok(read) if read > 0 => request.extend_from_slice(&buf[..read]),
+            _ => break,
+        }
+    }
+    Request {
+        request_line: String::from_utf8_lossy(&request[..body_start])
+            .lines()
+            .next()
+            .unwrap_or_default()
+            .to_string(),
+        body: request[body_start..].to_vec(),
+    }
+}
@@ -1,27 +1,33 @@
+use crate::{CircuitBreaker, PayloadKind, RateLimited, RawPayloads};
 use anyhow::{anyhow, Error};
 use async_compression::tokio::bufread::GzipDecoder;
-use clap::Parser;
+use clap::{Parser, Subcommand};
 use futures::TryStreamExt;
-use reqwest::Client;
-use reqwest_middleware::ClientBuilder;
+use reqwest::{Client, StatusCode};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use slog::{debug, error, info, o, Drain, Level, Logger};
 use std::{
     env,
+    fmt::{self, Display},
     fs::{self, File},
     io::Read,
     path::Path,
     sync::Arc,
-    thread,
-    time::{Duration, Instant},
+    time::Duration,
 };
 use tokio::io::AsyncBufReadExt;
 use tokio::sync::Mutex;
+use tokio::time::{sleep, Instant};
 use tokio_util::compat::FuturesAsyncReadCompatExt;
 
 #[derive(Parser, Clone, Debug, serde::Deserialize)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
+    #[command(subcommand)]
+    #[serde(skip)]
+    pub command: Option<Command>,
+
     /// Path to Settings.toml file holding the rest of the cli options
     #[arg(short, long)]
     pub config: Option<String>,
@@ -42,6 +48,14 @@ pub struct Cli {
     #[arg(short, long)]
     pub sleep_interval: Option<u64>,
 
+    /// Length of time to wait before pulling forecasts again in seconds (default: sleep_interval)
+    #[arg(long)]
+    pub forecast_interval: Option<u64>,
+
+    /// Length of time to wait before pulling observations again in seconds (default: sleep_interval)
+    #[arg(long)]
+    pub observation_interval: Option<u64>,
+
     /// How quickly the rate limiter will release tokens (default: 15 seconds)
     #[arg(short, long)]
     pub refill_rate: Option<f64>,
@@ -53,6 +67,141 @@ pub struct Cli {
     /// User agent, header sent to NOAA's api to allow them to connect you
     #[arg(short, long)]
     pub user_agent: Option<String>,
+
+    /// Length of time to wait on a NOAA request in seconds, covers connecting and reading the response, timed out requests are retried (default: 30)
+    #[arg(long)]
+    pub request_timeout: Option<u64>,
+
+    /// Number of failed requests in a row before requests to NOAA are paused (default: 5)
+    #[arg(long)]
+    pub breaker_failure_threshold: Option<usize>,
+
+    /// Length of time to pause requests to NOAA once too many have failed in seconds (default: 300)
+    #[arg(long)]
+    pub breaker_cooldown: Option<u64>,
+
+    /// Path to a JSON file of forecasts to convert into a forecast parquet file, skips pulling data from NOAA
+    #[arg(long)]
+    pub import_forecasts: Option<String>,
+
+    /// Comma separated list of NDFD products to request in forecasts (default: maxt,mint,wspd,wdir,pop12,qpf,maxrh,minrh)
+    #[arg(long, value_delimiter = ',')]
+    pub forecast_products: Option<Vec<String>>,
+
+    /// Where to pull forecasts from, `xml` for graphical.weather.gov or `api` for api.weather.gov (default: xml)
+    #[arg(long)]
+    pub forecast_source: Option<String>,
+
+    /// Number of decimals liquid precipitation amounts are rounded to before being written to parquet, rounding is lossy (default: none, raw amounts are written)
+    #[arg(long)]
+    pub precipitation_decimals: Option<u32>,
+
+    /// Path to a toml file mapping forecast station ids to the nearby observation station ids to use in their place
+    #[arg(long)]
+    pub observation_stations: Option<String>,
+
+    /// Path to a toml file mapping station ids to the station name observations get when the station list has none for them
+    #[arg(long)]
+    pub station_names: Option<String>,
+
+    /// Number of times a failed parquet upload or active station lookup is retried before the run gives up on it, the file is kept so it can be re-sent (default: 3)
+    #[arg(long)]
+    pub upload_retries: Option<usize>,
+
+    /// Length of time to wait before retrying a failed request to the oracle in seconds, doubles after each retry (default: 5)
+    #[arg(long)]
+    pub upload_retry_delay: Option<u64>,
+
+    /// Length of time to wait on each request to the oracle in seconds, covers connecting and reading the response, timed out requests are retried (default: 30)
+    #[arg(long)]
+    pub oracle_timeout: Option<u64>,
+
+    /// Base url of the station list and metar observations, ie. a local mirror (default: https://aviationweather.gov)
+    #[arg(long)]
+    pub aviation_weather_url: Option<String>,
+
+    /// Base url of the NDFD XML forecasts (default: https://graphical.weather.gov)
+    #[arg(long)]
+    pub ndfd_url: Option<String>,
+
+    /// Base url of the api.weather.gov forecasts (default: https://api.weather.gov)
+    #[arg(long)]
+    pub weather_api_url: Option<String>,
+
+    /// Compression codec used when writing parquet files, one of none, snappy, zstd or gzip (default: none)
+    #[arg(long)]
+    pub parquet_compression: Option<String>,
+
+    /// Longest wait in seconds between runs while the oracle has no events needing weather data, checked from base_url's /stations/active before each run. The wait doubles after each idle run and goes back to the run's interval once events show up (default: none, runs keep their interval)
+    #[arg(long)]
+    pub idle_backoff: Option<u64>,
+
+    /// Lowest wind speed in knots NOAA can report before the value is treated as a bad reading (default: 0)
+    #[arg(long)]
+    pub min_wind_speed: Option<f64>,
+
+    /// Highest wind speed in knots NOAA can report before the value is treated as a bad reading (default: 250)
+    #[arg(long)]
+    pub max_wind_speed: Option<f64>,
+
+    /// Lowest temperature in fahrenheit NOAA can report before the value is treated as a bad reading, observations are checked after converting it to celsius (default: -130)
+    #[arg(long)]
+    pub min_temperature: Option<f64>,
+
+    /// Highest temperature in fahrenheit NOAA can report before the value is treated as a bad reading, observations are checked after converting it to celsius (default: 140)
+    #[arg(long)]
+    pub max_temperature: Option<f64>,
+
+    /// What happens to a wind speed or temperature outside of its bounds, `drop` writes it as null and `clamp` writes the bound it went past (default: drop)
+    #[arg(long)]
+    pub out_of_range_values: Option<String>,
+
+    /// Only fetch the stations used by the oracle's unsigned events, asked for from base_url's /stations/active before each run (default: false, all stations are fetched)
+    #[arg(long)]
+    pub stations_from_oracle: Option<bool>,
+
+    /// Max number of observation requests in flight at once, when set observations are requested in batches of 50 stations instead of downloading NOAA's full metar cache in one request (default: none, the full cache is downloaded)
+    #[arg(long)]
+    pub observation_concurrency: Option<usize>,
+
+    /// Directory to write raw NOAA responses that failed to parse to, for debugging format changes (default: none, nothing is saved)
+    #[arg(long)]
+    pub save_raw_payloads: Option<String>,
+
+    /// Also write the raw NOAA responses that parsed successfully to save_raw_payloads, this grows quickly (default: false)
+    #[arg(long)]
+    pub save_all_raw_payloads: Option<bool>,
+
+    /// Url the run summary is POSTed to as JSON when a run fails or a pipeline's data goes stale (default: none, no alerts are sent)
+    #[arg(long)]
+    pub alert_webhook_url: Option<String>,
+
+    /// Length of time in seconds since a pipeline last uploaded a file before its data is stale and an alert is sent after each run (default: twice the longer of forecast_interval and observation_interval)
+    #[arg(long)]
+    pub alert_stale_after: Option<u64>,
+
+    /// Length of time in seconds since the forecast pipeline last uploaded a file before its data is stale (default: alert_stale_after)
+    #[arg(long)]
+    pub alert_forecast_stale_after: Option<u64>,
+
+    /// Length of time in seconds since the observation pipeline last uploaded a file before its data is stale (default: alert_stale_after)
+    #[arg(long)]
+    pub alert_observation_stale_after: Option<u64>,
+
+    /// File each run's summary, including the min/max/avg/p95 fetch durations, is appended to as a line of JSON (default: none, no metrics are written)
+    #[arg(long)]
+    pub metrics_file: Option<String>,
+}
+
+#[derive(Subcommand, Clone, Debug)]
+pub enum Command {
+    /// Runs the pipeline against synthetic data without calling NOAA, reporting pass/fail per stage
+    Selftest,
+    /// Fetches and logs the current observation of a single station, without running a full pull or uploading it
+    Observation {
+        /// NOAA station id to fetch the observation of, ie. KSAW
+        station_id: String,
+    },
 }
 
 pub fn get_config_info() -> Cli {
@@ -63,7 +212,10 @@ pub fn get_config_info() -> Cli {
             let mut content = String::new();
             file.read_to_string(&mut content)
                 .expect("Failed to read config file");
-            cli = toml::from_str(&content).expect("Failed to deserialize config")
+            // subcommands only come from the command line
+            let command = cli.command.take();
+            cli = toml::from_str(&content).expect("Failed to deserialize config");
+            cli.command = command;
         };
     };
     cli
@@ -99,6 +251,11 @@ pub fn setup_logger(cli: &Cli) -> Logger {
     slog::Logger::root(drain, o!("version" => "0.5"))
 }
 
+/// Wait before checking the limiter again once it's out of tokens
+const RATE_LIMIT_WAIT: Duration = Duration::from_secs(20);
+/// Times the limiter is checked again before a request gives up
+const RATE_LIMIT_RETRIES: usize = 3;
+
 pub struct RateLimiter {
     capacity: usize,
     tokens: f64,
@@ -121,36 +278,126 @@ impl RateLimiter {
         let elapsed_time = now.duration_since(self.last_refill).as_secs_f64();
         let tokens_to_add = elapsed_time * self.refill_rate;
 
-        self.tokens += tokens_to_add.min(self.capacity as f64);
+        self.tokens = (self.tokens + tokens_to_add).min(self.capacity as f64);
         self.last_refill = now;
     }
 
-    fn try_acquire(&mut self, tokens: f64) -> bool {
+    fn try_take(&mut self, tokens: f64) -> bool {
+        self.refill_tokens();
+        if tokens <= self.tokens {
+            self.tokens -= tokens;
+            return true;
+        }
+        false
+    }
+
+    /// Takes `tokens` from the shared limiter, waiting for it to refill up to 3 times. The lock is released
+    /// while waiting so other requests can still take the tokens refilled in the meantime
+    pub async fn try_acquire(limiter: &Mutex<RateLimiter>, tokens: f64) -> bool {
         let mut retries = 0;
 
         loop {
-            self.refill_tokens();
-
-            if tokens <= self.tokens {
-                self.tokens -= tokens;
+            if limiter.lock().await.try_take(tokens) {
                 return true;
-            } else {
-                if retries >= 3 {
-                    // Maximum number of retries reached
-                    return false;
-                }
-
-                retries += 1;
-                thread::sleep(Duration::from_secs(20));
             }
+            if retries >= RATE_LIMIT_RETRIES {
+                // Maximum number of retries reached
+                return false;
+            }
+
+            retries += 1;
+            sleep(RATE_LIMIT_WAIT).await;
+        }
+    }
+}
+
+pub const DEFAULT_AVIATION_WEATHER_URL: &str = "https://aviationweather.gov";
+pub const DEFAULT_NDFD_URL: &str = "https://graphical.weather.gov";
+pub const DEFAULT_WEATHER_API_URL: &str = "https://api.weather.gov";
+
+/// Base urls of the NOAA services data is pulled from, can point at a mirror or a local fixture server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoaaEndpoints {
+    /// Serves the station list and the metar observations
+    pub aviation_weather: String,
+    /// Serves the NDFD XML forecasts
+    pub ndfd: String,
+    /// Serves the api.weather.gov point and gridpoint forecasts
+    pub weather_api: String,
+}
+
+impl Default for NoaaEndpoints {
+    fn default() -> Self {
+        NoaaEndpoints {
+            aviation_weather: String::from(DEFAULT_AVIATION_WEATHER_URL),
+            ndfd: String::from(DEFAULT_NDFD_URL),
+            weather_api: String::from(DEFAULT_WEATHER_API_URL),
         }
     }
 }
 
+impl NoaaEndpoints {
+    pub fn from_cli(cli: &Cli) -> Self {
+        let base_url = |url: &Option<String>, default: &str| {
+            url.as_deref()
+                .unwrap_or(default)
+                .trim_end_matches('/')
+                .to_string()
+        };
+        NoaaEndpoints {
+            aviation_weather: base_url(&cli.aviation_weather_url, DEFAULT_AVIATION_WEATHER_URL),
+            ndfd: base_url(&cli.ndfd_url, DEFAULT_NDFD_URL),
+            weather_api: base_url(&cli.weather_api_url, DEFAULT_WEATHER_API_URL),
+        }
+    }
+
+    pub fn stations_url(&self) -> String {
+        format!("{}/data/cache/stations.cache.xml.gz", self.aviation_weather)
+    }
+
+    pub fn metars_url(&self) -> String {
+        format!("{}/data/cache/metars.cache.xml.gz", self.aviation_weather)
+    }
+
+    pub fn station_metars_url(&self, station_ids: &[String]) -> String {
+        format!(
+            "{}/api/data/metar?ids={}&format=xml",
+            self.aviation_weather,
+            station_ids.join(",")
+        )
+    }
+}
+
+pub const DEFAULT_REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// NOAA didn't respond within the request timeout, the request was retried and can be tried again on the next run
+#[derive(Debug)]
+pub struct RequestTimeout {
+    pub url: String,
+    pub timeout: Duration,
+}
+
+impl Display for RequestTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "request to {} timed out after {} ms",
+            self.url,
+            self.timeout.as_millis()
+        )
+    }
+}
+
+impl std::error::Error for RequestTimeout {}
+
 pub struct XmlFetcher {
     logger: Logger,
     user_agent: String,
     rate_limiter: Arc<Mutex<RateLimiter>>,
+    circuit_breaker: Arc<CircuitBreaker>,
+    endpoints: NoaaEndpoints,
+    raw_payloads: Option<RawPayloads>,
+    request_timeout: Duration,
 }
 
 impl XmlFetcher {
@@ -158,56 +405,140 @@ impl XmlFetcher {
         logger: Logger,
         user_agent: String,
         rate_limiter: Arc<Mutex<RateLimiter>>,
+        circuit_breaker: Arc<CircuitBreaker>,
     ) -> XmlFetcher {
         Self {
             logger,
             user_agent,
             rate_limiter,
+            circuit_breaker,
+            endpoints: NoaaEndpoints::default(),
+            raw_payloads: None,
+            request_timeout: DEFAULT_REQUEST_TIMEOUT,
         }
     }
-    pub async fn fetch_xml(&self, url: &str) -> Result<String, Error> {
-        let mut limiter = self.rate_limiter.lock().await;
-        if !limiter.try_acquire(1.0) {
-            // This happens after waitin and trying 3 times
-            return Err(anyhow!("Rate limit exceeded after retries"));
+
+    pub fn with_request_timeout(mut self, request_timeout: Duration) -> Self {
+        self.request_timeout = request_timeout;
+        self
+    }
+
+    pub fn with_endpoints(mut self, endpoints: NoaaEndpoints) -> Self {
+        self.endpoints = endpoints;
+        self
+    }
+
+    pub fn endpoints(&self) -> &NoaaEndpoints {
+        &self.endpoints
+    }
+
+    pub fn with_raw_payloads(mut self, raw_payloads: Option<RawPayloads>) -> Self {
+        self.raw_payloads = raw_payloads;
+        self
+    }
+
+    /// Saves a fetched response if raw payloads are enabled, call once the response has been parsed
+    pub fn record_payload(&self, kind: PayloadKind, payload: &str, failed: bool) {
+        let Some(raw_payloads) = &self.raw_payloads else {
+            return;
+        };
+        if !raw_payloads.should_save(failed) {
+            return;
+        }
+        match raw_payloads.save(kind, payload, failed) {
+            Ok(file_path) => info!(
+                self.logger,
+                "saved raw {} payload to {}",
+                kind,
+                file_path.display()
+            ),
+            Err(err) => error!(self.logger, "error saving raw {} payload: {}", kind, err),
         }
+    }
 
-        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
-        let client = ClientBuilder::new(Client::builder().user_agent(&self.user_agent).build()?)
-            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-            .build();
+    pub async fn fetch_xml(&self, url: &str) -> Result<String, Error> {
+        self.circuit_breaker
+            .call(|| self.fetch_xml_request(url))
+            .await
+    }
 
-        debug!(self.logger, "requesting: {}", url);
-        let response = client
-            .get(url)
-            .timeout(Duration::from_secs(20))
-            .send()
+    // api.weather.gov responds with geo+json by default, the request is the same as for xml
+    pub async fn fetch_json(&self, url: &str) -> Result<String, Error> {
+        self.circuit_breaker
+            .call(|| self.fetch_xml_request(url))
             .await
-            .map_err(|e| anyhow!("error sending request: {}", e))?;
-        match response.text().await {
-            Ok(xml_content) => Ok(xml_content),
-            Err(e) => Err(anyhow!("error parsing body of request: {}", e)),
-        }
     }
 
     pub async fn fetch_xml_gzip(&self, url: &str) -> Result<String, Error> {
-        let mut limiter = self.rate_limiter.lock().await;
-        if !limiter.try_acquire(1.0) {
+        self.circuit_breaker
+            .call(|| self.fetch_xml_gzip_request(url))
+            .await
+    }
+
+    // The limiter is only held while taking a token so requests can be in flight at the same time
+    async fn acquire_rate_limit(&self) -> Result<(), Error> {
+        if !RateLimiter::try_acquire(&self.rate_limiter, 1.0).await {
             // This happens after waiting and trying 3 times
-            return Err(anyhow!("Rate limit exceeded after retries"));
+            return Err(Error::new(RateLimited {
+                reason: String::from("rate limit exceeded after retries"),
+            }));
         }
+        Ok(())
+    }
+
+    // Timeouts are transient to the retry middleware, so a hung connection is retried before giving up
+    fn client(&self) -> Result<ClientWithMiddleware, Error> {
         let retry_policy = ExponentialBackoff::builder().build_with_max_retries(3);
-        let client = ClientBuilder::new(Client::builder().user_agent(&self.user_agent).build()?)
+        let client = Client::builder()
+            .user_agent(&self.user_agent)
+            .connect_timeout(self.request_timeout)
+            .timeout(self.request_timeout)
+            .build()?;
+        Ok(ClientBuilder::new(client)
             .with(RetryTransientMiddleware::new_with_policy(retry_policy))
-            .build();
+            .build())
+    }
+
+    fn request_error(&self, url: &str, action: &str, err: reqwest::Error) -> Error {
+        if err.is_timeout() {
+            return Error::new(RequestTimeout {
+                url: url.to_string(),
+                timeout: self.request_timeout,
+            });
+        }
+        anyhow!("error {} request: {}", action, err)
+    }
+
+    async fn send_request(&self, url: &str) -> Result<reqwest::Response, Error> {
+        self.acquire_rate_limit().await?;
+        let client = self.client()?;
 
         debug!(self.logger, "requesting: {}", url);
-        let response = client
-            .get(url)
-            .timeout(Duration::from_secs(1))
-            .send()
+        let response = client.get(url).send().await.map_err(|e| match e {
+            reqwest_middleware::Error::Reqwest(err) => self.request_error(url, "sending", err),
+            reqwest_middleware::Error::Middleware(err) => {
+                anyhow!("error sending request: {}", err)
+            }
+        })?;
+        // Still 429 once the retry middleware gave up
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            return Err(Error::new(RateLimited {
+                reason: format!("NOAA responded {} to {}", response.status(), url),
+            }));
+        }
+        Ok(response)
+    }
+
+    async fn fetch_xml_request(&self, url: &str) -> Result<String, Error> {
+        let response = self.send_request(url).await?;
+        response
+            .text()
             .await
-            .map_err(|e| anyhow!("error sending request: {}", e))?;
+            .map_err(|e| self.request_error(url, "parsing body of", e))
+    }
+
+    async fn fetch_xml_gzip_request(&self, url: &str) -> Result<String, Error> {
+        let response = self.send_request(url).await?;
         if !response.status().is_success() {
             return Err(anyhow!("error response from request"));
         }
@@ -260,3 +591,123 @@ pub fn create_folder(root_path: &str, logger: &Logger) {
 pub fn subfolder_exists(subfolder_path: &str) -> bool {
     fs::metadata(subfolder_path).is_ok()
 }
+
+#[cfg(test)]
+mod test {
+    use super::{RateLimiter, RequestTimeout, XmlFetcher};
+    use crate::{
+        test_server::{spawn_server, MockServer, Response},
+        CircuitBreaker, CircuitState, RateLimited,
+    };
+    use slog::{o, Discard, Logger};
+    use std::{sync::Arc, time::Duration};
+    use tokio::{
+        sync::Mutex,
+        task::yield_now,
+        time::{sleep, Instant},
+    };
+
+    /// Accepts requests but waits `delay` before answering each of them
+    async fn spawn_slow_api(delay: Duration) -> MockServer {
+        spawn_server(move |_, _| async move {
+            sleep(delay).await;
+            Response::ok("<response></response>")
+        })
+        .await
+    }
+
+    fn fetcher(request_timeout: Duration) -> XmlFetcher {
+        let logger = Logger::root(Discard, o!());
+        XmlFetcher::new(
+            logger.clone(),
+            String::from("noaa-data-pipeline/test"),
+            Arc::new(Mutex::new(RateLimiter::new(10, 10.0))),
+            Arc::new(CircuitBreaker::new(logger, 5, Duration::from_secs(300))),
+        )
+        .with_request_timeout(request_timeout)
+    }
+
+    #[tokio::test]
+    async fn times_out_and_retries_hung_requests() {
+        let api = spawn_slow_api(Duration::from_secs(30)).await;
+        let url = format!("{}/api/data/metar?ids=KSAW&format=xml", api.url());
+        let request_timeout = Duration::from_millis(200);
+
+        let started = Instant::now();
+        let err = fetcher(request_timeout).fetch_xml(&url).await.unwrap_err();
+
+        let timeout = err.downcast_ref::<RequestTimeout>().unwrap();
+        assert_eq!(timeout.url, url);
+        assert_eq!(timeout.timeout, request_timeout);
+        // The first attempt and its 3 retries each gave up at the timeout instead of waiting on the server
+        assert_eq!(api.requests(), 4);
+        assert!(started.elapsed() < Duration::from_secs(30));
+    }
+
+    #[tokio::test]
+    async fn responses_within_the_timeout_are_returned() {
+        let api = spawn_slow_api(Duration::from_millis(50)).await;
+
+        let body = fetcher(Duration::from_secs(5))
+            .fetch_xml(&api.url())
+            .await
+            .unwrap();
+
+        assert_eq!(body, "<response></response>");
+        assert_eq!(api.requests(), 1);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn releases_the_limiter_while_waiting_for_tokens() {
+        let limiter = Arc::new(Mutex::new(RateLimiter::new(1, 0.1)));
+        assert!(RateLimiter::try_acquire(&limiter, 1.0).await);
+
+        let started = Instant::now();
+        let waiting = tokio::spawn({
+            let limiter = limiter.clone();
+            async move { RateLimiter::try_acquire(&limiter, 1.0).await }
+        });
+        // Let the spawned request find the limiter empty and start waiting on it
+        yield_now().await;
+        assert!(limiter.try_lock().is_ok());
+
+        assert!(waiting.await.unwrap());
+        assert_eq!(started.elapsed(), Duration::from_secs(20));
+        // The tokens refilled while waiting are capped at the capacity
+        assert_eq!(limiter.lock().await.tokens, 0.0);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn gives_up_once_the_limiter_stays_empty() {
+        let limiter = Mutex::new(RateLimiter::new(1, 0.0));
+        assert!(RateLimiter::try_acquire(&limiter, 1.0).await);
+
+        let started = Instant::now();
+        assert!(!RateLimiter::try_acquire(&limiter, 1.0).await);
+        assert_eq!(started.elapsed(), Duration::from_secs(60));
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn rate_limited_requests_do_not_open_the_circuit() {
+        let logger = Logger::root(Discard, o!());
+        let breaker = Arc::new(CircuitBreaker::new(
+            logger.clone(),
+            1,
+            Duration::from_secs(300),
+        ));
+        let fetcher = XmlFetcher::new(
+            logger,
+            String::from("noaa-data-pipeline/test"),
+            Arc::new(Mutex::new(RateLimiter::new(0, 0.0))),
+            breaker.clone(),
+        );
+
+        let err = fetcher
+            .fetch_xml("http://127.0.0.1:1/api/data/metar")
+            .await
+            .unwrap_err();
+
+        assert!(err.downcast_ref::<RateLimited>().is_some());
+        assert_eq!(breaker.state(), CircuitState::Closed);
+    }
+}
@@ -0,0 +1,29 @@
+use daemon::run_selftest;
+use slog::{o, Discard, Logger};
+
+#[test]
+fn selftest_passes_every_stage_offline() {
+    let logger = Logger::root(Discard, o!());
+    let report = run_selftest(&logger);
+
+    let stages: Vec<String> = report
+        .stages
+        .iter()
+        .map(|stage| stage.to_string())
+        .collect();
+    assert!(report.passed(), "selftest failed: {:?}", stages);
+    assert_eq!(
+        report
+            .stages
+            .iter()
+            .map(|stage| stage.name)
+            .collect::<Vec<_>>(),
+        vec![
+            "flatten forecasts",
+            "convert forecasts",
+            "convert observations",
+            "write parquet",
+            "read parquet"
+        ]
+    );
+}
@@ -0,0 +1,95 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+fn main() {
+    // Allow the release pipeline to pass in the sha when building outside of a git checkout
+    let git_sha = std::env::var("GIT_SHA").ok().or_else(|| {
+        Command::new("git")
+            .args(["rev-parse", "--short", "HEAD"])
+            .output()
+            .ok()
+            .filter(|output| output.status.success())
+            .and_then(|output| String::from_utf8(output.stdout).ok())
+            .map(|sha| sha.trim().to_string())
+    });
+    println!(
+        "cargo:rustc-env=GIT_SHA={}",
+        git_sha.unwrap_or_else(|| String::from("unknown"))
+    );
+
+    let build_time = OffsetDateTime::now_utc()
+        .format(&Rfc3339)
+        .unwrap_or_else(|_| String::from("unknown"));
+    println!("cargo:rustc-env=BUILD_TIME={}", build_time);
+
+    println!("cargo:rerun-if-env-changed=GIT_SHA");
+    watch_git_head();
+    // The ui files are compiled into the binary with the `embed-ui` feature
+    println!("cargo:rerun-if-changed=ui");
+    if env::var_os("CARGO_FEATURE_EMBED_UI").is_some() {
+        embed_ui_assets();
+    }
+}
+
+/// Reruns when HEAD moves to another commit, HEAD itself only changes on checkout while a commit on the current
+/// branch updates the ref it points to, or `packed-refs` once the ref has been packed
+fn watch_git_head() {
+    let git_dir = Path::new("../../.git");
+    let head = git_dir.join("HEAD");
+    println!("cargo:rerun-if-changed={}", head.display());
+    // Missing paths are treated as always changed, only watch the ones in this checkout
+    let branch_ref = fs::read_to_string(&head).ok().and_then(|head| {
+        head.strip_prefix("ref: ")
+            .map(|branch_ref| git_dir.join(branch_ref.trim()))
+    });
+    for path in branch_ref.into_iter().chain([git_dir.join("packed-refs")]) {
+        if path.exists() {
+            println!("cargo:rerun-if-changed={}", path.display());
+        }
+    }
+}
+
+/// Writes `ui_assets.rs` to OUT_DIR, a table of every file under `ui` by its path relative to the folder
+fn embed_ui_assets() {
+    let ui_dir = Path::new(&env::var("CARGO_MANIFEST_DIR").unwrap()).join("ui");
+    let mut files = vec![];
+    collect_files(&ui_dir, &mut files);
+    files.sort();
+
+    let mut assets = String::from("static UI_ASSETS: &[(&str, &[u8])] = &[\n");
+    for file in files {
+        let relative_path = file
+            .strip_prefix(&ui_dir)
+            .unwrap()
+            .components()
+            .map(|component| component.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/");
+        assets.push_str(&format!(
+            "    ({:?}, include_bytes!({:?})),\n",
+            relative_path,
+            file.display().to_string()
+        ));
+    }
+    assets.push_str("];\n");
+
+    let out_file = Path::new(&env::var("OUT_DIR").unwrap()).join("ui_assets.rs");
+    fs::write(out_file, assets).expect("failed to write embedded ui assets");
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) {
+    let entries = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("failed to read ui folder {}: {}", dir.display(), e));
+    for entry in entries {
+        let path = entry.expect("failed to read ui folder entry").path();
+        if path.is_dir() {
+            collect_files(&path, files);
+        } else {
+            files.push(path);
+        }
+    }
+}
@@ -0,0 +1,168 @@
+use crate::EventAnnouncement;
+use std::{
+    collections::{HashMap, VecDeque},
+    sync::Arc,
+};
+use uuid::Uuid;
+
+/// Most announcements kept at once
+pub const DEFAULT_ANNOUNCEMENT_CACHE_EVENTS: usize = 256;
+/// Most outcome messages kept across all the cached announcements, an announcement with more than this is
+/// never cached
+pub const DEFAULT_ANNOUNCEMENT_CACHE_OUTCOMES: usize = 250_000;
+
+/// Announcements already built per event, an event's outcome messages grow with every permutation of its
+/// entries and places so they're only generated once. Bounded by both the number of events and their total
+/// outcome messages, the least recently read announcement is evicted first
+pub struct AnnouncementCache {
+    announcements: HashMap<Uuid, Arc<EventAnnouncement>>,
+    // Least recently read first
+    order: VecDeque<Uuid>,
+    outcomes: usize,
+    max_events: usize,
+    max_outcomes: usize,
+}
+
+impl Default for AnnouncementCache {
+    fn default() -> Self {
+        Self::new(
+            DEFAULT_ANNOUNCEMENT_CACHE_EVENTS,
+            DEFAULT_ANNOUNCEMENT_CACHE_OUTCOMES,
+        )
+    }
+}
+
+impl AnnouncementCache {
+    pub fn new(max_events: usize, max_outcomes: usize) -> Self {
+        Self {
+            announcements: HashMap::new(),
+            order: VecDeque::new(),
+            outcomes: 0,
+            max_events,
+            max_outcomes,
+        }
+    }
+
+    pub fn get(&mut self, id: &Uuid) -> Option<Arc<EventAnnouncement>> {
+        let announcement = self.announcements.get(id).cloned()?;
+        self.touch(id);
+        Some(announcement)
+    }
+
+    pub fn insert(&mut self, announcement: Arc<EventAnnouncement>) {
+        self.remove(&announcement.id);
+        let outcomes = announcement.outcome_messages.len();
+        if self.max_events == 0 || outcomes > self.max_outcomes {
+            return;
+        }
+        while self.order.len() >= self.max_events || self.outcomes + outcomes > self.max_outcomes {
+            let Some(oldest) = self.order.front().copied() else {
+                break;
+            };
+            self.remove(&oldest);
+        }
+        self.outcomes += outcomes;
+        self.order.push_back(announcement.id);
+        self.announcements.insert(announcement.id, announcement);
+    }
+
+    pub fn remove(&mut self, id: &Uuid) {
+        if let Some(announcement) = self.announcements.remove(id) {
+            self.outcomes -= announcement.outcome_messages.len();
+            self.order.retain(|cached| cached != id);
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.announcements.len()
+    }
+
+    fn touch(&mut self, id: &Uuid) {
+        if let Some(position) = self.order.iter().position(|cached| cached == id) {
+            self.order.remove(position);
+            self.order.push_back(*id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dlctix::{
+        musig2::secp256k1::{PublicKey, Secp256k1, SecretKey},
+        secp::Point,
+        EventLockingConditions,
+    };
+
+    fn announcement(outcomes: usize) -> Arc<EventAnnouncement> {
+        let point = Point::from(PublicKey::from_secret_key(
+            &Secp256k1::new(),
+            &SecretKey::from_slice(&[1; 32]).unwrap(),
+        ));
+        Arc::new(EventAnnouncement {
+            id: Uuid::now_v7(),
+            oracle_pubkey: point,
+            nonce_point: point,
+            outcome_messages: vec![String::from("00"); outcomes],
+            event_announcement: EventLockingConditions {
+                locking_points: vec![],
+                expiry: None,
+            },
+        })
+    }
+
+    #[test]
+    fn evicts_the_least_recently_read_announcement() {
+        let mut cache = AnnouncementCache::new(2, 100);
+        let first = announcement(1);
+        let second = announcement(1);
+        cache.insert(first.clone());
+        cache.insert(second.clone());
+        assert!(cache.get(&first.id).is_some());
+
+        let third = announcement(1);
+        cache.insert(third.clone());
+
+        assert_eq!(cache.len(), 2);
+        assert!(cache.get(&second.id).is_none());
+        assert!(cache.get(&first.id).is_some());
+        assert!(cache.get(&third.id).is_some());
+    }
+
+    #[test]
+    fn bounds_the_total_outcome_messages() {
+        let mut cache = AnnouncementCache::new(10, 10);
+        let first = announcement(6);
+        cache.insert(first.clone());
+        let second = announcement(6);
+        cache.insert(second.clone());
+        assert!(cache.get(&first.id).is_none());
+        assert!(cache.get(&second.id).is_some());
+
+        let too_big = announcement(11);
+        cache.insert(too_big.clone());
+        assert!(cache.get(&too_big.id).is_none());
+        assert!(cache.get(&second.id).is_some());
+    }
+
+    #[test]
+    fn removes_an_announcement() {
+        let mut cache = AnnouncementCache::new(10, 10);
+        let first = announcement(6);
+        cache.insert(first.clone());
+        cache.remove(&first.id);
+        assert_eq!(cache.len(), 0);
+
+        let second = announcement(10);
+        cache.insert(second.clone());
+        assert!(cache.get(&second.id).is_some());
+    }
+
+    #[test]
+    fn returns_the_cached_announcement_without_copying_it() {
+        let mut cache = AnnouncementCache::default();
+        let first = announcement(1);
+        cache.insert(first.clone());
+        assert!(Arc::ptr_eq(&cache.get(&first.id).unwrap(), &first));
+    }
+}
@@ -1,4 +1,4 @@
-use crate::{file_access, weather_data};
+use crate::{file_access, service_unavailable, weather_data, WEATHER_UNAVAILABLE_RETRY_AFTER_SECS};
 use axum::{
     response::{IntoResponse, Response},
     Json,
@@ -22,6 +22,10 @@ impl IntoResponse for AppError {
     fn into_response(self) -> Response {
         error!("error handling request: {}", self.to_string());
 
+        if let AppError::WeatherData(weather_data::Error::Unavailable(_)) = self.borrow() {
+            return service_unavailable(self.to_string(), WEATHER_UNAVAILABLE_RETRY_AFTER_SECS);
+        }
+
         let (status, error_message) = match self.borrow() {
             AppError::Request(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             AppError::WeatherData(e) => match e {
@@ -0,0 +1,39 @@
+use crate::oracle::Oracle;
+use log::{error, info};
+use rand::Rng;
+use std::{sync::Arc, time::Duration};
+use time::OffsetDateTime;
+use tokio::time::sleep;
+
+pub const DEFAULT_AUTO_SIGN_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Signs auto sign events as their signing_date passes, waking up at the next signing_date or after
+/// `max_wait` so events created in the meantime are picked up
+pub async fn run_auto_signer(oracle: Arc<Oracle>, max_wait: Duration) {
+    info!(
+        "starting auto signer, checking for events at least every {:?}",
+        max_wait
+    );
+    loop {
+        let etl_process_id: usize = rand::thread_rng().gen();
+        if let Err(e) = oracle.auto_sign_events(etl_process_id).await {
+            error!(
+                "failed auto signing events in etl process {}: {}",
+                etl_process_id, e
+            );
+        }
+
+        let wait = match oracle.next_auto_sign_date().await {
+            Ok(Some(signing_date)) => (signing_date - OffsetDateTime::now_utc())
+                .try_into()
+                .map(|until_signing: Duration| until_signing.min(max_wait))
+                .unwrap_or(Duration::ZERO),
+            Ok(None) => max_wait,
+            Err(e) => {
+                error!("failed getting next auto sign date: {}", e);
+                max_wait
+            }
+        };
+        sleep(wait).await;
+    }
+}
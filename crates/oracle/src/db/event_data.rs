@@ -1,44 +1,358 @@
-use super::{run_migrations, CreateEventData, Event, EventFilter, EventSummary};
+use super::{
+    attestation_to_sql, get_status, is_event_full, normalize_station_id, parse_sql_timestamp,
+    run_migrations, to_sql_timestamp, CreateEventData, Event, EventFilter, EventSignStatus,
+    EventStatus, EventStatusGroup, EventSummary, IdGenerator, UuidV7Generator,
+};
 
 use crate::{
-    ActiveEvent, Forecasted, Observed, SignEvent, ToRawSql, ValueOptions, Weather, WeatherChoices,
-    WeatherChoicesWithEntry, WeatherEntry,
+    ActiveEvent, ForecastAccuracy, Forecasted, Observed, RawObservedTemps, SignEvent, ToRawSql,
+    ValueOptions, Weather, WeatherChoices, WeatherChoicesWithEntry, WeatherEntry,
 };
 use dlctix::musig2::secp256k1::XOnlyPublicKey;
-use duckdb::types::Value;
+use duckdb::types::{Type, Value};
 use duckdb::{params, params_from_iter, AccessMode, Config, Connection};
-use log::{debug, info};
+use log::{debug, error, info, warn};
 use regex::Regex;
-use scooby::postgres::{insert_into, select, update, with, Aliasable, Joinable, Parameters};
+use scooby::postgres::{insert_into, select, update, Aliasable, Joinable, Parameters};
 use serde_json::to_vec;
 use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::time::Duration as StdDuration;
-use time::format_description::well_known::Rfc3339;
 use time::OffsetDateTime;
+use tokio::sync::{Mutex as AsyncMutex, OwnedMutexGuard};
 use tokio::time::timeout;
 use uuid::Uuid;
 
+pub const DEFAULT_EVENT_DB_FILE: &str = "events.db3";
+
+// DuckDB's message when another process holds the database file lock
+const LOCK_ERROR: &str = "Could not set lock on file";
+
+/// True when the connection retries gave up because the database file stayed locked
+pub fn is_database_locked(e: &duckdb::Error) -> bool {
+    match e {
+        duckdb::Error::DuckDBFailure(ffi_error, _)
+            if ffi_error.code == duckdb::ErrorCode::DatabaseLocked =>
+        {
+            true
+        }
+        _ => e.to_string().contains(LOCK_ERROR),
+    }
+}
+
+#[derive(thiserror::Error, Debug)]
+pub enum EventDbError {
+    #[error("event db path {0} is a file, it needs to be a directory")]
+    NotADirectory(String),
+    #[error("Failed to create event db directory {0}: {1}")]
+    CreateDirectory(String, std::io::Error),
+    #[error("Failed to open event db: {0}")]
+    Open(#[from] duckdb::Error),
+    #[error("invalid duckdb extension name {0:?}, expected only letters, digits and underscores")]
+    InvalidExtension(String),
+}
+
+/// Why weather pushed as parquet wasn't stored
+#[derive(thiserror::Error, Debug)]
+pub enum WeatherParquetError {
+    #[error("the parquet extension isn't loaded, add it to the event db extensions")]
+    ParquetNotLoaded,
+    #[error("unreadable weather parquet: {0}")]
+    Unreadable(duckdb::Error),
+    #[error("invalid weather parquet: {0}")]
+    Invalid(String),
+    #[error("Failed to store weather parquet: {0}")]
+    Query(#[from] duckdb::Error),
+}
+
+/// Why an entry wasn't added to its event
+#[derive(thiserror::Error, Debug)]
+pub enum AddEntryError {
+    #[error("event {0} is full, no more entries are allowed")]
+    EventFull(Uuid),
+    #[error("Failed to add entry: {0}")]
+    Query(#[from] duckdb::Error),
+}
+
+/// Which parts of an observation are stored next to its rounded values
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ObservationStorage {
+    /// Keep the offset the observation was reported in
+    pub original_offsets: bool,
+    /// Keep the temperatures as reported, before rounding
+    pub raw_temps: bool,
+}
+
+/// Creates the directory the event db is kept in along with any missing parents
+pub fn create_event_db_dir(path: &str) -> Result<(), EventDbError> {
+    let dir = Path::new(path);
+    if dir.is_dir() {
+        return Ok(());
+    }
+    if dir.exists() {
+        return Err(EventDbError::NotADirectory(path.to_string()));
+    }
+    fs::create_dir_all(dir).map_err(|e| EventDbError::CreateDirectory(path.to_string(), e))?;
+    info!("event db directory created: {}", path);
+    Ok(())
+}
+
+/// Per event async locks, dropped once nothing is waiting on them so the map doesn't grow with every event
+#[derive(Default)]
+struct EventLocks(Mutex<HashMap<Uuid, Arc<AsyncMutex<()>>>>);
+
+impl EventLocks {
+    /// Waits for the event's lock, it's held until the returned guard is dropped
+    async fn lock(&self, event_id: Uuid) -> EventLockGuard<'_> {
+        let lock = {
+            let mut locks = self.locks();
+            // A caller cancelled while waiting leaves its lock behind with nothing else holding it
+            locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+            locks.entry(event_id).or_default().clone()
+        };
+        EventLockGuard {
+            locks: self,
+            event_id,
+            guard: Some(lock.lock_owned().await),
+        }
+    }
+
+    fn locks(&self) -> MutexGuard<'_, HashMap<Uuid, Arc<AsyncMutex<()>>>> {
+        self.0
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    #[cfg(test)]
+    fn len(&self) -> usize {
+        self.locks().len()
+    }
+}
+
+/// Releases the event's lock when dropped, also when the future holding it is cancelled
+struct EventLockGuard<'a> {
+    locks: &'a EventLocks,
+    event_id: Uuid,
+    guard: Option<OwnedMutexGuard<()>>,
+}
+
+impl Drop for EventLockGuard<'_> {
+    fn drop(&mut self) {
+        let Some(guard) = self.guard.take() else {
+            return;
+        };
+        // Held while unlocking so no other caller can pick up the lock between the unlock and the count check
+        let mut locks = self.locks.locks();
+        let lock = Arc::clone(OwnedMutexGuard::mutex(&guard));
+        drop(guard);
+        // Only the map and this guard hold the lock, so no other caller is waiting on it
+        if Arc::strong_count(&lock) == 2 {
+            locks.remove(&self.event_id);
+        }
+    }
+}
+
+/// Recounts every event's entries and fixes any `total_entries` counter that drifted from them, returns how
+/// many counters were fixed
+pub fn reconcile_total_entries(conn: &Connection) -> Result<usize, duckdb::Error> {
+    let fixed = conn.execute(
+        "UPDATE events_settings SET total_entries = entry_counts.total_entries
+        FROM (
+            SELECT events_settings.event_id, COUNT(events_entries.id) AS total_entries
+            FROM events_settings LEFT JOIN events_entries ON events_entries.event_id = events_settings.event_id
+            GROUP BY events_settings.event_id
+        ) AS entry_counts
+        WHERE events_settings.event_id = entry_counts.event_id
+            AND events_settings.total_entries IS DISTINCT FROM entry_counts.total_entries",
+        [],
+    )?;
+    if fixed > 0 {
+        warn!("reconciled total_entries of {} events", fixed);
+    }
+    Ok(fixed)
+}
+
+// Copies the pushed file into the connection's `pushed_weather` temp table, numbering the rows, and returns
+// their station ids in row order. A file that can't be read or is missing a column is `Unreadable`
+fn read_pushed_weather(
+    conn: &Connection,
+    file_path: &str,
+) -> Result<Vec<String>, WeatherParquetError> {
+    let create_pushed_weather = format!(
+        "CREATE TEMP TABLE pushed_weather AS
+        SELECT
+            row_number() OVER () AS row_number,
+            station_id::VARCHAR AS station_id,
+            forecast_date::DATE AS forecast_date,
+            forecast_start_time::VARCHAR AS forecast_start_time,
+            forecast_end_time::VARCHAR AS forecast_end_time,
+            forecast_temp_low::BIGINT AS forecast_temp_low,
+            forecast_temp_high::BIGINT AS forecast_temp_high,
+            forecast_wind_speed::BIGINT AS forecast_wind_speed,
+            observation_start_time::VARCHAR AS observation_start_time,
+            observation_end_time::VARCHAR AS observation_end_time,
+            observation_temp_low::DOUBLE AS observation_temp_low,
+            observation_temp_high::DOUBLE AS observation_temp_high,
+            observation_wind_speed::BIGINT AS observation_wind_speed,
+            observation_start_time IS NOT NULL AND observation_end_time IS NOT NULL
+                AND observation_temp_low IS NOT NULL AND observation_temp_high IS NOT NULL
+                AND observation_wind_speed IS NOT NULL AS has_observation
+        FROM read_parquet('{}')",
+        file_path.replace('\'', "''")
+    );
+    debug!("query_str: {}", create_pushed_weather);
+    conn.execute_batch(&create_pushed_weather)
+        .map_err(WeatherParquetError::Unreadable)?;
+
+    let missing_forecasts: i64 = conn.query_row(
+        "SELECT COUNT(*) FROM pushed_weather WHERE station_id IS NULL OR forecast_date IS NULL
+            OR forecast_start_time IS NULL OR forecast_end_time IS NULL OR forecast_temp_low IS NULL
+            OR forecast_temp_high IS NULL OR forecast_wind_speed IS NULL",
+        [],
+        |row| row.get(0),
+    )?;
+    if missing_forecasts > 0 {
+        return Err(WeatherParquetError::Invalid(format!(
+            "{} rows are missing a station_id or forecast value",
+            missing_forecasts
+        )));
+    }
+    let mut stmt = conn.prepare("SELECT station_id FROM pushed_weather ORDER BY row_number")?;
+    let mut rows = stmt.query([])?;
+    let mut station_ids = vec![];
+    while let Some(row) = rows.next()? {
+        station_ids.push(row.get::<usize, String>(0)?);
+    }
+    Ok(station_ids)
+}
+
+// Points each station at the weather row that overrides it, a station listed twice keeps the later row
+fn insert_weather_overrides(
+    conn: &Connection,
+    event_id: Uuid,
+    overrides: Vec<(String, Uuid)>,
+) -> Result<(), duckdb::Error> {
+    let mut latest: HashMap<String, Uuid> = HashMap::new();
+    for (station_id, weather_id) in overrides {
+        latest.insert(station_id.to_uppercase(), weather_id);
+    }
+    if latest.is_empty() {
+        return Ok(());
+    }
+    let query_str = format!(
+        "INSERT OR REPLACE INTO events_weather_overrides (event_id, station_id, weather_id) VALUES {}",
+        vec!["(?,?,?)"; latest.len()].join(",")
+    );
+    debug!("query_str: {}", query_str);
+    let insert_values: Vec<String> = latest
+        .into_iter()
+        .flat_map(|(station_id, weather_id)| {
+            vec![event_id.to_string(), station_id, weather_id.to_string()]
+        })
+        .collect();
+    let mut stmt = conn.prepare(&query_str)?;
+    stmt.execute(params_from_iter(insert_values.iter()))?;
+    Ok(())
+}
+
 pub struct EventData {
     connection_path: String,
     retry_duration: StdDuration,
     retry_max_attemps: i32,
+    // Serializes weather updates per event, different events can still be updated in parallel
+    event_weather_locks: EventLocks,
+    // Serializes entries per event so the total_entries counter is never raced
+    event_entry_locks: EventLocks,
+    id_generator: Arc<dyn IdGenerator>,
+    // DuckDB extensions loaded on every connection
+    extensions: Vec<String>,
 }
 
 impl EventData {
-    pub fn new(path: &str) -> Result<Self, duckdb::Error> {
-        let connection_path = format!("{}/events.db3", path);
+    pub fn new(path: &str) -> Result<Self, EventDbError> {
+        Self::new_with_id_generator(path, Arc::new(UuidV7Generator))
+    }
+
+    pub fn new_with_id_generator(
+        path: &str,
+        id_generator: Arc<dyn IdGenerator>,
+    ) -> Result<Self, EventDbError> {
+        Self::open(path, DEFAULT_EVENT_DB_FILE, id_generator)
+    }
+
+    /// Opens `{path}/{file_name}`, so separate oracle instances can keep their events in the same directory
+    pub fn new_with_file_name(path: &str, file_name: &str) -> Result<Self, EventDbError> {
+        Self::open(path, file_name, Arc::new(UuidV7Generator))
+    }
+
+    /// `path` and any missing parents are created, an existing file at `path` is an error
+    fn open(
+        path: &str,
+        file_name: &str,
+        id_generator: Arc<dyn IdGenerator>,
+    ) -> Result<Self, EventDbError> {
+        create_event_db_dir(path)?;
+        let connection_path = format!("{}/{}", path, file_name);
         let mut conn = Connection::open(connection_path.clone())?;
         run_migrations(&mut conn)?;
+        reconcile_total_entries(&conn)?;
         Ok(Self {
             connection_path,
             retry_duration: StdDuration::from_millis(100),
             retry_max_attemps: 5,
+            event_weather_locks: EventLocks::default(),
+            event_entry_locks: EventLocks::default(),
+            id_generator,
+            extensions: vec![],
         })
     }
 
+    /// DuckDB extensions to `INSTALL` once and `LOAD` on every connection, ie. `parquet` so queries can use
+    /// `read_parquet`. An extension that fails to install or load is logged and left out, a name that isn't a
+    /// valid extension name is an error
+    pub fn with_extensions(mut self, extensions: &[String]) -> Result<Self, EventDbError> {
+        if extensions.is_empty() {
+            return Ok(self);
+        }
+        let conn = Connection::open(self.connection_path.clone())?;
+        for extension in extensions {
+            let extension = extension.trim().to_ascii_lowercase();
+            if extension.is_empty()
+                || !extension
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_')
+            {
+                return Err(EventDbError::InvalidExtension(extension));
+            }
+            match conn.execute_batch(&format!("INSTALL {0}; LOAD {0};", extension)) {
+                Ok(()) => {
+                    info!("duckdb extension loaded: {}", extension);
+                    self.extensions.push(extension);
+                }
+                Err(e) => error!("failed to load duckdb extension {}: {}", extension, e),
+            }
+        }
+        Ok(self)
+    }
+
+    pub fn extensions(&self) -> &[String] {
+        &self.extensions
+    }
+
+    fn load_extensions(&self, conn: &Connection) -> Result<(), duckdb::Error> {
+        for extension in &self.extensions {
+            conn.execute_batch(&format!("LOAD {};", extension))?;
+        }
+        Ok(())
+    }
+
     async fn new_readonly_connection(&self) -> Result<Connection, duckdb::Error> {
         let config = Config::default().access_mode(AccessMode::ReadOnly)?;
-        Connection::open_with_flags(self.connection_path.clone(), config)
+        let conn = Connection::open_with_flags(self.connection_path.clone(), config)?;
+        self.load_extensions(&conn)?;
+        Ok(conn)
     }
 
     pub async fn new_readonly_connection_retry(&self) -> Result<Connection, duckdb::Error> {
@@ -47,9 +361,7 @@ impl EventData {
             match timeout(self.retry_duration, self.new_readonly_connection()).await {
                 Ok(Ok(connection)) => return Ok(connection),
                 Ok(Err(e)) => {
-                    if attempt >= self.retry_max_attemps
-                        || !e.to_string().contains("Could not set lock on file")
-                    {
+                    if attempt >= self.retry_max_attemps || !e.to_string().contains(LOCK_ERROR) {
                         return Err(e);
                     }
                     info!("Retrying: {}", e);
@@ -70,7 +382,9 @@ impl EventData {
 
     async fn new_write_connection(&self) -> Result<Connection, duckdb::Error> {
         let config = Config::default().access_mode(AccessMode::ReadWrite)?;
-        Connection::open_with_flags(self.connection_path.clone(), config)
+        let conn = Connection::open_with_flags(self.connection_path.clone(), config)?;
+        self.load_extensions(&conn)?;
+        Ok(conn)
     }
 
     pub async fn new_write_connection_retry(&self) -> Result<Connection, duckdb::Error> {
@@ -79,9 +393,7 @@ impl EventData {
             match timeout(self.retry_duration, self.new_write_connection()).await {
                 Ok(Ok(connection)) => return Ok(connection),
                 Ok(Err(e)) => {
-                    if attempt >= self.retry_max_attemps
-                        || !e.to_string().contains("Could not set lock on file")
-                    {
+                    if attempt >= self.retry_max_attemps || !e.to_string().contains(LOCK_ERROR) {
                         return Err(e);
                     }
                     info!("Retrying: {}", e);
@@ -120,21 +432,273 @@ impl EventData {
         Ok(())
     }
 
+    pub async fn update_oracle_metadata(
+        &self,
+        pubkey: XOnlyPublicKey,
+    ) -> Result<(), duckdb::Error> {
+        let pubkey_raw = pubkey.serialize().to_vec();
+        let name = String::from("4casttruth");
+        let mut conn = self.new_write_connection_retry().await?;
+        // duckdb runs updates to indexed columns as a delete + insert and rejects re-inserting the same unique
+        // values in one transaction, so the row is written to a fresh copy of the table that replaces the old one.
+        // Doing it in one transaction means a failed rotation never leaves the oracle without its metadata
+        let tx = conn.transaction()?;
+        tx.execute_batch(
+            "CREATE TABLE oracle_metadata_next
+            (
+                pubkey             BLOB     NOT NULL UNIQUE PRIMARY KEY,
+                name               TEXT      NOT NULL UNIQUE,
+                created_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                updated_at         TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+                singleton_constant BOOLEAN   NOT NULL DEFAULT TRUE, -- make sure there is only one row
+                CONSTRAINT one_row_check UNIQUE (singleton_constant)
+            );",
+        )?;
+        tx.execute(
+            "INSERT INTO oracle_metadata_next (pubkey,name) VALUES(?,?)",
+            [pubkey_raw, name.into()],
+        )?;
+        tx.execute_batch(
+            "DROP TABLE oracle_metadata;
+            ALTER TABLE oracle_metadata_next RENAME TO oracle_metadata;",
+        )?;
+        tx.commit()
+    }
+
     // Call as an ETL process to update the weather for running events
     pub async fn update_weather_station_data(
         &self,
         event_id: Uuid,
         weather: Vec<Weather>,
     ) -> Result<(), duckdb::Error> {
+        let _guard = self.event_weather_locks.lock(event_id).await;
         //1) grab events that are using this weather data
         //2) add new weather data to table
         let weather_ids = self.add_weather_readings(weather).await?;
-
         //3) create join between weather and events
-        self.batch_add_weather_to_event(event_id, weather_ids)
+        self.batch_add_weather_to_event(event_id, weather_ids).await
+    }
+
+    /// Adds weather an operator pushed for an event, each station's latest push is scored in place of the
+    /// daemon's readings, which keep being added by the etl
+    pub async fn add_weather_overrides(
+        &self,
+        event_id: Uuid,
+        weather: Vec<Weather>,
+    ) -> Result<(), duckdb::Error> {
+        let _guard = self.event_weather_locks.lock(event_id).await;
+        let station_ids: Vec<String> = weather
+            .iter()
+            .map(|weather| weather.station_id.clone())
+            .collect();
+        let weather_ids = self.add_weather_readings(weather).await?;
+        self.batch_add_weather_to_event(event_id, weather_ids.clone())
             .await?;
+        self.set_weather_overrides(event_id, station_ids.into_iter().zip(weather_ids).collect())
+            .await
+    }
 
-        Ok(())
+    /// Points each station at the weather row that overrides it, a station pushed twice keeps the later row
+    async fn set_weather_overrides(
+        &self,
+        event_id: Uuid,
+        overrides: Vec<(String, Uuid)>,
+    ) -> Result<(), duckdb::Error> {
+        let conn = self.new_write_connection_retry().await?;
+        insert_weather_overrides(&conn, event_id, overrides)
+    }
+
+    /// Same as `add_weather_overrides` with the weather read straight from a parquet file by DuckDB instead
+    /// of going through `Weather`. One row per station with `station_id`, the `forecast_date`,
+    /// `forecast_start_time`, `forecast_end_time`, `forecast_temp_low`, `forecast_temp_high`,
+    /// `forecast_wind_speed` columns and `observation_*` columns in the shape of an `Observation`, left null for
+    /// stations without an observation, times are RFC 3339 strings. Every station has to be one of `locations`
+    /// and the db needs the `parquet` extension. Returns the ids of the added weather rows
+    pub async fn update_weather_station_parquet(
+        &self,
+        event_id: Uuid,
+        file_path: &str,
+        locations: &[String],
+        storage: ObservationStorage,
+    ) -> Result<Vec<Uuid>, WeatherParquetError> {
+        if !self
+            .extensions
+            .iter()
+            .any(|extension| extension == "parquet")
+        {
+            return Err(WeatherParquetError::ParquetNotLoaded);
+        }
+        let _guard = self.event_weather_locks.lock(event_id).await;
+        let mut conn = self.new_write_connection_retry().await?;
+        let mut station_ids = vec![];
+        for station_id in read_pushed_weather(&conn, file_path)? {
+            let station_id = normalize_station_id(&station_id)
+                .map_err(|e| WeatherParquetError::Invalid(e.to_string()))?;
+            if !locations
+                .iter()
+                .any(|location| location.eq_ignore_ascii_case(&station_id))
+            {
+                return Err(WeatherParquetError::Invalid(format!(
+                    "station {} isn't one of event {}'s locations",
+                    station_id, event_id
+                )));
+            }
+            station_ids.push(station_id);
+        }
+        if station_ids.is_empty() {
+            return Err(WeatherParquetError::Invalid(String::from(
+                "needs weather for at least one station",
+            )));
+        }
+
+        // Ids only need to be unique, which row gets which doesn't matter
+        let weather_ids: Vec<Uuid> = station_ids
+            .iter()
+            .map(|_| self.id_generator.next_id())
+            .collect();
+        let event_weather_ids: Vec<Uuid> = station_ids
+            .iter()
+            .map(|_| self.id_generator.next_id())
+            .collect();
+        let uuid_list = |ids: &[Uuid]| {
+            ids.iter()
+                .map(|id| format!("'{}'", id))
+                .collect::<Vec<String>>()
+                .join(",")
+        };
+        // Normalized station ids are only letters and digits, so they can be inlined
+        let readings = format!(
+            "pushed_weather AS pushed
+            JOIN (SELECT unnest([{}])::UUID AS weather_id, unnest([{}]) AS station_id,
+                unnest(range(1, {} + 1)) AS row_number) AS ids
+                ON ids.row_number = pushed.row_number",
+            uuid_list(&weather_ids),
+            station_ids
+                .iter()
+                .map(|station_id| format!("'{}'", station_id))
+                .collect::<Vec<String>>()
+                .join(","),
+            station_ids.len()
+        );
+        let insert_weather = format!(
+            "INSERT INTO weather (id, station_id, forecasted, observed)
+            SELECT
+                ids.weather_id,
+                ids.station_id,
+                ROW((pushed.forecast_date::VARCHAR || ' 00:00:00+00')::TIMESTAMPTZ,
+                    pushed.forecast_temp_low::INTEGER, pushed.forecast_temp_high::INTEGER,
+                    pushed.forecast_wind_speed::INTEGER),
+                CASE WHEN pushed.has_observation
+                    THEN ROW(pushed.observation_start_time::TIMESTAMPTZ,
+                        round(pushed.observation_temp_low)::INTEGER, round(pushed.observation_temp_high)::INTEGER,
+                        pushed.observation_wind_speed::INTEGER)
+                END
+            FROM {}",
+            readings
+        );
+        let insert_event_weather = format!(
+            "INSERT INTO events_weather (id, event_id, weather_id)
+            SELECT unnest([{}])::UUID, ?::UUID, unnest([{}])::UUID",
+            uuid_list(&event_weather_ids),
+            uuid_list(&weather_ids)
+        );
+        // `Z` and `-00:00` are both kept as `+00:00`, the same as formatting the parsed offset does
+        let insert_offsets = format!(
+            "INSERT INTO weather_observed_offsets (weather_id, original_offset)
+            SELECT
+                ids.weather_id,
+                CASE WHEN regexp_extract(pushed.observation_start_time, '[+-][0-9]{{2}}:[0-9]{{2}}$') IN ('', '-00:00')
+                    THEN '+00:00'
+                    ELSE regexp_extract(pushed.observation_start_time, '[+-][0-9]{{2}}:[0-9]{{2}}$')
+                END
+            FROM {}
+            WHERE pushed.has_observation",
+            readings
+        );
+        let insert_raw_temps = format!(
+            "INSERT INTO weather_observed_raw_temps (weather_id, temp_low, temp_high)
+            SELECT ids.weather_id, pushed.observation_temp_low, pushed.observation_temp_high
+            FROM {}
+            WHERE pushed.has_observation",
+            readings
+        );
+        debug!("query_str: {}", insert_weather);
+        debug!("query_str: {}", insert_event_weather);
+
+        let tx = conn.transaction()?;
+        tx.execute(&insert_weather, [])?;
+        tx.execute(&insert_event_weather, params![event_id.to_string()])?;
+        if storage.original_offsets {
+            debug!("query_str: {}", insert_offsets);
+            tx.execute(&insert_offsets, [])?;
+        }
+        if storage.raw_temps {
+            debug!("query_str: {}", insert_raw_temps);
+            tx.execute(&insert_raw_temps, [])?;
+        }
+        insert_weather_overrides(
+            &tx,
+            event_id,
+            station_ids.into_iter().zip(weather_ids.clone()).collect(),
+        )?;
+        tx.commit()?;
+        Ok(weather_ids)
+    }
+
+    pub async fn get_event_weather_count(&self, event_id: Uuid) -> Result<i64, duckdb::Error> {
+        let event_weather_count = select("COUNT(events_weather.id)")
+            .from(
+                "events_weather"
+                    .join("weather")
+                    .on("weather.id = events_weather.weather_id"),
+            )
+            .where_("events_weather.event_id = ?");
+        let query_str = event_weather_count.to_string();
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(&query_str)?;
+        stmt.query_row([event_id.to_string()], |row| row.get(0))
+    }
+
+    /// The event's denormalized `total_entries` counter, 0 for events without settings
+    pub async fn get_event_total_entries(&self, event_id: Uuid) -> Result<i64, duckdb::Error> {
+        let total_entries = select("COALESCE(events_settings.total_entries, 0)")
+            .from(
+                "events"
+                    .left_join("events_settings")
+                    .on("events_settings.event_id = events.id"),
+            )
+            .where_("events.id = ?");
+        let query_str = total_entries.to_string();
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(&query_str)?;
+        stmt.query_row([event_id.to_string()], |row| row.get(0))
+    }
+
+    /// Distinct station ids in the stored weather with how many readings each has, sorted by station id
+    pub async fn get_stored_stations(&self) -> Result<Vec<(String, usize)>, duckdb::Error> {
+        // Weather stored before station ids were normalized may hold lower case ids
+        let stations_select = select(("upper(station_id) AS station", "COUNT(id)"))
+            .from("weather")
+            .group_by("station")
+            .order_by("station");
+        let query_str = stations_select.to_string();
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(&query_str)?;
+        let mut rows = stmt.query([])?;
+        let mut stations = vec![];
+        while let Some(row) = rows.next()? {
+            stations.push((
+                row.get::<usize, String>(0)?,
+                row.get::<usize, i64>(1)? as usize,
+            ));
+        }
+        Ok(stations)
     }
 
     pub async fn add_weather_readings(
@@ -144,7 +708,7 @@ impl EventData {
         let params: Vec<(Uuid, Value, Forecasted, Option<Observed>)> = weather
             .iter()
             .map(|weather| {
-                let weather_id = Uuid::now_v7();
+                let weather_id = self.id_generator.next_id();
                 (
                     weather_id,
                     Value::Text(weather.station_id.clone()),
@@ -174,15 +738,46 @@ impl EventData {
             .values(params_values);
         let query_str = self.prepare_query(insert_weather.to_string());
         debug!("query_str: {}", query_str);
+        let original_offsets: Vec<(Uuid, String)> = params
+            .iter()
+            .filter_map(|(weather_id, _, _, observed)| {
+                observed
+                    .as_ref()
+                    .and_then(|observed| observed.original_offset.clone())
+                    .map(|original_offset| (*weather_id, original_offset))
+            })
+            .collect();
+        let raw_temps: Vec<(Uuid, RawObservedTemps)> = params
+            .iter()
+            .filter_map(|(weather_id, _, _, observed)| {
+                observed
+                    .as_ref()
+                    .and_then(|observed| observed.raw_temps)
+                    .map(|raw_temps| (*weather_id, raw_temps))
+            })
+            .collect();
         let insert_values: Vec<Value> = params
             .into_iter()
             .flat_map(|(a, b, _, _)| vec![Value::Text(a.to_string()), b])
             .collect();
         debug!("insert values: {:?}", insert_values);
 
-        let conn = self.new_write_connection_retry().await?;
-        let mut weather_stmt = conn.prepare(&query_str)?;
-        weather_stmt.execute(params_from_iter(insert_values.iter()))?;
+        let mut conn = self.new_write_connection_retry().await?;
+        let tx = conn.transaction()?;
+        tx.execute(&query_str, params_from_iter(insert_values.iter()))?;
+        for (weather_id, original_offset) in original_offsets {
+            tx.execute(
+                "INSERT INTO weather_observed_offsets (weather_id, original_offset) VALUES(?,?)",
+                params![weather_id.to_string(), original_offset],
+            )?;
+        }
+        for (weather_id, raw_temps) in raw_temps {
+            tx.execute(
+                "INSERT INTO weather_observed_raw_temps (weather_id, temp_low, temp_high) VALUES(?,?,?)",
+                params![weather_id.to_string(), raw_temps.temp_low, raw_temps.temp_high],
+            )?;
+        }
+        tx.commit()?;
         Ok(weather_ids)
     }
 
@@ -194,7 +789,7 @@ impl EventData {
         let params: Vec<(String, Uuid, String)> = weather_ids
             .iter()
             .map(|weather_id| {
-                let event_weather_id = Uuid::now_v7().to_string();
+                let event_weather_id = self.id_generator.next_id().to_string();
                 (event_weather_id, event_id, weather_id.to_string())
             })
             .collect();
@@ -230,9 +825,9 @@ impl EventData {
     pub async fn add_event(&self, event: CreateEventData) -> Result<Event, duckdb::Error> {
         let locations_sql = format!("[{}]", event.locations.join(","));
 
-        let signing_date = OffsetDateTime::format(event.signing_date, &Rfc3339)
+        let signing_date = to_sql_timestamp(event.signing_date)
             .map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
-        let observation_date = OffsetDateTime::format(event.observation_date, &Rfc3339)
+        let observation_date = to_sql_timestamp(event.observation_date)
             .map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
         let nonce = to_vec(&event.nonce).unwrap();
         let announcement_bytes = to_vec(&event.event_announcement).unwrap();
@@ -263,6 +858,28 @@ impl EventData {
             event.coordinator_pubkey
         ])?;
 
+        let mut settings_stmt = conn.prepare(
+            "INSERT INTO events_settings (
+                event_id,
+                win_mode,
+                score_threshold,
+                auto_sign,
+                scoring_mode,
+                late_entry_grace_seconds,
+                timezone,
+                par_tolerance) VALUES(?,?,?,?,?,?,?,?)",
+        )?;
+        settings_stmt.execute(params![
+            event.id.to_string(),
+            event.win_mode.to_string(),
+            event.score_threshold,
+            event.auto_sign,
+            event.scoring_mode.to_string(),
+            event.late_entry_grace_seconds,
+            event.timezone,
+            event.par_tolerance
+        ])?;
+
         Ok(event.into())
     }
 
@@ -284,24 +901,57 @@ impl EventData {
     pub async fn add_event_entry(
         &self,
         entry: WeatherEntry,
-    ) -> Result<WeatherEntry, duckdb::Error> {
+    ) -> Result<WeatherEntry, AddEntryError> {
         //TODO: have these transactions happen in the same transaction
         self.add_entry(entry.clone()).await?;
         self.add_entry_choices(entry.clone()).await?;
         Ok(entry)
     }
 
-    pub async fn add_entry(&self, entry: WeatherEntry) -> Result<(), duckdb::Error> {
-        let conn = self.new_write_connection_retry().await?;
-        // TODO: add check on the INSERT transaction to verify we never go over number of allowed entries in an event
-        // (current worse case is just a couple of extra entries made it into the event, doesn't change how we sign the result)
-        let insert_query = "INSERT INTO events_entries (id, event_id) VALUES(?,?)";
-        let mut event_stmt = conn.prepare(insert_query)?;
+    /// Adds the entry unless its event is already full, checked under the event's entry lock so concurrent
+    /// entries can't go over the limit together
+    pub async fn add_entry(&self, entry: WeatherEntry) -> Result<(), AddEntryError> {
+        let _guard = self.event_entry_locks.lock(entry.event_id).await;
+        self.insert_entry(&entry).await
+    }
 
+    async fn insert_entry(&self, entry: &WeatherEntry) -> Result<(), AddEntryError> {
+        let mut conn = self.new_write_connection_retry().await?;
+        let tx = conn.transaction()?;
+        let capacity_query =
+            "SELECT COALESCE(events_settings.total_entries, 0), events.total_allowed_entries
+            FROM events LEFT JOIN events_settings ON events_settings.event_id = events.id
+            WHERE events.id = ?";
+        debug!("query_str: {}", capacity_query);
+        let (total_entries, total_allowed_entries): (i64, i64) =
+            tx.query_row(capacity_query, params![entry.event_id.to_string()], |row| {
+                Ok((row.get(0)?, row.get(1)?))
+            })?;
+        if is_event_full(total_entries, total_allowed_entries) {
+            return Err(AddEntryError::EventFull(entry.event_id));
+        }
+        let insert_query = "INSERT INTO events_entries (id, event_id) VALUES(?,?)";
         debug!("query_str: {}", insert_query);
-        let insert_values = params![entry.id.to_string(), entry.event_id.to_string()];
+        tx.execute(
+            insert_query,
+            params![entry.id.to_string(), entry.event_id.to_string()],
+        )?;
 
-        event_stmt.execute(insert_values)?;
+        if let Some(coordinator_pubkey) = &entry.coordinator_pubkey {
+            let insert_coordinator_query = "INSERT INTO events_entries_coordinators (entry_id, coordinator_pubkey) VALUES(?,?)";
+            debug!("query_str: {}", insert_coordinator_query);
+            tx.execute(
+                insert_coordinator_query,
+                params![entry.id.to_string(), coordinator_pubkey],
+            )?;
+        }
+
+        // Listings read this counter instead of counting events_entries on every call
+        let update_count_query =
+            "UPDATE events_settings SET total_entries = total_entries + 1, updated_at = NOW() WHERE event_id = ?";
+        debug!("query_str: {}", update_count_query);
+        tx.execute(update_count_query, params![entry.event_id.to_string()])?;
+        tx.commit()?;
         Ok(())
     }
 
@@ -382,10 +1032,89 @@ impl EventData {
         weather_stmt.execute(params_from_iter(insert_values.iter()))?;
         Ok(())
     }
-    pub async fn update_event_attestation(&self, event: &SignEvent) -> Result<(), duckdb::Error> {
+    /// Stations already recorded for an event are left as they are, so re-signing never duplicates rows
+    pub async fn add_forecast_accuracy(
+        &self,
+        accuracy: &[ForecastAccuracy],
+    ) -> Result<(), duckdb::Error> {
+        if accuracy.is_empty() {
+            return Ok(());
+        }
+        let insert_query = "INSERT OR IGNORE INTO forecast_accuracy (
+                event_id,
+                station_id,
+                observation_date,
+                temp_low_diff,
+                temp_low_outcome,
+                temp_high_diff,
+                temp_high_outcome,
+                wind_speed_diff,
+                wind_speed_outcome) VALUES(?,?,?,?,?,?,?,?,?)";
+        debug!("query_str: {}", insert_query);
+
+        let mut conn = self.new_write_connection_retry().await?;
+        let tx = conn.transaction()?;
+        for station in accuracy {
+            let observation_date = to_sql_timestamp(station.observation_date)
+                .map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
+            tx.execute(
+                insert_query,
+                params![
+                    station.event_id.to_string(),
+                    station.station_id,
+                    observation_date,
+                    station.temp_low.diff,
+                    station.temp_low.outcome.to_string(),
+                    station.temp_high.diff,
+                    station.temp_high.outcome.to_string(),
+                    station.wind_speed.diff,
+                    station.wind_speed.outcome.to_string()
+                ],
+            )?;
+        }
+        tx.commit()
+    }
+
+    /// Oldest observation first
+    pub async fn get_station_forecast_accuracy(
+        &self,
+        station_id: &str,
+    ) -> Result<Vec<ForecastAccuracy>, duckdb::Error> {
+        let accuracy_select = select((
+            "event_id::TEXT",
+            "station_id",
+            "observation_date::TEXT",
+            "temp_low_diff",
+            "temp_low_outcome::TEXT",
+            "temp_high_diff",
+            "temp_high_outcome::TEXT",
+            "wind_speed_diff",
+            "wind_speed_outcome::TEXT",
+        ))
+        .from("forecast_accuracy")
+        .where_("station_id = $1")
+        .order_by("observation_date, event_id");
+        let query_str = self.prepare_query(accuracy_select.to_string());
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(&query_str)?;
+        let mut rows = stmt.query([station_id])?;
+        let mut accuracy = vec![];
+        while let Some(row) = rows.next()? {
+            accuracy.push(ForecastAccuracy::try_from(row)?);
+        }
+        Ok(accuracy)
+    }
+
+    /// Stores the event's attestation, returns false when the event was already signed or cancelled so an
+    /// attestation is never replaced
+    pub async fn update_event_attestation(&self, event: &SignEvent) -> Result<bool, duckdb::Error> {
         let entry_score_update_query = update("events")
             .set("attestation_signature", "$1")
-            .where_("events.id = $2");
+            .where_("events.id = $2")
+            .where_("attestation_signature IS NULL")
+            .where_("events.id NOT IN (SELECT event_id FROM events_settings WHERE cancelled_at IS NOT NULL)");
 
         let query_str = self.prepare_query(entry_score_update_query.to_string());
         debug!("query_str: {}", query_str);
@@ -396,9 +1125,71 @@ impl EventData {
         let Some(attestation) = event.attestation else {
             return Err(duckdb::Error::InvalidParameterCount(1, 2));
         };
-        let attestation_bytes = to_vec(&attestation).unwrap();
-        stmt.execute(params![attestation_bytes, event.id.to_string()])?;
-        Ok(())
+        let attestation_bytes = attestation_to_sql(&attestation);
+        let updated = stmt.execute(params![attestation_bytes, event.id.to_string()])?;
+        Ok(updated > 0)
+    }
+
+    /// Marks an unsigned event as cancelled, returns false when the event was already signed or cancelled
+    pub async fn cancel_event(&self, event_id: &Uuid) -> Result<bool, duckdb::Error> {
+        let cancel_query = "UPDATE events_settings SET cancelled_at = NOW(), updated_at = NOW()
+            WHERE event_id = ? AND cancelled_at IS NULL
+            AND event_id IN (SELECT id FROM events WHERE attestation_signature IS NULL)";
+        debug!("query_str: {}", cancel_query);
+
+        let conn = self.new_write_connection_retry().await?;
+        let cancelled = conn.execute(cancel_query, params![event_id.to_string()])?;
+        Ok(cancelled > 0)
+    }
+
+    /// Replaces the outcome parameters, nonce and announcement of an event, returns false when the event
+    /// already has entries or was signed. Holds the event's entry lock so an entry can't be added part way through
+    pub async fn update_event_outcomes(
+        &self,
+        event: &CreateEventData,
+    ) -> Result<bool, duckdb::Error> {
+        let _guard = self.event_entry_locks.lock(event.id).await;
+        self.replace_event_outcomes(event).await
+    }
+
+    async fn replace_event_outcomes(&self, event: &CreateEventData) -> Result<bool, duckdb::Error> {
+        let nonce = to_vec(&event.nonce).unwrap();
+        let announcement_bytes = to_vec(&event.event_announcement).unwrap();
+        let mut conn = self.new_write_connection_retry().await?;
+        let tx = conn.transaction()?;
+        let update_event_query = "UPDATE events SET total_allowed_entries = ?, number_of_places_win = ?, nonce = ?, event_announcement = ?
+            WHERE id = ? AND attestation_signature IS NULL
+            AND NOT EXISTS (SELECT 1 FROM events_entries WHERE event_id = ?)";
+        debug!("query_str: {}", update_event_query);
+        let updated = tx.execute(
+            update_event_query,
+            params![
+                event.total_allowed_entries,
+                event.number_of_places_win,
+                nonce,
+                announcement_bytes,
+                event.id.to_string(),
+                event.id.to_string()
+            ],
+        )?;
+        if updated == 0 {
+            return Ok(false);
+        }
+
+        let update_settings_query =
+            "UPDATE events_settings SET win_mode = ?, score_threshold = ?, updated_at = NOW()
+            WHERE event_id = ?";
+        debug!("query_str: {}", update_settings_query);
+        tx.execute(
+            update_settings_query,
+            params![
+                event.win_mode.to_string(),
+                event.score_threshold,
+                event.id.to_string()
+            ],
+        )?;
+        tx.commit()?;
+        Ok(true)
     }
 
     ///Danger: a raw SQL query is used, input is not escaped with '?'
@@ -448,15 +1239,30 @@ impl EventData {
     }
 
     pub async fn get_event_weather(&self, event_id: Uuid) -> Result<Vec<Weather>, duckdb::Error> {
-        let event_weather = select(("station_id", "observed", "forecasted"))
-            .from(
-                "events_weather"
-                    .join("events")
-                    .on("events_weather.event_id = events.id")
-                    .join("weather")
-                    .on("weather.id = events_weather.weather_id"),
-            )
-            .where_("event_id = ?");
+        let event_weather = select((
+            "station_id",
+            "observed",
+            "forecasted",
+            "weather_observed_offsets.original_offset",
+            "weather_observed_raw_temps.temp_low",
+            "weather_observed_raw_temps.temp_high",
+        ))
+        .from(
+            "events_weather"
+                .join("events")
+                .on("events_weather.event_id = events.id")
+                .join("weather")
+                .on("weather.id = events_weather.weather_id")
+                .left_join("weather_observed_offsets")
+                .on("weather_observed_offsets.weather_id = weather.id")
+                .left_join("weather_observed_raw_temps")
+                .on("weather_observed_raw_temps.weather_id = weather.id")
+                .left_join("events_weather_overrides")
+                .on("events_weather_overrides.event_id = events_weather.event_id AND events_weather_overrides.weather_id = weather.id"),
+        )
+        .where_("events_weather.event_id = ?")
+        // Overrides go last so they're each station's latest reading, the etl keeps adding the daemon's after them
+        .order_by("events_weather_overrides.weather_id IS NOT NULL, weather.created_at");
         let query_str = event_weather.to_string();
         debug!("query_str: {}", query_str);
 
@@ -468,7 +1274,44 @@ impl EventData {
             let data: Weather = row.try_into()?;
             event_weather.push(data);
         }
-        Ok(vec![])
+        Ok(event_weather)
+    }
+
+    /// The weather operators pushed for an event, one reading per station
+    pub async fn get_event_weather_overrides(
+        &self,
+        event_id: Uuid,
+    ) -> Result<Vec<Weather>, duckdb::Error> {
+        let overrides = select((
+            "station_id",
+            "observed",
+            "forecasted",
+            "weather_observed_offsets.original_offset",
+            "weather_observed_raw_temps.temp_low",
+            "weather_observed_raw_temps.temp_high",
+        ))
+        .from(
+            "events_weather_overrides"
+                .join("weather")
+                .on("weather.id = events_weather_overrides.weather_id")
+                .left_join("weather_observed_offsets")
+                .on("weather_observed_offsets.weather_id = weather.id")
+                .left_join("weather_observed_raw_temps")
+                .on("weather_observed_raw_temps.weather_id = weather.id"),
+        )
+        .where_("events_weather_overrides.event_id = ?");
+        let query_str = overrides.to_string();
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(&query_str)?;
+        let mut rows = stmt.query([event_id.to_string()])?;
+        let mut weather = vec![];
+        while let Some(row) = rows.next()? {
+            let data: Weather = row.try_into()?;
+            weather.push(data);
+        }
+        Ok(weather)
     }
 
     pub async fn get_event_weather_entries(
@@ -476,15 +1319,26 @@ impl EventData {
         event_id: &Uuid,
     ) -> Result<Vec<WeatherEntry>, duckdb::Error> {
         // Query 1
-        let event_entries_select =
-            select(("events_entries.id", "events_entries.event_id", "score"))
-                .from(
-                    "events_entries"
-                        .join("events")
-                        .on("events_entries.event_id = events.id"),
-                )
-                .where_("events_entries.event_id = ?")
-                .group_by(("events_entries.id", "events_entries.event_id", "score"));
+        let event_entries_select = select((
+            "events_entries.id",
+            "events_entries.event_id",
+            "score",
+            "events_entries_coordinators.coordinator_pubkey",
+        ))
+        .from(
+            "events_entries"
+                .join("events")
+                .on("events_entries.event_id = events.id")
+                .left_join("events_entries_coordinators")
+                .on("events_entries_coordinators.entry_id = events_entries.id"),
+        )
+        .where_("events_entries.event_id = ?")
+        .group_by((
+            "events_entries.id",
+            "events_entries.event_id",
+            "score",
+            "events_entries_coordinators.coordinator_pubkey",
+        ));
 
         let query_str = event_entries_select.to_string();
         debug!("query_str: {}", query_str);
@@ -547,8 +1401,13 @@ impl EventData {
             "events_entries.id as id",
             "events_entries.event_id as event_id",
             "score",
+            "events_entries_coordinators.coordinator_pubkey",
         ))
-        .from("events_entries")
+        .from(
+            "events_entries"
+                .left_join("events_entries_coordinators")
+                .on("events_entries_coordinators.entry_id = events_entries.id"),
+        )
         .where_("events_entries.id = $1 AND events_entries.event_id = $2");
 
         let conn = self.new_readonly_connection_retry().await?;
@@ -585,6 +1444,74 @@ impl EventData {
         Ok(weather_entry)
     }
 
+    pub async fn get_coordinator_weather_entries(
+        &self,
+        coordinator_pubkey: &str,
+    ) -> Result<Vec<WeatherEntry>, duckdb::Error> {
+        // Query 1
+        let coordinator_entries_select = select((
+            "events_entries.id",
+            "events_entries.event_id",
+            "score",
+            "events_entries_coordinators.coordinator_pubkey",
+        ))
+        .from(
+            "events_entries"
+                .join("events_entries_coordinators")
+                .on("events_entries_coordinators.entry_id = events_entries.id"),
+        )
+        .where_("events_entries_coordinators.coordinator_pubkey = $1")
+        .order_by("events_entries.id");
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let query_str = self.prepare_query(coordinator_entries_select.to_string());
+        debug!("query_str: {}", query_str);
+        let mut stmt = conn.prepare(&query_str)?;
+        let mut weather_entry_rows = stmt.query([coordinator_pubkey])?;
+        let mut weather_entries = vec![];
+        while let Some(row) = weather_entry_rows.next()? {
+            let data: WeatherEntry = row.try_into()?;
+            weather_entries.push(data);
+        }
+
+        // Query 2
+        let entry_choices = select((
+            "expected_observations.entry_id",
+            "station",
+            "temp_low::TEXT",
+            "temp_high::TEXT",
+            "wind_speed::TEXT",
+        ))
+        .from(
+            "expected_observations"
+                .join("events_entries_coordinators")
+                .on("events_entries_coordinators.entry_id = expected_observations.entry_id"),
+        )
+        .where_("events_entries_coordinators.coordinator_pubkey = $1");
+        let entry_choices_query_str = self.prepare_query(entry_choices.to_string());
+        debug!("query_str: {}", entry_choices_query_str);
+        let mut stmt_choices = conn.prepare(&entry_choices_query_str)?;
+        let mut rows = stmt_choices.query([coordinator_pubkey])?;
+
+        //Combine query results
+        let mut weather_choices: HashMap<Uuid, Vec<WeatherChoices>> = HashMap::new();
+        while let Some(row) = rows.next()? {
+            let data: WeatherChoicesWithEntry = row.try_into()?;
+            weather_choices
+                .entry(data.entry_id)
+                .or_default()
+                .push(data.into());
+        }
+
+        for weather_entry in weather_entries.iter_mut() {
+            if let Some(choices) = weather_choices.remove(&weather_entry.id) {
+                weather_entry.expected_observations = choices;
+            }
+        }
+
+        Ok(weather_entries)
+    }
+
     pub async fn filtered_list_events(
         &self,
         filter: EventFilter,
@@ -600,29 +1527,24 @@ impl EventData {
         &self,
         filter: EventFilter,
     ) -> Result<Vec<EventSummary>, duckdb::Error> {
-        let event_entries_select = select(("Count(id) as total_entries", "event_id"))
-            .from("events_entries")
-            .group_by("event_id");
-
-        let mut event_select = with("event_entries")
-            .as_(event_entries_select)
-            .select((
-                "id",
-                "signing_date::TEXT",
-                "observation_date::TEXT",
-                "locations",
-                "total_allowed_entries",
-                "COALESCE(event_entries.total_entries,0) as total_entries",
-                "number_of_places_win",
-                "number_of_values_per_entry",
-                "attestation_signature",
-                "nonce",
-            ))
-            .from(
-                "events"
-                    .left_join("event_entries")
-                    .on("event_entries.event_id = events.id"),
-            );
+        let mut event_select = select((
+            "id",
+            "signing_date::TEXT",
+            "observation_date::TEXT",
+            "locations",
+            "total_allowed_entries",
+            "COALESCE(events_settings.total_entries, 0) as total_entries",
+            "number_of_places_win",
+            "number_of_values_per_entry",
+            "attestation_signature",
+            "nonce",
+            "events_settings.cancelled_at::TEXT",
+        ))
+        .from(
+            "events"
+                .left_join("events_settings")
+                .on("events_settings.event_id = events.id"),
+        );
         if let Some(ids) = filter.event_ids.clone() {
             let mut event_ids_val = String::new();
             event_ids_val.push('(');
@@ -636,6 +1558,12 @@ impl EventData {
             let where_clause = format!("events.id IN {}", event_ids_val);
             event_select = event_select.clone().where_(where_clause);
         }
+        if filter.station_id.is_some() {
+            // Events created before station ids were normalized may hold lower case ids
+            event_select = event_select
+                .clone()
+                .where_("list_contains(list_transform(locations, location -> upper(location)), ?)");
+        }
         if let Some(limit) = filter.limit {
             event_select = event_select.clone().limit(limit);
         }
@@ -644,15 +1572,16 @@ impl EventData {
         let query_str = self.prepare_query(event_select.to_string());
         debug!("query_str: {}", query_str);
         let mut stmt = conn.prepare(&query_str)?;
-        let mut rows = if let Some(ids) = filter.event_ids {
-            let params: Vec<Value> = ids
-                .iter()
-                .map(|event_id| Value::Text(event_id.to_string()))
-                .collect();
-            stmt.query(params_from_iter(params.iter()))
-        } else {
-            stmt.query([])
-        }?;
+        let mut params: Vec<Value> = filter
+            .event_ids
+            .unwrap_or_default()
+            .iter()
+            .map(|event_id| Value::Text(event_id.to_string()))
+            .collect();
+        if let Some(station_id) = filter.station_id {
+            params.push(Value::Text(station_id.to_ascii_uppercase()));
+        }
+        let mut rows = stmt.query(params_from_iter(params.iter()))?;
         let mut event_data: Vec<EventSummary> = vec![];
         while let Some(row) = rows.next()? {
             let data: EventSummary = row.try_into()?;
@@ -685,8 +1614,24 @@ impl EventData {
             "number_of_places_win",
             "number_of_values_per_entry",
         ))
-        .and_select(("attestation_signature", "nonce", "coordinator_pubkey"))
-        .from("events")
+        .and_select((
+            "attestation_signature",
+            "nonce",
+            "coordinator_pubkey",
+            "COALESCE(events_settings.win_mode, 'top_n') as win_mode",
+            "events_settings.score_threshold",
+            "COALESCE(events_settings.auto_sign, false) as auto_sign",
+            "COALESCE(events_settings.scoring_mode, 'linear') as scoring_mode",
+            "events_settings.cancelled_at::TEXT",
+            "COALESCE(events_settings.late_entry_grace_seconds, 0) as late_entry_grace_seconds",
+            "events_settings.timezone",
+            "events_settings.par_tolerance",
+        ))
+        .from(
+            "events"
+                .left_join("events_settings")
+                .on("events_settings.event_id = events.id"),
+        )
         .where_("id = $1");
 
         let query_str = self.prepare_query(event_select.to_string());
@@ -698,29 +1643,27 @@ impl EventData {
     }
 
     pub async fn get_active_events(&self) -> Result<Vec<ActiveEvent>, duckdb::Error> {
-        let event_entries_select = select(("Count(id) as total_entries", "event_id"))
-            .from("events_entries")
-            .group_by("event_id");
-
-        let event_select = with("event_entries")
-            .as_(event_entries_select)
-            .select((
-                "id",
-                "signing_date::TEXT",
-                "observation_date::TEXT",
-                "locations",
-                "total_allowed_entries",
-                "COALESCE(event_entries.total_entries, 0) as total_entries",
-                "number_of_places_win",
-                "number_of_values_per_entry",
-                "attestation_signature",
-            ))
-            .from(
-                "events"
-                    .left_join("event_entries")
-                    .on("event_entries.event_id = events.id"),
-            )
-            .where_("attestation_signature IS NULL"); //Only filter out events that have been signed
+        let event_select = select((
+            "id",
+            "signing_date::TEXT",
+            "observation_date::TEXT",
+            "locations",
+            "total_allowed_entries",
+            "COALESCE(events_settings.total_entries, 0) as total_entries",
+            "number_of_places_win",
+            "number_of_values_per_entry",
+            "attestation_signature",
+            "COALESCE(events_settings.scoring_mode, 'linear') as scoring_mode",
+            "events_settings.timezone",
+            "events_settings.par_tolerance",
+        ))
+        .from(
+            "events"
+                .left_join("events_settings")
+                .on("events_settings.event_id = events.id"),
+        )
+        .where_("attestation_signature IS NULL") //Only filter out events that have been signed
+        .where_("events_settings.cancelled_at IS NULL");
 
         let conn = self.new_readonly_connection_retry().await?;
         let query_str = self.prepare_query(event_select.to_string());
@@ -737,6 +1680,99 @@ impl EventData {
         Ok(event_data)
     }
 
+    /// Every event that wasn't cancelled grouped by status, read in one query so an event signed meanwhile
+    /// can't land in two groups. Only the `signed_limit` newest signed events are listed, `signed.count` still
+    /// counts all of them
+    pub async fn get_sign_status(
+        &self,
+        signed_limit: usize,
+    ) -> Result<EventSignStatus, duckdb::Error> {
+        let query_str = "SELECT id::TEXT, observation_date::TEXT, signed, signed_count
+            FROM (
+                SELECT
+                    events.id,
+                    events.observation_date,
+                    events.attestation_signature IS NOT NULL AS signed,
+                    COUNT(*) FILTER (WHERE events.attestation_signature IS NOT NULL) OVER () AS signed_count,
+                    row_number() OVER (
+                        PARTITION BY events.attestation_signature IS NOT NULL ORDER BY events.id DESC
+                    ) AS signed_rank
+                FROM events LEFT JOIN events_settings ON events_settings.event_id = events.id
+                WHERE events_settings.cancelled_at IS NULL
+            ) AS event_statuses
+            WHERE NOT signed OR signed_rank <= ?
+            ORDER BY id";
+        debug!("query_str: {}", query_str);
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let mut stmt = conn.prepare(query_str)?;
+        let mut rows = stmt.query(params![signed_limit as i64])?;
+        let (mut live, mut running, mut completed, mut signed) = (vec![], vec![], vec![], vec![]);
+        let mut signed_count = 0;
+        while let Some(row) = rows.next()? {
+            let id = Uuid::parse_str(&row.get::<usize, String>(0)?)
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?;
+            signed_count = row.get::<usize, i64>(3)? as usize;
+            if row.get::<usize, bool>(2)? {
+                signed.push(id);
+                continue;
+            }
+            let observation_date = row
+                .get::<usize, String>(1)
+                .map(|val| parse_sql_timestamp(&val))?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?;
+            match get_status(observation_date, None, None) {
+                EventStatus::Live => live.push(id),
+                EventStatus::Running => running.push(id),
+                EventStatus::Completed => completed.push(id),
+                // Unsigned events that weren't cancelled are always in one of the groups above
+                EventStatus::Signed | EventStatus::Cancelled => {}
+            }
+        }
+
+        Ok(EventSignStatus {
+            live: live.into(),
+            running: running.into(),
+            completed: completed.into(),
+            signed: EventStatusGroup {
+                count: signed_count,
+                event_ids: signed,
+            },
+        })
+    }
+
+    /// Unsigned events the oracle should sign on its own with their signing dates, every unsigned event when `all_events` is set
+    pub async fn get_auto_sign_events(
+        &self,
+        all_events: bool,
+    ) -> Result<Vec<(Uuid, OffsetDateTime)>, duckdb::Error> {
+        let event_select = select(("id::TEXT", "signing_date::TEXT"))
+            .from(
+                "events"
+                    .left_join("events_settings")
+                    .on("events_settings.event_id = events.id"),
+            )
+            .where_("attestation_signature IS NULL")
+            .where_("events_settings.cancelled_at IS NULL")
+            .where_("(COALESCE(events_settings.auto_sign, false) OR $1)");
+
+        let conn = self.new_readonly_connection_retry().await?;
+        let query_str = self.prepare_query(event_select.to_string());
+        debug!("query_str: {}", query_str);
+        let mut stmt = conn.prepare(&query_str)?;
+
+        let mut rows = stmt.query(params![all_events])?;
+        let mut events = vec![];
+        while let Some(row) = rows.next()? {
+            let id = Uuid::parse_str(&row.get::<usize, String>(0)?)
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?;
+            let signing_date = parse_sql_timestamp(&row.get::<usize, String>(1)?)
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?;
+            events.push((id, signing_date));
+        }
+        Ok(events)
+    }
+
     pub async fn get_events_to_sign(
         &self,
         event_ids: Vec<Uuid>,
@@ -751,7 +1787,7 @@ impl EventData {
         }
         event_ids_val.push(')');
         let where_clause = format!(
-            "attestation_signature IS NULL AND events.id IN {}",
+            "attestation_signature IS NULL AND events_settings.cancelled_at IS NULL AND events.id IN {}",
             event_ids_val
         );
 
@@ -764,8 +1800,14 @@ impl EventData {
             "attestation_signature",
             "nonce",
             "event_announcement",
+            "COALESCE(events_settings.win_mode, 'top_n') as win_mode",
+            "events_settings.score_threshold",
         ))
-        .from("events")
+        .from(
+            "events"
+                .left_join("events_settings")
+                .on("events_settings.event_id = events.id"),
+        )
         .where_(where_clause);
 
         let params: Vec<Value> = event_ids
@@ -788,9 +1830,157 @@ impl EventData {
         Ok(event_data)
     }
 
+    /// Writes the weather rows into a flat parquet file, observed columns are null when the observation has not happened yet
+    pub fn write_weather_parquet(
+        weather: &[Weather],
+        file_path: &str,
+    ) -> Result<(), duckdb::Error> {
+        let conn = Connection::open_in_memory()?;
+        conn.execute_batch(
+            "CREATE TABLE event_weather (
+                station_id TEXT NOT NULL,
+                forecasted_date TIMESTAMPTZ NOT NULL,
+                forecasted_temp_low BIGINT NOT NULL,
+                forecasted_temp_high BIGINT NOT NULL,
+                forecasted_wind_speed BIGINT NOT NULL,
+                observed_date TIMESTAMPTZ,
+                observed_temp_low BIGINT,
+                observed_temp_high BIGINT,
+                observed_wind_speed BIGINT
+            );",
+        )?;
+        let mut stmt = conn.prepare(
+            "INSERT INTO event_weather VALUES(?, ?::TIMESTAMPTZ, ?, ?, ?, ?::TIMESTAMPTZ, ?, ?, ?)",
+        )?;
+        for reading in weather {
+            let forecasted_date = to_sql_timestamp(reading.forecasted.date)
+                .map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
+            let observed_date = reading
+                .observed
+                .as_ref()
+                .map(|observed| to_sql_timestamp(observed.date))
+                .transpose()
+                .map_err(|e| duckdb::Error::ToSqlConversionFailure(Box::new(e)))?;
+            stmt.execute(params![
+                reading.station_id,
+                forecasted_date,
+                reading.forecasted.temp_low,
+                reading.forecasted.temp_high,
+                reading.forecasted.wind_speed,
+                observed_date,
+                reading.observed.as_ref().map(|observed| observed.temp_low),
+                reading.observed.as_ref().map(|observed| observed.temp_high),
+                reading
+                    .observed
+                    .as_ref()
+                    .map(|observed| observed.wind_speed),
+            ])?;
+        }
+        conn.execute_batch(&format!(
+            "COPY event_weather TO '{}' (FORMAT PARQUET);",
+            file_path.replace('\'', "''")
+        ))?;
+        Ok(())
+    }
+
     fn prepare_query(&self, query: String) -> String {
         let re = Regex::new(r"\$(\d+)").unwrap();
         let fixed_params = re.replace_all(&query, "?");
         fixed_params.to_string()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{EventData, EventLocks};
+    use crate::{Forecasted, Weather};
+    use futures::future::join_all;
+    use std::{fs, sync::Mutex, time::Duration};
+    use time::macros::datetime;
+    use tokio::time::{sleep, timeout};
+    use uuid::Uuid;
+
+    #[tokio::test]
+    async fn runs_one_update_per_event_at_a_time() {
+        let locks = EventLocks::default();
+        let event_id = Uuid::now_v7();
+        let steps = Mutex::new(vec![]);
+
+        let updates = (0..4).map(|update| {
+            let (locks, steps) = (&locks, &steps);
+            async move {
+                let _guard = locks.lock(event_id).await;
+                steps.lock().unwrap().push(("start", update));
+                sleep(Duration::from_millis(10)).await;
+                steps.lock().unwrap().push(("end", update));
+            }
+        });
+        join_all(updates).await;
+
+        // Every update ends before the next one starts
+        let steps = steps.into_inner().unwrap();
+        assert_eq!(steps.len(), 8);
+        for update in steps.chunks(2) {
+            assert_eq!(update[0].0, "start");
+            assert_eq!(update[1], ("end", update[0].1));
+        }
+        assert_eq!(locks.len(), 0);
+    }
+
+    #[tokio::test]
+    async fn different_events_do_not_wait_on_each_other() {
+        let locks = EventLocks::default();
+        let _held = locks.lock(Uuid::now_v7()).await;
+
+        let other = timeout(Duration::from_millis(100), locks.lock(Uuid::now_v7())).await;
+
+        assert!(other.is_ok());
+    }
+
+    #[tokio::test]
+    async fn releases_the_lock_when_cancelled() {
+        let locks = EventLocks::default();
+        let event_id = Uuid::now_v7();
+
+        // Cancelled while holding the lock
+        let holding = timeout(Duration::from_millis(20), async {
+            let _guard = locks.lock(event_id).await;
+            sleep(Duration::from_secs(10)).await;
+        })
+        .await;
+        assert!(holding.is_err());
+        assert_eq!(locks.len(), 0);
+
+        // Cancelled while waiting on the lock
+        let held = locks.lock(event_id).await;
+        let waiting = timeout(Duration::from_millis(20), locks.lock(event_id)).await;
+        assert!(waiting.is_err());
+        drop(held);
+        assert_eq!(locks.len(), 0);
+        assert!(timeout(Duration::from_millis(100), locks.lock(event_id))
+            .await
+            .is_ok());
+    }
+
+    #[test]
+    fn writes_weather_parquet_to_paths_with_quotes() {
+        let dir = std::env::temp_dir().join(format!("o'hare_{}", Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        let file_path = dir.join("event_weather.parquet");
+        let weather = vec![Weather {
+            station_id: String::from("KORD"),
+            observed: None,
+            forecasted: Forecasted {
+                date: datetime!(2024-08-12 00:00:00 UTC),
+                temp_low: 17,
+                temp_high: 25,
+                wind_speed: 3,
+            },
+        }];
+
+        EventData::write_weather_parquet(&weather, &file_path.to_string_lossy()).unwrap();
+
+        assert!(fs::metadata(&file_path).unwrap().len() > 0);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -1,5 +1,7 @@
-use duckdb::Connection;
-use log::info;
+use super::attestation_to_sql;
+use dlctix::secp::MaybeScalar;
+use duckdb::{params, Connection};
+use log::{info, warn};
 
 pub fn run_migrations(conn: &mut Connection) -> Result<(), duckdb::Error> {
     create_version_table(conn)?;
@@ -12,15 +14,52 @@ pub fn run_migrations(conn: &mut Connection) -> Result<(), duckdb::Error> {
         0
     };
 
-    match current_version {
-        0 => {
-            create_initial_schema(conn)?;
-        }
-        /*1 => {
+    if current_version < 1 {
+        create_initial_schema(conn)?;
+    }
+    if current_version < 2 {
         migrate_to_version_2(conn)?;
-        }*/
-        _ => info!("database is up-to-date."),
     }
+    if current_version < 3 {
+        migrate_to_version_3(conn)?;
+    }
+    if current_version < 4 {
+        migrate_to_version_4(conn)?;
+    }
+    if current_version < 5 {
+        migrate_to_version_5(conn)?;
+    }
+    if current_version < 6 {
+        migrate_to_version_6(conn)?;
+    }
+    if current_version < 7 {
+        migrate_to_version_7(conn)?;
+    }
+    if current_version < 8 {
+        migrate_to_version_8(conn)?;
+    }
+    if current_version < 9 {
+        migrate_to_version_9(conn)?;
+    }
+    if current_version < 10 {
+        migrate_to_version_10(conn)?;
+    }
+    if current_version < 11 {
+        migrate_to_version_11(conn)?;
+    }
+    if current_version < 12 {
+        migrate_to_version_12(conn)?;
+    }
+    if current_version < 13 {
+        migrate_to_version_13(conn)?;
+    }
+    if current_version < 14 {
+        migrate_to_version_14(conn)?;
+    }
+    if current_version < 15 {
+        migrate_to_version_15(conn)?;
+    }
+    info!("database is up-to-date.");
 
     Ok(())
 }
@@ -110,11 +149,246 @@ pub fn create_initial_schema(conn: &mut Connection) -> Result<(), duckdb::Error>
     Ok(())
 }
 
-/* how to add the next sql migration:
+// DuckDB does not allow altering a table other tables reference with a foreign key,
+// so per event settings added after the initial schema live in their own table
 pub fn migrate_to_version_2(conn: &mut Connection) -> Result<(), duckdb::Error> {
     let migration_2 = r#"
-    UPDATE db_version SET version = 2;"#;"
+    CREATE TABLE IF NOT EXISTS events_settings
+    (
+        event_id UUID PRIMARY KEY,
+        win_mode TEXT NOT NULL DEFAULT 'top_n',
+        score_threshold INTEGER,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    UPDATE db_version SET version = 2;
+    "#;
     conn.execute_batch(migration_2)?;
     Ok(())
 }
+
+// Same foreign key limitation as version 2, events_entries can't gain a coordinator_pubkey column
+pub fn migrate_to_version_3(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_3 = r#"
+    CREATE TABLE IF NOT EXISTS events_entries_coordinators
+    (
+        entry_id UUID PRIMARY KEY,
+        coordinator_pubkey TEXT,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        updated_at TIMESTAMPTZ NOT NULL DEFAULT NOW()
+    );
+
+    -- only an event's coordinator can add entries, so existing entries belong to their event's coordinator
+    INSERT INTO events_entries_coordinators (entry_id, coordinator_pubkey)
+    SELECT events_entries.id, events.coordinator_pubkey
+    FROM events_entries JOIN events ON events_entries.event_id = events.id;
+
+    UPDATE db_version SET version = 3;
+    "#;
+    conn.execute_batch(migration_3)?;
+    Ok(())
+}
+
+// Attestations used to be written as json but read back as the compact 32 byte scalar,
+// rewrite any json encoded rows so every path reads the same encoding
+pub fn migrate_to_version_4(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let tx = conn.transaction()?;
+    let json_attestations = {
+        let mut stmt = tx.prepare(
+            "SELECT id::TEXT, attestation_signature FROM events WHERE attestation_signature IS NOT NULL",
+        )?;
+        let rows = stmt.query_map([], |row| {
+            Ok((row.get::<usize, String>(0)?, row.get::<usize, Vec<u8>>(1)?))
+        })?;
+        let mut json_attestations = vec![];
+        for row in rows {
+            let (event_id, blob) = row?;
+            if MaybeScalar::from_slice(&blob).is_ok() {
+                continue;
+            }
+            match serde_json::from_slice::<MaybeScalar>(&blob) {
+                Ok(attestation) => json_attestations.push((event_id, attestation)),
+                Err(e) => warn!(
+                    "unable to normalize attestation for event {}: {}",
+                    event_id, e
+                ),
+            }
+        }
+        json_attestations
+    };
+    for (event_id, attestation) in json_attestations {
+        tx.execute(
+            "UPDATE events SET attestation_signature = ? WHERE id = ?",
+            params![attestation_to_sql(&attestation), event_id],
+        )?;
+    }
+    tx.execute_batch("UPDATE db_version SET version = 4;")?;
+    tx.commit()
+}
+
+// events_settings isn't referenced by any foreign key so it can be altered in place
+pub fn migrate_to_version_5(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_5 = r#"
+    ALTER TABLE events_settings ADD COLUMN auto_sign BOOLEAN DEFAULT false;
+
+    UPDATE db_version SET version = 5;
+    "#;
+    conn.execute_batch(migration_5)?;
+    Ok(())
+}
+
+pub fn migrate_to_version_6(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_6 = r#"
+    ALTER TABLE events_settings ADD COLUMN scoring_mode TEXT DEFAULT 'linear';
+
+    UPDATE db_version SET version = 6;
+    "#;
+    conn.execute_batch(migration_6)?;
+    Ok(())
+}
+
+// Keeps a running count of each event's entries so listings don't count events_entries on every call,
+// events created before version 2 get a settings row with the same defaults the queries fall back to
+pub fn migrate_to_version_7(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_7 = r#"
+    ALTER TABLE events_settings ADD COLUMN IF NOT EXISTS total_entries INTEGER DEFAULT 0;
+
+    INSERT INTO events_settings (event_id)
+    SELECT id FROM events WHERE id NOT IN (SELECT event_id FROM events_settings);
+
+    UPDATE events_settings SET total_entries = entry_counts.total_entries
+    FROM (SELECT event_id, COUNT(id) AS total_entries FROM events_entries GROUP BY event_id) AS entry_counts
+    WHERE events_settings.event_id = entry_counts.event_id;
+
+    UPDATE db_version SET version = 7;
+    "#;
+    conn.execute_batch(migration_7)?;
+    Ok(())
+}
+
+// How each station's forecast compared to its observation, written once per station when an event is signed
+pub fn migrate_to_version_8(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_8 = r#"
+    CREATE TABLE IF NOT EXISTS forecast_accuracy
+    (
+        event_id UUID NOT NULL,
+        station_id TEXT NOT NULL,
+        observation_date TIMESTAMPTZ NOT NULL,
+        temp_low_diff INTEGER NOT NULL,
+        temp_low_outcome options NOT NULL,
+        temp_high_diff INTEGER NOT NULL,
+        temp_high_outcome options NOT NULL,
+        wind_speed_diff INTEGER NOT NULL,
+        wind_speed_outcome options NOT NULL,
+        created_at TIMESTAMPTZ NOT NULL DEFAULT NOW(),
+        PRIMARY KEY (event_id, station_id)
+    );
+
+    UPDATE db_version SET version = 8;
+    "#;
+    conn.execute_batch(migration_8)?;
+    Ok(())
+}
+
+// The offset an observation was reported in, the weather table is referenced by events_weather so it can't be altered
+pub fn migrate_to_version_9(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_9 = r#"
+    CREATE TABLE IF NOT EXISTS weather_observed_offsets
+    (
+        weather_id UUID PRIMARY KEY,
+        original_offset TEXT NOT NULL
+    );
+
+    UPDATE db_version SET version = 9;
+    "#;
+    conn.execute_batch(migration_9)?;
+    Ok(())
+}
+
+// When an event was cancelled, a cancelled event is never signed
+pub fn migrate_to_version_10(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_10 = r#"
+    ALTER TABLE events_settings ADD COLUMN cancelled_at TIMESTAMPTZ;
+
+    UPDATE db_version SET version = 10;
+    "#;
+    conn.execute_batch(migration_10)?;
+    Ok(())
+}
+
+// How long after the observation window starts an event keeps taking entries
+pub fn migrate_to_version_11(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_11 = r#"
+    ALTER TABLE events_settings ADD COLUMN late_entry_grace_seconds INTEGER DEFAULT 0;
+
+    UPDATE db_version SET version = 11;
+    "#;
+    conn.execute_batch(migration_11)?;
+    Ok(())
+}
+
+// Fixed UTC offset the event's day runs in, events from before it run in UTC
+pub fn migrate_to_version_12(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_12 = r#"
+    ALTER TABLE events_settings ADD COLUMN timezone TEXT;
+
+    UPDATE db_version SET version = 12;
+    "#;
+    conn.execute_batch(migration_12)?;
+    Ok(())
+}
+
+// Observed temperatures before they're rounded for scoring, kept out of the weather table for the same reason as version 9
+pub fn migrate_to_version_13(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_13 = r#"
+    CREATE TABLE IF NOT EXISTS weather_observed_raw_temps
+    (
+        weather_id UUID PRIMARY KEY,
+        temp_low DOUBLE NOT NULL,
+        temp_high DOUBLE NOT NULL
+    );
+
+    UPDATE db_version SET version = 13;
+    "#;
+    conn.execute_batch(migration_13)?;
+    Ok(())
+}
+
+// Par tolerance each event is scored with, so changing the oracle's tolerance doesn't rescore events already announced
+pub fn migrate_to_version_14(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_14 = r#"
+    ALTER TABLE events_settings ADD COLUMN par_tolerance INTEGER;
+
+    UPDATE db_version SET version = 14;
+    "#;
+    conn.execute_batch(migration_14)?;
+    Ok(())
+}
+
+// Weather an operator pushed for an event, scored in place of the daemon's readings for that station until
+// the next push replaces it
+pub fn migrate_to_version_15(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_15 = r#"
+    CREATE TABLE IF NOT EXISTS events_weather_overrides
+    (
+        event_id UUID NOT NULL,
+        station_id TEXT NOT NULL,
+        weather_id UUID NOT NULL,
+        PRIMARY KEY (event_id, station_id)
+    );
+
+    UPDATE db_version SET version = 15;
+    "#;
+    conn.execute_batch(migration_15)?;
+    Ok(())
+}
+
+/* how to add the next sql migration:
+pub fn migrate_to_version_16(conn: &mut Connection) -> Result<(), duckdb::Error> {
+    let migration_16 = r#"
+    UPDATE db_version SET version = 16;"#;
+    conn.execute_batch(migration_16)?;
+    Ok(())
+}
 */
@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use uuid::Uuid;
+
+/// Source of the ids the database layer creates for new rows
+pub trait IdGenerator: Send + Sync {
+    fn next_id(&self) -> Uuid;
+}
+
+#[derive(Debug, Default)]
+pub struct UuidV7Generator;
+
+impl IdGenerator for UuidV7Generator {
+    fn next_id(&self) -> Uuid {
+        Uuid::now_v7()
+    }
+}
+
+/// Hands out ids from a fixed, increasing sequence so tests can know ahead of time which ids will be used
+#[derive(Debug)]
+pub struct SequenceIdGenerator {
+    next: AtomicU64,
+}
+
+impl SequenceIdGenerator {
+    pub fn new(start: u64) -> Self {
+        Self {
+            next: AtomicU64::new(start),
+        }
+    }
+
+    pub fn id_at(value: u64) -> Uuid {
+        Uuid::from_u128(value as u128)
+    }
+}
+
+impl IdGenerator for SequenceIdGenerator {
+    fn next_id(&self) -> Uuid {
+        Self::id_at(self.next.fetch_add(1, Ordering::SeqCst))
+    }
+}
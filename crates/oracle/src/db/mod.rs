@@ -1,14 +1,13 @@
 use anyhow::anyhow;
 use dlctix::musig2::secp256k1::PublicKey;
-use dlctix::secp::{MaybeScalar, Scalar};
+use dlctix::secp::{MaybeScalar, Point, Scalar};
 use dlctix::{attestation_locking_point, EventLockingConditions};
-use duckdb::arrow::datatypes::ToByteSlice;
 use duckdb::types::{OrderedMap, ToSqlOutput, Type, Value};
 use duckdb::{ffi, ErrorCode, Row, ToSql};
-use log::{debug, info};
+use log::{debug, info, warn};
 use nostr_sdk::{PublicKey as NostrPublicKey, ToBech32};
 use serde::{Deserialize, Serialize};
-use time::format_description::well_known::Rfc3339;
+use time::format_description::{well_known::Rfc3339, BorrowedFormatItem};
 use time::macros::format_description;
 use time::{Date, Duration, OffsetDateTime, UtcOffset};
 use utoipa::{IntoParams, ToSchema};
@@ -16,13 +15,21 @@ use uuid::Uuid;
 
 pub mod event_data;
 pub mod event_db_migrations;
+pub mod id_generator;
+pub mod nonce_source;
 pub mod outcome_generator;
+pub mod parquet_schema;
+pub mod sql_time;
 pub mod weather_data;
 
 pub use event_data::*;
 pub use event_db_migrations::*;
+pub use id_generator::*;
+pub use nonce_source::*;
 pub use outcome_generator::*;
-pub use weather_data::{Forecast, Observation, Station, WeatherData};
+pub use parquet_schema::*;
+pub use sql_time::*;
+pub use weather_data::{Forecast, Observation, RetryWeatherData, Station, WeatherData};
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
 pub struct CreateEvent {
@@ -32,7 +39,7 @@ pub struct CreateEvent {
     /// Time at which the attestation will be added to the event, needs to be after the observation date
     pub signing_date: OffsetDateTime,
     #[serde(with = "time::serde::rfc3339")]
-    /// Date of when the weather observations occured (midnight UTC), all entries must be made before this time
+    /// Date of when the weather observations occured (midnight in the event's timezone, UTC by default), all entries must be made before this time
     pub observation_date: OffsetDateTime,
     /// NOAA observation stations used in this event
     pub locations: Vec<String>,
@@ -40,8 +47,141 @@ pub struct CreateEvent {
     pub number_of_values_per_entry: usize,
     /// Total number of allowed entries into the event
     pub total_allowed_entries: usize,
-    /// Total number of ranks can win (max 5 ranks)
+    /// Total number of ranks can win (max 5 ranks), can't be more than total_allowed_entries or the number of
+    /// different winning scores an entry can get
     pub number_of_places_win: i64,
+    /// How the winners are picked when the event is signed (defaults to top_n)
+    #[serde(default)]
+    pub win_mode: WinMode,
+    /// Minimum base score (without the time tie breaker) an entry needs to win, required when win_mode is threshold
+    #[serde(default)]
+    pub score_threshold: Option<i64>,
+    /// Have the oracle sign the event on its own once the signing_date passes (defaults to false)
+    #[serde(default)]
+    pub auto_sign: bool,
+    /// How the picks of each entry are turned into its score (defaults to linear)
+    #[serde(default)]
+    pub scoring_mode: ScoringMode,
+    /// Name of a station group configured on the oracle, its stations are added to `locations`
+    #[serde(default)]
+    pub station_group: Option<String>,
+    /// Seconds after the observation_date the event still takes entries, for entries delayed on the way in.
+    /// Needs to be shorter than the observation window (defaults to 0)
+    #[serde(default)]
+    pub late_entry_grace_seconds: u64,
+    /// Fixed UTC offset the event's day runs in, ie. "-05:00", the observation window starts at midnight in it
+    /// and daylight saving time isn't applied (defaults to UTC)
+    #[serde(default)]
+    pub timezone: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct CloneEvent {
+    /// Client needs to provide a valid Uuidv7 for the new event
+    pub id: Uuid,
+    #[serde(with = "time::serde::rfc3339")]
+    /// Time at which the attestation will be added to the new event, needs to be after the observation date
+    pub signing_date: OffsetDateTime,
+    #[serde(with = "time::serde::rfc3339")]
+    /// Date of when the weather observations occur for the new event (midnight in the source event's timezone)
+    pub observation_date: OffsetDateTime,
+}
+
+/// Outcome parameters to change on a live event that has no entries yet, fields left out keep their current
+/// value. The event gets a new nonce and announcement for its new outcomes
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+pub struct UpdateEvent {
+    /// Total number of allowed entries into the event
+    #[serde(default)]
+    pub total_allowed_entries: Option<usize>,
+    /// Total number of ranks can win, the same limits as creating an event apply
+    #[serde(default)]
+    pub number_of_places_win: Option<i64>,
+    /// How the winners are picked when the event is signed
+    #[serde(default)]
+    pub win_mode: Option<WinMode>,
+    /// Minimum base score an entry needs to win, a threshold event keeps its current one when left out and
+    /// switching to top_n clears it
+    #[serde(default)]
+    pub score_threshold: Option<i64>,
+}
+
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum WinMode {
+    /// The entries with the highest scores win
+    #[default]
+    TopN,
+    /// Any entry at or above the score threshold wins, capped at number_of_places_win entries
+    Threshold,
+}
+
+impl std::fmt::Display for WinMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::TopN => write!(f, "top_n"),
+            Self::Threshold => write!(f, "threshold"),
+        }
+    }
+}
+
+impl TryFrom<&str> for WinMode {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "top_n" => Ok(WinMode::TopN),
+            "threshold" => Ok(WinMode::Threshold),
+            val => Err(anyhow!("invalid win mode: {}", val)),
+        }
+    }
+}
+
+impl TryFrom<String> for WinMode {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        WinMode::try_from(s.as_str())
+    }
+}
+
+#[derive(Debug, Default, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoringMode {
+    /// Every correct pick adds its points, Par 20 and Over/Under 10
+    #[default]
+    Linear,
+    /// Every correct pick is worth double the one before it, rewarding entries that get most of their picks right
+    Exponential,
+}
+
+impl std::fmt::Display for ScoringMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Linear => write!(f, "linear"),
+            Self::Exponential => write!(f, "exponential"),
+        }
+    }
+}
+
+impl TryFrom<&str> for ScoringMode {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "linear" => Ok(ScoringMode::Linear),
+            "exponential" => Ok(ScoringMode::Exponential),
+            val => Err(anyhow!("invalid scoring mode: {}", val)),
+        }
+    }
+}
+
+impl TryFrom<String> for ScoringMode {
+    type Error = anyhow::Error;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        ScoringMode::try_from(s.as_str())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -62,9 +202,24 @@ pub struct CreateEventData {
     pub total_allowed_entries: i64,
     /// Total number of ranks can win (max 5 ranks)
     pub number_of_places_win: i64,
+    /// How the winners are picked when the event is signed
+    pub win_mode: WinMode,
+    /// Minimum base score an entry needs to win when using the threshold win mode
+    pub score_threshold: Option<i64>,
+    /// Whether the oracle signs the event on its own once the signing_date passes
+    pub auto_sign: bool,
+    /// How the picks of each entry are turned into its score
+    pub scoring_mode: ScoringMode,
+    /// Seconds after the observation_date the event still takes entries
+    pub late_entry_grace_seconds: u64,
+    /// Fixed UTC offset the event's day runs in, UTC when not set
+    pub timezone: Option<String>,
+    /// Par tolerance the event is scored with, the oracle's current one when not set
+    pub par_tolerance: Option<i64>,
     /// Used to sign the result of the event being watched
     pub nonce: Scalar,
     /// Used in constructing the dlctix transactions
+    #[serde(alias = "event_annoucement")]
     pub event_announcement: EventLockingConditions,
     /// The pubkey of the coordinator
     pub coordinator_pubkey: String,
@@ -75,6 +230,15 @@ impl CreateEventData {
         oracle_pubkey: PublicKey,
         coordinator_pubkey: NostrPublicKey,
         event: CreateEvent,
+    ) -> Result<Self, anyhow::Error> {
+        Self::new_with_nonce_source(oracle_pubkey, coordinator_pubkey, event, &RandomNonceSource)
+    }
+
+    pub fn new_with_nonce_source(
+        oracle_pubkey: PublicKey,
+        coordinator_pubkey: NostrPublicKey,
+        event: CreateEvent,
+        nonce_source: &dyn NonceSource,
     ) -> Result<Self, anyhow::Error> {
         if event.id.get_version_num() != 7 {
             return Err(anyhow!(
@@ -95,21 +259,18 @@ impl CreateEventData {
                 event.number_of_places_win
             ));
         }
-        let possible_user_outcomes: Vec<Vec<usize>> = generate_ranking_permutations(
+        let outcome_messages: Vec<Vec<u8>> = possible_outcome_messages(
+            &event.win_mode,
             event.total_allowed_entries,
             event.number_of_places_win as usize,
         );
-        info!("user outcomes: {:?}", possible_user_outcomes);
 
-        let outcome_messages: Vec<Vec<u8>> = generate_outcome_messages(possible_user_outcomes);
-
-        let mut rng = rand::thread_rng();
-        let nonce = Scalar::random(&mut rng);
+        let nonce = nonce_source.next_nonce();
         let nonce_point = nonce.base_point_mul();
         // Manually set expiry to 7 days after the signature should have been provided so users can get their funds back
         let expiry = event
             .signing_date
-            .saturating_add(Duration::DAY * 7)
+            .saturating_add(ANNOUNCEMENT_EXPIRY_DELAY)
             .unix_timestamp() as u32;
 
         let locking_points = outcome_messages
@@ -129,12 +290,19 @@ impl CreateEventData {
 
         Ok(Self {
             id: event.id,
-            observation_date: event.observation_date,
-            signing_date: event.signing_date,
+            observation_date: truncate_to_sql_precision(event.observation_date),
+            signing_date: truncate_to_sql_precision(event.signing_date),
             nonce,
             total_allowed_entries: event.total_allowed_entries as i64,
-            number_of_places_win: 1_i64, // Default to 1 winning score to simplify possible outcomes
+            number_of_places_win: event.number_of_places_win,
             number_of_values_per_entry: event.number_of_values_per_entry as i64,
+            win_mode: event.win_mode.clone(),
+            score_threshold: event.score_threshold,
+            auto_sign: event.auto_sign,
+            scoring_mode: event.scoring_mode,
+            late_entry_grace_seconds: event.late_entry_grace_seconds,
+            timezone: event.timezone.clone(),
+            par_tolerance: None,
             locations: event.clone().locations,
             event_announcement,
             coordinator_pubkey,
@@ -142,6 +310,261 @@ impl CreateEventData {
     }
 }
 
+/// How long after an event's signing_date its announcement expires and DLCs can be refunded
+pub const ANNOUNCEMENT_EXPIRY_DELAY: Duration = Duration::days(7);
+
+/// How long an event's observations run from its observation_date, always a single day for now
+pub const OBSERVATION_WINDOW: Duration = Duration::days(1);
+
+/// Parses an event's timezone, a fixed UTC offset like "-05:00" or "+09:30", "Z" and "UTC" are UTC
+pub fn parse_timezone(timezone: &str) -> Result<UtcOffset, anyhow::Error> {
+    let timezone = timezone.trim();
+    if timezone.eq_ignore_ascii_case("z") || timezone.eq_ignore_ascii_case("utc") {
+        return Ok(UtcOffset::UTC);
+    }
+    let offset = UtcOffset::parse(timezone, &OFFSET_FORMAT).map_err(|e| {
+        anyhow!(
+            "invalid timezone '{}', expected a UTC offset like -05:00: {}",
+            timezone,
+            e
+        )
+    })?;
+    // Every timezone in use is within 14 hours of UTC
+    if offset.whole_hours().abs() > 14 {
+        return Err(anyhow!(
+            "invalid timezone '{}', UTC offsets range from -14:00 to +14:00",
+            timezone
+        ));
+    }
+    Ok(offset)
+}
+
+/// Formats an offset the way event timezones are stored, ie. "-05:00"
+pub fn format_timezone(offset: UtcOffset) -> String {
+    offset
+        .format(&OFFSET_FORMAT)
+        .unwrap_or_else(|_| offset.to_string())
+}
+
+/// Calendar day an event's weather is for, the observation_date's date in the event's timezone. Timezones
+/// are validated when the event is created, so one that no longer parses falls back to UTC
+pub fn observation_day(observation_date: OffsetDateTime, timezone: Option<&str>) -> Date {
+    let offset = timezone
+        .and_then(|timezone| parse_timezone(timezone).ok())
+        .unwrap_or(UtcOffset::UTC);
+    observation_date.to_offset(offset).date()
+}
+
+/// Every outcome message the oracle could attest to for an event, in the same order as the announcement's locking points
+pub fn possible_outcome_messages(
+    win_mode: &WinMode,
+    total_allowed_entries: usize,
+    number_of_places_win: usize,
+) -> Vec<Vec<u8>> {
+    generate_outcome_messages(possible_user_outcomes(
+        win_mode,
+        total_allowed_entries,
+        number_of_places_win,
+    ))
+}
+
+/// The entry indices each outcome message names as winners, first place first for `top_n` events
+pub fn possible_user_outcomes(
+    win_mode: &WinMode,
+    total_allowed_entries: usize,
+    number_of_places_win: usize,
+) -> Vec<Vec<usize>> {
+    match win_mode {
+        WinMode::TopN => generate_ranking_permutations(total_allowed_entries, number_of_places_win),
+        WinMode::Threshold => {
+            generate_winner_combinations(total_allowed_entries, number_of_places_win)
+        }
+    }
+}
+
+/// Maps the outcomes that can happen with the event's current entries to the entries they pay out, one page at a
+/// time. Entries are indexed by ascending id, the same order the oracle uses when it signs, outcomes naming an index
+/// no entry holds yet are left out until enough entries join. Returns how many outcomes can happen with the page
+pub fn map_outcome_winners(
+    win_mode: &WinMode,
+    total_allowed_entries: usize,
+    number_of_places_win: usize,
+    entry_ids: &[Uuid],
+    filter: &OutcomeWinnersFilter,
+) -> (usize, Vec<OutcomeWinners>) {
+    let mut entry_indices = entry_ids.to_vec();
+    entry_indices.sort();
+    let outcomes: Vec<(usize, Vec<usize>)> =
+        possible_user_outcomes(win_mode, total_allowed_entries, number_of_places_win)
+            .into_iter()
+            .enumerate()
+            .filter(|(_, outcome)| outcome.iter().all(|index| *index < entry_indices.len()))
+            .collect();
+    let page = outcomes
+        .iter()
+        .skip(filter.offset.unwrap_or(0))
+        .take(filter.page_size())
+        .map(|(outcome_index, outcome)| OutcomeWinners {
+            outcome_index: *outcome_index,
+            outcome_message: hex::encode(generate_outcome_messages(vec![outcome.clone()]).concat()),
+            winners: outcome.iter().map(|index| entry_indices[*index]).collect(),
+        })
+        .collect();
+    (outcomes.len(), page)
+}
+
+/// Most outcomes `GET /oracle/events/{event_id}/outcomes/winners` lists when no `limit` is given
+pub const DEFAULT_OUTCOME_WINNERS_LIMIT: usize = 100;
+/// Most outcomes listed in one page, larger limits are capped to it
+pub const MAX_OUTCOME_WINNERS_LIMIT: usize = 1_000;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct OutcomeWinnersFilter {
+    /// Outcomes that can happen to skip, in announcement order (default: 0)
+    pub offset: Option<usize>,
+    /// Most outcomes to list, capped at 1000 (default: 100)
+    pub limit: Option<usize>,
+}
+
+impl OutcomeWinnersFilter {
+    pub fn page_size(&self) -> usize {
+        self.limit
+            .unwrap_or(DEFAULT_OUTCOME_WINNERS_LIMIT)
+            .min(MAX_OUTCOME_WINNERS_LIMIT)
+    }
+}
+
+/// An outcome message the oracle may attest and the entries that win when it does
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct OutcomeWinners {
+    /// Position of the outcome in the announcement's locking points
+    pub outcome_index: usize,
+    /// Hex encoded outcome message
+    pub outcome_message: String,
+    /// Ids of the winning entries, first place first for `top_n` events
+    pub winners: Vec<Uuid>,
+}
+
+/// The winning entries of every outcome of an event, so coordinators can build each contract execution
+/// transaction ahead of the attestation
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EventOutcomeWinners {
+    pub id: Uuid,
+    /// Outcomes that can happen with the current entries, across every page
+    pub total_outcomes: usize,
+    /// Outcomes in this page, in announcement order
+    pub outcomes: Vec<OutcomeWinners>,
+}
+
+/// What a DLC client needs to build contracts for an event, the locking points are the stored announcement
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, ToSchema)]
+pub struct EventAnnouncement {
+    /// Id of the event being announced
+    pub id: Uuid,
+    /// Pubkey of the oracle key that will attest the outcome
+    #[schema(value_type = String)]
+    pub oracle_pubkey: Point,
+    /// Public point of the nonce the oracle committed to sign the outcome with
+    #[schema(value_type = String)]
+    pub nonce_point: Point,
+    /// Hex encoded outcome messages, in the same order as the announcement's locking points
+    pub outcome_messages: Vec<String>,
+    /// The locking points and expiry (unix timestamp) the oracle committed to when the event was created
+    #[schema(value_type = String)]
+    pub event_announcement: EventLockingConditions,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum OutcomeEncoding {
+    #[default]
+    Hex,
+    Base64,
+}
+
+/// The exact messages the oracle may attest for an event, checked against the stored announcement
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EventOutcomeMessages {
+    pub id: Uuid,
+    pub encoding: OutcomeEncoding,
+    /// Encoded outcome messages, in the same order as the announcement's locking points
+    pub outcome_messages: Vec<String>,
+}
+
+/// Percent of an event's pot paid to each winning place, first place first
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PayoutSplit(Vec<u64>);
+
+impl PayoutSplit {
+    pub fn new(percents: Vec<u64>) -> Result<Self, anyhow::Error> {
+        if percents.is_empty() {
+            return Err(anyhow!("must pay out at least one place"));
+        }
+        let total: u64 = percents.iter().sum();
+        if total != 100 {
+            return Err(anyhow!(
+                "places need to add up to 100 percent, got {}",
+                total
+            ));
+        }
+        Ok(Self(percents))
+    }
+
+    /// Splits the pot as evenly as whole percents allow, the leftover goes to the top places (34/33/33)
+    pub fn even(places: usize) -> Self {
+        let places = places.max(1) as u64;
+        let remainder = 100 % places;
+        Self(
+            (0..places)
+                .map(|place| 100 / places + u64::from(place < remainder))
+                .collect(),
+        )
+    }
+
+    pub fn percents(&self) -> &[u64] {
+        &self.0
+    }
+}
+
+impl TryFrom<&str> for PayoutSplit {
+    type Error = anyhow::Error;
+
+    /// Parses percents separated by commas, ie. `60,30,10`
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        let percents = s
+            .split(',')
+            .map(|percent| {
+                percent
+                    .trim()
+                    .parse::<u64>()
+                    .map_err(|_| anyhow!("invalid percent: {}", percent))
+            })
+            .collect::<Result<Vec<u64>, anyhow::Error>>()?;
+        PayoutSplit::new(percents)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct PlacePayout {
+    /// 1 is first place
+    pub place: usize,
+    pub entry_id: Uuid,
+    pub score: Option<i64>,
+    /// Percent of the pot paid to this place
+    pub percent: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EventPayouts {
+    pub id: Uuid,
+    /// Percent per place the payouts were computed with, first place first
+    pub split: Vec<u64>,
+    /// Winning entries in place order
+    pub payouts: Vec<PlacePayout>,
+    /// Percent of places no entry won, ie. when fewer entries passed a threshold event than it pays out
+    pub unclaimed_percent: u64,
+}
+
 impl From<CreateEventData> for Event {
     fn from(value: CreateEventData) -> Self {
         Self {
@@ -152,6 +575,13 @@ impl From<CreateEventData> for Event {
             total_allowed_entries: value.total_allowed_entries,
             number_of_places_win: value.number_of_places_win,
             number_of_values_per_entry: value.number_of_values_per_entry,
+            win_mode: value.win_mode,
+            score_threshold: value.score_threshold,
+            auto_sign: value.auto_sign,
+            scoring_mode: value.scoring_mode,
+            late_entry_grace_seconds: value.late_entry_grace_seconds,
+            timezone: value.timezone,
+            par_tolerance: value.par_tolerance,
             event_announcement: value.event_announcement,
             nonce: value.nonce,
             status: EventStatus::default(),
@@ -160,6 +590,7 @@ impl From<CreateEventData> for Event {
             weather: vec![],
             attestation: None,
             coordinator_pubkey: value.coordinator_pubkey,
+            cancelled_at: None,
         }
     }
 }
@@ -169,6 +600,8 @@ pub struct EventFilter {
     // TODO: add more options, proper pagination and search
     pub limit: Option<usize>,
     pub event_ids: Option<Vec<Uuid>>,
+    /// Only events with this NOAA station in their locations
+    pub station_id: Option<String>,
 }
 
 impl Default for EventFilter {
@@ -176,6 +609,7 @@ impl Default for EventFilter {
         Self {
             limit: Some(100_usize),
             event_ids: None,
+            station_id: None,
         }
     }
 }
@@ -191,16 +625,20 @@ pub struct SignEvent {
     #[schema(value_type = String)]
     pub nonce: Scalar,
     #[schema(value_type = String)]
+    #[serde(alias = "event_annoucement")]
     pub event_announcement: EventLockingConditions,
     pub number_of_places_win: i64,
     pub number_of_values_per_entry: i64,
     #[schema(value_type = String)]
     pub attestation: Option<MaybeScalar>,
+    pub win_mode: WinMode,
+    pub score_threshold: Option<i64>,
 }
 
 impl SignEvent {
     pub fn update_status(&mut self) {
-        self.status = get_status(self.observation_date, self.attestation)
+        // Cancelled events are never picked up to be signed
+        self.status = get_status(self.observation_date, self.attestation, None)
     }
 }
 
@@ -208,10 +646,6 @@ impl<'a> TryFrom<&Row<'a>> for SignEvent {
     type Error = duckdb::Error;
 
     fn try_from(row: &Row) -> Result<Self, Self::Error> {
-        //raw date format 2024-08-11 00:27:39.013046-04
-        let sql_time_format = format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour]"
-        );
         let mut sign_events = SignEvent {
             id: row
                 .get::<usize, String>(0)
@@ -219,30 +653,16 @@ impl<'a> TryFrom<&Row<'a>> for SignEvent {
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?,
             signing_date: row
                 .get::<usize, String>(1)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             observation_date: row
                 .get::<usize, String>(2)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             status: EventStatus::default(),
             number_of_places_win: row.get::<usize, i64>(3)?,
             number_of_values_per_entry: row.get::<usize, i64>(4)?,
-            attestation: row
-                .get::<usize, Value>(5)
-                .map(|v| {
-                    let blob_attestation = match v {
-                        Value::Blob(raw) => raw,
-                        _ => vec![],
-                    };
-                    if !blob_attestation.is_empty() {
-                        //TODO: handle the conversion more gracefully than unwrap
-                        Some(MaybeScalar::from_slice(blob_attestation.to_byte_slice()).unwrap())
-                    } else {
-                        None
-                    }
-                })
-                .map_err(|e| duckdb::Error::FromSqlConversionFailure(5, Type::Any, Box::new(e)))?,
+            attestation: attestation_from_sql(row.get::<usize, Value>(5)?, 5)?,
             nonce: row
                 .get::<usize, Value>(6)
                 .map(|raw| {
@@ -263,6 +683,11 @@ impl<'a> TryFrom<&Row<'a>> for SignEvent {
                     serde_json::from_slice(&blob)
                 })?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(7, Type::Any, Box::new(e)))?,
+            win_mode: row
+                .get::<usize, String>(8)
+                .map(WinMode::try_from)?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(8, Type::Any, e.into()))?,
+            score_threshold: row.get::<usize, Option<i64>>(9)?,
         };
         sign_events.update_status();
         Ok(sign_events)
@@ -284,12 +709,78 @@ pub struct ActiveEvent {
     pub number_of_places_win: i64,
     #[schema(value_type = String)]
     pub attestation: Option<MaybeScalar>,
+    pub scoring_mode: ScoringMode,
+    /// Fixed UTC offset the event's day runs in, UTC when not set
+    pub timezone: Option<String>,
+    /// Par tolerance stored when the event was created, events from before it use the oracle's
+    pub par_tolerance: Option<i64>,
 }
 
 impl ActiveEvent {
     pub fn update_status(&mut self) {
-        self.status = get_status(self.observation_date, self.attestation)
+        // Cancelled events are left out of the active events
+        self.status = get_status(self.observation_date, self.attestation, None)
     }
+
+    /// Calendar day the event's weather is for, in the event's timezone
+    pub fn observation_day(&self) -> Date {
+        observation_day(self.observation_date, self.timezone.as_deref())
+    }
+}
+
+/// Most signed event ids `GET /oracle/events/sign-status` lists when no `signed_limit` is given
+pub const DEFAULT_SIGNED_LIMIT: usize = 100;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, IntoParams)]
+pub struct SignStatusFilter {
+    /// Most signed event ids to list, the newest signed events are kept (default: 100)
+    pub signed_limit: Option<usize>,
+}
+
+/// Events in one signing status
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct EventStatusGroup {
+    pub count: usize,
+    /// Oldest event first
+    pub event_ids: Vec<Uuid>,
+}
+
+/// Every event that wasn't cancelled grouped by how far along it is towards being signed
+#[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct EventSignStatus {
+    pub live: EventStatusGroup,
+    pub running: EventStatusGroup,
+    /// Observation window is over and the oracle still has to sign these
+    pub completed: EventStatusGroup,
+    /// `count` is every signed event, `event_ids` only holds the newest `signed_limit` of them
+    pub signed: EventStatusGroup,
+}
+
+impl From<Vec<Uuid>> for EventStatusGroup {
+    fn from(event_ids: Vec<Uuid>) -> Self {
+        Self {
+            count: event_ids.len(),
+            event_ids,
+        }
+    }
+}
+
+/// A station the oracle has stored weather readings for
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct StoredStation {
+    pub station_id: String,
+    /// Name of the station from the weather data, not set when the station isn't in it or it's unavailable
+    pub station_name: Option<String>,
+    /// Number of weather readings stored for the station across every event
+    pub readings: usize,
+}
+
+/// A station at least one unsigned event still needs weather data for
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ActiveStation {
+    pub station_id: String,
+    /// Number of unsigned events using the station
+    pub event_count: usize,
 }
 
 #[derive(Debug, Default, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
@@ -303,6 +794,8 @@ pub enum EventStatus {
     Completed,
     /// Event has completed and been signed by the oracle
     Signed,
+    /// Event was cancelled by its coordinator before being signed, it will never be signed
+    Cancelled,
 }
 
 impl std::fmt::Display for EventStatus {
@@ -312,6 +805,7 @@ impl std::fmt::Display for EventStatus {
             Self::Running => write!(f, "running"),
             Self::Completed => write!(f, "completed"),
             Self::Signed => write!(f, "signed"),
+            Self::Cancelled => write!(f, "cancelled"),
         }
     }
 }
@@ -325,6 +819,7 @@ impl TryFrom<&str> for EventStatus {
             "running" => Ok(EventStatus::Running),
             "completed" => Ok(EventStatus::Completed),
             "signed" => Ok(EventStatus::Signed),
+            "cancelled" => Ok(EventStatus::Cancelled),
             val => Err(anyhow!("invalid status: {}", val)),
         }
     }
@@ -339,6 +834,7 @@ impl TryFrom<String> for EventStatus {
             "running" => Ok(EventStatus::Running),
             "completed" => Ok(EventStatus::Completed),
             "signed" => Ok(EventStatus::Signed),
+            "cancelled" => Ok(EventStatus::Cancelled),
             val => Err(anyhow!("invalid status: {}", val)),
         }
     }
@@ -348,10 +844,6 @@ impl<'a> TryFrom<&Row<'a>> for ActiveEvent {
     type Error = duckdb::Error;
 
     fn try_from(row: &Row) -> Result<Self, Self::Error> {
-        //raw date format 2024-08-11 00:27:39.013046-04
-        let sql_time_format = format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour]"
-        );
         let mut active_events = ActiveEvent {
             id: row
                 .get::<usize, String>(0)
@@ -359,11 +851,11 @@ impl<'a> TryFrom<&Row<'a>> for ActiveEvent {
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?,
             signing_date: row
                 .get::<usize, String>(1)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             observation_date: row
                 .get::<usize, String>(2)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             locations: row
                 .get::<usize, Value>(3)
@@ -386,21 +878,13 @@ impl<'a> TryFrom<&Row<'a>> for ActiveEvent {
             total_entries: row.get::<usize, i64>(5)?,
             number_of_places_win: row.get::<usize, i64>(6)?,
             number_of_values_per_entry: row.get::<usize, i64>(7)?,
-            attestation: row
-                .get::<usize, Value>(8)
-                .map(|v| {
-                    let blob_attestation = match v {
-                        Value::Blob(raw) => raw,
-                        _ => vec![],
-                    };
-                    if !blob_attestation.is_empty() {
-                        //TODO: handle the conversion more gracefully than unwrap
-                        Some(MaybeScalar::from_slice(blob_attestation.to_byte_slice()).unwrap())
-                    } else {
-                        None
-                    }
-                })
-                .map_err(|e| duckdb::Error::FromSqlConversionFailure(8, Type::Any, Box::new(e)))?,
+            attestation: attestation_from_sql(row.get::<usize, Value>(8)?, 8)?,
+            scoring_mode: row
+                .get::<usize, String>(9)
+                .map(ScoringMode::try_from)?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(9, Type::Any, e.into()))?,
+            timezone: row.get::<usize, Option<String>>(10)?,
+            par_tolerance: row.get::<usize, Option<i64>>(11)?,
         };
         active_events.update_status();
         Ok(active_events)
@@ -420,7 +904,7 @@ pub struct EventSummary {
     pub locations: Vec<String>,
     /// The number of values that can be selected per entry in the event (default to number_of_locations * 3, (temp_low, temp_high, wind_speed))
     pub number_of_values_per_entry: i64,
-    /// Current status of the event, where in the lifecyle are we (LIVE, RUNNING, COMPLETED, SIGNED, defaults to LIVE)
+    /// Current status of the event, where in the lifecyle are we (LIVE, RUNNING, COMPLETED, SIGNED, CANCELLED, defaults to LIVE)
     pub status: EventStatus,
     /// Knowing the total number of entries, how many can place
     /// The dlctix coordinator can determine how many transactions to create
@@ -436,28 +920,35 @@ pub struct EventSummary {
     /// Used to sign the result of the event being watched
     #[schema(value_type = String)]
     pub nonce: Scalar,
+    /// When the event was cancelled, a cancelled event is never signed
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub cancelled_at: Option<OffsetDateTime>,
 }
 
 impl EventSummary {
     pub fn update_status(&mut self) {
-        self.status = get_status(self.observation_date, self.attestation)
+        self.status = get_status(self.observation_date, self.attestation, self.cancelled_at)
     }
 }
 
 pub fn get_status(
     observation_date: OffsetDateTime,
     attestation: Option<MaybeScalar>,
+    cancelled_at: Option<OffsetDateTime>,
 ) -> EventStatus {
-    //always have the events run for a single day for now
+    if cancelled_at.is_some() && attestation.is_none() {
+        return EventStatus::Cancelled;
+    }
+
     if observation_date < OffsetDateTime::now_utc()
-        && observation_date.saturating_sub(Duration::days(1)) > OffsetDateTime::now_utc()
+        && observation_date.saturating_sub(OBSERVATION_WINDOW) > OffsetDateTime::now_utc()
         && attestation.is_none()
     {
         return EventStatus::Running;
     }
 
     if observation_date < OffsetDateTime::now_utc()
-        && observation_date.saturating_sub(Duration::days(1)) < OffsetDateTime::now_utc()
+        && observation_date.saturating_sub(OBSERVATION_WINDOW) < OffsetDateTime::now_utc()
         && attestation.is_none()
     {
         return EventStatus::Completed;
@@ -470,14 +961,34 @@ pub fn get_status(
     EventStatus::Live
 }
 
+/// Attestations are stored as the compact 32 byte scalar, the same encoding on every read and write path
+pub fn attestation_to_sql(attestation: &MaybeScalar) -> Vec<u8> {
+    attestation.serialize().to_vec()
+}
+
+/// Reads a stored attestation, an empty or missing blob means the event has not been signed yet
+pub fn attestation_from_sql(
+    value: Value,
+    index: usize,
+) -> Result<Option<MaybeScalar>, duckdb::Error> {
+    let blob_attestation = match value {
+        Value::Blob(raw) => raw,
+        _ => vec![],
+    };
+    if blob_attestation.is_empty() {
+        return Ok(None);
+    }
+    MaybeScalar::from_slice(&blob_attestation)
+        .map(Some)
+        .map_err(|e| {
+            duckdb::Error::FromSqlConversionFailure(index, Type::Blob, e.to_string().into())
+        })
+}
+
 impl<'a> TryFrom<&Row<'a>> for EventSummary {
     type Error = duckdb::Error;
 
     fn try_from(row: &Row) -> Result<Self, Self::Error> {
-        //raw date format 2024-08-11 00:27:39.013046-04
-        let sql_time_format = format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour]"
-        );
         let mut event_summary = EventSummary {
             id: row
                 .get::<usize, String>(0)
@@ -485,11 +996,11 @@ impl<'a> TryFrom<&Row<'a>> for EventSummary {
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?,
             signing_date: row
                 .get::<usize, String>(1)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             observation_date: row
                 .get::<usize, String>(2)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             status: EventStatus::default(),
             locations: row
@@ -512,21 +1023,7 @@ impl<'a> TryFrom<&Row<'a>> for EventSummary {
             total_entries: row.get::<usize, i64>(5)?,
             number_of_places_win: row.get::<usize, i64>(6)?,
             number_of_values_per_entry: row.get::<usize, i64>(7)?,
-            attestation: row
-                .get::<usize, Value>(8)
-                .map(|v| {
-                    let blob_attestation = match v {
-                        Value::Blob(raw) => raw,
-                        _ => vec![],
-                    };
-                    if !blob_attestation.is_empty() {
-                        //TODO: handle the conversion more gracefully than unwrap
-                        Some(MaybeScalar::from_slice(blob_attestation.to_byte_slice()).unwrap())
-                    } else {
-                        None
-                    }
-                })
-                .map_err(|e| duckdb::Error::FromSqlConversionFailure(8, Type::Any, Box::new(e)))?,
+            attestation: attestation_from_sql(row.get::<usize, Value>(8)?, 8)?,
             nonce: row
                 .get::<usize, Value>(9)
                 .map(|raw| {
@@ -537,6 +1034,11 @@ impl<'a> TryFrom<&Row<'a>> for EventSummary {
                     serde_json::from_slice(&blob)
                 })?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(9, Type::Any, Box::new(e)))?,
+            cancelled_at: row
+                .get::<usize, Option<String>>(10)?
+                .map(|val| parse_sql_timestamp(&val))
+                .transpose()
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(10, Type::Any, Box::new(e)))?,
             weather: vec![],
         };
         event_summary.update_status();
@@ -557,7 +1059,7 @@ pub struct Event {
     pub locations: Vec<String>,
     /// The number of values that can be selected per entry in the event (default to number_of_locations * 3, (temp_low, temp_high, wind_speed))
     pub number_of_values_per_entry: i64,
-    /// Current status of the event, where in the lifecyle are we (LIVE, RUNNING, COMPLETED, SIGNED)
+    /// Current status of the event, where in the lifecyle are we (LIVE, RUNNING, COMPLETED, SIGNED, CANCELLED)
     pub status: EventStatus,
     /// Knowing the total number of entries, how many can place
     /// The dlctix coordinator can determine how many transactions to create
@@ -574,17 +1076,87 @@ pub struct Event {
     pub nonce: Scalar,
     /// Holds the predefined outcomes the oracle will attest to at event complete
     #[schema(value_type = String)]
+    #[serde(alias = "event_annoucement")]
     pub event_announcement: EventLockingConditions,
     /// When added it means the oracle has signed that the current data is the final result
     #[schema(value_type = String)]
     pub attestation: Option<MaybeScalar>,
     /// The pubkey of the coordinator
     pub coordinator_pubkey: String,
+    /// How the winners are picked when the event is signed
+    pub win_mode: WinMode,
+    /// Minimum base score an entry needs to win when using the threshold win mode
+    pub score_threshold: Option<i64>,
+    /// Whether the oracle signs the event on its own once the signing_date passes
+    pub auto_sign: bool,
+    /// How the picks of each entry are turned into its score
+    pub scoring_mode: ScoringMode,
+    /// Seconds after the observation_date the event still takes entries
+    pub late_entry_grace_seconds: u64,
+    /// Fixed UTC offset the event's day runs in, UTC when not set
+    #[serde(default)]
+    pub timezone: Option<String>,
+    /// How far off the forecast an observed value can be and still score as Par, fixed when the event is
+    /// created. Events from before it was stored are scored with the oracle's current tolerance
+    #[serde(default)]
+    pub par_tolerance: Option<i64>,
+    /// When the event was cancelled, a cancelled event is never signed
+    #[serde(default, with = "time::serde::rfc3339::option")]
+    pub cancelled_at: Option<OffsetDateTime>,
 }
 
 impl Event {
     pub fn update_status(&mut self) {
-        self.status = get_status(self.observation_date, self.attestation)
+        self.status = get_status(self.observation_date, self.attestation, self.cancelled_at)
+    }
+
+    /// One row per entry and event location, so every entry has the same rows no matter which stations it
+    /// picked. Stations an entry didn't pick, picks it left out and unscored entries are left blank
+    pub fn entries_csv(&self) -> String {
+        let mut csv = String::from("entry_id,station,temp_low,temp_high,wind_speed,score\n");
+        let pick = |value: &Option<ValueOptions>| {
+            value
+                .as_ref()
+                .map(|value| value.to_string())
+                .unwrap_or_default()
+        };
+        for entry in &self.entries {
+            let score = entry
+                .score
+                .map(|score| score.to_string())
+                .unwrap_or_default();
+            for location in &self.locations {
+                let choice = entry
+                    .expected_observations
+                    .iter()
+                    .find(|choice| choice.stations.eq_ignore_ascii_case(location));
+                csv.push_str(&format!(
+                    "{},{},{},{},{},{}\n",
+                    entry.id,
+                    csv_field(location),
+                    choice
+                        .map(|choice| pick(&choice.temp_low))
+                        .unwrap_or_default(),
+                    choice
+                        .map(|choice| pick(&choice.temp_high))
+                        .unwrap_or_default(),
+                    choice
+                        .map(|choice| pick(&choice.wind_speed))
+                        .unwrap_or_default(),
+                    score
+                ));
+            }
+        }
+        csv
+    }
+}
+
+/// Quotes a csv field holding a comma, quote or newline
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
     }
 }
 
@@ -592,10 +1164,6 @@ impl<'a> TryFrom<&Row<'a>> for Event {
     type Error = duckdb::Error;
 
     fn try_from(row: &Row) -> Result<Self, Self::Error> {
-        //raw date format 2024-08-11 00:27:39.013046-04
-        let sql_time_format = format_description!(
-            "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour]"
-        );
         let mut oracle_event_data = Event {
             id: row
                 .get::<usize, String>(0)
@@ -608,17 +1176,12 @@ impl<'a> TryFrom<&Row<'a>> for Event {
                 .get::<usize, String>(1)
                 .map(|val| {
                     debug!("{}", val.to_string());
-                    OffsetDateTime::parse(&val, &sql_time_format)
+                    parse_sql_timestamp(&val)
                 })?
-                .map(|val| {
-                    debug!("{}", val.to_string());
-                    val.to_offset(UtcOffset::UTC)
-                })
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             observation_date: row
                 .get::<usize, String>(2)
-                .map(|val| OffsetDateTime::parse(&val, &sql_time_format))?
-                .map(|val| val.to_offset(UtcOffset::UTC))
+                .map(|val| parse_sql_timestamp(&val))?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             event_announcement: row
                 .get::<usize, Value>(3)
@@ -650,24 +1213,7 @@ impl<'a> TryFrom<&Row<'a>> for Event {
             total_allowed_entries: row.get::<usize, i64>(5)?,
             number_of_places_win: row.get::<usize, i64>(6)?,
             number_of_values_per_entry: row.get::<usize, i64>(7)?,
-            attestation: row
-                .get::<usize, Value>(8)
-                .map(|v| {
-                    info!("val: {:?}", v);
-                    let blob_attestation = match v {
-                        Value::Blob(raw) => raw,
-                        _ => vec![],
-                    };
-                    if !blob_attestation.is_empty() {
-                        //TODO: handle the conversion more gracefully than unwrap
-                        let converted: MaybeScalar =
-                            serde_json::from_slice(&blob_attestation).unwrap();
-                        Some(converted)
-                    } else {
-                        None
-                    }
-                })
-                .map_err(|e| duckdb::Error::FromSqlConversionFailure(8, Type::Any, Box::new(e)))?,
+            attestation: attestation_from_sql(row.get::<usize, Value>(8)?, 8)?,
             nonce: row
                 .get::<usize, Value>(9)
                 .map(|raw| {
@@ -679,6 +1225,24 @@ impl<'a> TryFrom<&Row<'a>> for Event {
                 })?
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(9, Type::Any, Box::new(e)))?,
             coordinator_pubkey: row.get(10)?,
+            win_mode: row
+                .get::<usize, String>(11)
+                .map(WinMode::try_from)?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(11, Type::Any, e.into()))?,
+            score_threshold: row.get::<usize, Option<i64>>(12)?,
+            auto_sign: row.get::<usize, bool>(13)?,
+            scoring_mode: row
+                .get::<usize, String>(14)
+                .map(ScoringMode::try_from)?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(14, Type::Any, e.into()))?,
+            cancelled_at: row
+                .get::<usize, Option<String>>(15)?
+                .map(|val| parse_sql_timestamp(&val))
+                .transpose()
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(15, Type::Any, Box::new(e)))?,
+            late_entry_grace_seconds: row.get::<usize, u64>(16)?,
+            timezone: row.get::<usize, Option<String>>(17)?,
+            par_tolerance: row.get::<usize, Option<i64>>(18)?,
             status: EventStatus::default(),
             //These nested values have to be made by more quries
             entry_ids: vec![],
@@ -748,10 +1312,26 @@ impl<'a> TryFrom<&Row<'a>> for Weather {
                         None,
                     )),
                 })??;
+        // Kept in weather_observed_offsets and weather_observed_raw_temps, the weather table can't be altered
+        let original_offset = row.get::<usize, Option<String>>(3)?;
+        let raw_temps = match (
+            row.get::<usize, Option<f64>>(4)?,
+            row.get::<usize, Option<f64>>(5)?,
+        ) {
+            (Some(temp_low), Some(temp_high)) => Some(RawObservedTemps {
+                temp_low,
+                temp_high,
+            }),
+            _ => None,
+        };
         Ok(Weather {
             station_id: row.get::<usize, String>(0)?,
             forecasted,
-            observed,
+            observed: observed.map(|observed| Observed {
+                original_offset,
+                raw_temps,
+                ..observed
+            }),
         })
     }
 }
@@ -850,6 +1430,57 @@ pub struct Observed {
     pub temp_low: i64,
     pub temp_high: i64,
     pub wind_speed: i64,
+    /// Offset the observation's start_time was reported in, ie. `-04:00` during daylight saving time.
+    /// Only kept when the oracle is set to preserve observation offsets, `date` is always read back in UTC
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub original_offset: Option<String>,
+    /// Temperatures as they were observed, before they're rounded into `temp_low` and `temp_high`.
+    /// Only kept when the oracle is set to keep raw observation temps, scoring always uses the rounded values
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub raw_temps: Option<RawObservedTemps>,
+}
+
+/// Unrounded observed temperatures, compared bit for bit so observations can still be `Eq`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+pub struct RawObservedTemps {
+    pub temp_low: f64,
+    pub temp_high: f64,
+}
+
+impl PartialEq for RawObservedTemps {
+    fn eq(&self, other: &Self) -> bool {
+        self.temp_low.to_bits() == other.temp_low.to_bits()
+            && self.temp_high.to_bits() == other.temp_high.to_bits()
+    }
+}
+
+impl Eq for RawObservedTemps {}
+
+const OFFSET_FORMAT: &[BorrowedFormatItem<'static>] =
+    format_description!("[offset_hour sign:mandatory]:[offset_minute]");
+
+impl Observed {
+    /// Keeps the offset `date` is currently in as the original offset
+    pub fn with_original_offset(mut self) -> Self {
+        self.original_offset = self.date.offset().format(&OFFSET_FORMAT).ok();
+        self
+    }
+
+    /// Leaves only the rounded temperatures
+    pub fn without_raw_temps(mut self) -> Self {
+        self.raw_temps = None;
+        self
+    }
+
+    /// `date` in the offset the observation was reported in, UTC when it wasn't kept
+    pub fn original_date(&self) -> OffsetDateTime {
+        let offset = self
+            .original_offset
+            .as_deref()
+            .and_then(|offset| UtcOffset::parse(offset, &OFFSET_FORMAT).ok())
+            .unwrap_or(UtcOffset::UTC);
+        self.date.to_offset(offset)
+    }
 }
 
 impl TryFrom<&Observation> for Observed {
@@ -860,6 +1491,11 @@ impl TryFrom<&Observation> for Observed {
             temp_low: value.temp_low.round() as i64,
             temp_high: value.temp_high.round() as i64,
             wind_speed: value.wind_speed,
+            original_offset: None,
+            raw_temps: Some(RawObservedTemps {
+                temp_low: value.temp_low,
+                temp_high: value.temp_high,
+            }),
         })
     }
 }
@@ -882,15 +1518,13 @@ impl TryInto<Observed> for &OrderedMap<String, Value> {
                 )),
             })
             .and_then(|timestamp| {
-                OffsetDateTime::from_unix_timestamp_nanos((*timestamp as i128) * 1000_i128).map_err(
-                    |e| {
-                        anyhow!(
-                            "error parsing observed date into offsetdatetime: {} {}",
-                            timestamp,
-                            e
-                        )
-                    },
-                )
+                from_sql_micros(*timestamp).map_err(|e| {
+                    anyhow!(
+                        "error parsing observed date into offsetdatetime: {} {}",
+                        timestamp,
+                        e
+                    )
+                })
             })
             .map(|val| val.to_offset(UtcOffset::UTC))?;
 
@@ -926,6 +1560,8 @@ impl TryInto<Observed> for &OrderedMap<String, Value> {
             temp_low,
             temp_high,
             wind_speed,
+            original_offset: None,
+            raw_temps: None,
         })
     }
 }
@@ -948,15 +1584,13 @@ impl TryInto<Observed> for OrderedMap<String, Value> {
                 )),
             })
             .and_then(|timestamp| {
-                OffsetDateTime::from_unix_timestamp_nanos((*timestamp as i128) * 1000_i128).map_err(
-                    |e| {
-                        anyhow!(
-                            "error parsing observed date into offsetdatetime: {} {}",
-                            timestamp,
-                            e
-                        )
-                    },
-                )
+                from_sql_micros(*timestamp).map_err(|e| {
+                    anyhow!(
+                        "error parsing observed date into offsetdatetime: {} {}",
+                        timestamp,
+                        e
+                    )
+                })
             })
             .map(|val| val.to_offset(UtcOffset::UTC))?;
 
@@ -992,6 +1626,8 @@ impl TryInto<Observed> for OrderedMap<String, Value> {
             temp_low,
             temp_high,
             wind_speed,
+            original_offset: None,
+            raw_temps: None,
         })
     }
 }
@@ -1001,7 +1637,7 @@ impl ToSql for Observed {
         let ordered_struct: OrderedMap<String, Value> = OrderedMap::from(vec![
             (
                 String::from("date"),
-                Value::Text(self.date.format(&Rfc3339).unwrap()),
+                Value::Text(to_sql_timestamp(self.date).unwrap()),
             ),
             (String::from("temp_low"), Value::Int(self.temp_low as i32)),
             (String::from("temp_high"), Value::Int(self.temp_high as i32)),
@@ -1021,7 +1657,7 @@ impl ToRawSql for Observed {
         // example of how to write a struct to duckdb: `INSERT INTO t1 VALUES (ROW('a', 42));`
         let mut vals = String::new();
         vals.push_str("ROW('");
-        let data_str = self.date.format(&Rfc3339).unwrap();
+        let data_str = to_sql_timestamp(self.date).unwrap();
         vals.push_str(&data_str);
         vals.push_str(r#"',"#);
         vals.push_str(&format!("{}", self.temp_low));
@@ -1059,15 +1695,13 @@ impl TryInto<Forecasted> for &OrderedMap<String, Value> {
                 )),
             })
             .and_then(|timestamp| {
-                OffsetDateTime::from_unix_timestamp_nanos((*timestamp as i128) * 1000_i128).map_err(
-                    |e| {
-                        anyhow!(
-                            "error parsing forecast date into offsetdatetime: {} {}",
-                            timestamp,
-                            e
-                        )
-                    },
-                )
+                from_sql_micros(*timestamp).map_err(|e| {
+                    anyhow!(
+                        "error parsing forecast date into offsetdatetime: {} {}",
+                        timestamp,
+                        e
+                    )
+                })
             })
             .map(|val| val.to_offset(UtcOffset::UTC))?;
 
@@ -1123,15 +1757,13 @@ impl TryInto<Forecasted> for OrderedMap<String, Value> {
                 )),
             })
             .and_then(|timestamp| {
-                OffsetDateTime::from_unix_timestamp_nanos((*timestamp as i128) * 1000_i128).map_err(
-                    |e| {
-                        anyhow!(
-                            "error parsing forecast date into offsetdatetime: {} {}",
-                            timestamp,
-                            e
-                        )
-                    },
-                )
+                from_sql_micros(*timestamp).map_err(|e| {
+                    anyhow!(
+                        "error parsing forecast date into offsetdatetime: {} {}",
+                        timestamp,
+                        e
+                    )
+                })
             })
             .map(|val| val.to_offset(UtcOffset::UTC))?;
 
@@ -1183,7 +1815,7 @@ impl ToRawSql for Forecasted {
         // example of how to write a struct to duckdb: `INSERT INTO t1 VALUES (ROW('a', 42));`
         let mut vals = String::new();
         vals.push_str("ROW('");
-        let data_str = self.date.format(&Rfc3339).unwrap();
+        let data_str = to_sql_timestamp(self.date).unwrap();
         vals.push_str(&data_str);
         vals.push_str(r#"',"#);
         vals.push_str(&format!("{}", self.temp_low));
@@ -1201,7 +1833,7 @@ impl ToSql for Forecasted {
         let ordered_struct: OrderedMap<String, Value> = OrderedMap::from(vec![
             (
                 String::from("date"),
-                Value::Text(self.date.format(&Rfc3339).unwrap()),
+                Value::Text(to_sql_timestamp(self.date).unwrap()),
             ),
             (String::from("temp_low"), Value::Int(self.temp_low as i32)),
             (String::from("temp_high"), Value::Int(self.temp_high as i32)),
@@ -1214,6 +1846,220 @@ impl ToSql for Forecasted {
     }
 }
 
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct WeatherDiff {
+    pub station_id: String,
+    pub forecasted: Forecasted,
+    pub observed: Option<Observed>,
+    /// Only set once the station has an observation
+    pub temp_low: Option<ValueDiff>,
+    /// Only set once the station has an observation
+    pub temp_high: Option<ValueDiff>,
+    /// Only set once the station has an observation
+    pub wind_speed: Option<ValueDiff>,
+}
+
+impl From<&Weather> for WeatherDiff {
+    fn from(weather: &Weather) -> Self {
+        WeatherDiff::new(weather, ParTolerance::default())
+    }
+}
+
+impl WeatherDiff {
+    pub fn new(weather: &Weather, par_tolerance: ParTolerance) -> Self {
+        let diff = |values: fn(&Forecasted, &Observed) -> (i64, i64)| {
+            weather.observed.as_ref().map(|observed| {
+                let (forecasted, observed) = values(&weather.forecasted, observed);
+                ValueDiff::with_par_tolerance(forecasted, observed, par_tolerance)
+            })
+        };
+        Self {
+            station_id: weather.station_id.clone(),
+            forecasted: weather.forecasted.clone(),
+            observed: weather.observed.clone(),
+            temp_low: diff(|forecasted, observed| (forecasted.temp_low, observed.temp_low)),
+            temp_high: diff(|forecasted, observed| (forecasted.temp_high, observed.temp_high)),
+            wind_speed: diff(|forecasted, observed| (forecasted.wind_speed, observed.wind_speed)),
+        }
+    }
+}
+
+/// How one pick of an entry scored against its station's weather
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct PickBreakdown {
+    pub station_id: String,
+    pub dimension: Dimension,
+    pub choice: ValueOptions,
+    /// Not set when the station has no forecast for the event
+    pub forecasted: Option<i64>,
+    /// Not set when the station has no observation yet, the pick doesn't score until it does
+    pub observed: Option<i64>,
+    pub matched: bool,
+    /// What the event's scoring mode made of the pick, a streak of matched picks changes this with exponential scoring
+    pub points: u64,
+}
+
+/// Every pick of an entry with what it added to the entry's score
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct EntryBreakdown {
+    pub entry_id: Uuid,
+    pub event_id: Uuid,
+    pub scoring_mode: ScoringMode,
+    /// In the order the scoring mode counted them, the event's locations then temp_high, temp_low and wind_speed
+    pub picks: Vec<PickBreakdown>,
+    /// Sum of the points of every pick
+    pub base_score: u64,
+    /// Breaks ties between entries with the same base score, older entries get the higher value
+    pub tie_breaker: u64,
+    /// `base_score * 10000 + tie_breaker`, the entry's score from the event's latest weather
+    pub score: i64,
+    /// Score the oracle last stored for the entry, not set until the observation_date has begun
+    pub stored_score: Option<i64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ValueDiff {
+    /// Observed minus forecasted
+    pub diff: i64,
+    /// The pick that scores for this value, same comparison used when scoring entries
+    pub outcome: ValueOptions,
+}
+
+impl ValueDiff {
+    pub fn new(forecasted: i64, observed: i64) -> Self {
+        ValueDiff::with_par_tolerance(forecasted, observed, ParTolerance::default())
+    }
+
+    pub fn with_par_tolerance(forecasted: i64, observed: i64, par_tolerance: ParTolerance) -> Self {
+        Self {
+            diff: observed - forecasted,
+            outcome: ValueOptions::outcome(forecasted, observed, par_tolerance),
+        }
+    }
+}
+
+/// How a station's forecast compared to its observation for one signed event
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+pub struct ForecastAccuracy {
+    pub event_id: Uuid,
+    pub station_id: String,
+    #[serde(with = "time::serde::rfc3339")]
+    pub observation_date: OffsetDateTime,
+    pub temp_low: ValueDiff,
+    pub temp_high: ValueDiff,
+    pub wind_speed: ValueDiff,
+}
+
+impl ForecastAccuracy {
+    /// Only stations with an observation have an accuracy to record
+    pub fn from_diff(
+        event_id: Uuid,
+        observation_date: OffsetDateTime,
+        diff: WeatherDiff,
+    ) -> Option<Self> {
+        Some(Self {
+            event_id,
+            station_id: diff.station_id.to_ascii_uppercase(),
+            observation_date,
+            temp_low: diff.temp_low?,
+            temp_high: diff.temp_high?,
+            wind_speed: diff.wind_speed?,
+        })
+    }
+}
+
+impl TryFrom<&Row<'_>> for ForecastAccuracy {
+    type Error = duckdb::Error;
+
+    fn try_from(row: &Row) -> Result<Self, Self::Error> {
+        let value_diff = |diff_index: usize,
+                          outcome_index: usize|
+         -> Result<ValueDiff, Self::Error> {
+            Ok(ValueDiff {
+                diff: row.get::<usize, i64>(diff_index)?,
+                outcome: ValueOptions::try_from(row.get::<usize, String>(outcome_index)?).map_err(
+                    |e| duckdb::Error::FromSqlConversionFailure(outcome_index, Type::Any, e.into()),
+                )?,
+            })
+        };
+        Ok(Self {
+            event_id: row
+                .get::<usize, String>(0)
+                .map(|val| Uuid::parse_str(&val))?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?,
+            station_id: row.get(1)?,
+            observation_date: row
+                .get::<usize, String>(2)
+                .map(|val| parse_sql_timestamp(&val))?
+                .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
+            temp_low: value_diff(3, 4)?,
+            temp_high: value_diff(5, 6)?,
+            wind_speed: value_diff(7, 8)?,
+        })
+    }
+}
+
+/// Mean of the absolute difference between forecasted and observed values
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct MeanAbsoluteDiff {
+    pub temp_low: f64,
+    pub temp_high: f64,
+    pub wind_speed: f64,
+}
+
+/// A station's forecast accuracy over the signed events it was part of, oldest event first
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq)]
+pub struct StationAccuracy {
+    pub station_id: String,
+    /// Only set once the station has been part of a signed event
+    pub mean_absolute_diff: Option<MeanAbsoluteDiff>,
+    /// How often each value's observation landed within par of the forecast, between 0 and 1
+    pub par_rate: Option<f64>,
+    pub history: Vec<ForecastAccuracy>,
+}
+
+impl StationAccuracy {
+    pub fn new(station_id: String, history: Vec<ForecastAccuracy>) -> Self {
+        let total = history.len() as f64;
+        let mean = |value: fn(&ForecastAccuracy) -> &ValueDiff| {
+            history
+                .iter()
+                .map(|accuracy| value(accuracy).diff.abs() as f64)
+                .sum::<f64>()
+                / total
+        };
+        let (mean_absolute_diff, par_rate) = if history.is_empty() {
+            (None, None)
+        } else {
+            let pars = history
+                .iter()
+                .flat_map(|accuracy| {
+                    [
+                        &accuracy.temp_low,
+                        &accuracy.temp_high,
+                        &accuracy.wind_speed,
+                    ]
+                })
+                .filter(|value| value.outcome == ValueOptions::Par)
+                .count() as f64;
+            (
+                Some(MeanAbsoluteDiff {
+                    temp_low: mean(|accuracy| &accuracy.temp_low),
+                    temp_high: mean(|accuracy| &accuracy.temp_high),
+                    wind_speed: mean(|accuracy| &accuracy.wind_speed),
+                }),
+                Some(pars / (total * 3.0)),
+            )
+        };
+        Self {
+            station_id,
+            mean_absolute_diff,
+            par_rate,
+            history,
+        }
+    }
+}
+
 // Once submitted for now don't allow changes
 // Decide if we want to add a pubkey for who submitted the entry?
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -1231,6 +2077,7 @@ impl From<AddEventEntry> for WeatherEntry {
             event_id: value.event_id,
             expected_observations: value.expected_observations,
             score: None,
+            coordinator_pubkey: None,
         }
     }
 }
@@ -1242,6 +2089,9 @@ pub struct WeatherEntry {
     pub expected_observations: Vec<WeatherChoices>,
     /// A score wont appear until the observation_date has begun
     pub score: Option<i64>,
+    /// The nostr pubkey (bech32) of the coordinator that added the entry
+    #[serde(default)]
+    pub coordinator_pubkey: Option<String>,
 }
 
 impl TryInto<WeatherEntry> for &OrderedMap<String, Value> {
@@ -1311,11 +2161,18 @@ impl TryInto<WeatherEntry> for &OrderedMap<String, Value> {
             _ => None,
         });
 
+        // Entries added before coordinators were recorded don't have a pubkey
+        let coordinator_pubkey = values.get(4).and_then(|raw| match raw {
+            Value::Text(coordinator_pubkey) => Some(coordinator_pubkey.clone()),
+            _ => None,
+        });
+
         Ok(WeatherEntry {
             id,
             event_id,
             score,
             expected_observations,
+            coordinator_pubkey,
         })
     }
 }
@@ -1336,6 +2193,7 @@ impl<'a> TryFrom<&Row<'a>> for WeatherEntry {
             score: row
                 .get::<usize, Option<i64>>(2)
                 .map(|val| val.filter(|&val| val != 0))?,
+            coordinator_pubkey: row.get::<usize, Option<String>>(3)?,
             expected_observations: vec![],
         })
     }
@@ -1364,20 +2222,30 @@ impl<'a> TryFrom<&Row<'a>> for WeatherChoicesWithEntry {
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             temp_low: row
                 .get::<usize, Option<String>>(2)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             temp_high: row
                 .get::<usize, Option<String>>(3)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(3, Type::Any, Box::new(e)))?,
             wind_speed: row
                 .get::<usize, Option<String>>(4)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(4, Type::Any, Box::new(e)))?,
         })
     }
 }
 
+/// Station ids are stored and compared upper case, so `kjfk` and `KJFK` are the same station
+pub fn normalize_station_id(station_id: &str) -> Result<String, anyhow::Error> {
+    let station_id = station_id.trim();
+    if station_id.is_empty() || !station_id.chars().all(|c| c.is_ascii_alphanumeric()) {
+        return Err(anyhow!("invalid station id: '{}'", station_id));
+    }
+    Ok(station_id.to_ascii_uppercase())
+}
+
+/// An entry's picks for one station, any value left as `None` is abstained on and scores 0 points
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
 pub struct WeatherChoices {
     // NOAA weather stations we're using
@@ -1387,6 +2255,15 @@ pub struct WeatherChoices {
     pub wind_speed: Option<ValueOptions>,
 }
 
+impl WeatherChoices {
+    pub fn pick_count(&self) -> usize {
+        [&self.temp_high, &self.temp_low, &self.wind_speed]
+            .iter()
+            .filter(|pick| pick.is_some())
+            .count()
+    }
+}
+
 impl From<WeatherChoicesWithEntry> for WeatherChoices {
     fn from(value: WeatherChoicesWithEntry) -> Self {
         Self {
@@ -1408,15 +2285,15 @@ impl<'a> TryFrom<&Row<'a>> for WeatherChoices {
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(0, Type::Any, Box::new(e)))?,
             temp_low: row
                 .get::<usize, Option<String>>(1)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(1, Type::Any, Box::new(e)))?,
             temp_high: row
                 .get::<usize, Option<String>>(2)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(2, Type::Any, Box::new(e)))?,
             wind_speed: row
                 .get::<usize, Option<String>>(3)
-                .map(|raw| raw.and_then(|inner| ValueOptions::try_from(inner).ok()))
+                .map(|raw| raw.and_then(|inner| ValueOptions::from_stored(inner)))
                 .map_err(|e| duckdb::Error::FromSqlConversionFailure(3, Type::Any, Box::new(e)))?,
         })
     }
@@ -1438,17 +2315,17 @@ impl TryInto<WeatherChoices> for &OrderedMap<String, Value> {
                 )),
             })?;
         let temp_low = values.get(1).and_then(|raw_temp| match raw_temp {
-            Value::Text(temp) => ValueOptions::try_from(temp.clone()).ok(),
+            Value::Text(temp) => ValueOptions::from_stored(temp.clone()),
             _ => None,
         });
         let temp_high = values.get(2).and_then(|raw_temp| match raw_temp {
-            Value::Text(temp) => ValueOptions::try_from(temp.clone()).ok(),
+            Value::Text(temp) => ValueOptions::from_stored(temp.clone()),
             _ => None,
         });
         let wind_speed = values
             .get(3)
             .and_then(|raw_wind_speed| match raw_wind_speed {
-                Value::Text(wind_speed) => ValueOptions::try_from(wind_speed.clone()).ok(),
+                Value::Text(wind_speed) => ValueOptions::from_stored(wind_speed.clone()),
                 _ => None,
             });
         Ok(WeatherChoices {
@@ -1493,6 +2370,141 @@ pub enum ValueOptions {
     Under,
 }
 
+impl ValueOptions {
+    pub fn all() -> Vec<ValueOptions> {
+        vec![ValueOptions::Over, ValueOptions::Par, ValueOptions::Under]
+    }
+
+    pub fn iter() -> impl Iterator<Item = ValueOptions> {
+        ValueOptions::all().into_iter()
+    }
+
+    pub fn count() -> usize {
+        ValueOptions::all().len()
+    }
+
+    /// The pick that scores when `observed` is compared to `forecasted`, anything within the tolerance is Par
+    pub fn outcome(forecasted: i64, observed: i64, par_tolerance: ParTolerance) -> ValueOptions {
+        let diff = observed - forecasted;
+        if diff.abs() <= par_tolerance.value() {
+            ValueOptions::Par
+        } else if diff > 0 {
+            ValueOptions::Over
+        } else {
+            ValueOptions::Under
+        }
+    }
+
+    /// Lenient parsing only meant for rows already stored in the db, older entries may hold
+    /// values we no longer accept, those are treated as no choice instead of failing the read
+    pub fn from_stored(raw: String) -> Option<ValueOptions> {
+        match ValueOptions::try_from(raw) {
+            Ok(option) => Some(option),
+            Err(e) => {
+                warn!("ignoring stored weather choice: {}", e);
+                None
+            }
+        }
+    }
+}
+
+/// A weather value entries can pick Over, Par or Under on, named like its field on `WeatherChoices`
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum Dimension {
+    TempHigh,
+    TempLow,
+    WindSpeed,
+}
+
+impl Dimension {
+    pub fn all() -> Vec<Dimension> {
+        vec![
+            Dimension::TempHigh,
+            Dimension::TempLow,
+            Dimension::WindSpeed,
+        ]
+    }
+
+    /// Units the forecasted and observed values are compared in
+    pub fn units(&self) -> &'static str {
+        match self {
+            Dimension::TempHigh | Dimension::TempLow => "fahrenheit",
+            Dimension::WindSpeed => "mph",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct DimensionOptions {
+    pub dimension: Dimension,
+    pub units: String,
+    /// Picks an entry can make on the dimension
+    pub options: Vec<ValueOptions>,
+}
+
+impl From<Dimension> for DimensionOptions {
+    fn from(dimension: Dimension) -> Self {
+        Self {
+            dimension,
+            units: dimension.units().to_string(),
+            options: ValueOptions::all(),
+        }
+    }
+}
+
+/// The weather values entries of an event can pick on, clients should only offer these
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EventDimensions {
+    pub id: Uuid,
+    pub dimensions: Vec<DimensionOptions>,
+}
+
+/// How many more entries an event can take, for clients showing "X of Y entries left"
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct EventCapacity {
+    pub total_allowed: i64,
+    pub total_entries: i64,
+    pub remaining: i64,
+    /// Whether an entry sent now is taken, false once the event is full, signed, cancelled or past its entry cutoff
+    pub accepting: bool,
+}
+
+/// An event with as many entries as it allows takes no more, the outcomes announced for it only cover that many
+pub fn is_event_full(total_entries: i64, total_allowed_entries: i64) -> bool {
+    total_entries >= total_allowed_entries
+}
+
+/// How far an observed value can be from what was forecasted, in the value's own units, and still count as Par
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct ParTolerance(i64);
+
+impl ParTolerance {
+    pub fn value(&self) -> i64 {
+        self.0
+    }
+}
+
+impl std::fmt::Display for ParTolerance {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+impl TryFrom<i64> for ParTolerance {
+    type Error = anyhow::Error;
+
+    fn try_from(tolerance: i64) -> Result<Self, Self::Error> {
+        if tolerance < 0 {
+            return Err(anyhow!(
+                "invalid par tolerance: {}, must not be negative",
+                tolerance
+            ));
+        }
+        Ok(ParTolerance(tolerance))
+    }
+}
+
 impl std::fmt::Display for ValueOptions {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1507,12 +2519,9 @@ impl TryFrom<&str> for ValueOptions {
     type Error = anyhow::Error;
 
     fn try_from(s: &str) -> Result<Self, Self::Error> {
-        match s {
-            "over" => Ok(ValueOptions::Over),
-            "par" => Ok(ValueOptions::Par),
-            "under" => Ok(ValueOptions::Under),
-            val => Err(anyhow!("invalid option: {}", val)),
-        }
+        ValueOptions::iter()
+            .find(|option| option.to_string() == s)
+            .ok_or_else(|| anyhow!("invalid option: {}", s))
     }
 }
 
@@ -1520,11 +2529,6 @@ impl TryFrom<String> for ValueOptions {
     type Error = anyhow::Error;
 
     fn try_from(s: String) -> Result<Self, Self::Error> {
-        match s.as_str() {
-            "over" => Ok(ValueOptions::Over),
-            "par" => Ok(ValueOptions::Par),
-            "under" => Ok(ValueOptions::Under),
-            val => Err(anyhow!("invalid option: {}", val)),
-        }
+        ValueOptions::try_from(s.as_str())
     }
 }
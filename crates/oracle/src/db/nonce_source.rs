@@ -0,0 +1,34 @@
+use dlctix::secp::Scalar;
+
+/// Source of the nonces new events commit to signing their outcome with
+pub trait NonceSource: Send + Sync {
+    fn next_nonce(&self) -> Scalar;
+}
+
+#[derive(Debug, Default)]
+pub struct RandomNonceSource;
+
+impl NonceSource for RandomNonceSource {
+    fn next_nonce(&self) -> Scalar {
+        Scalar::random(&mut rand::thread_rng())
+    }
+}
+
+/// Hands out the same nonce for every event so tests can know ahead of time what will be announced,
+/// reusing a nonce across real events leaks the oracle's private key once both are attested
+#[derive(Debug)]
+pub struct FixedNonceSource {
+    nonce: Scalar,
+}
+
+impl FixedNonceSource {
+    pub fn new(nonce: Scalar) -> Self {
+        Self { nonce }
+    }
+}
+
+impl NonceSource for FixedNonceSource {
+    fn next_nonce(&self) -> Scalar {
+        self.nonce
+    }
+}
@@ -6,6 +6,14 @@ pub fn generate_ranking_permutations(num_players: usize, rankings: usize) -> Vec
     (0..num_players).permutations(rankings).collect()
 }
 
+/// Used when winners are picked by a score threshold, the order of the winners does not matter
+/// and anywhere from no entries up to `max_winners` entries can win
+pub fn generate_winner_combinations(num_players: usize, max_winners: usize) -> Vec<Vec<usize>> {
+    (0..=max_winners.min(num_players))
+        .flat_map(|size| (0..num_players).combinations(size))
+        .collect()
+}
+
 pub fn generate_outcome_messages(possible_user_outcomes: Vec<Vec<usize>>) -> Vec<Vec<u8>> {
     possible_user_outcomes
         .into_iter()
@@ -21,7 +29,7 @@ pub fn generate_outcome_messages(possible_user_outcomes: Vec<Vec<usize>>) -> Vec
 #[cfg(test)]
 mod test {
 
-    use super::generate_ranking_permutations;
+    use super::{generate_ranking_permutations, generate_winner_combinations};
 
     #[test]
     fn can_generate_list_of_winners_n5() {
@@ -67,4 +75,23 @@ mod test {
         let permutations: Vec<Vec<usize>> = generate_ranking_permutations(num_players, 3);
         assert_eq!(permutations.len(), 63_520_800);
     }
+
+    #[test]
+    fn can_generate_winner_combinations_n5() {
+        let num_players = 5;
+        let combinations: Vec<Vec<usize>> = generate_winner_combinations(num_players, 3);
+        // 1 (no winners) + 5 + 10 + 10
+        assert_eq!(combinations.len(), 26);
+        assert!(combinations.contains(&vec![]));
+        assert!(combinations.contains(&vec![0, 2, 4]));
+        assert!(!combinations.contains(&vec![2, 0, 4]));
+    }
+
+    #[test]
+    fn can_generate_winner_combinations_n25() {
+        let num_players = 25;
+        let combinations: Vec<Vec<usize>> = generate_winner_combinations(num_players, 3);
+        // 1 + 25 + 300 + 2300
+        assert_eq!(combinations.len(), 2_626);
+    }
 }
@@ -0,0 +1,195 @@
+use anyhow::anyhow;
+use duckdb::Connection;
+use std::{
+    fmt::{self, Display},
+    fs,
+};
+
+/// Columns the daemon writes to forecast files, with the duckdb type they're read as
+const FORECAST_COLUMNS: &[(&str, &str)] = &[
+    ("station_id", "VARCHAR"),
+    ("station_name", "VARCHAR"),
+    ("latitude", "DOUBLE"),
+    ("longitude", "DOUBLE"),
+    ("generated_at", "VARCHAR"),
+    ("begin_time", "VARCHAR"),
+    ("end_time", "VARCHAR"),
+    ("max_temp", "BIGINT"),
+    ("min_temp", "BIGINT"),
+    ("temperature_unit_code", "VARCHAR"),
+    ("wind_speed", "BIGINT"),
+    ("wind_speed_unit_code", "VARCHAR"),
+    ("wind_direction", "BIGINT"),
+    ("wind_direction_unit_code", "VARCHAR"),
+    ("relative_humidity_max", "BIGINT"),
+    ("relative_humidity_min", "BIGINT"),
+    ("relative_humidity_unit_code", "VARCHAR"),
+    ("liquid_precipitation_amt", "DOUBLE"),
+    ("liquid_precipitation_unit_code", "VARCHAR"),
+    ("twelve_hour_probability_of_precipitation", "BIGINT"),
+    (
+        "twelve_hour_probability_of_precipitation_unit_code",
+        "VARCHAR",
+    ),
+];
+
+/// Columns the daemon writes to observation files, with the duckdb type they're read as
+const OBSERVATION_COLUMNS: &[(&str, &str)] = &[
+    ("station_id", "VARCHAR"),
+    ("station_name", "VARCHAR"),
+    ("latitude", "DOUBLE"),
+    ("longitude", "DOUBLE"),
+    ("generated_at", "VARCHAR"),
+    ("temperature_value", "DOUBLE"),
+    ("temperature_unit_code", "VARCHAR"),
+    ("wind_direction", "BIGINT"),
+    ("wind_direction_unit_code", "VARCHAR"),
+    ("wind_speed", "BIGINT"),
+    ("wind_speed_unit_code", "VARCHAR"),
+    ("dewpoint_value", "DOUBLE"),
+    ("dewpoint_unit_code", "VARCHAR"),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WeatherFileKind {
+    Forecasts,
+    Observations,
+}
+
+impl WeatherFileKind {
+    /// Picks the kind from the daemon's `{kind}_{generated_at}.parquet` file names
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        if file_name.starts_with("forecasts_") {
+            Some(WeatherFileKind::Forecasts)
+        } else if file_name.starts_with("observations_") {
+            Some(WeatherFileKind::Observations)
+        } else {
+            None
+        }
+    }
+
+    pub fn expected_columns(&self) -> &'static [(&'static str, &'static str)] {
+        match self {
+            WeatherFileKind::Forecasts => FORECAST_COLUMNS,
+            WeatherFileKind::Observations => OBSERVATION_COLUMNS,
+        }
+    }
+}
+
+impl Display for WeatherFileKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            WeatherFileKind::Forecasts => write!(f, "forecasts"),
+            WeatherFileKind::Observations => write!(f, "observations"),
+        }
+    }
+}
+
+/// Columns of a parquet file that don't line up with what the oracle expects for its kind
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SchemaDrift {
+    /// Expected columns the file doesn't have
+    pub missing: Vec<String>,
+    /// Columns in the file the oracle doesn't know about, they're kept but never read
+    pub extra: Vec<String>,
+}
+
+impl SchemaDrift {
+    pub fn is_empty(&self) -> bool {
+        self.missing.is_empty() && self.extra.is_empty()
+    }
+}
+
+impl Display for SchemaDrift {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "missing columns: [{}], extra columns: [{}]",
+            self.missing.join(", "),
+            self.extra.join(", ")
+        )
+    }
+}
+
+/// What the oracle does with an uploaded weather file missing some of the expected columns
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum SchemaDriftPolicy {
+    /// Keep the file, adding the missing columns filled with nulls
+    #[default]
+    Adapt,
+    /// Refuse the upload
+    Reject,
+}
+
+impl Display for SchemaDriftPolicy {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SchemaDriftPolicy::Adapt => write!(f, "adapt"),
+            SchemaDriftPolicy::Reject => write!(f, "reject"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SchemaDriftPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "adapt" => Ok(SchemaDriftPolicy::Adapt),
+            "reject" => Ok(SchemaDriftPolicy::Reject),
+            val => Err(anyhow!("invalid schema drift policy: {}", val)),
+        }
+    }
+}
+
+pub fn compare_parquet_schema(
+    conn: &Connection,
+    path: &str,
+    kind: WeatherFileKind,
+) -> Result<SchemaDrift, duckdb::Error> {
+    let mut stmt = conn.prepare(&format!(
+        "DESCRIBE SELECT * FROM read_parquet('{}')",
+        path.replace('\'', "''")
+    ))?;
+    let columns = stmt
+        .query_map([], |row| row.get::<usize, String>(0))?
+        .collect::<Result<Vec<String>, duckdb::Error>>()?;
+    let expected = kind.expected_columns();
+    Ok(SchemaDrift {
+        missing: expected
+            .iter()
+            .filter(|(name, _)| !columns.iter().any(|column| column == name))
+            .map(|(name, _)| name.to_string())
+            .collect(),
+        extra: columns
+            .into_iter()
+            .filter(|column| !expected.iter().any(|(name, _)| name == column))
+            .collect(),
+    })
+}
+
+/// Rewrites the file in place with each missing column added as nulls of its expected type
+pub fn adapt_parquet_file(
+    conn: &Connection,
+    path: &str,
+    kind: WeatherFileKind,
+    drift: &SchemaDrift,
+) -> Result<(), anyhow::Error> {
+    if drift.missing.is_empty() {
+        return Ok(());
+    }
+    let null_columns = kind
+        .expected_columns()
+        .iter()
+        .filter(|(name, _)| drift.missing.iter().any(|missing| missing == name))
+        .map(|(name, column_type)| format!(", NULL::{} AS {}", column_type, name))
+        .collect::<String>();
+    let escaped_path = path.replace('\'', "''");
+    let adapted_path = format!("{}.adapted", escaped_path);
+    conn.execute_batch(&format!(
+        "COPY (SELECT *{} FROM read_parquet('{}')) TO '{}' (FORMAT PARQUET);",
+        null_columns, escaped_path, adapted_path
+    ))?;
+    fs::rename(format!("{}.adapted", path), path)?;
+    Ok(())
+}
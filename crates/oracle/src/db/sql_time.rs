@@ -0,0 +1,44 @@
+use time::{
+    error::{ComponentRange, Format, Parse},
+    format_description::{well_known::Rfc3339, BorrowedFormatItem},
+    macros::format_description,
+    OffsetDateTime, UtcOffset,
+};
+
+// DuckDB keeps TIMESTAMPTZ values as microseconds since the unix epoch, `OffsetDateTime` carries
+// nanoseconds. Every date is truncated to microseconds before it's written so what's read back is
+// exactly what was stored, instead of leaving the rounding of the extra digits up to duckdb.
+// Dates come back either as `::TEXT` in the session time zone, parsed with `SQL_TIME_FORMAT`, or
+// as raw microseconds in a `Value::Timestamp`, converted with `from_sql_micros`.
+
+/// Subsecond digits duckdb keeps for a timestamp
+pub const SQL_SUBSECOND_DIGITS: u32 = 6;
+
+const NANOS_PER_SQL_UNIT: u32 = 10_u32.pow(9 - SQL_SUBSECOND_DIGITS);
+
+/// Format of a TIMESTAMPTZ cast to TEXT, ie `2024-08-11 00:27:39.013046-04` or `2024-08-11 00:27:39+05:30`
+pub const SQL_TIME_FORMAT: &[BorrowedFormatItem<'static>] = format_description!(
+    "[year]-[month]-[day] [hour]:[minute]:[second][optional [.[subsecond]]][offset_hour][optional [:[offset_minute]]]"
+);
+
+/// Drops the subsecond digits duckdb can't hold
+pub fn truncate_to_sql_precision(date: OffsetDateTime) -> OffsetDateTime {
+    let nanosecond = date.nanosecond() / NANOS_PER_SQL_UNIT * NANOS_PER_SQL_UNIT;
+    date.replace_nanosecond(nanosecond)
+        .expect("truncated nanosecond is always in range")
+}
+
+/// Value to bind for a TIMESTAMPTZ column, in UTC at the precision duckdb stores
+pub fn to_sql_timestamp(date: OffsetDateTime) -> Result<String, Format> {
+    truncate_to_sql_precision(date.to_offset(UtcOffset::UTC)).format(&Rfc3339)
+}
+
+/// Reads a TIMESTAMPTZ selected as `::TEXT`
+pub fn parse_sql_timestamp(value: &str) -> Result<OffsetDateTime, Parse> {
+    OffsetDateTime::parse(value, &SQL_TIME_FORMAT).map(|date| date.to_offset(UtcOffset::UTC))
+}
+
+/// Reads a TIMESTAMPTZ returned as microseconds since the unix epoch
+pub fn from_sql_micros(micros: i64) -> Result<OffsetDateTime, ComponentRange> {
+    OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * NANOS_PER_SQL_UNIT as i128)
+}
@@ -2,13 +2,15 @@ use crate::{file_access, FileAccess, FileData, FileParams, ForecastRequest, Obse
 use async_trait::async_trait;
 use duckdb::{
     arrow::array::{Float64Array, Int64Array, RecordBatch, StringArray},
-    params_from_iter, Connection,
+    params, params_from_iter, Connection,
 };
+use log::warn;
 use regex::Regex;
 use scooby::postgres::{select, with, Aliasable, Parameters, Select};
 use serde::{Deserialize, Serialize};
-use std::sync::Arc;
-use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+use std::{future::Future, sync::Arc, time::Duration as StdDuration};
+use time::{format_description::well_known::Rfc3339, Date, Duration, OffsetDateTime, Time};
+use tokio::time::sleep;
 use utoipa::ToSchema;
 
 pub struct WeatherAccess {
@@ -25,6 +27,18 @@ pub enum Error {
     TimeParse(#[from] time::error::Parse),
     #[error("Failed to access files: {0}")]
     FileAccess(#[from] file_access::Error),
+    #[error("Weather data source is unavailable: {0}")]
+    Unavailable(String),
+}
+
+impl Error {
+    /// Errors from reading the weather source itself, as opposed to a bad request, that may clear up on their own
+    pub fn is_transient(&self) -> bool {
+        matches!(
+            self,
+            Error::Query(_) | Error::FileAccess(_) | Error::Unavailable(_)
+        )
+    }
 }
 
 #[async_trait]
@@ -67,6 +81,141 @@ impl WeatherAccess {
         let sql_params = params_from_iter(params.iter());
         Ok(stmt.query_arrow(sql_params)?.collect())
     }
+
+    /// Writes one parquet file for `date` pairing each observation with the forecast period it falls in,
+    /// keyed by station_id and time. Forecast periods without observations and observations without a
+    /// forecast period (ie. stations only in one data set) are kept with the other side's columns set to null
+    pub async fn write_combined_parquet(&self, date: Date, file_path: &str) -> Result<(), Error> {
+        let day_start = date.with_time(Time::MIDNIGHT).assume_utc();
+        let day_end = day_start.saturating_add(Duration::days(1));
+        // Forecasts for the day are generated ahead of it, observations can be uploaded just after it
+        let file_params = |forecasts: bool| FileParams {
+            start: Some(day_start.saturating_sub(Duration::days(1))),
+            end: Some(day_end.saturating_add(Duration::days(1))),
+            observations: Some(!forecasts),
+            forecasts: Some(forecasts),
+        };
+        let forecast_files = self.file_access.grab_file_names(file_params(true)).await?;
+        let forecast_paths = self.file_access.build_file_paths(forecast_files);
+        let observation_files = self.file_access.grab_file_names(file_params(false)).await?;
+        let observation_paths = self.file_access.build_file_paths(observation_files);
+
+        let day_start = day_start.format(&Rfc3339)?;
+        let day_end = day_end.format(&Rfc3339)?;
+        let conn = self.open_connection()?;
+        conn.execute_batch(
+            "CREATE TABLE forecasts (
+                station_id TEXT NOT NULL,
+                station_name TEXT,
+                begin_time TIMESTAMPTZ NOT NULL,
+                end_time TIMESTAMPTZ NOT NULL,
+                generated_at TIMESTAMPTZ NOT NULL,
+                max_temp BIGINT,
+                min_temp BIGINT,
+                wind_speed BIGINT,
+                wind_direction BIGINT,
+                relative_humidity_max BIGINT,
+                relative_humidity_min BIGINT,
+                liquid_precipitation_amt DOUBLE,
+                twelve_hour_probability_of_precipitation BIGINT
+            );
+            CREATE TABLE observations (
+                station_id TEXT NOT NULL,
+                station_name TEXT,
+                generated_at TIMESTAMPTZ NOT NULL,
+                temperature_value DOUBLE,
+                wind_speed BIGINT,
+                wind_direction BIGINT,
+                dewpoint_value DOUBLE
+            );",
+        )?;
+        if !forecast_paths.is_empty() {
+            // The same period is forecasted on every pull, only the most recent forecast of it is kept
+            conn.execute(
+                &format!(
+                    "INSERT INTO forecasts
+                    SELECT
+                        station_id,
+                        station_name,
+                        begin_time::TIMESTAMPTZ,
+                        end_time::TIMESTAMPTZ,
+                        generated_at::TIMESTAMPTZ,
+                        max_temp,
+                        min_temp,
+                        wind_speed,
+                        wind_direction,
+                        relative_humidity_max,
+                        relative_humidity_min,
+                        liquid_precipitation_amt,
+                        twelve_hour_probability_of_precipitation
+                    FROM read_parquet(['{}'], union_by_name = true)
+                    WHERE begin_time::TIMESTAMPTZ >= ?::TIMESTAMPTZ
+                        AND begin_time::TIMESTAMPTZ < ?::TIMESTAMPTZ
+                    QUALIFY ROW_NUMBER() OVER (
+                        PARTITION BY station_id, begin_time::TIMESTAMPTZ, end_time::TIMESTAMPTZ
+                        ORDER BY generated_at::TIMESTAMPTZ DESC
+                    ) = 1",
+                    forecast_paths.join("', '")
+                ),
+                params![day_start, day_end],
+            )?;
+        }
+        if !observation_paths.is_empty() {
+            // A metar reading is repeated in every pull until the station reports again
+            conn.execute(
+                &format!(
+                    "INSERT INTO observations
+                    SELECT
+                        station_id,
+                        station_name,
+                        generated_at::TIMESTAMPTZ,
+                        temperature_value,
+                        wind_speed,
+                        wind_direction,
+                        dewpoint_value
+                    FROM read_parquet(['{}'], union_by_name = true)
+                    WHERE generated_at::TIMESTAMPTZ >= ?::TIMESTAMPTZ
+                        AND generated_at::TIMESTAMPTZ < ?::TIMESTAMPTZ
+                    QUALIFY ROW_NUMBER() OVER (
+                        PARTITION BY station_id, generated_at::TIMESTAMPTZ
+                    ) = 1",
+                    observation_paths.join("', '")
+                ),
+                params![day_start, day_end],
+            )?;
+        }
+        conn.execute_batch(&format!(
+            "COPY (
+                SELECT
+                    COALESCE(forecasts.station_id, observations.station_id) AS station_id,
+                    COALESCE(forecasts.station_name, observations.station_name) AS station_name,
+                    forecasts.begin_time AS forecast_begin_time,
+                    forecasts.end_time AS forecast_end_time,
+                    forecasts.generated_at AS forecast_generated_at,
+                    forecasts.max_temp AS forecast_max_temp,
+                    forecasts.min_temp AS forecast_min_temp,
+                    forecasts.wind_speed AS forecast_wind_speed,
+                    forecasts.wind_direction AS forecast_wind_direction,
+                    forecasts.relative_humidity_max AS forecast_relative_humidity_max,
+                    forecasts.relative_humidity_min AS forecast_relative_humidity_min,
+                    forecasts.liquid_precipitation_amt AS forecast_liquid_precipitation_amt,
+                    forecasts.twelve_hour_probability_of_precipitation AS forecast_twelve_hour_probability_of_precipitation,
+                    observations.generated_at AS observed_at,
+                    observations.temperature_value AS observed_temperature,
+                    observations.wind_speed AS observed_wind_speed,
+                    observations.wind_direction AS observed_wind_direction,
+                    observations.dewpoint_value AS observed_dewpoint
+                FROM forecasts
+                FULL OUTER JOIN observations
+                    ON forecasts.station_id = observations.station_id
+                    AND observations.generated_at >= forecasts.begin_time
+                    AND observations.generated_at < forecasts.end_time
+                ORDER BY station_id, COALESCE(forecast_begin_time, observed_at), observed_at
+            ) TO '{}' (FORMAT PARQUET);",
+            file_path.replace('\'', "''")
+        ))?;
+        Ok(())
+    }
 }
 #[async_trait]
 impl WeatherData for WeatherAccess {
@@ -111,7 +260,7 @@ impl WeatherData for WeatherAccess {
             ));
 
             for station_id in station_ids {
-                values.push(station_id);
+                values.push(station_id.to_ascii_uppercase());
             }
         }
         if let Some(start) = &req.start {
@@ -190,7 +339,7 @@ impl WeatherData for WeatherAccess {
             ));
 
             for station_id in station_ids {
-                values.push(station_id);
+                values.push(station_id.to_ascii_uppercase());
             }
         }
         if let Some(start) = &req.start {
@@ -259,6 +408,84 @@ impl WeatherData for WeatherAccess {
     }
 }
 
+pub const DEFAULT_WEATHER_RETRY_ATTEMPTS: usize = 3;
+pub const DEFAULT_WEATHER_RETRY_DELAY: StdDuration = StdDuration::from_millis(250);
+
+/// Retries transient errors from the wrapped weather source, once the attempts run out
+/// the error is returned as `Error::Unavailable`
+pub struct RetryWeatherData {
+    inner: Arc<dyn WeatherData>,
+    attempts: usize,
+    delay: StdDuration,
+}
+
+impl RetryWeatherData {
+    pub fn new(inner: Arc<dyn WeatherData>, attempts: usize, delay: StdDuration) -> Self {
+        Self {
+            inner,
+            attempts: attempts.max(1),
+            delay,
+        }
+    }
+
+    async fn retry<T, F, Fut>(&self, name: &str, mut call: F) -> Result<T, Error>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, Error>>,
+    {
+        let mut attempt = 1;
+        loop {
+            match call().await {
+                Ok(value) => return Ok(value),
+                Err(e) if !e.is_transient() => return Err(e),
+                Err(e) if attempt >= self.attempts => {
+                    return Err(Error::Unavailable(format!(
+                        "{} failed after {} attempts: {}",
+                        name, attempt, e
+                    )))
+                }
+                Err(e) => {
+                    warn!(
+                        "{} failed on attempt {} of {}, retrying: {}",
+                        name, attempt, self.attempts, e
+                    );
+                    attempt += 1;
+                    sleep(self.delay).await;
+                }
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl WeatherData for RetryWeatherData {
+    async fn forecasts_data(
+        &self,
+        req: &ForecastRequest,
+        station_ids: Vec<String>,
+    ) -> Result<Vec<Forecast>, Error> {
+        self.retry("forecasts", || {
+            self.inner.forecasts_data(req, station_ids.clone())
+        })
+        .await
+    }
+
+    async fn observation_data(
+        &self,
+        req: &ObservationRequest,
+        station_ids: Vec<String>,
+    ) -> Result<Vec<Observation>, Error> {
+        self.retry("observations", || {
+            self.inner.observation_data(req, station_ids.clone())
+        })
+        .await
+    }
+
+    async fn stations(&self) -> Result<Vec<Station>, Error> {
+        self.retry("stations", || self.inner.stations()).await
+    }
+}
+
 struct Forecasts {
     values: Vec<Forecast>,
 }
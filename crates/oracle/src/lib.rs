@@ -1,16 +1,29 @@
+mod announcement_cache;
 mod app_error;
+mod auto_signer;
 mod db;
 mod file_access;
 mod nostr_extractor;
 pub mod oracle;
+mod rate_limit;
 pub mod routes;
+mod scoring;
+mod signing_queue;
 mod startup;
+mod station_groups;
 mod utils;
 
 pub use app_error::AppError;
+pub use auto_signer::{run_auto_signer, DEFAULT_AUTO_SIGN_INTERVAL};
 pub use db::*;
 pub use file_access::{drop_suffix, Error, FileAccess, FileData, FileParams};
 pub use nostr_extractor::{AuthError, NostrAuth};
+pub use rate_limit::{RateLimit, RateLimiter};
 pub use routes::*;
+pub use scoring::{
+    ExponentialScoring, LinearScoring, ScoredPick, ScoringScheme, OVER_OR_UNDER_POINTS, PAR_POINTS,
+};
+pub use signing_queue::{SigningQueue, SigningQueueStatus, DEFAULT_SIGNING_CONCURRENCY};
 pub use startup::*;
+pub use station_groups::StationGroups;
 pub use utils::*;
@@ -1,10 +1,13 @@
 use anyhow::anyhow;
-use axum::serve;
-use futures::TryFutureExt;
 use log::{error, info};
-use oracle::{app, build_app_state, create_folder, get_config_info, get_log_level, setup_logger};
-use std::{net::SocketAddr, str::FromStr};
-use tokio::{net::TcpListener, signal};
+use oracle::{
+    app, apply_server_config, bind_listener, build_app_state, create_folder, get_config_info,
+    get_log_level, run_auto_signer, serve_with_config, setup_logger, AppConfig, RateLimit,
+    ServerConfig, DEFAULT_AUTO_SIGN_INTERVAL,
+};
+use std::{net::SocketAddr, str::FromStr, time::Duration};
+use tokio::signal;
+use tokio_util::sync::CancellationToken;
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -17,10 +20,9 @@ async fn main() -> anyhow::Result<()> {
         .level_for("http_response", log_level)
         .level_for("http_request", log_level)
         .apply()?;
-    let weather_data = cli.weather_dir.unwrap_or(String::from("./weather_data"));
-    create_folder(&weather_data.clone());
-    let event_data = cli.event_db.unwrap_or(String::from("./event_data"));
-    create_folder(&event_data.clone());
+    let app_config = AppConfig::try_from(&cli)?;
+    create_folder(&app_config.data_dir);
+    create_folder(&app_config.event_dir);
     let socket_addr = SocketAddr::from_str(&format!(
         "{}:{}",
         cli.domain.unwrap_or(String::from("127.0.0.1")),
@@ -28,36 +30,58 @@ async fn main() -> anyhow::Result<()> {
     ))
     .unwrap();
 
-    let listener = TcpListener::bind(socket_addr)
-        .map_err(|e| anyhow!("error binding to IO socket: {}", e.to_string()))
-        .await?;
+    let default_server_config = ServerConfig::default();
+    let server_config = ServerConfig {
+        listen_backlog: cli
+            .listen_backlog
+            .unwrap_or(default_server_config.listen_backlog),
+        keep_alive: cli
+            .keep_alive_secs
+            .map(Duration::from_secs)
+            .unwrap_or(default_server_config.keep_alive),
+        request_timeout: cli
+            .request_timeout_secs
+            .map(Duration::from_secs)
+            .unwrap_or(default_server_config.request_timeout),
+        rate_limit: cli.rate_limit_requests.map(|requests| {
+            RateLimit::new(
+                requests,
+                Duration::from_secs(cli.rate_limit_window_secs.unwrap_or(60)),
+            )
+        }),
+    };
+    info!("server config: {:?}", server_config);
+
+    let listener = bind_listener(socket_addr, &server_config)
+        .map_err(|e| anyhow!("error binding to IO socket: {}", e.to_string()))?;
 
     info!("listening on http://{}", socket_addr);
     info!("docs hosted @ http://{}/docs", socket_addr);
 
-    let app_state = build_app_state(
-        cli.remote_url
-            .unwrap_or(String::from("http://127.0.0.1:9100")),
-        cli.ui_dir.unwrap_or(String::from("./ui")),
-        weather_data,
-        event_data,
-        cli.oracle_private_key
-            .unwrap_or(String::from("./oracle_private_key.pem")),
-    )
-    .await
-    .map_err(|e| {
+    let app_state = build_app_state(app_config).await.map_err(|e| {
         error!("error building app: {}", e);
         e
     })?;
 
-    let app = app(app_state.clone());
+    // Runs for the life of the server, events that opted in are signed even when the global setting is off
+    tokio::spawn(run_auto_signer(
+        app_state.oracle.clone(),
+        cli.auto_sign_interval_secs
+            .map(Duration::from_secs)
+            .unwrap_or(DEFAULT_AUTO_SIGN_INTERVAL),
+    ));
+
+    let app = apply_server_config(app(app_state.clone()), &server_config);
 
-    serve(
-        listener,
-        app.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .with_graceful_shutdown(shutdown_signal())
-    .await?;
+    let shutdown = CancellationToken::new();
+    tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move {
+            shutdown_signal().await;
+            shutdown.cancel();
+        }
+    });
+    serve_with_config(listener, app, &server_config, shutdown).await;
     Ok(())
 }
 
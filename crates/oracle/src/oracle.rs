@@ -1,31 +1,47 @@
 use crate::{
-    weather_data, ActiveEvent, AddEventEntry, CreateEvent, CreateEventData, Event, EventData,
-    EventFilter, EventStatus, EventSummary, Forecast, ForecastRequest, Observation,
-    ObservationRequest, SignEvent, ValueOptions, Weather, WeatherData, WeatherEntry,
+    announcement_cache::AnnouncementCache, format_timezone, is_event_full, map_outcome_winners,
+    normalize_station_id, parse_timezone, possible_outcome_messages, weather_data, ActiveEvent,
+    ActiveStation, AddEntryError, AddEventEntry, CloneEvent, CreateEvent, CreateEventData,
+    Dimension, DimensionOptions, EntryBreakdown, Event, EventAnnouncement, EventCapacity,
+    EventData, EventDimensions, EventFilter, EventOutcomeMessages, EventOutcomeWinners,
+    EventPayouts, EventSignStatus, EventStatus, EventSummary, Forecast, ForecastAccuracy,
+    ForecastRequest, Forecasted, NonceSource, Observation, ObservationRequest, ObservationStorage,
+    Observed, OutcomeEncoding, OutcomeWinnersFilter, ParTolerance, PayoutSplit, PickBreakdown,
+    PlacePayout, RandomNonceSource, ScoredPick, ScoringMode, SignEvent, SignStatusFilter,
+    SigningQueue, SigningQueueStatus, StationAccuracy, StationGroups, StoredStation, UpdateEvent,
+    ValueOptions, Weather, WeatherData, WeatherDiff, WeatherEntry, WeatherParquetError, WinMode,
+    DEFAULT_SIGNED_LIMIT, OBSERVATION_WINDOW, OVER_OR_UNDER_POINTS,
 };
 use anyhow::anyhow;
 use base64::{engine::general_purpose, Engine};
 use dlctix::{
     attestation_locking_point, attestation_secret,
     musig2::secp256k1::{rand, PublicKey, Secp256k1, SecretKey},
-    secp::{MaybePoint, Point},
+    secp::{MaybePoint, Point, Scalar},
+    EventLockingConditions,
 };
+use futures::future::join_all;
 use log::{debug, error, info, warn};
 use nostr_sdk::{key::Keys, nips::nip19::ToBech32, PublicKey as NostrPublicKey};
 use pem_rfc7468::{decode_vec, encode_string};
 use serde::Serialize;
 use std::{
     cmp,
+    collections::{BTreeMap, HashMap, HashSet},
     fs::{metadata, File},
     io::{Read, Write},
+    mem,
     path::Path,
-    sync::Arc,
+    sync::{Arc, Mutex, MutexGuard},
 };
 use thiserror::Error;
-use time::{Duration, OffsetDateTime};
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time, UtcOffset};
+use tokio::sync::Mutex as AsyncMutex;
 use utoipa::ToSchema;
 use uuid::Uuid;
 
+pub const DEFAULT_MAX_LOCATIONS_PER_EVENT: usize = 50;
+
 #[derive(Error, Debug, Serialize, ToSchema)]
 pub enum Error {
     #[error("{0}")]
@@ -76,8 +92,40 @@ pub enum Error {
         #[from]
         weather_data::Error,
     ),
+    #[schema(value_type = String)]
+    #[error("{0}")]
+    WeatherParquet(
+        #[serde(skip)]
+        #[from]
+        WeatherParquetError,
+    ),
     #[error("Failed to find winning outcome: {0}")]
     OutcomeNotFound(String),
+    #[error("Invalid payout split: {0}")]
+    BadPayoutSplit(String),
+    /// The deadline for the operation has passed, ie. entries after the observation window starts or once the
+    /// event is signed
+    #[error("Event expired: {0}")]
+    EventExpired(String),
+    #[error("Forbidden: {0}")]
+    Forbidden(String),
+    /// The event can no longer be changed, ie. updating its outcomes once it has entries
+    #[error("Event locked: {0}")]
+    EventLocked(String),
+    #[schema(value_type = String)]
+    #[error("Failed to read or write file: {0}")]
+    File(
+        #[serde(skip)]
+        #[from]
+        std::io::Error,
+    ),
+    #[schema(value_type = String)]
+    #[error("Failed to encode: {0}")]
+    Encoding(
+        #[serde(skip)]
+        #[from]
+        bincode::Error,
+    ),
     #[schema(value_type = String)]
     #[error("Failed to validate message: {0}")]
     Validation(
@@ -87,11 +135,70 @@ pub enum Error {
     ),
 }
 
+/// What the oracle does with a completed event when NOAA never reported some of its stations
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub enum SigningPolicy {
+    /// Sign using only the stations with observations, picks on the missing stations score 0 points
+    #[default]
+    SignWithAvailable,
+    /// Hold off signing the event until every station has an observation
+    RequireAllObservations,
+}
+
+impl std::fmt::Display for SigningPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::SignWithAvailable => write!(f, "sign_with_available"),
+            Self::RequireAllObservations => write!(f, "require_all_observations"),
+        }
+    }
+}
+
+impl TryFrom<&str> for SigningPolicy {
+    type Error = anyhow::Error;
+
+    fn try_from(s: &str) -> Result<Self, Self::Error> {
+        match s {
+            "sign_with_available" => Ok(SigningPolicy::SignWithAvailable),
+            "require_all_observations" => Ok(SigningPolicy::RequireAllObservations),
+            val => Err(anyhow!("invalid signing policy: {}", val)),
+        }
+    }
+}
+
 pub struct Oracle {
     event_data: Arc<EventData>,
     weather_data: Arc<dyn WeatherData>, //need this to be a trait so I can mock the weather data
     private_key: SecretKey,
     public_key: PublicKey,
+    /// Keys the oracle used before rotating to `private_key`, only used to attest events announced under them
+    retired_keys: Vec<SecretKey>,
+    /// How long after the observation_date entries are still accepted
+    entry_grace_period: Duration,
+    /// Most stations a single event can track
+    max_locations_per_event: usize,
+    signing_policy: SigningPolicy,
+    /// Sign every event on its own once its signing_date passes, not just the events that opted in
+    auto_sign: bool,
+    /// How long after the signing_date the oracle waits before signing, so late observations can still arrive
+    sign_buffer: Duration,
+    nonce_source: Arc<dyn NonceSource>,
+    /// How far off the forecast an observed value can be and still score as Par
+    par_tolerance: ParTolerance,
+    /// Named station lists events can use in place of listing their locations
+    station_groups: StationGroups,
+    /// Nostr pubkeys allowed to push weather for events directly, ie. to correct a bad NOAA reading
+    operator_pubkeys: Vec<NostrPublicKey>,
+    /// Keep the offset each observation was reported in next to its UTC date, for reporting in local time
+    preserve_observation_offsets: bool,
+    /// Keep the unrounded temperatures of each observation next to the rounded ones that are scored
+    keep_raw_observation_temps: bool,
+    signing_queue: Arc<SigningQueue>,
+    /// Announcements already built for unsigned events, dropped once the event is signed or cancelled
+    announcements: Arc<Mutex<AnnouncementCache>>,
+    /// Held for a whole etl pass, from reading the running events to signing them, so `/oracle/update` and
+    /// the auto signer never score or sign the same events at the same time
+    etl_lock: Arc<AsyncMutex<()>>,
 }
 
 impl Oracle {
@@ -99,20 +206,110 @@ impl Oracle {
         event_data: Arc<EventData>,
         weather_data: Arc<dyn WeatherData>,
         private_key_file_path: &String,
+        retired_key_file_paths: &[String],
     ) -> Result<Self, Error> {
         let secret_key = get_key(private_key_file_path)?;
         let secp = Secp256k1::new();
         let public_key = secret_key.public_key(&secp);
+        let retired_keys = retired_key_file_paths
+            .iter()
+            .map(get_retired_key)
+            .collect::<Result<Vec<SecretKey>, anyhow::Error>>()?;
         let oracle = Self {
             event_data,
             weather_data,
             private_key: secret_key,
             public_key,
+            retired_keys,
+            entry_grace_period: Duration::ZERO,
+            max_locations_per_event: DEFAULT_MAX_LOCATIONS_PER_EVENT,
+            signing_policy: SigningPolicy::default(),
+            auto_sign: false,
+            sign_buffer: Duration::ZERO,
+            nonce_source: Arc::new(RandomNonceSource),
+            par_tolerance: ParTolerance::default(),
+            station_groups: StationGroups::default(),
+            operator_pubkeys: vec![],
+            preserve_observation_offsets: false,
+            keep_raw_observation_temps: false,
+            signing_queue: Arc::new(SigningQueue::default()),
+            announcements: Arc::new(Mutex::new(AnnouncementCache::default())),
+            etl_lock: Arc::new(AsyncMutex::new(())),
         };
         oracle.validate_oracle_metadata().await?;
         Ok(oracle)
     }
 
+    pub fn with_entry_grace_period(mut self, entry_grace_period: Duration) -> Self {
+        self.entry_grace_period = entry_grace_period;
+        self
+    }
+
+    pub fn with_max_locations_per_event(mut self, max_locations_per_event: usize) -> Self {
+        self.max_locations_per_event = max_locations_per_event;
+        self
+    }
+
+    pub fn with_signing_policy(mut self, signing_policy: SigningPolicy) -> Self {
+        self.signing_policy = signing_policy;
+        self
+    }
+
+    pub fn with_auto_sign(mut self, auto_sign: bool) -> Self {
+        self.auto_sign = auto_sign;
+        self
+    }
+
+    pub fn with_sign_buffer(mut self, sign_buffer: Duration) -> Self {
+        self.sign_buffer = sign_buffer;
+        self
+    }
+
+    pub fn with_nonce_source(mut self, nonce_source: Arc<dyn NonceSource>) -> Self {
+        self.nonce_source = nonce_source;
+        self
+    }
+
+    pub fn with_par_tolerance(mut self, par_tolerance: ParTolerance) -> Self {
+        self.par_tolerance = par_tolerance;
+        self
+    }
+
+    pub fn with_station_groups(mut self, station_groups: StationGroups) -> Self {
+        self.station_groups = station_groups;
+        self
+    }
+
+    pub fn with_operator_pubkeys(mut self, operator_pubkeys: Vec<NostrPublicKey>) -> Self {
+        self.operator_pubkeys = operator_pubkeys;
+        self
+    }
+
+    pub fn with_preserve_observation_offsets(mut self, preserve_observation_offsets: bool) -> Self {
+        self.preserve_observation_offsets = preserve_observation_offsets;
+        self
+    }
+
+    pub fn with_keep_raw_observation_temps(mut self, keep_raw_observation_temps: bool) -> Self {
+        self.keep_raw_observation_temps = keep_raw_observation_temps;
+        self
+    }
+
+    pub fn with_signing_concurrency(mut self, signing_concurrency: usize) -> Self {
+        self.signing_queue = Arc::new(SigningQueue::new(signing_concurrency));
+        self
+    }
+
+    /// Signs through a queue shared with something else, ie. to hold a slot while an event is being signed
+    pub fn with_signing_queue(mut self, signing_queue: Arc<SigningQueue>) -> Self {
+        self.signing_queue = signing_queue;
+        self
+    }
+
+    pub fn signing_queue_status(&self) -> SigningQueueStatus {
+        self.signing_queue.status()
+    }
+
     pub async fn validate_oracle_metadata(&self) -> Result<(), Error> {
         let stored_public_key = match self.event_data.get_stored_public_key().await {
             Ok(key) => key,
@@ -122,14 +319,45 @@ impl Oracle {
             }
             Err(e) => return Err(Error::DataQuery(e)),
         };
-        if stored_public_key != self.public_key.x_only_public_key().0 {
+        if stored_public_key == self.public_key.x_only_public_key().0 {
+            return Ok(());
+        }
+        let secp = Secp256k1::new();
+        let is_retired_key = self
+            .retired_keys
+            .iter()
+            .any(|key| key.x_only_public_key(&secp).0 == stored_public_key);
+        if !is_retired_key {
             return Err(Error::MismatchPubkey(format!(
                 "stored_pubkey: {:?} pem_pubkey: {:?}",
                 stored_public_key,
                 self.public_key()
             )));
         }
-        Ok(())
+        info!(
+            "rotating oracle key from retired pubkey {:?} to {}",
+            stored_public_key,
+            self.public_key()
+        );
+        self.event_data
+            .update_oracle_metadata(self.public_key.x_only_public_key().0)
+            .await
+            .map_err(Error::DataQuery)
+    }
+
+    /// Finds which of the oracle's keys (primary first, then retired) announced the outcome for an event
+    pub fn event_signing_key(
+        &self,
+        nonce: Scalar,
+        event_announcement: &EventLockingConditions,
+        outcome: &[u8],
+    ) -> Option<SecretKey> {
+        find_signing_key(
+            std::iter::once(&self.private_key).chain(self.retired_keys.iter()),
+            nonce,
+            event_announcement,
+            outcome,
+        )
     }
 
     async fn add_meta_data(&self) -> Result<(), Error> {
@@ -159,6 +387,59 @@ impl Oracle {
         Ok(keys.public_key().to_bech32()?)
     }
 
+    /// Distinct stations across every event that hasn't been signed yet, sorted by station id
+    pub async fn active_stations(&self) -> Result<Vec<ActiveStation>, Error> {
+        let events = self.get_running_events().await?;
+        let mut event_counts: BTreeMap<String, usize> = BTreeMap::new();
+        for event in events {
+            // Events created before station ids were normalized may hold lower case ids
+            let locations: HashSet<String> = event
+                .locations
+                .iter()
+                .map(|location| location.to_ascii_uppercase())
+                .collect();
+            for location in locations {
+                *event_counts.entry(location).or_default() += 1;
+            }
+        }
+        Ok(event_counts
+            .into_iter()
+            .map(|(station_id, event_count)| ActiveStation {
+                station_id,
+                event_count,
+            })
+            .collect())
+    }
+
+    /// Every station the oracle has stored weather for with its number of readings, sorted by station id. Names
+    /// come from the weather data and are left out if it can't be reached
+    pub async fn stored_stations(&self) -> Result<Vec<StoredStation>, Error> {
+        let stations = self.event_data.get_stored_stations().await?;
+        let station_names: HashMap<String, String> = match self.weather_data.stations().await {
+            Ok(stations) => stations
+                .into_iter()
+                .map(|station| {
+                    (
+                        station.station_id.to_ascii_uppercase(),
+                        station.station_name,
+                    )
+                })
+                .collect(),
+            Err(e) => {
+                warn!("failed to get station names for stored stations: {}", e);
+                HashMap::new()
+            }
+        };
+        Ok(stations
+            .into_iter()
+            .map(|(station_id, readings)| StoredStation {
+                station_name: station_names.get(&station_id).cloned(),
+                station_id,
+                readings,
+            })
+            .collect())
+    }
+
     pub async fn list_events(&self, filter: EventFilter) -> Result<Vec<EventSummary>, Error> {
         // TODO: add filter/pagination etc.
         // filter on active event/completed event/time range of event
@@ -170,6 +451,29 @@ impl Oracle {
             .map_err(Error::DataQuery)
     }
 
+    /// Events with the station in their locations, only the ones in `status` when it's set
+    pub async fn list_station_events(
+        &self,
+        station_id: &str,
+        status: Option<EventStatus>,
+    ) -> Result<Vec<EventSummary>, Error> {
+        let station_id =
+            normalize_station_id(station_id).map_err(|e| Error::NotFound(e.to_string()))?;
+        let events = self
+            .event_data
+            .filtered_list_events(EventFilter {
+                limit: None,
+                event_ids: None,
+                station_id: Some(station_id),
+            })
+            .await
+            .map_err(Error::DataQuery)?;
+        Ok(events
+            .into_iter()
+            .filter(|event| status.is_none() || status.as_ref() == Some(&event.status))
+            .collect())
+    }
+
     pub async fn get_event(&self, id: &Uuid) -> Result<Event, Error> {
         match self.event_data.get_event(id).await {
             Ok(event_data) => Ok(event_data),
@@ -180,17 +484,131 @@ impl Oracle {
         }
     }
 
+    /// Creates a new event with the same locations and parameters as an existing one, the new event
+    /// gets its own nonce and announcement and belongs to the coordinator cloning it
+    pub async fn clone_event(
+        &self,
+        coordinator_pubkey: NostrPublicKey,
+        event_id: &Uuid,
+        clone: CloneEvent,
+    ) -> Result<Event, Error> {
+        let source_event = self.get_event(event_id).await?;
+        let new_event = CreateEvent {
+            id: clone.id,
+            signing_date: clone.signing_date,
+            observation_date: clone.observation_date,
+            locations: source_event.locations,
+            number_of_values_per_entry: source_event.number_of_values_per_entry as usize,
+            total_allowed_entries: source_event.total_allowed_entries as usize,
+            number_of_places_win: source_event.number_of_places_win,
+            win_mode: source_event.win_mode,
+            score_threshold: source_event.score_threshold,
+            auto_sign: source_event.auto_sign,
+            scoring_mode: source_event.scoring_mode,
+            // The source event's group was already expanded into its locations
+            station_group: None,
+            late_entry_grace_seconds: source_event.late_entry_grace_seconds,
+            timezone: source_event.timezone,
+        };
+        self.create_event(coordinator_pubkey, new_event).await
+    }
+
     pub async fn create_event(
         &self,
         coordinator_pubkey: NostrPublicKey,
-        event: CreateEvent,
+        mut event: CreateEvent,
     ) -> Result<Event, Error> {
+        self.validate_event(&mut event)?;
+        let mut oracle_event = CreateEventData::new_with_nonce_source(
+            self.raw_public_key(),
+            coordinator_pubkey,
+            event,
+            self.nonce_source.as_ref(),
+        )
+        .map_err(Error::BadEvent)?;
+        // Kept with the event so it's scored the way it was announced, even if the oracle's tolerance changes
+        oracle_event.par_tolerance = Some(self.par_tolerance.value());
+        self.event_data
+            .add_event(oracle_event)
+            .await
+            .map_err(Error::DataQuery)
+    }
+
+    /// Checks an event against the oracle's limits before it's announced, normalizing its dates, timezone and
+    /// locations to how they're stored
+    fn validate_event(&self, event: &mut CreateEvent) -> Result<(), Error> {
         if event.id.get_version_num() != 7 {
             return Err(Error::BadEvent(anyhow!(
                 "event needs to provide a valid Uuidv7 for event id {}",
                 event.id
             )));
         }
+        let timezone = event
+            .timezone
+            .as_deref()
+            .map(parse_timezone)
+            .transpose()
+            .map_err(Error::BadEvent)?;
+        // Clients may send dates in any offset, the observation window starts at midnight in the event's
+        // timezone and everything is stored and compared in UTC
+        let offset = timezone.unwrap_or(UtcOffset::UTC);
+        let observation_date = event.observation_date.to_offset(offset);
+        if observation_date.time() != Time::MIDNIGHT {
+            return Err(Error::BadEvent(anyhow!(
+                "observation_date needs to be midnight {}, requested {} which is {} there",
+                timezone
+                    .map(|offset| format!("in the event's timezone {}", format_timezone(offset)))
+                    .unwrap_or_else(|| String::from("UTC")),
+                event
+                    .observation_date
+                    .format(&Rfc3339)
+                    .unwrap_or_else(|_| event.observation_date.to_string()),
+                observation_date
+                    .format(&Rfc3339)
+                    .unwrap_or_else(|_| observation_date.to_string())
+            )));
+        }
+        event.observation_date = observation_date.to_offset(UtcOffset::UTC);
+        event.timezone = timezone.map(format_timezone);
+        event.signing_date = event.signing_date.to_offset(UtcOffset::UTC);
+        if event.late_entry_grace_seconds >= OBSERVATION_WINDOW.whole_seconds() as u64 {
+            return Err(Error::BadEvent(anyhow!(
+                "late_entry_grace_seconds needs to be less than the {} second observation window, requested: {}",
+                OBSERVATION_WINDOW.whole_seconds(),
+                event.late_entry_grace_seconds
+            )));
+        }
+        if let Some(group) = event.station_group.take() {
+            let Some(stations) = self.station_groups.get(&group) else {
+                return Err(Error::BadEvent(anyhow!(
+                    "unknown station group '{}', known groups: [{}]",
+                    group,
+                    self.station_groups.names().join(", ")
+                )));
+            };
+            // Inline locations are tracked along with the group's stations
+            event.locations = stations
+                .iter()
+                .cloned()
+                .chain(mem::take(&mut event.locations))
+                .collect();
+        }
+        event.locations = event
+            .locations
+            .iter()
+            .map(|location| normalize_station_id(location))
+            .collect::<Result<Vec<String>, anyhow::Error>>()
+            .map_err(Error::BadEvent)?;
+        // Stored sorted so an event reads back the same no matter what order the locations were sent in
+        event.locations.sort();
+        event.locations.dedup();
+        if event.locations.len() > self.max_locations_per_event {
+            return Err(Error::BadEvent(anyhow!(
+                "Max number of locations in an event is {}, requested: {}",
+                self.max_locations_per_event,
+                event.locations.len()
+            )));
+        }
         if event.total_allowed_entries > 25 {
             return Err(Error::BadEvent(anyhow!(
                 "Max number of allowed entries the oracle can watch is 25"
@@ -202,18 +620,408 @@ impl Oracle {
                 event.number_of_places_win
             )));
         }
-        let oracle_event = CreateEventData::new(self.raw_public_key(), coordinator_pubkey, event)
-            .map_err(Error::BadEvent)?;
-        self.event_data
-            .add_event(oracle_event)
+        if event.number_of_places_win < 1 {
+            return Err(Error::BadEvent(anyhow!(
+                "number_of_places_win needs to be at least 1, requested: {}",
+                event.number_of_places_win
+            )));
+        }
+        if event.number_of_places_win as usize > event.total_allowed_entries {
+            return Err(Error::BadEvent(anyhow!(
+                "number_of_places_win can't be more than the {} total_allowed_entries, requested: {}",
+                event.total_allowed_entries,
+                event.number_of_places_win
+            )));
+        }
+        let max_score = event
+            .scoring_mode
+            .scheme()
+            .max_base_score(event.number_of_values_per_entry);
+        // Base scores are stored shifted past the 4 digit time tie breaker
+        if max_score
+            .checked_mul(10000)
+            .is_none_or(|total| total > i64::MAX as u64)
+        {
+            return Err(Error::BadEvent(anyhow!(
+                "{} scoring can't score {} values per entry",
+                event.scoring_mode,
+                event.number_of_values_per_entry
+            )));
+        }
+        match event.win_mode {
+            WinMode::TopN => {
+                if event.score_threshold.is_some() {
+                    return Err(Error::BadEvent(anyhow!(
+                        "score_threshold can only be set when win_mode is threshold"
+                    )));
+                }
+            }
+            WinMode::Threshold => {
+                let max_score = max_score as i64;
+                match event.score_threshold {
+                    Some(threshold) if threshold > 0 && threshold <= max_score => {}
+                    Some(threshold) => {
+                        return Err(Error::BadEvent(anyhow!(
+                            "score_threshold must be between 1 and {}, requested: {}",
+                            max_score,
+                            threshold
+                        )));
+                    }
+                    None => {
+                        return Err(Error::BadEvent(anyhow!(
+                            "score_threshold is required when win_mode is threshold"
+                        )));
+                    }
+                }
+            }
+        }
+        // Every pick is worth a multiple of the over or under points, so winning base scores can only
+        // take so many values. Places past that would all be decided by the time tie breaker
+        let lowest_winning_score = (event.score_threshold.unwrap_or(0).max(0) as u64)
+            .div_ceil(OVER_OR_UNDER_POINTS)
+            * OVER_OR_UNDER_POINTS;
+        let winning_scores =
+            max_score.saturating_sub(lowest_winning_score) / OVER_OR_UNDER_POINTS + 1;
+        if event.number_of_places_win as u64 > winning_scores {
+            return Err(Error::BadEvent(anyhow!(
+                "number_of_places_win can't be more than the {} different winning scores {} values per entry can get, requested: {}",
+                winning_scores,
+                event.number_of_values_per_entry,
+                event.number_of_places_win
+            )));
+        }
+        Ok(())
+    }
+
+    /// Cancels an event that hasn't been signed yet, the oracle stops pulling weather for it and never signs it.
+    /// Cancelling an event that's already cancelled returns it unchanged
+    pub async fn cancel_event(
+        &self,
+        nostr_pubkey: NostrPublicKey,
+        event_id: &Uuid,
+    ) -> Result<Event, Error> {
+        let event = self.get_event(event_id).await?;
+        if event.coordinator_pubkey != nostr_pubkey.to_bech32()? {
+            return Err(Error::Forbidden(format!(
+                "only the coordinator of event {} can cancel it",
+                event.id
+            )));
+        }
+        match event.status {
+            EventStatus::Cancelled => return Ok(event),
+            EventStatus::Signed => {
+                return Err(Error::EventExpired(format!(
+                    "event {} has already been signed, it can't be cancelled",
+                    event.id
+                )))
+            }
+            _ => {}
+        }
+        if !self.event_data.cancel_event(event_id).await? {
+            // Signed or cancelled between reading the event and cancelling it
+            let event = self.get_event(event_id).await?;
+            if event.status == EventStatus::Cancelled {
+                return Ok(event);
+            }
+            return Err(Error::EventExpired(format!(
+                "event {} has already been signed, it can't be cancelled",
+                event.id
+            )));
+        }
+        self.announcements().remove(event_id);
+        info!("event {} cancelled", event_id);
+        self.get_event(event_id).await
+    }
+
+    /// Changes the outcome parameters of a live event that no one has entered yet, the event gets a new nonce
+    /// and announcement since its possible outcomes change with them
+    pub async fn update_event(
+        &self,
+        nostr_pubkey: NostrPublicKey,
+        event_id: &Uuid,
+        update: UpdateEvent,
+    ) -> Result<Event, Error> {
+        let event = self.get_event(event_id).await?;
+        if event.coordinator_pubkey != nostr_pubkey.to_bech32()? {
+            return Err(Error::Forbidden(format!(
+                "only the coordinator of event {} can update it",
+                event.id
+            )));
+        }
+        if event.status != EventStatus::Live {
+            return Err(Error::EventLocked(format!(
+                "event {} is {}, only live events can be updated",
+                event.id, event.status
+            )));
+        }
+        if !event.entry_ids.is_empty() {
+            return Err(Error::EventLocked(format!(
+                "event {} already has entries ({}), its outcomes can't change",
+                event.id,
+                event.entry_ids.len()
+            )));
+        }
+        let win_mode = update.win_mode.unwrap_or(event.win_mode);
+        let score_threshold = match win_mode {
+            WinMode::Threshold => update.score_threshold.or(event.score_threshold),
+            WinMode::TopN => update.score_threshold,
+        };
+        let mut updated_event = CreateEvent {
+            id: event.id,
+            signing_date: event.signing_date,
+            observation_date: event.observation_date,
+            locations: event.locations,
+            number_of_values_per_entry: event.number_of_values_per_entry as usize,
+            total_allowed_entries: update
+                .total_allowed_entries
+                .unwrap_or(event.total_allowed_entries as usize),
+            number_of_places_win: update
+                .number_of_places_win
+                .unwrap_or(event.number_of_places_win),
+            win_mode,
+            score_threshold,
+            auto_sign: event.auto_sign,
+            scoring_mode: event.scoring_mode,
+            station_group: None,
+            late_entry_grace_seconds: event.late_entry_grace_seconds,
+            timezone: event.timezone,
+        };
+        self.validate_event(&mut updated_event)?;
+        let oracle_event = CreateEventData::new_with_nonce_source(
+            self.raw_public_key(),
+            nostr_pubkey,
+            updated_event,
+            self.nonce_source.as_ref(),
+        )
+        .map_err(Error::BadEvent)?;
+        if !self.event_data.update_event_outcomes(&oracle_event).await? {
+            // Entered or signed between reading the event and updating it
+            return Err(Error::EventLocked(format!(
+                "event {} already has entries, its outcomes can't change",
+                event.id
+            )));
+        }
+        info!("event {} outcomes updated", event_id);
+        self.get_event(event_id).await
+    }
+
+    pub async fn get_event_announcement(&self, id: &Uuid) -> Result<Arc<EventAnnouncement>, Error> {
+        let event = self.get_event(id).await?;
+        // Updating a live event's outcomes replaces its announcement, so only reuse one built for the stored one
+        let cached = self.announcements().get(&event.id);
+        if let Some(announcement) = cached {
+            if announcement.event_announcement == event.event_announcement {
+                return Ok(announcement);
+            }
+        }
+        let cacheable = !matches!(event.status, EventStatus::Signed | EventStatus::Cancelled);
+        let keys: Vec<SecretKey> = std::iter::once(self.private_key)
+            .chain(self.retired_keys.iter().copied())
+            .collect();
+        // Generating and checking every outcome is cpu heavy for events with a lot of entries and places
+        let announcement = tokio::task::spawn_blocking(move || build_announcement(event, &keys))
             .await
-            .map_err(Error::DataQuery)
+            .map_err(|e| {
+                Error::OutcomeNotFound(format!("failed to build announcement: {}", e))
+            })??;
+        let announcement = Arc::new(announcement);
+        // Signed and cancelled events are rarely read again, don't let them push out the ones still running
+        if cacheable {
+            self.announcements().insert(announcement.clone());
+        }
+        Ok(announcement)
+    }
+
+    fn announcements(&self) -> MutexGuard<'_, AnnouncementCache> {
+        self.announcements
+            .lock()
+            .unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    /// The outcome messages of the event's announcement, they were checked against its locking points when the
+    /// announcement was built so callers only ever get the bytes the oracle will sign
+    pub async fn get_event_outcome_messages(
+        &self,
+        id: &Uuid,
+        encoding: OutcomeEncoding,
+    ) -> Result<EventOutcomeMessages, Error> {
+        let announcement = self.get_event_announcement(id).await?;
+        let outcome_messages = match encoding {
+            OutcomeEncoding::Hex => announcement.outcome_messages.clone(),
+            OutcomeEncoding::Base64 => announcement
+                .outcome_messages
+                .iter()
+                .map(|outcome| {
+                    hex::decode(outcome)
+                        .map(|outcome| general_purpose::STANDARD.encode(outcome))
+                        .map_err(|e| {
+                            Error::OutcomeNotFound(format!(
+                                "bad outcome message in announcement of event {}: {}",
+                                announcement.id, e
+                            ))
+                        })
+                })
+                .collect::<Result<Vec<String>, Error>>()?,
+        };
+        Ok(EventOutcomeMessages {
+            id: announcement.id,
+            encoding,
+            outcome_messages,
+        })
+    }
+
+    /// Which of the event's current entries win under each outcome the oracle may attest, a page at a time
+    pub async fn get_event_outcome_winners(
+        &self,
+        id: &Uuid,
+        filter: OutcomeWinnersFilter,
+    ) -> Result<EventOutcomeWinners, Error> {
+        let event = self.get_event(id).await?;
+        let (total_outcomes, outcomes) = map_outcome_winners(
+            &event.win_mode,
+            event.total_allowed_entries as usize,
+            event.number_of_places_win as usize,
+            &event.entry_ids,
+            &filter,
+        );
+        Ok(EventOutcomeWinners {
+            id: event.id,
+            total_outcomes,
+            outcomes,
+        })
+    }
+
+    /// The weather values entries of the event can pick on, with the options each one accepts
+    pub async fn get_event_dimensions(&self, id: &Uuid) -> Result<EventDimensions, Error> {
+        let event = self.get_event(id).await?;
+        Ok(EventDimensions {
+            id: event.id,
+            dimensions: Dimension::all()
+                .into_iter()
+                .map(DimensionOptions::from)
+                .collect(),
+        })
+    }
+
+    /// Pays out the winning places of a signed event, `split` defaults to an even split over `number_of_places_win`
+    pub async fn get_event_payouts(
+        &self,
+        id: &Uuid,
+        split: Option<PayoutSplit>,
+    ) -> Result<EventPayouts, Error> {
+        let event = self.get_event(id).await?;
+        if event.status != EventStatus::Signed {
+            return Err(Error::BadEvent(anyhow!(
+                "event {} hasn't been signed yet, payouts are only known once it is",
+                event.id
+            )));
+        }
+        let places = event.number_of_places_win as usize;
+        let split = split.unwrap_or_else(|| PayoutSplit::even(places));
+        if split.percents().len() != places {
+            return Err(Error::BadPayoutSplit(format!(
+                "event {} pays out {} places but the split has {}",
+                event.id,
+                places,
+                split.percents().len()
+            )));
+        }
+
+        let payouts: Vec<PlacePayout> = ranked_winners(&event)
+            .into_iter()
+            .zip(split.percents())
+            .enumerate()
+            .map(|(index, (entry, percent))| PlacePayout {
+                place: index + 1,
+                entry_id: entry.id,
+                score: entry.score,
+                percent: *percent,
+            })
+            .collect();
+        let claimed: u64 = payouts.iter().map(|payout| payout.percent).sum();
+        Ok(EventPayouts {
+            id: event.id,
+            split: split.percents().to_vec(),
+            payouts,
+            unclaimed_percent: 100 - claimed,
+        })
+    }
+
+    pub async fn get_event_announcement_bytes(&self, id: &Uuid) -> Result<Vec<u8>, Error> {
+        let announcement = self.get_event_announcement(id).await?;
+        Ok(bincode::serialize(announcement.as_ref())?)
+    }
+
+    /// Compares the latest forecast and observation per station, in the order of the event's locations
+    pub async fn get_event_weather_diff(&self, id: &Uuid) -> Result<Vec<WeatherDiff>, Error> {
+        let event = self.get_event(id).await?;
+        // Readings come back oldest first, so the last one for a station is the latest
+        let mut latest_weather: HashMap<&str, &Weather> = HashMap::new();
+        for weather in &event.weather {
+            latest_weather.insert(&weather.station_id, weather);
+        }
+        Ok(event
+            .locations
+            .iter()
+            .filter_map(|location| latest_weather.get(location.as_str()))
+            .map(|weather| WeatherDiff::new(weather, self.event_par_tolerance(event.par_tolerance)))
+            .collect())
+    }
+
+    pub async fn get_event_weather_parquet(&self, id: &Uuid) -> Result<Vec<u8>, Error> {
+        let event = self.get_event(id).await?;
+        let file_path = std::env::temp_dir()
+            .join(format!("event_weather_{}_{}.parquet", id, Uuid::now_v7()))
+            .to_string_lossy()
+            .to_string();
+        EventData::write_weather_parquet(&event.weather, &file_path)?;
+        let parquet = tokio::fs::read(&file_path).await;
+        if let Err(e) = tokio::fs::remove_file(&file_path).await {
+            warn!("failed to remove temp parquet file {}: {}", file_path, e);
+        }
+        Ok(parquet?)
+    }
+
+    pub async fn get_event_entries_csv(&self, id: &Uuid) -> Result<String, Error> {
+        let event = self.get_event(id).await?;
+        Ok(event.entries_csv())
+    }
+
+    /// Once the observation window starts the event is no longer live, the grace period only
+    /// covers entries that were sent right before the cutoff but are slow to arrive. The event's
+    /// own grace can only extend the oracle's, not shorten it
+    fn entry_cutoff(&self, event: &Event) -> OffsetDateTime {
+        let late_entry_grace = Duration::seconds(event.late_entry_grace_seconds as i64);
+        event.observation_date + self.entry_grace_period.max(late_entry_grace)
+    }
+
+    /// The tolerance stored with the event, events created before it was stored use the oracle's
+    fn event_par_tolerance(&self, par_tolerance: Option<i64>) -> ParTolerance {
+        par_tolerance
+            .and_then(|tolerance| ParTolerance::try_from(tolerance).ok())
+            .unwrap_or(self.par_tolerance)
+    }
+
+    pub async fn get_event_capacity(&self, id: &Uuid) -> Result<EventCapacity, Error> {
+        let event = self.get_event(id).await?;
+        let total_entries = self.event_data.get_event_total_entries(event.id).await?;
+        let remaining = (event.total_allowed_entries - total_entries).max(0);
+        let accepting = !is_event_full(total_entries, event.total_allowed_entries)
+            && event.attestation.is_none()
+            && event.status != EventStatus::Cancelled
+            && OffsetDateTime::now_utc() < self.entry_cutoff(&event);
+        Ok(EventCapacity {
+            total_allowed: event.total_allowed_entries,
+            total_entries,
+            remaining,
+            accepting,
+        })
     }
 
     pub async fn add_event_entry(
         &self,
         nostr_pubkey: NostrPublicKey,
-        entry: AddEventEntry,
+        mut entry: AddEventEntry,
     ) -> Result<WeatherEntry, Error> {
         if entry.id.get_version_num() != 7 {
             return Err(Error::BadEntry(format!(
@@ -238,33 +1046,74 @@ impl Oracle {
                 entry.id
             )));
         }
-        // NOTE: It's not the end of the world if we do go over the allowed number of entries,
-        // worse case just means more people in the event, doesn't change our score mechanism
-        if event.total_allowed_entries < event.entry_ids.len() as i64 {
+        let entry_cutoff = self.entry_cutoff(&event);
+        if OffsetDateTime::now_utc() >= entry_cutoff {
+            return Err(Error::EventExpired(format!(
+                "event {} stopped taking entries at {}",
+                event.id,
+                entry_cutoff
+                    .format(&Rfc3339)
+                    .unwrap_or(entry_cutoff.to_string())
+            )));
+        }
+        // A grace period running past the signing_date can't reopen an event that's already been signed
+        if event.attestation.is_some() {
+            return Err(Error::EventExpired(format!(
+                "event {} has already been signed, no more entries are allowed",
+                event.id
+            )));
+        }
+        if event.status == EventStatus::Cancelled {
+            return Err(Error::EventExpired(format!(
+                "event {} has been cancelled, no more entries are allowed",
+                event.id
+            )));
+        }
+        // Checked again when the entry is stored, an entry added meanwhile can still fill the event
+        if is_event_full(event.entry_ids.len() as i64, event.total_allowed_entries) {
             return Err(Error::BadEntry(format!(
                 "event {} is full, no more entries are allowed",
                 event.id
             )));
         }
 
-        let mut choice_count = 0;
+        for weather_choice in entry.expected_observations.iter_mut() {
+            weather_choice.stations =
+                normalize_station_id(&weather_choice.stations).map_err(|e| {
+                    Error::BadEntry(format!("entry_id {0} not valid, {1}", entry.id, e))
+                })?;
+        }
+        // Stations without a single pick are abstained on entirely, they're dropped so the stored entry
+        // only holds the stations it picked on
+        entry
+            .expected_observations
+            .retain(|weather_choice| weather_choice.pick_count() > 0);
+        if entry.expected_observations.is_empty() {
+            return Err(Error::BadEntry(format!(
+                "entry_id {0} not valid, needs at least one value choice",
+                entry.id
+            )));
+        }
+        let mut seen_stations: Vec<&str> = vec![];
         for weather_choice in &entry.expected_observations {
-            if weather_choice.temp_high.is_some() {
-                choice_count += 1;
-            }
-            if weather_choice.temp_low.is_some() {
-                choice_count += 1;
-            }
-            if weather_choice.wind_speed.is_some() {
-                choice_count += 1;
-            }
-
-            if choice_count > event.number_of_values_per_entry {
+            if seen_stations.contains(&weather_choice.stations.as_str()) {
                 return Err(Error::BadEntry(format!(
-                    "entry_id {0} not valid, too many value choices, max allowed {1} but got {2}",
-                    entry.id, event.number_of_values_per_entry, choice_count
+                    "entry_id {0} not valid, station {1} is chosen more than once",
+                    entry.id, weather_choice.stations
                 )));
             }
+            seen_stations.push(&weather_choice.stations);
+        }
+        let choice_count: usize = entry
+            .expected_observations
+            .iter()
+            .map(|weather_choice| weather_choice.pick_count())
+            .sum();
+        if choice_count > event.number_of_values_per_entry {
+            return Err(Error::BadEntry(format!(
+                "entry_id {0} not valid, too many value choices, max allowed {1} but got {2}",
+                entry.id, event.number_of_values_per_entry, choice_count
+            )));
         }
 
         let locations_choose: Vec<String> = entry
@@ -273,21 +1122,150 @@ impl Oracle {
             .iter()
             .map(|weather_vals| weather_vals.stations.clone())
             .collect();
-        let all_valid_locations = locations_choose
-            .iter()
-            .all(|choose| event.locations.contains(choose));
+        let all_valid_locations = locations_choose.iter().all(|choose| {
+            event
+                .locations
+                .iter()
+                .any(|location| location.eq_ignore_ascii_case(choose))
+        });
         if !all_valid_locations {
             return Err(Error::BadEntry(format!(
                 "entry_id {0} not valid, choose locations not in the even",
                 entry.id
             )));
         }
+        let weather_entry = WeatherEntry {
+            coordinator_pubkey: Some(nostr_pubkey),
+            ..entry.into()
+        };
+        self.event_data
+            .add_event_entry(weather_entry)
+            .await
+            .map_err(|e| match e {
+                AddEntryError::EventFull(_) => Error::BadEntry(e.to_string()),
+                AddEntryError::Query(e) => Error::DataQuery(e),
+            })
+    }
+
+    pub async fn get_coordinator_entries(
+        &self,
+        nostr_pubkey: NostrPublicKey,
+        coordinator_pubkey: NostrPublicKey,
+    ) -> Result<Vec<WeatherEntry>, Error> {
+        if nostr_pubkey != coordinator_pubkey {
+            return Err(Error::Forbidden(String::from(
+                "coordinators can only view their own entries",
+            )));
+        }
         self.event_data
-            .add_event_entry(entry.into())
+            .get_coordinator_weather_entries(&coordinator_pubkey.to_bech32()?)
             .await
             .map_err(Error::DataQuery)
     }
 
+    /// Stores weather an operator pushed for an event, each pushed station is scored on its latest push in place
+    /// of the daemon's readings, including the ones the etl adds afterwards
+    pub async fn update_event_weather(
+        &self,
+        nostr_pubkey: NostrPublicKey,
+        event_id: &Uuid,
+        weather: Vec<Weather>,
+    ) -> Result<Event, Error> {
+        self.check_operator(&nostr_pubkey)?;
+        let event = self.get_event(event_id).await?;
+        check_weather_updatable(&event)?;
+        if weather.is_empty() {
+            return Err(Error::BadEvent(anyhow!(
+                "needs weather for at least one station"
+            )));
+        }
+        let mut normalized = Vec::with_capacity(weather.len());
+        for mut station_weather in weather {
+            station_weather.station_id =
+                normalize_station_id(&station_weather.station_id).map_err(Error::BadEvent)?;
+            if !event
+                .locations
+                .iter()
+                .any(|location| location.eq_ignore_ascii_case(&station_weather.station_id))
+            {
+                return Err(Error::BadEvent(anyhow!(
+                    "station {} isn't one of event {}'s locations",
+                    station_weather.station_id,
+                    event.id
+                )));
+            }
+            normalized.push(station_weather);
+        }
+        info!(
+            "operator {} updating weather of stations {:?} for event {}",
+            nostr_pubkey.to_bech32()?,
+            normalized
+                .iter()
+                .map(|weather| weather.station_id.as_str())
+                .collect::<Vec<&str>>(),
+            event.id
+        );
+        self.event_data
+            .add_weather_overrides(event.id, normalized)
+            .await?;
+        self.get_event(&event.id).await
+    }
+
+    /// Same as `update_event_weather`, with the weather loaded straight from a parquet file by DuckDB instead of
+    /// json so large station sets don't need the round trip. See `EventData::update_weather_station_parquet`
+    /// for the columns
+    pub async fn update_event_weather_parquet(
+        &self,
+        nostr_pubkey: NostrPublicKey,
+        event_id: &Uuid,
+        parquet: Vec<u8>,
+    ) -> Result<Event, Error> {
+        self.check_operator(&nostr_pubkey)?;
+        let event = self.get_event(event_id).await?;
+        check_weather_updatable(&event)?;
+        let file_path = std::env::temp_dir()
+            .join(format!(
+                "event_weather_upload_{}_{}.parquet",
+                event_id,
+                Uuid::now_v7()
+            ))
+            .to_string_lossy()
+            .to_string();
+        tokio::fs::write(&file_path, parquet).await?;
+        let stored = self
+            .event_data
+            .update_weather_station_parquet(
+                event.id,
+                &file_path,
+                &event.locations,
+                ObservationStorage {
+                    original_offsets: self.preserve_observation_offsets,
+                    raw_temps: self.keep_raw_observation_temps,
+                },
+            )
+            .await;
+        if let Err(e) = tokio::fs::remove_file(&file_path).await {
+            warn!("failed to remove temp parquet file {}: {}", file_path, e);
+        }
+        let weather_ids = stored?;
+        info!(
+            "operator {} updated weather of {} stations for event {} from parquet",
+            nostr_pubkey.to_bech32()?,
+            weather_ids.len(),
+            event.id
+        );
+        self.get_event(&event.id).await
+    }
+
+    fn check_operator(&self, nostr_pubkey: &NostrPublicKey) -> Result<(), Error> {
+        if !self.operator_pubkeys.contains(nostr_pubkey) {
+            return Err(Error::Forbidden(String::from(
+                "only operators can update event weather",
+            )));
+        }
+        Ok(())
+    }
+
     pub async fn get_running_events(&self) -> Result<Vec<ActiveEvent>, Error> {
         match self.event_data.get_active_events().await {
             Ok(event_data) => Ok(event_data),
@@ -296,6 +1274,17 @@ impl Oracle {
         }
     }
 
+    /// Every event that wasn't cancelled grouped by status, listing at most `signed_limit` signed events
+    pub async fn get_sign_status(
+        &self,
+        filter: SignStatusFilter,
+    ) -> Result<EventSignStatus, Error> {
+        self.event_data
+            .get_sign_status(filter.signed_limit.unwrap_or(DEFAULT_SIGNED_LIMIT))
+            .await
+            .map_err(Error::DataQuery)
+    }
+
     pub async fn get_event_entry(
         &self,
         event_id: &Uuid,
@@ -311,9 +1300,122 @@ impl Oracle {
         }
     }
 
+    /// How each pick of an entry scored against the event's latest weather for its station
+    pub async fn get_entry_breakdown(
+        &self,
+        event_id: &Uuid,
+        entry_id: &Uuid,
+    ) -> Result<EntryBreakdown, Error> {
+        let event = self.get_event(event_id).await?;
+        let entry = self.get_event_entry(event_id, entry_id).await?;
+        // Readings come back oldest first, so the last one for a station is the latest
+        let mut forecasts: HashMap<String, Forecasted> = HashMap::new();
+        let mut observations: HashMap<String, Observed> = HashMap::new();
+        for weather in &event.weather {
+            forecasts.insert(weather.station_id.clone(), weather.forecasted.clone());
+            match &weather.observed {
+                Some(observed) => {
+                    observations.insert(weather.station_id.clone(), observed.clone());
+                }
+                None => {
+                    observations.remove(&weather.station_id);
+                }
+            }
+        }
+        let picks = score_entry_picks(
+            &event.locations,
+            event.scoring_mode,
+            &entry,
+            &forecasts,
+            &observations,
+            self.event_par_tolerance(event.par_tolerance),
+        );
+        let base_score = picks
+            .iter()
+            .map(|pick| pick.points)
+            .fold(0, u64::saturating_add);
+        Ok(EntryBreakdown {
+            entry_id: entry.id,
+            event_id: event.id,
+            scoring_mode: event.scoring_mode,
+            base_score,
+            tie_breaker: entry_tie_breaker(&entry.id),
+            score: entry_score(base_score, &entry.id),
+            stored_score: entry.score,
+            picks,
+        })
+    }
+
+    /// Next time still to come an event the oracle signs on its own is due, its signing_date plus the sign buffer
+    pub async fn next_auto_sign_date(&self) -> Result<Option<OffsetDateTime>, Error> {
+        let now = OffsetDateTime::now_utc();
+        let events = self.event_data.get_auto_sign_events(self.auto_sign).await?;
+        Ok(events
+            .into_iter()
+            .map(|(_, signing_date)| self.sign_after(signing_date))
+            .filter(|sign_after| *sign_after > now)
+            .min())
+    }
+
+    /// The earliest an event with this signing_date is signed
+    fn sign_after(&self, signing_date: OffsetDateTime) -> OffsetDateTime {
+        signing_date.saturating_add(self.sign_buffer)
+    }
+
+    /// Scores and signs the auto sign events whose signing_date has passed, returns the ids of the events signed
+    pub async fn auto_sign_events(&self, etl_process_id: usize) -> Result<Vec<Uuid>, Error> {
+        // Taken before the due events are read, so events another pass just signed are seen as signed
+        let _etl_guard = self.etl_lock.lock().await;
+        let now = OffsetDateTime::now_utc();
+        let due_events: Vec<Uuid> = self
+            .event_data
+            .get_auto_sign_events(self.auto_sign)
+            .await?
+            .into_iter()
+            .filter(|(_, signing_date)| self.sign_after(*signing_date) <= now)
+            .map(|(event_id, _)| event_id)
+            .collect();
+        if due_events.is_empty() {
+            return Ok(vec![]);
+        }
+        info!(
+            " etl_process_id {}, auto signing events: {:?}",
+            etl_process_id, due_events
+        );
+
+        let events_to_update: Vec<ActiveEvent> = self
+            .get_running_events()
+            .await?
+            .into_iter()
+            .filter(|event| due_events.contains(&event.id))
+            .collect();
+        self.etl_events(etl_process_id, events_to_update).await?;
+
+        // Events can still be unsigned, ie. waiting on observations with the require_all_observations policy
+        let unsigned: Vec<Uuid> = self
+            .event_data
+            .get_auto_sign_events(self.auto_sign)
+            .await?
+            .into_iter()
+            .map(|(event_id, _)| event_id)
+            .collect();
+        let signed: Vec<Uuid> = due_events
+            .into_iter()
+            .filter(|event_id| !unsigned.contains(event_id))
+            .collect();
+        for event_id in &signed {
+            info!(
+                " etl_process_id {}, auto signed event {}",
+                etl_process_id, event_id
+            );
+        }
+        Ok(signed)
+    }
+
     pub async fn etl_data(&self, etl_process_id: usize) -> Result<(), Error> {
         // NOTE: Making the assumption the number of active events will remain small, maybe 10 at most for now,
         // Also assuming it's okay to have duplicate location weather reading rows for now (if this becomes a problem we will need to de-dup)
+        let _etl_guard = self.etl_lock.lock().await;
         info!(" etl_process_id {}, starting etl process", etl_process_id);
         debug!(" etl_process_id {}, getting running events", etl_process_id);
         let events_to_update = self.get_running_events().await?;
@@ -321,6 +1423,14 @@ impl Oracle {
             " etl_process_id {}, completed getting running events",
             etl_process_id
         );
+        self.etl_events(etl_process_id, events_to_update).await
+    }
+
+    async fn etl_events(
+        &self,
+        etl_process_id: usize,
+        events_to_update: Vec<ActiveEvent>,
+    ) -> Result<(), Error> {
         // 1) update weather readings
         debug!(
             " etl_process_id {}, updating weather readings",
@@ -358,11 +1468,25 @@ impl Oracle {
         );
         debug!(" etl_process_id {}, getting events to sign", etl_process_id);
         // 3) sign results for events that are completed and need it
-        let events_to_sign: Vec<Uuid> = events_to_update
+        let mut events_to_sign: Vec<Uuid> = vec![];
+        for event in events_to_update
             .iter()
             .filter(|event| event.status == EventStatus::Completed && event.attestation.is_none())
-            .map(|event| event.id)
-            .collect();
+        {
+            if self.signing_policy == SigningPolicy::RequireAllObservations {
+                let missing_stations = self.missing_observation_stations(event).await?;
+                if !missing_stations.is_empty() {
+                    warn!(
+                        " etl_process_id {}, not signing event {} yet, no observations for stations: {}",
+                        etl_process_id,
+                        event.id,
+                        missing_stations.join(",")
+                    );
+                    continue;
+                }
+            }
+            events_to_sign.push(event.id);
+        }
         debug!(
             " etl_process_id {}, completed getting events to sign",
             etl_process_id
@@ -388,6 +1512,20 @@ impl Oracle {
         Ok(())
     }
 
+    /// Keeps only what the oracle is configured to store of an observation read from the weather data
+    fn observed_to_store(&self, observed: Observed) -> Observed {
+        let observed = if self.preserve_observation_offsets {
+            observed.with_original_offset()
+        } else {
+            observed
+        };
+        if self.keep_raw_observation_temps {
+            observed
+        } else {
+            observed.without_raw_temps()
+        }
+    }
+
     async fn update_event_weather_data(
         &self,
         etl_process_id: usize,
@@ -404,8 +1542,16 @@ impl Oracle {
             } else {
                 let observation_data = self.event_observation_data(&event).await?;
                 info!("above update ksks");
-                add_forecast_data_and_observation_data(&event, forecast_data, observation_data)
-                    .await?
+                let mut weather =
+                    add_forecast_data_and_observation_data(&event, forecast_data, observation_data)
+                        .await?;
+                for reading in weather.iter_mut() {
+                    reading.observed = reading
+                        .observed
+                        .take()
+                        .map(|observed| self.observed_to_store(observed));
+                }
+                weather
             };
             info!("above update");
             self.event_data
@@ -452,7 +1598,28 @@ impl Oracle {
 
         let observation_data = self.event_observation_data(&event).await?;
         let forecast_data = self.event_forecast_data(&event).await?;
+        let mut forecasts: HashMap<String, Forecasted> = HashMap::new();
+        for location in &event.locations {
+            if let Some(forecasted) = event_forecast(&event, &forecast_data, location)? {
+                forecasts.insert(location.clone(), forecasted);
+            }
+        }
+        let mut observations: HashMap<String, Observed> = HashMap::new();
+        for location in &event.locations {
+            if let Some(observation) = observation_data
+                .iter()
+                .find(|observation| observation.station_id.eq_ignore_ascii_case(location))
+            {
+                observations.insert(
+                    location.clone(),
+                    observation.try_into().map_err(Error::WeatherData)?,
+                );
+            }
+        }
+        self.apply_weather_overrides(&event, &mut forecasts, &mut observations)
+            .await?;
         let mut entry_scores: Vec<(Uuid, i64)> = vec![];
+        let par_tolerance = self.event_par_tolerance(event.par_tolerance);
 
         for entry in entries {
             if entry.event_id != event.id {
@@ -460,119 +1627,19 @@ impl Oracle {
                 continue;
             }
 
-            // Score logic, match on Par 2pts, on Over 1pt, on Under 1pt, created_at used as tie breaker (older > newer)
-            let mut base_score = 0;
-            const OVER_OR_UNDER_POINTS: u64 = 10;
-            const PAR_POINTS: u64 = 20;
-            let expected_observations = entry.expected_observations.clone();
-            let locations = event.locations.clone();
-            for location in locations {
-                let Some(choice) = expected_observations
-                    .iter()
-                    .find(|expected| expected.stations == location)
-                else {
-                    continue;
-                };
-
-                let Some(forecast) = forecast_data
-                    .iter()
-                    .find(|forecast| forecast.station_id == location)
-                else {
-                    warn!("no forecast found for: {}", location);
-                    continue;
-                };
-
-                let Some(observation) = observation_data
-                    .iter()
-                    .find(|observation| observation.station_id == location)
-                else {
-                    warn!("no observation found for: {}", location);
-                    continue;
-                };
-
-                if let Some(high_temp) = choice.temp_high.clone() {
-                    match high_temp {
-                        ValueOptions::Over => {
-                            if forecast.temp_high < observation.temp_high.round() as i64 {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                        ValueOptions::Par => {
-                            if forecast.temp_high == observation.temp_high.round() as i64 {
-                                base_score += PAR_POINTS;
-                            }
-                        }
-                        ValueOptions::Under => {
-                            if forecast.temp_high > observation.temp_high.round() as i64 {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                    }
-                }
-
-                if let Some(temp_low) = choice.temp_low.clone() {
-                    match temp_low {
-                        ValueOptions::Over => {
-                            if forecast.temp_low < observation.temp_low.round() as i64 {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                        ValueOptions::Par => {
-                            if forecast.temp_low == observation.temp_low.round() as i64 {
-                                base_score += PAR_POINTS;
-                            }
-                        }
-                        ValueOptions::Under => {
-                            if forecast.temp_low > observation.temp_low.round() as i64 {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                    }
-                }
-
-                if let Some(wind_speed) = choice.wind_speed.clone() {
-                    match wind_speed {
-                        ValueOptions::Over => {
-                            if forecast.wind_speed < observation.wind_speed {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                        ValueOptions::Par => {
-                            if forecast.wind_speed == observation.wind_speed {
-                                base_score += PAR_POINTS;
-                            }
-                        }
-                        ValueOptions::Under => {
-                            if forecast.wind_speed > observation.wind_speed {
-                                base_score += OVER_OR_UNDER_POINTS;
-                            }
-                        }
-                    }
-                }
-            }
-            let (created_at_secs, created_at_nano) = entry
-                .id
-                .get_timestamp()
-                .expect("UUIDv7 should have timestamp")
-                .to_unix();
-            let time_millis = (created_at_secs * 1000) + (created_at_nano as u64 / 1_000_000);
-            let time_part = 9999 - (time_millis % 10000) as u64;
-
-            /* By adding the time element we are able to make competitions that have 1mil unique possible scores
-            meaning no ties under the following constraints:
-
-            With queue for entries (serialized creation):
-            - Up to 10,000 entries over 24h: negligible collision risk
-            - Max burst: ~40 entries/second with millisecond precision
-
-            Without queue for entries (concurrent creation):
-            - Up to 1,300 entries over 24h: negligible collision risk
-            - Burst limit: ~30 entries/second for < 0.01% collision risk
-
-            This is important for keeping the amount of possible outcomes for the DLC as low as possible
-            but able to scale to as many entries as possible
-            */
-            let total_score = ((base_score * 10000) + time_part) as i64;
+            let picks = score_entry_picks(
+                &event.locations,
+                event.scoring_mode,
+                &entry,
+                &forecasts,
+                &observations,
+                par_tolerance,
+            );
+            let base_score = picks
+                .iter()
+                .map(|pick| pick.points)
+                .fold(0, u64::saturating_add);
+            let total_score = entry_score(base_score, &entry.id);
 
             info!(
                 "updating entry {} for event {} to score {} in etl process {}",
@@ -592,75 +1659,255 @@ impl Oracle {
         etl_process_id: usize,
         event_ids: Vec<Uuid>,
     ) -> Result<(), Error> {
-        let mut events: Vec<SignEvent> = self.event_data.get_events_to_sign(event_ids).await?;
-        info!("events: {:?}", events);
-        for event in events.iter_mut() {
-            let entries = self.event_data.get_event_weather_entries(&event.id).await?;
-            let mut entry_indices = entries.clone();
-            // very important, the sort index of the entry should always be the same when getting the outcome
-            entry_indices.sort_by_key(|entry| entry.id);
-
-            // Sort by score descending for top 3
-            let mut top_entries = entries.clone();
-            top_entries.sort_by_key(|entry| cmp::Reverse(entry.score));
-            top_entries.truncate(3);
-
-            // Get indices of top 3 in original entry_indices order
-            let winners: Vec<usize> = top_entries
-                .iter()
-                .map(|top_entry| {
-                    entry_indices
-                        .iter()
-                        .position(|entry| entry.id == top_entry.id)
-                        .expect("Entry should exist")
-                })
-                .collect();
+        // An event due from more than one place is only queued once
+        let mut unique_event_ids: Vec<Uuid> = Vec::with_capacity(event_ids.len());
+        for event_id in event_ids {
+            if !unique_event_ids.contains(&event_id) {
+                unique_event_ids.push(event_id);
+            }
+        }
+        info!("events to sign: {:?}", unique_event_ids);
+        // Every event is queued at once, the signing queue decides how many are worked on together
+        let signings = unique_event_ids.into_iter().map(|event_id| {
+            self.signing_queue
+                .run(self.sign_queued_event(etl_process_id, event_id))
+        });
+        for signed in join_all(signings).await {
+            signed?;
+        }
+        info!(
+            "completed adding oracle signature to all events that need it in etl process {}",
+            etl_process_id
+        );
+        Ok(())
+    }
+
+    /// Reads the event once it has a slot in the signing queue, it can be signed or cancelled while it waits
+    async fn sign_queued_event(&self, etl_process_id: usize, event_id: Uuid) -> Result<(), Error> {
+        let Some(mut event) = self
+            .event_data
+            .get_events_to_sign(vec![event_id])
+            .await?
+            .pop()
+        else {
+            info!(
+                " etl_process_id {}, event {} was signed or cancelled while queued, skipping it",
+                etl_process_id, event_id
+            );
+            return Ok(());
+        };
+        self.sign_event(&mut event).await
+    }
+
+    async fn sign_event(&self, event: &mut SignEvent) -> Result<(), Error> {
+        let entries = self.event_data.get_event_weather_entries(&event.id).await?;
+        let mut entry_indices = entries.clone();
+        // very important, the sort index of the entry should always be the same when getting the outcome
+        entry_indices.sort_by_key(|entry| entry.id);
+
+        let winners: Vec<usize> = match event.win_mode {
+            WinMode::TopN => {
+                // Sort by score descending, the announced outcomes rank the top number_of_places_win entries
+                let mut top_entries = entries.clone();
+                top_entries.sort_by_key(|entry| cmp::Reverse(entry.score));
+                top_entries.truncate(event.number_of_places_win as usize);
+
+                // Get indices of the winners in original entry_indices order
+                top_entries
+                    .iter()
+                    .map(|top_entry| {
+                        entry_indices
+                            .iter()
+                            .position(|entry| entry.id == top_entry.id)
+                            .expect("Entry should exist")
+                    })
+                    .collect()
+            }
+            WinMode::Threshold => {
+                let threshold = event.score_threshold.unwrap_or_default();
+                // Total score includes the time tie breaker in the lower 4 digits
+                let mut passing_entries: Vec<WeatherEntry> = entries
+                    .iter()
+                    .filter(|entry| entry.score.unwrap_or_default() / 10000 >= threshold)
+                    .cloned()
+                    .collect();
+                passing_entries.sort_by_key(|entry| cmp::Reverse(entry.score));
+                passing_entries.truncate(event.number_of_places_win as usize);
+
+                // Outcomes for threshold events are combinations, so the indices are kept in ascending order
+                let mut winners: Vec<usize> = passing_entries
+                    .iter()
+                    .map(|passing_entry| {
+                        entry_indices
+                            .iter()
+                            .position(|entry| entry.id == passing_entry.id)
+                            .expect("Entry should exist")
+                    })
+                    .collect();
+                winners.sort();
+                winners
+            }
+        };
+
+        if self.sign_after(event.signing_date) < OffsetDateTime::now_utc() {
+            let winner_bytes: Vec<u8> = get_winning_bytes(winners.clone());
 
-            if event.signing_date < OffsetDateTime::now_utc() {
-                let winner_bytes: Vec<u8> = get_winning_bytes(winners.clone());
+            let nonce_point = event.nonce.base_point_mul();
+
+            let winners_str = winners
+                .iter()
+                .filter_map(|entry_index| entry_indices.get(*entry_index))
+                .map(|entry| format!("({}, {})", entry.score.unwrap_or_default(), entry.id))
+                .collect::<Vec<String>>()
+                .join(", ");
+
+            // An attestation for an outcome no key announced can't unlock any DLC, so the event is left unsigned
+            let Some(signing_key) =
+                self.event_signing_key(event.nonce, &event.event_announcement, &winner_bytes)
+            else {
+                error!("no oracle key announced the outcome, leaving the event unsigned: event_id {} winners {} expiry {:?}", event.id, winners_str, event.event_announcement.expiry);
+
+                return Err(Error::OutcomeNotFound(format!(
+                    "event_id {} outcome winners {} wasn't announced",
+                    event.id, winners_str
+                )));
+            };
 
-                let nonce_point = event.nonce.base_point_mul();
+            let locking_point = attestation_locking_point(
+                signing_key.public_key(&Secp256k1::new()),
+                nonce_point,
+                &winner_bytes,
+            );
 
-                let locking_point =
-                    attestation_locking_point(self.public_key, nonce_point, &winner_bytes);
+            info!("winner_bytes: {:?}", winner_bytes);
 
-                info!("winner_bytes: {:?}", winner_bytes);
+            let MaybePoint::Valid(_) = locking_point else {
+                // Something went horribly wrong, use the info from this log line to track refunding users based on DLC expiry (we set to 1 week)
+                error!("final result doesn't match any of the possible outcomes: event_id {} winners {} expiry {:?}", event.id, winners_str, event.event_announcement.expiry);
 
-                let winners_str = winners
-                    .iter()
-                    .filter_map(|entry_index| entry_indices.get(*entry_index))
-                    .map(|entry| format!("({}, {})", entry.score.unwrap_or_default(), entry.id))
-                    .collect::<Vec<String>>()
-                    .join(", ");
-
-                let MaybePoint::Valid(_) = locking_point else {
-                    // Something went horribly wrong, use the info from this log line to track refunding users based on DLC expiry (we set to 1 week)
-                    error!("final result doesn't match any of the possible outcomes: event_id {} winners {} expiry {:?}", event.id, winners_str, event.event_announcement.expiry);
-
-                    return Err(Error::OutcomeNotFound(format!(
-                        "event_id {} outcome winners {} expiry {:?}",
-                        event.id, winners_str, event.event_announcement.expiry
-                    )));
-                };
+                return Err(Error::OutcomeNotFound(format!(
+                    "event_id {} outcome winners {} expiry {:?}",
+                    event.id, winners_str, event.event_announcement.expiry
+                )));
+            };
 
-                info!("winners: event_id {} winners {}", event.id, winners_str);
+            info!("winners: event_id {} winners {}", event.id, winners_str);
 
-                let attestation = attestation_secret(self.private_key, event.nonce, &winner_bytes);
-                event.attestation = Some(attestation);
-                self.event_data.update_event_attestation(event).await?;
+            let attestation = attestation_secret(signing_key, event.nonce, &winner_bytes);
+            event.attestation = Some(attestation);
+            if !self.event_data.update_event_attestation(event).await? {
+                return Err(Error::EventLocked(format!(
+                    "event {} was signed or cancelled before its attestation was stored",
+                    event.id
+                )));
+            }
+            self.announcements().remove(&event.id);
+            // The event is already signed, missing accuracy rows shouldn't fail the rest of the etl
+            if let Err(e) = self.add_forecast_accuracy(event).await {
+                error!(
+                    "failed to record forecast accuracy for event_id {}: {}",
+                    event.id, e
+                );
             }
         }
+        Ok(())
+    }
+
+    async fn add_forecast_accuracy(&self, event: &SignEvent) -> Result<(), Error> {
+        let accuracy: Vec<ForecastAccuracy> = self
+            .get_event_weather_diff(&event.id)
+            .await?
+            .into_iter()
+            .filter_map(|diff| ForecastAccuracy::from_diff(event.id, event.observation_date, diff))
+            .collect();
         info!(
-            "completed adding oracle signature to all events that need it in etl process {}",
-            etl_process_id
+            "recording forecast accuracy of {} stations for event_id {}",
+            accuracy.len(),
+            event.id
         );
+        self.event_data
+            .add_forecast_accuracy(&accuracy)
+            .await
+            .map_err(Error::DataQuery)
+    }
+
+    /// How a station's forecasts compared to its observations across every signed event it was part of
+    pub async fn get_station_accuracy(&self, station_id: &str) -> Result<StationAccuracy, Error> {
+        let station_id =
+            normalize_station_id(station_id).map_err(|e| Error::NotFound(e.to_string()))?;
+        let history = self
+            .event_data
+            .get_station_forecast_accuracy(&station_id)
+            .await
+            .map_err(Error::DataQuery)?;
+        Ok(StationAccuracy::new(station_id, history))
+    }
+
+    async fn missing_observation_stations(
+        &self,
+        event: &ActiveEvent,
+    ) -> Result<Vec<String>, Error> {
+        let observation_data = self.event_observation_data(event).await?;
+        let overrides = self
+            .event_data
+            .get_event_weather_overrides(event.id)
+            .await?;
+        Ok(event
+            .locations
+            .iter()
+            .filter(|location| {
+                match overrides
+                    .iter()
+                    .find(|weather| weather.station_id.eq_ignore_ascii_case(location))
+                {
+                    Some(pushed) => pushed.observed.is_none(),
+                    None => !observation_data
+                        .iter()
+                        .any(|observation| observation.station_id.eq_ignore_ascii_case(location)),
+                }
+            })
+            .cloned()
+            .collect())
+    }
+
+    /// Replaces the daemon's readings with the weather operators pushed for the event, a pushed station is
+    /// scored on its pushed reading alone, so one pushed without an observation has none
+    async fn apply_weather_overrides(
+        &self,
+        event: &ActiveEvent,
+        forecasts: &mut HashMap<String, Forecasted>,
+        observations: &mut HashMap<String, Observed>,
+    ) -> Result<(), Error> {
+        for weather in self
+            .event_data
+            .get_event_weather_overrides(event.id)
+            .await?
+        {
+            let Some(location) = event
+                .locations
+                .iter()
+                .find(|location| location.eq_ignore_ascii_case(&weather.station_id))
+            else {
+                continue;
+            };
+            forecasts.insert(location.clone(), weather.forecasted);
+            match weather.observed {
+                Some(observed) => {
+                    observations.insert(location.clone(), observed);
+                }
+                None => {
+                    observations.remove(location);
+                }
+            }
+        }
         Ok(())
     }
 
     async fn event_forecast_data(&self, event: &ActiveEvent) -> Result<Vec<Forecast>, Error> {
-        let start_date = event.observation_date;
+        // Daily forecasts are bucketed by calendar day, so the event's local day is looked up as that day in UTC
+        let start_date = event.observation_day().midnight().assume_utc();
         // Assumes all events are only a day long, may change in the future
-        let end_date = event.observation_date.saturating_add(Duration::days(1));
+        let end_date = start_date.saturating_add(Duration::days(1));
         // Assumes locations have been sanitized when the event was created
         let station_ids = event.locations.join(",");
         let forecast_requests = ForecastRequest {
@@ -690,6 +1937,151 @@ impl Oracle {
     }
 }
 
+/// Scores every pick of an entry against its station's forecasted and observed values, in the order the event's
+/// scoring scheme counts them. Par matches anything within the par tolerance of the forecast, Over/Under only past it.
+/// Picks on a station without a forecast or observation yet are listed without matching
+fn score_entry_picks(
+    locations: &[String],
+    scoring_mode: ScoringMode,
+    entry: &WeatherEntry,
+    forecasts: &HashMap<String, Forecasted>,
+    observations: &HashMap<String, Observed>,
+    par_tolerance: ParTolerance,
+) -> Vec<PickBreakdown> {
+    let mut picks = vec![];
+    for location in locations {
+        let Some(choice) = entry
+            .expected_observations
+            .iter()
+            .find(|expected| expected.stations.eq_ignore_ascii_case(location))
+        else {
+            continue;
+        };
+        let forecast = forecasts.get(location);
+        if forecast.is_none() {
+            warn!("no forecast found for: {}", location);
+        }
+        let observation = observations.get(location);
+        if observation.is_none() {
+            warn!("no observation found for: {}", location);
+        }
+
+        let values = [
+            (
+                Dimension::TempHigh,
+                &choice.temp_high,
+                forecast.map(|forecast| forecast.temp_high),
+                observation.map(|observation| observation.temp_high),
+            ),
+            (
+                Dimension::TempLow,
+                &choice.temp_low,
+                forecast.map(|forecast| forecast.temp_low),
+                observation.map(|observation| observation.temp_low),
+            ),
+            (
+                Dimension::WindSpeed,
+                &choice.wind_speed,
+                forecast.map(|forecast| forecast.wind_speed),
+                observation.map(|observation| observation.wind_speed),
+            ),
+        ];
+        for (dimension, choice, forecasted, observed) in values {
+            let Some(choice) = choice else {
+                continue;
+            };
+            let matched = match (forecasted, observed) {
+                (Some(forecasted), Some(observed)) => {
+                    *choice == ValueOptions::outcome(forecasted, observed, par_tolerance)
+                }
+                _ => false,
+            };
+            picks.push(PickBreakdown {
+                station_id: location.clone(),
+                dimension,
+                choice: choice.clone(),
+                forecasted,
+                observed,
+                matched,
+                points: 0,
+            });
+        }
+    }
+
+    let scored: Vec<ScoredPick> = picks
+        .iter()
+        .map(|pick| ScoredPick {
+            pick: pick.choice.clone(),
+            correct: pick.matched,
+        })
+        .collect();
+    for (pick, points) in picks
+        .iter_mut()
+        .zip(scoring_mode.scheme().pick_points(&scored))
+    {
+        pick.points = points;
+    }
+    picks
+}
+
+/// Lower 4 digits of an entry's score, created_at used as tie breaker (older > newer)
+fn entry_tie_breaker(entry_id: &Uuid) -> u64 {
+    let (created_at_secs, created_at_nano) = entry_id
+        .get_timestamp()
+        .expect("UUIDv7 should have timestamp")
+        .to_unix();
+    let time_millis = (created_at_secs * 1000) + (created_at_nano as u64 / 1_000_000);
+    9999 - (time_millis % 10000)
+}
+
+fn entry_score(base_score: u64, entry_id: &Uuid) -> i64 {
+    /* By adding the time element we are able to make competitions that have 1mil unique possible scores
+    meaning no ties under the following constraints:
+
+    With queue for entries (serialized creation):
+    - Up to 10,000 entries over 24h: negligible collision risk
+    - Max burst: ~40 entries/second with millisecond precision
+
+    Without queue for entries (concurrent creation):
+    - Up to 1,300 entries over 24h: negligible collision risk
+    - Burst limit: ~30 entries/second for < 0.01% collision risk
+
+    This is important for keeping the amount of possible outcomes for the DLC as low as possible
+    but able to scale to as many entries as possible
+    */
+    ((base_score * 10000) + entry_tie_breaker(entry_id)) as i64
+}
+
+// The attestation is already out, changing the weather now wouldn't change who won
+fn check_weather_updatable(event: &Event) -> Result<(), Error> {
+    if event.attestation.is_some() {
+        return Err(Error::EventExpired(format!(
+            "event {} has already been signed, its weather can't be updated",
+            event.id
+        )));
+    }
+    Ok(())
+}
+
+/// Entries that won a place, best score first, threshold events only place entries that reached the threshold
+fn ranked_winners(event: &Event) -> Vec<WeatherEntry> {
+    let mut winners: Vec<WeatherEntry> = event
+        .entries
+        .iter()
+        .filter(|entry| match event.win_mode {
+            WinMode::TopN => true,
+            // Total score includes the time tie breaker in the lower 4 digits
+            WinMode::Threshold => {
+                entry.score.unwrap_or_default() / 10000 >= event.score_threshold.unwrap_or_default()
+            }
+        })
+        .cloned()
+        .collect();
+    winners.sort_by_key(|entry| cmp::Reverse(entry.score));
+    winners.truncate(event.number_of_places_win as usize);
+    winners
+}
+
 pub fn get_winning_bytes(winners: Vec<usize>) -> Vec<u8> {
     winners
         .iter()
@@ -704,14 +2096,11 @@ async fn add_only_forecast_data(
     let mut all_weather: Vec<Weather> = vec![];
 
     for station_id in event.locations.clone() {
-        if let Some(forecast) = forecast_data
-            .iter()
-            .find(|forecast| forecast.station_id == station_id.clone())
-        {
+        if let Some(forecasted) = event_forecast(event, &forecast_data, &station_id)? {
             let weather = Weather {
                 station_id: station_id.clone(),
                 observed: None,
-                forecasted: forecast.try_into().map_err(Error::WeatherData)?,
+                forecasted,
             };
             all_weather.push(weather);
         }
@@ -727,13 +2116,10 @@ async fn add_forecast_data_and_observation_data(
     let mut all_weather: Vec<Weather> = vec![];
 
     for station_id in event.locations.clone() {
-        if let Some(forecast) = forecast_data
-            .iter()
-            .find(|forecast| forecast.station_id == station_id.clone())
-        {
+        if let Some(forecasted) = event_forecast(event, &forecast_data, &station_id)? {
             let weather = if let Some(observation) = observation_data
                 .iter()
-                .find(|observation| observation.station_id == station_id.clone())
+                .find(|observation| observation.station_id.eq_ignore_ascii_case(&station_id))
             {
                 Weather {
                     station_id: station_id.clone(),
@@ -741,13 +2127,13 @@ async fn add_forecast_data_and_observation_data(
                         .try_into()
                         .map(Some)
                         .map_err(Error::WeatherData)?,
-                    forecasted: forecast.try_into().map_err(Error::WeatherData)?,
+                    forecasted,
                 }
             } else {
                 Weather {
                     station_id: station_id.clone(),
                     observed: None,
-                    forecasted: forecast.try_into().map_err(Error::WeatherData)?,
+                    forecasted,
                 }
             };
             all_weather.push(weather);
@@ -756,6 +2142,96 @@ async fn add_forecast_data_and_observation_data(
     Ok(all_weather)
 }
 
+/// The station's forecast for the event's observation day, forecasts for any other day are stale and skipped so they are never scored
+fn event_forecast(
+    event: &ActiveEvent,
+    forecast_data: &[Forecast],
+    station_id: &str,
+) -> Result<Option<Forecasted>, Error> {
+    let observation_date = event.observation_day();
+    for forecast in forecast_data
+        .iter()
+        .filter(|forecast| forecast.station_id.eq_ignore_ascii_case(station_id))
+    {
+        let forecasted: Forecasted = forecast.try_into().map_err(Error::WeatherData)?;
+        if forecasted.date.date() == observation_date {
+            return Ok(Some(forecasted));
+        }
+        warn!(
+            "skipping forecast for station {} in event {}, the forecast is for {} but the observation date is {}",
+            station_id,
+            event.id,
+            forecasted.date.date(),
+            observation_date
+        );
+    }
+    Ok(None)
+}
+
+/// The first of the keys that locks the outcome into one of the announcement's points
+fn find_signing_key<'a>(
+    mut keys: impl Iterator<Item = &'a SecretKey>,
+    nonce: Scalar,
+    event_announcement: &EventLockingConditions,
+    outcome: &[u8],
+) -> Option<SecretKey> {
+    let secp = Secp256k1::new();
+    let nonce_point = nonce.base_point_mul();
+    keys.find(|key| {
+        let locking_point = attestation_locking_point(key.public_key(&secp), nonce_point, outcome);
+        event_announcement.locking_points.contains(&locking_point)
+    })
+    .copied()
+}
+
+/// Regenerates the event's outcome messages, erroring if any of them doesn't lock the matching point of the
+/// stored announcement. `keys` are the oracle's current key followed by its retired ones
+fn build_announcement(event: Event, keys: &[SecretKey]) -> Result<EventAnnouncement, Error> {
+    let outcome_messages = possible_outcome_messages(
+        &event.win_mode,
+        event.total_allowed_entries as usize,
+        event.number_of_places_win as usize,
+    );
+    let mismatch = || {
+        Error::OutcomeNotFound(format!(
+            "outcome messages of event {} don't match its announcement",
+            event.id
+        ))
+    };
+    let locking_points = &event.event_announcement.locking_points;
+    if outcome_messages.len() != locking_points.len() {
+        return Err(mismatch());
+    }
+    // Events announced before a key rotation are attested with the retired key
+    let oracle_key = match outcome_messages.first() {
+        Some(outcome) => {
+            find_signing_key(keys.iter(), event.nonce, &event.event_announcement, outcome)
+                .ok_or_else(|| {
+                    Error::MismatchPubkey(format!("no oracle key announced event {}", event.id))
+                })?
+        }
+        None => keys[0],
+    };
+    let oracle_pubkey = oracle_key.public_key(&Secp256k1::new());
+    let nonce_point = event.nonce.base_point_mul();
+    let matches = outcome_messages
+        .iter()
+        .zip(locking_points)
+        .all(|(outcome, locking_point)| {
+            attestation_locking_point(oracle_pubkey, nonce_point, outcome) == *locking_point
+        });
+    if !matches {
+        return Err(mismatch());
+    }
+    Ok(EventAnnouncement {
+        id: event.id,
+        oracle_pubkey: Point::from(oracle_pubkey),
+        nonce_point,
+        outcome_messages: outcome_messages.iter().map(hex::encode).collect(),
+        event_announcement: event.event_announcement,
+    })
+}
+
 fn get_key(file_path: &String) -> Result<SecretKey, anyhow::Error> {
     if !is_pem_file(file_path) {
         return Err(anyhow!("not a '.pem' file extension"));
@@ -770,6 +2246,14 @@ fn get_key(file_path: &String) -> Result<SecretKey, anyhow::Error> {
     }
 }
 
+// Retired keys are never generated, a missing file means the config is wrong
+fn get_retired_key(file_path: &String) -> Result<SecretKey, anyhow::Error> {
+    if !is_pem_file(file_path) {
+        return Err(anyhow!("not a '.pem' file extension"));
+    }
+    read_key(file_path).map_err(|e| anyhow!("failed to read retired key {}: {}", file_path, e))
+}
+
 fn generate_new_key() -> SecretKey {
     SecretKey::new(&mut rand::thread_rng())
 }
@@ -0,0 +1,106 @@
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Request, State},
+    http::{header::RETRY_AFTER, StatusCode},
+    middleware::Next,
+    response::{IntoResponse, Response},
+};
+use log::warn;
+use std::{
+    collections::HashMap,
+    net::{IpAddr, SocketAddr},
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+// Once this many clients are tracked, expired windows are dropped before adding another
+const PRUNE_THRESHOLD: usize = 10_000;
+
+#[derive(Debug, Clone, Copy)]
+pub struct RateLimit {
+    /// Max number of requests a single ip can make per window
+    pub requests: u32,
+    /// How long until a client's request count resets
+    pub window: Duration,
+}
+
+impl RateLimit {
+    pub fn new(requests: u32, window: Duration) -> Self {
+        Self { requests, window }
+    }
+}
+
+struct Window {
+    started_at: Instant,
+    count: u32,
+}
+
+/// Fixed window request counter per client ip, requests without connect info share a single window
+#[derive(Clone)]
+pub struct RateLimiter {
+    limit: RateLimit,
+    windows: Arc<Mutex<HashMap<Option<IpAddr>, Window>>>,
+}
+
+impl RateLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            windows: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Counts the request against the client, returns how long until it can retry if over the limit
+    pub fn check(&self, client: Option<IpAddr>) -> Result<(), Duration> {
+        let now = Instant::now();
+        let mut windows = self.windows.lock().unwrap_or_else(|e| e.into_inner());
+        if windows.len() >= PRUNE_THRESHOLD && !windows.contains_key(&client) {
+            windows.retain(|_, window| now.duration_since(window.started_at) < self.limit.window);
+        }
+
+        let window = windows.entry(client).or_insert(Window {
+            started_at: now,
+            count: 0,
+        });
+        let elapsed = now.duration_since(window.started_at);
+        if elapsed >= self.limit.window {
+            window.started_at = now;
+            window.count = 0;
+        }
+        if window.count >= self.limit.requests {
+            return Err(self.limit.window - now.duration_since(window.started_at));
+        }
+        window.count += 1;
+        Ok(())
+    }
+}
+
+pub async fn rate_limit(
+    State(limiter): State<RateLimiter>,
+    request: Request<Body>,
+    next: Next,
+) -> Response {
+    let client = request
+        .extensions()
+        .get::<ConnectInfo<SocketAddr>>()
+        .map(|ConnectInfo(addr)| addr.ip());
+    match limiter.check(client) {
+        Ok(()) => next.run(request).await,
+        Err(retry_after) => {
+            warn!(
+                "rate limit hit by {}",
+                client
+                    .map(|ip| ip.to_string())
+                    .unwrap_or(String::from("unknown client"))
+            );
+            // Retry-After is in whole seconds, round up so clients don't retry too early
+            let retry_after_secs =
+                retry_after.as_secs() + u64::from(retry_after.subsec_nanos() > 0);
+            (
+                StatusCode::TOO_MANY_REQUESTS,
+                [(RETRY_AFTER, retry_after_secs.max(1).to_string())],
+            )
+                .into_response()
+        }
+    }
+}
@@ -1,20 +1,30 @@
 use crate::{
-    oracle, AddEventEntry, AppState, CreateEvent, Event, EventFilter, EventSummary, NostrAuth,
-    WeatherEntry,
+    is_database_locked, oracle, weather_data, AddEventEntry, AppState, CloneEvent, CreateEvent,
+    EntryBreakdown, Event, EventAnnouncement, EventCapacity, EventDimensions, EventFilter,
+    EventOutcomeMessages, EventOutcomeWinners, EventPayouts, EventSignStatus, EventSummary,
+    NostrAuth, OutcomeEncoding, OutcomeWinnersFilter, PayoutSplit, SignStatusFilter,
+    SigningQueueStatus, StationAccuracy, UpdateEvent, Weather, WeatherDiff, WeatherEntry,
+    WeatherParquetError,
 };
 use axum::{
-    extract::{Path, Query, State},
-    http::StatusCode,
+    body::Bytes,
+    extract::{rejection::JsonRejection, Path, Query, State},
+    http::{HeaderValue, StatusCode},
     response::{ErrorResponse, IntoResponse, Response},
     Json,
 };
+use hyper::{
+    header::{CONTENT_DISPOSITION, CONTENT_TYPE, RETRY_AFTER},
+    HeaderMap,
+};
 use log::{error, info};
+use nostr_sdk::PublicKey as NostrPublicKey;
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 use serde_json::json;
 use std::{borrow::Borrow, sync::Arc};
 use tokio::task;
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 use uuid::Uuid;
 
 #[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
@@ -57,6 +67,38 @@ pub async fn get_npub(State(state): State<Arc<AppState>>) -> Result<Json<Pubkey>
     }))
 }
 
+#[utoipa::path(
+    get,
+    path = "/oracle/signing/queue",
+    responses(
+        (status = OK, description = "Successfully retrieved how many events are being signed and waiting to be signed", body = SigningQueueStatus),
+    ))]
+pub async fn get_signing_queue(State(state): State<Arc<AppState>>) -> Json<SigningQueueStatus> {
+    Json(state.oracle.signing_queue_status())
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/sign-status",
+    params(SignStatusFilter),
+    responses(
+        (status = OK, description = "Successfully retrieved the events that are waiting to be signed and the ones that are signed", body = EventSignStatus),
+    ))]
+pub async fn get_sign_status(
+    State(state): State<Arc<AppState>>,
+    Query(filter): Query<SignStatusFilter>,
+) -> Result<Json<EventSignStatus>, ErrorResponse> {
+    state
+        .oracle
+        .get_sign_status(filter)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting events sign status: {}", e);
+            e.into()
+        })
+}
+
 #[utoipa::path(
     get,
     path = "/oracle/events",
@@ -104,6 +146,98 @@ pub async fn create_event(
         })
 }
 
+#[utoipa::path(
+    post,
+    path = "/oracle/events/{event_id}/clone",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of the weather event to copy the locations and parameters from"),
+    ),
+    request_body = CloneEvent,
+    responses(
+        (status = OK, description = "Successfully created oracle weather event from the existing event", body = Event),
+        (status = BAD_REQUEST, description = "Invalid event to be created"),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+        (status = FORBIDDEN, description = "Invalid signature from coordinator in nostr authorization header"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using coordinator keys"),
+    ))]
+pub async fn clone_event(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Json(body): Json<CloneEvent>,
+) -> Result<Json<Event>, ErrorResponse> {
+    state
+        .oracle
+        .clone_event(pubkey, &event_id, body)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error cloning event {}: {}", event_id, e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/oracle/events/{event_id}/cancel",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of the weather event to cancel"),
+    ),
+    responses(
+        (status = OK, description = "Successfully cancelled the oracle weather event, it will never be signed", body = Event),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+        (status = GONE, description = "Event has already been signed and can't be cancelled"),
+        (status = FORBIDDEN, description = "Only the coordinator of the event can cancel it"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using coordinator keys"),
+    ))]
+pub async fn cancel_event(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<Event>, ErrorResponse> {
+    state
+        .oracle
+        .cancel_event(pubkey, &event_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error cancelling event {}: {}", event_id, e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    patch,
+    path = "/oracle/events/{event_id}",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of the weather event to update"),
+    ),
+    request_body = UpdateEvent,
+    responses(
+        (status = OK, description = "Successfully updated the event's outcome parameters, it has a new nonce and announcement", body = Event),
+        (status = BAD_REQUEST, description = "Invalid outcome parameters for the event"),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+        (status = CONFLICT, description = "Event isn't live or already has entries, its outcomes can't change"),
+        (status = FORBIDDEN, description = "Only the coordinator of the event can update it"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using coordinator keys"),
+    ))]
+pub async fn update_event(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Json(body): Json<UpdateEvent>,
+) -> Result<Json<Event>, ErrorResponse> {
+    state
+        .oracle
+        .update_event(pubkey, &event_id, body)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error updating event {}: {}", event_id, e);
+            e.into()
+        })
+}
+
 #[utoipa::path(
     get,
     path = "/oracle/events/{event_id}",
@@ -129,6 +263,320 @@ pub async fn get_event(
         })
 }
 
+#[derive(Debug, Clone, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum AnnouncementEncoding {
+    #[default]
+    Json,
+    /// bincode encoding of the same announcement
+    Binary,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct AnnouncementParams {
+    /// How the announcement is serialized (default: json)
+    pub encoding: Option<AnnouncementEncoding>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/announcement",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+        AnnouncementParams,
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved the event announcement", content(
+            (EventAnnouncement = "application/json"),
+            (Vec<u8> = "application/octet-stream"),
+        )),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_announcement(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Query(params): Query<AnnouncementParams>,
+) -> Result<Response, ErrorResponse> {
+    match params.encoding.unwrap_or_default() {
+        AnnouncementEncoding::Json => state
+            .oracle
+            .get_event_announcement(&event_id)
+            .await
+            .map(|announcement| Json(announcement.as_ref()).into_response())
+            .map_err(|e| {
+                error!("error getting event announcement: {}", e);
+                e.into()
+            }),
+        AnnouncementEncoding::Binary => {
+            let announcement = state
+                .oracle
+                .get_event_announcement_bytes(&event_id)
+                .await
+                .map_err(|e| {
+                    error!("error getting event announcement: {}", e);
+                    e
+                })?;
+            let mut headers = HeaderMap::new();
+            headers.insert(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/octet-stream"),
+            );
+            Ok((headers, announcement).into_response())
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct OutcomeParams {
+    /// How each outcome message is encoded (default: hex)
+    pub encoding: Option<OutcomeEncoding>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/outcomes",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+        OutcomeParams,
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved the outcome messages the oracle will sign one of", body = EventOutcomeMessages),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_outcome_messages(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Query(params): Query<OutcomeParams>,
+) -> Result<Json<EventOutcomeMessages>, ErrorResponse> {
+    state
+        .oracle
+        .get_event_outcome_messages(&event_id, params.encoding.unwrap_or_default())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event outcome messages: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/outcomes/winners",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+        OutcomeWinnersFilter,
+    ),
+    responses(
+        (status = OK, description = "Successfully mapped each outcome that can happen with the current entries to its winning entries", body = EventOutcomeWinners),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_outcome_winners(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Query(filter): Query<OutcomeWinnersFilter>,
+) -> Result<Json<EventOutcomeWinners>, ErrorResponse> {
+    state
+        .oracle
+        .get_event_outcome_winners(&event_id, filter)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event outcome winners: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/dimensions",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved the weather values entries of the event can pick on", body = EventDimensions),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_dimensions(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<EventDimensions>, ErrorResponse> {
+    state
+        .oracle
+        .get_event_dimensions(&event_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event dimensions: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/capacity",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved how many more entries the event can take", body = EventCapacity),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_capacity(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<EventCapacity>, ErrorResponse> {
+    state
+        .oracle
+        .get_event_capacity(&event_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event capacity: {}", e);
+            e.into()
+        })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct PayoutParams {
+    /// Percent paid to each place separated by commas, first place first and adding up to 100, ie. `60,30,10` (default: an even split)
+    pub split: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/payouts",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+        PayoutParams,
+    ),
+    responses(
+        (status = OK, description = "Successfully computed the payout of each winning place", body = EventPayouts),
+        (status = BAD_REQUEST, description = "Event isn't signed yet or the split is invalid"),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_payouts(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    Query(params): Query<PayoutParams>,
+) -> Result<Json<EventPayouts>, ErrorResponse> {
+    let split = params
+        .split
+        .as_deref()
+        .map(PayoutSplit::try_from)
+        .transpose()
+        .map_err(|e| oracle::Error::BadPayoutSplit(e.to_string()))?;
+    state
+        .oracle
+        .get_event_payouts(&event_id, split)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event payouts: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/weather/diff",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully compared the observed and forecasted weather for each station", body = Vec<WeatherDiff>),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_weather_diff(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<Json<Vec<WeatherDiff>>, ErrorResponse> {
+    state
+        .oracle
+        .get_event_weather_diff(&event_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting event weather diff: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/weather.parquet",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved all weather for the event", content_type = "application/parquet", body = Vec<u8>),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_weather_parquet(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<(HeaderMap, Vec<u8>), ErrorResponse> {
+    let parquet = state
+        .oracle
+        .get_event_weather_parquet(&event_id)
+        .await
+        .map_err(|e| {
+            error!("error getting event weather parquet: {}", e);
+            e
+        })?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("application/parquet"),
+    );
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"{}_weather.parquet\"",
+            event_id
+        ))
+        .unwrap(),
+    );
+    Ok((headers, parquet))
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/entries.csv",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved the event's entries with a row per entry and station", content_type = "text/csv", body = String),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+    ))]
+pub async fn get_event_entries_csv(
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+) -> Result<(HeaderMap, String), ErrorResponse> {
+    let csv = state
+        .oracle
+        .get_event_entries_csv(&event_id)
+        .await
+        .map_err(|e| {
+            error!("error getting event entries csv: {}", e);
+            e
+        })?;
+    let mut headers = HeaderMap::new();
+    headers.insert(
+        CONTENT_TYPE,
+        HeaderValue::from_static("text/csv; charset=utf-8"),
+    );
+    headers.insert(
+        CONTENT_DISPOSITION,
+        HeaderValue::from_str(&format!(
+            "attachment; filename=\"{}_entries.csv\"",
+            event_id
+        ))
+        .unwrap(),
+    );
+    Ok((headers, csv))
+}
+
 #[utoipa::path(
     post,
     path = "/oracle/events/{event_id}/entry",
@@ -136,6 +584,7 @@ pub async fn get_event(
     responses(
         (status = OK, description = "Successfully add entry into oracle weather event", body = WeatherEntry),
         (status = BAD_REQUEST, description = "Invalid entry to be created"),
+        (status = GONE, description = "Event is no longer taking entries, the observation window started or it's already signed"),
         (status = FORBIDDEN, description = "Invalid signature from coordinator in nostr authorization header"),
         (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using coordinator keys"),
     ))]
@@ -143,8 +592,13 @@ pub async fn add_event_entry(
     NostrAuth { pubkey, .. }: NostrAuth,
     State(state): State<Arc<AppState>>,
     Path(_event_id): Path<Uuid>,
-    Json(body): Json<AddEventEntry>,
+    body: Result<Json<AddEventEntry>, JsonRejection>,
 ) -> Result<Json<WeatherEntry>, ErrorResponse> {
+    // Reject unknown weather choices (ie. "ovr") as a bad entry instead of axum's default 422
+    let Json(body) = body.map_err(|e| {
+        error!("error parsing entry to event: {}", e);
+        oracle::Error::BadEntry(e.body_text())
+    })?;
     state
         .oracle
         .add_event_entry(pubkey, body)
@@ -182,6 +636,165 @@ pub async fn get_event_entry(
         })
 }
 
+#[utoipa::path(
+    get,
+    path = "/oracle/events/{event_id}/entry/{entry_id}/breakdown",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+        ("entry_id" = Uuid, Path, description = "ID of a entry into weather event the oracle is tracking"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved how each pick of the entry scored", body = EntryBreakdown),
+        (status = NOT_FOUND, description = "Event or event entry not found for the provided IDs"),
+    ))]
+pub async fn get_entry_breakdown(
+    State(state): State<Arc<AppState>>,
+    Path((event_id, entry_id)): Path<(Uuid, Uuid)>,
+) -> Result<Json<EntryBreakdown>, ErrorResponse> {
+    state
+        .oracle
+        .get_entry_breakdown(&event_id, &entry_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting entry breakdown: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/oracle/events/{event_id}/weather",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    request_body = Vec<Weather>,
+    responses(
+        (status = OK, description = "Successfully stored the weather, returns the updated event", body = Event),
+        (status = BAD_REQUEST, description = "Invalid weather or a station that isn't one of the event's locations"),
+        (status = FORBIDDEN, description = "Nostr authorization header isn't signed by an operator key"),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+        (status = GONE, description = "Event is already signed"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using operator keys"),
+    ))]
+pub async fn update_event_weather(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    body: Result<Json<Vec<Weather>>, JsonRejection>,
+) -> Result<Json<Event>, ErrorResponse> {
+    let Json(weather) = body.map_err(|e| {
+        error!("error parsing event weather: {}", e);
+        oracle::Error::BadEvent(anyhow::anyhow!(e.body_text()))
+    })?;
+    state
+        .oracle
+        .update_event_weather(pubkey, &event_id, weather)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error updating event weather: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    post,
+    path = "/oracle/events/{event_id}/weather.parquet",
+    params(
+        ("event_id" = Uuid, Path, description = "ID of a weather event the oracle is tracking"),
+    ),
+    request_body(
+        content = Vec<u8>,
+        content_type = "application/parquet",
+        description = "One row per station with station_id, forecast_date, forecast_start_time, forecast_end_time, forecast_temp_low, forecast_temp_high, forecast_wind_speed and the nullable observation_start_time, observation_end_time, observation_temp_low, observation_temp_high, observation_wind_speed columns"
+    ),
+    responses(
+        (status = OK, description = "Successfully stored the weather, returns the updated event", body = Event),
+        (status = BAD_REQUEST, description = "Unreadable parquet, invalid weather or a station that isn't one of the event's locations"),
+        (status = FORBIDDEN, description = "Nostr authorization header isn't signed by an operator key"),
+        (status = NOT_FOUND, description = "Event not found for the provided ID"),
+        (status = GONE, description = "Event is already signed"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using operator keys"),
+    ))]
+pub async fn update_event_weather_parquet(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Path(event_id): Path<Uuid>,
+    body: Bytes,
+) -> Result<Json<Event>, ErrorResponse> {
+    state
+        .oracle
+        .update_event_weather_parquet(pubkey, &event_id, body.to_vec())
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error updating event weather from parquet: {}", e);
+            e.into()
+        })
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/stations/{station_id}/accuracy",
+    params(
+        ("station_id" = String, Path, description = "NOAA station id, ie. KSAW"),
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved how the station's forecasts compared to its observations in signed events", body = StationAccuracy),
+        (status = NOT_FOUND, description = "Invalid station id"),
+    ))]
+pub async fn get_station_accuracy(
+    State(state): State<Arc<AppState>>,
+    Path(station_id): Path<String>,
+) -> Result<Json<StationAccuracy>, ErrorResponse> {
+    state
+        .oracle
+        .get_station_accuracy(&station_id)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting station accuracy: {}", e);
+            e.into()
+        })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct CoordinatorEntriesParams {
+    /// nostr pubkey (npub or hex) of the coordinator, must match the nostr authorization header
+    pub coordinator: String,
+}
+
+#[utoipa::path(
+    get,
+    path = "/oracle/entries",
+    params(CoordinatorEntriesParams),
+    responses(
+        (status = OK, description = "Successfully retrieved the coordinator's entries across all events", body = Vec<WeatherEntry>),
+        (status = BAD_REQUEST, description = "Invalid coordinator pubkey"),
+        (status = FORBIDDEN, description = "Coordinator pubkey doesn't match the nostr authorization header"),
+        (status = UNAUTHORIZED, description = "Invalid nostr authorization header nip-98 using coordinator keys"),
+    ))]
+pub async fn get_coordinator_entries(
+    NostrAuth { pubkey, .. }: NostrAuth,
+    State(state): State<Arc<AppState>>,
+    Query(params): Query<CoordinatorEntriesParams>,
+) -> Result<Json<Vec<WeatherEntry>>, ErrorResponse> {
+    let coordinator = NostrPublicKey::parse(&params.coordinator).map_err(|e| {
+        error!("error parsing coordinator pubkey: {}", e);
+        oracle::Error::BadEntry(format!("invalid coordinator pubkey: {}", e))
+    })?;
+    state
+        .oracle
+        .get_coordinator_entries(pubkey, coordinator)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error getting coordinator entries: {}", e);
+            e.into()
+        })
+}
+
 #[utoipa::path(
     post,
     path = "/oracle/update",
@@ -204,14 +817,58 @@ pub async fn update_data(State(state): State<Arc<AppState>>) -> Result<StatusCod
     Ok(StatusCode::OK)
 }
 
+// How long clients are told to back off when the event database is locked by another connection
+pub const DATABASE_LOCKED_RETRY_AFTER_SECS: u64 = 5;
+// How long clients are told to back off when the weather data source keeps failing
+pub const WEATHER_UNAVAILABLE_RETRY_AFTER_SECS: u64 = 30;
+
+pub fn service_unavailable(error_message: String, retry_after_secs: u64) -> Response {
+    let body = Json(json!({
+        "error": error_message,
+    }));
+    (
+        StatusCode::SERVICE_UNAVAILABLE,
+        [(RETRY_AFTER, retry_after_secs.to_string())],
+        body,
+    )
+        .into_response()
+}
+
 impl IntoResponse for oracle::Error {
     fn into_response(self) -> Response {
+        match self.borrow() {
+            oracle::Error::DataQuery(e) if is_database_locked(e) => {
+                return service_unavailable(
+                    String::from("database is busy, try again later"),
+                    DATABASE_LOCKED_RETRY_AFTER_SECS,
+                )
+            }
+            oracle::Error::WeatherParquet(WeatherParquetError::Query(e))
+                if is_database_locked(e) =>
+            {
+                return service_unavailable(
+                    String::from("database is busy, try again later"),
+                    DATABASE_LOCKED_RETRY_AFTER_SECS,
+                )
+            }
+            oracle::Error::WeatherData(weather_data::Error::Unavailable(_)) => {
+                return service_unavailable(self.to_string(), WEATHER_UNAVAILABLE_RETRY_AFTER_SECS)
+            }
+            _ => {}
+        }
         let (status, error_message) = match self.borrow() {
             oracle::Error::NotFound(_) => (StatusCode::NOT_FOUND, self.to_string()),
             oracle::Error::MinOutcome(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             oracle::Error::EventMaturity(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             oracle::Error::BadEntry(_) => (StatusCode::BAD_REQUEST, self.to_string()),
             oracle::Error::BadEvent(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            oracle::Error::BadPayoutSplit(_) => (StatusCode::BAD_REQUEST, self.to_string()),
+            oracle::Error::WeatherParquet(
+                WeatherParquetError::Unreadable(_) | WeatherParquetError::Invalid(_),
+            ) => (StatusCode::BAD_REQUEST, self.to_string()),
+            oracle::Error::EventExpired(_) => (StatusCode::GONE, self.to_string()),
+            oracle::Error::Forbidden(_) => (StatusCode::FORBIDDEN, self.to_string()),
+            oracle::Error::EventLocked(_) => (StatusCode::CONFLICT, self.to_string()),
             _ => (
                 StatusCode::INTERNAL_SERVER_ERROR,
                 String::from("internal server error"),
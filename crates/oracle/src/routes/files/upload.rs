@@ -2,11 +2,20 @@ use axum::{
     extract::{Multipart, Path, State},
     http::StatusCode,
 };
-use log::{error, info};
-use std::sync::Arc;
+use duckdb::Connection;
+use log::{error, info, warn};
+use std::{io::ErrorKind, path::PathBuf, sync::Arc};
 use tokio::{fs::File, io::AsyncWriteExt};
+use uuid::Uuid;
 
-use crate::AppState;
+use crate::{
+    adapt_parquet_file, compare_parquet_schema, AppState, SchemaDriftPolicy, WeatherFileKind,
+};
+
+// The whole body is written to a single file, so only the file field itself is accepted
+const MAX_UPLOAD_FIELDS: usize = 1;
+// Matches the router's body limit
+const MAX_UPLOAD_BYTES: usize = 30 * 1024 * 1024;
 
 #[utoipa::path(
     post,
@@ -16,7 +25,8 @@ use crate::AppState;
     ),
     responses(
         (status = OK, description = "Successfully uploaded weather data file"),
-        (status = BAD_REQUEST, description = "Invalid file"),
+        (status = BAD_REQUEST, description = "Invalid file, or its columns don't match the expected schema and the schema drift policy is reject"),
+        (status = PAYLOAD_TOO_LARGE, description = "Upload has too many fields or is too large"),
         (status = INTERNAL_SERVER_ERROR, description = "Failed to save file")
     ))]
 pub async fn upload(
@@ -27,40 +37,194 @@ pub async fn upload(
     if !path_is_valid(&file_name) {
         return Err((StatusCode::BAD_REQUEST, "Invalid file".to_owned()));
     }
-    while let Some(field) = multipart.next_field().await.unwrap() {
-        let data = field.bytes().await.map_err(|err| {
+    let mut field_count = 0;
+    let mut total_bytes = 0;
+    let mut file_data = None;
+    while let Some(mut field) = multipart.next_field().await.map_err(|err| {
+        error!("error getting multipart field: {}", err);
+        (err.status(), err.body_text())
+    })? {
+        field_count += 1;
+        if field_count > MAX_UPLOAD_FIELDS {
+            error!(
+                "upload of `{}` has more than {} fields",
+                file_name, MAX_UPLOAD_FIELDS
+            );
+            return Err((
+                StatusCode::PAYLOAD_TOO_LARGE,
+                format!("Upload can't have more than {} fields", MAX_UPLOAD_FIELDS),
+            ));
+        }
+        let mut data = Vec::new();
+        while let Some(chunk) = field.chunk().await.map_err(|err| {
             error!("error getting file's bytes: {}", err);
-            (
-                StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to get file's bytes: {}", err),
-            )
-        })?;
+            (err.status(), format!("Failed to get file's bytes: {}", err))
+        })? {
+            total_bytes += chunk.len();
+            if total_bytes > MAX_UPLOAD_BYTES {
+                error!(
+                    "upload of `{}` is larger than {} mb",
+                    file_name,
+                    bytes_to_mb(MAX_UPLOAD_BYTES)
+                );
+                return Err((
+                    StatusCode::PAYLOAD_TOO_LARGE,
+                    format!(
+                        "Upload can't be larger than {} mb",
+                        bytes_to_mb(MAX_UPLOAD_BYTES)
+                    ),
+                ));
+            }
+            data.extend_from_slice(&chunk);
+        }
+        file_data = Some(data);
+    }
+
+    let Some(data) = file_data else {
+        return Ok(());
+    };
+    info!(
+        "length of `{}` is {} mb",
+        file_name,
+        bytes_to_mb(data.len())
+    );
+    let current_folder = state.file_access.current_folder();
+    let path = std::path::Path::new(&current_folder).join(&file_name);
+    let tmp_path = upload_tmp_path(&path, &file_name);
+    let saved = save_upload(
+        &tmp_path,
+        &path,
+        &file_name,
+        data,
+        state.schema_drift_policy,
+    )
+    .await;
+    if saved.is_err() {
+        if let Err(err) = tokio::fs::remove_file(&tmp_path).await {
+            if err.kind() != ErrorKind::NotFound {
+                error!("error removing upload {}: {}", tmp_path.display(), err);
+            }
+        }
+    }
+    saved
+}
 
-        info!(
-            "length of `{}` is {} mb",
-            file_name,
-            bytes_to_mb(data.len())
-        );
-        let current_folder = state.file_access.current_folder();
-        let path = std::path::Path::new(&current_folder).join(&file_name);
-        // Create a new file and write the data to it
-        let mut file = File::create(&path).await.map_err(|err| {
-            error!("error creating file: {}", err);
+// Weather queries only read the files inside the data folder's date folders, so a file written next to
+// them is never read while it's being written or checked, and a rejected one is never read at all
+fn upload_tmp_path(path: &std::path::Path, file_name: &str) -> PathBuf {
+    let data_folder = path
+        .parent()
+        .and_then(|date_folder| date_folder.parent())
+        .unwrap_or(std::path::Path::new("."));
+    data_folder.join(format!(".{}.{}.uploading", file_name, Uuid::now_v7()))
+}
+
+/// Writes the upload to `tmp_path` and checks its schema there, it's only moved to `path` once it's
+/// accepted
+async fn save_upload(
+    tmp_path: &std::path::Path,
+    path: &std::path::Path,
+    file_name: &str,
+    data: Vec<u8>,
+    policy: SchemaDriftPolicy,
+) -> Result<(), (StatusCode, String)> {
+    // Create a new file and write the data to it
+    let mut file = File::create(tmp_path).await.map_err(|err| {
+        error!("error creating file: {}", err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to create file: {}", err),
+        )
+    })?;
+    file.write_all(&data).await.map_err(|err| {
+        error!("error creating file: {}", err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write to file: {}", err),
+        )
+    })?;
+    file.flush().await.map_err(|err| {
+        error!("error creating file: {}", err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to write to file: {}", err),
+        )
+    })?;
+    drop(file);
+
+    if let Some(kind) = WeatherFileKind::from_file_name(file_name) {
+        let checked_path = tmp_path.to_string_lossy().to_string();
+        let checked_file_name = file_name.to_owned();
+        tokio::task::spawn_blocking(move || {
+            check_schema(&checked_path, &checked_file_name, kind, policy)
+        })
+        .await
+        .map_err(|err| {
+            error!("error checking file schema: {}", err);
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to create file: {}", err),
+                format!("Failed to check file schema: {}", err),
             )
-        })?;
-        file.write_all(&data).await.map_err(|err| {
-            error!("error creating file: {}", err);
+        })??;
+    }
+
+    tokio::fs::rename(tmp_path, path).await.map_err(|err| {
+        error!("error moving upload into {}: {}", path.display(), err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to save file: {}", err),
+        )
+    })
+}
+
+/// Compares the uploaded file's columns with the ones expected for its kind, logging any drift and
+/// then adapting or rejecting the file based on the policy
+fn check_schema(
+    path: &str,
+    file_name: &str,
+    kind: WeatherFileKind,
+    policy: SchemaDriftPolicy,
+) -> Result<(), (StatusCode, String)> {
+    let conn = Connection::open_in_memory().map_err(|err| {
+        error!("error opening duckdb to check file schema: {}", err);
+        (
+            StatusCode::INTERNAL_SERVER_ERROR,
+            format!("Failed to check file schema: {}", err),
+        )
+    })?;
+    let drift = compare_parquet_schema(&conn, path, kind).map_err(|err| {
+        error!("error reading schema of `{}`: {}", file_name, err);
+        (
+            StatusCode::BAD_REQUEST,
+            format!("Failed to read file as parquet: {}", err),
+        )
+    })?;
+    if drift.is_empty() {
+        return Ok(());
+    }
+    warn!(
+        "schema drift in `{}`: kind={} policy={} missing={:?} extra={:?}",
+        file_name, kind, policy, drift.missing, drift.extra
+    );
+    if drift.missing.is_empty() {
+        return Ok(());
+    }
+    match policy {
+        SchemaDriftPolicy::Reject => Err((
+            StatusCode::BAD_REQUEST,
+            format!("{} file doesn't match the expected schema, {}", kind, drift),
+        )),
+        SchemaDriftPolicy::Adapt => adapt_parquet_file(&conn, path, kind, &drift).map_err(|err| {
+            error!(
+                "error adapting `{}` to the expected schema: {}",
+                file_name, err
+            );
             (
                 StatusCode::INTERNAL_SERVER_ERROR,
-                format!("Failed to write to file: {}", err),
+                format!("Failed to adapt file to the expected schema: {}", err),
             )
-        })?;
+        }),
     }
-
-    Ok(())
 }
 
 fn bytes_to_mb(bytes: usize) -> f64 {
@@ -0,0 +1,45 @@
+use axum::{
+    http::{header::CONTENT_TYPE, StatusCode, Uri},
+    response::{IntoResponse, Response},
+};
+use std::path::Path;
+
+// Generated by build.rs from the files in the `ui` folder
+include!(concat!(env!("OUT_DIR"), "/ui_assets.rs"));
+
+fn get_file(path: &str) -> Option<(&'static str, &'static [u8])> {
+    UI_ASSETS
+        .iter()
+        .find(|(file_path, _)| *file_path == path)
+        .copied()
+}
+
+pub fn embedded_index() -> Option<&'static str> {
+    get_file("index.html").and_then(|(_, contents)| std::str::from_utf8(contents).ok())
+}
+
+// Matches the on disk ServeDir, unknown paths get the index page
+pub async fn embedded_ui_handler(uri: Uri) -> Response {
+    let path = uri.path().trim_start_matches('/');
+    match get_file(path).or_else(|| get_file("index.html")) {
+        Some((file_path, contents)) => {
+            ([(CONTENT_TYPE, content_type(file_path))], contents).into_response()
+        }
+        None => StatusCode::NOT_FOUND.into_response(),
+    }
+}
+
+fn content_type(file_path: &str) -> &'static str {
+    match Path::new(file_path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+    {
+        Some("html") => "text/html; charset=utf-8",
+        Some("js") => "application/javascript; charset=utf-8",
+        Some("css") => "text/css; charset=utf-8",
+        Some("json") => "application/json",
+        Some("svg") => "image/svg+xml",
+        Some("png") => "image/png",
+        _ => "application/octet-stream",
+    }
+}
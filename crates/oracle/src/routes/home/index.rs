@@ -1,18 +1,43 @@
 use std::sync::Arc;
 
 use axum::{extract::State, response::Html};
+use log::warn;
 use tokio::fs;
 
 use crate::AppState;
 
+// Served when the ui directory hasn't been generated, so `/` still points people at the api docs
+const FALLBACK_INDEX: &str = r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="utf-8">
+    <title>4casttruth oracle</title>
+</head>
+<body>
+    <h1>4casttruth oracle</h1>
+    <p>The UI is not available on this server, the api docs are at <a href="{SERVER_ADDRESS}/docs">{SERVER_ADDRESS}/docs</a></p>
+</body>
+</html>
+"#;
+
 pub async fn index_handler(State(state): State<Arc<AppState>>) -> Html<String> {
     Html(index(&state.remote_url, &state.ui_dir).await)
 }
 
 pub async fn index(remote_url: &str, ui_dir: &str) -> String {
-    let file_content = fs::read_to_string(&format!("{}/index.html", ui_dir))
-        .await
-        .expect("Unable to read index.html");
+    #[cfg(feature = "embed-ui")]
+    if let Some(file_content) = super::embedded_index() {
+        return file_content.replace("{SERVER_ADDRESS}", remote_url);
+    }
+
+    let file_path = format!("{}/index.html", ui_dir);
+    let file_content = match fs::read_to_string(&file_path).await {
+        Ok(file_content) => file_content,
+        Err(e) => {
+            warn!("unable to read {}, serving fallback page: {}", file_path, e);
+            String::from(FALLBACK_INDEX)
+        }
+    };
 
     file_content.replace("{SERVER_ADDRESS}", remote_url)
 }
@@ -1,3 +1,7 @@
+#[cfg(feature = "embed-ui")]
+mod embedded;
 mod index;
 
+#[cfg(feature = "embed-ui")]
+pub use embedded::*;
 pub use index::*;
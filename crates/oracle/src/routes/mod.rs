@@ -1,9 +1,13 @@
 pub mod events;
 pub mod files;
 mod home;
+pub mod schema;
 pub mod stations;
+pub mod version;
 
 pub use events::*;
 pub use files::*;
 pub use home::*;
+pub use schema::*;
 pub use stations::*;
+pub use version::*;
@@ -0,0 +1,3 @@
+pub mod request_bodies;
+
+pub use request_bodies::*;
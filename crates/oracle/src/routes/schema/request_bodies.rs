@@ -0,0 +1,41 @@
+use crate::{AddEventEntry, CreateEvent};
+use axum::Json;
+use serde_json::{json, Map, Value};
+use utoipa::ToSchema;
+
+/// Builds the json schema of a request body, nested types are included under `components.schemas`
+/// so the `$ref`s in the schema resolve against the returned document
+pub fn request_body_schema<T: ToSchema>() -> Value {
+    let mut nested = vec![];
+    T::schemas(&mut nested);
+    let components: Map<String, Value> = nested
+        .into_iter()
+        .map(|(name, schema)| (name, json!(schema)))
+        .collect();
+
+    let mut schema = json!(T::schema());
+    if let Value::Object(fields) = &mut schema {
+        fields.insert(String::from("components"), json!({ "schemas": components }));
+    }
+    schema
+}
+
+#[utoipa::path(
+    get,
+    path = "/schema/create-event",
+    responses(
+        (status = OK, description = "Successfully retrieved the json schema of the create event request body", body = serde_json::Value),
+    ))]
+pub async fn create_event_schema() -> Json<Value> {
+    Json(request_body_schema::<CreateEvent>())
+}
+
+#[utoipa::path(
+    get,
+    path = "/schema/add-entry",
+    responses(
+        (status = OK, description = "Successfully retrieved the json schema of the add event entry request body", body = serde_json::Value),
+    ))]
+pub async fn add_entry_schema() -> Json<Value> {
+    Json(request_body_schema::<AddEventEntry>())
+}
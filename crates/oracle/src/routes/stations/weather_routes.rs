@@ -2,14 +2,19 @@ use std::sync::Arc;
 
 use ::serde::Deserialize;
 use axum::{
-    extract::{Query, State},
+    extract::{Path, Query, State},
+    response::ErrorResponse,
     Json,
 };
+use log::error;
 use serde::Serialize;
 use time::OffsetDateTime;
 use utoipa::IntoParams;
 
-use crate::{AppError, AppState, FileParams, Forecast, Observation, Station};
+use crate::{
+    oracle::Error, ActiveStation, AppError, AppState, EventStatus, EventSummary, FileParams,
+    Forecast, Observation, Station, StoredStation,
+};
 
 #[utoipa::path(
     get,
@@ -20,7 +25,8 @@ use crate::{AppError, AppState, FileParams, Forecast, Observation, Station};
     responses(
         (status = OK, description = "Successfully retrieved forecast data", body = Vec<Forecast>),
         (status = BAD_REQUEST, description = "Times are not in RFC3339 format"),
-        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather data")
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather data"),
+        (status = SERVICE_UNAVAILABLE, description = "Weather data source is failing, retry after the Retry-After header seconds")
     ))]
 pub async fn forecasts(
     State(state): State<Arc<AppState>>,
@@ -49,7 +55,8 @@ impl ForecastRequest {
     pub fn station_ids(&self) -> Vec<String> {
         self.station_ids
             .split(',')
-            .map(|id| id.to_owned())
+            .map(|id| id.trim().to_ascii_uppercase())
+            .filter(|id| !id.is_empty())
             .collect()
     }
 }
@@ -80,7 +87,8 @@ impl ObservationRequest {
     pub fn station_ids(&self) -> Vec<String> {
         self.station_ids
             .split(',')
-            .map(|id| id.to_owned())
+            .map(|id| id.trim().to_ascii_uppercase())
+            .filter(|id| !id.is_empty())
             .collect()
     }
 }
@@ -105,7 +113,8 @@ impl From<&ObservationRequest> for FileParams {
     responses(
         (status = OK, description = "Successfully retrieved observation data", body = Vec<Observation>),
         (status = BAD_REQUEST, description = "Times are not in RFC3339 format"),
-        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather data")
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather data"),
+        (status = SERVICE_UNAVAILABLE, description = "Weather data source is failing, retry after the Retry-After header seconds")
     ))]
 pub async fn observations(
     State(state): State<Arc<AppState>>,
@@ -124,7 +133,8 @@ pub async fn observations(
     path = "stations",
     responses(
         (status = OK, description = "Successfully retrieved weather stations", body = Vec<Station>),
-        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather stations from data")
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieved weather stations from data"),
+        (status = SERVICE_UNAVAILABLE, description = "Weather data source is failing, retry after the Retry-After header seconds")
     ))]
 pub async fn get_stations(
     State(state): State<Arc<AppState>>,
@@ -132,3 +142,76 @@ pub async fn get_stations(
     let stations: Vec<Station> = state.weather_db.stations().await?;
     Ok(Json(stations))
 }
+
+#[utoipa::path(
+    get,
+    path = "stations/active",
+    responses(
+        (status = OK, description = "Successfully retrieved the stations used by unsigned events", body = Vec<ActiveStation>),
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieve event data"),
+    ))]
+pub async fn get_active_stations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<ActiveStation>>, ErrorResponse> {
+    state.oracle.active_stations().await.map(Json).map_err(|e| {
+        error!("error retrieving active stations: {}", e);
+        e.into()
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "stations/weather",
+    responses(
+        (status = OK, description = "Successfully retrieved the stations the oracle has stored weather for", body = Vec<StoredStation>),
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieve event data"),
+    ))]
+pub async fn get_stored_stations(
+    State(state): State<Arc<AppState>>,
+) -> Result<Json<Vec<StoredStation>>, ErrorResponse> {
+    state.oracle.stored_stations().await.map(Json).map_err(|e| {
+        error!("error retrieving stored stations: {}", e);
+        e.into()
+    })
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, IntoParams)]
+pub struct StationEventsParams {
+    /// Only events in this status, one of live, running, completed, signed or cancelled
+    pub status: Option<String>,
+}
+
+#[utoipa::path(
+    get,
+    path = "stations/{station_id}/events",
+    params(
+        ("station_id" = String, Path, description = "NOAA station id, ie. KSAW"),
+        StationEventsParams
+    ),
+    responses(
+        (status = OK, description = "Successfully retrieved the events using the station", body = Vec<EventSummary>),
+        (status = BAD_REQUEST, description = "Invalid status"),
+        (status = NOT_FOUND, description = "Invalid station id"),
+        (status = INTERNAL_SERVER_ERROR, description = "Failed to retrieve event data"),
+    ))]
+pub async fn get_station_events(
+    State(state): State<Arc<AppState>>,
+    Path(station_id): Path<String>,
+    Query(params): Query<StationEventsParams>,
+) -> Result<Json<Vec<EventSummary>>, ErrorResponse> {
+    let status = params
+        .status
+        .as_deref()
+        .map(EventStatus::try_from)
+        .transpose()
+        .map_err(Error::BadEvent)?;
+    state
+        .oracle
+        .list_station_events(&station_id, status)
+        .await
+        .map(Json)
+        .map_err(|e| {
+            error!("error retrieving station events: {}", e);
+            e.into()
+        })
+}
@@ -0,0 +1,33 @@
+use axum::Json;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct VersionInfo {
+    /// Version of the oracle crate the server was built from
+    pub version: String,
+    /// Short git sha of the commit the server was built from, `unknown` when not available
+    pub git_sha: String,
+    /// Time the server was built at in RFC3339 format
+    pub build_time: String,
+}
+
+impl VersionInfo {
+    pub fn current() -> Self {
+        Self {
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            git_sha: env!("GIT_SHA").to_string(),
+            build_time: env!("BUILD_TIME").to_string(),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/version",
+    responses(
+        (status = OK, description = "Successfully retrieved the build info of the running server", body = VersionInfo),
+    ))]
+pub async fn get_version() -> Json<VersionInfo> {
+    Json(VersionInfo::current())
+}
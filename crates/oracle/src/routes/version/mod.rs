@@ -0,0 +1,3 @@
+pub mod build_info;
+
+pub use build_info::*;
@@ -0,0 +1,89 @@
+use crate::{ScoringMode, ValueOptions};
+
+pub const OVER_OR_UNDER_POINTS: u64 = 10;
+pub const PAR_POINTS: u64 = 20;
+
+/// A pick of an entry once the observation is in
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ScoredPick {
+    pub pick: ValueOptions,
+    /// The observation matched the pick
+    pub correct: bool,
+}
+
+/// Turns the picks of an entry into its base score, the oracle adds the time tie breaker on top
+pub trait ScoringScheme: Send + Sync {
+    /// Points each pick is worth, in the same order as `picks`
+    fn pick_points(&self, picks: &[ScoredPick]) -> Vec<u64>;
+
+    fn base_score(&self, picks: &[ScoredPick]) -> u64 {
+        self.pick_points(picks)
+            .into_iter()
+            .fold(0, u64::saturating_add)
+    }
+
+    /// Highest base score an entry making `number_of_values` picks can get, saturating at `u64::MAX`
+    fn max_base_score(&self, number_of_values: usize) -> u64;
+}
+
+fn pick_points(pick: &ValueOptions) -> u64 {
+    match pick {
+        ValueOptions::Par => PAR_POINTS,
+        ValueOptions::Over | ValueOptions::Under => OVER_OR_UNDER_POINTS,
+    }
+}
+
+/// Match on Par 20pts, on Over 10pts, on Under 10pts
+#[derive(Debug, Clone, Copy, Default)]
+pub struct LinearScoring;
+
+impl ScoringScheme for LinearScoring {
+    fn pick_points(&self, picks: &[ScoredPick]) -> Vec<u64> {
+        picks
+            .iter()
+            .map(|scored| match scored.correct {
+                true => pick_points(&scored.pick),
+                false => 0,
+            })
+            .collect()
+    }
+
+    fn max_base_score(&self, number_of_values: usize) -> u64 {
+        (number_of_values as u64).saturating_mul(PAR_POINTS)
+    }
+}
+
+/// Same points per pick as linear, but each correct pick is worth double the correct pick before it
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ExponentialScoring;
+
+impl ScoringScheme for ExponentialScoring {
+    fn pick_points(&self, picks: &[ScoredPick]) -> Vec<u64> {
+        let mut streak = 0;
+        picks
+            .iter()
+            .map(|scored| {
+                if !scored.correct {
+                    return 0;
+                }
+                let points = pick_points(&scored.pick).saturating_mul(2_u64.saturating_pow(streak));
+                streak += 1;
+                points
+            })
+            .collect()
+    }
+
+    fn max_base_score(&self, number_of_values: usize) -> u64 {
+        let doublings = 2_u64.saturating_pow(number_of_values.min(u32::MAX as usize) as u32);
+        PAR_POINTS.saturating_mul(doublings - 1)
+    }
+}
+
+impl ScoringMode {
+    pub fn scheme(&self) -> &'static dyn ScoringScheme {
+        match self {
+            ScoringMode::Linear => &LinearScoring,
+            ScoringMode::Exponential => &ExponentialScoring,
+        }
+    }
+}
@@ -0,0 +1,91 @@
+use serde::{Deserialize, Serialize};
+use std::{
+    future::Future,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+use tokio::sync::Semaphore;
+use utoipa::ToSchema;
+
+pub const DEFAULT_SIGNING_CONCURRENCY: usize = 1;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, ToSchema)]
+pub struct SigningQueueStatus {
+    /// Max number of events signed at the same time
+    pub concurrency: usize,
+    /// Events being signed right now
+    pub in_flight: usize,
+    /// Events waiting on a free slot to be signed
+    pub queued: usize,
+    /// Most events that have been signed at the same time since the oracle started
+    pub peak_in_flight: usize,
+}
+
+/// Caps how many events are signed at once, building and checking the outcomes of an event is cpu heavy
+/// so a lot of events reaching their signing_date together would otherwise all be worked on in parallel.
+/// Signings past the cap wait in fifo order for a slot to free up
+pub struct SigningQueue {
+    slots: Semaphore,
+    concurrency: usize,
+    queued: AtomicUsize,
+    in_flight: AtomicUsize,
+    peak_in_flight: AtomicUsize,
+}
+
+// Counters are decremented on drop so a signing future that's cancelled doesn't leave them off
+struct Counted<'a>(&'a AtomicUsize);
+
+impl<'a> Counted<'a> {
+    fn new(counter: &'a AtomicUsize) -> (Self, usize) {
+        let count = counter.fetch_add(1, Ordering::SeqCst) + 1;
+        (Self(counter), count)
+    }
+}
+
+impl Drop for Counted<'_> {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+impl SigningQueue {
+    /// A concurrency of 0 is treated as 1, signing is never turned off
+    pub fn new(concurrency: usize) -> Self {
+        let concurrency = concurrency.max(1);
+        Self {
+            slots: Semaphore::new(concurrency),
+            concurrency,
+            queued: AtomicUsize::new(0),
+            in_flight: AtomicUsize::new(0),
+            peak_in_flight: AtomicUsize::new(0),
+        }
+    }
+
+    /// Waits for a free slot, then runs the signing
+    pub async fn run<F: Future>(&self, signing: F) -> F::Output {
+        let queued = Counted::new(&self.queued).0;
+        let _slot = self
+            .slots
+            .acquire()
+            .await
+            .expect("signing queue slots are never closed");
+        drop(queued);
+        let (_in_flight, in_flight) = Counted::new(&self.in_flight);
+        self.peak_in_flight.fetch_max(in_flight, Ordering::SeqCst);
+        signing.await
+    }
+
+    pub fn status(&self) -> SigningQueueStatus {
+        SigningQueueStatus {
+            concurrency: self.concurrency,
+            in_flight: self.in_flight.load(Ordering::SeqCst),
+            queued: self.queued.load(Ordering::SeqCst),
+            peak_in_flight: self.peak_in_flight.load(Ordering::SeqCst),
+        }
+    }
+}
+
+impl Default for SigningQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_SIGNING_CONCURRENCY)
+    }
+}
@@ -1,29 +1,54 @@
+#[cfg(feature = "embed-ui")]
+use crate::embedded_ui_handler;
 use crate::{
-    add_event_entry, create_event, db, download, files, forecasts, get_event, get_event_entry,
-    get_npub, get_pubkey, get_stations, index_handler, list_events, observations,
-    oracle::{self, Oracle},
-    routes, update_data, upload,
-    weather_data::WeatherAccess,
-    EventData, FileAccess, FileData, WeatherData,
+    add_entry_schema, add_event_entry, cancel_event, check_folder_writable, clone_event,
+    create_event, create_event_db_dir, create_event_schema, db, download, files, forecasts,
+    get_active_stations, get_coordinator_entries, get_entry_breakdown, get_event,
+    get_event_announcement, get_event_capacity, get_event_dimensions, get_event_entries_csv,
+    get_event_entry, get_event_outcome_messages, get_event_outcome_winners, get_event_payouts,
+    get_event_weather_diff, get_event_weather_parquet, get_npub, get_pubkey, get_sign_status,
+    get_signing_queue, get_station_accuracy, get_station_events, get_stations, get_stored_stations,
+    get_version, index_handler, list_events, observations,
+    oracle::{self, Oracle, SigningPolicy, DEFAULT_MAX_LOCATIONS_PER_EVENT},
+    rate_limit::rate_limit,
+    routes, update_data, update_event, update_event_weather, update_event_weather_parquet, upload,
+    weather_data::{WeatherAccess, DEFAULT_WEATHER_RETRY_ATTEMPTS, DEFAULT_WEATHER_RETRY_DELAY},
+    Cli, EventData, FileAccess, FileData, ParTolerance, RateLimit, RateLimiter, RetryWeatherData,
+    SchemaDriftPolicy, StationGroups, WeatherData, ANNOUNCEMENT_EXPIRY_DELAY,
+    DEFAULT_EVENT_DB_FILE, DEFAULT_SIGNING_CONCURRENCY,
 };
 use anyhow::anyhow;
 use axum::{
     body::Body,
-    extract::{DefaultBodyLimit, Request},
+    extract::{ConnectInfo, DefaultBodyLimit, Request},
     middleware::{self, Next},
     response::IntoResponse,
     routing::{get, post},
     Router,
 };
 use hyper::{
+    body::Incoming,
     header::{ACCEPT, CONTENT_TYPE},
+    server::conn::http1,
+    service::service_fn,
     Method,
 };
-use log::info;
-use std::sync::Arc;
+use hyper_util::rt::{TokioIo, TokioTimer};
+use log::{debug, info, warn};
+use nostr_sdk::PublicKey as NostrPublicKey;
+use std::{net::SocketAddr, sync::Arc, time::Duration};
+use tokio::{
+    net::{TcpListener, TcpSocket},
+    select,
+    task::JoinSet,
+};
+use tokio_util::sync::CancellationToken;
+use tower::Service;
+#[cfg(not(feature = "embed-ui"))]
+use tower_http::services::{ServeDir, ServeFile};
 use tower_http::{
     cors::{Any, CorsLayer},
-    services::{ServeDir, ServeFile},
+    timeout::TimeoutLayer,
 };
 use utoipa::OpenApi;
 use utoipa_scalar::{Scalar, Servable};
@@ -35,36 +60,92 @@ pub struct AppState {
     pub file_access: Arc<dyn FileData>,
     pub weather_db: Arc<dyn WeatherData>,
     pub oracle: Arc<Oracle>,
+    /// What uploads of weather files with missing columns do
+    pub schema_drift_policy: SchemaDriftPolicy,
 }
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         routes::events::oracle_routes::get_npub,
+        routes::events::oracle_routes::get_signing_queue,
         routes::events::oracle_routes::get_pubkey,
+        routes::events::oracle_routes::get_sign_status,
         routes::events::oracle_routes::list_events,
         routes::events::oracle_routes::create_event,
         routes::events::oracle_routes::get_event,
+        routes::events::oracle_routes::clone_event,
+        routes::events::oracle_routes::cancel_event,
+        routes::events::oracle_routes::update_event,
+        routes::events::oracle_routes::get_event_announcement,
+        routes::events::oracle_routes::get_event_outcome_messages,
+        routes::events::oracle_routes::get_event_outcome_winners,
+        routes::events::oracle_routes::get_event_dimensions,
+        routes::events::oracle_routes::get_event_capacity,
+        routes::events::oracle_routes::get_event_payouts,
+        routes::events::oracle_routes::get_event_weather_diff,
+        routes::events::oracle_routes::get_event_weather_parquet,
+        routes::events::oracle_routes::get_event_entries_csv,
         routes::events::oracle_routes::add_event_entry,
         routes::events::oracle_routes::get_event_entry,
+        routes::events::oracle_routes::get_entry_breakdown,
+        routes::events::oracle_routes::get_coordinator_entries,
+        routes::events::oracle_routes::get_station_accuracy,
+        routes::events::oracle_routes::update_event_weather,
+        routes::events::oracle_routes::update_event_weather_parquet,
         routes::events::oracle_routes::update_data,
         routes::stations::weather_routes::forecasts,
         routes::stations::weather_routes::observations,
         routes::stations::weather_routes::get_stations,
+        routes::stations::weather_routes::get_active_stations,
+        routes::stations::weather_routes::get_stored_stations,
+        routes::stations::weather_routes::get_station_events,
         routes::files::download::download,
         routes::files::get_names::files,
         routes::files::upload::upload,
+        routes::version::build_info::get_version,
+        routes::schema::request_bodies::create_event_schema,
+        routes::schema::request_bodies::add_entry_schema,
     ),
     components(
         schemas(
                 routes::files::get_names::Files,
                 oracle::Error,
                 db::Event,
+                db::EventSummary,
+                db::EventSignStatus,
+                db::EventStatusGroup,
+                db::ActiveStation,
+                db::StoredStation,
                 db::WeatherEntry,
                 db::AddEventEntry,
                 db::CreateEvent,
+                db::CloneEvent,
+                db::UpdateEvent,
+                db::EventAnnouncement,
+                db::EventOutcomeMessages,
+                db::EventOutcomeWinners,
+                db::OutcomeWinners,
+                db::EventDimensions,
+                db::RawObservedTemps,
+                db::EventCapacity,
+                db::DimensionOptions,
+                db::Dimension,
+                db::OutcomeEncoding,
+                db::EventPayouts,
+                db::PlacePayout,
+                db::WeatherDiff,
+                db::ValueDiff,
+                db::StationAccuracy,
+                db::ForecastAccuracy,
+                db::MeanAbsoluteDiff,
+                db::EntryBreakdown,
+                db::PickBreakdown,
+                crate::signing_queue::SigningQueueStatus,
+                routes::events::oracle_routes::AnnouncementEncoding,
                 routes::events::oracle_routes::Pubkey,
-                routes::events::oracle_routes::Base64Pubkey
+                routes::events::oracle_routes::Base64Pubkey,
+                routes::version::build_info::VersionInfo
             )
     ),
     tags(
@@ -73,69 +154,408 @@ pub struct AppState {
 )]
 struct ApiDoc;
 
-pub async fn build_app_state(
-    remote_url: String,
-    ui_dir: String,
-    data_dir: String,
-    event_dir: String,
-    private_key_file_path: String,
-) -> Result<AppState, anyhow::Error> {
-    let file_access = Arc::new(FileAccess::new(data_dir));
-    let weather_db = Arc::new(
+/// Everything the oracle needs to build its app state, resolved from the cli with defaults filled in
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    /// Url the UI hits for the backend
+    pub remote_url: String,
+    /// Path to files used to make the browser UI
+    pub ui_dir: String,
+    /// Path to the uploaded weather parquet files
+    pub data_dir: String,
+    /// Path to the directory holding the event db
+    pub event_dir: String,
+    /// Name of the event db file in `event_dir`
+    pub event_db_file: String,
+    /// DuckDB extensions loaded on every event db connection
+    pub event_db_extensions: Vec<String>,
+    pub private_key_file_path: String,
+    /// Keys the oracle used before `private_key_file_path`, still used to attest events announced under them
+    pub retired_key_file_paths: Vec<String>,
+    pub entry_grace_period: time::Duration,
+    pub max_locations_per_event: usize,
+    pub signing_policy: SigningPolicy,
+    pub auto_sign: bool,
+    pub sign_buffer: time::Duration,
+    pub par_tolerance: ParTolerance,
+    pub station_groups: StationGroups,
+    pub schema_drift_policy: SchemaDriftPolicy,
+    pub operator_pubkeys: Vec<NostrPublicKey>,
+    pub preserve_observation_offsets: bool,
+    pub keep_raw_observation_temps: bool,
+    pub signing_concurrency: usize,
+}
+
+impl TryFrom<&Cli> for AppConfig {
+    type Error = anyhow::Error;
+
+    fn try_from(cli: &Cli) -> Result<Self, Self::Error> {
+        let signing_policy = match cli.signing_policy.as_deref() {
+            Some(policy) => SigningPolicy::try_from(policy)?,
+            None => SigningPolicy::default(),
+        };
+        info!("signing policy: {}", signing_policy);
+        let par_tolerance = ParTolerance::try_from(cli.par_tolerance.unwrap_or(0))?;
+        info!("par tolerance: {}", par_tolerance);
+        let station_groups = match cli.station_groups.as_deref() {
+            Some(path) => StationGroups::load(path)?,
+            None => StationGroups::default(),
+        };
+        info!("station groups: {:?}", station_groups.names());
+        let schema_drift_policy = match cli.schema_drift_policy.as_deref() {
+            Some(policy) => SchemaDriftPolicy::try_from(policy)?,
+            None => SchemaDriftPolicy::default(),
+        };
+        info!("schema drift policy: {}", schema_drift_policy);
+        let auto_sign = cli.auto_sign.unwrap_or(false);
+        info!("auto sign all events: {}", auto_sign);
+        let sign_buffer = time::Duration::seconds(cli.sign_buffer_secs.unwrap_or(0) as i64);
+        // Signing at or past the expiry would let DLCs be refunded before the attestation exists
+        if sign_buffer >= ANNOUNCEMENT_EXPIRY_DELAY {
+            return Err(anyhow!(
+                "sign_buffer_secs needs to be less than the {} until announcements expire",
+                ANNOUNCEMENT_EXPIRY_DELAY
+            ));
+        }
+        info!("sign buffer: {}", sign_buffer);
+        let operator_pubkeys = cli
+            .operator_pubkeys
+            .clone()
+            .unwrap_or_default()
+            .iter()
+            .map(|pubkey| {
+                NostrPublicKey::parse(pubkey)
+                    .map_err(|e| anyhow!("invalid operator pubkey {}: {}", pubkey, e))
+            })
+            .collect::<Result<Vec<NostrPublicKey>, anyhow::Error>>()?;
+        info!("operator pubkeys: {}", operator_pubkeys.len());
+        let preserve_observation_offsets = cli.preserve_observation_offsets.unwrap_or(false);
+        info!(
+            "preserve observation offsets: {}",
+            preserve_observation_offsets
+        );
+        let keep_raw_observation_temps = cli.keep_raw_observation_temps.unwrap_or(false);
+        info!("keep raw observation temps: {}", keep_raw_observation_temps);
+        let signing_concurrency = cli
+            .signing_concurrency
+            .unwrap_or(DEFAULT_SIGNING_CONCURRENCY);
+        info!("signing concurrency: {}", signing_concurrency);
+
+        Ok(Self {
+            remote_url: cli
+                .remote_url
+                .clone()
+                .unwrap_or(String::from("http://127.0.0.1:9100")),
+            ui_dir: cli.ui_dir.clone().unwrap_or(String::from("./ui")),
+            data_dir: cli
+                .weather_dir
+                .clone()
+                .unwrap_or(String::from("./weather_data")),
+            event_dir: cli.event_db.clone().unwrap_or(String::from("./event_data")),
+            event_db_file: cli
+                .event_db_file
+                .clone()
+                .unwrap_or(String::from(DEFAULT_EVENT_DB_FILE)),
+            event_db_extensions: cli
+                .event_db_extensions
+                .clone()
+                .unwrap_or_else(|| vec![String::from("parquet")]),
+            private_key_file_path: cli
+                .oracle_private_key
+                .clone()
+                .unwrap_or(String::from("./oracle_private_key.pem")),
+            retired_key_file_paths: cli.oracle_retired_keys.clone().unwrap_or_default(),
+            entry_grace_period: time::Duration::seconds(
+                cli.entry_grace_period_secs.unwrap_or(0) as i64
+            ),
+            max_locations_per_event: cli
+                .max_locations_per_event
+                .unwrap_or(DEFAULT_MAX_LOCATIONS_PER_EVENT),
+            signing_policy,
+            auto_sign,
+            sign_buffer,
+            par_tolerance,
+            station_groups,
+            schema_drift_policy,
+            operator_pubkeys,
+            preserve_observation_offsets,
+            keep_raw_observation_temps,
+            signing_concurrency,
+        })
+    }
+}
+
+pub async fn build_app_state(config: AppConfig) -> Result<AppState, anyhow::Error> {
+    let file_access = Arc::new(FileAccess::new(config.data_dir));
+    let weather_access = Arc::new(
         WeatherAccess::new(file_access.clone())
             .map_err(|e| anyhow!("error setting up weather data: {}", e))?,
     );
+    // A failing weather source only affects the weather routes and etl, the rest of the api keeps working
+    let weather_db = Arc::new(RetryWeatherData::new(
+        weather_access,
+        DEFAULT_WEATHER_RETRY_ATTEMPTS,
+        DEFAULT_WEATHER_RETRY_DELAY,
+    ));
 
+    let event_db_file = config.event_db_file;
+    if event_db_file.is_empty() || event_db_file.contains(['/', '\\']) {
+        return Err(anyhow!(
+            "invalid event db file name: {:?}, must be a file name without a path",
+            event_db_file
+        ));
+    }
+    create_event_db_dir(&config.event_dir)?;
+    check_folder_writable(&config.event_dir)?;
     let event_db = Arc::new(
-        EventData::new(&event_dir).map_err(|e| anyhow!("error setting up event data: {}", e))?,
+        EventData::new_with_file_name(&config.event_dir, &event_db_file)
+            .and_then(|event_db| event_db.with_extensions(&config.event_db_extensions))
+            .map_err(|e| anyhow!("error setting up event data: {}", e))?,
+    );
+    let oracle = Arc::new(
+        Oracle::new(
+            event_db,
+            weather_db.clone(),
+            &config.private_key_file_path,
+            &config.retired_key_file_paths,
+        )
+        .await?
+        .with_entry_grace_period(config.entry_grace_period)
+        .with_max_locations_per_event(config.max_locations_per_event)
+        .with_signing_policy(config.signing_policy)
+        .with_auto_sign(config.auto_sign)
+        .with_sign_buffer(config.sign_buffer)
+        .with_par_tolerance(config.par_tolerance)
+        .with_station_groups(config.station_groups)
+        .with_operator_pubkeys(config.operator_pubkeys)
+        .with_preserve_observation_offsets(config.preserve_observation_offsets)
+        .with_keep_raw_observation_temps(config.keep_raw_observation_temps)
+        .with_signing_concurrency(config.signing_concurrency),
     );
-    let oracle = Arc::new(Oracle::new(event_db, weather_db.clone(), &private_key_file_path).await?);
 
     Ok(AppState {
-        ui_dir,
-        remote_url,
+        ui_dir: config.ui_dir,
+        remote_url: config.remote_url,
         weather_db,
         file_access,
         oracle,
+        schema_drift_policy: config.schema_drift_policy,
     })
 }
 
+#[derive(Debug, Clone)]
+pub struct ServerConfig {
+    /// Max number of pending connections waiting to be accepted
+    pub listen_backlog: u32,
+    /// How long a kept-alive connection can sit idle waiting for its next request before the server closes it
+    pub keep_alive: Duration,
+    /// How long a request has to be read and responded to before the server returns a 408
+    pub request_timeout: Duration,
+    /// Max requests per client ip before the server returns a 429, no limit when not set
+    pub rate_limit: Option<RateLimit>,
+}
+
+impl Default for ServerConfig {
+    fn default() -> Self {
+        Self {
+            listen_backlog: 1024,
+            keep_alive: Duration::from_secs(75),
+            request_timeout: Duration::from_secs(30),
+            rate_limit: None,
+        }
+    }
+}
+
+pub fn bind_listener(
+    socket_addr: SocketAddr,
+    config: &ServerConfig,
+) -> Result<TcpListener, std::io::Error> {
+    let socket = if socket_addr.is_ipv4() {
+        TcpSocket::new_v4()?
+    } else {
+        TcpSocket::new_v6()?
+    };
+    socket.set_reuseaddr(true)?;
+    socket.bind(socket_addr)?;
+    socket.listen(config.listen_backlog)
+}
+
+/// Serves the app over http/1 until `shutdown` is cancelled, then waits for in flight requests to finish. Idle
+/// kept-alive connections are closed once they've waited `keep_alive` for their next request
+pub async fn serve_with_config(
+    listener: TcpListener,
+    app: Router,
+    config: &ServerConfig,
+    shutdown: CancellationToken,
+) {
+    let mut builder = http1::Builder::new();
+    // The header read timeout starts as soon as the connection is waiting on a request, which makes it the idle timeout
+    builder
+        .keep_alive(true)
+        .timer(TokioTimer::new())
+        .header_read_timeout(config.keep_alive);
+
+    let mut connections = JoinSet::new();
+    loop {
+        let (stream, remote_addr) = select! {
+            accepted = listener.accept() => match accepted {
+                Ok(accepted) => accepted,
+                Err(e) => {
+                    warn!("failed to accept connection: {}", e);
+                    continue;
+                }
+            },
+            // Finished connections are reaped as they go so the set only holds open ones
+            Some(_) = connections.join_next(), if !connections.is_empty() => continue,
+            _ = shutdown.cancelled() => break,
+        };
+        let builder = builder.clone();
+        let app = app.clone();
+        let shutdown = shutdown.clone();
+        connections.spawn(async move {
+            let service = service_fn(move |mut request: Request<Incoming>| {
+                // Same as `into_make_service_with_connect_info`, the rate limiter keys clients on their address
+                request.extensions_mut().insert(ConnectInfo(remote_addr));
+                app.clone().call(request)
+            });
+            let connection = builder.serve_connection(TokioIo::new(stream), service);
+            tokio::pin!(connection);
+            let result = select! {
+                result = connection.as_mut() => result,
+                _ = shutdown.cancelled() => {
+                    connection.as_mut().graceful_shutdown();
+                    connection.await
+                }
+            };
+            if let Err(e) = result {
+                debug!("connection from {} closed: {}", remote_addr, e);
+            }
+        });
+    }
+    while connections.join_next().await.is_some() {}
+}
+
+pub fn apply_server_config(router: Router, config: &ServerConfig) -> Router {
+    let router = router.layer(TimeoutLayer::new(config.request_timeout));
+    match config.rate_limit {
+        Some(limit) => router.layer(middleware::from_fn_with_state(
+            RateLimiter::new(limit),
+            rate_limit,
+        )),
+        None => router,
+    }
+}
+
 pub fn app(app_state: AppState) -> Router {
     let api_docs = ApiDoc::openapi();
-    // The ui folder needs to be generated and have this relative path from where the binary is being run
-    let serve_dir = ServeDir::new("ui").not_found_service(ServeFile::new(app_state.ui_dir.clone()));
+    let ui_dir = app_state.ui_dir.clone();
     let cors = CorsLayer::new()
-        // allow `GET` and `POST` when accessing the resource
-        .allow_methods([Method::GET, Method::POST, Method::OPTIONS])
+        // allow `GET`, `POST` and `PATCH` when accessing the resource
+        .allow_methods([Method::GET, Method::POST, Method::PATCH, Method::OPTIONS])
         .allow_headers([ACCEPT, CONTENT_TYPE])
         // allow requests from any origin
         .allow_origin(Any);
-    Router::new()
+    let router = Router::new()
+        .route("/version", get(get_version))
+        .route("/schema/create-event", get(create_event_schema))
+        .route("/schema/add-entry", get(add_entry_schema))
         .route("/files", get(files))
         .route("/file/{file_name}", get(download))
         .route("/file/{file_name}", post(upload))
         .route("/stations", get(get_stations))
+        .route("/stations/active", get(get_active_stations))
+        .route("/stations/weather", get(get_stored_stations))
+        .route("/stations/{station_id}/events", get(get_station_events))
         .route("/stations/forecasts", get(forecasts))
         .route("/stations/observations", get(observations))
         .route("/oracle/npub", get(get_npub))
         .route("/oracle/pubkey", get(get_pubkey))
         .route("/oracle/update", post(update_data))
+        .route("/oracle/signing/queue", get(get_signing_queue))
         .route("/oracle/events", get(list_events))
         .route("/oracle/events", post(create_event))
-        .route("/oracle/events/{event_id}", get(get_event))
+        .route("/oracle/events/sign-status", get(get_sign_status))
+        .route(
+            "/oracle/events/{event_id}",
+            get(get_event).patch(update_event),
+        )
+        .route("/oracle/events/{event_id}/clone", post(clone_event))
+        .route("/oracle/events/{event_id}/cancel", post(cancel_event))
+        .route(
+            "/oracle/events/{event_id}/announcement",
+            get(get_event_announcement),
+        )
+        .route(
+            "/oracle/events/{event_id}/outcomes",
+            get(get_event_outcome_messages),
+        )
+        .route(
+            "/oracle/events/{event_id}/outcomes/winners",
+            get(get_event_outcome_winners),
+        )
+        .route(
+            "/oracle/events/{event_id}/dimensions",
+            get(get_event_dimensions),
+        )
+        .route(
+            "/oracle/events/{event_id}/capacity",
+            get(get_event_capacity),
+        )
+        .route("/oracle/events/{event_id}/payouts", get(get_event_payouts))
+        .route(
+            "/oracle/events/{event_id}/weather/diff",
+            get(get_event_weather_diff),
+        )
+        .route(
+            "/oracle/events/{event_id}/entries.csv",
+            get(get_event_entries_csv),
+        )
+        .route(
+            "/oracle/events/{event_id}/weather",
+            post(update_event_weather),
+        )
+        .route(
+            "/oracle/events/{event_id}/weather.parquet",
+            get(get_event_weather_parquet).post(update_event_weather_parquet),
+        )
         .route("/oracle/events/{event_id}/entry", post(add_event_entry))
         .route(
             "/oracle/events/{event_id}/entry/{entry_id}",
             get(get_event_entry),
         )
+        .route(
+            "/oracle/events/{event_id}/entry/{entry_id}/breakdown",
+            get(get_entry_breakdown),
+        )
+        .route("/oracle/entries", get(get_coordinator_entries))
+        .route(
+            "/oracle/stations/{station_id}/accuracy",
+            get(get_station_accuracy),
+        )
         .layer(middleware::from_fn(log_request))
         .layer(DefaultBodyLimit::max(30 * 1024 * 1024)) // max is in bytes
         .route("/", get(index_handler))
         .with_state(Arc::new(app_state))
-        .merge(Scalar::with_url("/docs", api_docs))
+        .merge(Scalar::with_url("/docs", api_docs));
+
+    with_ui_assets(router, &ui_dir).layer(cors)
+}
+
+#[cfg(feature = "embed-ui")]
+fn with_ui_assets(router: Router, _ui_dir: &str) -> Router {
+    router
+        .nest_service("/ui", get(embedded_ui_handler))
+        .fallback(embedded_ui_handler)
+}
+
+#[cfg(not(feature = "embed-ui"))]
+fn with_ui_assets(router: Router, ui_dir: &str) -> Router {
+    // The ui folder needs to be generated, ui_dir is relative to where the binary is being run
+    let serve_dir =
+        ServeDir::new(ui_dir).not_found_service(ServeFile::new(format!("{}/index.html", ui_dir)));
+    router
         .nest_service("/ui", serve_dir.clone())
         .fallback_service(serve_dir)
-        .layer(cors)
 }
 
 async fn log_request(request: Request<Body>, next: Next) -> impl IntoResponse {
@@ -0,0 +1,54 @@
+use crate::normalize_station_id;
+use anyhow::{anyhow, Error};
+use std::{collections::BTreeMap, fs};
+
+/// Named lists of stations events can reference instead of listing every location, loaded from a toml
+/// file of `group_name = ["KSAW", "PFNO"]` pairs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StationGroups(BTreeMap<String, Vec<String>>);
+
+impl StationGroups {
+    pub fn new(groups: BTreeMap<String, Vec<String>>) -> Result<Self, Error> {
+        let mut normalized = BTreeMap::new();
+        for (name, stations) in groups {
+            if stations.is_empty() {
+                return Err(anyhow!("station group '{}' has no stations", name));
+            }
+            let stations = stations
+                .iter()
+                .map(|station| normalize_station_id(station))
+                .collect::<Result<Vec<String>, Error>>()
+                .map_err(|e| anyhow!("station group '{}' is invalid: {}", name, e))?;
+            normalized.insert(name, stations);
+        }
+        Ok(Self(normalized))
+    }
+
+    pub fn from_toml(content: &str) -> Result<Self, Error> {
+        let groups: BTreeMap<String, Vec<String>> =
+            toml::from_str(content).map_err(|e| anyhow!("error parsing station groups: {}", e))?;
+        StationGroups::new(groups)
+    }
+
+    pub fn load(path: &str) -> Result<Self, Error> {
+        let content = fs::read_to_string(path)
+            .map_err(|e| anyhow!("error reading station groups {}: {}", path, e))?;
+        StationGroups::from_toml(&content)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Vec<String>> {
+        self.0.get(name)
+    }
+
+    pub fn names(&self) -> Vec<&str> {
+        self.0.keys().map(|name| name.as_str()).collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+}
@@ -31,6 +31,14 @@ pub fn subfolder_exists(subfolder_path: &str) -> bool {
     fs::metadata(subfolder_path).is_ok()
 }
 
+/// Errors unless a file can be created in the folder, checked on startup so a read only folder fails fast
+pub fn check_folder_writable(folder_path: &str) -> Result<(), anyhow::Error> {
+    let probe = Path::new(folder_path).join(".write_check");
+    fs::write(&probe, b"")
+        .and_then(|_| fs::remove_file(&probe))
+        .map_err(|e| anyhow::anyhow!("folder {} is not writable: {}", folder_path, e))
+}
+
 #[derive(Parser, Clone, Debug, serde::Deserialize)]
 #[command(author, version, about, long_about = None)]
 pub struct Cli {
@@ -62,6 +70,14 @@ pub struct Cli {
     #[arg(short, long)]
     pub event_db: Option<String>,
 
+    /// Name of the database file in `event_db` holding the dlc event data, use a different name per oracle instance sharing the directory (default: events.db3)
+    #[arg(long)]
+    pub event_db_file: Option<String>,
+
+    /// Comma separated DuckDB extensions installed and loaded on every event db connection, ie. json,parquet,httpfs. Extensions that fail to load are logged and skipped, pushing event weather as parquet needs `parquet` (default: parquet)
+    #[arg(long, value_delimiter = ',')]
+    pub event_db_extensions: Option<Vec<String>>,
+
     /// Path to files used to make the browser UI (default: ./ui)
     #[arg(short, long)]
     pub ui_dir: Option<String>,
@@ -69,6 +85,82 @@ pub struct Cli {
     /// Path to oracle private key (default: ./oracle_private_key.pem)
     #[arg(short, long)]
     pub oracle_private_key: Option<String>,
+
+    /// Comma separated paths to private keys the oracle used before its current key, events announced under them are still attested with them (default: none)
+    #[arg(long, value_delimiter = ',')]
+    pub oracle_retired_keys: Option<Vec<String>>,
+
+    /// Max number of pending connections waiting to be accepted (default: 1024)
+    #[arg(long)]
+    pub listen_backlog: Option<u32>,
+
+    /// Seconds a kept-alive http connection can sit idle waiting for its next request before it's closed (default: 75)
+    #[arg(long)]
+    pub keep_alive_secs: Option<u64>,
+
+    /// Seconds a request has to be read and responded to before it times out (default: 30)
+    #[arg(long)]
+    pub request_timeout_secs: Option<u64>,
+
+    /// Max number of requests a single ip can make per rate limit window, unlimited when not set (default: none)
+    #[arg(long)]
+    pub rate_limit_requests: Option<u32>,
+
+    /// Seconds until a client's rate limit request count resets (default: 60)
+    #[arg(long)]
+    pub rate_limit_window_secs: Option<u64>,
+
+    /// Seconds after an event's observation_date that new entries are still accepted (default: 0)
+    #[arg(long)]
+    pub entry_grace_period_secs: Option<u64>,
+
+    /// Path to a toml file of named station groups, `group_name = ["KSAW", "PFNO"]`, events can set `station_group` to track a group's stations (default: none)
+    #[arg(long)]
+    pub station_groups: Option<String>,
+
+    /// What uploaded weather files missing some of the expected columns do, `adapt` fills the missing columns with nulls and `reject` refuses the upload (default: adapt)
+    #[arg(long)]
+    pub schema_drift_policy: Option<String>,
+
+    /// Max number of stations a single event can track (default: 50)
+    #[arg(long)]
+    pub max_locations_per_event: Option<usize>,
+
+    /// How far an observed value can be from the forecast, in degrees for temperatures and mph for wind speed, and still score a Par pick, can't be negative (default: 0, only an exact match is Par)
+    #[arg(long)]
+    pub par_tolerance: Option<i64>,
+
+    /// How completed events missing observations for some stations are signed, `sign_with_available` or `require_all_observations` (default: sign_with_available)
+    #[arg(long)]
+    pub signing_policy: Option<String>,
+
+    /// Sign every event once its signing_date passes, events can also opt in on their own with `auto_sign` when created (default: false)
+    #[arg(long)]
+    pub auto_sign: Option<bool>,
+
+    /// Most seconds between checks for events to auto sign, new events are picked up on the next check (default: 60)
+    #[arg(long)]
+    pub auto_sign_interval_secs: Option<u64>,
+
+    /// Comma separated nostr pubkeys (npub or hex) of the operators allowed to push weather directly to events, ie. to correct a NOAA reading (default: none)
+    #[arg(long, value_delimiter = ',')]
+    pub operator_pubkeys: Option<Vec<String>>,
+
+    /// Seconds after an event's signing_date the oracle waits before signing it, so observations that arrive late are still scored. Needs to be less than the 7 days until the announcement expires (default: 0)
+    #[arg(long)]
+    pub sign_buffer_secs: Option<u64>,
+
+    /// Keep the offset observations were reported in, ie. `-04:00` during daylight saving time, as `original_offset` next to their UTC date (default: false)
+    #[arg(long)]
+    pub preserve_observation_offsets: Option<bool>,
+
+    /// Keep the unrounded observed temperatures as `raw_temps` next to the rounded ones, scoring always uses the rounded temperatures (default: false)
+    #[arg(long)]
+    pub keep_raw_observation_temps: Option<bool>,
+
+    /// Max number of events signed at the same time, the rest wait their turn (default: 1)
+    #[arg(long)]
+    pub signing_concurrency: Option<usize>,
 }
 
 pub fn get_config_info() -> Cli {
@@ -0,0 +1,70 @@
+use crate::helpers::{get_json, new_event, spawn_app, station_ids, MockWeatherAccess};
+use dlctix::secp::MaybeScalar;
+use nostr_sdk::Keys;
+use oracle::{ActiveStation, CreateEvent};
+use std::sync::Arc;
+
+#[tokio::test]
+async fn can_get_stations_used_by_unsigned_events() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    for locations in [
+        vec!["PFNO", "KSAW", "PAPG"],
+        vec!["KSAW", "KITH"],
+        vec!["PAPG", "KSAW", "KJAN"],
+    ] {
+        test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                CreateEvent {
+                    locations: station_ids(&locations),
+                    ..new_event()
+                },
+            )
+            .await
+            .unwrap();
+    }
+    // Signed events no longer need weather data
+    let signed = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                locations: station_ids(&["KSAW", "KDEN"]),
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    let mut sign_event = test_app
+        .event_data
+        .get_events_to_sign(vec![signed.id])
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    sign_event.attestation = Some(MaybeScalar::from_slice(&[9u8; 32]).unwrap());
+    test_app
+        .event_data
+        .update_event_attestation(&sign_event)
+        .await
+        .unwrap();
+
+    let stations: Vec<ActiveStation> = get_json(&test_app, "/stations/active").await;
+
+    let expected: Vec<ActiveStation> = [
+        ("KITH", 1),
+        ("KJAN", 1),
+        ("KSAW", 3),
+        ("PAPG", 2),
+        ("PFNO", 1),
+    ]
+    .into_iter()
+    .map(|(station_id, event_count)| ActiveStation {
+        station_id: String::from(station_id),
+        event_count,
+    })
+    .collect();
+    assert_eq!(stations, expected);
+}
@@ -0,0 +1,77 @@
+use clap::Parser;
+use oracle::{
+    oracle::{SigningPolicy, DEFAULT_MAX_LOCATIONS_PER_EVENT},
+    AppConfig, Cli, ParTolerance, DEFAULT_EVENT_DB_FILE, DEFAULT_SIGNING_CONCURRENCY,
+};
+use time::Duration;
+
+#[test]
+fn fills_in_defaults_for_unset_options() {
+    let cli = Cli::parse_from(["oracle"]);
+
+    let config = AppConfig::try_from(&cli).unwrap();
+
+    assert_eq!(config.remote_url, "http://127.0.0.1:9100");
+    assert_eq!(config.data_dir, "./weather_data");
+    assert_eq!(config.event_dir, "./event_data");
+    assert_eq!(config.event_db_file, DEFAULT_EVENT_DB_FILE);
+    assert_eq!(config.private_key_file_path, "./oracle_private_key.pem");
+    assert!(config.retired_key_file_paths.is_empty());
+    assert_eq!(config.entry_grace_period, Duration::ZERO);
+    assert_eq!(
+        config.max_locations_per_event,
+        DEFAULT_MAX_LOCATIONS_PER_EVENT
+    );
+    assert_eq!(config.signing_policy, SigningPolicy::default());
+    assert!(!config.auto_sign);
+    assert_eq!(config.par_tolerance, ParTolerance::default());
+    assert_eq!(config.signing_concurrency, DEFAULT_SIGNING_CONCURRENCY);
+}
+
+#[test]
+fn reads_options_from_the_cli() {
+    let cli = Cli::parse_from([
+        "oracle",
+        "--event-db-file",
+        "oracle_2.db3",
+        "--oracle-retired-keys",
+        "./old_key.pem,./older_key.pem",
+        "--entry-grace-period-secs",
+        "90",
+        "--par-tolerance",
+        "2",
+        "--sign-buffer-secs",
+        "3600",
+        "--signing-concurrency",
+        "4",
+    ]);
+
+    let config = AppConfig::try_from(&cli).unwrap();
+
+    assert_eq!(config.event_db_file, "oracle_2.db3");
+    assert_eq!(
+        config.retired_key_file_paths,
+        vec![
+            String::from("./old_key.pem"),
+            String::from("./older_key.pem")
+        ]
+    );
+    assert_eq!(config.entry_grace_period, Duration::seconds(90));
+    assert_eq!(config.par_tolerance, ParTolerance::try_from(2).unwrap());
+    assert_eq!(config.sign_buffer, Duration::hours(1));
+    assert_eq!(config.signing_concurrency, 4);
+}
+
+#[test]
+fn rejects_a_sign_buffer_past_the_announcement_expiry() {
+    let cli = Cli::parse_from(["oracle", "--sign-buffer-secs", "604800"]);
+
+    assert!(AppConfig::try_from(&cli).is_err());
+}
+
+#[test]
+fn rejects_invalid_operator_pubkeys() {
+    let cli = Cli::parse_from(["oracle", "--operator-pubkeys", "not_a_pubkey"]);
+
+    assert!(AppConfig::try_from(&cli).is_err());
+}
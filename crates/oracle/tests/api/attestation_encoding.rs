@@ -0,0 +1,92 @@
+use crate::helpers::{new_event, random_test_number};
+use dlctix::secp::{MaybeScalar, Scalar};
+use duckdb::{params, Connection};
+use nostr_sdk::Keys;
+use oracle::{create_folder, CreateEvent, CreateEventData, EventData, EventFilter};
+use time::{Duration, OffsetDateTime, Time};
+use uuid::Uuid;
+
+fn new_event_data_folder() -> String {
+    let event_data = format!("./test_data/{}/event_data", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_data.replace("/event_data", ""));
+    create_folder(&event_data);
+    event_data
+}
+
+async fn add_signed_event(event_db: &EventData) -> (Uuid, MaybeScalar) {
+    let oracle_key = Scalar::from_slice(&[7u8; 32]).unwrap();
+    let create_event = CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc() + Duration::hours(1),
+        ..new_event()
+    };
+    let event_data = CreateEventData::new(
+        oracle_key.base_point_mul().into(),
+        Keys::generate().public_key,
+        create_event,
+    )
+    .unwrap();
+    let event = event_db.add_event(event_data).await.unwrap();
+
+    let mut sign_event = event_db
+        .get_events_to_sign(vec![event.id])
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    let attestation = MaybeScalar::from_slice(&[9u8; 32]).unwrap();
+    sign_event.attestation = Some(attestation);
+    event_db
+        .update_event_attestation(&sign_event)
+        .await
+        .unwrap();
+    (event.id, attestation)
+}
+
+#[tokio::test]
+async fn stored_attestations_read_back_identically() {
+    let event_db = EventData::new(&new_event_data_folder()).unwrap();
+    let (event_id, attestation) = add_signed_event(&event_db).await;
+
+    let event = event_db.get_event(&event_id).await.unwrap();
+    assert_eq!(event.attestation, Some(attestation));
+
+    let summaries = event_db
+        .filtered_list_events(EventFilter {
+            limit: None,
+            event_ids: Some(vec![event_id]),
+            station_id: None,
+        })
+        .await
+        .unwrap();
+    assert_eq!(summaries.len(), 1);
+    assert_eq!(summaries[0].attestation, Some(attestation));
+}
+
+#[tokio::test]
+async fn migrates_json_encoded_attestations() {
+    let event_data = new_event_data_folder();
+    let event_db = EventData::new(&event_data).unwrap();
+    let (event_id, attestation) = add_signed_event(&event_db).await;
+    drop(event_db);
+
+    // Write the attestation the way older versions of the oracle stored it
+    {
+        let conn = Connection::open(format!("{}/events.db3", event_data)).unwrap();
+        conn.execute(
+            "UPDATE events SET attestation_signature = ? WHERE id = ?",
+            params![
+                serde_json::to_vec(&attestation).unwrap(),
+                event_id.to_string()
+            ],
+        )
+        .unwrap();
+        conn.execute("UPDATE db_version SET version = 3", [])
+            .unwrap();
+    }
+
+    let event_db = EventData::new(&event_data).unwrap();
+    let event = event_db.get_event(&event_id).await.unwrap();
+    assert_eq!(event.attestation, Some(attestation));
+}
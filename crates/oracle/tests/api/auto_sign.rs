@@ -0,0 +1,217 @@
+use crate::{
+    etl_workflow::{
+        mock_entries, mock_forecast_data, mock_observation_data, mock_observation_date,
+    },
+    helpers::{mock_event, spawn_app, spawn_app_with_config, MockWeatherAccess, TestAppConfig},
+};
+use nostr_sdk::Keys;
+use oracle::{run_auto_signer, CreateEvent, EventStatus};
+use std::{sync::Arc, time::Duration as StdDuration};
+use time::{Duration, OffsetDateTime};
+use tokio::time::{sleep, timeout};
+
+#[tokio::test]
+async fn auto_signs_event_at_signing_date() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let signing_date = OffsetDateTime::now_utc() + Duration::seconds(2);
+    let auto_sign_event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                auto_sign: true,
+                ..mock_event(mock_observation_date(), signing_date)
+            },
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(auto_sign_event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    // Already past its signing date but never opted in, so it is left for the etl to sign
+    let manual_event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                mock_observation_date(),
+                OffsetDateTime::now_utc() - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+
+    // Stored dates are truncated to microseconds
+    let next_auto_sign_date = test_app
+        .oracle
+        .next_auto_sign_date()
+        .await
+        .unwrap()
+        .unwrap();
+    assert!((next_auto_sign_date - signing_date).abs() < Duration::milliseconds(1));
+    tokio::spawn(run_auto_signer(
+        test_app.oracle.clone(),
+        StdDuration::from_millis(200),
+    ));
+
+    let signed_event = timeout(StdDuration::from_secs(10), async {
+        loop {
+            let event = test_app
+                .oracle
+                .get_event(&auto_sign_event.id)
+                .await
+                .unwrap();
+            if event.attestation.is_some() {
+                return event;
+            }
+            sleep(StdDuration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("event should be auto signed");
+    assert_eq!(signed_event.status, EventStatus::Signed);
+    assert!(OffsetDateTime::now_utc() >= signing_date);
+
+    let manual_event = test_app.oracle.get_event(&manual_event.id).await.unwrap();
+    assert!(manual_event.attestation.is_none());
+    assert_eq!(test_app.oracle.next_auto_sign_date().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn waits_the_sign_buffer_past_signing_date() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let sign_buffer = Duration::seconds(3);
+    let config = TestAppConfig {
+        sign_buffer,
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(weather_data), config).await;
+
+    let signing_date = OffsetDateTime::now_utc() - Duration::seconds(1);
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                auto_sign: true,
+                ..mock_event(mock_observation_date(), signing_date)
+            },
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+
+    // Past the signing_date but still inside the buffer, neither the auto signer nor the etl sign it yet
+    assert!(test_app
+        .oracle
+        .auto_sign_events(1)
+        .await
+        .unwrap()
+        .is_empty());
+    test_app.oracle.etl_data(2).await.unwrap();
+    let unsigned = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert!(unsigned.attestation.is_none());
+    let next_auto_sign_date = test_app
+        .oracle
+        .next_auto_sign_date()
+        .await
+        .unwrap()
+        .unwrap();
+    assert!((next_auto_sign_date - (signing_date + sign_buffer)).abs() < Duration::milliseconds(1));
+
+    tokio::spawn(run_auto_signer(
+        test_app.oracle.clone(),
+        StdDuration::from_millis(200),
+    ));
+    let signed_event = timeout(StdDuration::from_secs(10), async {
+        loop {
+            let event = test_app.oracle.get_event(&event.id).await.unwrap();
+            if event.attestation.is_some() {
+                return event;
+            }
+            sleep(StdDuration::from_millis(100)).await;
+        }
+    })
+    .await
+    .expect("event should be auto signed once the buffer passes");
+    assert_eq!(signed_event.status, EventStatus::Signed);
+    assert!(OffsetDateTime::now_utc() >= signing_date + sign_buffer);
+}
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn signs_once_when_the_etl_and_auto_signer_run_together() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                auto_sign: true,
+                ..mock_event(
+                    mock_observation_date(),
+                    OffsetDateTime::now_utc() - Duration::hours(1),
+                )
+            },
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+
+    // `/oracle/update` kicks off the etl on its own task while the auto signer is mid pass
+    let etl = tokio::spawn({
+        let oracle = test_app.oracle.clone();
+        async move { oracle.etl_data(1).await }
+    });
+    let auto_signed = test_app.oracle.auto_sign_events(2).await.unwrap();
+    etl.await.unwrap().unwrap();
+
+    // Whichever pass got the lock first signed it, the other one saw it already signed
+    assert!(auto_signed.is_empty() || auto_signed == vec![event.id]);
+    let signed_event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(signed_event.status, EventStatus::Signed);
+    assert!(signed_event.attestation.is_some());
+    test_app.oracle.etl_data(3).await.unwrap();
+    let resigned = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(resigned.attestation, signed_event.attestation);
+}
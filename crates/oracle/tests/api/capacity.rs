@@ -0,0 +1,184 @@
+use crate::{
+    etl_workflow::mock_entries,
+    helpers::{
+        create_auth_event, get, get_json, mock_event, spawn_app, MockWeatherAccess, TestApp,
+    },
+};
+use axum::{
+    body::Body,
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hyper::{header, Method};
+use nostr_sdk::{
+    hashes::{sha256::Hash as Sha256Hash, Hash},
+    Keys,
+};
+use oracle::{AddEventEntry, EventCapacity};
+use serde_json::to_string;
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime, Time};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn remaining_decrements_after_an_entry() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let observation_date =
+        (OffsetDateTime::now_utc() + Duration::days(1)).replace_time(Time::MIDNIGHT);
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(observation_date, observation_date + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+
+    let capacity: EventCapacity =
+        get_json(&test_app, &format!("/oracle/events/{}/capacity", event.id)).await;
+    assert_eq!(
+        capacity,
+        EventCapacity {
+            total_allowed: 4,
+            total_entries: 0,
+            remaining: 4,
+            accepting: true,
+        }
+    );
+
+    let [entry, ..] = mock_entries(event.id);
+    test_app
+        .event_data
+        .add_event_entry(entry.into())
+        .await
+        .unwrap();
+
+    let capacity: EventCapacity =
+        get_json(&test_app, &format!("/oracle/events/{}/capacity", event.id)).await;
+    assert_eq!(
+        capacity,
+        EventCapacity {
+            total_allowed: 4,
+            total_entries: 1,
+            remaining: 3,
+            accepting: true,
+        }
+    );
+}
+
+#[tokio::test]
+async fn not_accepting_once_past_the_entry_cutoff() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let observation_date = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT);
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(observation_date, observation_date + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+
+    let capacity: EventCapacity =
+        get_json(&test_app, &format!("/oracle/events/{}/capacity", event.id)).await;
+    assert_eq!(capacity.remaining, 4);
+    assert!(!capacity.accepting);
+
+    let (status, _) = get(
+        &test_app,
+        &format!("/oracle/events/{}/capacity", Uuid::now_v7()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+async fn post_entry(test_app: &TestApp, keys: &Keys, entry: &AddEventEntry) -> StatusCode {
+    let body_json = to_string(entry).unwrap();
+    let path = format!("/oracle/events/{}/entry", entry.event_id);
+    let auth_event = create_auth_event(
+        "POST",
+        &format!("http://localhost:3000{}", path),
+        Some(Sha256Hash::hash(body_json.as_bytes())),
+        keys,
+    )
+    .await;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::AUTHORIZATION,
+            format!(
+                "Nostr {}",
+                BASE64.encode(serde_json::to_string(&auth_event).unwrap())
+            ),
+        )
+        .header("host", "localhost:3000")
+        .body(Body::from(body_json))
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    response.status()
+}
+
+#[tokio::test]
+async fn rejects_entries_once_the_event_is_full() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let observation_date =
+        (OffsetDateTime::now_utc() + Duration::days(1)).replace_time(Time::MIDNIGHT);
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(observation_date, observation_date + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+    let entries = mock_entries(event.id);
+    for entry in &entries {
+        assert_eq!(post_entry(&test_app, &keys, entry).await, StatusCode::OK);
+    }
+
+    let capacity: EventCapacity =
+        get_json(&test_app, &format!("/oracle/events/{}/capacity", event.id)).await;
+    assert_eq!(
+        capacity,
+        EventCapacity {
+            total_allowed: 4,
+            total_entries: 4,
+            remaining: 0,
+            accepting: false,
+        }
+    );
+
+    let one_too_many = AddEventEntry {
+        id: Uuid::now_v7(),
+        ..entries[0].clone()
+    };
+    assert_eq!(
+        post_entry(&test_app, &keys, &one_too_many).await,
+        StatusCode::BAD_REQUEST
+    );
+    // The stored entries can't go over the limit either, whichever check the entry gets past first
+    assert!(test_app
+        .event_data
+        .add_event_entry(one_too_many.into())
+        .await
+        .is_err());
+    assert_eq!(
+        test_app
+            .event_data
+            .get_event_total_entries(event.id)
+            .await
+            .unwrap(),
+        4
+    );
+}
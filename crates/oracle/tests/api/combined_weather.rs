@@ -0,0 +1,206 @@
+use crate::helpers::random_test_number;
+use duckdb::Connection;
+use oracle::{create_folder, weather_data::WeatherAccess, FileAccess};
+use std::sync::Arc;
+use time::{
+    macros::{date, datetime},
+    Duration,
+};
+
+fn write_fixture(data_dir: &str, folder: &str, file_name: &str, rows: &str) {
+    create_folder(&format!("{}/{}", data_dir, folder));
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!(
+        "COPY ({}) TO '{}/{}/{}' (FORMAT PARQUET);",
+        rows, data_dir, folder, file_name
+    ))
+    .unwrap();
+}
+
+fn forecast_rows(rows: &[(&str, &str, &str, &str, i64)]) -> String {
+    rows.iter()
+        .map(|(station_id, generated_at, begin_time, end_time, max_temp)| {
+            format!(
+                "SELECT '{station_id}' AS station_id, '{station_id} airport' AS station_name,
+                    '{generated_at}' AS generated_at, '{begin_time}' AS begin_time, '{end_time}' AS end_time,
+                    {max_temp}::BIGINT AS max_temp, 60::BIGINT AS min_temp, 8::BIGINT AS wind_speed,
+                    180::BIGINT AS wind_direction, 90::BIGINT AS relative_humidity_max,
+                    40::BIGINT AS relative_humidity_min, 0.1::DOUBLE AS liquid_precipitation_amt,
+                    20::BIGINT AS twelve_hour_probability_of_precipitation"
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" UNION ALL ")
+}
+
+fn observation_rows(rows: &[(&str, &str, f64)]) -> String {
+    rows.iter()
+        .map(|(station_id, generated_at, temperature)| {
+            format!(
+                "SELECT '{station_id}' AS station_id, '{station_id} airport' AS station_name,
+                    '{generated_at}' AS generated_at, {temperature}::DOUBLE AS temperature_value,
+                    5::BIGINT AS wind_speed, 200::BIGINT AS wind_direction, 15.0::DOUBLE AS dewpoint_value"
+            )
+        })
+        .collect::<Vec<String>>()
+        .join(" UNION ALL ")
+}
+
+#[tokio::test]
+async fn can_write_combined_forecast_and_observation_parquet() {
+    let root = format!("./test_data/{}", random_test_number());
+    let data_dir = format!("{}/weather_data", root);
+    create_folder("./test_data");
+    create_folder(&root);
+    create_folder(&data_dir);
+
+    write_fixture(
+        &data_dir,
+        "2024-08-11",
+        "forecasts_2024-08-11T12:00:00Z.parquet",
+        &forecast_rows(&[
+            (
+                "KSAW",
+                "2024-08-11T12:00:00Z",
+                "2024-08-12T12:00:00Z",
+                "2024-08-12T18:00:00Z",
+                80,
+            ),
+            (
+                "PFNO",
+                "2024-08-11T12:00:00Z",
+                "2024-08-12T12:00:00Z",
+                "2024-08-12T18:00:00Z",
+                55,
+            ),
+        ]),
+    );
+    // A later pull updates the KSAW forecast and adds the evening period
+    write_fixture(
+        &data_dir,
+        "2024-08-12",
+        "forecasts_2024-08-12T00:00:00Z.parquet",
+        &forecast_rows(&[
+            (
+                "KSAW",
+                "2024-08-12T00:00:00Z",
+                "2024-08-12T12:00:00Z",
+                "2024-08-12T18:00:00Z",
+                82,
+            ),
+            (
+                "KSAW",
+                "2024-08-12T00:00:00Z",
+                "2024-08-12T18:00:00Z",
+                "2024-08-13T00:00:00Z",
+                75,
+            ),
+            // Outside of the requested day
+            (
+                "KSAW",
+                "2024-08-12T00:00:00Z",
+                "2024-08-13T12:00:00Z",
+                "2024-08-13T18:00:00Z",
+                90,
+            ),
+        ]),
+    );
+    write_fixture(
+        &data_dir,
+        "2024-08-12",
+        "observations_2024-08-12T15:00:00Z.parquet",
+        &observation_rows(&[
+            ("KSAW", "2024-08-12T13:00:00Z", 27.5),
+            ("KSAW", "2024-08-12T14:00:00Z", 28.0),
+            ("KDEN", "2024-08-12T13:00:00Z", 21.0),
+        ]),
+    );
+    // The same KSAW reading shows up again in the next pull
+    write_fixture(
+        &data_dir,
+        "2024-08-12",
+        "observations_2024-08-12T16:00:00Z.parquet",
+        &observation_rows(&[("KSAW", "2024-08-12T14:00:00Z", 28.0)]),
+    );
+
+    let weather_access = WeatherAccess::new(Arc::new(FileAccess::new(data_dir.clone()))).unwrap();
+    let file_path = format!("{}/combined_2024-08-12.parquet", root);
+    weather_access
+        .write_combined_parquet(date!(2024 - 08 - 12), &file_path)
+        .await
+        .unwrap();
+
+    let conn = Connection::open_in_memory().unwrap();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT
+                station_id,
+                epoch_us(forecast_begin_time),
+                forecast_max_temp,
+                epoch_us(observed_at),
+                observed_temperature,
+                observed_dewpoint
+            FROM read_parquet('{}')
+            ORDER BY station_id, forecast_begin_time NULLS LAST, observed_at",
+            file_path
+        ))
+        .unwrap();
+    let rows: Vec<(
+        String,
+        Option<i64>,
+        Option<i64>,
+        Option<i64>,
+        Option<f64>,
+        Option<f64>,
+    )> = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get(0)?,
+                row.get(1)?,
+                row.get(2)?,
+                row.get(3)?,
+                row.get(4)?,
+                row.get(5)?,
+            ))
+        })
+        .unwrap()
+        .map(|row| row.unwrap())
+        .collect();
+
+    let at = |hour: i64| {
+        Some((datetime!(2024-08-12 0:00 UTC) + Duration::hours(hour)).unix_timestamp() * 1_000_000)
+    };
+    let expected = vec![
+        // Observation station without any forecasts
+        (
+            String::from("KDEN"),
+            None,
+            None,
+            at(13),
+            Some(21.0),
+            Some(15.0),
+        ),
+        // Both observations fall in the latest forecast of the first period
+        (
+            String::from("KSAW"),
+            at(12),
+            Some(82),
+            at(13),
+            Some(27.5),
+            Some(15.0),
+        ),
+        (
+            String::from("KSAW"),
+            at(12),
+            Some(82),
+            at(14),
+            Some(28.0),
+            Some(15.0),
+        ),
+        // Forecast period without observations
+        (String::from("KSAW"), at(18), Some(75), None, None, None),
+        // Forecast station without any observations
+        (String::from("PFNO"), at(12), Some(55), None, None, None),
+    ];
+    assert_eq!(rows, expected);
+}
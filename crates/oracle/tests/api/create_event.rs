@@ -1,19 +1,25 @@
-use crate::helpers::{create_auth_event, spawn_app, MockWeatherAccess};
+use crate::helpers::{
+    create_auth_event, spawn_app, spawn_app_with_config, MockWeatherAccess, TestAppConfig,
+};
 use axum::{
     body::{to_bytes, Body},
     http::Request,
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
-use dlctix::Outcome;
+use dlctix::{secp::Scalar, Outcome};
 use hyper::{header, Method};
 use nostr_sdk::{
     hashes::{sha256::Hash as Sha256Hash, Hash},
     Keys,
 };
-use oracle::{CreateEvent, Event};
+use oracle::{
+    oracle::{Error, DEFAULT_MAX_LOCATIONS_PER_EVENT},
+    truncate_to_sql_precision, CloneEvent, CreateEvent, Event, FixedNonceSource, ScoringMode,
+    WinMode,
+};
 use serde_json::{from_slice, to_string};
 use std::sync::Arc;
-use time::OffsetDateTime;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time, UtcOffset};
 use tower::ServiceExt;
 use uuid::Uuid;
 
@@ -25,17 +31,24 @@ async fn can_create_oracle_event() {
     let keys = Keys::generate();
     let new_event = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
-            String::from("PFNO"),
             String::from("KSAW"),
-            String::from("PAPG"),
             String::from("KWMC"),
+            String::from("PAPG"),
+            String::from("PFNO"),
         ],
         total_allowed_entries: 5,
         number_of_places_win: 3,
         number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
 
     let body_json = to_string(&new_event).unwrap();
@@ -72,7 +85,10 @@ async fn can_create_oracle_event() {
 
     let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
     let res: Event = from_slice(&body).unwrap();
-    assert_eq!(res.signing_date, new_event.signing_date);
+    assert_eq!(
+        res.signing_date,
+        truncate_to_sql_precision(new_event.signing_date)
+    );
     assert_eq!(res.locations, new_event.locations);
     assert_eq!(
         res.total_allowed_entries,
@@ -100,17 +116,24 @@ async fn can_create_and_get_oracle_event() {
 
     let new_event = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
-            String::from("PFNO"),
             String::from("KSAW"),
-            String::from("PAPG"),
             String::from("KWMC"),
+            String::from("PAPG"),
+            String::from("PFNO"),
         ],
         total_allowed_entries: 5,
         number_of_values_per_entry: 6,
         number_of_places_win: 3,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
     let body_json = to_string(&new_event).unwrap();
     let payload_hash = Sha256Hash::hash(body_json.as_bytes());
@@ -169,17 +192,11 @@ async fn can_create_and_get_oracle_event() {
     let res: Event = from_slice(&body).unwrap();
     assert_eq!(
         res.signing_date,
-        new_event
-            .signing_date
-            .replace_nanosecond(new_event.signing_date.nanosecond() / 1_000 * 1_000)
-            .unwrap()
+        truncate_to_sql_precision(new_event.signing_date)
     );
     assert_eq!(
         res.observation_date,
-        new_event
-            .observation_date
-            .replace_nanosecond(new_event.observation_date.nanosecond() / 1_000 * 1_000)
-            .unwrap()
+        truncate_to_sql_precision(new_event.observation_date)
     );
     assert_eq!(res.locations, new_event.locations);
     assert_eq!(
@@ -198,3 +215,440 @@ async fn can_create_and_get_oracle_event() {
         .event_announcement
         .is_valid_outcome(&Outcome::Attestation(1)));
 }
+
+#[tokio::test]
+async fn can_create_event_with_offset_observation_date_at_midnight_utc() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T05:00:00+05:00", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T05:00:00+05:00", &Rfc3339).unwrap(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_places_win: 1,
+        number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+
+    let res = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    assert_eq!(res.observation_date.offset(), UtcOffset::UTC);
+    assert_eq!(
+        res.observation_date,
+        OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap()
+    );
+    assert_eq!(res.signing_date.offset(), UtcOffset::UTC);
+}
+
+#[tokio::test]
+async fn rejects_offset_observation_date_not_midnight_utc() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+05:00", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+05:00", &Rfc3339).unwrap(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_places_win: 1,
+        number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+
+    let err = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap_err();
+
+    assert!(err.to_string().contains("2024-08-11T19:00:00Z"));
+}
+
+#[tokio::test]
+async fn rejects_event_with_too_many_locations() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00Z", &Rfc3339).unwrap(),
+        locations: (0..=DEFAULT_MAX_LOCATIONS_PER_EVENT)
+            .map(|index| format!("K{:03}", index))
+            .collect(),
+        total_allowed_entries: 5,
+        number_of_places_win: 1,
+        number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+
+    let err = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap_err();
+
+    assert!(matches!(err, Error::BadEvent(_)));
+    assert!(err.to_string().contains(&format!(
+        "requested: {}",
+        DEFAULT_MAX_LOCATIONS_PER_EVENT + 1
+    )));
+}
+
+#[tokio::test]
+async fn deduplicates_event_locations() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00Z", &Rfc3339).unwrap(),
+        locations: vec![
+            String::from("PFNO"),
+            String::from("KSAW"),
+            String::from("PFNO"),
+            String::from("KWMC"),
+            String::from("KSAW"),
+        ],
+        total_allowed_entries: 5,
+        number_of_places_win: 1,
+        number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+
+    let res = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    let expected = vec![
+        String::from("KSAW"),
+        String::from("KWMC"),
+        String::from("PFNO"),
+    ];
+    assert_eq!(res.locations, expected);
+    let stored = test_app.oracle.get_event(&res.id).await.unwrap();
+    assert_eq!(stored.locations, expected);
+}
+
+#[tokio::test]
+async fn stores_event_locations_in_the_same_order_regardless_of_input_order() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let expected = vec![
+        String::from("KSAW"),
+        String::from("KWMC"),
+        String::from("PAPG"),
+        String::from("PFNO"),
+    ];
+
+    for locations in [
+        vec!["PFNO", "KSAW", "PAPG", "KWMC"],
+        vec!["kwmc", "PAPG", "KSAW", "pfno"],
+        vec!["KSAW", "KWMC", "PAPG", "PFNO"],
+    ] {
+        let new_event = CreateEvent {
+            id: Uuid::now_v7(),
+            observation_date: OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap(),
+            signing_date: OffsetDateTime::parse("2024-08-13T00:00:00Z", &Rfc3339).unwrap(),
+            locations: locations.into_iter().map(String::from).collect(),
+            total_allowed_entries: 5,
+            number_of_places_win: 1,
+            number_of_values_per_entry: 6,
+            win_mode: WinMode::TopN,
+            score_threshold: None,
+            auto_sign: false,
+            scoring_mode: ScoringMode::Linear,
+            station_group: None,
+            late_entry_grace_seconds: 0,
+            timezone: None,
+        };
+
+        let res = test_app
+            .oracle
+            .create_event(keys.public_key, new_event)
+            .await
+            .unwrap();
+
+        assert_eq!(res.locations, expected);
+        let stored = test_app.oracle.get_event(&res.id).await.unwrap();
+        assert_eq!(stored.locations, expected);
+    }
+}
+
+#[tokio::test]
+async fn can_clone_oracle_event() {
+    let base_url = "http://localhost:3000";
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let source_event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                id: Uuid::now_v7(),
+                observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+                signing_date: OffsetDateTime::now_utc(),
+                locations: vec![String::from("PFNO"), String::from("KSAW")],
+                total_allowed_entries: 10,
+                number_of_places_win: 1,
+                number_of_values_per_entry: 4,
+                win_mode: WinMode::Threshold,
+                score_threshold: Some(20),
+                auto_sign: false,
+                scoring_mode: ScoringMode::Linear,
+                station_group: None,
+                late_entry_grace_seconds: 0,
+                timezone: None,
+            },
+        )
+        .await
+        .unwrap();
+
+    let next_day = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) + Duration::days(1);
+    let clone = CloneEvent {
+        id: Uuid::now_v7(),
+        observation_date: next_day,
+        signing_date: next_day + Duration::days(1),
+    };
+    let body_json = to_string(&clone).unwrap();
+    let payload_hash = Sha256Hash::hash(body_json.as_bytes());
+    let path = format!("/oracle/events/{}/clone", source_event.id);
+    let event = create_auth_event(
+        "POST",
+        &format!("{}{}", base_url, path),
+        Some(payload_hash),
+        &keys,
+    )
+    .await;
+    let auth_header = format!(
+        "Nostr {}",
+        BASE64.encode(serde_json::to_string(&event).unwrap())
+    );
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, auth_header)
+        .header("host", "localhost:3000")
+        .body(Body::from(body_json))
+        .unwrap();
+
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let res: Event = from_slice(&body).unwrap();
+    assert_eq!(res.id, clone.id);
+    assert_ne!(res.id, source_event.id);
+    assert_eq!(res.observation_date, clone.observation_date);
+    assert_eq!(res.signing_date, clone.signing_date);
+    assert_eq!(res.locations, source_event.locations);
+    assert_eq!(
+        res.total_allowed_entries,
+        source_event.total_allowed_entries
+    );
+    assert_eq!(res.number_of_places_win, source_event.number_of_places_win);
+    assert_eq!(
+        res.number_of_values_per_entry,
+        source_event.number_of_values_per_entry
+    );
+    assert_eq!(res.win_mode, source_event.win_mode);
+    assert_eq!(res.score_threshold, source_event.score_threshold);
+    assert_ne!(res.nonce, source_event.nonce);
+    assert_ne!(
+        res.event_announcement.locking_points,
+        source_event.event_announcement.locking_points
+    );
+    assert!(res.entry_ids.is_empty());
+}
+
+#[tokio::test]
+async fn cannot_clone_missing_event() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let next_day = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) + Duration::days(1);
+    let res = test_app
+        .oracle
+        .clone_event(
+            keys.public_key,
+            &Uuid::now_v7(),
+            CloneEvent {
+                id: Uuid::now_v7(),
+                observation_date: next_day,
+                signing_date: next_day + Duration::days(1),
+            },
+        )
+        .await;
+    assert!(matches!(res, Err(Error::NotFound(_))));
+}
+
+#[tokio::test]
+async fn fixed_nonce_source_gives_identical_announcements() {
+    let nonce = Scalar::from_slice(&[9u8; 32]).unwrap();
+    let config = TestAppConfig {
+        nonce_source: Arc::new(FixedNonceSource::new(nonce)),
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config).await;
+    let keys = Keys::generate();
+    let new_event = |id: Uuid| CreateEvent {
+        id,
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00Z", &Rfc3339).unwrap(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 3,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+
+    let first = test_app
+        .oracle
+        .create_event(keys.public_key, new_event(Uuid::now_v7()))
+        .await
+        .unwrap();
+    let second = test_app
+        .oracle
+        .create_event(keys.public_key, new_event(Uuid::now_v7()))
+        .await
+        .unwrap();
+
+    assert_ne!(first.id, second.id);
+    assert_eq!(first.nonce, nonce);
+    assert_eq!(second.nonce, nonce);
+    let first_announcement = test_app
+        .oracle
+        .get_event_announcement(&first.id)
+        .await
+        .unwrap();
+    let second_announcement = test_app
+        .oracle
+        .get_event_announcement(&second.id)
+        .await
+        .unwrap();
+    assert_eq!(first_announcement.nonce_point, nonce.base_point_mul());
+    assert_eq!(
+        first_announcement.nonce_point,
+        second_announcement.nonce_point
+    );
+    assert_eq!(
+        first_announcement.outcome_messages,
+        second_announcement.outcome_messages
+    );
+    assert_eq!(
+        first_announcement.event_announcement,
+        second_announcement.event_announcement
+    );
+}
+
+fn places_event(
+    total_allowed_entries: usize,
+    number_of_places_win: i64,
+    number_of_values_per_entry: usize,
+    score_threshold: Option<i64>,
+) -> CreateEvent {
+    CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00Z", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00Z", &Rfc3339).unwrap(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries,
+        number_of_places_win,
+        number_of_values_per_entry,
+        win_mode: match score_threshold {
+            Some(_) => WinMode::Threshold,
+            None => WinMode::TopN,
+        },
+        score_threshold,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    }
+}
+
+#[tokio::test]
+async fn can_create_event_with_as_many_places_as_entries_and_scores() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+
+    // A single pick scores 0, 10 or 20
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, places_event(3, 3, 1, None))
+        .await
+        .unwrap();
+    assert_eq!(event.number_of_places_win, 3);
+
+    // Two picks at a threshold of 25 can win with 30 or 40
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, places_event(5, 2, 2, Some(25)))
+        .await
+        .unwrap();
+    assert_eq!(event.number_of_places_win, 2);
+}
+
+#[tokio::test]
+async fn rejects_event_with_impossible_number_of_places_win() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+
+    for (event, reason) in [
+        (places_event(3, 4, 6, None), "total_allowed_entries"),
+        (places_event(5, 4, 1, None), "different winning scores"),
+        (places_event(5, 3, 2, Some(25)), "different winning scores"),
+        (places_event(5, 0, 6, None), "at least 1"),
+    ] {
+        let err = test_app
+            .oracle
+            .create_event(keys.public_key, event)
+            .await
+            .unwrap_err();
+
+        assert!(matches!(err, Error::BadEvent(_)));
+        assert!(err.to_string().contains(reason), "{}", err);
+    }
+}
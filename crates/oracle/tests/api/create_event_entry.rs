@@ -1,19 +1,26 @@
-use crate::helpers::{create_auth_event, spawn_app, MockWeatherAccess};
+use crate::helpers::{
+    create_auth_event, spawn_app, spawn_app_with_entry_grace_period, MockWeatherAccess, TestApp,
+};
 use axum::{
     body::{to_bytes, Body},
-    http::Request,
+    http::{Request, StatusCode},
 };
 use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use duckdb::types::{OrderedMap, Value};
 use hyper::{header, Method};
 use log::info;
 use nostr_sdk::{
     hashes::{sha256::Hash as Sha256Hash, Hash},
+    nips::nip19::ToBech32,
     Keys,
 };
-use oracle::{AddEventEntry, CreateEvent, WeatherChoices, WeatherEntry};
+use oracle::{
+    oracle::Error, AddEventEntry, CreateEvent, ScoringMode, ValueOptions, WeatherChoices,
+    WeatherEntry, WinMode,
+};
 use serde_json::{from_slice, to_string};
 use std::sync::Arc;
-use time::OffsetDateTime;
+use time::{Duration, OffsetDateTime, Time};
 use tower::ServiceExt;
 use uuid::Uuid;
 
@@ -24,7 +31,8 @@ async fn can_create_entry_into_event() {
     let oracle_event_id = Uuid::now_v7();
     let new_event = CreateEvent {
         id: oracle_event_id,
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+            + Duration::days(1),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
             String::from("PFNO"),
@@ -35,6 +43,13 @@ async fn can_create_entry_into_event() {
         total_allowed_entries: 5,
         number_of_values_per_entry: 6,
         number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
 
     let new_entry = AddEventEntry {
@@ -113,7 +128,8 @@ async fn can_create_and_get_event_entry() {
     let keys = Keys::generate();
     let new_event = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+            + Duration::days(1),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
             String::from("PFNO"),
@@ -124,6 +140,13 @@ async fn can_create_and_get_event_entry() {
         total_allowed_entries: 10,
         number_of_places_win: 1,
         number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
     let new_entry = AddEventEntry {
         id: Uuid::now_v7(),
@@ -216,3 +239,301 @@ async fn can_create_and_get_event_entry() {
     assert_eq!(res_post.score, res.score);
     assert_eq!(res_post.expected_observations, res.expected_observations);
 }
+
+#[tokio::test]
+async fn rejects_entry_with_unknown_weather_choice() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+            + Duration::days(1),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: vec![
+            String::from("PFNO"),
+            String::from("KSAW"),
+            String::from("PAPG"),
+            String::from("KWMC"),
+        ],
+        total_allowed_entries: 5,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let oracle_event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    let body_json = serde_json::json!({
+        "id": Uuid::now_v7(),
+        "event_id": oracle_event.id,
+        "expected_observations": [
+            {
+                "stations": "PFNO",
+                "temp_low": "Par",
+                "temp_high": null,
+                "wind_speed": "ovr"
+            }
+        ]
+    })
+    .to_string();
+    let payload_hash = Sha256Hash::hash(body_json.as_bytes());
+
+    let base_url = "http://localhost:3000";
+    let path = format!("/oracle/events/{}/entry", oracle_event.id);
+    let event = create_auth_event(
+        "POST",
+        &format!("{}{}", base_url, path),
+        Some(payload_hash),
+        &keys,
+    )
+    .await;
+
+    let auth_header = format!(
+        "Nostr {}",
+        BASE64.encode(serde_json::to_string(&event).unwrap())
+    );
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(header::AUTHORIZATION, auth_header)
+        .header("host", "localhost:3000")
+        .body(Body::from(body_json))
+        .unwrap();
+
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::BAD_REQUEST);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let res: serde_json::Value = from_slice(&body).unwrap();
+    assert!(res["error"].as_str().unwrap().contains("ovr"));
+}
+
+#[test]
+fn value_options_lists_every_choice_once() {
+    assert_eq!(
+        ValueOptions::all(),
+        vec![ValueOptions::Over, ValueOptions::Par, ValueOptions::Under]
+    );
+    assert_eq!(ValueOptions::count(), 3);
+    for option in ValueOptions::iter() {
+        assert_eq!(ValueOptions::try_from(option.to_string()).unwrap(), option);
+    }
+    assert!(ValueOptions::try_from("Over").is_err());
+}
+
+async fn add_entry_with_cutoff_in(cutoff_from_now: Duration) -> Result<WeatherEntry, Error> {
+    // Events observe from midnight UTC, so the grace period moves the cutoff relative to now
+    let observation_date = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT);
+    let entry_grace_period = (OffsetDateTime::now_utc() - observation_date) + cutoff_from_now;
+    let test_app =
+        spawn_app_with_entry_grace_period(Arc::new(MockWeatherAccess::new()), entry_grace_period)
+            .await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date,
+        signing_date: OffsetDateTime::now_utc() + Duration::days(1),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    let new_entry = AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id: event.id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("PFNO"),
+            temp_low: Some(oracle::ValueOptions::Par),
+            temp_high: None,
+            wind_speed: None,
+        }],
+    };
+    test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry)
+        .await
+}
+
+#[tokio::test]
+async fn accepts_entry_just_before_cutoff() {
+    let res = add_entry_with_cutoff_in(Duration::minutes(1)).await;
+    assert!(res.is_ok(), "entry was rejected: {:?}", res.err());
+}
+
+#[tokio::test]
+async fn rejects_entry_just_after_cutoff() {
+    let res = add_entry_with_cutoff_in(Duration::minutes(-1)).await;
+    assert!(matches!(res, Err(Error::EventExpired(_))));
+}
+
+async fn create_event_with_entry(test_app: &TestApp, keys: &Keys) -> WeatherEntry {
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+            + Duration::days(1),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    let new_entry = AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id: event.id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("KSAW"),
+            temp_low: None,
+            temp_high: Some(oracle::ValueOptions::Over),
+            wind_speed: None,
+        }],
+    };
+    test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry)
+        .await
+        .unwrap()
+}
+
+async fn get_coordinator_entries(
+    test_app: &TestApp,
+    coordinator: &str,
+    keys: &Keys,
+) -> axum::response::Response {
+    let path = format!("/oracle/entries?coordinator={}", coordinator);
+    let event =
+        create_auth_event("GET", &format!("http://localhost:3000{}", path), None, keys).await;
+    let auth_header = format!(
+        "Nostr {}",
+        BASE64.encode(serde_json::to_string(&event).unwrap())
+    );
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(path)
+        .header(header::AUTHORIZATION, auth_header)
+        .header("host", "localhost:3000")
+        .body(Body::empty())
+        .unwrap();
+
+    test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn can_get_entries_by_coordinator() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let other_keys = Keys::generate();
+    let first_entry = create_event_with_entry(&test_app, &keys).await;
+    let second_entry = create_event_with_entry(&test_app, &keys).await;
+    create_event_with_entry(&test_app, &other_keys).await;
+
+    let npub = keys.public_key.to_bech32().unwrap();
+    let response = get_coordinator_entries(&test_app, &npub, &keys).await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let res: Vec<WeatherEntry> = from_slice(&body).unwrap();
+
+    let mut entry_ids: Vec<Uuid> = res.iter().map(|entry| entry.id).collect();
+    entry_ids.sort();
+    let mut expected_ids = vec![first_entry.id, second_entry.id];
+    expected_ids.sort();
+    assert_eq!(entry_ids, expected_ids);
+    for entry in res {
+        assert_eq!(entry.coordinator_pubkey, Some(npub.clone()));
+        assert_eq!(entry.expected_observations.len(), 1);
+    }
+}
+
+#[tokio::test]
+async fn rejects_getting_another_coordinators_entries() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let other_keys = Keys::generate();
+    create_event_with_entry(&test_app, &keys).await;
+
+    let npub = keys.public_key.to_bech32().unwrap();
+    let response = get_coordinator_entries(&test_app, &npub, &other_keys).await;
+    assert_eq!(response.status(), StatusCode::FORBIDDEN);
+}
+
+#[tokio::test]
+async fn get_event_includes_the_coordinator_of_each_entry() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let entry = create_event_with_entry(&test_app, &keys).await;
+
+    let event = test_app.oracle.get_event(&entry.event_id).await.unwrap();
+
+    let npub = keys.public_key.to_bech32().unwrap();
+    assert_eq!(event.entries.len(), 1);
+    assert_eq!(event.entries[0].id, entry.id);
+    assert_eq!(event.entries[0].coordinator_pubkey, Some(npub));
+}
+
+#[test]
+fn converts_the_coordinator_of_a_struct_entry() {
+    let entry_id = Uuid::now_v7();
+    let event_id = Uuid::now_v7();
+    let npub = Keys::generate().public_key.to_bech32().unwrap();
+    let raw_entry = |coordinator_pubkey: Value| {
+        OrderedMap::from(vec![
+            (String::from("id"), Value::Text(entry_id.to_string())),
+            (String::from("event_id"), Value::Text(event_id.to_string())),
+            (String::from("expected_observations"), Value::List(vec![])),
+            (String::from("score"), Value::Int(3)),
+            (String::from("coordinator_pubkey"), coordinator_pubkey),
+        ])
+    };
+
+    let entry: WeatherEntry = (&raw_entry(Value::Text(npub.clone()))).try_into().unwrap();
+    assert_eq!(entry.id, entry_id);
+    assert_eq!(entry.event_id, event_id);
+    assert_eq!(entry.score, Some(3));
+    assert_eq!(entry.coordinator_pubkey, Some(npub));
+
+    let entry: WeatherEntry = (&raw_entry(Value::Null)).try_into().unwrap();
+    assert_eq!(entry.coordinator_pubkey, None);
+}
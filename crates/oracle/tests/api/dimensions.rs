@@ -0,0 +1,73 @@
+use crate::helpers::{get, new_event, spawn_app, MockWeatherAccess};
+use axum::http::StatusCode;
+use nostr_sdk::Keys;
+use oracle::{Dimension, DimensionOptions, EventDimensions, ValueOptions};
+use serde_json::{from_slice, Value};
+use std::sync::Arc;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn returns_the_default_dimensions() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event())
+        .await
+        .unwrap();
+
+    let (status, body) = get(
+        &test_app,
+        &format!("/oracle/events/{}/dimensions", event.id),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::OK);
+    let dimensions: EventDimensions = from_slice(&body).unwrap();
+    let options = vec![ValueOptions::Over, ValueOptions::Par, ValueOptions::Under];
+    assert_eq!(
+        dimensions,
+        EventDimensions {
+            id: event.id,
+            dimensions: vec![
+                DimensionOptions {
+                    dimension: Dimension::TempHigh,
+                    units: String::from("fahrenheit"),
+                    options: options.clone(),
+                },
+                DimensionOptions {
+                    dimension: Dimension::TempLow,
+                    units: String::from("fahrenheit"),
+                    options: options.clone(),
+                },
+                DimensionOptions {
+                    dimension: Dimension::WindSpeed,
+                    units: String::from("mph"),
+                    options,
+                },
+            ],
+        }
+    );
+    // Named like the fields of an entry's choices
+    let json: Value = from_slice(&body).unwrap();
+    let names: Vec<&str> = json["dimensions"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|dimension| dimension["dimension"].as_str().unwrap())
+        .collect();
+    assert_eq!(names, vec!["temp_high", "temp_low", "wind_speed"]);
+}
+
+#[tokio::test]
+async fn unknown_event_has_no_dimensions() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let (status, _) = get(
+        &test_app,
+        &format!("/oracle/events/{}/dimensions", Uuid::now_v7()),
+    )
+    .await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
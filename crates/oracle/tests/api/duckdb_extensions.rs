@@ -0,0 +1,68 @@
+use crate::helpers::random_test_number;
+use duckdb::Connection;
+use oracle::{create_folder, EventData, EventDbError};
+
+fn event_dir() -> String {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_dir);
+    event_dir
+}
+
+#[tokio::test]
+async fn loaded_parquet_extension_can_query_fixture_file() {
+    let event_dir = event_dir();
+    let fixture = format!("{}/stations.parquet", event_dir);
+    Connection::open_in_memory()
+        .unwrap()
+        .execute_batch(&format!(
+            "COPY (SELECT * FROM (VALUES ('KSAW', 46.35), ('PFNO', 67.57)) stations(station_id, latitude))
+            TO '{}' (FORMAT PARQUET);",
+            fixture
+        ))
+        .unwrap();
+
+    let event_db = EventData::new(&event_dir)
+        .unwrap()
+        .with_extensions(&[String::from(" Parquet ")])
+        .unwrap();
+    assert_eq!(event_db.extensions(), [String::from("parquet")]);
+
+    let conn = event_db.new_readonly_connection_retry().await.unwrap();
+    let loaded: bool = conn
+        .query_row(
+            "SELECT loaded FROM duckdb_extensions() WHERE extension_name = 'parquet'",
+            [],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert!(loaded);
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT station_id FROM read_parquet('{}') WHERE latitude > 50",
+            fixture
+        ))
+        .unwrap();
+    let stations: Vec<String> = stmt
+        .query_map([], |row| row.get(0))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    assert_eq!(stations, vec![String::from("PFNO")]);
+}
+
+#[test]
+fn rejects_invalid_extension_names() {
+    let event_dir = event_dir();
+
+    for name in ["parquet; DROP TABLE events", "", "http-fs"] {
+        let result = EventData::new(&event_dir)
+            .unwrap()
+            .with_extensions(&[String::from(name)]);
+        assert!(
+            matches!(result, Err(EventDbError::InvalidExtension(_))),
+            "{}",
+            name
+        );
+    }
+}
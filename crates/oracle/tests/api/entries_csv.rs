@@ -0,0 +1,94 @@
+use crate::helpers::{new_event, spawn_app, station_ids, MockWeatherAccess};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use hyper::{header::CONTENT_TYPE, Method};
+use nostr_sdk::Keys;
+use oracle::{AddEventEntry, CreateEvent, ValueOptions, WeatherChoices, WeatherEntry};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn can_export_event_entries_as_csv() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                locations: station_ids(&["KSAW", "PFNO"]),
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    // Picks on KSAW only, so the PFNO row is left blank
+    let entry: WeatherEntry = AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id: event.id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("KSAW"),
+            temp_high: Some(ValueOptions::Over),
+            temp_low: Some(ValueOptions::Under),
+            wind_speed: None,
+        }],
+    }
+    .into();
+    test_app
+        .event_data
+        .add_event_entry(entry.clone())
+        .await
+        .unwrap();
+    test_app
+        .event_data
+        .update_entry_scores(vec![(entry.id, 20_0042)])
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/oracle/events/{}/entries.csv", event.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "text/csv; charset=utf-8"
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let csv = String::from_utf8(body.to_vec()).unwrap();
+
+    let lines: Vec<&str> = csv.lines().collect();
+    assert_eq!(
+        lines,
+        vec![
+            "entry_id,station,temp_low,temp_high,wind_speed,score".to_string(),
+            format!("{},KSAW,under,over,,200042", entry.id),
+            format!("{},PFNO,,,,200042", entry.id),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn entries_csv_is_not_found_for_unknown_event() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/oracle/events/{}/entries.csv", Uuid::now_v7()))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,117 @@
+use crate::{
+    etl_workflow::{
+        mock_entries, mock_forecast_data, mock_observation_data, mock_observation_date,
+    },
+    helpers::{get, mock_event, spawn_app, MockWeatherAccess, TestApp},
+};
+use axum::http::StatusCode;
+use nostr_sdk::Keys;
+use oracle::{CreateEvent, EntryBreakdown, ScoringMode};
+use serde_json::from_slice;
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// Scores the etl workflow entries with the scoring mode, returns the app and the event's id
+async fn scored_event(scoring_mode: ScoringMode) -> (TestApp, Uuid) {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                scoring_mode,
+                ..mock_event(
+                    mock_observation_date(),
+                    OffsetDateTime::now_utc() + Duration::days(1),
+                )
+            },
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    test_app.oracle.etl_data(1).await.unwrap();
+    (test_app, event.id)
+}
+
+#[tokio::test]
+async fn entry_breakdown_adds_up_to_the_stored_score() {
+    for scoring_mode in [ScoringMode::Linear, ScoringMode::Exponential] {
+        let (test_app, event_id) = scored_event(scoring_mode).await;
+        let event = test_app.oracle.get_event(&event_id).await.unwrap();
+        assert_eq!(event.entries.len(), 4);
+
+        for entry in event.entries {
+            let (status, body) = get(
+                &test_app,
+                &format!("/oracle/events/{}/entry/{}/breakdown", event_id, entry.id),
+            )
+            .await;
+            assert_eq!(status, StatusCode::OK);
+            let breakdown: EntryBreakdown = from_slice(&body).unwrap();
+
+            assert_eq!(breakdown.entry_id, entry.id);
+            assert_eq!(breakdown.scoring_mode, scoring_mode);
+            let picks: usize = entry
+                .expected_observations
+                .iter()
+                .map(|choices| choices.pick_count())
+                .sum();
+            assert_eq!(breakdown.picks.len(), picks);
+            assert_eq!(
+                breakdown.picks.iter().map(|pick| pick.points).sum::<u64>(),
+                breakdown.base_score
+            );
+            for pick in &breakdown.picks {
+                assert!(pick.forecasted.is_some() && pick.observed.is_some());
+                assert_eq!(pick.matched, pick.points > 0);
+            }
+            assert_eq!(
+                breakdown.score,
+                (breakdown.base_score * 10000 + breakdown.tie_breaker) as i64
+            );
+            assert_eq!(entry.score, Some(breakdown.score));
+            assert_eq!(breakdown.stored_score, Some(breakdown.score));
+        }
+    }
+}
+
+#[tokio::test]
+async fn entry_breakdown_of_unknown_entry_is_not_found() {
+    let (test_app, event_id) = scored_event(ScoringMode::Linear).await;
+
+    let (status, _) = get(
+        &test_app,
+        &format!(
+            "/oracle/events/{}/entry/{}/breakdown",
+            event_id,
+            Uuid::now_v7()
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = get(
+        &test_app,
+        &format!(
+            "/oracle/events/{}/entry/{}/breakdown",
+            Uuid::now_v7(),
+            Uuid::now_v7()
+        ),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,113 @@
+use crate::helpers::{new_event, random_test_number, spawn_app, MockWeatherAccess};
+use dlctix::secp::Scalar;
+use duckdb::{params, Connection};
+use futures::future::join_all;
+use nostr_sdk::Keys;
+use oracle::{
+    create_folder, reconcile_total_entries, CreateEvent, CreateEventData, EventData, EventFilter,
+    ValueOptions, WeatherChoices, WeatherEntry,
+};
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn new_entry(event_id: Uuid) -> WeatherEntry {
+    WeatherEntry {
+        id: Uuid::now_v7(),
+        event_id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("PFNO"),
+            temp_low: Some(ValueOptions::Par),
+            temp_high: None,
+            wind_speed: None,
+        }],
+        score: None,
+        coordinator_pubkey: None,
+    }
+}
+
+async fn total_entries(event_data: &EventData, event_id: Uuid) -> i64 {
+    let summaries = event_data
+        .filtered_list_events(EventFilter {
+            limit: None,
+            event_ids: Some(vec![event_id]),
+            station_id: None,
+        })
+        .await
+        .unwrap();
+    summaries[0].total_entries
+}
+
+#[tokio::test]
+async fn counts_entries_added_concurrently() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                total_allowed_entries: 20,
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+
+    let total = 10;
+    let inserts = (0..total).map(|_| {
+        let event_data = test_app.event_data.clone();
+        tokio::spawn(async move { event_data.add_event_entry(new_entry(event.id)).await })
+    });
+    for result in join_all(inserts).await {
+        result.unwrap().unwrap();
+    }
+
+    assert_eq!(total_entries(&test_app.event_data, event.id).await, total);
+    let active = test_app.event_data.get_active_events().await.unwrap();
+    let active = active.iter().find(|active| active.id == event.id).unwrap();
+    assert_eq!(active.total_entries, total);
+    let stored = test_app.event_data.get_event(&event.id).await.unwrap();
+    assert_eq!(stored.entry_ids.len() as i64, total);
+}
+
+#[tokio::test]
+async fn reconciles_drifted_counters_on_open() {
+    let event_dir = format!("./test_data/{}/event_data", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_dir.replace("/event_data", ""));
+    create_folder(&event_dir);
+    let event_db = EventData::new(&event_dir).unwrap();
+    let event_data = CreateEventData::new(
+        Scalar::from_slice(&[7u8; 32])
+            .unwrap()
+            .base_point_mul()
+            .into(),
+        Keys::generate().public_key,
+        new_event(),
+    )
+    .unwrap();
+    let event = event_db.add_event(event_data).await.unwrap();
+    for _ in 0..3 {
+        event_db.add_event_entry(new_entry(event.id)).await.unwrap();
+    }
+    drop(event_db);
+
+    {
+        let conn = Connection::open(format!("{}/events.db3", event_dir)).unwrap();
+        conn.execute(
+            "UPDATE events_settings SET total_entries = 42 WHERE event_id = ?",
+            params![event.id.to_string()],
+        )
+        .unwrap();
+        assert_eq!(reconcile_total_entries(&conn).unwrap(), 1);
+        assert_eq!(reconcile_total_entries(&conn).unwrap(), 0);
+        conn.execute(
+            "UPDATE events_settings SET total_entries = 0 WHERE event_id = ?",
+            params![event.id.to_string()],
+        )
+        .unwrap();
+    }
+
+    let event_db = EventData::new(&event_dir).unwrap();
+    assert_eq!(total_entries(&event_db, event.id).await, 3);
+}
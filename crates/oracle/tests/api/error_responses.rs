@@ -0,0 +1,55 @@
+use axum::{
+    body::to_bytes,
+    http::{header::RETRY_AFTER, StatusCode},
+    response::IntoResponse,
+};
+use duckdb::{ffi, ErrorCode};
+use oracle::{oracle::Error, DATABASE_LOCKED_RETRY_AFTER_SECS};
+use serde_json::{from_slice, Value};
+
+#[tokio::test]
+async fn database_locked_returns_service_unavailable() {
+    // Same error the event db connection retries return once they give up on the file lock
+    let locked = Error::DataQuery(duckdb::Error::DuckDBFailure(
+        ffi::Error {
+            code: ErrorCode::DatabaseLocked,
+            extended_code: 0,
+        },
+        None,
+    ));
+
+    let response = locked.into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        response.headers().get(RETRY_AFTER).unwrap(),
+        &DATABASE_LOCKED_RETRY_AFTER_SECS.to_string()
+    );
+}
+
+#[tokio::test]
+async fn lock_error_message_returns_service_unavailable() {
+    let locked = Error::DataQuery(duckdb::Error::DuckDBFailure(
+        ffi::Error {
+            code: ErrorCode::Unknown,
+            extended_code: 0,
+        },
+        Some(String::from(
+            "IO Error: Could not set lock on file \"event_data/events.db\": Conflicting lock is held",
+        )),
+    ));
+
+    let response = locked.into_response();
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert!(response.headers().contains_key(RETRY_AFTER));
+}
+
+#[tokio::test]
+async fn other_query_errors_return_internal_server_error() {
+    let response = Error::DataQuery(duckdb::Error::QueryReturnedNoRows).into_response();
+    assert_eq!(response.status(), StatusCode::INTERNAL_SERVER_ERROR);
+    assert!(!response.headers().contains_key(RETRY_AFTER));
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let res: Value = from_slice(&body).unwrap();
+    assert_eq!(res["error"], "internal server error");
+}
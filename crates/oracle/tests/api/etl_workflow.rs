@@ -1,4 +1,4 @@
-use crate::helpers::{spawn_app, MockWeatherAccess};
+use crate::helpers::{spawn_app, spawn_app_with_signing_policy, MockWeatherAccess, TestApp};
 use axum::{
     body::{to_bytes, Body},
     http::Request,
@@ -8,12 +8,13 @@ use hyper::{header, Method};
 use log::info;
 use nostr_sdk::Keys;
 use oracle::{
-    oracle::get_winning_bytes, AddEventEntry, CreateEvent, Event, EventStatus, Forecast,
-    Observation, WeatherChoices,
+    oracle::{get_winning_bytes, Error, SigningPolicy},
+    AddEventEntry, CreateEvent, Event, EventStatus, Forecast, Observation, ScoringMode,
+    WeatherChoices, WinMode,
 };
 use serde_json::from_slice;
 use std::{cmp, sync::Arc};
-use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime, Time};
 use tokio::time::sleep;
 use tower::ServiceExt;
 use uuid::{ClockSequence, Timestamp, Uuid};
@@ -91,6 +92,13 @@ async fn can_get_event_run_etl_and_see_it_signed() {
         total_allowed_entries: 4,
         number_of_values_per_entry: 6,
         number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
 
     info!("above create event");
@@ -100,120 +108,26 @@ async fn can_get_event_run_etl_and_see_it_signed() {
         .await
         .unwrap();
 
-    let entry_1 = AddEventEntry {
-        id: get_uuid_from_timestamp("2024-08-11T00:00:00.10Z"),
-        event_id: event.id,
-        expected_observations: vec![
-            WeatherChoices {
-                stations: String::from("PFNO"),
-                temp_low: Some(oracle::ValueOptions::Under),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Over),
-            },
-            WeatherChoices {
-                stations: String::from("KSAW"),
-                temp_low: None,
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Over),
-            },
-            WeatherChoices {
-                stations: String::from("KWMC"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: Some(oracle::ValueOptions::Under),
-                wind_speed: Some(oracle::ValueOptions::Par),
-            },
-        ],
-    };
-    let entry_2 = AddEventEntry {
-        id: get_uuid_from_timestamp("2024-08-11T00:00:00.20Z"),
-        event_id: event.id,
-        expected_observations: vec![
-            WeatherChoices {
-                stations: String::from("PFNO"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Par),
-            },
-            WeatherChoices {
-                stations: String::from("KSAW"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Over),
-            },
-            WeatherChoices {
-                stations: String::from("KWMC"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: Some(oracle::ValueOptions::Under),
-                wind_speed: None,
-            },
-        ],
-    };
-    let entry_3 = AddEventEntry {
-        id: get_uuid_from_timestamp("2024-08-11T00:00:00.30Z"),
-        event_id: event.id,
-        expected_observations: vec![
-            WeatherChoices {
-                stations: String::from("PFNO"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Under),
-            },
-            WeatherChoices {
-                stations: String::from("KSAW"),
-                temp_low: Some(oracle::ValueOptions::Over),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Over),
-            },
-            WeatherChoices {
-                stations: String::from("KWMC"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Under),
-            },
-        ],
-    };
-    let entry_4 = AddEventEntry {
-        id: get_uuid_from_timestamp("2024-08-11T00:00:00.40Z"),
-        event_id: event.id,
-        expected_observations: vec![
-            WeatherChoices {
-                stations: String::from("PFNO"),
-                temp_low: Some(oracle::ValueOptions::Over),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Par),
-            },
-            WeatherChoices {
-                stations: String::from("KSAW"),
-                temp_low: None,
-                temp_high: Some(oracle::ValueOptions::Under),
-                wind_speed: Some(oracle::ValueOptions::Over),
-            },
-            WeatherChoices {
-                stations: String::from("KWMC"),
-                temp_low: Some(oracle::ValueOptions::Par),
-                temp_high: None,
-                wind_speed: Some(oracle::ValueOptions::Under),
-            },
-        ],
-    };
+    // The observation window has already passed, so entries skip the oracle's cutoff check
+    let [entry_1, entry_2, entry_3, entry_4] = mock_entries(event.id);
     test_app
-        .oracle
-        .add_event_entry(keys.public_key, entry_1.clone())
+        .event_data
+        .add_event_entry(entry_1.clone().into())
         .await
         .unwrap();
     test_app
-        .oracle
-        .add_event_entry(keys.public_key, entry_2.clone())
+        .event_data
+        .add_event_entry(entry_2.clone().into())
         .await
         .unwrap();
     test_app
-        .oracle
-        .add_event_entry(keys.public_key, entry_3.clone())
+        .event_data
+        .add_event_entry(entry_3.clone().into())
         .await
         .unwrap();
     test_app
-        .oracle
-        .add_event_entry(keys.public_key, entry_4.clone())
+        .event_data
+        .add_event_entry(entry_4.clone().into())
         .await
         .unwrap();
 
@@ -311,29 +225,587 @@ async fn can_get_event_run_etl_and_see_it_signed() {
         .position(|entry| entry.id == entry_1.id)
         .unwrap();
 
-    let second_place_index = entry_outcome_order
-        .iter()
-        .position(|entry| entry.id == entry_3.id)
+    // The event only pays out one place
+    let winners = vec![first_place_index];
+
+    let winning_bytes = get_winning_bytes(winners);
+    println!("winning_bytes in test: {:?}", winning_bytes);
+
+    let attested_outcome =
+        attestation_secret(test_app.oracle.raw_private_key(), res.nonce, &winning_bytes);
+
+    // Verify the attestation matches what we calculate in the test
+    assert_eq!(attested_outcome, res.attestation.unwrap());
+}
+
+async fn sign_top_n_event(number_of_places_win: i64) -> (TestApp, Event) {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                id: Uuid::now_v7(),
+                observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339)
+                    .unwrap(),
+                signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap(),
+                locations: vec![
+                    String::from("PFNO"),
+                    String::from("KSAW"),
+                    String::from("PAPG"),
+                    String::from("KWMC"),
+                ],
+                total_allowed_entries: 5,
+                number_of_values_per_entry: 6,
+                number_of_places_win,
+                win_mode: WinMode::TopN,
+                score_threshold: None,
+                auto_sign: false,
+                scoring_mode: ScoringMode::Linear,
+                station_group: None,
+                late_entry_grace_seconds: 0,
+                timezone: None,
+            },
+        )
+        .await
         .unwrap();
+    let [entry_1, entry_2, entry_3, entry_4] = mock_entries(event.id);
+    // A fifth entry with the same picks as the first, it sorts last by id
+    let entry_5 = AddEventEntry {
+        id: Uuid::now_v7(),
+        ..entry_1.clone()
+    };
+    for entry in [entry_1, entry_2, entry_3, entry_4, entry_5] {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
 
-    let third_place_index = entry_outcome_order
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.status, EventStatus::Signed);
+    (test_app, event)
+}
+
+fn expected_top_n_winners(event: &Event) -> Vec<usize> {
+    let mut entry_outcome_order = event.entries.clone();
+    entry_outcome_order.sort_by_key(|entry| entry.id);
+    let mut ranked = event.entries.clone();
+    ranked.sort_by_key(|entry| cmp::Reverse(entry.score));
+    ranked
         .iter()
-        .position(|entry| entry.id == entry_2.id)
-        .unwrap();
+        .take(event.number_of_places_win as usize)
+        .map(|ranked_entry| {
+            entry_outcome_order
+                .iter()
+                .position(|entry| entry.id == ranked_entry.id)
+                .unwrap()
+        })
+        .collect()
+}
 
-    let winners = vec![first_place_index, second_place_index, third_place_index];
+#[tokio::test]
+async fn signs_top_n_event_with_one_winning_place() {
+    let (test_app, event) = sign_top_n_event(1).await;
 
+    let winners = expected_top_n_winners(&event);
+    assert_eq!(winners.len(), 1);
     let winning_bytes = get_winning_bytes(winners);
-    println!("winning_bytes in test: {:?}", winning_bytes);
+    // The signed outcome has to be one of the announced ones for a DLC to use it
+    assert!(test_app
+        .oracle
+        .event_signing_key(event.nonce, &event.event_announcement, &winning_bytes)
+        .is_some());
+    let attested_outcome = attestation_secret(
+        test_app.oracle.raw_private_key(),
+        event.nonce,
+        &winning_bytes,
+    );
+    assert_eq!(attested_outcome, event.attestation.unwrap());
+}
+
+#[tokio::test]
+async fn signs_top_n_event_with_five_winning_places() {
+    let (test_app, event) = sign_top_n_event(5).await;
+
+    let winners = expected_top_n_winners(&event);
+    assert_eq!(winners.len(), 5);
+    let winning_bytes = get_winning_bytes(winners);
+    assert!(test_app
+        .oracle
+        .event_signing_key(event.nonce, &event.event_announcement, &winning_bytes)
+        .is_some());
+    let attested_outcome = attestation_secret(
+        test_app.oracle.raw_private_key(),
+        event.nonce,
+        &winning_bytes,
+    );
+    assert_eq!(attested_outcome, event.attestation.unwrap());
+}
+
+#[tokio::test]
+async fn leaves_event_unsigned_when_no_key_announced_the_outcome() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                id: Uuid::now_v7(),
+                observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339)
+                    .unwrap(),
+                signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap(),
+                locations: vec![
+                    String::from("PFNO"),
+                    String::from("KSAW"),
+                    String::from("PAPG"),
+                    String::from("KWMC"),
+                ],
+                total_allowed_entries: 4,
+                number_of_values_per_entry: 6,
+                number_of_places_win: 2,
+                win_mode: WinMode::TopN,
+                score_threshold: None,
+                auto_sign: false,
+                scoring_mode: ScoringMode::Linear,
+                station_group: None,
+                late_entry_grace_seconds: 0,
+                timezone: None,
+            },
+        )
+        .await
+        .unwrap();
+    // Only ranking a single entry isn't one of the announced two place outcomes
+    let [entry_1, ..] = mock_entries(event.id);
+    test_app
+        .event_data
+        .add_event_entry(entry_1.into())
+        .await
+        .unwrap();
+
+    let result = test_app.oracle.etl_data(1).await;
 
+    assert!(matches!(result, Err(Error::OutcomeNotFound(_))));
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.status, EventStatus::Completed);
+    assert!(event.attestation.is_none());
+}
+
+#[tokio::test]
+async fn can_sign_threshold_event_with_entries_over_threshold() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    //called twice per ETL process
+    weather_data
+        .expect_forecasts_data()
+        .times(2)
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .times(2)
+        .returning(|_, _| Ok(mock_observation_data()));
+
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let signing_date = OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap();
+
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date,
+        signing_date,
+        locations: vec![
+            String::from("PFNO"),
+            String::from("KSAW"),
+            String::from("PAPG"),
+            String::from("KWMC"),
+        ],
+        total_allowed_entries: 4,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 3,
+        win_mode: WinMode::Threshold,
+        score_threshold: Some(30),
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+    assert_eq!(event.win_mode, WinMode::Threshold);
+    assert_eq!(event.score_threshold, Some(30));
+
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    assert_eq!(res.win_mode, WinMode::Threshold);
+
+    // entries 1, 2 and 3 score 40, 30 and 40 base points, entry 4 only scores 10
+    let mut entry_outcome_order = res.entries.clone();
+    entry_outcome_order.sort_by_key(|entry| entry.id);
+    let winners: Vec<usize> = entry_outcome_order
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| entry.score.unwrap() / 10000 >= 30)
+        .map(|(index, _)| index)
+        .collect();
+    assert_eq!(winners, vec![0, 1, 2]);
+
+    let winning_bytes = get_winning_bytes(winners);
     let attested_outcome =
         attestation_secret(test_app.oracle.raw_private_key(), res.nonce, &winning_bytes);
+    assert_eq!(attested_outcome, res.attestation.unwrap());
+}
 
-    // Verify the attestation matches what we calculate in the test
+#[tokio::test]
+async fn rejects_threshold_event_without_threshold() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: vec![String::from("PFNO")],
+        total_allowed_entries: 4,
+        number_of_values_per_entry: 3,
+        number_of_places_win: 2,
+        win_mode: WinMode::Threshold,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let res = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await;
+    assert!(res.is_err());
+}
+
+async fn create_event_missing_kwmc_observation(
+    signing_policy: SigningPolicy,
+    observation_data_calls: usize,
+) -> (TestApp, Event) {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .times(2)
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .times(observation_data_calls)
+        .returning(|_, _| {
+            Ok(mock_observation_data()
+                .into_iter()
+                .filter(|observation| observation.station_id != "KWMC")
+                .collect())
+        });
+
+    let test_app = spawn_app_with_signing_policy(Arc::new(weather_data), signing_policy).await;
+
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap(),
+        locations: vec![
+            String::from("PFNO"),
+            String::from("KSAW"),
+            String::from("PAPG"),
+            String::from("KWMC"),
+        ],
+        total_allowed_entries: 4,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    (test_app, event)
+}
+
+#[tokio::test]
+async fn can_sign_event_with_available_observations() {
+    // weather update and scoring each fetch observations once
+    let (test_app, event) =
+        create_event_missing_kwmc_observation(SigningPolicy::SignWithAvailable, 2).await;
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+
+    // Without KWMC entries 1, 2, 3 and 4 only score 20, 30, 40 and 10 base points
+    let mut entry_outcome_order = res.entries.clone();
+    entry_outcome_order.sort_by_key(|entry| entry.id);
+    let base_scores: Vec<i64> = entry_outcome_order
+        .iter()
+        .map(|entry| entry.score.unwrap() / 10000)
+        .collect();
+    assert_eq!(base_scores, vec![20, 30, 40, 10]);
+
+    let winning_bytes = get_winning_bytes(vec![2]);
+    let attested_outcome =
+        attestation_secret(test_app.oracle.raw_private_key(), res.nonce, &winning_bytes);
     assert_eq!(attested_outcome, res.attestation.unwrap());
 }
 
-fn mock_forecast_data() -> Vec<Forecast> {
+#[tokio::test]
+async fn holds_signing_event_until_all_observations_arrive() {
+    // the signing policy check fetches observations a third time
+    let (test_app, event) =
+        create_event_missing_kwmc_observation(SigningPolicy::RequireAllObservations, 3).await;
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Completed);
+    assert!(res.attestation.is_none());
+}
+
+#[tokio::test]
+async fn skips_forecasts_for_a_different_day_than_the_observation_date() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        let mut forecasts = mock_forecast_data();
+        // PFNO only has a stale forecast, KSAW's stale forecast is listed ahead of the current one
+        for forecast in forecasts
+            .iter_mut()
+            .filter(|forecast| forecast.station_id == "PFNO")
+        {
+            forecast.date = String::from("2024-08-11");
+        }
+        forecasts.insert(
+            0,
+            Forecast {
+                station_id: String::from("KSAW"),
+                date: String::from("2024-08-11"),
+                start_time: String::from("2024-08-10T00:00:00+00:00"),
+                end_time: String::from("2024-08-11T00:00:00+00:00"),
+                temp_low: 0,
+                temp_high: 99,
+                wind_speed: 50,
+            },
+        );
+        Ok(forecasts)
+    });
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap(),
+        signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap(),
+        locations: vec![
+            String::from("PFNO"),
+            String::from("KSAW"),
+            String::from("PAPG"),
+            String::from("KWMC"),
+        ],
+        total_allowed_entries: 4,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert!(res
+        .weather
+        .iter()
+        .all(|weather| weather.station_id != "PFNO"));
+    let ksaw = res
+        .weather
+        .iter()
+        .find(|weather| weather.station_id == "KSAW")
+        .unwrap();
+    assert_eq!(ksaw.forecasted.temp_low, 17);
+    assert_eq!(ksaw.forecasted.temp_high, 25);
+    assert_eq!(ksaw.forecasted.wind_speed, 3);
+}
+
+pub fn mock_entries(event_id: Uuid) -> [AddEventEntry; 4] {
+    [
+        AddEventEntry {
+            id: get_uuid_from_timestamp("2024-08-11T00:00:00.10Z"),
+            event_id,
+            expected_observations: vec![
+                WeatherChoices {
+                    stations: String::from("PFNO"),
+                    temp_low: Some(oracle::ValueOptions::Under),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Over),
+                },
+                WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_low: None,
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Over),
+                },
+                WeatherChoices {
+                    stations: String::from("KWMC"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: Some(oracle::ValueOptions::Under),
+                    wind_speed: Some(oracle::ValueOptions::Par),
+                },
+            ],
+        },
+        AddEventEntry {
+            id: get_uuid_from_timestamp("2024-08-11T00:00:00.20Z"),
+            event_id,
+            expected_observations: vec![
+                WeatherChoices {
+                    stations: String::from("PFNO"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Par),
+                },
+                WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Over),
+                },
+                WeatherChoices {
+                    stations: String::from("KWMC"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: Some(oracle::ValueOptions::Under),
+                    wind_speed: None,
+                },
+            ],
+        },
+        AddEventEntry {
+            id: get_uuid_from_timestamp("2024-08-11T00:00:00.30Z"),
+            event_id,
+            expected_observations: vec![
+                WeatherChoices {
+                    stations: String::from("PFNO"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Under),
+                },
+                WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_low: Some(oracle::ValueOptions::Over),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Over),
+                },
+                WeatherChoices {
+                    stations: String::from("KWMC"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Under),
+                },
+            ],
+        },
+        AddEventEntry {
+            id: get_uuid_from_timestamp("2024-08-11T00:00:00.40Z"),
+            event_id,
+            expected_observations: vec![
+                WeatherChoices {
+                    stations: String::from("PFNO"),
+                    temp_low: Some(oracle::ValueOptions::Over),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Par),
+                },
+                WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_low: None,
+                    temp_high: Some(oracle::ValueOptions::Under),
+                    wind_speed: Some(oracle::ValueOptions::Over),
+                },
+                WeatherChoices {
+                    stations: String::from("KWMC"),
+                    temp_low: Some(oracle::ValueOptions::Par),
+                    temp_high: None,
+                    wind_speed: Some(oracle::ValueOptions::Under),
+                },
+            ],
+        },
+    ]
+}
+
+/// Day the mock forecasts and observations are for
+pub fn mock_observation_date() -> OffsetDateTime {
+    OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap()
+}
+
+pub fn mock_forecast_data() -> Vec<Forecast> {
     vec![
         Forecast {
             station_id: String::from("PFNO"),
@@ -374,7 +846,7 @@ fn mock_forecast_data() -> Vec<Forecast> {
     ]
 }
 
-fn mock_observation_data() -> Vec<Observation> {
+pub fn mock_observation_data() -> Vec<Observation> {
     vec![
         Observation {
             station_id: String::from("PFNO"),
@@ -0,0 +1,386 @@
+use crate::helpers::{get, get_json, new_event, spawn_app, MockWeatherAccess};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use dlctix::{attestation_locking_point, secp::Point};
+use hyper::{header::CONTENT_TYPE, Method};
+use nostr_sdk::Keys;
+use oracle::{
+    possible_outcome_messages, AddEventEntry, CreateEvent, EventAnnouncement, EventOutcomeMessages,
+    EventOutcomeWinners, OutcomeEncoding, UpdateEvent, ValueOptions, WeatherChoices, WinMode,
+};
+use std::sync::Arc;
+use tower::ServiceExt;
+use uuid::Uuid;
+
+// 5 entries with 2 ranked winners
+fn two_place_event() -> CreateEvent {
+    CreateEvent {
+        number_of_places_win: 2,
+        ..new_event()
+    }
+}
+
+#[tokio::test]
+async fn can_get_event_announcement_as_json() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, two_place_event())
+        .await
+        .unwrap();
+    let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+
+    let announcement: EventAnnouncement = get_json(
+        &test_app,
+        &format!("/oracle/events/{}/announcement", event.id),
+    )
+    .await;
+
+    assert_eq!(announcement.id, event.id);
+    assert_eq!(announcement.event_announcement, stored.event_announcement);
+    assert_eq!(
+        announcement.oracle_pubkey,
+        Point::from(test_app.oracle.raw_public_key())
+    );
+    assert_eq!(announcement.nonce_point, stored.nonce.base_point_mul());
+
+    // 5 entries with 2 ranked winners
+    assert_eq!(announcement.outcome_messages.len(), 20);
+    let locking_points: Vec<_> = announcement
+        .outcome_messages
+        .iter()
+        .map(|message| {
+            attestation_locking_point(
+                announcement.oracle_pubkey,
+                announcement.nonce_point,
+                &hex::decode(message).unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(locking_points, stored.event_announcement.locking_points);
+}
+
+#[tokio::test]
+async fn can_get_event_announcement_as_binary() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, two_place_event())
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!(
+            "/oracle/events/{}/announcement?encoding=binary",
+            event.id
+        ))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/octet-stream"
+    );
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let announcement: EventAnnouncement = bincode::deserialize(&body).unwrap();
+
+    let expected = test_app
+        .oracle
+        .get_event_announcement(&event.id)
+        .await
+        .unwrap();
+    assert_eq!(announcement, *expected);
+    assert_eq!(announcement.event_announcement, event.event_announcement);
+}
+
+#[tokio::test]
+async fn rebuilds_the_announcement_once_the_event_outcomes_change() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, two_place_event())
+        .await
+        .unwrap();
+    let announcement = test_app
+        .oracle
+        .get_event_announcement(&event.id)
+        .await
+        .unwrap();
+    assert_eq!(announcement.outcome_messages.len(), 20);
+    // Served again for the same stored announcement
+    assert!(Arc::ptr_eq(
+        &test_app
+            .oracle
+            .get_event_announcement(&event.id)
+            .await
+            .unwrap(),
+        &announcement
+    ));
+
+    let updated = test_app
+        .oracle
+        .update_event(
+            keys.public_key,
+            &event.id,
+            UpdateEvent {
+                total_allowed_entries: None,
+                number_of_places_win: Some(1),
+                win_mode: None,
+                score_threshold: None,
+            },
+        )
+        .await
+        .unwrap();
+    let announcement = test_app
+        .oracle
+        .get_event_announcement(&event.id)
+        .await
+        .unwrap();
+
+    // 5 entries with a single winner
+    assert_eq!(announcement.outcome_messages.len(), 5);
+    assert_eq!(announcement.event_announcement, updated.event_announcement);
+    assert_eq!(announcement.nonce_point, updated.nonce.base_point_mul());
+    let expected: Vec<String> = possible_outcome_messages(&WinMode::TopN, 5, 1)
+        .iter()
+        .map(hex::encode)
+        .collect();
+    assert_eq!(announcement.outcome_messages, expected);
+}
+
+#[tokio::test]
+async fn returns_not_found_for_unknown_event_announcement() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let (status, _) = get(
+        &test_app,
+        &format!("/oracle/events/{}/announcement", Uuid::now_v7()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn can_get_event_outcome_messages_in_each_encoding() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, two_place_event())
+        .await
+        .unwrap();
+    // The messages the announcement's locking points were made from
+    let expected = possible_outcome_messages(&WinMode::TopN, 5, 2);
+
+    let hex_outcomes: EventOutcomeMessages =
+        get_json(&test_app, &format!("/oracle/events/{}/outcomes", event.id)).await;
+    assert_eq!(hex_outcomes.id, event.id);
+    assert_eq!(hex_outcomes.encoding, OutcomeEncoding::Hex);
+    let decoded: Vec<Vec<u8>> = hex_outcomes
+        .outcome_messages
+        .iter()
+        .map(|message| hex::decode(message).unwrap())
+        .collect();
+    assert_eq!(decoded, expected);
+
+    let base64_outcomes: EventOutcomeMessages = get_json(
+        &test_app,
+        &format!("/oracle/events/{}/outcomes?encoding=base64", event.id),
+    )
+    .await;
+    assert_eq!(base64_outcomes.encoding, OutcomeEncoding::Base64);
+    let decoded: Vec<Vec<u8>> = base64_outcomes
+        .outcome_messages
+        .iter()
+        .map(|message| BASE64.decode(message).unwrap())
+        .collect();
+    assert_eq!(decoded, expected);
+
+    let locking_points: Vec<_> = decoded
+        .iter()
+        .map(|message| {
+            attestation_locking_point(
+                Point::from(test_app.oracle.raw_public_key()),
+                event.nonce.base_point_mul(),
+                message,
+            )
+        })
+        .collect();
+    assert_eq!(locking_points, event.event_announcement.locking_points);
+}
+
+#[tokio::test]
+async fn returns_not_found_for_unknown_event_outcome_messages() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let (status, _) = get(
+        &test_app,
+        &format!("/oracle/events/{}/outcomes", Uuid::now_v7()),
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
+
+#[tokio::test]
+async fn maps_each_outcome_to_its_winning_entries() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, two_place_event())
+        .await
+        .unwrap();
+    let mut entry_ids = vec![];
+    for _ in 0..3 {
+        let entry = test_app
+            .oracle
+            .add_event_entry(
+                keys.public_key,
+                AddEventEntry {
+                    id: Uuid::now_v7(),
+                    event_id: event.id,
+                    expected_observations: vec![WeatherChoices {
+                        stations: String::from("PFNO"),
+                        temp_low: Some(ValueOptions::Par),
+                        temp_high: None,
+                        wind_speed: None,
+                    }],
+                },
+            )
+            .await
+            .unwrap();
+        entry_ids.push(entry.id);
+    }
+    entry_ids.sort();
+
+    let res: EventOutcomeWinners = get_json(
+        &test_app,
+        &format!("/oracle/events/{}/outcomes/winners", event.id),
+    )
+    .await;
+
+    // 5 allowed entries ranked 2 deep, only the outcomes ranking the 3 entries that joined can happen
+    let outcome_messages = possible_outcome_messages(&WinMode::TopN, 5, 2);
+    assert_eq!(res.id, event.id);
+    assert_eq!(res.total_outcomes, 6);
+    let winners: Vec<Vec<Uuid>> = res
+        .outcomes
+        .iter()
+        .map(|outcome| outcome.winners.clone())
+        .collect();
+    assert_eq!(
+        winners,
+        vec![
+            vec![entry_ids[0], entry_ids[1]],
+            vec![entry_ids[0], entry_ids[2]],
+            vec![entry_ids[1], entry_ids[0]],
+            vec![entry_ids[1], entry_ids[2]],
+            vec![entry_ids[2], entry_ids[0]],
+            vec![entry_ids[2], entry_ids[1]],
+        ]
+    );
+    for outcome in res.outcomes {
+        assert_eq!(
+            hex::decode(&outcome.outcome_message).unwrap(),
+            outcome_messages[outcome.outcome_index]
+        );
+    }
+}
+
+#[tokio::test]
+async fn drops_the_cached_announcement_once_the_event_is_cancelled() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, two_place_event())
+        .await
+        .unwrap();
+    let announcement = test_app
+        .oracle
+        .get_event_announcement(&event.id)
+        .await
+        .unwrap();
+
+    test_app
+        .oracle
+        .cancel_event(keys.public_key, &event.id)
+        .await
+        .unwrap();
+    let cancelled = test_app
+        .oracle
+        .get_event_announcement(&event.id)
+        .await
+        .unwrap();
+
+    assert!(!Arc::ptr_eq(&cancelled, &announcement));
+    assert_eq!(cancelled, announcement);
+}
+
+#[tokio::test]
+async fn pages_through_the_outcome_winners() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, two_place_event())
+        .await
+        .unwrap();
+    for _ in 0..5 {
+        test_app
+            .oracle
+            .add_event_entry(
+                keys.public_key,
+                AddEventEntry {
+                    id: Uuid::now_v7(),
+                    event_id: event.id,
+                    expected_observations: vec![WeatherChoices {
+                        stations: String::from("PFNO"),
+                        temp_low: Some(ValueOptions::Par),
+                        temp_high: None,
+                        wind_speed: None,
+                    }],
+                },
+            )
+            .await
+            .unwrap();
+    }
+    let all: EventOutcomeWinners = get_json(
+        &test_app,
+        &format!("/oracle/events/{}/outcomes/winners", event.id),
+    )
+    .await;
+    // 5 entries ranked 2 deep
+    assert_eq!(all.total_outcomes, 20);
+    assert_eq!(all.outcomes.len(), 20);
+
+    let page: EventOutcomeWinners = get_json(
+        &test_app,
+        &format!(
+            "/oracle/events/{}/outcomes/winners?offset=4&limit=3",
+            event.id
+        ),
+    )
+    .await;
+    assert_eq!(page.total_outcomes, 20);
+    assert_eq!(page.outcomes, all.outcomes[4..7]);
+
+    let last: EventOutcomeWinners = get_json(
+        &test_app,
+        &format!(
+            "/oracle/events/{}/outcomes/winners?offset=18&limit=10",
+            event.id
+        ),
+    )
+    .await;
+    assert_eq!(last.outcomes, all.outcomes[18..]);
+}
@@ -0,0 +1,291 @@
+use crate::{
+    etl_workflow::{
+        mock_entries, mock_forecast_data, mock_observation_data, mock_observation_date,
+    },
+    helpers::{create_auth_event, mock_event, spawn_app, MockWeatherAccess, TestApp},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hyper::{header::AUTHORIZATION, Method};
+use nostr_sdk::Keys;
+use oracle::{
+    oracle::{Error, Oracle},
+    AddEventEntry, Event, EventFilter, EventStatus, EventSummary, SigningQueue, ValueOptions,
+    WeatherChoices,
+};
+use serde_json::{from_slice, from_str, to_string};
+use std::{sync::Arc, time::Duration as StdDuration};
+use time::{Duration, OffsetDateTime, Time};
+use tokio::{sync::oneshot, time::sleep};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn cancel_event(test_app: &TestApp, keys: &Keys, event_id: Uuid) -> (StatusCode, Vec<u8>) {
+    let path = format!("/oracle/events/{}/cancel", event_id);
+    let auth_event = create_auth_event(
+        "POST",
+        &format!("http://localhost:3000{}", path),
+        None,
+        keys,
+    )
+    .await;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(
+            AUTHORIZATION,
+            format!("Nostr {}", BASE64.encode(to_string(&auth_event).unwrap())),
+        )
+        .header("host", "localhost:3000")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+fn mock_weather() -> MockWeatherAccess {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    weather_data
+}
+
+#[test]
+fn event_status_round_trips() {
+    for (status, name) in [
+        (EventStatus::Live, "live"),
+        (EventStatus::Running, "running"),
+        (EventStatus::Completed, "completed"),
+        (EventStatus::Signed, "signed"),
+        (EventStatus::Cancelled, "cancelled"),
+    ] {
+        assert_eq!(status.to_string(), name);
+        assert_eq!(EventStatus::try_from(name).unwrap(), status);
+        assert_eq!(EventStatus::try_from(name.to_string()).unwrap(), status);
+        let json = to_string(&status).unwrap();
+        assert_eq!(from_str::<EventStatus>(&json).unwrap(), status);
+    }
+    assert_eq!(to_string(&EventStatus::Cancelled).unwrap(), "\"Cancelled\"");
+    assert!(EventStatus::try_from("voided").is_err());
+}
+
+#[tokio::test]
+async fn cancelled_event_is_never_signed() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(mock_weather())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                mock_observation_date(),
+                OffsetDateTime::now_utc() - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    assert_eq!(event.status, EventStatus::Live);
+    assert_eq!(event.cancelled_at, None);
+
+    let (status, body) = cancel_event(&test_app, &keys, event.id).await;
+    assert_eq!(status, StatusCode::OK);
+    let cancelled: Event = from_slice(&body).unwrap();
+    assert_eq!(cancelled.status, EventStatus::Cancelled);
+    let cancelled_at = cancelled.cancelled_at.unwrap();
+
+    // Cancelling again leaves the event as it was
+    let (status, body) = cancel_event(&test_app, &keys, event.id).await;
+    assert_eq!(status, StatusCode::OK);
+    let cancelled_again: Event = from_slice(&body).unwrap();
+    assert_eq!(cancelled_again.cancelled_at, Some(cancelled_at));
+
+    let listed: Vec<EventSummary> = test_app
+        .oracle
+        .list_events(EventFilter {
+            event_ids: Some(vec![event.id]),
+            ..Default::default()
+        })
+        .await
+        .unwrap();
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].status, EventStatus::Cancelled);
+    assert_eq!(listed[0].cancelled_at, Some(cancelled_at));
+    let station_events = test_app
+        .oracle
+        .list_station_events("KSAW", Some(EventStatus::Cancelled))
+        .await
+        .unwrap();
+    assert_eq!(station_events.len(), 1);
+    assert_eq!(station_events[0].id, event.id);
+    assert!(test_app
+        .oracle
+        .get_running_events()
+        .await
+        .unwrap()
+        .is_empty());
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.status, EventStatus::Cancelled);
+    assert_eq!(event.attestation, None);
+    assert!(event.entries.iter().all(|entry| entry.score.is_none()));
+}
+
+#[tokio::test]
+async fn cancelling_stops_a_sign_waiting_in_the_queue() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(mock_weather())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                mock_observation_date(),
+                OffsetDateTime::now_utc() - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    // The only slot is held, so the etl has picked the event to sign but has to wait to sign it
+    let signing_queue = Arc::new(SigningQueue::new(1));
+    let oracle = Arc::new(
+        Oracle::new(
+            test_app.event_data.clone(),
+            Arc::new(mock_weather()),
+            &String::from("./oracle_private_key.pem"),
+            &[],
+        )
+        .await
+        .unwrap()
+        .with_signing_queue(signing_queue.clone()),
+    );
+    let (release_slot, slot_released) = oneshot::channel::<()>();
+    let held_slot = tokio::spawn({
+        let signing_queue = signing_queue.clone();
+        async move {
+            signing_queue
+                .run(async {
+                    slot_released.await.ok();
+                })
+                .await
+        }
+    });
+    while signing_queue.status().in_flight == 0 {
+        sleep(StdDuration::from_millis(10)).await;
+    }
+    let etl = tokio::spawn({
+        let oracle = oracle.clone();
+        async move { oracle.etl_data(1).await }
+    });
+    while signing_queue.status().queued == 0 {
+        sleep(StdDuration::from_millis(10)).await;
+    }
+
+    let (status, _) = cancel_event(&test_app, &keys, event.id).await;
+    assert_eq!(status, StatusCode::OK);
+    release_slot.send(()).unwrap();
+    held_slot.await.unwrap();
+    etl.await.unwrap().unwrap();
+
+    let event = oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.status, EventStatus::Cancelled);
+    assert_eq!(event.attestation, None);
+}
+
+#[tokio::test]
+async fn only_unsigned_events_can_be_cancelled_by_their_coordinator() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(mock_weather())).await;
+    let signed_event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                mock_observation_date(),
+                OffsetDateTime::now_utc() - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(signed_event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    test_app.oracle.etl_data(1).await.unwrap();
+    let signed_event = test_app.oracle.get_event(&signed_event.id).await.unwrap();
+    assert_eq!(signed_event.status, EventStatus::Signed);
+
+    let (status, _) = cancel_event(&test_app, &keys, signed_event.id).await;
+    assert_eq!(status, StatusCode::GONE);
+    let signed_event = test_app.oracle.get_event(&signed_event.id).await.unwrap();
+    assert_eq!(signed_event.status, EventStatus::Signed);
+    assert_eq!(signed_event.cancelled_at, None);
+
+    let live_event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) + Duration::days(1),
+                OffsetDateTime::now_utc() + Duration::days(2),
+            ),
+        )
+        .await
+        .unwrap();
+    let (status, _) = cancel_event(&test_app, &Keys::generate(), live_event.id).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    let (status, _) = cancel_event(&test_app, &keys, Uuid::now_v7()).await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let (status, _) = cancel_event(&test_app, &keys, live_event.id).await;
+    assert_eq!(status, StatusCode::OK);
+    let entry = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: live_event.id,
+                expected_observations: vec![WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_high: Some(ValueOptions::Par),
+                    temp_low: None,
+                    wind_speed: None,
+                }],
+            },
+        )
+        .await;
+    assert!(matches!(entry, Err(Error::EventExpired(_))));
+}
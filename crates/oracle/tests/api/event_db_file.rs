@@ -0,0 +1,93 @@
+use crate::helpers::{new_event, random_test_number};
+use dlctix::secp::Scalar;
+use nostr_sdk::Keys;
+use oracle::{
+    check_folder_writable, create_folder, CreateEventData, EventData, EventDbError, EventFilter,
+    EventSummary,
+};
+use std::{fs, path::Path};
+
+fn new_event_data() -> CreateEventData {
+    let oracle_key = Scalar::from_slice(&[7u8; 32]).unwrap();
+    let create_event = new_event();
+    CreateEventData::new(
+        oracle_key.base_point_mul().into(),
+        Keys::generate().public_key,
+        create_event,
+    )
+    .unwrap()
+}
+
+async fn list_events(event_db: &EventData) -> Vec<EventSummary> {
+    event_db
+        .filtered_list_events(EventFilter {
+            limit: None,
+            event_ids: None,
+            station_id: None,
+        })
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn event_dbs_with_different_file_names_share_a_folder() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_dir);
+
+    let test_db = EventData::new_with_file_name(&event_dir, "test_events.db3").unwrap();
+    let prod_db = EventData::new_with_file_name(&event_dir, "prod_events.db3").unwrap();
+    let test_event = test_db.add_event(new_event_data()).await.unwrap();
+    let prod_event = prod_db.add_event(new_event_data()).await.unwrap();
+
+    assert!(Path::new(&format!("{}/test_events.db3", event_dir)).exists());
+    assert!(Path::new(&format!("{}/prod_events.db3", event_dir)).exists());
+    let test_events = list_events(&test_db).await;
+    assert_eq!(test_events.len(), 1);
+    assert_eq!(test_events[0].id, test_event.id);
+    let prod_events = list_events(&prod_db).await;
+    assert_eq!(prod_events.len(), 1);
+    assert_eq!(prod_events[0].id, prod_event.id);
+}
+
+#[test]
+fn only_existing_folders_are_writable() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_dir);
+
+    check_folder_writable(&event_dir).unwrap();
+    assert!(check_folder_writable(&format!("{}/missing", event_dir)).is_err());
+}
+
+#[tokio::test]
+async fn creates_missing_nested_event_db_folders() {
+    let event_dir = format!("./test_data/{}/nested/event_data", random_test_number());
+    assert!(!Path::new(&event_dir).exists());
+
+    let event_db = EventData::new(&event_dir).unwrap();
+    let event = event_db.add_event(new_event_data()).await.unwrap();
+
+    assert!(Path::new(&format!("{}/events.db3", event_dir)).exists());
+    let events = list_events(&event_db).await;
+    assert_eq!(events.len(), 1);
+    assert_eq!(events[0].id, event.id);
+}
+
+#[test]
+fn rejects_event_db_path_that_is_a_file() {
+    let test_dir = format!("./test_data/{}", random_test_number());
+    create_folder("./test_data");
+    create_folder(&test_dir);
+    let file_path = format!("{}/event_data", test_dir);
+    fs::write(&file_path, b"not a folder").unwrap();
+
+    let res = EventData::new(&file_path);
+
+    assert!(matches!(res, Err(EventDbError::NotADirectory(_))));
+    assert!(res
+        .err()
+        .unwrap()
+        .to_string()
+        .contains("is a file, it needs to be a directory"));
+}
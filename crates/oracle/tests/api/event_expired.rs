@@ -0,0 +1,188 @@
+use crate::helpers::{
+    new_event, spawn_app, spawn_app_with_entry_grace_period, station_ids, MockWeatherAccess,
+};
+use axum::{http::StatusCode, response::IntoResponse};
+use nostr_sdk::Keys;
+use oracle::{
+    oracle::Error, AddEventEntry, CreateEvent, EventStatus, Forecast, Observation, ValueOptions,
+    WeatherChoices,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time};
+use uuid::Uuid;
+
+fn ksaw_event(observation_date: OffsetDateTime) -> CreateEvent {
+    CreateEvent {
+        observation_date,
+        signing_date: observation_date + Duration::days(1),
+        locations: station_ids(&["KSAW"]),
+        number_of_values_per_entry: 3,
+        ..new_event()
+    }
+}
+
+fn new_entry(event_id: Uuid) -> AddEventEntry {
+    AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("KSAW"),
+            temp_high: Some(ValueOptions::Par),
+            temp_low: None,
+            wind_speed: None,
+        }],
+    }
+}
+
+#[tokio::test]
+async fn entry_after_observation_returns_gone() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            ksaw_event(OffsetDateTime::now_utc() - Duration::hours(1)),
+        )
+        .await
+        .unwrap();
+
+    let res = test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry(event.id))
+        .await;
+
+    let err = res.unwrap_err();
+    assert!(matches!(err, Error::EventExpired(_)), "{:?}", err);
+    assert_eq!(err.into_response().status(), StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn entry_after_signing_returns_gone() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 17,
+            temp_high: 25,
+            wind_speed: 3,
+        }])
+    });
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 15_f64,
+            temp_high: 25_f64,
+            wind_speed: 5,
+        }])
+    });
+    // The grace period runs well past the signing date, so only the signature closes the event
+    let test_app =
+        spawn_app_with_entry_grace_period(Arc::new(weather_data), Duration::days(365 * 20)).await;
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, ksaw_event(observation_date))
+        .await
+        .unwrap();
+    test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry(event.id))
+        .await
+        .unwrap();
+    test_app.oracle.etl_data(1).await.unwrap();
+    let signed = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(signed.status, EventStatus::Signed);
+
+    let res = test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry(event.id))
+        .await;
+
+    let err = res.unwrap_err();
+    assert!(matches!(err, Error::EventExpired(_)), "{:?}", err);
+    assert_eq!(err.into_response().status(), StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn entry_within_late_entry_grace_is_accepted() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    // Today's observation window has started, the longest grace allowed covers the rest of it
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                late_entry_grace_seconds: 86399,
+                ..ksaw_event(OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT))
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(event.late_entry_grace_seconds, 86399);
+
+    test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry(event.id))
+        .await
+        .unwrap();
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.entries.len(), 1);
+    assert_eq!(event.late_entry_grace_seconds, 86399);
+}
+
+#[tokio::test]
+async fn entry_beyond_late_entry_grace_returns_gone() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                late_entry_grace_seconds: 86399,
+                ..ksaw_event(
+                    OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) - Duration::days(2),
+                )
+            },
+        )
+        .await
+        .unwrap();
+
+    let res = test_app
+        .oracle
+        .add_event_entry(keys.public_key, new_entry(event.id))
+        .await;
+
+    let err = res.unwrap_err();
+    assert!(matches!(err, Error::EventExpired(_)), "{:?}", err);
+    assert_eq!(err.into_response().status(), StatusCode::GONE);
+}
+
+#[tokio::test]
+async fn late_entry_grace_must_be_shorter_than_the_observation_window() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let res = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                late_entry_grace_seconds: 86400,
+                ..ksaw_event(OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT))
+            },
+        )
+        .await;
+
+    let err = res.unwrap_err();
+    assert!(matches!(err, Error::BadEvent(_)), "{:?}", err);
+    assert_eq!(err.into_response().status(), StatusCode::BAD_REQUEST);
+}
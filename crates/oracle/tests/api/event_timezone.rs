@@ -0,0 +1,179 @@
+use crate::{
+    etl_workflow::{mock_entries, mock_forecast_data, mock_observation_data},
+    helpers::{mock_event, spawn_app, MockWeatherAccess},
+};
+use nostr_sdk::Keys;
+use oracle::{oracle::Error, CreateEvent, EventStatus};
+use std::sync::Arc;
+use time::{
+    format_description::well_known::Rfc3339, macros::offset, Duration, OffsetDateTime, Time,
+    UtcOffset,
+};
+
+fn date(value: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(value, &Rfc3339).unwrap()
+}
+
+#[tokio::test]
+async fn observation_date_is_midnight_in_the_events_timezone() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    for observation_date in ["2024-08-12T00:00:00-05:00", "2024-08-12T05:00:00Z"] {
+        let event = test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                CreateEvent {
+                    timezone: Some(String::from("-05:00")),
+                    ..mock_event(date(observation_date), date("2024-08-13T05:00:00Z"))
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(event.observation_date, date("2024-08-12T05:00:00Z"));
+        assert_eq!(event.observation_date.offset(), UtcOffset::UTC);
+        let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+        assert_eq!(stored.observation_date, date("2024-08-12T05:00:00Z"));
+        assert_eq!(stored.timezone, Some(String::from("-05:00")));
+    }
+
+    let utc = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                timezone: Some(String::from("UTC")),
+                ..mock_event(date("2024-08-12T00:00:00Z"), date("2024-08-13T00:00:00Z"))
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(utc.timezone, Some(String::from("+00:00")));
+
+    // Midnight UTC is 19:00 the day before in the event's timezone
+    let not_midnight = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                timezone: Some(String::from("-05:00")),
+                ..mock_event(date("2024-08-12T00:00:00Z"), date("2024-08-13T05:00:00Z"))
+            },
+        )
+        .await;
+    assert!(
+        matches!(&not_midnight, Err(Error::BadEvent(err)) if err.to_string().contains("2024-08-11T19:00:00-05:00"))
+    );
+
+    for timezone in ["America/Chicago", "-5", "+15:00"] {
+        let invalid = test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                CreateEvent {
+                    timezone: Some(String::from(timezone)),
+                    ..mock_event(date("2024-08-12T00:00:00Z"), date("2024-08-13T00:00:00Z"))
+                },
+            )
+            .await;
+        assert!(matches!(invalid, Err(Error::BadEvent(_))), "{}", timezone);
+    }
+}
+
+#[tokio::test]
+async fn negative_offset_event_transitions_at_local_midnight() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let local_midnight = OffsetDateTime::now_utc()
+        .to_offset(offset!(-5))
+        .replace_time(Time::MIDNIGHT);
+
+    let started = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                timezone: Some(String::from("-05:00")),
+                ..mock_event(local_midnight, local_midnight + Duration::days(2))
+            },
+        )
+        .await
+        .unwrap();
+    let upcoming = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                timezone: Some(String::from("-05:00")),
+                ..mock_event(
+                    local_midnight + Duration::days(1),
+                    local_midnight + Duration::days(2),
+                )
+            },
+        )
+        .await
+        .unwrap();
+
+    let started = test_app.oracle.get_event(&started.id).await.unwrap();
+    assert_eq!(started.observation_date, local_midnight);
+    assert_eq!(
+        started.observation_date.time(),
+        Time::from_hms(5, 0, 0).unwrap()
+    );
+    assert_eq!(started.status, EventStatus::Completed);
+    let upcoming = test_app.oracle.get_event(&upcoming.id).await.unwrap();
+    assert_eq!(upcoming.status, EventStatus::Live);
+}
+
+#[tokio::test]
+async fn negative_offset_event_is_scored_with_its_local_days_weather() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    // Daily forecasts are looked up by the local day, observations from local midnight on
+    weather_data
+        .expect_forecasts_data()
+        .withf(|request, _| {
+            request.start == Some(date("2024-08-12T00:00:00Z"))
+                && request.end == Some(date("2024-08-13T00:00:00Z"))
+        })
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .withf(|request, _| {
+            request.start == Some(date("2024-08-12T05:00:00Z"))
+                && request.end == Some(date("2024-08-13T05:00:00Z"))
+        })
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                timezone: Some(String::from("-05:00")),
+                ..mock_event(
+                    date("2024-08-12T00:00:00-05:00"),
+                    OffsetDateTime::now_utc() - Duration::hours(1),
+                )
+            },
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.status, EventStatus::Signed);
+    assert!(event.attestation.is_some());
+    assert_eq!(event.entries.len(), 4);
+    assert!(event.entries.iter().all(|entry| entry.score.is_some()));
+}
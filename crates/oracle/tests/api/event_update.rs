@@ -0,0 +1,137 @@
+use crate::{
+    etl_workflow::mock_entries,
+    helpers::{create_auth_event, mock_event, spawn_app, MockWeatherAccess, TestApp},
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hyper::{header, Method};
+use nostr_sdk::Keys;
+use oracle::{possible_outcome_messages, Event, UpdateEvent, WinMode};
+use serde_json::{from_slice, to_string};
+use std::sync::Arc;
+use time::{Duration, OffsetDateTime, Time};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+async fn update_event(
+    test_app: &TestApp,
+    keys: &Keys,
+    event_id: Uuid,
+    update: &UpdateEvent,
+) -> (StatusCode, Vec<u8>) {
+    let path = format!("/oracle/events/{}", event_id);
+    let auth_event = create_auth_event(
+        "PATCH",
+        &format!("http://localhost:3000{}", path),
+        None,
+        keys,
+    )
+    .await;
+    let request = Request::builder()
+        .method(Method::PATCH)
+        .uri(path)
+        .header(header::CONTENT_TYPE, "application/json")
+        .header(
+            header::AUTHORIZATION,
+            format!("Nostr {}", BASE64.encode(to_string(&auth_event).unwrap())),
+        )
+        .header("host", "localhost:3000")
+        .body(Body::from(to_string(update).unwrap()))
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+async fn create_live_event(test_app: &TestApp, keys: &Keys) -> Event {
+    let observation_date =
+        (OffsetDateTime::now_utc() + Duration::days(1)).replace_time(Time::MIDNIGHT);
+    test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(observation_date, observation_date + Duration::days(1)),
+        )
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn can_update_outcomes_of_event_without_entries() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = create_live_event(&test_app, &keys).await;
+    let update = UpdateEvent {
+        number_of_places_win: Some(2),
+        ..Default::default()
+    };
+
+    let (status, body) = update_event(&test_app, &keys, event.id, &update).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let updated: Event = from_slice(&body).unwrap();
+    assert_eq!(updated.number_of_places_win, 2);
+    assert_eq!(updated.total_allowed_entries, 4);
+    assert_eq!(updated.win_mode, WinMode::TopN);
+    assert_eq!(updated.locations, event.locations);
+    assert_ne!(updated.nonce, event.nonce);
+    assert_eq!(
+        updated.event_announcement.locking_points.len(),
+        possible_outcome_messages(&WinMode::TopN, 4, 2).len()
+    );
+    assert_ne!(
+        updated.event_announcement.locking_points,
+        event.event_announcement.locking_points
+    );
+    assert_eq!(test_app.oracle.get_event(&event.id).await.unwrap(), updated);
+
+    // The edit goes through the same checks as creating an event
+    let too_many_places = UpdateEvent {
+        number_of_places_win: Some(5),
+        ..Default::default()
+    };
+    let (status, _) = update_event(&test_app, &keys, event.id, &too_many_places).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = update_event(&test_app, &Keys::generate(), event.id, &update).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+    assert_eq!(test_app.oracle.get_event(&event.id).await.unwrap(), updated);
+}
+
+#[tokio::test]
+async fn rejects_update_once_event_has_entries() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = create_live_event(&test_app, &keys).await;
+    let [entry, ..] = mock_entries(event.id);
+    test_app
+        .event_data
+        .add_event_entry(entry.into())
+        .await
+        .unwrap();
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+
+    let update = UpdateEvent {
+        number_of_places_win: Some(2),
+        ..Default::default()
+    };
+    let (status, body) = update_event(&test_app, &keys, event.id, &update).await;
+
+    assert_eq!(status, StatusCode::CONFLICT);
+    assert!(String::from_utf8(body)
+        .unwrap()
+        .contains("already has entries"));
+    let unchanged = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(unchanged.number_of_places_win, 1);
+    assert_eq!(unchanged.nonce, event.nonce);
+    assert_eq!(unchanged.event_announcement, event.event_announcement);
+}
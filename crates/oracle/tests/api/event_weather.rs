@@ -0,0 +1,780 @@
+use crate::helpers::{
+    create_auth_event, new_event, random_test_number, spawn_app, spawn_app_with_config,
+    station_ids, MockWeatherAccess, TestApp, TestAppConfig,
+};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use duckdb::Connection;
+use futures::future::join_all;
+use hyper::{
+    header::{AUTHORIZATION, CONTENT_TYPE},
+    Method,
+};
+use nostr_sdk::{
+    hashes::{sha256::Hash as Sha256Hash, Hash},
+    Keys,
+};
+use oracle::{
+    create_folder, AddEventEntry, CreateEvent, Event, EventData, EventStatus, Forecast, Forecasted,
+    Observation, Observed, SequenceIdGenerator, ValueDiff, ValueOptions, Weather, WeatherChoices,
+    WeatherDiff,
+};
+use serde_json::{from_slice, to_string};
+use std::sync::Arc;
+use time::{macros::datetime, OffsetDateTime, Time};
+use tower::ServiceExt;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn can_serialize_concurrent_weather_updates_for_an_event() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let locations = vec![
+        String::from("PFNO"),
+        String::from("KSAW"),
+        String::from("PAPG"),
+        String::from("KWMC"),
+    ];
+    let create_event = CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: locations.clone(),
+        ..new_event()
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, create_event)
+        .await
+        .unwrap();
+
+    let weather: Vec<Weather> = locations
+        .iter()
+        .map(|station_id| Weather {
+            station_id: station_id.clone(),
+            observed: None,
+            forecasted: Forecasted {
+                date: OffsetDateTime::now_utc(),
+                temp_low: 10,
+                temp_high: 20,
+                wind_speed: 5,
+            },
+        })
+        .collect();
+
+    let total_updates = 5;
+    let updates = (0..total_updates).map(|_| {
+        let event_data = test_app.event_data.clone();
+        let weather = weather.clone();
+        tokio::spawn(async move {
+            event_data
+                .update_weather_station_data(event.id, weather)
+                .await
+        })
+    });
+    for result in join_all(updates).await {
+        result.unwrap().unwrap();
+    }
+
+    let event_weather_count = test_app
+        .event_data
+        .get_event_weather_count(event.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        event_weather_count,
+        (total_updates * locations.len()) as i64
+    );
+}
+
+#[tokio::test]
+async fn can_generate_predictable_weather_ids() {
+    let event_data = format!("./test_data/{}/event_data", random_test_number());
+    create_folder("./test_data");
+    create_folder(&event_data.replace("/event_data", ""));
+    create_folder(&event_data);
+    let event_db =
+        EventData::new_with_id_generator(&event_data, Arc::new(SequenceIdGenerator::new(100)))
+            .unwrap();
+
+    let weather: Vec<Weather> = vec![String::from("PFNO"), String::from("KSAW")]
+        .into_iter()
+        .map(|station_id| Weather {
+            station_id,
+            observed: None,
+            forecasted: Forecasted {
+                date: OffsetDateTime::now_utc(),
+                temp_low: 10,
+                temp_high: 20,
+                wind_speed: 5,
+            },
+        })
+        .collect();
+
+    let weather_ids = event_db.add_weather_readings(weather).await.unwrap();
+
+    assert_eq!(
+        weather_ids,
+        vec![
+            SequenceIdGenerator::id_at(100),
+            SequenceIdGenerator::id_at(101)
+        ]
+    );
+}
+
+#[tokio::test]
+async fn can_download_event_weather_as_parquet() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let create_event = CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        ..new_event()
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, create_event)
+        .await
+        .unwrap();
+
+    let weather = vec![
+        Weather {
+            station_id: String::from("KSAW"),
+            observed: Some(Observed {
+                date: datetime!(2024-08-12 00:00:00 UTC),
+                temp_low: 9,
+                temp_high: 22,
+                wind_speed: 7,
+                original_offset: None,
+                raw_temps: None,
+            }),
+            forecasted: Forecasted {
+                date: datetime!(2024-08-12 00:00:00 UTC),
+                temp_low: 10,
+                temp_high: 20,
+                wind_speed: 5,
+            },
+        },
+        Weather {
+            station_id: String::from("PFNO"),
+            observed: None,
+            forecasted: Forecasted {
+                date: datetime!(2024-08-12 00:00:00 UTC),
+                temp_low: 11,
+                temp_high: 21,
+                wind_speed: 6,
+            },
+        },
+    ];
+    test_app
+        .event_data
+        .update_weather_station_data(event.id, weather.clone())
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/oracle/events/{}/weather.parquet", event.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    assert_eq!(
+        response.headers().get(CONTENT_TYPE).unwrap(),
+        "application/parquet"
+    );
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let folder = format!("./test_data/{}", random_test_number());
+    create_folder("./test_data");
+    create_folder(&folder);
+    let file_path = format!("{}/{}_weather.parquet", folder, event.id);
+    std::fs::write(&file_path, &body).unwrap();
+
+    let conn = Connection::open_in_memory().unwrap();
+    let mut stmt = conn
+        .prepare(&format!(
+            "SELECT
+                station_id,
+                epoch_us(forecasted_date),
+                forecasted_temp_low,
+                forecasted_temp_high,
+                forecasted_wind_speed,
+                epoch_us(observed_date),
+                observed_temp_low,
+                observed_temp_high,
+                observed_wind_speed
+            FROM read_parquet('{}')
+            ORDER BY station_id",
+            file_path
+        ))
+        .unwrap();
+    let to_date =
+        |micros: i64| OffsetDateTime::from_unix_timestamp_nanos(micros as i128 * 1000).unwrap();
+    let downloaded: Vec<Weather> = stmt
+        .query_map([], |row| {
+            let observed_date: Option<i64> = row.get(5)?;
+            Ok(Weather {
+                station_id: row.get(0)?,
+                forecasted: Forecasted {
+                    date: to_date(row.get(1)?),
+                    temp_low: row.get(2)?,
+                    temp_high: row.get(3)?,
+                    wind_speed: row.get(4)?,
+                },
+                observed: match observed_date {
+                    Some(date) => Some(Observed {
+                        date: to_date(date),
+                        temp_low: row.get(6)?,
+                        temp_high: row.get(7)?,
+                        wind_speed: row.get(8)?,
+                        original_offset: None,
+                        raw_temps: None,
+                    }),
+                    None => None,
+                },
+            })
+        })
+        .unwrap()
+        .collect::<Result<Vec<Weather>, duckdb::Error>>()
+        .unwrap();
+
+    assert_eq!(downloaded, weather);
+}
+
+#[tokio::test]
+async fn can_diff_observed_and_forecasted_event_weather() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let create_event = CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: station_ids(&["PFNO", "KSAW", "PAPG"]),
+        ..new_event()
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, create_event)
+        .await
+        .unwrap();
+
+    let forecasted = Forecasted {
+        date: datetime!(2024-08-12 00:00:00 UTC),
+        temp_low: 10,
+        temp_high: 20,
+        wind_speed: 5,
+    };
+    let observed = Observed {
+        date: datetime!(2024-08-12 00:00:00 UTC),
+        temp_low: 8,
+        temp_high: 20,
+        wind_speed: 9,
+        original_offset: None,
+        raw_temps: None,
+    };
+    let weather = vec![
+        Weather {
+            station_id: String::from("KSAW"),
+            observed: Some(observed.clone()),
+            forecasted: forecasted.clone(),
+        },
+        Weather {
+            station_id: String::from("PFNO"),
+            observed: None,
+            forecasted: forecasted.clone(),
+        },
+    ];
+    test_app
+        .event_data
+        .update_weather_station_data(event.id, weather)
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/oracle/events/{}/weather/diff", event.id))
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let diffs: Vec<WeatherDiff> = from_slice(&body).unwrap();
+
+    // Ordered like the event locations, PAPG has no weather yet so it is left out
+    assert_eq!(
+        diffs,
+        vec![
+            WeatherDiff {
+                station_id: String::from("PFNO"),
+                forecasted: forecasted.clone(),
+                observed: None,
+                temp_low: None,
+                temp_high: None,
+                wind_speed: None,
+            },
+            WeatherDiff {
+                station_id: String::from("KSAW"),
+                forecasted,
+                observed: Some(observed),
+                temp_low: Some(ValueDiff {
+                    diff: -2,
+                    outcome: ValueOptions::Under,
+                }),
+                temp_high: Some(ValueDiff {
+                    diff: 0,
+                    outcome: ValueOptions::Par,
+                }),
+                wind_speed: Some(ValueDiff {
+                    diff: 4,
+                    outcome: ValueOptions::Over,
+                }),
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn uses_latest_reading_when_diffing_event_weather() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let create_event = CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: station_ids(&["KSAW"]),
+        number_of_values_per_entry: 3,
+        ..new_event()
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, create_event)
+        .await
+        .unwrap();
+
+    let forecasted = Forecasted {
+        date: datetime!(2024-08-12 00:00:00 UTC),
+        temp_low: 10,
+        temp_high: 20,
+        wind_speed: 5,
+    };
+    let readings = [
+        None,
+        Some(Observed {
+            date: datetime!(2024-08-12 00:00:00 UTC),
+            temp_low: 12,
+            temp_high: 18,
+            wind_speed: 5,
+            original_offset: None,
+            raw_temps: None,
+        }),
+    ];
+    for observed in readings {
+        test_app
+            .event_data
+            .update_weather_station_data(
+                event.id,
+                vec![Weather {
+                    station_id: String::from("KSAW"),
+                    observed,
+                    forecasted: forecasted.clone(),
+                }],
+            )
+            .await
+            .unwrap();
+    }
+
+    let diffs = test_app
+        .oracle
+        .get_event_weather_diff(&event.id)
+        .await
+        .unwrap();
+    assert_eq!(diffs.len(), 1);
+    assert_eq!(
+        diffs[0].temp_low,
+        Some(ValueDiff {
+            diff: 2,
+            outcome: ValueOptions::Over,
+        })
+    );
+    assert_eq!(
+        diffs[0].temp_high,
+        Some(ValueDiff {
+            diff: -2,
+            outcome: ValueOptions::Under,
+        })
+    );
+}
+
+async fn push_event_weather(
+    test_app: &TestApp,
+    keys: &Keys,
+    event_id: Uuid,
+    weather: &[Weather],
+) -> (StatusCode, Vec<u8>) {
+    let body_json = to_string(weather).unwrap();
+    let path = format!("/oracle/events/{}/weather", event_id);
+    let auth_event = create_auth_event(
+        "POST",
+        &format!("http://localhost:3000{}", path),
+        Some(Sha256Hash::hash(body_json.as_bytes())),
+        keys,
+    )
+    .await;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(CONTENT_TYPE, "application/json")
+        .header(
+            AUTHORIZATION,
+            format!("Nostr {}", BASE64.encode(to_string(&auth_event).unwrap())),
+        )
+        .header("host", "localhost:3000")
+        .body(Body::from(body_json))
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+async fn spawn_app_with_operator(operator: &Keys) -> TestApp {
+    let config = TestAppConfig {
+        operator_pubkeys: vec![operator.public_key],
+        ..Default::default()
+    };
+    spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config).await
+}
+
+fn operator_weather(station_id: &str) -> Weather {
+    Weather {
+        station_id: String::from(station_id),
+        observed: Some(Observed {
+            date: datetime!(2024-08-12 00:00:00 UTC),
+            temp_low: 11,
+            temp_high: 21,
+            wind_speed: 7,
+            original_offset: None,
+            raw_temps: None,
+        }),
+        forecasted: Forecasted {
+            date: datetime!(2024-08-12 00:00:00 UTC),
+            temp_low: 10,
+            temp_high: 20,
+            wind_speed: 5,
+        },
+    }
+}
+
+fn operator_event() -> CreateEvent {
+    CreateEvent {
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+            + time::Duration::days(1),
+        ..new_event()
+    }
+}
+
+#[tokio::test]
+async fn operator_can_push_event_weather() {
+    let operator = Keys::generate();
+    let test_app = spawn_app_with_operator(&operator).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, operator_event())
+        .await
+        .unwrap();
+
+    let (status, body) =
+        push_event_weather(&test_app, &operator, event.id, &[operator_weather("ksaw")]).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let res: Event = from_slice(&body).unwrap();
+    assert_eq!(res.weather, vec![operator_weather("KSAW")]);
+    let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(stored.weather, vec![operator_weather("KSAW")]);
+}
+
+#[tokio::test]
+async fn rejects_event_weather_from_non_operators_or_other_stations() {
+    let operator = Keys::generate();
+    let test_app = spawn_app_with_operator(&operator).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, operator_event())
+        .await
+        .unwrap();
+
+    let (status, _) = push_event_weather(
+        &test_app,
+        &Keys::generate(),
+        event.id,
+        &[operator_weather("KSAW")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, body) =
+        push_event_weather(&test_app, &operator, event.id, &[operator_weather("KWMC")]).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(String::from_utf8(body).unwrap().contains("KWMC"));
+
+    let (status, _) = push_event_weather(
+        &test_app,
+        &operator,
+        Uuid::now_v7(),
+        &[operator_weather("KSAW")],
+    )
+    .await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+
+    let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert!(stored.weather.is_empty());
+}
+
+#[tokio::test]
+async fn scores_pushed_weather_in_place_of_the_daemons_readings() {
+    let operator = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 10,
+            temp_high: 20,
+            wind_speed: 5,
+        }])
+    });
+    // The daemon's reading is over the forecast on every value
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 15_f64,
+            temp_high: 25_f64,
+            wind_speed: 9,
+        }])
+    });
+    let config = TestAppConfig {
+        operator_pubkeys: vec![operator.public_key],
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(weather_data), config).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                observation_date: datetime!(2024-08-12 00:00:00 UTC),
+                signing_date: datetime!(2024-08-13 00:00:00 UTC),
+                locations: vec![String::from("KSAW")],
+                number_of_values_per_entry: 3,
+                ..operator_event()
+            },
+        )
+        .await
+        .unwrap();
+    let entry = AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id: event.id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("KSAW"),
+            temp_high: Some(ValueOptions::Par),
+            temp_low: Some(ValueOptions::Par),
+            wind_speed: Some(ValueOptions::Par),
+        }],
+    };
+    test_app
+        .event_data
+        .add_event_entry(entry.clone().into())
+        .await
+        .unwrap();
+
+    // The correction matches the forecast, so every Par pick scores
+    let corrected = Weather {
+        station_id: String::from("KSAW"),
+        observed: Some(Observed {
+            date: datetime!(2024-08-12 00:00:00 UTC),
+            temp_low: 10,
+            temp_high: 20,
+            wind_speed: 5,
+            original_offset: None,
+            raw_temps: None,
+        }),
+        forecasted: Forecasted {
+            date: datetime!(2024-08-12 00:00:00 UTC),
+            temp_low: 10,
+            temp_high: 20,
+            wind_speed: 5,
+        },
+    };
+    let (status, _) =
+        push_event_weather(&test_app, &operator, event.id, &[corrected.clone()]).await;
+    assert_eq!(status, StatusCode::OK);
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    let score = res
+        .entries
+        .iter()
+        .find(|scored| scored.id == entry.id)
+        .unwrap()
+        .score
+        .unwrap();
+    assert_eq!(score / 10000, 60);
+    // The daemon's reading the etl added is kept, the correction stays the latest one
+    assert_eq!(res.weather.len(), 2);
+    assert_eq!(res.weather.last(), Some(&corrected));
+    let diff = test_app
+        .oracle
+        .get_event_weather_diff(&event.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        diff[0].temp_high.as_ref().unwrap().outcome,
+        ValueOptions::Par
+    );
+}
+
+const WEATHER_PARQUET_ROWS: &str = "SELECT * FROM (VALUES
+    ('ksaw', '2024-08-12', '2024-08-12T00:00:00+00:00', '2024-08-13T00:00:00+00:00', 10, 20, 5,
+        '2024-08-12T00:00:00+00:00', '2024-08-13T00:00:00+00:00', 10.6, 21.2, 7),
+    ('PFNO', '2024-08-12', '2024-08-12T00:00:00+00:00', '2024-08-13T00:00:00+00:00', 10, 20, 5,
+        NULL, NULL, NULL, NULL, NULL)
+) AS weather(station_id, forecast_date, forecast_start_time, forecast_end_time, forecast_temp_low,
+    forecast_temp_high, forecast_wind_speed, observation_start_time, observation_end_time,
+    observation_temp_low, observation_temp_high, observation_wind_speed)";
+
+fn write_weather_parquet(test_app: &TestApp, rows: &str) -> Vec<u8> {
+    let path = format!("{}/event_weather_fixture.parquet", test_app.weather_dir);
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!("COPY ({}) TO '{}' (FORMAT PARQUET);", rows, path))
+        .unwrap();
+    let data = std::fs::read(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    data
+}
+
+async fn push_event_weather_parquet(
+    test_app: &TestApp,
+    keys: &Keys,
+    event_id: Uuid,
+    parquet: Vec<u8>,
+) -> (StatusCode, Vec<u8>) {
+    let path = format!("/oracle/events/{}/weather.parquet", event_id);
+    let auth_event = create_auth_event(
+        "POST",
+        &format!("http://localhost:3000{}", path),
+        Some(Sha256Hash::hash(&parquet)),
+        keys,
+    )
+    .await;
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(path)
+        .header(CONTENT_TYPE, "application/parquet")
+        .header(
+            AUTHORIZATION,
+            format!("Nostr {}", BASE64.encode(to_string(&auth_event).unwrap())),
+        )
+        .header("host", "localhost:3000")
+        .body(Body::from(parquet))
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+#[tokio::test]
+async fn operator_can_push_event_weather_as_parquet() {
+    let operator = Keys::generate();
+    let test_app = spawn_app_with_operator(&operator).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, operator_event())
+        .await
+        .unwrap();
+    let parquet = write_weather_parquet(&test_app, WEATHER_PARQUET_ROWS);
+
+    let (status, body) = push_event_weather_parquet(&test_app, &operator, event.id, parquet).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let res: Event = from_slice(&body).unwrap();
+    let mut expected = vec![
+        operator_weather("KSAW"),
+        Weather {
+            observed: None,
+            ..operator_weather("PFNO")
+        },
+    ];
+    expected.sort_by(|a, b| a.station_id.cmp(&b.station_id));
+    let mut pushed = res.weather.clone();
+    pushed.sort_by(|a, b| a.station_id.cmp(&b.station_id));
+    assert_eq!(pushed, expected);
+    let stored_count = test_app
+        .event_data
+        .get_event_weather_count(event.id)
+        .await
+        .unwrap();
+    assert_eq!(stored_count, 2);
+}
+
+#[tokio::test]
+async fn rejects_unreadable_event_weather_parquet() {
+    let operator = Keys::generate();
+    let test_app = spawn_app_with_operator(&operator).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, operator_event())
+        .await
+        .unwrap();
+    let parquet = write_weather_parquet(&test_app, WEATHER_PARQUET_ROWS);
+
+    let (status, _) =
+        push_event_weather_parquet(&test_app, &Keys::generate(), event.id, parquet).await;
+    assert_eq!(status, StatusCode::FORBIDDEN);
+
+    let (status, _) = push_event_weather_parquet(
+        &test_app,
+        &operator,
+        event.id,
+        b"not a parquet file".to_vec(),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let missing_columns = write_weather_parquet(
+        &test_app,
+        "SELECT 'KSAW' AS station_id, '2024-08-12' AS forecast_date",
+    );
+    let (status, _) =
+        push_event_weather_parquet(&test_app, &operator, event.id, missing_columns).await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert!(stored.weather.is_empty());
+}
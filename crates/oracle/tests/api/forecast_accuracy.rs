@@ -0,0 +1,126 @@
+use crate::helpers::{
+    get, new_event, spawn_app, spawn_app_with_entry_grace_period, station_ids, MockWeatherAccess,
+};
+use axum::http::StatusCode;
+use nostr_sdk::Keys;
+use oracle::{
+    AddEventEntry, CreateEvent, EventStatus, Forecast, MeanAbsoluteDiff, Observation,
+    StationAccuracy, ValueOptions, WeatherChoices,
+};
+use serde_json::from_slice;
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+
+use uuid::Uuid;
+
+#[tokio::test]
+async fn signed_event_records_station_accuracy() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(["KSAW", "PFNO"]
+            .iter()
+            .map(|station_id| Forecast {
+                station_id: String::from(*station_id),
+                date: String::from("2024-08-12"),
+                start_time: String::from("2024-08-11T00:00:00+00:00"),
+                end_time: String::from("2024-08-12T00:00:00+00:00"),
+                temp_low: 17,
+                temp_high: 25,
+                wind_speed: 3,
+            })
+            .collect())
+    });
+    // Only KSAW reports, PFNO has nothing to compare its forecast against
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 15_f64,
+            temp_high: 25_f64,
+            wind_speed: 5,
+        }])
+    });
+    // Entries are added through the oracle after the observation date, so the grace period covers it
+    let test_app =
+        spawn_app_with_entry_grace_period(Arc::new(weather_data), Duration::days(365 * 20)).await;
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                observation_date,
+                signing_date: observation_date + Duration::days(1),
+                locations: station_ids(&["KSAW", "PFNO"]),
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: vec![WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_high: Some(ValueOptions::Par),
+                    temp_low: None,
+                    wind_speed: None,
+                }],
+            },
+        )
+        .await
+        .unwrap();
+
+    test_app.oracle.etl_data(1).await.unwrap();
+    let signed = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(signed.status, EventStatus::Signed);
+
+    let (status, body) = get(&test_app, "/oracle/stations/ksaw/accuracy").await;
+    assert_eq!(status, StatusCode::OK);
+    let accuracy: StationAccuracy = from_slice(&body).unwrap();
+    assert_eq!(accuracy.station_id, "KSAW");
+    assert_eq!(accuracy.history.len(), 1);
+    let recorded = &accuracy.history[0];
+    assert_eq!(recorded.event_id, event.id);
+    assert_eq!(recorded.observation_date, observation_date);
+    assert_eq!(recorded.temp_low.diff, -2);
+    assert_eq!(recorded.temp_low.outcome, ValueOptions::Under);
+    assert_eq!(recorded.temp_high.diff, 0);
+    assert_eq!(recorded.temp_high.outcome, ValueOptions::Par);
+    assert_eq!(recorded.wind_speed.diff, 2);
+    assert_eq!(recorded.wind_speed.outcome, ValueOptions::Over);
+    assert_eq!(
+        accuracy.mean_absolute_diff,
+        Some(MeanAbsoluteDiff {
+            temp_low: 2.0,
+            temp_high: 0.0,
+            wind_speed: 2.0,
+        })
+    );
+
+    // Signing again in a later etl run doesn't record the event twice
+    test_app.oracle.etl_data(2).await.unwrap();
+    let (_, body) = get(&test_app, "/oracle/stations/KSAW/accuracy").await;
+    let accuracy: StationAccuracy = from_slice(&body).unwrap();
+    assert_eq!(accuracy.history.len(), 1);
+
+    let (_, body) = get(&test_app, "/oracle/stations/PFNO/accuracy").await;
+    let accuracy: StationAccuracy = from_slice(&body).unwrap();
+    assert!(accuracy.history.is_empty());
+    assert_eq!(accuracy.mean_absolute_diff, None);
+}
+
+#[tokio::test]
+async fn rejects_invalid_station_ids() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let (status, _) = get(&test_app, "/oracle/stations/K-SAW/accuracy").await;
+
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -5,10 +5,10 @@ use axum::{
 };
 use hyper::{header, Method};
 use nostr_sdk::Keys;
-use oracle::{CreateEvent, EventSummary};
-use serde_json::from_slice;
+use oracle::{truncate_to_sql_precision, CreateEvent, Event, EventSummary, ScoringMode, WinMode};
+use serde_json::{from_slice, from_value, Value};
 use std::sync::Arc;
-use time::OffsetDateTime;
+use time::{OffsetDateTime, Time};
 use tower::ServiceExt;
 use uuid::Uuid;
 
@@ -20,7 +20,7 @@ async fn can_get_all_events() {
 
     let new_event_1 = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
             String::from("PFNO"),
@@ -31,10 +31,17 @@ async fn can_get_all_events() {
         total_allowed_entries: 5,
         number_of_values_per_entry: 6,
         number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
     let new_event_2 = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
             String::from("KITH"),
@@ -45,10 +52,17 @@ async fn can_get_all_events() {
         total_allowed_entries: 5,
         number_of_values_per_entry: 6,
         number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
     let new_event_3 = CreateEvent {
         id: Uuid::now_v7(),
-        observation_date: OffsetDateTime::now_utc(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
         signing_date: OffsetDateTime::now_utc(),
         locations: vec![
             String::from("KCQW"),
@@ -59,6 +73,13 @@ async fn can_get_all_events() {
         total_allowed_entries: 5,
         number_of_values_per_entry: 6,
         number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
     };
     let expected = vec![
         new_event_1.clone(),
@@ -100,17 +121,11 @@ async fn can_get_all_events() {
         let cur_expect = expected.get(index).unwrap();
         assert_eq!(
             event_summary.signing_date,
-            cur_expect
-                .signing_date
-                .replace_nanosecond(cur_expect.signing_date.nanosecond() / 1_000 * 1_000)
-                .unwrap()
+            truncate_to_sql_precision(cur_expect.signing_date)
         );
         assert_eq!(
             event_summary.observation_date,
-            cur_expect
-                .observation_date
-                .replace_nanosecond(cur_expect.observation_date.nanosecond() / 1_000 * 1_000)
-                .unwrap()
+            truncate_to_sql_precision(cur_expect.observation_date)
         );
         assert_eq!(
             event_summary.total_allowed_entries,
@@ -121,3 +136,57 @@ async fn can_get_all_events() {
         assert!(event_summary.attestation.is_none());
     }
 }
+
+#[tokio::test]
+async fn can_deserialize_event_with_either_announcement_spelling() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let new_event = CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT),
+        signing_date: OffsetDateTime::now_utc(),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_values_per_entry: 6,
+        number_of_places_win: 1,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    };
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, new_event)
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(format!("/oracle/events/{}", event.id))
+        .header(header::CONTENT_TYPE, "application/json")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let current: Value = from_slice(&body).unwrap();
+    assert!(current.get("event_announcement").is_some());
+
+    // Older clients and payloads used the misspelled field name
+    let mut legacy = current.clone();
+    let legacy_fields = legacy.as_object_mut().unwrap();
+    let announcement = legacy_fields.remove("event_announcement").unwrap();
+    legacy_fields.insert(String::from("event_annoucement"), announcement);
+
+    let from_current: Event = from_value(current).unwrap();
+    let from_legacy: Event = from_value(legacy).unwrap();
+    assert_eq!(from_current, from_legacy);
+    assert_eq!(from_legacy.event_announcement, event.event_announcement);
+}
@@ -1,24 +1,40 @@
 use async_trait::async_trait;
-use axum::Router;
+use axum::{
+    body::{to_bytes, Body},
+    http::{Method, Request, StatusCode},
+    Router,
+};
 use log::{info, LevelFilter};
 use mockall::mock;
 use nostr_sdk::{
     hashes::sha256::Hash as Sha256Hash,
     nips::nip98::{HttpData, HttpMethod},
-    Event, EventBuilder, Keys, Url,
+    Event, EventBuilder, Keys, PublicKey as NostrPublicKey, Url,
 };
 use oracle::{
-    app, create_folder, oracle::Oracle, setup_logger, AppState, EventData, FileData, WeatherData,
+    app, create_folder,
+    oracle::{Oracle, SigningPolicy},
+    setup_logger, AppState, CreateEvent, EventData, FileData, NonceSource, ParTolerance,
+    RandomNonceSource, SchemaDriftPolicy, ScoringMode, StationGroups, WeatherData, WinMode,
+    DEFAULT_SIGNING_CONCURRENCY,
 };
 use rand::Rng;
+use serde::de::DeserializeOwned;
+use serde_json::from_slice;
 use std::{
     str::FromStr,
     sync::{Arc, Once},
 };
+use time::{Duration, OffsetDateTime, Time};
+use tower::ServiceExt;
+use uuid::Uuid;
 
 pub struct TestApp {
     pub app: Router,
     pub oracle: Arc<Oracle>,
+    pub event_data: Arc<EventData>,
+    /// Folder uploaded weather files are written to
+    pub weather_dir: String,
 }
 static INIT_LOGGER: Once = Once::new();
 fn init_logger() {
@@ -32,7 +48,70 @@ pub fn random_test_number() -> i32 {
     rng.gen_range(10000..99999)
 }
 
+pub struct TestAppConfig {
+    pub entry_grace_period: Duration,
+    pub signing_policy: SigningPolicy,
+    pub ui_dir: String,
+    pub nonce_source: Arc<dyn NonceSource>,
+    pub par_tolerance: ParTolerance,
+    pub station_groups: StationGroups,
+    pub schema_drift_policy: SchemaDriftPolicy,
+    pub sign_buffer: Duration,
+    pub operator_pubkeys: Vec<NostrPublicKey>,
+    pub preserve_observation_offsets: bool,
+    pub keep_raw_observation_temps: bool,
+    pub signing_concurrency: usize,
+}
+
+impl Default for TestAppConfig {
+    fn default() -> Self {
+        Self {
+            entry_grace_period: Duration::ZERO,
+            signing_policy: SigningPolicy::default(),
+            ui_dir: String::from("./ui"),
+            nonce_source: Arc::new(RandomNonceSource),
+            par_tolerance: ParTolerance::default(),
+            station_groups: StationGroups::default(),
+            schema_drift_policy: SchemaDriftPolicy::default(),
+            sign_buffer: Duration::ZERO,
+            operator_pubkeys: vec![],
+            preserve_observation_offsets: false,
+            keep_raw_observation_temps: false,
+            signing_concurrency: DEFAULT_SIGNING_CONCURRENCY,
+        }
+    }
+}
+
 pub async fn spawn_app(weather_db: Arc<dyn WeatherData>) -> TestApp {
+    spawn_app_with_config(weather_db, TestAppConfig::default()).await
+}
+
+pub async fn spawn_app_with_entry_grace_period(
+    weather_db: Arc<dyn WeatherData>,
+    entry_grace_period: Duration,
+) -> TestApp {
+    let config = TestAppConfig {
+        entry_grace_period,
+        ..Default::default()
+    };
+    spawn_app_with_config(weather_db, config).await
+}
+
+pub async fn spawn_app_with_signing_policy(
+    weather_db: Arc<dyn WeatherData>,
+    signing_policy: SigningPolicy,
+) -> TestApp {
+    let config = TestAppConfig {
+        signing_policy,
+        ..Default::default()
+    };
+    spawn_app_with_config(weather_db, config).await
+}
+
+pub async fn spawn_app_with_config(
+    weather_db: Arc<dyn WeatherData>,
+    config: TestAppConfig,
+) -> TestApp {
     init_logger();
     create_folder("./test_data");
     let random_test_number = random_test_number();
@@ -42,24 +121,57 @@ pub async fn spawn_app(weather_db: Arc<dyn WeatherData>) -> TestApp {
     let event_data = format!("{}/event_data", test_folder);
     create_folder(&event_data.clone());
 
-    let event_db = Arc::new(EventData::new(&event_data).unwrap());
+    let weather_dir = format!("{}/weather_data", test_folder);
+    create_folder(&weather_dir.clone());
+
+    let event_db = Arc::new(
+        EventData::new(&event_data)
+            .and_then(|event_db| event_db.with_extensions(&[String::from("parquet")]))
+            .unwrap(),
+    );
     let private_key_file_path = String::from("./oracle_private_key.pem");
     let oracle = Arc::new(
-        Oracle::new(event_db, weather_db.clone(), &private_key_file_path)
-            .await
-            .unwrap(),
+        Oracle::new(
+            event_db.clone(),
+            weather_db.clone(),
+            &private_key_file_path,
+            &[],
+        )
+        .await
+        .unwrap()
+        .with_entry_grace_period(config.entry_grace_period)
+        .with_signing_policy(config.signing_policy)
+        .with_nonce_source(config.nonce_source)
+        .with_par_tolerance(config.par_tolerance)
+        .with_station_groups(config.station_groups)
+        .with_sign_buffer(config.sign_buffer)
+        .with_operator_pubkeys(config.operator_pubkeys)
+        .with_preserve_observation_offsets(config.preserve_observation_offsets)
+        .with_keep_raw_observation_temps(config.keep_raw_observation_temps)
+        .with_signing_concurrency(config.signing_concurrency),
     );
 
+    let mut file_access = MockFileAccess::new();
+    let current_folder = weather_dir.clone();
+    file_access
+        .expect_current_folder()
+        .returning(move || current_folder.clone());
     let app_state = AppState {
-        ui_dir: String::from("./ui"),
+        ui_dir: config.ui_dir,
         remote_url: String::from("http://127.0.0.1:9100"),
         weather_db,
-        file_access: Arc::new(MockFileAccess::new()),
+        file_access: Arc::new(file_access),
         oracle: oracle.clone(),
+        schema_drift_policy: config.schema_drift_policy,
     };
     let app = app(app_state);
 
-    TestApp { app, oracle }
+    TestApp {
+        app,
+        oracle,
+        event_data: event_db,
+        weather_dir,
+    }
 }
 
 mock! {
@@ -109,3 +221,69 @@ pub async fn create_auth_event(
         .sign_with_keys(keys)
         .expect("Failed to sign event")
 }
+
+/// A top_n event on PFNO and KSAW observed tomorrow (midnight UTC) and signed the day after, tests change the
+/// fields they care about with `CreateEvent { total_allowed_entries: 4, ..new_event() }`
+pub fn new_event() -> CreateEvent {
+    let observation_date =
+        OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT) + Duration::days(1);
+    CreateEvent {
+        id: Uuid::now_v7(),
+        observation_date,
+        signing_date: observation_date + Duration::days(1),
+        locations: vec![String::from("PFNO"), String::from("KSAW")],
+        total_allowed_entries: 5,
+        number_of_places_win: 1,
+        number_of_values_per_entry: 6,
+        win_mode: WinMode::TopN,
+        score_threshold: None,
+        auto_sign: false,
+        scoring_mode: ScoringMode::Linear,
+        station_group: None,
+        late_entry_grace_seconds: 0,
+        timezone: None,
+    }
+}
+
+/// An event on the four stations `etl_workflow`'s mock weather has readings for, taking its 4 `mock_entries`
+pub fn mock_event(observation_date: OffsetDateTime, signing_date: OffsetDateTime) -> CreateEvent {
+    CreateEvent {
+        observation_date,
+        signing_date,
+        locations: station_ids(&["PFNO", "KSAW", "PAPG", "KWMC"]),
+        total_allowed_entries: 4,
+        ..new_event()
+    }
+}
+
+pub fn station_ids(station_ids: &[&str]) -> Vec<String> {
+    station_ids
+        .iter()
+        .map(|station_id| station_id.to_string())
+        .collect()
+}
+
+/// Sends a GET for the uri to the test app, returning the response status and body
+pub async fn get(test_app: &TestApp, uri: &str) -> (StatusCode, Vec<u8>) {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, body.to_vec())
+}
+
+/// Sends a GET for the uri to the test app, expecting a 200 with a json body
+pub async fn get_json<T: DeserializeOwned>(test_app: &TestApp, uri: &str) -> T {
+    let (status, body) = get(test_app, uri).await;
+    assert_eq!(status, StatusCode::OK, "GET {}", uri);
+    from_slice(&body).unwrap()
+}
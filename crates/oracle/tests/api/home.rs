@@ -0,0 +1,82 @@
+use crate::helpers::{spawn_app_with_config, MockWeatherAccess, TestAppConfig};
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+};
+use hyper::Method;
+use std::{str::from_utf8, sync::Arc};
+use tower::ServiceExt;
+
+#[cfg(not(feature = "embed-ui"))]
+#[tokio::test]
+async fn can_get_fallback_home_page_when_ui_dir_missing() {
+    let config = TestAppConfig {
+        ui_dir: String::from("./ui_dir_that_does_not_exist"),
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config).await;
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let page = from_utf8(&body).unwrap();
+    assert!(page.contains("http://127.0.0.1:9100/docs"));
+    assert!(!page.contains("{SERVER_ADDRESS}"));
+}
+
+#[cfg(feature = "embed-ui")]
+#[tokio::test]
+async fn can_get_embedded_home_page_when_ui_dir_missing() {
+    let config = TestAppConfig {
+        ui_dir: String::from("./ui_dir_that_does_not_exist"),
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config).await;
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let page = from_utf8(&body).unwrap();
+    assert!(page.contains("NOAA Forecast and Observation Data Analyzer"));
+    assert!(page.contains("const API_BASE = \"http://127.0.0.1:9100\";"));
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/ui/main.js")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), 200);
+    assert_eq!(
+        response.headers().get("content-type").unwrap(),
+        "application/javascript; charset=utf-8"
+    );
+}
@@ -0,0 +1,128 @@
+use crate::helpers::{new_event, random_test_number, MockWeatherAccess};
+use nostr_sdk::Keys;
+use oracle::{
+    create_folder,
+    oracle::{get_winning_bytes, Error, Oracle},
+    Event, EventData,
+};
+use std::sync::Arc;
+
+struct KeyRotationTest {
+    event_db: Arc<EventData>,
+    folder: String,
+}
+
+impl KeyRotationTest {
+    fn new() -> Self {
+        let folder = format!("./test_data/{}", random_test_number());
+        create_folder("./test_data");
+        create_folder(&folder);
+        let event_data = format!("{}/event_data", folder);
+        create_folder(&event_data);
+        Self {
+            event_db: Arc::new(EventData::new(&event_data).unwrap()),
+            folder,
+        }
+    }
+
+    fn key_path(&self, name: &str) -> String {
+        format!("{}/{}.pem", self.folder, name)
+    }
+
+    async fn oracle(&self, primary_key: &str, retired_keys: &[&str]) -> Result<Oracle, Error> {
+        let retired_key_paths: Vec<String> = retired_keys
+            .iter()
+            .map(|name| self.key_path(name))
+            .collect();
+        Oracle::new(
+            self.event_db.clone(),
+            Arc::new(MockWeatherAccess::new()),
+            &self.key_path(primary_key),
+            &retired_key_paths,
+        )
+        .await
+    }
+}
+
+async fn create_event(oracle: &Oracle) -> Event {
+    let create_event = new_event();
+    oracle
+        .create_event(Keys::generate().public_key, create_event)
+        .await
+        .unwrap()
+}
+
+#[tokio::test]
+async fn can_validate_event_announced_under_retired_key() {
+    let test = KeyRotationTest::new();
+    let old_oracle = test.oracle("old_key", &[]).await.unwrap();
+    let event = create_event(&old_oracle).await;
+
+    let rotated_oracle = test.oracle("new_key", &["old_key"]).await.unwrap();
+    assert_ne!(rotated_oracle.raw_public_key(), old_oracle.raw_public_key());
+
+    let outcome = get_winning_bytes(vec![2]);
+    let signing_key =
+        rotated_oracle.event_signing_key(event.nonce, &event.event_announcement, &outcome);
+    assert_eq!(signing_key, Some(old_oracle.raw_private_key()));
+}
+
+#[tokio::test]
+async fn signs_new_events_with_primary_key_after_rotation() {
+    let test = KeyRotationTest::new();
+    test.oracle("old_key", &[]).await.unwrap();
+    let rotated_oracle = test.oracle("new_key", &["old_key"]).await.unwrap();
+    let event = create_event(&rotated_oracle).await;
+
+    let outcome = get_winning_bytes(vec![0]);
+    let signing_key =
+        rotated_oracle.event_signing_key(event.nonce, &event.event_announcement, &outcome);
+    assert_eq!(signing_key, Some(rotated_oracle.raw_private_key()));
+}
+
+#[tokio::test]
+async fn can_not_validate_event_once_its_key_is_dropped() {
+    let test = KeyRotationTest::new();
+    let old_oracle = test.oracle("old_key", &[]).await.unwrap();
+    let event = create_event(&old_oracle).await;
+    test.oracle("new_key", &["old_key"]).await.unwrap();
+
+    // The database now belongs to the new key, so it starts without the retired key
+    let new_oracle = test.oracle("new_key", &[]).await.unwrap();
+    let outcome = get_winning_bytes(vec![0]);
+    let signing_key =
+        new_oracle.event_signing_key(event.nonce, &event.event_announcement, &outcome);
+    assert!(signing_key.is_none());
+}
+
+#[tokio::test]
+async fn rejects_key_that_does_not_match_stored_pubkey() {
+    let test = KeyRotationTest::new();
+    test.oracle("old_key", &[]).await.unwrap();
+
+    let result = test.oracle("other_key", &[]).await;
+    assert!(matches!(result, Err(Error::MismatchPubkey(_))));
+}
+
+#[tokio::test]
+async fn rejects_missing_retired_key_file() {
+    let test = KeyRotationTest::new();
+
+    let result = test.oracle("new_key", &["missing_key"]).await;
+    assert!(matches!(result, Err(Error::ValidateKey(_))));
+}
+
+#[tokio::test]
+async fn stores_the_latest_key_across_rotations() {
+    let test = KeyRotationTest::new();
+    test.oracle("old_key", &[]).await.unwrap();
+    test.oracle("new_key", &["old_key"]).await.unwrap();
+
+    let newest_oracle = test.oracle("newest_key", &["new_key"]).await.unwrap();
+
+    let stored_public_key = test.event_db.get_stored_public_key().await.unwrap();
+    assert_eq!(
+        stored_public_key,
+        newest_oracle.raw_public_key().x_only_public_key().0
+    );
+}
@@ -1,5 +1,47 @@
+mod active_stations;
+mod app_config;
+mod attestation_encoding;
+mod auto_sign;
+mod capacity;
+mod combined_weather;
 mod create_event;
 mod create_event_entry;
+mod dimensions;
+mod duckdb_extensions;
+mod entries_csv;
+mod entry_breakdown;
+mod entry_counts;
+mod error_responses;
 mod etl_workflow;
+mod event_announcement;
+mod event_cancellation;
+mod event_db_file;
+mod event_expired;
+mod event_timezone;
+mod event_update;
+mod event_weather;
+mod forecast_accuracy;
 mod get_events;
 mod helpers;
+mod home;
+mod key_rotation;
+mod observation_offsets;
+mod par_tolerance;
+mod partial_entries;
+mod payouts;
+mod raw_observation_temps;
+mod schema;
+mod schema_drift;
+mod scoring;
+mod server_config;
+mod sign_status;
+mod signing_queue;
+mod sql_timestamps;
+mod station_events;
+mod station_groups;
+mod station_id_casing;
+mod stored_stations;
+mod upload_file;
+mod version;
+mod weather_parquet_import;
+mod weather_unavailable;
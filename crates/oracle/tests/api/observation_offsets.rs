@@ -0,0 +1,116 @@
+use crate::helpers::{
+    new_event, spawn_app_with_config, station_ids, MockWeatherAccess, TestAppConfig,
+};
+use nostr_sdk::Keys;
+use oracle::{
+    AddEventEntry, CreateEvent, Forecast, Observation, Observed, ValueOptions, WeatherChoices,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, UtcOffset};
+use uuid::Uuid;
+
+/// Runs an etl over a KSAW observation reported during eastern daylight time, returns what was stored
+async fn stored_observation(preserve_observation_offsets: bool) -> Observed {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 17,
+            temp_high: 25,
+            wind_speed: 3,
+        }])
+    });
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00-04:00"),
+            end_time: String::from("2024-08-13T00:00:00-04:00"),
+            temp_low: 15_f64,
+            temp_high: 25_f64,
+            wind_speed: 5,
+        }])
+    });
+    // Entries are added through the oracle after the observation date, so the grace period covers it
+    let test_app = spawn_app_with_config(
+        Arc::new(weather_data),
+        TestAppConfig {
+            entry_grace_period: Duration::days(365 * 20),
+            preserve_observation_offsets,
+            ..Default::default()
+        },
+    )
+    .await;
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                observation_date,
+                signing_date: observation_date + Duration::days(1),
+                locations: station_ids(&["KSAW"]),
+                number_of_values_per_entry: 3,
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: vec![WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_high: Some(ValueOptions::Par),
+                    temp_low: None,
+                    wind_speed: None,
+                }],
+            },
+        )
+        .await
+        .unwrap();
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.weather.len(), 1);
+    event.weather[0].observed.clone().unwrap()
+}
+
+#[tokio::test]
+async fn keeps_original_offset_of_observations_when_configured() {
+    let observed = stored_observation(true).await;
+
+    assert_eq!(
+        observed.date,
+        OffsetDateTime::parse("2024-08-12T04:00:00Z", &Rfc3339).unwrap()
+    );
+    assert_eq!(observed.date.offset(), UtcOffset::UTC);
+    assert_eq!(observed.original_offset, Some(String::from("-04:00")));
+    let original_date = observed.original_date();
+    assert_eq!(original_date, observed.date);
+    assert_eq!(
+        original_date.offset(),
+        UtcOffset::from_hms(-4, 0, 0).unwrap()
+    );
+    assert_eq!(original_date.hour(), 0);
+}
+
+#[tokio::test]
+async fn observations_are_only_kept_in_utc_by_default() {
+    let observed = stored_observation(false).await;
+
+    assert_eq!(
+        observed.date,
+        OffsetDateTime::parse("2024-08-12T04:00:00Z", &Rfc3339).unwrap()
+    );
+    assert_eq!(observed.original_offset, None);
+    assert_eq!(observed.original_date().offset(), UtcOffset::UTC);
+}
@@ -0,0 +1,211 @@
+use crate::{
+    etl_workflow::mock_observation_date,
+    helpers::{
+        new_event, spawn_app_with_config, station_ids, MockWeatherAccess, TestApp, TestAppConfig,
+    },
+};
+use nostr_sdk::Keys;
+use oracle::{
+    oracle::Oracle, AddEventEntry, CreateEvent, Event, EventStatus, Forecast, Observation,
+    ParTolerance, ValueOptions, WeatherChoices, WeatherData,
+};
+use std::sync::Arc;
+use time::Duration;
+use uuid::Uuid;
+
+#[test]
+fn par_covers_both_edges_of_the_tolerance_band() {
+    let tolerance = ParTolerance::try_from(2).unwrap();
+
+    assert_eq!(ValueOptions::outcome(30, 32, tolerance), ValueOptions::Par);
+    assert_eq!(ValueOptions::outcome(30, 28, tolerance), ValueOptions::Par);
+    assert_eq!(ValueOptions::outcome(30, 33, tolerance), ValueOptions::Over);
+    assert_eq!(
+        ValueOptions::outcome(30, 27, tolerance),
+        ValueOptions::Under
+    );
+}
+
+#[test]
+fn zero_tolerance_only_counts_exact_matches_as_par() {
+    let tolerance = ParTolerance::default();
+
+    assert_eq!(ValueOptions::outcome(30, 30, tolerance), ValueOptions::Par);
+    assert_eq!(ValueOptions::outcome(30, 31, tolerance), ValueOptions::Over);
+    assert_eq!(
+        ValueOptions::outcome(30, 29, tolerance),
+        ValueOptions::Under
+    );
+}
+
+#[test]
+fn rejects_negative_par_tolerance() {
+    assert!(ParTolerance::try_from(-1).is_err());
+    assert_eq!(ParTolerance::try_from(0).unwrap(), ParTolerance::default());
+}
+
+fn choices(
+    temp_high: ValueOptions,
+    temp_low: ValueOptions,
+    wind_speed: ValueOptions,
+) -> Vec<WeatherChoices> {
+    vec![WeatherChoices {
+        stations: String::from("KSAW"),
+        temp_high: Some(temp_high),
+        temp_low: Some(temp_low),
+        wind_speed: Some(wind_speed),
+    }]
+}
+
+// temp_high is at the top edge of a band of 2, wind_speed at the bottom edge and temp_low just past it
+fn band_edge_weather() -> MockWeatherAccess {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 17,
+            temp_high: 25,
+            wind_speed: 3,
+        }])
+    });
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 20_f64,
+            temp_high: 27_f64,
+            wind_speed: 1,
+        }])
+    });
+    weather_data
+}
+
+fn ksaw_event() -> CreateEvent {
+    CreateEvent {
+        observation_date: mock_observation_date(),
+        signing_date: mock_observation_date() + Duration::days(1),
+        locations: station_ids(&["KSAW"]),
+        total_allowed_entries: 2,
+        number_of_values_per_entry: 3,
+        ..new_event()
+    }
+}
+
+async fn add_entries(test_app: &TestApp, event_id: Uuid) -> Vec<Uuid> {
+    let entries = [
+        // Par, Par past the band and Par scores 20 + 0 + 20
+        choices(ValueOptions::Par, ValueOptions::Par, ValueOptions::Par),
+        // Over inside the band, Over past the band and Under inside the band scores 0 + 10 + 0
+        choices(ValueOptions::Over, ValueOptions::Over, ValueOptions::Under),
+    ];
+    let mut entry_ids = vec![];
+    for expected_observations in entries {
+        let entry = AddEventEntry {
+            id: Uuid::now_v7(),
+            event_id,
+            expected_observations,
+        };
+        entry_ids.push(entry.id);
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    entry_ids
+}
+
+fn base_scores(event: &Event, entry_ids: &[Uuid]) -> Vec<i64> {
+    entry_ids
+        .iter()
+        .map(|id| {
+            let entry = event.entries.iter().find(|entry| entry.id == *id).unwrap();
+            entry.score.unwrap() / 10000
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn scores_par_within_tolerance_of_the_forecast() {
+    let keys = Keys::generate();
+    let config = TestAppConfig {
+        par_tolerance: ParTolerance::try_from(2).unwrap(),
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(band_edge_weather()), config).await;
+
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, ksaw_event())
+        .await
+        .unwrap();
+    let entry_ids = add_entries(&test_app, event.id).await;
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    assert_eq!(base_scores(&res, &entry_ids), vec![40, 10]);
+
+    let diff = test_app
+        .oracle
+        .get_event_weather_diff(&event.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        diff[0].temp_high.as_ref().unwrap().outcome,
+        ValueOptions::Par
+    );
+    assert_eq!(
+        diff[0].temp_low.as_ref().unwrap().outcome,
+        ValueOptions::Over
+    );
+    assert_eq!(
+        diff[0].wind_speed.as_ref().unwrap().outcome,
+        ValueOptions::Par
+    );
+}
+
+#[tokio::test]
+async fn scores_with_the_tolerance_the_event_was_created_with() {
+    let keys = Keys::generate();
+    let weather_data: Arc<dyn WeatherData> = Arc::new(band_edge_weather());
+    let config = TestAppConfig {
+        par_tolerance: ParTolerance::try_from(2).unwrap(),
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(weather_data.clone(), config).await;
+
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, ksaw_event())
+        .await
+        .unwrap();
+    assert_eq!(event.par_tolerance, Some(2));
+    let entry_ids = add_entries(&test_app, event.id).await;
+
+    // Restarted with the default tolerance of 0 before the event is scored
+    let restarted = Oracle::new(
+        test_app.event_data.clone(),
+        weather_data,
+        &String::from("./oracle_private_key.pem"),
+        &[],
+    )
+    .await
+    .unwrap();
+    restarted.etl_data(1).await.unwrap();
+
+    let res = restarted.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    assert_eq!(res.par_tolerance, Some(2));
+    assert_eq!(base_scores(&res, &entry_ids), vec![40, 10]);
+    let diff = restarted.get_event_weather_diff(&event.id).await.unwrap();
+    assert_eq!(
+        diff[0].temp_high.as_ref().unwrap().outcome,
+        ValueOptions::Par
+    );
+}
@@ -0,0 +1,219 @@
+use crate::helpers::{
+    new_event, spawn_app, spawn_app_with_entry_grace_period, station_ids, MockWeatherAccess,
+};
+use nostr_sdk::Keys;
+use oracle::{
+    oracle::Error, AddEventEntry, CreateEvent, EventStatus, Forecast, Observation, ValueOptions,
+    WeatherChoices,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+use uuid::Uuid;
+
+fn partial_event(observation_date: OffsetDateTime) -> CreateEvent {
+    CreateEvent {
+        observation_date,
+        signing_date: observation_date + Duration::days(1),
+        locations: station_ids(&["KSAW", "PFNO"]),
+        ..new_event()
+    }
+}
+
+fn choice(
+    station: &str,
+    temp_high: Option<ValueOptions>,
+    temp_low: Option<ValueOptions>,
+    wind_speed: Option<ValueOptions>,
+) -> WeatherChoices {
+    WeatherChoices {
+        stations: String::from(station),
+        temp_high,
+        temp_low,
+        wind_speed,
+    }
+}
+
+fn full_choices() -> Vec<WeatherChoices> {
+    vec![
+        choice(
+            "KSAW",
+            Some(ValueOptions::Par),
+            Some(ValueOptions::Under),
+            Some(ValueOptions::Over),
+        ),
+        choice(
+            "PFNO",
+            Some(ValueOptions::Par),
+            Some(ValueOptions::Under),
+            Some(ValueOptions::Over),
+        ),
+    ]
+}
+
+fn partial_choices() -> Vec<WeatherChoices> {
+    vec![
+        choice("KSAW", Some(ValueOptions::Par), None, None),
+        choice("PFNO", None, None, None),
+    ]
+}
+
+#[tokio::test]
+async fn stores_full_and_partial_entries() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            partial_event(OffsetDateTime::now_utc() + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+
+    let full = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: full_choices(),
+            },
+        )
+        .await
+        .unwrap();
+    let partial = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: partial_choices(),
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(full.expected_observations, full_choices());
+    // PFNO has no picks so it isn't stored
+    assert_eq!(
+        partial.expected_observations,
+        vec![choice("KSAW", Some(ValueOptions::Par), None, None)]
+    );
+    let stored = test_app
+        .oracle
+        .get_event_entry(&event.id, &partial.id)
+        .await
+        .unwrap();
+    assert_eq!(stored.expected_observations, partial.expected_observations);
+}
+
+#[tokio::test]
+async fn rejects_entries_without_picks_or_with_repeated_stations() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            partial_event(OffsetDateTime::now_utc() + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+
+    for expected_observations in [
+        vec![],
+        vec![choice("KSAW", None, None, None)],
+        vec![
+            choice("KSAW", Some(ValueOptions::Par), None, None),
+            choice("ksaw", None, Some(ValueOptions::Over), None),
+        ],
+    ] {
+        let res = test_app
+            .oracle
+            .add_event_entry(
+                keys.public_key,
+                AddEventEntry {
+                    id: Uuid::now_v7(),
+                    event_id: event.id,
+                    expected_observations,
+                },
+            )
+            .await;
+        assert!(matches!(res, Err(Error::BadEntry(_))), "{:?}", res);
+    }
+}
+
+#[tokio::test]
+async fn abstained_picks_score_zero_points() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(["KSAW", "PFNO"]
+            .iter()
+            .map(|station_id| Forecast {
+                station_id: String::from(*station_id),
+                date: String::from("2024-08-12"),
+                start_time: String::from("2024-08-11T00:00:00+00:00"),
+                end_time: String::from("2024-08-12T00:00:00+00:00"),
+                temp_low: 17,
+                temp_high: 25,
+                wind_speed: 3,
+            })
+            .collect())
+    });
+    // temp_high is Par, temp_low Under and wind_speed Over at both stations
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(["KSAW", "PFNO"]
+            .iter()
+            .map(|station_id| Observation {
+                station_id: String::from(*station_id),
+                start_time: String::from("2024-08-12T00:00:00+00:00"),
+                end_time: String::from("2024-08-13T00:00:00+00:00"),
+                temp_low: 15_f64,
+                temp_high: 25_f64,
+                wind_speed: 5,
+            })
+            .collect())
+    });
+    // Entries are added through the oracle after the observation date, so the grace period covers it
+    let test_app =
+        spawn_app_with_entry_grace_period(Arc::new(weather_data), Duration::days(365 * 20)).await;
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let event = test_app
+        .oracle
+        .create_event(keys.public_key, partial_event(observation_date))
+        .await
+        .unwrap();
+    let mut entry_ids = vec![];
+    for expected_observations in [full_choices(), partial_choices()] {
+        let entry = test_app
+            .oracle
+            .add_event_entry(
+                keys.public_key,
+                AddEventEntry {
+                    id: Uuid::now_v7(),
+                    event_id: event.id,
+                    expected_observations,
+                },
+            )
+            .await
+            .unwrap();
+        entry_ids.push(entry.id);
+    }
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    let base_scores: Vec<i64> = entry_ids
+        .iter()
+        .map(|id| {
+            let entry = res.entries.iter().find(|entry| entry.id == *id).unwrap();
+            entry.score.unwrap() / 10000
+        })
+        .collect();
+    // Every pick of the full entry is correct, 2 * (20 + 10 + 10), the partial entry only picked one
+    assert_eq!(base_scores, vec![80, 20]);
+}
@@ -0,0 +1,171 @@
+use crate::helpers::{get, new_event, spawn_app, MockWeatherAccess, TestApp};
+use axum::http::StatusCode;
+use dlctix::secp::MaybeScalar;
+use nostr_sdk::Keys;
+use oracle::{
+    AddEventEntry, CreateEvent, EventPayouts, PayoutSplit, PlacePayout, WeatherChoices,
+    WeatherEntry,
+};
+use serde_json::from_slice;
+use std::sync::Arc;
+use uuid::Uuid;
+
+// Pays out 3 places
+fn top_3_event() -> CreateEvent {
+    CreateEvent {
+        number_of_places_win: 3,
+        ..new_event()
+    }
+}
+
+async fn add_scored_entries(test_app: &TestApp, event_id: Uuid, scores: &[i64]) -> Vec<Uuid> {
+    let mut entry_scores = vec![];
+    for score in scores {
+        let entry: WeatherEntry = AddEventEntry {
+            id: Uuid::now_v7(),
+            event_id,
+            expected_observations: vec![WeatherChoices {
+                stations: String::from("PFNO"),
+                temp_high: None,
+                temp_low: None,
+                wind_speed: None,
+            }],
+        }
+        .into();
+        test_app
+            .event_data
+            .add_event_entry(entry.clone())
+            .await
+            .unwrap();
+        entry_scores.push((entry.id, *score));
+    }
+    test_app
+        .event_data
+        .update_entry_scores(entry_scores.clone())
+        .await
+        .unwrap();
+    entry_scores.into_iter().map(|(id, _)| id).collect()
+}
+
+async fn sign_event(test_app: &TestApp, event_id: Uuid) {
+    let mut sign_event = test_app
+        .event_data
+        .get_events_to_sign(vec![event_id])
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    sign_event.attestation = Some(MaybeScalar::from_slice(&[9u8; 32]).unwrap());
+    test_app
+        .event_data
+        .update_event_attestation(&sign_event)
+        .await
+        .unwrap();
+}
+
+#[test]
+fn payout_split_must_add_up_to_100() {
+    assert_eq!(
+        PayoutSplit::try_from("60,30,10").unwrap().percents(),
+        &[60, 30, 10]
+    );
+    assert!(PayoutSplit::try_from("50,30,10").is_err());
+    assert!(PayoutSplit::try_from("60,30,abc").is_err());
+    assert!(PayoutSplit::new(vec![]).is_err());
+    assert_eq!(PayoutSplit::even(3).percents(), &[34, 33, 33]);
+}
+
+#[tokio::test]
+async fn can_get_payouts_of_top_3_event() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, top_3_event())
+        .await
+        .unwrap();
+    let entry_ids = add_scored_entries(
+        &test_app,
+        event.id,
+        &[200_0001, 500_0002, 100_0003, 300_0004],
+    )
+    .await;
+    sign_event(&test_app, event.id).await;
+
+    let (status, body) = get(
+        &test_app,
+        &format!("/oracle/events/{}/payouts?split=60,30,10", event.id),
+    )
+    .await;
+    assert_eq!(status, StatusCode::OK);
+    let payouts: EventPayouts = from_slice(&body).unwrap();
+
+    assert_eq!(payouts.id, event.id);
+    assert_eq!(payouts.split, vec![60, 30, 10]);
+    assert_eq!(payouts.unclaimed_percent, 0);
+    assert_eq!(
+        payouts.payouts,
+        vec![
+            PlacePayout {
+                place: 1,
+                entry_id: entry_ids[1],
+                score: Some(500_0002),
+                percent: 60,
+            },
+            PlacePayout {
+                place: 2,
+                entry_id: entry_ids[3],
+                score: Some(300_0004),
+                percent: 30,
+            },
+            PlacePayout {
+                place: 3,
+                entry_id: entry_ids[0],
+                score: Some(200_0001),
+                percent: 10,
+            },
+        ]
+    );
+
+    let (status, body) = get(&test_app, &format!("/oracle/events/{}/payouts", event.id)).await;
+    assert_eq!(status, StatusCode::OK);
+    let payouts: EventPayouts = from_slice(&body).unwrap();
+    assert_eq!(payouts.split, vec![34, 33, 33]);
+}
+
+#[tokio::test]
+async fn rejects_invalid_payout_splits() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, top_3_event())
+        .await
+        .unwrap();
+    add_scored_entries(&test_app, event.id, &[200_0001, 500_0002, 100_0003]).await;
+    sign_event(&test_app, event.id).await;
+
+    for split in ["50,30,10", "60,40", "60,30,x"] {
+        let (status, _) = get(
+            &test_app,
+            &format!("/oracle/events/{}/payouts?split={}", event.id, split),
+        )
+        .await;
+        assert_eq!(status, StatusCode::BAD_REQUEST, "split {}", split);
+    }
+}
+
+#[tokio::test]
+async fn rejects_payouts_of_unsigned_event() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, top_3_event())
+        .await
+        .unwrap();
+
+    let (status, _) = get(
+        &test_app,
+        &format!("/oracle/events/{}/payouts?split=60,30,10", event.id),
+    )
+    .await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+}
@@ -0,0 +1,122 @@
+use crate::helpers::{
+    new_event, spawn_app_with_config, station_ids, MockWeatherAccess, TestAppConfig,
+};
+use nostr_sdk::Keys;
+use oracle::{
+    AddEventEntry, CreateEvent, EntryBreakdown, Forecast, Observation, Observed, RawObservedTemps,
+    ValueOptions, WeatherChoices,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime};
+use uuid::Uuid;
+
+/// Runs an etl over a KSAW observation with fractional temperatures, returns what was stored and how the
+/// entry picking Par on temp_high scored
+async fn stored_observation(keep_raw_observation_temps: bool) -> (Observed, EntryBreakdown) {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 17,
+            temp_high: 26,
+            wind_speed: 3,
+        }])
+    });
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 15.4,
+            temp_high: 25.6,
+            wind_speed: 5,
+        }])
+    });
+    // Entries are added through the oracle after the observation date, so the grace period covers it
+    let test_app = spawn_app_with_config(
+        Arc::new(weather_data),
+        TestAppConfig {
+            entry_grace_period: Duration::days(365 * 20),
+            keep_raw_observation_temps,
+            ..Default::default()
+        },
+    )
+    .await;
+    let observation_date = OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap();
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                observation_date,
+                signing_date: observation_date + Duration::days(1),
+                locations: station_ids(&["KSAW"]),
+                number_of_values_per_entry: 3,
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    let entry = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: vec![WeatherChoices {
+                    stations: String::from("KSAW"),
+                    temp_high: Some(ValueOptions::Par),
+                    temp_low: None,
+                    wind_speed: None,
+                }],
+            },
+        )
+        .await
+        .unwrap();
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let event = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(event.weather.len(), 1);
+    let breakdown = test_app
+        .oracle
+        .get_entry_breakdown(&event.id, &entry.id)
+        .await
+        .unwrap();
+    (event.weather[0].observed.clone().unwrap(), breakdown)
+}
+
+#[tokio::test]
+async fn keeps_raw_and_rounded_observation_temps_when_configured() {
+    let (observed, breakdown) = stored_observation(true).await;
+
+    assert_eq!(observed.temp_low, 15);
+    assert_eq!(observed.temp_high, 26);
+    assert_eq!(
+        observed.raw_temps,
+        Some(RawObservedTemps {
+            temp_low: 15.4,
+            temp_high: 25.6,
+        })
+    );
+
+    // Only the rounded high matches the forecast of 26
+    assert_eq!(breakdown.picks[0].observed, Some(26));
+    assert!(breakdown.picks[0].matched);
+    assert!(breakdown.base_score > 0);
+}
+
+#[tokio::test]
+async fn only_keeps_rounded_observation_temps_by_default() {
+    let (observed, breakdown) = stored_observation(false).await;
+
+    assert_eq!(observed.temp_low, 15);
+    assert_eq!(observed.temp_high, 26);
+    assert_eq!(observed.raw_temps, None);
+    assert!(breakdown.picks[0].matched);
+}
@@ -0,0 +1,53 @@
+use crate::helpers::{get_json, spawn_app, MockWeatherAccess};
+use serde_json::Value;
+use std::sync::Arc;
+
+async fn get_schema(uri: &str) -> Value {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    get_json(&test_app, uri).await
+}
+
+fn required_fields(schema: &Value) -> Vec<&str> {
+    schema["required"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|field| field.as_str().unwrap())
+        .collect()
+}
+
+#[tokio::test]
+async fn can_get_create_event_schema() {
+    let schema = get_schema("/schema/create-event").await;
+
+    let required = required_fields(&schema);
+    for field in [
+        "id",
+        "signing_date",
+        "observation_date",
+        "locations",
+        "number_of_values_per_entry",
+        "total_allowed_entries",
+        "number_of_places_win",
+    ] {
+        assert!(required.contains(&field), "{} should be required", field);
+    }
+    assert_eq!(schema["properties"]["id"]["format"], "uuid");
+    assert!(schema["properties"]["id"]["description"]
+        .as_str()
+        .unwrap()
+        .contains("Uuidv7"));
+}
+
+#[tokio::test]
+async fn can_get_add_entry_schema() {
+    let schema = get_schema("/schema/add-entry").await;
+
+    let required = required_fields(&schema);
+    for field in ["id", "event_id", "expected_observations"] {
+        assert!(required.contains(&field), "{} should be required", field);
+    }
+    assert_eq!(schema["properties"]["id"]["format"], "uuid");
+    // Entry choices are referenced, their schema is returned alongside the body schema
+    assert!(schema["components"]["schemas"]["WeatherChoices"].is_object());
+}
@@ -0,0 +1,165 @@
+use crate::helpers::{spawn_app_with_config, MockWeatherAccess, TestApp, TestAppConfig};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use duckdb::Connection;
+use hyper::{header, Method};
+use oracle::{compare_parquet_schema, SchemaDriftPolicy, WeatherFileKind};
+use std::{fs, path::Path, sync::Arc};
+use tower::ServiceExt;
+
+const BOUNDARY: &str = "------------------------d8b9fa0cf983802b";
+const FILE_NAME: &str = "forecasts_2024-01-14T04:44:22.246930703Z.parquet";
+
+// Written the way an older daemon did, before the precipitation probability and unit code columns
+// existed and with a column the oracle never reads
+const OLD_FORECAST_ROW: &str = "SELECT 'KSAW' AS station_id, 'KSAW airport' AS station_name,
+    46.35::DOUBLE AS latitude, -87.39::DOUBLE AS longitude,
+    '2024-01-14T04:44:22Z' AS generated_at, '2024-01-14T06:00:00Z' AS begin_time,
+    '2024-01-15T06:00:00Z' AS end_time, 70::BIGINT AS max_temp, 60::BIGINT AS min_temp,
+    8::BIGINT AS wind_speed, 180::BIGINT AS wind_direction, 90::BIGINT AS relative_humidity_max,
+    40::BIGINT AS relative_humidity_min, 0.1::DOUBLE AS liquid_precipitation_amt,
+    'legacy' AS daemon_version";
+
+fn write_parquet(test_app: &TestApp, rows: &str) -> Vec<u8> {
+    let path = format!("{}/fixture.parquet", test_app.weather_dir);
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(&format!("COPY ({}) TO '{}' (FORMAT PARQUET);", rows, path))
+        .unwrap();
+    let data = fs::read(&path).unwrap();
+    fs::remove_file(&path).unwrap();
+    data
+}
+
+fn multipart_body(data: &[u8]) -> Vec<u8> {
+    let mut body = format!(
+        "--{}\r\nContent-Disposition: form-data; name=\"file\"; filename=\"{}\"\r\n\
+         Content-Type: application/octet-stream\r\n\r\n",
+        BOUNDARY, FILE_NAME
+    )
+    .into_bytes();
+    body.extend_from_slice(data);
+    body.extend_from_slice(format!("\r\n--{}--\r\n", BOUNDARY).as_bytes());
+    body
+}
+
+async fn upload(test_app: &TestApp, data: &[u8]) -> (StatusCode, String) {
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri(format!("/file/{}", FILE_NAME))
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from(multipart_body(data)))
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    let status = response.status();
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    (status, String::from_utf8(body.to_vec()).unwrap())
+}
+
+// Every file left in the weather folder and the folder above it, uploads are only written there while they're checked
+fn files_left(test_app: &TestApp) -> Vec<String> {
+    let weather_dir = Path::new(&test_app.weather_dir);
+    let mut files = vec![];
+    for dir in [weather_dir, weather_dir.parent().unwrap()] {
+        for entry in fs::read_dir(dir).unwrap() {
+            let entry = entry.unwrap();
+            if entry.file_type().unwrap().is_file() {
+                files.push(entry.file_name().to_string_lossy().to_string());
+            }
+        }
+    }
+    files
+}
+
+async fn spawn_app_with_policy(schema_drift_policy: SchemaDriftPolicy) -> TestApp {
+    let config = TestAppConfig {
+        schema_drift_policy,
+        ..Default::default()
+    };
+    spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config).await
+}
+
+#[tokio::test]
+async fn reports_missing_and_extra_columns() {
+    let test_app = spawn_app_with_policy(SchemaDriftPolicy::Adapt).await;
+    let path = format!("{}/old_forecasts.parquet", test_app.weather_dir);
+    fs::write(&path, write_parquet(&test_app, OLD_FORECAST_ROW)).unwrap();
+
+    let conn = Connection::open_in_memory().unwrap();
+    let drift = compare_parquet_schema(&conn, &path, WeatherFileKind::Forecasts).unwrap();
+
+    assert_eq!(
+        drift.missing,
+        vec![
+            "temperature_unit_code",
+            "wind_speed_unit_code",
+            "wind_direction_unit_code",
+            "relative_humidity_unit_code",
+            "liquid_precipitation_unit_code",
+            "twelve_hour_probability_of_precipitation",
+            "twelve_hour_probability_of_precipitation_unit_code",
+        ]
+    );
+    assert_eq!(drift.extra, vec!["daemon_version"]);
+}
+
+#[tokio::test]
+async fn adapts_file_with_missing_columns() {
+    let test_app = spawn_app_with_policy(SchemaDriftPolicy::Adapt).await;
+    let data = write_parquet(&test_app, OLD_FORECAST_ROW);
+
+    let (status, _) = upload(&test_app, &data).await;
+
+    assert_eq!(status, StatusCode::OK);
+    let path = format!("{}/{}", test_app.weather_dir, FILE_NAME);
+    let conn = Connection::open_in_memory().unwrap();
+    let drift = compare_parquet_schema(&conn, &path, WeatherFileKind::Forecasts).unwrap();
+    assert!(drift.missing.is_empty());
+    assert_eq!(drift.extra, vec!["daemon_version"]);
+    let (max_temp, probability): (i64, Option<i64>) = conn
+        .query_row(
+            &format!(
+                "SELECT max_temp, twelve_hour_probability_of_precipitation FROM read_parquet('{}')",
+                path
+            ),
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?)),
+        )
+        .unwrap();
+    assert_eq!(max_temp, 70);
+    assert_eq!(probability, None);
+    assert_eq!(files_left(&test_app), vec![FILE_NAME]);
+}
+
+#[tokio::test]
+async fn rejects_file_with_missing_columns() {
+    let test_app = spawn_app_with_policy(SchemaDriftPolicy::Reject).await;
+    let data = write_parquet(&test_app, OLD_FORECAST_ROW);
+
+    let (status, body) = upload(&test_app, &data).await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("twelve_hour_probability_of_precipitation"));
+    assert!(body.contains("extra columns: [daemon_version]"));
+    assert!(files_left(&test_app).is_empty());
+}
+
+#[tokio::test]
+async fn rejects_file_that_is_not_parquet() {
+    let test_app = spawn_app_with_policy(SchemaDriftPolicy::Adapt).await;
+
+    let (status, body) = upload(&test_app, b"not a parquet file").await;
+
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+    assert!(body.contains("Failed to read file as parquet"));
+    assert!(files_left(&test_app).is_empty());
+}
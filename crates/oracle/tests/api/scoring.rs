@@ -0,0 +1,172 @@
+use crate::{
+    etl_workflow::mock_observation_date,
+    helpers::{new_event, spawn_app, station_ids, MockWeatherAccess},
+};
+use nostr_sdk::Keys;
+use oracle::{
+    oracle::Error, AddEventEntry, CreateEvent, EventStatus, ExponentialScoring, Forecast,
+    LinearScoring, Observation, ScoredPick, ScoringMode, ScoringScheme, ValueOptions,
+    WeatherChoices,
+};
+use std::sync::Arc;
+use time::Duration;
+use uuid::Uuid;
+
+fn scored(pick: ValueOptions, correct: bool) -> ScoredPick {
+    ScoredPick { pick, correct }
+}
+
+#[test]
+fn schemes_score_the_same_picks_differently() {
+    let picks = vec![
+        scored(ValueOptions::Par, true),
+        scored(ValueOptions::Over, false),
+        scored(ValueOptions::Under, true),
+        scored(ValueOptions::Par, true),
+    ];
+
+    // 20 + 10 + 20
+    assert_eq!(LinearScoring.base_score(&picks), 50);
+    // 20 + 10 * 2 + 20 * 4
+    assert_eq!(ExponentialScoring.base_score(&picks), 120);
+    assert_eq!(LinearScoring.pick_points(&picks), vec![20, 0, 10, 20]);
+    assert_eq!(ExponentialScoring.pick_points(&picks), vec![20, 0, 20, 80]);
+    assert_eq!(LinearScoring.base_score(&[]), 0);
+    assert_eq!(ExponentialScoring.base_score(&[]), 0);
+}
+
+#[test]
+fn max_base_score_is_every_pick_a_correct_par() {
+    assert_eq!(LinearScoring.max_base_score(3), 60);
+    assert_eq!(ExponentialScoring.max_base_score(3), 140);
+    assert_eq!(ExponentialScoring.max_base_score(200), u64::MAX);
+    assert_eq!(
+        ScoringMode::try_from("exponential").unwrap(),
+        ScoringMode::Exponential
+    );
+    assert!(ScoringMode::try_from("quadratic").is_err());
+}
+
+fn scoring_event(scoring_mode: ScoringMode, number_of_values_per_entry: usize) -> CreateEvent {
+    CreateEvent {
+        observation_date: mock_observation_date(),
+        signing_date: mock_observation_date() + Duration::days(1),
+        locations: station_ids(&["KSAW"]),
+        total_allowed_entries: 2,
+        number_of_values_per_entry,
+        scoring_mode,
+        ..new_event()
+    }
+}
+
+fn choices(
+    temp_high: ValueOptions,
+    temp_low: ValueOptions,
+    wind_speed: ValueOptions,
+) -> Vec<WeatherChoices> {
+    vec![WeatherChoices {
+        stations: String::from("KSAW"),
+        temp_high: Some(temp_high),
+        temp_low: Some(temp_low),
+        wind_speed: Some(wind_speed),
+    }]
+}
+
+#[tokio::test]
+async fn events_score_entries_with_their_scoring_mode() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_forecasts_data().returning(|_, _| {
+        Ok(vec![Forecast {
+            station_id: String::from("KSAW"),
+            date: String::from("2024-08-12"),
+            start_time: String::from("2024-08-11T00:00:00+00:00"),
+            end_time: String::from("2024-08-12T00:00:00+00:00"),
+            temp_low: 17,
+            temp_high: 25,
+            wind_speed: 3,
+        }])
+    });
+    // temp_high is Par, temp_low Under and wind_speed Over
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("KSAW"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 15_f64,
+            temp_high: 25_f64,
+            wind_speed: 5,
+        }])
+    });
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let mut base_scores = vec![];
+    for scoring_mode in [ScoringMode::Linear, ScoringMode::Exponential] {
+        let event = test_app
+            .oracle
+            .create_event(keys.public_key, scoring_event(scoring_mode, 3))
+            .await
+            .unwrap();
+        assert_eq!(event.scoring_mode, scoring_mode);
+        let entries = [
+            // Every pick is correct
+            choices(ValueOptions::Par, ValueOptions::Under, ValueOptions::Over),
+            // Only temp_high is correct
+            choices(ValueOptions::Par, ValueOptions::Par, ValueOptions::Par),
+        ];
+        let mut entry_ids = vec![];
+        for expected_observations in entries {
+            let entry = AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations,
+            };
+            entry_ids.push(entry.id);
+            test_app
+                .event_data
+                .add_event_entry(entry.into())
+                .await
+                .unwrap();
+        }
+
+        test_app.oracle.etl_data(1).await.unwrap();
+
+        let res = test_app.oracle.get_event(&event.id).await.unwrap();
+        assert_eq!(res.status, EventStatus::Signed);
+        assert_eq!(res.scoring_mode, scoring_mode);
+        let scores: Vec<i64> = entry_ids
+            .iter()
+            .map(|id| {
+                let entry = res.entries.iter().find(|entry| entry.id == *id).unwrap();
+                entry.score.unwrap() / 10000
+            })
+            .collect();
+        base_scores.push(scores);
+    }
+
+    // Linear scores 20 + 10 + 10, exponential 20 + 10 * 2 + 10 * 4
+    assert_eq!(base_scores, vec![vec![40, 20], vec![80, 20]]);
+}
+
+#[tokio::test]
+async fn rejects_exponential_events_past_the_score_range() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let res = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            scoring_event(ScoringMode::Exponential, 60),
+        )
+        .await;
+    assert!(matches!(res, Err(Error::BadEvent(_))));
+
+    let res = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            scoring_event(ScoringMode::Linear, 60),
+        )
+        .await;
+    assert!(res.is_ok());
+}
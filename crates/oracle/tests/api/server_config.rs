@@ -0,0 +1,138 @@
+use crate::helpers::{spawn_app, MockWeatherAccess};
+use axum::{
+    body::Body,
+    extract::ConnectInfo,
+    http::{header::RETRY_AFTER, Request, StatusCode},
+    Router,
+};
+use hyper::Method;
+use oracle::{apply_server_config, bind_listener, serve_with_config, RateLimit, ServerConfig};
+use std::{net::SocketAddr, str::FromStr, sync::Arc, time::Duration};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    net::TcpStream,
+    time::timeout,
+};
+use tokio_util::sync::CancellationToken;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn can_serve_with_custom_server_config() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let server_config = ServerConfig {
+        listen_backlog: 16,
+        keep_alive: Duration::from_secs(5),
+        request_timeout: Duration::from_secs(2),
+        rate_limit: None,
+    };
+
+    let listener =
+        bind_listener(SocketAddr::from_str("127.0.0.1:0").unwrap(), &server_config).unwrap();
+    let socket_addr = listener.local_addr().unwrap();
+    let app = apply_server_config(test_app.app, &server_config);
+    tokio::spawn(async move {
+        serve_with_config(listener, app, &server_config, CancellationToken::new()).await
+    });
+
+    let mut stream = TcpStream::connect(socket_addr).await.unwrap();
+    stream
+        .write_all(b"GET /version HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+
+    assert!(response.starts_with("HTTP/1.1 200"));
+}
+
+#[tokio::test]
+async fn closes_idle_keep_alive_connections() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let server_config = ServerConfig {
+        keep_alive: Duration::from_millis(500),
+        ..Default::default()
+    };
+    let listener =
+        bind_listener(SocketAddr::from_str("127.0.0.1:0").unwrap(), &server_config).unwrap();
+    let socket_addr = listener.local_addr().unwrap();
+    let app = apply_server_config(test_app.app, &server_config);
+    let shutdown = CancellationToken::new();
+    let server = tokio::spawn({
+        let shutdown = shutdown.clone();
+        async move { serve_with_config(listener, app, &server_config, shutdown).await }
+    });
+
+    let mut stream = TcpStream::connect(socket_addr).await.unwrap();
+    stream
+        .write_all(b"GET /version HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut buf = [0u8; 4096];
+    let read = stream.read(&mut buf).await.unwrap();
+    assert!(String::from_utf8_lossy(&buf[..read]).starts_with("HTTP/1.1 200"));
+
+    // The connection is kept alive after the response, then closed once it has been idle for the keep alive
+    let closed = timeout(Duration::from_secs(5), async {
+        loop {
+            if stream.read(&mut buf).await.unwrap_or(0) == 0 {
+                break;
+            }
+        }
+    })
+    .await;
+    assert!(closed.is_ok(), "idle connection was never closed");
+
+    shutdown.cancel();
+    timeout(Duration::from_secs(5), server)
+        .await
+        .expect("server didn't stop after shutdown")
+        .unwrap();
+}
+
+#[tokio::test]
+async fn can_rate_limit_requests_per_client() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let server_config = ServerConfig {
+        rate_limit: Some(RateLimit::new(2, Duration::from_secs(60))),
+        ..Default::default()
+    };
+    let app = apply_server_config(test_app.app, &server_config);
+
+    for _ in 0..2 {
+        let response = get_version(&app, "10.0.0.1:5000").await;
+        assert_eq!(response.status(), StatusCode::OK);
+    }
+
+    for _ in 0..3 {
+        let response = get_version(&app, "10.0.0.1:5001").await;
+        assert_eq!(response.status(), StatusCode::TOO_MANY_REQUESTS);
+        let retry_after: u64 = response
+            .headers()
+            .get(RETRY_AFTER)
+            .expect("missing Retry-After header")
+            .to_str()
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert!(retry_after > 0 && retry_after <= 60);
+    }
+
+    let response = get_version(&app, "10.0.0.2:5000").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+async fn get_version(app: &Router, client_addr: &str) -> axum::response::Response {
+    let mut request = Request::builder()
+        .method(Method::GET)
+        .uri("/version")
+        .body(Body::empty())
+        .unwrap();
+    request
+        .extensions_mut()
+        .insert(ConnectInfo(SocketAddr::from_str(client_addr).unwrap()));
+
+    app.clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.")
+}
@@ -0,0 +1,123 @@
+use crate::{
+    etl_workflow::{mock_entries, mock_forecast_data, mock_observation_data},
+    helpers::{get_json, mock_event, spawn_app, MockWeatherAccess, TestApp},
+};
+use nostr_sdk::Keys;
+use oracle::{EventSignStatus, EventStatus};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time};
+use uuid::Uuid;
+
+// Event whose observation window is over with entries in it, so the next etl pass signs it
+async fn add_signable_event(test_app: &TestApp, keys: &Keys) -> Uuid {
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(
+                OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339).unwrap(),
+                OffsetDateTime::now_utc() - Duration::hours(1),
+            ),
+        )
+        .await
+        .unwrap();
+    for entry in mock_entries(event.id) {
+        test_app
+            .event_data
+            .add_event_entry(entry.into())
+            .await
+            .unwrap();
+    }
+    event.id
+}
+
+#[tokio::test]
+async fn groups_events_by_their_signing_status() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    let today = OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT);
+
+    let mut live = vec![];
+    for _ in 0..2 {
+        let event = test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                mock_event(today + Duration::days(1), today + Duration::days(2)),
+            )
+            .await
+            .unwrap();
+        live.push(event.id);
+    }
+    let completed = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(today - Duration::days(2), today + Duration::days(1)),
+        )
+        .await
+        .unwrap();
+    let cancelled = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(today + Duration::days(1), today + Duration::days(2)),
+        )
+        .await
+        .unwrap();
+    test_app
+        .oracle
+        .cancel_event(keys.public_key, &cancelled.id)
+        .await
+        .unwrap();
+    let signed = add_signable_event(&test_app, &keys).await;
+    test_app.oracle.etl_data(1).await.unwrap();
+    let signed = test_app.oracle.get_event(&signed).await.unwrap();
+    assert_eq!(signed.status, EventStatus::Signed);
+
+    let sign_status: EventSignStatus = get_json(&test_app, "/oracle/events/sign-status").await;
+    assert_eq!(sign_status.live.count, 2);
+    assert_eq!(sign_status.live.event_ids, live);
+    assert_eq!(sign_status.running.count, 0);
+    assert!(sign_status.running.event_ids.is_empty());
+    assert_eq!(sign_status.completed.count, 1);
+    assert_eq!(sign_status.completed.event_ids, vec![completed.id]);
+    assert_eq!(sign_status.signed.count, 1);
+    assert_eq!(sign_status.signed.event_ids, vec![signed.id]);
+}
+
+#[tokio::test]
+async fn lists_only_the_newest_signed_events_up_to_the_limit() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    let mut signed = vec![];
+    for _ in 0..3 {
+        signed.push(add_signable_event(&test_app, &keys).await);
+    }
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let sign_status: EventSignStatus =
+        get_json(&test_app, "/oracle/events/sign-status?signed_limit=2").await;
+
+    assert_eq!(sign_status.signed.count, 3);
+    assert_eq!(sign_status.signed.event_ids, signed[1..].to_vec());
+    assert_eq!(sign_status.completed.count, 0);
+
+    let sign_status: EventSignStatus = get_json(&test_app, "/oracle/events/sign-status").await;
+    assert_eq!(sign_status.signed.count, 3);
+    assert_eq!(sign_status.signed.event_ids, signed);
+}
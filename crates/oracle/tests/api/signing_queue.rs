@@ -0,0 +1,187 @@
+use crate::{
+    etl_workflow::{
+        mock_entries, mock_forecast_data, mock_observation_data, mock_observation_date,
+    },
+    helpers::{get_json, mock_event, spawn_app_with_config, MockWeatherAccess, TestAppConfig},
+};
+use dlctix::secp::MaybeScalar;
+use futures::future::join_all;
+use nostr_sdk::Keys;
+use oracle::{AddEventEntry, EventStatus, SigningQueue, SigningQueueStatus};
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+    time::Duration as StdDuration,
+};
+use time::{Duration, OffsetDateTime};
+use tokio::time::sleep;
+use uuid::Uuid;
+
+#[tokio::test]
+async fn caps_how_many_signings_run_at_once() {
+    let queue = SigningQueue::new(2);
+    let running = AtomicUsize::new(0);
+    let most_running = AtomicUsize::new(0);
+    let signings = (0..6).map(|_| {
+        queue.run(async {
+            let now_running = running.fetch_add(1, Ordering::SeqCst) + 1;
+            most_running.fetch_max(now_running, Ordering::SeqCst);
+            sleep(StdDuration::from_millis(100)).await;
+            running.fetch_sub(1, Ordering::SeqCst);
+        })
+    });
+
+    let (_, while_signing) = tokio::join!(join_all(signings), async {
+        sleep(StdDuration::from_millis(30)).await;
+        queue.status()
+    });
+
+    assert_eq!(
+        while_signing,
+        SigningQueueStatus {
+            concurrency: 2,
+            in_flight: 2,
+            queued: 4,
+            peak_in_flight: 2,
+        }
+    );
+    assert_eq!(most_running.load(Ordering::SeqCst), 2);
+    assert_eq!(
+        queue.status(),
+        SigningQueueStatus {
+            concurrency: 2,
+            in_flight: 0,
+            queued: 0,
+            peak_in_flight: 2,
+        }
+    );
+    assert_eq!(SigningQueue::new(0).status().concurrency, 1);
+}
+
+#[tokio::test]
+async fn signs_every_event_due_together_through_the_queue() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Ok(mock_forecast_data()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Ok(mock_observation_data()));
+    let config = TestAppConfig {
+        signing_concurrency: 2,
+        ..Default::default()
+    };
+    let test_app = spawn_app_with_config(Arc::new(weather_data), config).await;
+
+    let signing_date = OffsetDateTime::now_utc() - Duration::hours(1);
+    let mut events = vec![];
+    for _ in 0..5 {
+        let event = test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                mock_event(mock_observation_date(), signing_date),
+            )
+            .await
+            .unwrap();
+        // Entry ids have to be unique across events
+        for entry in mock_entries(event.id) {
+            let entry = AddEventEntry {
+                id: Uuid::now_v7(),
+                ..entry
+            };
+            test_app
+                .event_data
+                .add_event_entry(entry.into())
+                .await
+                .unwrap();
+        }
+        events.push(event.id);
+    }
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    for event_id in events {
+        let event = test_app.oracle.get_event(&event_id).await.unwrap();
+        assert_eq!(event.status, EventStatus::Signed);
+    }
+    let status: SigningQueueStatus = get_json(&test_app, "/oracle/signing/queue").await;
+    assert_eq!(status.concurrency, 2);
+    assert_eq!(status.in_flight, 0);
+    assert_eq!(status.queued, 0);
+    assert!((1..=2).contains(&status.peak_in_flight));
+}
+
+#[tokio::test]
+async fn never_replaces_an_attestation_or_signs_a_cancelled_event() {
+    let keys = Keys::generate();
+    let test_app =
+        spawn_app_with_config(Arc::new(MockWeatherAccess::new()), TestAppConfig::default()).await;
+    let signing_date = OffsetDateTime::now_utc() - Duration::hours(1);
+    let signed = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(mock_observation_date(), signing_date),
+        )
+        .await
+        .unwrap();
+    let cancelled = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            mock_event(mock_observation_date(), signing_date),
+        )
+        .await
+        .unwrap();
+    // Both read before either changes, the way two queued signings of the same events would
+    let mut sign_events = test_app
+        .event_data
+        .get_events_to_sign(vec![signed.id, cancelled.id])
+        .await
+        .unwrap();
+    assert_eq!(sign_events.len(), 2);
+    let first = MaybeScalar::from_slice(&[9u8; 32]).unwrap();
+    let second = MaybeScalar::from_slice(&[7u8; 32]).unwrap();
+
+    let signed_event = sign_events
+        .iter_mut()
+        .find(|event| event.id == signed.id)
+        .unwrap();
+    signed_event.attestation = Some(first);
+    assert!(test_app
+        .event_data
+        .update_event_attestation(signed_event)
+        .await
+        .unwrap());
+    signed_event.attestation = Some(second);
+    assert!(!test_app
+        .event_data
+        .update_event_attestation(signed_event)
+        .await
+        .unwrap());
+    let stored = test_app.oracle.get_event(&signed.id).await.unwrap();
+    assert_eq!(stored.attestation, Some(first));
+
+    test_app
+        .oracle
+        .cancel_event(keys.public_key, &cancelled.id)
+        .await
+        .unwrap();
+    let cancelled_event = sign_events
+        .iter_mut()
+        .find(|event| event.id == cancelled.id)
+        .unwrap();
+    cancelled_event.attestation = Some(first);
+    assert!(!test_app
+        .event_data
+        .update_event_attestation(cancelled_event)
+        .await
+        .unwrap());
+    let stored = test_app.oracle.get_event(&cancelled.id).await.unwrap();
+    assert_eq!(stored.status, EventStatus::Cancelled);
+    assert!(stored.attestation.is_none());
+}
@@ -0,0 +1,122 @@
+use crate::helpers::{new_event, spawn_app, station_ids, MockWeatherAccess};
+use duckdb::{params, types::Value, Connection};
+use nostr_sdk::Keys;
+use oracle::{
+    from_sql_micros, parse_sql_timestamp, to_sql_timestamp, truncate_to_sql_precision, CreateEvent,
+    Observed, ToRawSql,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, Duration, OffsetDateTime, Time};
+
+fn precise_date() -> OffsetDateTime {
+    OffsetDateTime::parse("2024-08-11T04:27:39.123456789Z", &Rfc3339).unwrap()
+}
+
+fn micro_date() -> OffsetDateTime {
+    OffsetDateTime::parse("2024-08-11T04:27:39.123456Z", &Rfc3339).unwrap()
+}
+
+#[test]
+fn round_trips_timestamps_at_microsecond_precision() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch("CREATE TABLE dates (date TIMESTAMPTZ);")
+        .unwrap();
+    conn.execute(
+        "INSERT INTO dates VALUES (?::TIMESTAMPTZ)",
+        params![to_sql_timestamp(precise_date()).unwrap()],
+    )
+    .unwrap();
+
+    let (text, raw): (String, Value) = conn
+        .query_row("SELECT date::TEXT, date FROM dates", [], |row| {
+            Ok((row.get(0)?, row.get(1)?))
+        })
+        .unwrap();
+
+    assert_eq!(truncate_to_sql_precision(precise_date()), micro_date());
+    assert_eq!(parse_sql_timestamp(&text).unwrap(), micro_date());
+    let Value::Timestamp(_, micros) = raw else {
+        panic!("expected a timestamp, got {:?}", raw);
+    };
+    assert_eq!(from_sql_micros(micros).unwrap(), micro_date());
+}
+
+#[test]
+fn parses_timestamps_in_any_session_offset() {
+    for text in [
+        "2024-08-11 04:27:39.123456+00",
+        "2024-08-11 00:27:39.123456-04",
+        "2024-08-11 09:57:39.123456+05:30",
+    ] {
+        assert_eq!(parse_sql_timestamp(text).unwrap(), micro_date(), "{}", text);
+    }
+    assert_eq!(
+        parse_sql_timestamp("2024-08-11 04:27:39+00").unwrap(),
+        micro_date().replace_nanosecond(0).unwrap()
+    );
+}
+
+#[test]
+fn round_trips_weather_struct_dates() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE readings (observed STRUCT(date TIMESTAMPTZ, temp_low INTEGER, temp_high INTEGER, wind_speed INTEGER));",
+    )
+    .unwrap();
+    let observed = Observed {
+        date: precise_date(),
+        temp_low: 15,
+        temp_high: 25,
+        wind_speed: 5,
+        original_offset: None,
+        raw_temps: None,
+    };
+    conn.execute_batch(&format!(
+        "INSERT INTO readings VALUES ({});",
+        observed.to_raw_sql()
+    ))
+    .unwrap();
+
+    let raw: Value = conn
+        .query_row("SELECT observed FROM readings", [], |row| row.get(0))
+        .unwrap();
+    let Value::Struct(raw) = raw else {
+        panic!("expected a struct, got {:?}", raw);
+    };
+    let read: Observed = raw.try_into().unwrap();
+
+    assert_eq!(
+        read,
+        Observed {
+            date: micro_date(),
+            ..observed
+        }
+    );
+}
+
+#[tokio::test]
+async fn created_event_dates_match_what_is_stored() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let signing_date = OffsetDateTime::now_utc()
+        .replace_nanosecond(123_456_789)
+        .unwrap()
+        + Duration::days(2);
+    let create_event = CreateEvent {
+        observation_date: signing_date.replace_time(Time::MIDNIGHT),
+        signing_date,
+        locations: station_ids(&["KSAW"]),
+        number_of_values_per_entry: 3,
+        ..new_event()
+    };
+
+    let created = test_app
+        .oracle
+        .create_event(Keys::generate().public_key, create_event)
+        .await
+        .unwrap();
+    let stored = test_app.oracle.get_event(&created.id).await.unwrap();
+
+    assert_eq!(created.signing_date.nanosecond(), 123_456_000);
+    assert_eq!(stored.signing_date, created.signing_date);
+    assert_eq!(stored.observation_date, created.observation_date);
+}
@@ -0,0 +1,88 @@
+use crate::helpers::{get, new_event, spawn_app, station_ids, MockWeatherAccess};
+use axum::http::StatusCode;
+use dlctix::secp::MaybeScalar;
+use nostr_sdk::Keys;
+use oracle::{CreateEvent, EventStatus, EventSummary};
+use serde_json::from_slice;
+use std::sync::Arc;
+use uuid::Uuid;
+
+fn event_ids(body: &[u8]) -> Vec<Uuid> {
+    let events: Vec<EventSummary> = from_slice(body).unwrap();
+    let mut ids: Vec<Uuid> = events.iter().map(|event| event.id).collect();
+    ids.sort();
+    ids
+}
+
+#[tokio::test]
+async fn can_get_events_using_a_station() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let keys = Keys::generate();
+    let mut events = vec![];
+    for locations in [
+        vec!["PFNO", "KSAW", "PAPG"],
+        vec!["KITH", "KJAN"],
+        vec!["KSAW"],
+        vec!["PAPG", "KSAW", "KJAN"],
+    ] {
+        let event = test_app
+            .oracle
+            .create_event(
+                keys.public_key,
+                CreateEvent {
+                    locations: station_ids(&locations),
+                    ..new_event()
+                },
+            )
+            .await
+            .unwrap();
+        events.push(event.id);
+    }
+    let mut sign_event = test_app
+        .event_data
+        .get_events_to_sign(vec![events[3]])
+        .await
+        .unwrap()
+        .pop()
+        .unwrap();
+    sign_event.attestation = Some(MaybeScalar::from_slice(&[9u8; 32]).unwrap());
+    test_app
+        .event_data
+        .update_event_attestation(&sign_event)
+        .await
+        .unwrap();
+
+    let (status, body) = get(&test_app, "/stations/ksaw/events").await;
+    assert_eq!(status, StatusCode::OK);
+    let mut expected = vec![events[0], events[2], events[3]];
+    expected.sort();
+    assert_eq!(event_ids(&body), expected);
+
+    let (status, body) = get(&test_app, "/stations/KJAN/events").await;
+    assert_eq!(status, StatusCode::OK);
+    let mut expected = vec![events[1], events[3]];
+    expected.sort();
+    assert_eq!(event_ids(&body), expected);
+
+    let (status, body) = get(&test_app, "/stations/KSAW/events?status=signed").await;
+    assert_eq!(status, StatusCode::OK);
+    let signed: Vec<EventSummary> = from_slice(&body).unwrap();
+    assert_eq!(signed.len(), 1);
+    assert_eq!(signed[0].id, events[3]);
+    assert_eq!(signed[0].status, EventStatus::Signed);
+
+    let (status, body) = get(&test_app, "/stations/KDEN/events").await;
+    assert_eq!(status, StatusCode::OK);
+    assert!(event_ids(&body).is_empty());
+}
+
+#[tokio::test]
+async fn rejects_invalid_station_event_queries() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let (status, _) = get(&test_app, "/stations/KSAW/events?status=done").await;
+    assert_eq!(status, StatusCode::BAD_REQUEST);
+
+    let (status, _) = get(&test_app, "/stations/not-a-station/events").await;
+    assert_eq!(status, StatusCode::NOT_FOUND);
+}
@@ -0,0 +1,118 @@
+use crate::helpers::{
+    new_event, spawn_app_with_config, station_ids, MockWeatherAccess, TestAppConfig,
+};
+use nostr_sdk::Keys;
+use oracle::{oracle::Error, CreateEvent, StationGroups};
+use std::sync::Arc;
+
+const GROUPS: &str = r#"
+west_coast = ["KSEA", "kpdx", "KSFO"]
+alaska = ["PFNO", "PABR"]
+"#;
+
+fn config() -> TestAppConfig {
+    TestAppConfig {
+        station_groups: StationGroups::from_toml(GROUPS).unwrap(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn loads_station_groups_from_toml() {
+    let groups = StationGroups::from_toml(GROUPS).unwrap();
+
+    assert_eq!(groups.names(), vec!["alaska", "west_coast"]);
+    assert_eq!(
+        groups.get("west_coast").unwrap(),
+        &vec![
+            String::from("KPDX"),
+            String::from("KSEA"),
+            String::from("KSFO")
+        ]
+    );
+    assert!(groups.get("east_coast").is_none());
+    assert!(StationGroups::from_toml("empty = []").is_err());
+    assert!(StationGroups::from_toml("bad = [\"K SEA\"]").is_err());
+    assert!(StationGroups::from_toml("not_a_list = \"KSEA\"").is_err());
+}
+
+#[tokio::test]
+async fn can_create_event_from_station_group() {
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config()).await;
+
+    let event = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                station_group: Some(String::from("west_coast")),
+                locations: vec![],
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        event.locations,
+        vec![
+            String::from("KPDX"),
+            String::from("KSEA"),
+            String::from("KSFO")
+        ]
+    );
+    let stored = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(stored.locations, event.locations);
+}
+
+#[tokio::test]
+async fn group_stations_are_merged_with_inline_locations() {
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config()).await;
+
+    let event = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                station_group: Some(String::from("alaska")),
+                locations: station_ids(&["pabr", "KSAW"]),
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        event.locations,
+        vec![
+            String::from("KSAW"),
+            String::from("PABR"),
+            String::from("PFNO")
+        ]
+    );
+}
+
+#[tokio::test]
+async fn rejects_unknown_station_group() {
+    let test_app = spawn_app_with_config(Arc::new(MockWeatherAccess::new()), config()).await;
+
+    let res = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                station_group: Some(String::from("east_coast")),
+                locations: station_ids(&["KSAW"]),
+                ..new_event()
+            },
+        )
+        .await;
+
+    match res {
+        Err(Error::BadEvent(e)) => {
+            assert!(e.to_string().contains("east_coast"));
+            assert!(e.to_string().contains("alaska, west_coast"));
+        }
+        other => panic!("expected a bad event error, got {:?}", other),
+    }
+}
@@ -0,0 +1,200 @@
+use crate::helpers::{new_event, spawn_app, station_ids, MockWeatherAccess};
+use nostr_sdk::Keys;
+use oracle::{
+    normalize_station_id, oracle::Error, AddEventEntry, CreateEvent, EventStatus, Forecast,
+    ForecastRequest, Observation, ObservationRequest, ValueOptions, WeatherChoices, WeatherEntry,
+};
+use std::sync::Arc;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+#[test]
+fn normalizes_station_ids_to_upper_case() {
+    assert_eq!(normalize_station_id("kjfk").unwrap(), "KJFK");
+    assert_eq!(normalize_station_id(" KjFk ").unwrap(), "KJFK");
+    assert!(normalize_station_id("").is_err());
+    assert!(normalize_station_id("K JFK").is_err());
+    assert!(normalize_station_id("KJFK'").is_err());
+
+    let forecast_request = ForecastRequest {
+        start: None,
+        end: None,
+        station_ids: String::from("ksaw, Pfno"),
+    };
+    assert_eq!(
+        forecast_request.station_ids(),
+        vec![String::from("KSAW"), String::from("PFNO")]
+    );
+    let observation_request = ObservationRequest {
+        start: None,
+        end: None,
+        station_ids: String::from("kSaW,"),
+    };
+    assert_eq!(
+        observation_request.station_ids(),
+        vec![String::from("KSAW")]
+    );
+}
+
+#[tokio::test]
+async fn mixed_case_station_ids_match_when_scoring() {
+    let keys = Keys::generate();
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, station_ids| {
+            assert_eq!(
+                station_ids,
+                vec![String::from("KSAW"), String::from("PFNO")]
+            );
+            Ok(vec![Forecast {
+                station_id: String::from("KSAW"),
+                date: String::from("2024-08-12"),
+                start_time: String::from("2024-08-11T00:00:00+00:00"),
+                end_time: String::from("2024-08-12T00:00:00+00:00"),
+                temp_low: 17,
+                temp_high: 25,
+                wind_speed: 3,
+            }])
+        });
+    // Weather data written with a lower case id still matches the event's station
+    weather_data.expect_observation_data().returning(|_, _| {
+        Ok(vec![Observation {
+            station_id: String::from("ksaw"),
+            start_time: String::from("2024-08-12T00:00:00+00:00"),
+            end_time: String::from("2024-08-13T00:00:00+00:00"),
+            temp_low: 17_f64,
+            temp_high: 25_f64,
+            wind_speed: 3,
+        }])
+    });
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                observation_date: OffsetDateTime::parse("2024-08-12T00:00:00+00:00", &Rfc3339)
+                    .unwrap(),
+                signing_date: OffsetDateTime::parse("2024-08-13T00:00:00+00:00", &Rfc3339).unwrap(),
+                locations: station_ids(&["ksaw", "KSAW", "Pfno"]),
+                total_allowed_entries: 2,
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(
+        event.locations,
+        vec![String::from("KSAW"), String::from("PFNO")]
+    );
+
+    // Entries stored before station ids were normalized may hold any casing
+    let entry = WeatherEntry::from(AddEventEntry {
+        id: Uuid::now_v7(),
+        event_id: event.id,
+        expected_observations: vec![WeatherChoices {
+            stations: String::from("kSaw"),
+            temp_high: Some(ValueOptions::Par),
+            temp_low: Some(ValueOptions::Par),
+            wind_speed: Some(ValueOptions::Par),
+        }],
+    });
+    test_app
+        .event_data
+        .add_event_entry(entry.clone())
+        .await
+        .unwrap();
+
+    test_app.oracle.etl_data(1).await.unwrap();
+
+    let res = test_app.oracle.get_event(&event.id).await.unwrap();
+    assert_eq!(res.status, EventStatus::Signed);
+    let scored = res.entries.iter().find(|e| e.id == entry.id).unwrap();
+    assert_eq!(scored.score.unwrap() / 10000, 60);
+}
+
+#[tokio::test]
+async fn accepts_entries_with_mixed_case_station_ids() {
+    let keys = Keys::generate();
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+    let event = test_app
+        .oracle
+        .create_event(
+            keys.public_key,
+            CreateEvent {
+                locations: station_ids(&["KSAW", "pfno"]),
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+
+    let entry = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: vec![
+                    WeatherChoices {
+                        stations: String::from("ksaw"),
+                        temp_high: Some(ValueOptions::Over),
+                        temp_low: None,
+                        wind_speed: None,
+                    },
+                    WeatherChoices {
+                        stations: String::from("PFNO"),
+                        temp_high: None,
+                        temp_low: Some(ValueOptions::Under),
+                        wind_speed: None,
+                    },
+                ],
+            },
+        )
+        .await
+        .unwrap();
+    let stations: Vec<String> = entry
+        .expected_observations
+        .iter()
+        .map(|choice| choice.stations.clone())
+        .collect();
+    assert_eq!(stations, vec![String::from("KSAW"), String::from("PFNO")]);
+
+    let res = test_app
+        .oracle
+        .add_event_entry(
+            keys.public_key,
+            AddEventEntry {
+                id: Uuid::now_v7(),
+                event_id: event.id,
+                expected_observations: vec![WeatherChoices {
+                    stations: String::from("k saw"),
+                    temp_high: Some(ValueOptions::Over),
+                    temp_low: None,
+                    wind_speed: None,
+                }],
+            },
+        )
+        .await;
+    assert!(matches!(res, Err(Error::BadEntry(_))));
+}
+
+#[tokio::test]
+async fn rejects_invalid_station_ids_in_events() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let res = test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                locations: station_ids(&["KSAW", ""]),
+                ..new_event()
+            },
+        )
+        .await;
+    assert!(matches!(res, Err(Error::BadEvent(_))));
+}
@@ -0,0 +1,106 @@
+use crate::helpers::{spawn_app, MockWeatherAccess};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use hyper::Method;
+use oracle::{Forecasted, Station, StoredStation, Weather};
+use serde_json::from_slice;
+use std::sync::Arc;
+use time::OffsetDateTime;
+use tower::ServiceExt;
+
+fn weather(station_id: &str) -> Weather {
+    Weather {
+        station_id: station_id.to_string(),
+        observed: None,
+        forecasted: Forecasted {
+            date: OffsetDateTime::now_utc(),
+            temp_low: 10,
+            temp_high: 20,
+            wind_speed: 5,
+        },
+    }
+}
+
+#[tokio::test]
+async fn can_get_distinct_stations_with_stored_weather() {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_stations().returning(|| {
+        Ok(vec![
+            Station {
+                station_id: String::from("KSAW"),
+                station_name: String::from("Sawyer International Airport"),
+                latitude: 46.35,
+                longitude: -87.4,
+            },
+            Station {
+                station_id: String::from("PFNO"),
+                station_name: String::from("Noatak Airport"),
+                latitude: 67.57,
+                longitude: -162.97,
+            },
+        ])
+    });
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+    // Weather stored before station ids were normalized may hold lower case ids
+    test_app
+        .event_data
+        .add_weather_readings(vec![
+            weather("KSAW"),
+            weather("PFNO"),
+            weather("papg"),
+            weather("KSAW"),
+            weather("PAPG"),
+            weather("KSAW"),
+        ])
+        .await
+        .unwrap();
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/stations/weather")
+        .body(Body::empty())
+        .unwrap();
+    let response = test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert_eq!(response.status(), StatusCode::OK);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let stations: Vec<StoredStation> = from_slice(&body).unwrap();
+
+    assert_eq!(
+        stations,
+        vec![
+            StoredStation {
+                station_id: String::from("KSAW"),
+                station_name: Some(String::from("Sawyer International Airport")),
+                readings: 3,
+            },
+            StoredStation {
+                station_id: String::from("PAPG"),
+                station_name: None,
+                readings: 2,
+            },
+            StoredStation {
+                station_id: String::from("PFNO"),
+                station_name: Some(String::from("Noatak Airport")),
+                readings: 1,
+            },
+        ]
+    );
+}
+
+#[tokio::test]
+async fn no_stored_stations_without_weather() {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data.expect_stations().returning(|| Ok(vec![]));
+    let test_app = spawn_app(Arc::new(weather_data)).await;
+
+    let stations = test_app.oracle.stored_stations().await.unwrap();
+
+    assert!(stations.is_empty());
+}
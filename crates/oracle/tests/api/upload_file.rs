@@ -0,0 +1,71 @@
+use crate::helpers::{spawn_app, MockWeatherAccess};
+use axum::{
+    body::{to_bytes, Body},
+    http::{Request, StatusCode},
+};
+use hyper::{header, Method};
+use std::sync::Arc;
+use tower::ServiceExt;
+
+const BOUNDARY: &str = "------------------------d8b9fa0cf983802b";
+
+fn multipart_body(field_count: usize) -> String {
+    let mut body = String::new();
+    for index in 0..field_count {
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"file{}\"; filename=\"file{}.parquet\"\r\n\
+             Content-Type: application/octet-stream\r\n\r\nPAR1\r\n",
+            BOUNDARY, index, index
+        ));
+    }
+    body.push_str(&format!("--{}--\r\n", BOUNDARY));
+    body
+}
+
+#[tokio::test]
+async fn rejects_upload_with_too_many_fields() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/file/forecasts_2024-01-14T04:44:22.246930703Z.parquet")
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from(multipart_body(100)))
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+
+    assert_eq!(response.status(), StatusCode::PAYLOAD_TOO_LARGE);
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    assert!(String::from_utf8(body.to_vec())
+        .unwrap()
+        .contains("more than 1 fields"));
+}
+
+#[tokio::test]
+async fn rejects_malformed_multipart_upload() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let request = Request::builder()
+        .method(Method::POST)
+        .uri("/file/forecasts_2024-01-14T04:44:22.246930703Z.parquet")
+        .header(
+            header::CONTENT_TYPE,
+            format!("multipart/form-data; boundary={}", BOUNDARY),
+        )
+        .body(Body::from("not a multipart body"))
+        .unwrap();
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+
+    assert!(response.status().is_client_error());
+}
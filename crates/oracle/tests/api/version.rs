@@ -0,0 +1,34 @@
+use crate::helpers::{spawn_app, MockWeatherAccess};
+use axum::{
+    body::{to_bytes, Body},
+    http::Request,
+};
+use hyper::Method;
+use oracle::VersionInfo;
+use serde_json::from_slice;
+use std::sync::Arc;
+use tower::ServiceExt;
+
+#[tokio::test]
+async fn can_get_version() {
+    let test_app = spawn_app(Arc::new(MockWeatherAccess::new())).await;
+
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri("/version")
+        .body(Body::empty())
+        .unwrap();
+
+    let response = test_app
+        .app
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.");
+    assert!(response.status().is_success());
+
+    let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+    let res: VersionInfo = from_slice(&body).unwrap();
+    assert_eq!(res.version, env!("CARGO_PKG_VERSION"));
+    assert!(!res.git_sha.is_empty());
+    assert!(!res.build_time.is_empty());
+}
@@ -0,0 +1,212 @@
+use crate::helpers::{new_event, random_test_number, station_ids};
+use dlctix::secp::Scalar;
+use duckdb::Connection;
+use nostr_sdk::Keys;
+use oracle::{
+    create_folder, CreateEvent, CreateEventData, EventData, Forecasted, ObservationStorage,
+    Observed, RawObservedTemps, Weather, WeatherParquetError,
+};
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use uuid::Uuid;
+
+fn new_event_data() -> CreateEventData {
+    let oracle_key = Scalar::from_slice(&[7u8; 32]).unwrap();
+    let create_event = CreateEvent {
+        locations: station_ids(&["KSAW", "PFNO"]),
+        ..new_event()
+    };
+    CreateEventData::new(
+        oracle_key.base_point_mul().into(),
+        Keys::generate().public_key,
+        create_event,
+    )
+    .unwrap()
+}
+
+fn date(value: &str) -> OffsetDateTime {
+    OffsetDateTime::parse(value, &Rfc3339).unwrap()
+}
+
+fn event_db(event_dir: &str) -> EventData {
+    create_folder("./test_data");
+    create_folder(event_dir);
+    EventData::new(event_dir)
+        .and_then(|event_db| event_db.with_extensions(&[String::from("parquet")]))
+        .unwrap()
+}
+
+// Writes `rows`, selected in the pushed weather layout, to a fixture parquet file
+fn write_fixture(event_dir: &str, rows: &str) -> String {
+    let fixture = format!("{}/event_weather.parquet", event_dir);
+    Connection::open_in_memory()
+        .unwrap()
+        .execute_batch(&format!(
+            "COPY (SELECT * FROM (VALUES {}) AS weather(station_id, forecast_date, forecast_start_time,
+                forecast_end_time, forecast_temp_low, forecast_temp_high, forecast_wind_speed,
+                observation_start_time, observation_end_time, observation_temp_low, observation_temp_high,
+                observation_wind_speed))
+            TO '{}' (FORMAT PARQUET);",
+            rows, fixture
+        ))
+        .unwrap();
+    fixture
+}
+
+const FIXTURE_ROWS: &str = "
+    ('KSAW', '2024-08-12', '2024-08-12T00:00:00-05:00', '2024-08-13T00:00:00-05:00', 10, 24, 8,
+        '2024-08-12T00:00:00-05:00', '2024-08-13T00:00:00-05:00', 8.6, 25.5, 11),
+    (' pfno', '2024-08-12', '2024-08-12T00:00:00Z', '2024-08-13T00:00:00Z', -2, 7, 15,
+        NULL, NULL, NULL, NULL, NULL)";
+
+fn fixture_weather() -> Vec<Weather> {
+    vec![
+        Weather {
+            station_id: String::from("KSAW"),
+            forecasted: Forecasted {
+                date: date("2024-08-12T00:00:00Z"),
+                temp_low: 10,
+                temp_high: 24,
+                wind_speed: 8,
+            },
+            observed: Some(Observed {
+                date: date("2024-08-12T05:00:00Z"),
+                temp_low: 9,
+                temp_high: 26,
+                wind_speed: 11,
+                original_offset: None,
+                raw_temps: None,
+            }),
+        },
+        Weather {
+            station_id: String::from("PFNO"),
+            forecasted: Forecasted {
+                date: date("2024-08-12T00:00:00Z"),
+                temp_low: -2,
+                temp_high: 7,
+                wind_speed: 15,
+            },
+            observed: None,
+        },
+    ]
+}
+
+async fn stored_weather(event_db: &EventData, event_id: Uuid) -> Vec<Weather> {
+    let mut stored = event_db.get_event_weather(event_id).await.unwrap();
+    stored.sort_by(|a, b| a.station_id.cmp(&b.station_id));
+    stored
+}
+
+#[tokio::test]
+async fn ingests_weather_parquet_into_weather_table() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    let event_db = event_db(&event_dir);
+    let event = event_db.add_event(new_event_data()).await.unwrap();
+    let fixture = write_fixture(&event_dir, FIXTURE_ROWS);
+
+    let weather_ids = event_db
+        .update_weather_station_parquet(
+            event.id,
+            &fixture,
+            &event.locations,
+            ObservationStorage::default(),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(weather_ids.len(), 2);
+    assert_eq!(event_db.get_event_weather_count(event.id).await.unwrap(), 2);
+    assert_eq!(stored_weather(&event_db, event.id).await, fixture_weather());
+    let mut overrides = event_db
+        .get_event_weather_overrides(event.id)
+        .await
+        .unwrap();
+    overrides.sort_by(|a, b| a.station_id.cmp(&b.station_id));
+    assert_eq!(overrides, fixture_weather());
+}
+
+#[tokio::test]
+async fn keeps_the_observation_offsets_and_raw_temps_it_is_configured_to() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    let event_db = event_db(&event_dir);
+    let event = event_db.add_event(new_event_data()).await.unwrap();
+    let fixture = write_fixture(&event_dir, FIXTURE_ROWS);
+
+    event_db
+        .update_weather_station_parquet(
+            event.id,
+            &fixture,
+            &event.locations,
+            ObservationStorage {
+                original_offsets: true,
+                raw_temps: true,
+            },
+        )
+        .await
+        .unwrap();
+
+    let mut expected = fixture_weather();
+    let observed = expected[0].observed.as_mut().unwrap();
+    observed.original_offset = Some(String::from("-05:00"));
+    observed.raw_temps = Some(RawObservedTemps {
+        temp_low: 8.6,
+        temp_high: 25.5,
+    });
+    assert_eq!(stored_weather(&event_db, event.id).await, expected);
+}
+
+#[tokio::test]
+async fn rejects_weather_parquet_missing_forecast_values() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    let event_db = event_db(&event_dir);
+    let event = event_db.add_event(new_event_data()).await.unwrap();
+    let fixture = write_fixture(
+        &event_dir,
+        "('KSAW', '2024-08-12', '2024-08-12T00:00:00Z', '2024-08-13T00:00:00Z', NULL::BIGINT, 24, 8,
+            NULL, NULL, NULL::DOUBLE, NULL::DOUBLE, NULL::BIGINT)",
+    );
+
+    let result = event_db
+        .update_weather_station_parquet(
+            event.id,
+            &fixture,
+            &event.locations,
+            ObservationStorage::default(),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(WeatherParquetError::Invalid(message))
+            if message.contains("missing a station_id or forecast value")
+    ));
+    assert_eq!(event_db.get_event_weather_count(event.id).await.unwrap(), 0);
+}
+
+#[tokio::test]
+async fn rejects_weather_parquet_for_stations_outside_the_event() {
+    let event_dir = format!("./test_data/{}", random_test_number());
+    let event_db = event_db(&event_dir);
+    let event = event_db.add_event(new_event_data()).await.unwrap();
+    let fixture = write_fixture(
+        &event_dir,
+        "('KSAW', '2024-08-12', '2024-08-12T00:00:00Z', '2024-08-13T00:00:00Z', 10, 24, 8,
+            NULL, NULL, NULL::DOUBLE, NULL::DOUBLE, NULL::BIGINT),
+        ('KDEN', '2024-08-12', '2024-08-12T00:00:00Z', '2024-08-13T00:00:00Z', 10, 24, 8,
+            NULL, NULL, NULL::DOUBLE, NULL::DOUBLE, NULL::BIGINT)",
+    );
+
+    let result = event_db
+        .update_weather_station_parquet(
+            event.id,
+            &fixture,
+            &event.locations,
+            ObservationStorage::default(),
+        )
+        .await;
+
+    assert!(matches!(
+        result,
+        Err(WeatherParquetError::Invalid(message)) if message.contains("KDEN")
+    ));
+    assert_eq!(event_db.get_event_weather_count(event.id).await.unwrap(), 0);
+}
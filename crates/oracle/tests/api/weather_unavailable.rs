@@ -0,0 +1,115 @@
+use crate::helpers::{new_event, spawn_app, MockWeatherAccess, TestApp};
+use axum::{
+    body::Body,
+    http::{header::RETRY_AFTER, Request, StatusCode},
+    response::Response,
+};
+use hyper::Method;
+use mockall::Sequence;
+use nostr_sdk::Keys;
+use oracle::{weather_data, CreateEvent, RetryWeatherData, WEATHER_UNAVAILABLE_RETRY_AFTER_SECS};
+use std::{sync::Arc, time::Duration};
+use time::{Duration as TimeDuration, OffsetDateTime, Time};
+use tower::ServiceExt;
+
+fn source_error() -> weather_data::Error {
+    weather_data::Error::Query(duckdb::Error::QueryReturnedNoRows)
+}
+
+async fn spawn_app_with_retries(weather_data: MockWeatherAccess) -> TestApp {
+    spawn_app(Arc::new(RetryWeatherData::new(
+        Arc::new(weather_data),
+        2,
+        Duration::ZERO,
+    )))
+    .await
+}
+
+async fn get(test_app: &TestApp, uri: &str) -> Response {
+    let request = Request::builder()
+        .method(Method::GET)
+        .uri(uri)
+        .body(Body::empty())
+        .unwrap();
+    test_app
+        .app
+        .clone()
+        .oneshot(request)
+        .await
+        .expect("Failed to execute request.")
+}
+
+#[tokio::test]
+async fn returns_service_unavailable_once_weather_retries_run_out() {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_stations()
+        .times(2)
+        .returning(|| Err(source_error()));
+    let test_app = spawn_app_with_retries(weather_data).await;
+
+    let response = get(&test_app, "/stations").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+    assert_eq!(
+        response.headers().get(RETRY_AFTER).unwrap(),
+        &WEATHER_UNAVAILABLE_RETRY_AFTER_SECS.to_string()
+    );
+}
+
+#[tokio::test]
+async fn retries_transient_weather_failure() {
+    let mut weather_data = MockWeatherAccess::new();
+    let mut seq = Sequence::new();
+    weather_data
+        .expect_stations()
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|| Err(source_error()));
+    weather_data
+        .expect_stations()
+        .times(1)
+        .in_sequence(&mut seq)
+        .returning(|| Ok(vec![]));
+    let test_app = spawn_app_with_retries(weather_data).await;
+
+    let response = get(&test_app, "/stations").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}
+
+#[tokio::test]
+async fn failing_weather_source_does_not_affect_event_endpoints() {
+    let mut weather_data = MockWeatherAccess::new();
+    weather_data
+        .expect_stations()
+        .returning(|| Err(source_error()));
+    weather_data
+        .expect_forecasts_data()
+        .returning(|_, _| Err(source_error()));
+    weather_data
+        .expect_observation_data()
+        .returning(|_, _| Err(source_error()));
+    let test_app = spawn_app_with_retries(weather_data).await;
+
+    test_app
+        .oracle
+        .create_event(
+            Keys::generate().public_key,
+            CreateEvent {
+                observation_date: OffsetDateTime::now_utc().replace_time(Time::MIDNIGHT)
+                    + TimeDuration::days(1),
+                signing_date: OffsetDateTime::now_utc() + TimeDuration::days(2),
+                number_of_values_per_entry: 4,
+                ..new_event()
+            },
+        )
+        .await
+        .unwrap();
+
+    let response = get(&test_app, "/stations").await;
+    assert_eq!(response.status(), StatusCode::SERVICE_UNAVAILABLE);
+
+    let response = get(&test_app, "/oracle/events").await;
+    assert_eq!(response.status(), StatusCode::OK);
+    let response = get(&test_app, "/version").await;
+    assert_eq!(response.status(), StatusCode::OK);
+}